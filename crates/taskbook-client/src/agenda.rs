@@ -0,0 +1,85 @@
+//! Agenda grouping shared by `tb --today` and the TUI today view.
+
+use chrono::NaiveDate;
+use taskbook_common::StorageItem;
+
+/// Sections of the agenda, in display order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgendaSection {
+    Overdue,
+    DueToday,
+    InProgress,
+    Starred,
+}
+
+impl AgendaSection {
+    pub const ALL: [AgendaSection; 4] = [
+        AgendaSection::Overdue,
+        AgendaSection::DueToday,
+        AgendaSection::InProgress,
+        AgendaSection::Starred,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            AgendaSection::Overdue => "Overdue",
+            AgendaSection::DueToday => "Due Today",
+            AgendaSection::InProgress => "In Progress",
+            AgendaSection::Starred => "Starred",
+        }
+    }
+}
+
+/// Pick the agenda section for an item. Completed tasks and items that are
+/// not due, started, or starred are left out. An item matching several
+/// sections goes into the first one.
+pub fn classify(item: &StorageItem, today: NaiveDate) -> Option<AgendaSection> {
+    if let Some(task) = item.as_task() {
+        if task.is_complete {
+            return None;
+        }
+        match task.due {
+            Some(due) if due < today => return Some(AgendaSection::Overdue),
+            Some(due) if due == today => return Some(AgendaSection::DueToday),
+            _ => {}
+        }
+        if task.in_progress {
+            return Some(AgendaSection::InProgress);
+        }
+    }
+    if item.is_starred() {
+        return Some(AgendaSection::Starred);
+    }
+    None
+}
+
+/// Group items into non-empty agenda sections. Items within a section are
+/// ordered by due date, then ID.
+pub fn group<'a>(
+    items: impl IntoIterator<Item = &'a StorageItem>,
+    today: NaiveDate,
+) -> Vec<(AgendaSection, Vec<&'a StorageItem>)> {
+    let mut sections: Vec<(AgendaSection, Vec<&StorageItem>)> = AgendaSection::ALL
+        .iter()
+        .map(|section| (*section, Vec::new()))
+        .collect();
+
+    for item in items {
+        if let Some(section) = classify(item, today) {
+            if let Some((_, list)) = sections.iter_mut().find(|(s, _)| *s == section) {
+                list.push(item);
+            }
+        }
+    }
+
+    for (_, list) in &mut sections {
+        list.sort_by(|a, b| {
+            let due_a = a.due_date().unwrap_or(NaiveDate::MAX);
+            let due_b = b.due_date().unwrap_or(NaiveDate::MAX);
+            due_a.cmp(&due_b).then_with(|| a.id().cmp(&b.id()))
+        });
+    }
+
+    sections.retain(|(_, list)| !list.is_empty());
+    sections
+}
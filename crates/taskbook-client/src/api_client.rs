@@ -1,14 +1,117 @@
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
 
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 
+use crate::config::SyncConfig;
+use crate::credentials::Credentials;
 use crate::error::{Result, TaskbookError};
 
+/// Sent with every request; the server shows it in the device list
+pub(crate) fn user_agent() -> String {
+    format!(
+        "taskbook/{} ({})",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS
+    )
+}
+
+/// Environment variable holding an API token to use instead of the session
+pub const API_TOKEN_VAR: &str = "TB_TOKEN";
+
+/// Longest pause between two attempts at a request
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How long requests may take, and how often ones that failed in passing
+/// are sent again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Limit on a whole request, if any
+    pub timeout: Option<Duration>,
+    pub connect_timeout: Duration,
+    pub retries: u32,
+    /// Pause before the first retry, doubled for each one after it
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(sync: &SyncConfig) -> Self {
+        Self {
+            timeout: (sync.timeout_secs > 0).then(|| Duration::from_secs(sync.timeout_secs)),
+            connect_timeout: Duration::from_secs(sync.connect_timeout_secs),
+            retries: sync.retries,
+            base_delay: Duration::from_millis(sync.retry_delay_ms),
+        }
+    }
+
+    /// The pause before retry number `attempt` (from 0). `jitter`, between 0
+    /// and 1, picks a point in the upper half of the backoff so that clients
+    /// cut off together do not all come back at once.
+    fn delay(&self, attempt: u32, jitter: f64) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_RETRY_DELAY);
+        backoff / 2 + backoff.mul_f64(jitter.clamp(0.0, 1.0) / 2.0)
+    }
+}
+
+/// A random number between 0 and 1
+fn jitter() -> f64 {
+    // The low 53 bits of a v4 UUID are random
+    let bits = uuid::Uuid::new_v4().as_u128() as u64 & ((1 << 53) - 1);
+    bits as f64 / (1u64 << 53) as f64
+}
+
+/// Whether a request answered with `status` may go through if sent again.
+/// The server turned it down without acting on it when busy; a gateway in
+/// between may have passed it on, so only requests that can safely be sent
+/// twice are retried then.
+fn retryable_status(status: StatusCode, idempotent: bool) -> bool {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+        StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => idempotent,
+        _ => false,
+    }
+}
+
+/// The pause the server asked for, in seconds, before trying again
+fn retry_after(resp: &Response) -> Option<Duration> {
+    resp.headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(|secs| Duration::from_secs(secs).min(MAX_RETRY_DELAY))
+}
+
 /// HTTP client for communicating with the taskbook server.
+///
+/// Given a refresh token, it renews an expired session by itself: a request
+/// answered with 401 is sent again with a new session token, and the new
+/// tokens are saved to the credentials file.
+///
+/// With a [`RetryPolicy`], a request that fails in a way that may pass (the
+/// server cannot be reached, takes too long, or is busy) is sent again after
+/// a growing pause, and gives [`TaskbookError::Unavailable`] if it still
+/// fails. Other failures are returned at once.
 pub struct ApiClient {
     base_url: String,
-    token: Option<String>,
-    client: reqwest::blocking::Client,
+    tokens: Mutex<Tokens>,
+    /// Whether the token is an API token from `TB_TOKEN`
+    api_token: bool,
+    client: Client,
+    retry: RetryPolicy,
+}
+
+struct Tokens {
+    session: Option<String>,
+    refresh: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -17,11 +120,61 @@ pub struct EncryptedItemData {
     pub nonce: String,
 }
 
+/// An item removed by an upload, which the server keeps for a while
+#[derive(Deserialize)]
+pub struct TrashedItem {
+    pub key: String,
+    #[serde(flatten)]
+    pub item: EncryptedItemData,
+    pub archived: bool,
+    pub deleted_at: String,
+    pub purge_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct TrashResponse {
+    pub items: Vec<TrashedItem>,
+    pub retention_days: i64,
+}
+
+/// A version of an item kept by the server
+#[derive(Deserialize)]
+pub struct ItemVersion {
+    #[serde(flatten)]
+    pub item: EncryptedItemData,
+    pub archived: bool,
+    pub saved_at: String,
+    pub current: bool,
+}
+
+#[derive(Deserialize)]
+struct HistoryResponse {
+    versions: Vec<ItemVersion>,
+}
+
+#[derive(Serialize)]
+struct RestoreRequest<'a> {
+    keys: Option<&'a [String]>,
+}
+
+#[derive(Deserialize)]
+struct RestoreResponse {
+    restored: u64,
+}
+
 #[derive(Deserialize)]
 pub struct ItemsResponse {
     pub items: HashMap<String, EncryptedItemData>,
 }
 
+/// Items as fetched, with the ETag to send back to fetch them only if they
+/// have changed
+#[derive(Clone)]
+pub struct TaggedItems {
+    pub etag: Option<String>,
+    pub items: HashMap<String, EncryptedItemData>,
+}
+
 #[derive(Serialize)]
 struct PutItemsRequest {
     items: HashMap<String, EncryptedItemData>,
@@ -32,22 +185,332 @@ pub struct RegisterRequest {
     pub username: String,
     pub email: String,
     pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invite_code: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct RegisterResponse {
     pub token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totp_code: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct LoginResponse {
     pub token: String,
+    /// Missing from servers that predate session refresh
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RefreshRequest<'a> {
+    refresh_token: &'a str,
+}
+
+#[derive(Deserialize)]
+struct RefreshResponse {
+    token: String,
+    refresh_token: String,
+}
+
+#[derive(Serialize)]
+struct ChangePasswordRequest<'a> {
+    current_password: &'a str,
+    new_password: &'a str,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordResponse {
+    pub token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DeleteAccountRequest<'a> {
+    password: &'a str,
+}
+
+#[derive(Serialize)]
+struct EnrollTotpRequest<'a> {
+    password: &'a str,
+}
+
+#[derive(Deserialize)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub uri: String,
+}
+
+#[derive(Serialize)]
+struct ConfirmTotpRequest<'a> {
+    code: &'a str,
+}
+
+#[derive(Serialize)]
+struct DisableTotpRequest<'a> {
+    password: &'a str,
+    code: &'a str,
+}
+
+#[derive(Deserialize)]
+struct SendVerificationResponse {
+    email: String,
+}
+
+#[derive(Serialize)]
+struct ForgotPasswordRequest<'a> {
+    email: &'a str,
+}
+
+/// A single sign-on started on the server
+#[derive(Deserialize)]
+pub struct SsoStart {
+    /// The provider's sign-in page, to open in a browser
+    pub authorize_url: String,
+    pub poll_token: String,
+    /// Seconds until the sign-in expires
+    pub expires_in: u64,
+}
+
+#[derive(Serialize)]
+struct SsoPollRequest<'a> {
+    poll_token: &'a str,
+}
+
+/// How a single sign-on is going
+#[derive(Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SsoStatus {
+    /// The browser has not come back from the provider yet
+    Pending,
+    /// Signed in; `created` is set when the sign-in created the account
+    Complete {
+        token: String,
+        refresh_token: String,
+        created: bool,
+    },
+    /// The provider account was linked to the logged-in one
+    Linked,
+}
+
+#[derive(Deserialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub current: bool,
+}
+
+#[derive(Deserialize)]
+struct SessionsResponse {
+    sessions: Vec<SessionInfo>,
+}
+
+#[derive(Deserialize)]
+pub struct AuditEntry {
+    pub event: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+struct AuditResponse {
+    events: Vec<AuditEntry>,
+}
+
+#[derive(Serialize)]
+struct CreateTokenRequest<'a> {
+    name: &'a str,
+    scope: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_in: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateTokenResponse {
+    pub id: String,
+    pub token: String,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TokenInfo {
+    pub id: String,
+    pub name: String,
+    pub scope: String,
+    pub last_used_at: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokensResponse {
+    tokens: Vec<TokenInfo>,
+}
+
+#[derive(Serialize)]
+struct CreateWebhookRequest<'a> {
+    url: &'a str,
+}
+
+#[derive(Deserialize)]
+pub struct CreateWebhookResponse {
+    pub id: String,
+    pub url: String,
+    pub secret: String,
+}
+
+#[derive(Deserialize)]
+pub struct WebhookInfo {
+    pub id: String,
+    pub url: String,
+    pub last_delivery_at: Option<String>,
+    pub last_status: Option<u16>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WebhooksResponse {
+    webhooks: Vec<WebhookInfo>,
+}
+
+/// How the receiver answered a test delivery
+#[derive(Deserialize)]
+pub struct WebhookTest {
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MeResponse {
+    pub username: String,
+    /// How the encryption key is derived from a passphrase, if it is;
+    /// missing from servers that predate passphrases
+    #[serde(default)]
+    pub key_params: Option<String>,
+}
+
+#[derive(Serialize)]
+struct KeyParamsRequest<'a> {
+    key_params: Option<&'a str>,
+}
+
+/// Storage used by the account, from GET /api/v1/me/usage
+#[derive(Deserialize)]
+pub struct UsageResponse {
+    pub used_bytes: i64,
+    pub quota_bytes: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct PublicKeyRequest {
+    public_key: String,
+}
+
+#[derive(Serialize)]
+struct BoardKeyRequest<'a> {
+    wrapped_key: &'a str,
+}
+
+#[derive(Deserialize)]
+struct PublicKeyResponse {
+    public_key: String,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct BoardMember {
+    pub username: String,
+    pub can_write: bool,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SharedBoard {
+    pub id: String,
+    pub owner: String,
+    pub is_owner: bool,
+    pub can_write: bool,
+    pub wrapped_key: String,
+    pub members: Vec<BoardMember>,
+}
+
+#[derive(Deserialize)]
+struct BoardsResponse {
+    boards: Vec<SharedBoard>,
+}
+
+#[derive(Serialize)]
+struct CreateBoardRequest {
+    wrapped_key: String,
+}
+
+#[derive(Deserialize)]
+struct CreateBoardResponse {
+    id: String,
+}
+
+#[derive(Serialize)]
+struct ShareBoardRequest<'a> {
+    username: &'a str,
+    wrapped_key: String,
+    can_write: bool,
+}
+
+#[derive(Serialize)]
+struct CreateLinkRequest {
+    data: String,
+    nonce: String,
+    expires_in: i64,
+}
+
+#[derive(Deserialize)]
+pub struct CreateLinkResponse {
+    pub token: String,
+    pub expires_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct LinkInfo {
+    pub token: String,
+    pub created_at: String,
+    pub expires_at: String,
+}
+
+#[derive(Deserialize)]
+struct LinksResponse {
+    links: Vec<LinkInfo>,
+}
+
+/// Everything the server holds for the account, from GET /api/v1/me/export.
+/// The parts that are not encrypted are passed through as they are.
+#[derive(Deserialize)]
+pub struct AccountExport {
+    pub version: u32,
+    pub exported_at: String,
+    pub account: serde_json::Value,
+    pub sessions: serde_json::Value,
+    pub api_tokens: serde_json::Value,
+    /// Missing from servers that predate webhooks
+    #[serde(default)]
+    pub webhooks: serde_json::Value,
+    pub share_links: serde_json::Value,
+    pub shared_boards: Vec<SharedBoard>,
+    pub items: HashMap<String, EncryptedItemData>,
+    pub archive: HashMap<String, EncryptedItemData>,
+    /// Items of each shared board, by board id
+    pub board_items: HashMap<String, HashMap<String, EncryptedItemData>>,
 }
 
 #[derive(Deserialize)]
@@ -55,13 +518,113 @@ struct ErrorResponse {
     error: String,
 }
 
+#[derive(Deserialize)]
+struct RegisterErrorResponse {
+    error: String,
+    #[serde(default)]
+    invite_required: bool,
+}
+
+#[derive(Deserialize)]
+struct LoginErrorResponse {
+    error: String,
+    #[serde(default)]
+    totp_required: bool,
+}
+
+#[derive(Deserialize)]
+struct SaveErrorResponse {
+    error: String,
+    #[serde(default)]
+    quota_exceeded: bool,
+}
+
+/// The error for a failed upload: the quota error when it is one, so it is
+/// not mistaken for a connection problem
+fn save_error(resp: reqwest::blocking::Response, fallback: &str) -> TaskbookError {
+    match resp.json::<SaveErrorResponse>() {
+        Ok(e) if e.quota_exceeded => TaskbookError::QuotaExceeded(e.error),
+        Ok(e) => TaskbookError::Network(e.error),
+        Err(_) => TaskbookError::Network(fallback.to_string()),
+    }
+}
+
+/// Whether the server turned a request down for its session token, as
+/// opposed to, say, a wrong password
+fn session_expired(resp: &Response) -> bool {
+    resp.status() == StatusCode::UNAUTHORIZED
+        && resp
+            .headers()
+            .get(reqwest::header::WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("invalid_token"))
+}
+
+/// The server's error message, or `fallback` if the body has none
+fn error_message(resp: reqwest::blocking::Response, fallback: &str) -> String {
+    resp.json::<ErrorResponse>()
+        .map(|e| e.error)
+        .unwrap_or_else(|_| fallback.to_string())
+}
+
 impl ApiClient {
     pub fn new(base_url: &str, token: Option<&str>) -> Self {
         Self {
             base_url: base_url.trim_end_matches('/').to_string(),
-            token: token.map(|t| t.to_string()),
-            client: reqwest::blocking::Client::new(),
+            tokens: Mutex::new(Tokens {
+                session: token.map(|t| t.to_string()),
+                refresh: None,
+            }),
+            api_token: false,
+            client: Client::builder()
+                .user_agent(user_agent())
+                .build()
+                .unwrap_or_default(),
+            retry: RetryPolicy {
+                timeout: None,
+                connect_timeout: Duration::ZERO,
+                retries: 0,
+                base_delay: Duration::ZERO,
+            },
+        }
+    }
+
+    /// Time out and retry requests as `policy` says
+    pub fn with_retry_policy(self, policy: RetryPolicy) -> Self {
+        let client = Client::builder()
+            .user_agent(user_agent())
+            .timeout(policy.timeout)
+            .connect_timeout(policy.connect_timeout)
+            .build()
+            .unwrap_or(self.client);
+        Self {
+            client,
+            retry: policy,
+            ..self
+        }
+    }
+
+    /// A client for the saved login, which renews the session when it
+    /// expires.
+    pub fn from_credentials(creds: &Credentials) -> Self {
+        Self::for_credentials(&creds.server_url, creds)
+    }
+
+    /// Like [`from_credentials`](Self::from_credentials), for a server URL
+    /// taken from the config rather than the credentials file.
+    ///
+    /// An API token in `TB_TOKEN` is used instead of the saved session, so
+    /// scripts need not be logged in.
+    pub fn for_credentials(base_url: &str, creds: &Credentials) -> Self {
+        if let Some(token) = std::env::var(API_TOKEN_VAR).ok().filter(|t| !t.is_empty()) {
+            return Self {
+                api_token: true,
+                ..Self::new(base_url, Some(&token))
+            };
         }
+        let client = Self::new(base_url, Some(&creds.token));
+        client.tokens.lock().unwrap().refresh = creds.refresh_token.clone();
+        client
     }
 
     fn url(&self, path: &str) -> String {
@@ -69,12 +632,121 @@ impl ApiClient {
     }
 
     fn auth_header(&self) -> Result<String> {
-        self.token
+        self.tokens
+            .lock()
+            .unwrap()
+            .session
             .as_ref()
             .map(|t| format!("Bearer {}", t))
             .ok_or_else(|| TaskbookError::Auth("not logged in".to_string()))
     }
 
+    /// Send an authenticated request built by `request`. If the session has
+    /// expired, renew it and send the request again.
+    fn send(&self, request: impl Fn(&Client) -> RequestBuilder) -> Result<Response> {
+        let send = || {
+            self.send_retrying(|| {
+                Ok(request(&self.client).header("Authorization", self.auth_header()?))
+            })
+        };
+        let mut resp = send()?;
+        if session_expired(&resp) && self.refresh_session()? {
+            resp = send()?;
+        }
+        if session_expired(&resp) && self.api_token {
+            return Err(TaskbookError::Auth(format!(
+                "the API token in {API_TOKEN_VAR} is invalid, expired or revoked"
+            )));
+        }
+        if session_expired(&resp) {
+            return Err(TaskbookError::Auth(
+                "session expired — run `tb sync login` to log in again".to_string(),
+            ));
+        }
+        Ok(resp)
+    }
+
+    /// Send the request built by `request`, and again while it fails in a
+    /// way that may pass, up to the policy's number of retries
+    fn send_retrying(&self, request: impl Fn() -> Result<RequestBuilder>) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let built = request()?
+                .build()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            let idempotent = built.method().is_idempotent();
+
+            let (wait, error) = match self.client.execute(built) {
+                Ok(resp) if !retryable_status(resp.status(), idempotent) => return Ok(resp),
+                Ok(resp) => {
+                    let status = resp.status();
+                    (retry_after(&resp), error_message(resp, status.as_str()))
+                }
+                // A request that timed out may have been carried out anyway
+                Err(e) if e.is_connect() || (e.is_timeout() && idempotent) => (None, e.to_string()),
+                Err(e) => return Err(TaskbookError::Network(e.to_string())),
+            };
+
+            if attempt >= self.retry.retries {
+                return Err(TaskbookError::Unavailable(match attempt {
+                    0 => error,
+                    _ => format!("{error} (tried {} times)", attempt + 1),
+                }));
+            }
+            std::thread::sleep(wait.unwrap_or_else(|| self.retry.delay(attempt, jitter())));
+            attempt += 1;
+        }
+    }
+
+    /// The current session token
+    pub fn token(&self) -> Option<String> {
+        self.tokens.lock().unwrap().session.clone()
+    }
+
+    /// Trade the refresh token for new tokens and save them. Returns whether
+    /// there are new tokens to retry with.
+    pub fn refresh_session(&self) -> Result<bool> {
+        let mut tokens = self.tokens.lock().unwrap();
+        let Some(refresh_token) = tokens.refresh.clone() else {
+            return Ok(false);
+        };
+
+        // Another tb process may have refreshed first, using up our token
+        let saved = Credentials::load()?;
+        if let Some(creds) = &saved {
+            if creds.refresh_token.is_some() && creds.refresh_token != tokens.refresh {
+                tokens.session = Some(creds.token.clone());
+                tokens.refresh = creds.refresh_token.clone();
+                return Ok(true);
+            }
+        }
+
+        let resp = self
+            .client
+            .post(self.url("/api/v1/refresh"))
+            .json(&RefreshRequest {
+                refresh_token: &refresh_token,
+            })
+            .send()
+            .map_err(|e| TaskbookError::Network(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Ok(false);
+        }
+        let body: RefreshResponse = resp
+            .json()
+            .map_err(|e| TaskbookError::Network(e.to_string()))?;
+
+        if let Some(mut creds) = saved.filter(|c| c.refresh_token.as_ref() == Some(&refresh_token))
+        {
+            creds.token = body.token.clone();
+            creds.refresh_token = Some(body.refresh_token.clone());
+            creds.save()?;
+        }
+        tokens.session = Some(body.token);
+        tokens.refresh = Some(body.refresh_token);
+        Ok(true)
+    }
+
     pub fn register(&self, req: &RegisterRequest) -> Result<RegisterResponse> {
         let resp = self
             .client
@@ -87,11 +759,11 @@ impl ApiClient {
             resp.json::<RegisterResponse>()
                 .map_err(|e| TaskbookError::Network(e.to_string()))
         } else {
-            let err = resp
-                .json::<ErrorResponse>()
-                .map(|e| e.error)
-                .unwrap_or_else(|_| "registration failed".to_string());
-            Err(TaskbookError::Auth(err))
+            match resp.json::<RegisterErrorResponse>() {
+                Ok(e) if e.invite_required => Err(TaskbookError::InviteRequired),
+                Ok(e) => Err(TaskbookError::Auth(e.error)),
+                Err(_) => Err(TaskbookError::Auth("registration failed".to_string())),
+            }
         }
     }
 
@@ -107,22 +779,16 @@ impl ApiClient {
             resp.json::<LoginResponse>()
                 .map_err(|e| TaskbookError::Network(e.to_string()))
         } else {
-            let err = resp
-                .json::<ErrorResponse>()
-                .map(|e| e.error)
-                .unwrap_or_else(|_| "login failed".to_string());
-            Err(TaskbookError::Auth(err))
+            match resp.json::<LoginErrorResponse>() {
+                Ok(e) if e.totp_required => Err(TaskbookError::TotpRequired),
+                Ok(e) => Err(TaskbookError::Auth(e.error)),
+                Err(_) => Err(TaskbookError::Auth("login failed".to_string())),
+            }
         }
     }
 
     pub fn logout(&self) -> Result<()> {
-        let auth = self.auth_header()?;
-        let resp = self
-            .client
-            .delete(self.url("/api/v1/logout"))
-            .header("Authorization", &auth)
-            .send()
-            .map_err(|e| TaskbookError::Network(e.to_string()))?;
+        let resp = self.send(|client| client.delete(self.url("/api/v1/logout")))?;
 
         if resp.status().is_success() {
             Ok(())
@@ -131,83 +797,798 @@ impl ApiClient {
         }
     }
 
-    pub fn get_items(&self) -> Result<HashMap<String, EncryptedItemData>> {
-        let auth = self.auth_header()?;
-        let resp = self
-            .client
-            .get(self.url("/api/v1/items"))
-            .header("Authorization", &auth)
-            .send()
-            .map_err(|e| TaskbookError::Network(e.to_string()))?;
+    pub fn me(&self) -> Result<MeResponse> {
+        let resp = self.send(|client| client.get(self.url("/api/v1/me")))?;
 
         if resp.status().is_success() {
-            let body: ItemsResponse = resp
-                .json()
-                .map_err(|e| TaskbookError::Network(e.to_string()))?;
-            Ok(body.items)
+            resp.json::<MeResponse>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
         } else {
-            Err(TaskbookError::Network("failed to fetch items".to_string()))
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to fetch account",
+            )))
         }
     }
 
-    pub fn put_items(&self, items: &HashMap<String, EncryptedItemData>) -> Result<()> {
-        let auth = self.auth_header()?;
-        let req = PutItemsRequest {
-            items: items.clone(),
-        };
-        let resp = self
-            .client
-            .put(self.url("/api/v1/items"))
-            .header("Authorization", &auth)
-            .json(&req)
+    /// Storage used by the account, and its quota
+    pub fn usage(&self) -> Result<UsageResponse> {
+        let resp = self.send(|client| client.get(self.url("/api/v1/me/usage")))?;
+
+        if resp.status().is_success() {
+            resp.json::<UsageResponse>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to fetch storage usage",
+            )))
+        }
+    }
+
+    /// Change the account password. Returns the new session's tokens; every
+    /// other session is ended.
+    pub fn change_password(
+        &self,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<ChangePasswordResponse> {
+        let req = ChangePasswordRequest {
+            current_password,
+            new_password,
+        };
+        let resp = self.send(|client| client.patch(self.url("/api/v1/me/password")).json(&req))?;
+
+        if resp.status().is_success() {
+            resp.json::<ChangePasswordResponse>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "password change failed",
+            )))
+        }
+    }
+
+    pub fn delete_account(&self, password: &str) -> Result<()> {
+        let resp = self.send(|client| {
+            client
+                .delete(self.url("/api/v1/me"))
+                .json(&DeleteAccountRequest { password })
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "account deletion failed",
+            )))
+        }
+    }
+
+    /// Start enrolling in two-factor authentication
+    pub fn enroll_totp(&self, password: &str) -> Result<TotpEnrollment> {
+        let resp = self.send(|client| {
+            client
+                .post(self.url("/api/v1/me/totp"))
+                .json(&EnrollTotpRequest { password })
+        })?;
+
+        if resp.status().is_success() {
+            resp.json::<TotpEnrollment>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "two-factor enrollment failed",
+            )))
+        }
+    }
+
+    /// Turn two-factor authentication on with a code from the new secret
+    pub fn confirm_totp(&self, code: &str) -> Result<()> {
+        let resp = self.send(|client| {
+            client
+                .post(self.url("/api/v1/me/totp/confirm"))
+                .json(&ConfirmTotpRequest { code })
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "two-factor confirmation failed",
+            )))
+        }
+    }
+
+    pub fn disable_totp(&self, password: &str, code: &str) -> Result<()> {
+        let resp = self.send(|client| {
+            client
+                .delete(self.url("/api/v1/me/totp"))
+                .json(&DisableTotpRequest { password, code })
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "disabling two-factor authentication failed",
+            )))
+        }
+    }
+
+    /// Ask the server to email a verification link. Returns the address it
+    /// was sent to.
+    pub fn send_verification_email(&self) -> Result<String> {
+        let resp = self.send(|client| client.post(self.url("/api/v1/me/email/verify")))?;
+
+        if resp.status().is_success() {
+            let body: SendVerificationResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.email)
+        } else {
+            Err(TaskbookError::General(error_message(
+                resp,
+                "failed to send verification email",
+            )))
+        }
+    }
+
+    /// Ask the server to email a password reset link to `email`
+    pub fn forgot_password(&self, email: &str) -> Result<()> {
+        let resp = self
+            .client
+            .post(self.url("/api/v1/password/forgot"))
+            .json(&ForgotPasswordRequest { email })
             .send()
             .map_err(|e| TaskbookError::Network(e.to_string()))?;
 
         if resp.status().is_success() {
             Ok(())
         } else {
-            Err(TaskbookError::Network("failed to save items".to_string()))
+            Err(TaskbookError::General(error_message(
+                resp,
+                "failed to request a password reset",
+            )))
         }
     }
 
-    pub fn get_archive(&self) -> Result<HashMap<String, EncryptedItemData>> {
-        let auth = self.auth_header()?;
+    /// Start signing in through the server's single sign-on provider
+    pub fn start_sso(&self) -> Result<SsoStart> {
         let resp = self
             .client
-            .get(self.url("/api/v1/items/archive"))
-            .header("Authorization", &auth)
+            .post(self.url("/api/v1/oidc/start"))
             .send()
             .map_err(|e| TaskbookError::Network(e.to_string()))?;
 
         if resp.status().is_success() {
-            let body: ItemsResponse = resp
+            resp.json::<SsoStart>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to start single sign-on",
+            )))
+        }
+    }
+
+    /// Start linking a single sign-on account to the logged-in one
+    pub fn start_sso_link(&self) -> Result<SsoStart> {
+        let resp = self.send(|client| client.post(self.url("/api/v1/oidc/link")))?;
+
+        if resp.status().is_success() {
+            resp.json::<SsoStart>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to start single sign-on",
+            )))
+        }
+    }
+
+    /// Check whether a single sign-on from `start_sso` or `start_sso_link`
+    /// is done
+    pub fn poll_sso(&self, poll_token: &str) -> Result<SsoStatus> {
+        let resp = self
+            .client
+            .post(self.url("/api/v1/oidc/poll"))
+            .json(&SsoPollRequest { poll_token })
+            .send()
+            .map_err(|e| TaskbookError::Network(e.to_string()))?;
+
+        if resp.status().is_success() {
+            resp.json::<SsoStatus>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "single sign-on failed",
+            )))
+        }
+    }
+
+    pub fn list_sessions(&self) -> Result<Vec<SessionInfo>> {
+        let resp = self.send(|client| client.get(self.url("/api/v1/sessions")))?;
+
+        if resp.status().is_success() {
+            let body: SessionsResponse = resp
                 .json()
                 .map_err(|e| TaskbookError::Network(e.to_string()))?;
-            Ok(body.items)
+            Ok(body.sessions)
         } else {
-            Err(TaskbookError::Network(
-                "failed to fetch archive".to_string(),
-            ))
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to fetch devices",
+            )))
         }
     }
 
+    /// The latest entries in the account's audit log, newest first
+    pub fn list_audit(&self) -> Result<Vec<AuditEntry>> {
+        let resp = self.send(|client| client.get(self.url("/api/v1/me/audit")))?;
+
+        if resp.status().is_success() {
+            let body: AuditResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.events)
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to fetch the audit log",
+            )))
+        }
+    }
+
+    pub fn revoke_session(&self, session_id: &str) -> Result<()> {
+        let resp =
+            self.send(|client| client.delete(self.url(&format!("/api/v1/sessions/{session_id}"))))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to revoke device",
+            )))
+        }
+    }
+
+    /// Create an API token with scope `read` or `write`
+    pub fn create_token(
+        &self,
+        name: &str,
+        scope: &str,
+        expires_in: Option<i64>,
+    ) -> Result<CreateTokenResponse> {
+        let req = CreateTokenRequest {
+            name,
+            scope,
+            expires_in,
+        };
+        let resp = self.send(|client| client.post(self.url("/api/v1/tokens")).json(&req))?;
+
+        if resp.status().is_success() {
+            resp.json::<CreateTokenResponse>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to create API token",
+            )))
+        }
+    }
+
+    pub fn list_tokens(&self) -> Result<Vec<TokenInfo>> {
+        let resp = self.send(|client| client.get(self.url("/api/v1/tokens")))?;
+
+        if resp.status().is_success() {
+            let body: TokensResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.tokens)
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to fetch API tokens",
+            )))
+        }
+    }
+
+    pub fn revoke_token(&self, token_id: &str) -> Result<()> {
+        let resp =
+            self.send(|client| client.delete(self.url(&format!("/api/v1/tokens/{token_id}"))))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to revoke API token",
+            )))
+        }
+    }
+
+    /// Register a URL to be sent a signed payload when items change
+    pub fn create_webhook(&self, url: &str) -> Result<CreateWebhookResponse> {
+        let req = CreateWebhookRequest { url };
+        let resp = self.send(|client| client.post(self.url("/api/v1/webhooks")).json(&req))?;
+
+        if resp.status().is_success() {
+            resp.json::<CreateWebhookResponse>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to add webhook",
+            )))
+        }
+    }
+
+    pub fn list_webhooks(&self) -> Result<Vec<WebhookInfo>> {
+        let resp = self.send(|client| client.get(self.url("/api/v1/webhooks")))?;
+
+        if resp.status().is_success() {
+            let body: WebhooksResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.webhooks)
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to fetch webhooks",
+            )))
+        }
+    }
+
+    pub fn delete_webhook(&self, webhook_id: &str) -> Result<()> {
+        let resp =
+            self.send(|client| client.delete(self.url(&format!("/api/v1/webhooks/{webhook_id}"))))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to remove webhook",
+            )))
+        }
+    }
+
+    /// Have the server send a test payload to a webhook now
+    pub fn test_webhook(&self, webhook_id: &str) -> Result<WebhookTest> {
+        let resp = self
+            .send(|client| client.post(self.url(&format!("/api/v1/webhooks/{webhook_id}/test"))))?;
+
+        if resp.status().is_success() {
+            resp.json::<WebhookTest>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Auth(error_message(
+                resp,
+                "failed to test webhook",
+            )))
+        }
+    }
+
+    pub fn export(&self) -> Result<AccountExport> {
+        let resp = self.send(|client| client.get(self.url("/api/v1/me/export")))?;
+
+        if resp.status().is_success() {
+            resp.json::<AccountExport>()
+                .map_err(|e| TaskbookError::Network(format!("export download failed: {e}")))
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to export account",
+            )))
+        }
+    }
+
+    /// The user's items, or `None` if they still have the ETag `cached`.
+    pub fn get_items(&self, cached: Option<&str>) -> Result<Option<TaggedItems>> {
+        self.get_tagged("/api/v1/items", cached, "failed to fetch items")
+    }
+
+    /// Fetch items from `path`, sending `cached` as `If-None-Match`
+    fn get_tagged(
+        &self,
+        path: &str,
+        cached: Option<&str>,
+        error: &str,
+    ) -> Result<Option<TaggedItems>> {
+        let resp = self.send(|client| {
+            let request = client.get(self.url(path));
+            match cached {
+                Some(etag) => request.header(reqwest::header::IF_NONE_MATCH, etag),
+                None => request,
+            }
+        })?;
+
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(TaskbookError::Network(error.to_string()));
+        }
+        let etag = resp
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body: ItemsResponse = resp
+            .json()
+            .map_err(|e| TaskbookError::Network(e.to_string()))?;
+        Ok(Some(TaggedItems {
+            etag,
+            items: body.items,
+        }))
+    }
+
+    pub fn put_items(&self, items: &HashMap<String, EncryptedItemData>) -> Result<()> {
+        let req = PutItemsRequest {
+            items: items.clone(),
+        };
+        let resp = self.send(|client| client.put(self.url("/api/v1/items")).json(&req))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(save_error(resp, "failed to save items"))
+        }
+    }
+
+    /// The user's archive, or `None` if they still have the ETag `cached`.
+    pub fn get_archive(&self, cached: Option<&str>) -> Result<Option<TaggedItems>> {
+        self.get_tagged("/api/v1/items/archive", cached, "failed to fetch archive")
+    }
+
     pub fn put_archive(&self, items: &HashMap<String, EncryptedItemData>) -> Result<()> {
-        let auth = self.auth_header()?;
         let req = PutItemsRequest {
             items: items.clone(),
         };
+        let resp = self.send(|client| client.put(self.url("/api/v1/items/archive")).json(&req))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(save_error(resp, "failed to save archive"))
+        }
+    }
+
+    pub fn get_trash(&self) -> Result<TrashResponse> {
+        let resp = self.send(|client| client.get(self.url("/api/v1/items/trash")))?;
+
+        if resp.status().is_success() {
+            resp.json::<TrashResponse>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to fetch trash",
+            )))
+        }
+    }
+
+    /// Restore the items with these keys from the trash, or all of them;
+    /// returns how many were restored
+    pub fn restore_trash(&self, keys: Option<&[String]>) -> Result<u64> {
+        let req = RestoreRequest { keys };
+        let resp = self.send(|client| {
+            client
+                .post(self.url("/api/v1/items/trash/restore"))
+                .json(&req)
+        })?;
+
+        if resp.status().is_success() {
+            let body: RestoreResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.restored)
+        } else {
+            Err(save_error(resp, "failed to restore items"))
+        }
+    }
+
+    /// Versions of the item with this key, newest first
+    pub fn get_item_history(&self, key: &str) -> Result<Vec<ItemVersion>> {
+        let resp =
+            self.send(|client| client.get(self.url(&format!("/api/v1/items/{key}/history"))))?;
+
+        if resp.status().is_success() {
+            let body: HistoryResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.versions)
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to fetch item history",
+            )))
+        }
+    }
+
+    pub fn put_public_key(&self, public_key: &str) -> Result<()> {
+        let req = PublicKeyRequest {
+            public_key: public_key.to_string(),
+        };
+        let resp = self.send(|client| client.put(self.url("/api/v1/me/public-key")).json(&req))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to upload public key",
+            )))
+        }
+    }
+
+    /// Store how the encryption key is derived from a passphrase, for the
+    /// account's other devices
+    pub fn put_key_params(&self, key_params: Option<&str>) -> Result<()> {
+        let req = KeyParamsRequest { key_params };
+        let resp = self.send(|client| client.put(self.url("/api/v1/me/key-params")).json(&req))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to store the passphrase settings",
+            )))
+        }
+    }
+
+    pub fn get_public_key(&self, username: &str) -> Result<String> {
         let resp = self
-            .client
-            .put(self.url("/api/v1/items/archive"))
-            .header("Authorization", &auth)
-            .json(&req)
-            .send()
-            .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            .send(|client| client.get(self.url(&format!("/api/v1/users/{username}/public-key"))))?;
+
+        if resp.status().is_success() {
+            let body: PublicKeyResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.public_key)
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to fetch public key",
+            )))
+        }
+    }
+
+    pub fn list_boards(&self) -> Result<Vec<SharedBoard>> {
+        let resp = self.send(|client| client.get(self.url("/api/v1/boards")))?;
+
+        if resp.status().is_success() {
+            let body: BoardsResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.boards)
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to fetch shared boards",
+            )))
+        }
+    }
+
+    pub fn create_board(&self, wrapped_key: &str) -> Result<String> {
+        let req = CreateBoardRequest {
+            wrapped_key: wrapped_key.to_string(),
+        };
+        let resp = self.send(|client| client.post(self.url("/api/v1/boards")).json(&req))?;
+
+        if resp.status().is_success() {
+            let body: CreateBoardResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.id)
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to create shared board",
+            )))
+        }
+    }
+
+    pub fn delete_board(&self, board_id: &str) -> Result<()> {
+        let resp =
+            self.send(|client| client.delete(self.url(&format!("/api/v1/boards/{board_id}"))))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to stop sharing board",
+            )))
+        }
+    }
+
+    pub fn share_board(
+        &self,
+        board_id: &str,
+        username: &str,
+        wrapped_key: &str,
+        can_write: bool,
+    ) -> Result<()> {
+        let req = ShareBoardRequest {
+            username,
+            wrapped_key: wrapped_key.to_string(),
+            can_write,
+        };
+        let resp = self.send(|client| {
+            client
+                .put(self.url(&format!("/api/v1/boards/{board_id}/members")))
+                .json(&req)
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to share board",
+            )))
+        }
+    }
+
+    /// Replace the user's own copy of a shared board's key
+    pub fn put_board_key(&self, board_id: &str, wrapped_key: &str) -> Result<()> {
+        let req = BoardKeyRequest { wrapped_key };
+        let resp = self.send(|client| {
+            client
+                .put(self.url(&format!("/api/v1/boards/{board_id}/key")))
+                .json(&req)
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to update board key",
+            )))
+        }
+    }
+
+    pub fn unshare_board(&self, board_id: &str, username: &str) -> Result<()> {
+        let resp = self.send(|client| {
+            client.delete(self.url(&format!("/api/v1/boards/{board_id}/members/{username}")))
+        })?;
 
         if resp.status().is_success() {
             Ok(())
         } else {
-            Err(TaskbookError::Network("failed to save archive".to_string()))
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to unshare board",
+            )))
+        }
+    }
+
+    pub fn get_board_items(&self, board_id: &str) -> Result<HashMap<String, EncryptedItemData>> {
+        let resp =
+            self.send(|client| client.get(self.url(&format!("/api/v1/boards/{board_id}/items"))))?;
+
+        if resp.status().is_success() {
+            let body: ItemsResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.items)
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to fetch shared board items",
+            )))
         }
     }
+
+    pub fn put_board_items(
+        &self,
+        board_id: &str,
+        items: &HashMap<String, EncryptedItemData>,
+    ) -> Result<()> {
+        let req = PutItemsRequest {
+            items: items.clone(),
+        };
+        let resp = self.send(|client| {
+            client
+                .put(self.url(&format!("/api/v1/boards/{board_id}/items")))
+                .json(&req)
+        })?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to save shared board items",
+            )))
+        }
+    }
+
+    pub fn create_link(
+        &self,
+        snapshot: &EncryptedItemData,
+        expires_in: i64,
+    ) -> Result<CreateLinkResponse> {
+        let req = CreateLinkRequest {
+            data: snapshot.data.clone(),
+            nonce: snapshot.nonce.clone(),
+            expires_in,
+        };
+        let resp = self.send(|client| client.post(self.url("/api/v1/links")).json(&req))?;
+
+        if resp.status().is_success() {
+            resp.json::<CreateLinkResponse>()
+                .map_err(|e| TaskbookError::Network(e.to_string()))
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to create share link",
+            )))
+        }
+    }
+
+    pub fn list_links(&self) -> Result<Vec<LinkInfo>> {
+        let resp = self.send(|client| client.get(self.url("/api/v1/links")))?;
+
+        if resp.status().is_success() {
+            let body: LinksResponse = resp
+                .json()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            Ok(body.links)
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to fetch share links",
+            )))
+        }
+    }
+
+    pub fn delete_link(&self, token: &str) -> Result<()> {
+        let resp =
+            self.send(|client| client.delete(self.url(&format!("/api/v1/links/{token}"))))?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(TaskbookError::Network(error_message(
+                resp,
+                "failed to revoke share link",
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_backs_off_with_jitter_up_to_a_limit() {
+        let policy = RetryPolicy::from_config(&SyncConfig::default());
+        assert_eq!(policy.delay(0, 0.0), Duration::from_millis(250));
+        assert_eq!(policy.delay(0, 1.0), Duration::from_millis(500));
+        assert_eq!(policy.delay(2, 0.5), Duration::from_millis(1500));
+        assert_eq!(policy.delay(40, 1.0), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn only_busy_or_unreachable_servers_are_retried() {
+        assert!(retryable_status(StatusCode::SERVICE_UNAVAILABLE, false));
+        assert!(retryable_status(StatusCode::TOO_MANY_REQUESTS, false));
+        assert!(retryable_status(StatusCode::BAD_GATEWAY, true));
+        assert!(!retryable_status(StatusCode::BAD_GATEWAY, false));
+        assert!(!retryable_status(StatusCode::INTERNAL_SERVER_ERROR, true));
+        assert!(!retryable_status(StatusCode::UNAUTHORIZED, true));
+    }
 }
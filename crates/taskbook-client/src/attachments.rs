@@ -0,0 +1,99 @@
+//! Files and URLs attached to items with `tb --attach`, and what
+//! `tb --open` can open: those, then the links in the item.
+
+use std::path::{Path, PathBuf};
+
+use taskbook_common::StorageItem;
+
+use crate::copy;
+use crate::error::{Result, TaskbookError};
+
+/// Whether `target` is a URL rather than a file path
+pub fn is_url(target: &str) -> bool {
+    target.starts_with("mailto:")
+        || target.split_once("://").is_some_and(|(scheme, _)| {
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        })
+}
+
+/// How an attachment is stored: URLs as given, files as an absolute path,
+/// so they still open from another directory. Files must exist.
+pub fn resolve(target: &str) -> Result<String> {
+    if is_url(target) {
+        return Ok(target.to_string());
+    }
+
+    let path = match target.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .ok_or_else(|| TaskbookError::General("cannot find the home directory".into()))?
+            .join(rest),
+        None => PathBuf::from(target),
+    };
+    let path = std::fs::canonicalize(&path)
+        .map_err(|e| TaskbookError::General(format!("cannot attach {target}: {e}")))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// What an item can open, numbered from 1 in this order: its attachments,
+/// then its links (see [`copy::item_links`])
+pub fn openable(item: &StorageItem) -> Vec<&str> {
+    let mut targets: Vec<&str> = item.attachments().iter().map(String::as_str).collect();
+    for link in copy::item_links(item) {
+        if !targets.contains(&link) {
+            targets.push(link);
+        }
+    }
+    targets
+}
+
+/// Short name of an attachment: a file's name, or the URL
+pub fn display_name(target: &str) -> &str {
+    if is_url(target) {
+        return target;
+    }
+    Path::new(target)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taskbook_common::Task;
+
+    #[test]
+    fn urls_are_kept_and_files_made_absolute() {
+        assert!(is_url("https://example.com/spec"));
+        assert!(is_url("mailto:ada@example.com"));
+        assert!(!is_url("./spec.pdf"));
+        assert!(!is_url("notes/a://b"));
+
+        let file = std::env::temp_dir().join("taskbook-attachment-test.txt");
+        std::fs::write(&file, "spec").unwrap();
+        let resolved = resolve(file.to_str().unwrap()).unwrap();
+        assert!(Path::new(&resolved).is_absolute());
+        assert_eq!(display_name(&resolved), "taskbook-attachment-test.txt");
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(resolve("/no/such/file.pdf").is_err());
+    }
+
+    #[test]
+    fn attachments_open_before_links() {
+        let mut task = Task::new(1, "Read https://example.com/a".into(), vec![], 1);
+        task.link = Some("https://example.com/issue".into());
+        task.attachments = vec!["/tmp/spec.pdf".into(), "https://example.com/a".into()];
+        assert_eq!(
+            openable(&StorageItem::Task(task)),
+            [
+                "/tmp/spec.pdf",
+                "https://example.com/a",
+                "https://example.com/issue"
+            ]
+        );
+    }
+}
@@ -1,12 +1,19 @@
 use std::io::{self, Write};
+use std::time::{Duration, Instant};
 
 use base64::Engine;
+use chrono::{DateTime, Local};
 use colored::Colorize;
 
-use crate::api_client::{ApiClient, LoginRequest, RegisterRequest};
-use crate::config::Config;
+use crate::api_client::{
+    ApiClient, LoginRequest, RegisterRequest, SsoStart, SsoStatus, UsageResponse, WebhookInfo,
+};
+use crate::config::{active_profile, Config};
 use crate::credentials::Credentials;
 use crate::error::{Result, TaskbookError};
+use crate::passphrase;
+use crate::relative_time;
+use crate::sharing;
 
 fn prompt(message: &str) -> Result<String> {
     print!("{}", message);
@@ -20,7 +27,7 @@ fn prompt(message: &str) -> Result<String> {
     Ok(input.trim().to_string())
 }
 
-fn prompt_password(message: &str) -> Result<String> {
+pub(crate) fn prompt_password(message: &str) -> Result<String> {
     rpassword::prompt_password(message)
         .map_err(|e| TaskbookError::General(format!("failed to read password: {e}")))
 }
@@ -31,6 +38,7 @@ pub fn register(
     username: Option<&str>,
     email: Option<&str>,
     password: Option<&str>,
+    invite_code: Option<&str>,
 ) -> Result<()> {
     println!("{}", "Register new account".bold());
     println!();
@@ -64,11 +72,23 @@ pub fn register(
 
     let client = ApiClient::new(&server, None);
 
-    let resp = client.register(&RegisterRequest {
+    let mut req = RegisterRequest {
         username: user,
         email: mail,
         password: pass,
-    })?;
+        invite_code: invite_code.map(str::to_string),
+    };
+    let resp = match client.register(&req) {
+        Err(TaskbookError::InviteRequired) if req.invite_code.is_none() => {
+            println!(
+                "{}",
+                "This server only lets invited people register.".yellow()
+            );
+            req.invite_code = Some(prompt("Invite code: ")?);
+            client.register(&req)?
+        }
+        result => result?,
+    };
 
     // Generate encryption key locally
     let key = taskbook_common::encryption::generate_key();
@@ -78,13 +98,15 @@ pub fn register(
     let creds = Credentials {
         server_url: server.clone(),
         token: resp.token,
+        refresh_token: resp.refresh_token,
         encryption_key: key_b64.clone(),
     };
-    creds.save()?;
-
-    // Enable sync in config
+    // Enable sync in config first: under a profile this gives the profile
+    // its own sync settings, and with them its own credentials file
     let mut config = Config::load_or_default();
     config.enable_sync(&server)?;
+    creds.save()?;
+    publish_identity(&server, &creds);
 
     println!();
     println!("{}", "Registration successful!".green().bold());
@@ -107,6 +129,7 @@ pub fn login(
     username: Option<&str>,
     password: Option<&str>,
     encryption_key: Option<&str>,
+    totp_code: Option<&str>,
 ) -> Result<()> {
     println!("{}", "Login".bold());
     println!();
@@ -128,26 +151,40 @@ pub fn login(
 
     let key = match encryption_key {
         Some(k) => k.to_string(),
-        None => prompt("Encryption key: ")?,
+        None => prompt_password("Encryption key or passphrase: ")?,
     };
 
     let client = ApiClient::new(&server, None);
 
-    let resp = client.login(&LoginRequest {
+    let mut req = LoginRequest {
         username: user,
         password: pass,
-    })?;
+        totp_code: totp_code.map(str::to_string),
+    };
+    let resp = match client.login(&req) {
+        Err(TaskbookError::TotpRequired) if req.totp_code.is_none() => {
+            req.totp_code = Some(prompt("Two-factor code: ")?);
+            client.login(&req)?
+        }
+        result => result?,
+    };
+
+    let session = ApiClient::new(&server, Some(&resp.token));
+    let key = passphrase::resolve_key(&session, &key)?;
+    passphrase::check_key(&session, &key)?;
 
     let creds = Credentials {
         server_url: server.clone(),
         token: resp.token,
+        refresh_token: resp.refresh_token,
         encryption_key: key,
     };
-    creds.save()?;
-
-    // Enable sync in config
+    // Enable sync in config first: under a profile this gives the profile
+    // its own sync settings, and with them its own credentials file
     let mut config = Config::load_or_default();
     config.enable_sync(&server)?;
+    creds.save()?;
+    publish_identity(&server, &creds);
 
     println!();
     println!("{}", "Login successful!".green().bold());
@@ -156,10 +193,132 @@ pub fn login(
     Ok(())
 }
 
+/// Log in through the server's single sign-on provider (interactive). The
+/// first sign-in creates the account, with a new encryption key.
+pub fn login_sso(server_url: Option<&str>, encryption_key: Option<&str>) -> Result<()> {
+    println!("{}", "Single sign-on".bold());
+    println!();
+
+    let server = match server_url {
+        Some(s) => s.to_string(),
+        None => prompt("Server URL: ")?,
+    };
+
+    let client = ApiClient::new(&server, None);
+    let start = client.start_sso()?;
+    let (token, refresh_token, created) = match wait_for_sso(&client, &start)? {
+        SsoStatus::Complete {
+            token,
+            refresh_token,
+            created,
+        } => (token, refresh_token, created),
+        _ => {
+            return Err(TaskbookError::Auth(
+                "unexpected answer from the server".to_string(),
+            ))
+        }
+    };
+
+    let key = if created {
+        let key = taskbook_common::encryption::generate_key();
+        base64::engine::general_purpose::STANDARD.encode(key)
+    } else {
+        let key = match encryption_key {
+            Some(k) => k.to_string(),
+            None => prompt_password("Encryption key or passphrase: ")?,
+        };
+        let session = ApiClient::new(&server, Some(&token));
+        let key = passphrase::resolve_key(&session, &key)?;
+        passphrase::check_key(&session, &key)?;
+        key
+    };
+
+    let creds = Credentials {
+        server_url: server.clone(),
+        token,
+        refresh_token: Some(refresh_token),
+        encryption_key: key.clone(),
+    };
+    // Enable sync in config first: under a profile this gives the profile
+    // its own sync settings, and with them its own credentials file
+    let mut config = Config::load_or_default();
+    config.enable_sync(&server)?;
+    creds.save()?;
+    publish_identity(&server, &creds);
+
+    println!();
+    if created {
+        println!("{}", "Account created!".green().bold());
+        println!("{}", "Sync is now enabled.".green());
+        println!();
+        println!(
+            "{}",
+            "Your encryption key (save this — it cannot be recovered):".yellow()
+        );
+        println!();
+        println!("  {}", key.bright_white().bold());
+        println!();
+    } else {
+        println!("{}", "Login successful!".green().bold());
+        println!("{}", "Sync is now enabled.".green());
+    }
+
+    Ok(())
+}
+
+/// Link a single sign-on account to the logged-in one, so it can log in
+/// with `login --sso`.
+pub fn link_sso() -> Result<()> {
+    let client = logged_in_client()?;
+    let start = client.start_sso_link()?;
+    match wait_for_sso(&client, &start)? {
+        SsoStatus::Linked => {
+            println!("{}", "Single sign-on linked.".green());
+            Ok(())
+        }
+        _ => Err(TaskbookError::Auth(
+            "unexpected answer from the server".to_string(),
+        )),
+    }
+}
+
+/// Show the provider's sign-in page and wait for the browser to come back.
+fn wait_for_sso(client: &ApiClient, start: &SsoStart) -> Result<SsoStatus> {
+    println!("Open this page in a browser to sign in:");
+    println!();
+    println!("  {}", start.authorize_url.bright_white());
+    println!();
+    println!("{}", "Waiting for the sign-in to finish...".dimmed());
+
+    let deadline = Instant::now() + Duration::from_secs(start.expires_in);
+    loop {
+        std::thread::sleep(SSO_POLL_INTERVAL);
+        match client.poll_sso(&start.poll_token)? {
+            SsoStatus::Pending if Instant::now() >= deadline => {
+                return Err(TaskbookError::Auth("the sign-in expired".to_string()))
+            }
+            SsoStatus::Pending => {}
+            status => return Ok(status),
+        }
+    }
+}
+
+/// How often `wait_for_sso` asks whether the sign-in is done
+const SSO_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Publish the sharing identity for `creds`. Best-effort: `tb share`
+/// publishes it again before sharing anything.
+fn publish_identity(server: &str, creds: &Credentials) {
+    if let Ok(key) = creds.encryption_key_bytes() {
+        let client = ApiClient::new(server, Some(&creds.token));
+        let _ = sharing::publish_identity(&client, &key);
+    }
+}
+
 /// Log out and delete credentials.
 pub fn logout() -> Result<()> {
     if let Some(creds) = Credentials::load()? {
-        let client = ApiClient::new(&creds.server_url, Some(&creds.token));
+        let client = ApiClient::from_credentials(&creds);
         // Best-effort server logout
         let _ = client.logout();
     }
@@ -176,10 +335,509 @@ pub fn logout() -> Result<()> {
     Ok(())
 }
 
+/// Change the account password (interactive).
+///
+/// The encryption key does not depend on the password, so stored data is
+/// unaffected. Other devices are logged out and have to log in again.
+pub fn change_password(current: Option<&str>, new: Option<&str>) -> Result<()> {
+    let mut creds = Credentials::load()?.ok_or_else(|| {
+        TaskbookError::Auth("not logged in — run `tb register` or `tb login` first".to_string())
+    })?;
+
+    let current = match current {
+        Some(p) => p.to_string(),
+        None => prompt_password("Current password: ")?,
+    };
+    let new = match new {
+        Some(p) => p.to_string(),
+        None => {
+            let p1 = prompt_password("New password: ")?;
+            let p2 = prompt_password("Confirm new password: ")?;
+            if p1 != p2 {
+                return Err(TaskbookError::Auth("passwords do not match".to_string()));
+            }
+            p1
+        }
+    };
+
+    let resp = ApiClient::from_credentials(&creds).change_password(&current, &new)?;
+    creds.token = resp.token;
+    creds.refresh_token = resp.refresh_token;
+    creds.save()?;
+
+    println!("{}", "Password changed.".green().bold());
+    println!(
+        "{}",
+        "Other devices have been logged out and need to log in again.".dimmed()
+    );
+
+    Ok(())
+}
+
+/// Delete the server account and everything stored with it (interactive).
+/// Local data is left alone; sync is turned off as with `logout`.
+pub fn delete_account(password: Option<&str>) -> Result<()> {
+    let creds = Credentials::load()?.ok_or_else(|| {
+        TaskbookError::Auth("not logged in — run `tb register` or `tb login` first".to_string())
+    })?;
+    let client = ApiClient::from_credentials(&creds);
+    let username = client.me()?.username;
+
+    println!(
+        "{}",
+        format!(
+            "This permanently deletes the account '{username}' on {} with all its items, \
+             shared boards and share links.",
+            creds.server_url
+        )
+        .yellow()
+    );
+    let answer = prompt(&format!("Type '{username}' to confirm: "))?;
+    if answer != username {
+        println!("{}", "Account not deleted.".dimmed());
+        return Ok(());
+    }
+
+    let pass = match password {
+        Some(p) => p.to_string(),
+        None => prompt_password("Password: ")?,
+    };
+    client.delete_account(&pass)?;
+
+    Credentials::delete()?;
+    let mut config = Config::load_or_default();
+    config.disable_sync()?;
+
+    println!("{}", "Account deleted.".green().bold());
+    println!("{}", "Sync disabled, using local storage.".dimmed());
+
+    Ok(())
+}
+
+/// Email a link to confirm the account's email address.
+pub fn verify_email() -> Result<()> {
+    let email = logged_in_client()?.send_verification_email()?;
+    println!("{}", format!("Verification email sent to {email}.").green());
+    println!("{}", "Open the link in it to confirm the address.".dimmed());
+    Ok(())
+}
+
+/// Request a password reset link by email (interactive). Works without
+/// being logged in; the server comes from the saved config if not given.
+pub fn forgot_password(server_url: Option<&str>, email: Option<&str>) -> Result<()> {
+    let server = match server_url {
+        Some(s) => s.to_string(),
+        None => match Credentials::load()? {
+            Some(creds) => creds.server_url,
+            None => prompt("Server URL: ")?,
+        },
+    };
+    let email = match email {
+        Some(e) => e.to_string(),
+        None => prompt("Email: ")?,
+    };
+
+    ApiClient::new(&server, None).forgot_password(&email)?;
+
+    println!(
+        "{}",
+        format!("If an account on {server} uses {email}, a reset link is on its way.").green()
+    );
+    println!(
+        "{}",
+        "The link expires in an hour. After resetting, log in again with `tb sync login`.".dimmed()
+    );
+    Ok(())
+}
+
+/// Split a base32 secret into groups of four, which is easier to type
+fn group_secret(secret: &str) -> String {
+    secret
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Turn on two-factor authentication (interactive). The server hands out a
+/// secret for an authenticator app, and a code from the app confirms it.
+pub fn enable_totp(password: Option<&str>) -> Result<()> {
+    let client = logged_in_client()?;
+    let pass = match password {
+        Some(p) => p.to_string(),
+        None => prompt_password("Password: ")?,
+    };
+    let enrollment = client.enroll_totp(&pass)?;
+
+    println!();
+    println!("Add the account to your authenticator app with this key:");
+    println!();
+    println!(
+        "  {}",
+        group_secret(&enrollment.secret).bright_white().bold()
+    );
+    println!();
+    println!(
+        "{}",
+        "or with this link, if the app can import one:".dimmed()
+    );
+    println!("  {}", enrollment.uri.dimmed());
+    println!();
+
+    let code = prompt("Code from the app: ")?;
+    client.confirm_totp(&code)?;
+
+    println!("{}", "Two-factor authentication enabled.".green().bold());
+    println!(
+        "{}",
+        "Logging in will now ask for a code from the app.".dimmed()
+    );
+
+    Ok(())
+}
+
+/// Turn off two-factor authentication (interactive).
+pub fn disable_totp(password: Option<&str>, code: Option<&str>) -> Result<()> {
+    let client = logged_in_client()?;
+    let pass = match password {
+        Some(p) => p.to_string(),
+        None => prompt_password("Password: ")?,
+    };
+    let code = match code {
+        Some(c) => c.to_string(),
+        None => prompt("Two-factor code: ")?,
+    };
+    client.disable_totp(&pass, &code)?;
+
+    println!("{}", "Two-factor authentication disabled.".green());
+
+    Ok(())
+}
+
+/// Length of the session and API token id prefixes shown by `devices` and
+/// `tokens`, and accepted when revoking one
+const SHORT_ID_LEN: usize = 8;
+
+fn logged_in_client() -> Result<ApiClient> {
+    let creds = Credentials::load()?.ok_or_else(|| {
+        TaskbookError::Auth("not logged in — run `tb register` or `tb login` first".to_string())
+    })?;
+    Ok(ApiClient::from_credentials(&creds))
+}
+
+/// List the devices (sessions) logged in to the account.
+pub fn devices() -> Result<()> {
+    let sessions = logged_in_client()?.list_sessions()?;
+    let now = Local::now();
+
+    for session in sessions {
+        let last_used = session
+            .last_used_at
+            .as_deref()
+            .or(Some(&session.created_at))
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| relative_time::format(t.timestamp_millis(), now))
+            .unwrap_or_default();
+        let short_id: String = session.id.chars().take(SHORT_ID_LEN).collect();
+        let mut line = format!(
+            "{}  {}  {}  {}",
+            short_id.bold(),
+            session.user_agent.as_deref().unwrap_or("unknown client"),
+            session.ip.as_deref().unwrap_or("-").dimmed(),
+            format!("last used {last_used}").dimmed()
+        );
+        if session.current {
+            line.push_str(&format!("  {}", "(this device)".green()));
+        }
+        println!("{line}");
+    }
+
+    Ok(())
+}
+
+/// Show the account's audit log: logins, failed logins, password changes,
+/// logged-out devices and exports, newest first.
+pub fn audit() -> Result<()> {
+    let events = logged_in_client()?.list_audit()?;
+    if events.is_empty() {
+        println!("No account activity recorded yet.");
+        return Ok(());
+    }
+
+    for entry in events {
+        let event = audit_event_name(&entry.event);
+        let event = if entry.event == "login_failed" {
+            event.red()
+        } else {
+            event.normal()
+        };
+        println!(
+            "{}  {:<21}  {}  {}",
+            sharing::local_time(&entry.created_at).dimmed(),
+            event,
+            entry.ip.as_deref().unwrap_or("-"),
+            entry
+                .user_agent
+                .as_deref()
+                .unwrap_or("unknown client")
+                .dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// How an audit log event is shown
+fn audit_event_name(event: &str) -> String {
+    match event {
+        "login" => "login",
+        "login_failed" => "failed login",
+        "logged_out" => "logged out everywhere",
+        "password_changed" => "password changed",
+        "password_reset" => "password reset",
+        "session_revoked" => "device logged out",
+        "token_created" => "API token created",
+        "token_revoked" => "API token revoked",
+        "data_exported" => "data exported",
+        "sso_linked" => "single sign-on linked",
+        "totp_enabled" => "two-factor authentication enabled",
+        "totp_disabled" => "two-factor authentication disabled",
+        "account_disabled" => "account disabled by an admin",
+        "account_enabled" => "account enabled by an admin",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Log out another device, given the start of its id from `devices`.
+pub fn revoke_device(id: &str) -> Result<()> {
+    let client = logged_in_client()?;
+    let sessions = client.list_sessions()?;
+    let matching: Vec<_> = sessions.iter().filter(|s| s.id.starts_with(id)).collect();
+
+    let session = match matching.as_slice() {
+        [session] => *session,
+        [] => return Err(TaskbookError::General(format!("no device with id {id}"))),
+        _ => {
+            return Err(TaskbookError::General(format!(
+                "more than one device id starts with {id}"
+            )))
+        }
+    };
+    if session.current {
+        return Err(TaskbookError::General(
+            "that is this device — use `tb --logout` instead".to_string(),
+        ));
+    }
+
+    client.revoke_session(&session.id)?;
+    println!("{}", "Device logged out.".green());
+
+    Ok(())
+}
+
+/// List the account's API tokens.
+pub fn tokens() -> Result<()> {
+    let tokens = logged_in_client()?.list_tokens()?;
+    if tokens.is_empty() {
+        println!("No API tokens. Create one with `tb sync tokens --create <name>`.");
+        return Ok(());
+    }
+    let now = Local::now();
+
+    for token in tokens {
+        let last_used = token
+            .last_used_at
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| {
+                format!(
+                    "last used {}",
+                    relative_time::format(t.timestamp_millis(), now)
+                )
+            })
+            .unwrap_or_else(|| "never used".to_string());
+        let expires = token
+            .expires_at
+            .as_deref()
+            .map(|t| format!("expires {}", sharing::local_time(t)))
+            .unwrap_or_else(|| "no expiry".to_string());
+        let short_id: String = token.id.chars().take(SHORT_ID_LEN).collect();
+        println!(
+            "{}  {}  {}  {}",
+            short_id.bold(),
+            token.name,
+            token.scope.dimmed(),
+            format!("{last_used}, {expires}").dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Create an API token for scripts and print it.
+pub fn create_token(name: &str, read_only: bool, expires: Option<&str>) -> Result<()> {
+    let expires_in = expires
+        .map(|spec| {
+            sharing::parse_expiry(spec)
+                .map(|d| d.num_seconds())
+                .ok_or_else(|| {
+                    TaskbookError::General(format!(
+                        "invalid expiry {spec:?}: use a number followed by h, d or w"
+                    ))
+                })
+        })
+        .transpose()?;
+    let scope = if read_only { "read" } else { "write" };
+    let created = logged_in_client()?.create_token(name, scope, expires_in)?;
+    let short_id: String = created.id.chars().take(SHORT_ID_LEN).collect();
+
+    println!("Created {scope} token {} ({name}):", short_id.bold());
+    println!("{}", created.token.bold());
+    println!();
+    println!(
+        "{}",
+        "Copy the token now; it is not shown again. Scripts use it by setting TB_TOKEN.".yellow()
+    );
+    if let Some(expires_at) = &created.expires_at {
+        println!("Expires {}.", sharing::local_time(expires_at));
+    }
+
+    Ok(())
+}
+
+/// Revoke an API token, given the start of its id from `tokens`.
+pub fn revoke_token(id: &str) -> Result<()> {
+    let client = logged_in_client()?;
+    let tokens = client.list_tokens()?;
+    let matching: Vec<_> = tokens.iter().filter(|t| t.id.starts_with(id)).collect();
+
+    let token = match matching.as_slice() {
+        [token] => *token,
+        [] => return Err(TaskbookError::General(format!("no API token with id {id}"))),
+        _ => {
+            return Err(TaskbookError::General(format!(
+                "more than one API token id starts with {id}"
+            )))
+        }
+    };
+
+    client.revoke_token(&token.id)?;
+    println!("{}", format!("API token {} revoked.", token.name).green());
+
+    Ok(())
+}
+
+/// List the account's webhooks and how their last delivery went.
+pub fn webhooks() -> Result<()> {
+    let webhooks = logged_in_client()?.list_webhooks()?;
+    if webhooks.is_empty() {
+        println!("No webhooks. Add one with `tb sync webhooks --add <url>`.");
+        return Ok(());
+    }
+
+    for webhook in webhooks {
+        let last = match (&webhook.last_delivery_at, &webhook.last_error) {
+            (None, _) => "never called".dimmed(),
+            (Some(at), None) => format!(
+                "last delivered {} ({})",
+                sharing::local_time(at),
+                webhook.last_status.unwrap_or_default()
+            )
+            .dimmed(),
+            (Some(at), Some(error)) => {
+                format!("last failed {}: {error}", sharing::local_time(at)).red()
+            }
+        };
+        let short_id: String = webhook.id.chars().take(SHORT_ID_LEN).collect();
+        println!("{}  {}  {}", short_id.bold(), webhook.url, last);
+    }
+
+    Ok(())
+}
+
+/// Register a webhook and print its signing secret.
+pub fn add_webhook(url: &str) -> Result<()> {
+    let created = logged_in_client()?.create_webhook(url)?;
+    let short_id: String = created.id.chars().take(SHORT_ID_LEN).collect();
+
+    println!("Added webhook {} for {}.", short_id.bold(), created.url);
+    println!("Signing secret: {}", created.secret.bold());
+    println!();
+    println!(
+        "{}",
+        "Copy the secret now; it is not shown again. Payloads are signed with it \
+         in the X-Taskbook-Signature header."
+            .yellow()
+    );
+
+    Ok(())
+}
+
+/// Find a webhook by the start of its id from `webhooks`.
+fn find_webhook(client: &ApiClient, id: &str) -> Result<WebhookInfo> {
+    let mut matching: Vec<_> = client
+        .list_webhooks()?
+        .into_iter()
+        .filter(|w| w.id.starts_with(id))
+        .collect();
+    match matching.len() {
+        1 => Ok(matching.remove(0)),
+        0 => Err(TaskbookError::General(format!("no webhook with id {id}"))),
+        _ => Err(TaskbookError::General(format!(
+            "more than one webhook id starts with {id}"
+        ))),
+    }
+}
+
+/// Remove a webhook, given the start of its id.
+pub fn remove_webhook(id: &str) -> Result<()> {
+    let client = logged_in_client()?;
+    let webhook = find_webhook(&client, id)?;
+    client.delete_webhook(&webhook.id)?;
+    println!(
+        "{}",
+        format!("Webhook for {} removed.", webhook.url).green()
+    );
+
+    Ok(())
+}
+
+/// Send a test payload to a webhook, given the start of its id.
+pub fn test_webhook(id: &str) -> Result<()> {
+    let client = logged_in_client()?;
+    let webhook = find_webhook(&client, id)?;
+    let test = client.test_webhook(&webhook.id)?;
+    match (test.status, test.error) {
+        (Some(status), _) if (200..300).contains(&status) => {
+            println!("{}", format!("{} answered {status}.", webhook.url).green())
+        }
+        (Some(status), _) => {
+            return Err(TaskbookError::General(format!(
+                "{} answered {status}",
+                webhook.url
+            )))
+        }
+        (None, error) => {
+            return Err(TaskbookError::General(format!(
+                "cannot reach {}: {}",
+                webhook.url,
+                error.unwrap_or_default()
+            )))
+        }
+    }
+
+    Ok(())
+}
+
 /// Show current sync status.
 pub fn status() -> Result<()> {
     let config = Config::load_or_default();
 
+    if let Some(profile) = active_profile() {
+        println!("Profile: {}", profile.bold());
+    }
     if config.sync.enabled {
         println!("Mode:   {}", "remote".green().bold());
         println!("Server: {}", config.sync.server_url);
@@ -191,6 +849,10 @@ pub fn status() -> Result<()> {
         Some(creds) => {
             println!("Credentials: {}", "saved".green());
             println!("Server URL:  {}", creds.server_url);
+            match ApiClient::from_credentials(&creds).usage() {
+                Ok(usage) => println!("Storage:     {}", format_usage(&usage)),
+                Err(e) => println!("Storage:     {}", format!("unavailable ({e})").dimmed()),
+            }
         }
         None => {
             println!("Credentials: {}", "none".dimmed());
@@ -199,3 +861,70 @@ pub fn status() -> Result<()> {
 
     Ok(())
 }
+
+/// `1.2 MiB of 10.0 MiB (12%)`, coloured as the quota fills up
+fn format_usage(usage: &UsageResponse) -> String {
+    let used = format_bytes(usage.used_bytes);
+    let Some(quota) = usage.quota_bytes else {
+        return format!("{used} (no quota)");
+    };
+    let percent = if quota > 0 {
+        usage.used_bytes.saturating_mul(100) / quota
+    } else {
+        100
+    };
+    let text = format!("{used} of {} ({percent}%)", format_bytes(quota));
+    match percent {
+        p if p >= 100 => text.red().bold().to_string(),
+        p if p >= 90 => text.yellow().to_string(),
+        _ => text,
+    }
+}
+
+/// Byte count in binary units, e.g. `12.3 KiB`
+fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{value:.1} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_secret_splits_into_fours() {
+        assert_eq!(group_secret("GEZDGNBVGY"), "GEZD GNBV GY");
+        assert_eq!(group_secret("GEZD"), "GEZD");
+        assert_eq!(group_secret(""), "");
+    }
+
+    #[test]
+    fn format_usage_shows_share_of_quota() {
+        colored::control::set_override(false);
+        let usage = |used_bytes, quota_bytes| UsageResponse {
+            used_bytes,
+            quota_bytes,
+        };
+        assert_eq!(format_usage(&usage(512, None)), "512 B (no quota)");
+        assert_eq!(
+            format_usage(&usage(1536, Some(10 * 1024))),
+            "1.5 KiB of 10.0 KiB (15%)"
+        );
+        assert_eq!(
+            format_usage(&usage(3 << 20, Some(2 << 20))),
+            "3.0 MiB of 2.0 MiB (150%)"
+        );
+    }
+}
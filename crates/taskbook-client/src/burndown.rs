@@ -0,0 +1,134 @@
+//! Burndown and velocity series shared by `tb --burndown` and the TUI
+//! journal header.
+
+use chrono::{Duration, Local, NaiveDate, TimeZone};
+use taskbook_common::{board, StorageItem};
+
+/// Number of days covered by a burndown
+pub const BURNDOWN_DAYS: usize = 14;
+
+const SPARK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Open and completed task counts for one day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurndownDay {
+    pub date: NaiveDate,
+    /// Tasks still open at the end of the day
+    pub remaining: usize,
+    /// Tasks completed during the day
+    pub completed: usize,
+}
+
+/// Build a day-by-day burndown for the `days` days ending on `today`,
+/// optionally limited to one board.
+///
+/// Tasks completed before completion timestamps were recorded count as
+/// done for the whole window.
+pub fn build<'a>(
+    items: impl IntoIterator<Item = &'a StorageItem>,
+    board_name: Option<&str>,
+    today: NaiveDate,
+    days: usize,
+) -> Vec<BurndownDay> {
+    let tasks: Vec<_> = items
+        .into_iter()
+        .filter(|item| {
+            board_name.map_or(true, |name| {
+                item.boards().iter().any(|b| board::board_eq(b, name))
+            })
+        })
+        .filter_map(|item| item.as_task())
+        .collect();
+
+    (0..days)
+        .rev()
+        .map(|offset| {
+            let date = today - Duration::days(offset as i64);
+            let start = day_start_millis(date);
+            let end = day_start_millis(date + Duration::days(1));
+
+            let mut remaining = 0;
+            let mut completed = 0;
+            for task in &tasks {
+                if task.timestamp >= end {
+                    continue;
+                }
+                match (task.is_complete, task.completed_at) {
+                    (true, Some(at)) if at < end => {
+                        if at >= start {
+                            completed += 1;
+                        }
+                    }
+                    (true, None) => {}
+                    _ => remaining += 1,
+                }
+            }
+
+            BurndownDay {
+                date,
+                remaining,
+                completed,
+            }
+        })
+        .collect()
+}
+
+/// Render values as a one-line text sparkline scaled to the largest value
+pub fn sparkline(values: &[usize]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|&value| {
+            let level = (value * (SPARK_BARS.len() - 1))
+                .checked_div(max)
+                .unwrap_or(0);
+            SPARK_BARS[level]
+        })
+        .collect()
+}
+
+fn day_start_millis(date: NaiveDate) -> i64 {
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taskbook_common::Task;
+
+    #[test]
+    fn sparkline_scales_to_max() {
+        assert_eq!(sparkline(&[0, 7, 14]), "▁▄█");
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn build_tracks_remaining_and_completed() {
+        let today = Local::now().date_naive();
+        let two_days_ago = day_start_millis(today - Duration::days(2));
+
+        let mut done = Task::new(1, "Done".to_string(), vec!["work".to_string()], 1);
+        done.timestamp = two_days_ago;
+        done.set_complete(true);
+        let mut open = Task::new(2, "Open".to_string(), vec!["work".to_string()], 1);
+        open.timestamp = two_days_ago;
+        let other = Task::new(3, "Other".to_string(), vec!["home".to_string()], 1);
+
+        let items: Vec<StorageItem> = [done, open, other]
+            .into_iter()
+            .map(StorageItem::Task)
+            .collect();
+        let days = build(&items, Some("work"), today, 3);
+
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[0].remaining, 2);
+        assert_eq!(days[2].date, today);
+        assert_eq!(days[2].remaining, 1);
+        assert_eq!(days[2].completed, 1);
+    }
+}
@@ -0,0 +1,419 @@
+//! `tb caldav push`: mirror tasks to a CalDAV server as VTODOs, so they
+//! show up in phone and desktop task apps (Nextcloud Tasks, Apple
+//! Reminders through iCloud, Thunderbird).
+//!
+//! The mirror is one-way. Each board becomes a calendar, each task a
+//! `<uuid>.ics` resource in it; what was pushed is remembered in
+//! `caldav.json` in the taskbook directory, so tasks that changed are
+//! uploaded again and tasks that are gone (deleted, archived, or turned
+//! into notes) are removed from the server.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use colored::Colorize;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use taskbook_common::board::{board_eq, normalize_board_name};
+use taskbook_common::priority::{HIGH, MEDIUM};
+use taskbook_common::Task;
+
+use crate::api_client::user_agent;
+use crate::auth::prompt_password;
+use crate::config::{CaldavConfig, Config};
+use crate::directory::resolve_taskbook_directory;
+use crate::error::{Result, TaskbookError};
+use crate::taskbook::Taskbook;
+
+const PASSWORD_ENV: &str = "TASKBOOK_CALDAV_PASSWORD";
+
+const STATE_FILE: &str = "caldav.json";
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// iCalendar content lines are folded after this many octets
+const FOLD_WIDTH: usize = 75;
+
+/// A task as last pushed
+#[derive(Debug, Serialize, Deserialize)]
+struct Pushed {
+    url: String,
+    /// Hash of the VTODO, to skip tasks that haven't changed
+    hash: u64,
+}
+
+/// Upload every task to the configured CalDAV server and remove the ones
+/// pushed before that no longer exist
+pub fn push(taskbook_dir: Option<&Path>) -> Result<()> {
+    let config = Config::load()?;
+    let caldav = &config.caldav;
+    let base = caldav
+        .url
+        .as_deref()
+        .filter(|url| !url.trim().is_empty())
+        .ok_or_else(|| {
+            TaskbookError::General(
+                "no CalDAV server configured; set caldav.url in the config file".to_string(),
+            )
+        })?;
+    let base = format!("{}/", base.trim().trim_end_matches('/'));
+    let state_file = resolve_taskbook_directory(taskbook_dir, &config)?.join(STATE_FILE);
+
+    let taskbook = Taskbook::new(taskbook_dir)?;
+    let mut tasks: Vec<Task> = taskbook
+        .get_all_items()?
+        .into_values()
+        .filter_map(|item| item.as_task().cloned())
+        .collect();
+    tasks.sort_by_key(|task| task.id);
+
+    let server = Server::connect(caldav, base)?;
+    // Until the state is saved at the end, an interrupted push leaves the
+    // previous one on record; pushing again is then safe, only slower
+    let mut previous = load_state(&state_file)?;
+    let mut state = HashMap::new();
+    let (mut uploaded, mut unchanged) = (0, 0);
+    for task in &tasks {
+        let calendar = calendar_for(caldav, task);
+        let url = format!("{}{}/{}.ics", server.base, calendar, task.uuid);
+        let body = vtodo(task);
+        let hash = hash(&body);
+        match previous.remove(&task.uuid) {
+            Some(pushed) if pushed.url == url && pushed.hash == hash => unchanged += 1,
+            pushed => {
+                server.put(&calendar, &url, &body)?;
+                // A task moved to another board leaves its old calendar
+                if let Some(old) = pushed.filter(|pushed| pushed.url != url) {
+                    server.delete(&old.url)?;
+                }
+                uploaded += 1;
+            }
+        }
+        state.insert(task.uuid.clone(), Pushed { url, hash });
+    }
+
+    // What's left was pushed before and is gone now
+    for pushed in previous.values() {
+        server.delete(&pushed.url)?;
+    }
+    let removed = previous.len();
+    fs::write(&state_file, serde_json::to_string_pretty(&state)?)?;
+
+    println!(
+        "{}",
+        format!("Pushed {uploaded} tasks to {}.", server.base)
+            .green()
+            .bold()
+    );
+    if unchanged > 0 || removed > 0 {
+        println!(
+            "{}",
+            format!("{unchanged} unchanged, {removed} removed from the server.").dimmed()
+        );
+    }
+    Ok(())
+}
+
+struct Server {
+    client: Client,
+    base: String,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl Server {
+    fn connect(caldav: &CaldavConfig, base: String) -> Result<Self> {
+        let password = match (&caldav.username, std::env::var(PASSWORD_ENV)) {
+            (None, _) => None,
+            (Some(_), Ok(password)) => Some(password),
+            (Some(username), Err(_)) => Some(prompt_password(&format!(
+                "CalDAV password for {username}: "
+            ))?),
+        };
+        let client = Client::builder()
+            .user_agent(user_agent())
+            .timeout(TIMEOUT)
+            .build()
+            .map_err(network)?;
+        Ok(Self {
+            client,
+            base,
+            username: caldav.username.clone(),
+            password,
+        })
+    }
+
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_deref()),
+            None => request,
+        }
+    }
+
+    /// Upload a VTODO, creating its calendar if the server doesn't have it
+    fn put(&self, calendar: &str, url: &str, body: &str) -> Result<()> {
+        let upload = || {
+            self.request(Method::PUT, url)
+                .header("Content-Type", "text/calendar; charset=utf-8")
+                .body(body.to_string())
+                .send()
+                .map_err(network)
+        };
+        let mut response = upload()?;
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::CONFLICT
+        ) {
+            self.make_calendar(calendar)?;
+            response = upload()?;
+        }
+        check(response, url)
+    }
+
+    fn make_calendar(&self, calendar: &str) -> Result<()> {
+        let url = format!("{}{calendar}/", self.base);
+        let method = Method::from_bytes(b"MKCALENDAR").expect("valid method name");
+        let response = self
+            .request(method, &url)
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(mkcalendar_body(calendar))
+            .send()
+            .map_err(network)?;
+        check(response, &url)
+    }
+
+    /// Remove a VTODO; one already gone is fine
+    fn delete(&self, url: &str) -> Result<()> {
+        let response = self.request(Method::DELETE, url).send().map_err(network)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        check(response, url)
+    }
+}
+
+fn network(e: reqwest::Error) -> TaskbookError {
+    TaskbookError::Network(e.to_string())
+}
+
+fn check(response: Response, url: &str) -> Result<()> {
+    let status = response.status();
+    match status {
+        s if s.is_success() => Ok(()),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(TaskbookError::Auth(format!(
+            "the CalDAV server refused {url} ({status}); check caldav.username and \
+             {PASSWORD_ENV}"
+        ))),
+        _ => Err(TaskbookError::General(format!(
+            "CalDAV request to {url} failed ({status})"
+        ))),
+    }
+}
+
+fn mkcalendar_body(calendar: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<c:mkcalendar xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:set>
+    <d:prop>
+      <d:displayname>{}</d:displayname>
+      <c:supported-calendar-component-set>
+        <c:comp name="VTODO"/>
+      </c:supported-calendar-component-set>
+    </d:prop>
+  </d:set>
+</c:mkcalendar>
+"#,
+        xml_escape(calendar)
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The calendar a task goes to: the one configured for its first board, or
+/// one named after the board
+fn calendar_for(caldav: &CaldavConfig, task: &Task) -> String {
+    let board = task.boards.first().map(String::as_str).unwrap_or_default();
+    caldav
+        .calendars
+        .iter()
+        .find(|(name, _)| board_eq(&normalize_board_name(name), board))
+        .map(|(_, calendar)| calendar.trim_matches('/').to_string())
+        .unwrap_or_else(|| slug(board))
+}
+
+/// A URL path segment for a board name: `My Board` is `my-board`
+fn slug(board: &str) -> String {
+    let slug: String = board
+        .trim_start_matches('@')
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "taskbook".to_string()
+    } else {
+        slug
+    }
+}
+
+/// The task as an iCalendar object with one VTODO
+fn vtodo(task: &Task) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//taskbook//tb {}//EN", env!("CARGO_PKG_VERSION")),
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}", task.uuid),
+        format!(
+            "DTSTAMP:{}",
+            utc(task.completed_at.unwrap_or(task.timestamp))
+        ),
+        format!("CREATED:{}", utc(task.timestamp)),
+        format!("SUMMARY:{}", escape(&task.description)),
+    ];
+    // iCalendar priorities run from 1 (highest) to 9; 0 is none
+    match task.priority {
+        HIGH => lines.push("PRIORITY:1".to_string()),
+        MEDIUM => lines.push("PRIORITY:5".to_string()),
+        _ => {}
+    }
+    if task.is_complete {
+        lines.push("STATUS:COMPLETED".to_string());
+        lines.push("PERCENT-COMPLETE:100".to_string());
+        if let Some(completed) = task.completed_at {
+            lines.push(format!("COMPLETED:{}", utc(completed)));
+        }
+    } else if task.in_progress {
+        lines.push("STATUS:IN-PROCESS".to_string());
+    } else {
+        lines.push("STATUS:NEEDS-ACTION".to_string());
+    }
+    if let Some(due) = task.due {
+        lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+    }
+    if !task.tags.is_empty() {
+        let tags: Vec<String> = task.tags.iter().map(|tag| escape(tag)).collect();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+    lines.push("END:VTODO".to_string());
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::new();
+    for line in lines {
+        ics.push_str(&fold(&line));
+        ics.push_str("\r\n");
+    }
+    ics
+}
+
+fn utc(ms: i64) -> String {
+    Utc.timestamp_millis_opt(ms)
+        .single()
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Escape a TEXT value
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+/// Fold a content line into lines of at most 75 octets, continuation lines
+/// starting with a space, without splitting a character
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / FOLD_WIDTH * 3);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > FOLD_WIDTH {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+fn hash(body: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn load_state(path: &Path) -> Result<HashMap<String, Pushed>> {
+    match fs::read_to_string(path) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tasks_become_vtodos() {
+        let mut task = Task::new_with_tags(
+            1,
+            "Pack snacks, water; sunscreen".into(),
+            vec!["My Board".into()],
+            HIGH,
+            vec!["trip".into(), "kids".into()],
+        );
+        task.due = chrono::NaiveDate::from_ymd_opt(2024, 7, 1);
+        task.set_complete(true);
+
+        let ics = vtodo(&task);
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        let lines: Vec<&str> = ics.split("\r\n").collect();
+        assert!(lines.contains(&format!("UID:{}", task.uuid).as_str()));
+        assert!(lines.contains(&"SUMMARY:Pack snacks\\, water\\; sunscreen"));
+        assert!(lines.contains(&"PRIORITY:1"));
+        assert!(lines.contains(&"STATUS:COMPLETED"));
+        assert!(lines.contains(&"DUE;VALUE=DATE:20240701"));
+        assert!(lines.contains(&"CATEGORIES:trip,kids"));
+    }
+
+    #[test]
+    fn long_lines_fold_between_characters() {
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let folded = fold(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= FOLD_WIDTH));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn boards_map_to_calendars() {
+        let mut caldav = CaldavConfig::default();
+        caldav
+            .calendars
+            .insert("@work".to_string(), "personal/".to_string());
+        let task = |board: &str| Task::new(1, "x".into(), vec![board.into()], 1);
+        assert_eq!(calendar_for(&caldav, &task("work")), "personal");
+        assert_eq!(calendar_for(&caldav, &task("My Board")), "my-board");
+        assert_eq!(calendar_for(&caldav, &task("Q3 / Launch!")), "q3-launch");
+    }
+}
@@ -1,16 +1,20 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use base64::Engine;
 use colored::Colorize;
 
 use crate::api_client::{ApiClient, EncryptedItemData};
-use crate::config::Config;
+use crate::config::{Config, TimelineGroup};
 use crate::credentials::Credentials;
 use crate::directory::resolve_taskbook_directory;
 use crate::error::{Result, TaskbookError};
-use crate::storage::{LocalStorage, StorageBackend};
+use crate::passphrase::new_passphrase;
+use crate::report::ReportPeriod;
+use crate::storage::{self, LocalStorage, StorageBackend};
 use crate::taskbook::Taskbook;
 use taskbook_common::encryption::encrypt_item;
+use taskbook_common::StorageItem;
 
 /// Execute CLI commands
 #[allow(clippy::too_many_arguments)]
@@ -20,20 +24,41 @@ pub fn run(
     task: bool,
     restore: bool,
     note: bool,
+    body_from_stdin: bool,
+    cat: bool,
+    history: bool,
+    log_for: Option<String>,
     delete: bool,
     check: bool,
     begin: bool,
     star: bool,
     priority: bool,
     copy: bool,
+    copy_as: Option<String>,
     timeline: bool,
+    group: Option<String>,
     find: bool,
     list: bool,
+    save_filter: Option<String>,
+    use_filter: Option<String>,
     edit: bool,
+    use_editor: bool,
     edit_note: bool,
     r#move: bool,
     clear: bool,
+    dry_run: bool,
     tag: bool,
+    due: bool,
+    comment: bool,
+    journal: bool,
+    attach: bool,
+    detach: bool,
+    open: bool,
+    today: bool,
+    burndown: bool,
+    board: Option<String>,
+    report: Option<String>,
+    json: bool,
     taskbook_dir: Option<PathBuf>,
 ) -> Result<()> {
     let taskbook = Taskbook::new(taskbook_dir.as_deref())?;
@@ -52,11 +77,29 @@ pub fn run(
     }
 
     if note {
+        if body_from_stdin {
+            let body = std::io::read_to_string(std::io::stdin())?;
+            let body = body.trim_end();
+            let body = (!body.is_empty()).then(|| body.to_string());
+            return taskbook.create_note(&input, body);
+        }
         // If no description provided, open external editor
         if input.is_empty() {
             return taskbook.create_note_with_editor();
         }
-        return taskbook.create_note(&input);
+        return taskbook.create_note(&input, None);
+    }
+
+    if cat {
+        return taskbook.print_item_content(&input);
+    }
+
+    if history {
+        return taskbook.display_history(&input);
+    }
+
+    if let Some(id) = log_for {
+        return taskbook.display_commits(&[format!("@{}", id.trim_start_matches('@'))]);
     }
 
     if edit_note {
@@ -89,11 +132,19 @@ pub fn run(
 
     if copy {
         let ids: Vec<u64> = input.iter().filter_map(|s| s.parse().ok()).collect();
-        return taskbook.copy_to_clipboard(&ids);
+        let format = copy_as.as_deref().unwrap_or("text").parse()?;
+        return taskbook.copy_to_clipboard(&ids, format);
     }
 
     if timeline {
-        taskbook.display_by_date()?;
+        let group = group
+            .map(|group| {
+                TimelineGroup::parse(&group).ok_or_else(|| {
+                    TaskbookError::General(format!("unknown timeline grouping: {group}"))
+                })
+            })
+            .transpose()?;
+        taskbook.display_by_date(group)?;
         return taskbook.display_stats();
     }
 
@@ -102,11 +153,23 @@ pub fn run(
     }
 
     if list {
-        taskbook.list_by_attributes(&input)?;
-        return taskbook.display_stats();
+        let mut terms = match use_filter {
+            Some(name) => taskbook.saved_filter(&name)?,
+            None => Vec::new(),
+        };
+        terms.extend(input);
+        taskbook.list_by_attributes(&terms)?;
+        taskbook.display_stats()?;
+        if let Some(name) = save_filter {
+            taskbook.save_filter(&name, &terms)?;
+        }
+        return Ok(());
     }
 
     if edit {
+        if use_editor {
+            return taskbook.edit_description_in_editor(&input);
+        }
         return taskbook.edit_description(&input);
     }
 
@@ -115,18 +178,182 @@ pub fn run(
     }
 
     if clear {
-        return taskbook.clear();
+        return taskbook.clear(&input, dry_run);
     }
 
     if tag {
         return taskbook.update_tags(&input);
     }
 
+    if due {
+        return taskbook.update_due(&input);
+    }
+
+    if comment {
+        return taskbook.add_comment(&input);
+    }
+
+    if journal {
+        return taskbook.open_journal(&input);
+    }
+
+    if attach {
+        return taskbook.attach(&input);
+    }
+
+    if detach {
+        return taskbook.detach(&input);
+    }
+
+    if open {
+        return taskbook.open(&input);
+    }
+
+    if today {
+        taskbook.display_agenda()?;
+        return taskbook.display_stats();
+    }
+
+    if burndown {
+        return taskbook.display_burndown(&input);
+    }
+
+    if let Some(action) = board {
+        return match action.as_str() {
+            "archive" => taskbook.set_board_archived(&input, true),
+            "unarchive" => taskbook.set_board_archived(&input, false),
+            _ => taskbook.display_board_list(),
+        };
+    }
+
+    if let Some(period) = report {
+        let period = ReportPeriod::parse(&period)
+            .ok_or_else(|| TaskbookError::General(format!("unknown report period: {period}")))?;
+        return taskbook.display_report(period, json);
+    }
+
     // Default: display board view and stats
     taskbook.display_by_board()?;
     taskbook.display_stats()
 }
 
+/// Rebuild local storage files from whatever still parses (`tb --repair`).
+pub fn repair(taskbook_dir: Option<PathBuf>, renumber: bool) -> Result<()> {
+    let config = Config::load_or_default();
+    let resolved_dir = resolve_taskbook_directory(taskbook_dir.as_deref(), &config)?;
+    let local = LocalStorage::new(&resolved_dir, &config)?;
+
+    for summary in local.repair(renumber)? {
+        let name = summary
+            .file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let stats = summary.stats;
+        println!(
+            "{}",
+            format!("Repaired {}: {} items kept", name, stats.kept)
+                .green()
+                .bold()
+        );
+        if stats.salvaged {
+            println!(
+                "{}",
+                "  File was not valid JSON; items were recovered by scanning it".yellow()
+            );
+        }
+        println!(
+            "{}",
+            format!(
+                "  {} duplicates removed, {} ids reassigned, {} unreadable entries skipped",
+                stats.duplicates, stats.reassigned, stats.skipped
+            )
+            .dimmed()
+        );
+        if let Some(backup) = summary.backup {
+            println!("{}", format!("  Backup: {}", backup.display()).dimmed());
+        }
+    }
+
+    if config.sync.enabled {
+        println!(
+            "{}",
+            "Sync is enabled, so tb reads from the server; run --migrate to upload the repaired data."
+                .dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Encrypt the local storage files with a passphrase (`tb storage encrypt`).
+pub fn encrypt_storage(taskbook_dir: Option<PathBuf>) -> Result<()> {
+    let config = Config::load_or_default();
+    let resolved_dir = resolve_taskbook_directory(taskbook_dir.as_deref(), &config)?;
+    if storage::is_encrypted(&resolved_dir) {
+        return Err(TaskbookError::General(
+            "local storage is already encrypted".to_string(),
+        ));
+    }
+
+    let local = LocalStorage::new(&resolved_dir, &config)?;
+    let items = local.get()?;
+    let archive = local.get_archive()?;
+
+    let passphrase = new_passphrase("Storage passphrase: ", "Confirm passphrase: ")?;
+    let key = storage::create_storage_key(&resolved_dir, &passphrase, &config)?;
+    let encrypted = LocalStorage::with_key(&resolved_dir, key)?;
+    encrypted.set(&items)?;
+    encrypted.set_archive(&archive)?;
+
+    println!("{}", "Local storage encrypted.".green().bold());
+    println!(
+        "{}",
+        format!(
+            "tb asks for the passphrase on startup unless the system keyring remembers it or {} \
+             is set. It cannot be recovered if lost.",
+            storage::PASSPHRASE_VAR
+        )
+        .dimmed()
+    );
+    if config.sync.enabled {
+        println!(
+            "{}",
+            "Sync is enabled, so tb reads from the server, where items are already encrypted."
+                .dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Store the local storage files unencrypted again (`tb storage decrypt`).
+pub fn decrypt_storage(taskbook_dir: Option<PathBuf>) -> Result<()> {
+    let config = Config::load_or_default();
+    let resolved_dir = resolve_taskbook_directory(taskbook_dir.as_deref(), &config)?;
+    if !storage::is_encrypted(&resolved_dir) {
+        return Err(TaskbookError::General(
+            "local storage is not encrypted".to_string(),
+        ));
+    }
+
+    // Also reads the files an interrupted encrypt or decrypt left plain
+    let local = LocalStorage::new(&resolved_dir, &config)?.allowing_plain_files();
+    let items = local.get()?;
+    let archive = local.get_archive()?;
+
+    // Write the plain files before dropping the key, so nothing is ever
+    // left encrypted without it
+    let plain = LocalStorage::plain(&resolved_dir)?;
+    plain.set(&items)?;
+    plain.set_archive(&archive)?;
+    storage::remove_storage_key(&resolved_dir)?;
+
+    println!("{}", "Local storage decrypted.".green().bold());
+
+    Ok(())
+}
+
 /// Migrate local data to the remote server.
 pub fn migrate(taskbook_dir: Option<PathBuf>) -> Result<()> {
     let creds = Credentials::load()?.ok_or_else(|| {
@@ -138,21 +365,33 @@ pub fn migrate(taskbook_dir: Option<PathBuf>) -> Result<()> {
     let engine = base64::engine::general_purpose::STANDARD;
 
     // Load local data
-    let resolved_dir = resolve_taskbook_directory(taskbook_dir.as_deref())?;
-    let local = LocalStorage::new(&resolved_dir)?;
+    let resolved_dir = resolve_taskbook_directory(taskbook_dir.as_deref(), &config)?;
+    let local = LocalStorage::new(&resolved_dir, &config)?;
 
-    let items = local.get()?;
-    let archive = local.get_archive()?;
+    // Items on local-only boards stay where they are
+    let local_boards = &config.sync.local_boards;
+    let mut kept_local = 0;
+    let mut upload = |items: HashMap<String, StorageItem>| {
+        let before = items.len();
+        let items: Vec<StorageItem> = items
+            .into_values()
+            .filter(|item| !storage::is_local_only(local_boards, item))
+            .collect();
+        kept_local += before - items.len();
+        items
+    };
+    let items = upload(local.get()?);
+    let archive = upload(local.get_archive()?);
 
     // Encrypt and upload items
-    let client = ApiClient::new(&config.sync.server_url, Some(&creds.token));
+    let client = ApiClient::for_credentials(&config.sync.server_url, &creds);
 
     let mut encrypted_items = std::collections::HashMap::new();
-    for (key, item) in &items {
+    for item in &items {
         let encrypted = encrypt_item(&encryption_key, item)
             .map_err(|e| TaskbookError::General(format!("encryption failed: {e}")))?;
         encrypted_items.insert(
-            key.clone(),
+            item.uuid().to_string(),
             EncryptedItemData {
                 data: engine.encode(&encrypted.data),
                 nonce: engine.encode(&encrypted.nonce),
@@ -162,11 +401,11 @@ pub fn migrate(taskbook_dir: Option<PathBuf>) -> Result<()> {
     client.put_items(&encrypted_items)?;
 
     let mut encrypted_archive = std::collections::HashMap::new();
-    for (key, item) in &archive {
+    for item in &archive {
         let encrypted = encrypt_item(&encryption_key, item)
             .map_err(|e| TaskbookError::General(format!("encryption failed: {e}")))?;
         encrypted_archive.insert(
-            key.clone(),
+            item.uuid().to_string(),
             EncryptedItemData {
                 data: engine.encode(&encrypted.data),
                 nonce: engine.encode(&encrypted.nonce),
@@ -185,9 +424,19 @@ pub fn migrate(taskbook_dir: Option<PathBuf>) -> Result<()> {
         .green()
         .bold()
     );
+    if kept_local > 0 {
+        println!(
+            "{}",
+            format!("Kept {kept_local} items on local-only boards on this device.").dimmed()
+        );
+    }
     println!(
         "{}",
-        "To enable sync, set sync.enabled = true in ~/.taskbook.json".dimmed()
+        format!(
+            "To enable sync, set sync.enabled = true in {}",
+            Config::config_file_path()?.display()
+        )
+        .dimmed()
     );
 
     Ok(())
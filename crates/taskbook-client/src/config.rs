@@ -1,9 +1,16 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
-use crate::error::Result;
+use crate::error::{Result, TaskbookError};
+use crate::paths;
 use crate::tui::ViewMode;
+use taskbook_common::board;
+use taskbook_common::encryption::KdfCost;
 
 /// RGB color values
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -37,6 +44,102 @@ pub struct ThemeColors {
     pub pending: Rgb,
     /// Starred item indicator
     pub starred: Rgb,
+    /// Colors of the TUI itself; a custom theme may leave them out
+    #[serde(flatten)]
+    pub ui: UiColors,
+}
+
+/// Colors of the TUI's text, surfaces and controls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UiColors {
+    /// Task descriptions
+    pub text: Rgb,
+    /// Note titles
+    pub note_text: Rgb,
+    /// Titles, section headings and labels
+    pub heading: Rgb,
+    /// Item ids
+    pub item_id: Rgb,
+    /// Completed task descriptions (struck through)
+    pub completed: Rgb,
+    /// Borders and the command line prompt
+    pub border: Rgb,
+    /// Background of the selected item
+    pub selection: Rgb,
+    /// Background of popups
+    pub surface: Rgb,
+    /// Background of the autocomplete menu
+    pub menu: Rgb,
+    /// Background of the selected autocomplete entry
+    pub menu_selected: Rgb,
+    /// Command line cursor
+    pub cursor: Rgb,
+    /// Scrollbar thumb in scrolling popups
+    pub scrollbar: Rgb,
+    /// Search matches in descriptions, shown in inverse video; the
+    /// warning color when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_match: Option<Rgb>,
+    /// Background of the whole screen; the terminal's own when unset.
+    /// Light themes set it so they look right in a dark terminal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<Rgb>,
+}
+
+impl Default for UiColors {
+    fn default() -> Self {
+        // For dark terminals
+        Self {
+            text: Rgb::new(255, 255, 255),
+            note_text: Rgb::new(200, 200, 220),
+            heading: Rgb::new(255, 255, 255),
+            item_id: Rgb::new(180, 180, 200),
+            completed: Rgb::new(140, 140, 160),
+            border: Rgb::new(80, 80, 100),
+            selection: Rgb::new(50, 50, 70),
+            surface: Rgb::new(0, 0, 0),
+            menu: Rgb::new(40, 40, 55),
+            menu_selected: Rgb::new(60, 60, 90),
+            cursor: Rgb::new(255, 255, 255),
+            scrollbar: Rgb::new(120, 120, 140),
+            search_match: None,
+            background: None,
+        }
+    }
+}
+
+impl UiColors {
+    /// From a Catppuccin flavor: Text, Subtext1, Overlay0, Surface2,
+    /// Surface0, Mantle, Surface1 and Rosewater
+    #[allow(clippy::too_many_arguments)]
+    fn catppuccin(
+        text: Rgb,
+        subtext: Rgb,
+        overlay: Rgb,
+        surface2: Rgb,
+        surface0: Rgb,
+        mantle: Rgb,
+        surface1: Rgb,
+        rosewater: Rgb,
+    ) -> Self {
+        Self {
+            text,
+            note_text: subtext,
+            heading: text,
+            item_id: subtext,
+            completed: overlay,
+            border: surface2,
+            selection: surface0,
+            surface: mantle,
+            menu: surface0,
+            menu_selected: surface1,
+            cursor: rosewater,
+            scrollbar: overlay,
+            search_match: None,
+            background: None,
+        }
+    }
 }
 
 impl Default for ThemeColors {
@@ -50,11 +153,22 @@ impl Default for ThemeColors {
             info: Rgb::new(147, 197, 253),
             pending: Rgb::new(216, 180, 254),
             starred: Rgb::new(253, 224, 71),
+            ui: UiColors::default(),
         }
     }
 }
 
 impl ThemeColors {
+    /// Names of the preset themes, as `from_name` takes them
+    pub const PRESETS: &'static [&'static str] = &[
+        "default",
+        "catppuccin-macchiato",
+        "catppuccin-mocha",
+        "catppuccin-frappe",
+        "catppuccin-latte",
+        "high-contrast",
+    ];
+
     /// Catppuccin Macchiato theme
     pub fn catppuccin_macchiato() -> Self {
         Self {
@@ -65,6 +179,16 @@ impl ThemeColors {
             info: Rgb::new(138, 173, 244),    // Blue
             pending: Rgb::new(198, 160, 246), // Mauve
             starred: Rgb::new(238, 212, 159), // Yellow
+            ui: UiColors::catppuccin(
+                Rgb::new(202, 211, 245),
+                Rgb::new(184, 192, 224),
+                Rgb::new(110, 115, 141),
+                Rgb::new(91, 96, 120),
+                Rgb::new(54, 58, 79),
+                Rgb::new(30, 32, 48),
+                Rgb::new(73, 77, 100),
+                Rgb::new(244, 219, 214),
+            ),
         }
     }
 
@@ -78,6 +202,16 @@ impl ThemeColors {
             info: Rgb::new(137, 180, 250),    // Blue
             pending: Rgb::new(203, 166, 247), // Mauve
             starred: Rgb::new(249, 226, 175), // Yellow
+            ui: UiColors::catppuccin(
+                Rgb::new(205, 214, 244),
+                Rgb::new(186, 194, 222),
+                Rgb::new(108, 112, 134),
+                Rgb::new(88, 91, 112),
+                Rgb::new(49, 50, 68),
+                Rgb::new(24, 24, 37),
+                Rgb::new(69, 71, 90),
+                Rgb::new(245, 224, 220),
+            ),
         }
     }
 
@@ -91,6 +225,16 @@ impl ThemeColors {
             info: Rgb::new(140, 170, 238),    // Blue
             pending: Rgb::new(202, 158, 230), // Mauve
             starred: Rgb::new(229, 200, 144), // Yellow
+            ui: UiColors::catppuccin(
+                Rgb::new(198, 208, 245),
+                Rgb::new(181, 191, 226),
+                Rgb::new(115, 121, 148),
+                Rgb::new(98, 104, 128),
+                Rgb::new(65, 69, 89),
+                Rgb::new(41, 44, 60),
+                Rgb::new(81, 87, 109),
+                Rgb::new(242, 213, 207),
+            ),
         }
     }
 
@@ -104,6 +248,20 @@ impl ThemeColors {
             info: Rgb::new(30, 102, 245),    // Blue
             pending: Rgb::new(136, 57, 239), // Mauve
             starred: Rgb::new(223, 142, 29), // Yellow
+            ui: UiColors {
+                // Base, so the theme is light whatever the terminal is
+                background: Some(Rgb::new(239, 241, 245)),
+                ..UiColors::catppuccin(
+                    Rgb::new(76, 79, 105),
+                    Rgb::new(92, 95, 119),
+                    Rgb::new(156, 160, 176),
+                    Rgb::new(172, 176, 190),
+                    Rgb::new(204, 208, 218),
+                    Rgb::new(230, 233, 239),
+                    Rgb::new(188, 192, 204),
+                    Rgb::new(220, 138, 120),
+                )
+            },
         }
     }
 
@@ -117,9 +275,30 @@ impl ThemeColors {
             info: Rgb::new(0, 255, 255),
             pending: Rgb::new(255, 0, 255),
             starred: Rgb::new(255, 255, 0),
+            ui: UiColors {
+                note_text: Rgb::new(255, 255, 255),
+                item_id: Rgb::new(255, 255, 255),
+                completed: Rgb::new(200, 200, 200),
+                border: Rgb::new(255, 255, 255),
+                selection: Rgb::new(0, 0, 160),
+                menu: Rgb::new(0, 0, 0),
+                menu_selected: Rgb::new(0, 0, 160),
+                scrollbar: Rgb::new(255, 255, 255),
+                ..UiColors::default()
+            },
         }
     }
 
+    /// The entry of `PRESETS` that `name` refers to, ignoring case, spaces,
+    /// dashes and underscores as `from_name` does
+    pub fn preset_name(name: &str) -> Option<&'static str> {
+        let normalize = |name: &str| name.to_lowercase().replace(['-', '_', ' '], "");
+        Self::PRESETS
+            .iter()
+            .find(|preset| normalize(preset) == normalize(name))
+            .copied()
+    }
+
     /// Get theme by name
     pub fn from_name(name: &str) -> Option<Self> {
         match name.to_lowercase().replace(['-', '_', ' '], "") {
@@ -160,6 +339,219 @@ impl ThemeConfig {
     }
 }
 
+/// Glyphs for item states and markers, in the CLI and the TUI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Icons {
+    pub complete: String,
+    pub in_progress: String,
+    pub pending: String,
+    pub note: String,
+    pub star: String,
+    /// Before a task's comment count
+    pub comments: String,
+    /// Before an item's attachment count
+    pub attachments: String,
+    /// Before time logged in focus sessions
+    pub timer: String,
+    /// Start of messages saying something worked
+    pub success: String,
+    /// Start of error messages
+    pub error: String,
+    /// Start of warnings
+    pub warning: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self::unicode()
+    }
+}
+
+impl Icons {
+    /// Names of the preset icon sets, as `from_name` takes them
+    pub const PRESETS: &'static [&'static str] = &["unicode", "nerd-font", "ascii"];
+
+    /// Symbols found in most fonts
+    pub fn unicode() -> Self {
+        Self::from_glyphs(["✔", "…", "☐", "●", "★", "💬", "📎", "⏱", "✔", "✖", "⚠"])
+    }
+
+    /// Font Awesome glyphs from a patched Nerd Font
+    pub fn nerd_font() -> Self {
+        Self::from_glyphs([
+            "\u{f046}",
+            "\u{f252}",
+            "\u{f096}",
+            "\u{f24a}",
+            "\u{f005}",
+            "\u{f0e5} ",
+            "\u{f0c6} ",
+            "\u{f017}",
+            "\u{f00c}",
+            "\u{f00d}",
+            "\u{f071}",
+        ])
+    }
+
+    /// Plain ASCII, for any terminal and font
+    pub fn ascii() -> Self {
+        Self::from_glyphs([
+            "[x]", "[~]", "[ ]", "-", "*", "c:", "a:", "t:", "ok", "x", "!",
+        ])
+    }
+
+    fn from_glyphs(glyphs: [&str; 11]) -> Self {
+        let [complete, in_progress, pending, note, star, comments, attachments, timer, success, error, warning] =
+            glyphs.map(str::to_string);
+        Self {
+            complete,
+            in_progress,
+            pending,
+            note,
+            star,
+            comments,
+            attachments,
+            timer,
+            success,
+            error,
+            warning,
+        }
+    }
+
+    /// Get an icon set by name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "unicode" => Some(Self::unicode()),
+            "nerdfont" | "nerd" => Some(Self::nerd_font()),
+            "ascii" => Some(Self::ascii()),
+            _ => None,
+        }
+    }
+
+    /// Replace the glyph named `key` (as in the config file). Returns false
+    /// if there is no glyph by that name.
+    fn set(&mut self, key: &str, glyph: &str) -> bool {
+        let slot = match key {
+            "complete" => &mut self.complete,
+            "inProgress" => &mut self.in_progress,
+            "pending" => &mut self.pending,
+            "note" => &mut self.note,
+            "star" => &mut self.star,
+            "comments" => &mut self.comments,
+            "attachments" => &mut self.attachments,
+            "timer" => &mut self.timer,
+            "success" => &mut self.success,
+            "error" => &mut self.error,
+            "warning" => &mut self.warning,
+            _ => return false,
+        };
+        *slot = glyph.to_string();
+        true
+    }
+}
+
+/// Icon configuration - a preset name, or a preset with some glyphs
+/// replaced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IconsConfig {
+    /// Preset icon set name
+    Preset(String),
+    Custom {
+        /// Preset the glyphs replace those of; unicode when not given
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        preset: Option<String>,
+        /// Glyphs by name: `complete`, `inProgress`, `star`, ...
+        #[serde(flatten)]
+        glyphs: BTreeMap<String, String>,
+    },
+}
+
+impl Default for IconsConfig {
+    fn default() -> Self {
+        IconsConfig::Preset("unicode".to_string())
+    }
+}
+
+impl IconsConfig {
+    /// Resolve to the glyphs to use; unknown names give the unicode set
+    pub fn resolve(&self) -> Icons {
+        match self {
+            IconsConfig::Preset(name) => Icons::from_name(name).unwrap_or_default(),
+            IconsConfig::Custom { preset, glyphs } => {
+                let mut icons = preset
+                    .as_deref()
+                    .and_then(Icons::from_name)
+                    .unwrap_or_default();
+                for (key, glyph) in glyphs {
+                    icons.set(key, glyph);
+                }
+                icons
+            }
+        }
+    }
+}
+
+/// How many colors the terminal can show, and so how theme colors are sent
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMode {
+    /// Guess from `COLORTERM` and `TERM`
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    /// 24-bit RGB
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    /// The nearest of the 256-color palette
+    #[serde(rename = "256")]
+    Ansi256,
+    /// The nearest of the 16 basic colors
+    #[serde(rename = "16")]
+    Ansi16,
+}
+
+/// How much each item line shows, in `tb` output and the TUI
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayDensity {
+    /// No ages or boards
+    Compact,
+    #[default]
+    Normal,
+    /// Boards, ages and time logged in focus sessions on every line
+    Detailed,
+}
+
+impl DisplayDensity {
+    /// The next density, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            DisplayDensity::Compact => DisplayDensity::Normal,
+            DisplayDensity::Normal => DisplayDensity::Detailed,
+            DisplayDensity::Detailed => DisplayDensity::Compact,
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            DisplayDensity::Compact => "compact",
+            DisplayDensity::Normal => "normal",
+            DisplayDensity::Detailed => "detailed",
+        }
+    }
+}
+
+/// What `tb` does with item descriptions too long for the terminal
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Overflow {
+    /// Continue on the next lines, under the start of the description
+    #[default]
+    Wrap,
+    /// Cut short, ending with an ellipsis
+    Truncate,
+}
+
 /// Sort method for items within boards
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -171,15 +563,38 @@ pub enum SortMethod {
     Priority,
     /// Sort by status (pending, in-progress, done), then ID
     Status,
+    /// In the order set by hand (Shift+J/K in the TUI), then ID
+    Manual,
 }
 
 impl SortMethod {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "id" => Some(SortMethod::Id),
+            "priority" => Some(SortMethod::Priority),
+            "status" => Some(SortMethod::Status),
+            "manual" => Some(SortMethod::Manual),
+            _ => None,
+        }
+    }
+
     /// Cycle to the next sort method
     pub fn next(self) -> Self {
         match self {
             SortMethod::Id => SortMethod::Priority,
             SortMethod::Priority => SortMethod::Status,
-            SortMethod::Status => SortMethod::Id,
+            SortMethod::Status => SortMethod::Manual,
+            SortMethod::Manual => SortMethod::Id,
+        }
+    }
+
+    /// Cycle to the previous sort method
+    pub fn prev(self) -> Self {
+        match self {
+            SortMethod::Id => SortMethod::Manual,
+            SortMethod::Priority => SortMethod::Id,
+            SortMethod::Status => SortMethod::Priority,
+            SortMethod::Manual => SortMethod::Status,
         }
     }
 
@@ -189,6 +604,69 @@ impl SortMethod {
             SortMethod::Id => "ID",
             SortMethod::Priority => "Priority",
             SortMethod::Status => "Status",
+            SortMethod::Manual => "Manual",
+        }
+    }
+}
+
+/// Period the timeline groups items by
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineGroup {
+    /// One heading per day
+    #[default]
+    Day,
+    /// One heading per ISO week (Monday to Sunday)
+    Week,
+    /// One heading per calendar month
+    Month,
+}
+
+impl TimelineGroup {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "day" => Some(TimelineGroup::Day),
+            "week" => Some(TimelineGroup::Week),
+            "month" => Some(TimelineGroup::Month),
+            _ => None,
+        }
+    }
+
+    /// Cycle to the next grouping
+    pub fn next(self) -> Self {
+        match self {
+            TimelineGroup::Day => TimelineGroup::Week,
+            TimelineGroup::Week => TimelineGroup::Month,
+            TimelineGroup::Month => TimelineGroup::Day,
+        }
+    }
+
+    /// Display name for the grouping
+    pub fn display_name(self) -> &'static str {
+        match self {
+            TimelineGroup::Day => "Day",
+            TimelineGroup::Week => "Week",
+            TimelineGroup::Month => "Month",
+        }
+    }
+
+    /// First day of the period containing `date`
+    pub fn start_of(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            TimelineGroup::Day => date,
+            TimelineGroup::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+            TimelineGroup::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// Marker shown next to the heading of the period containing today
+    pub fn current_label(self) -> &'static str {
+        match self {
+            TimelineGroup::Day => "Today",
+            TimelineGroup::Week => "This week",
+            TimelineGroup::Month => "This month",
         }
     }
 }
@@ -202,21 +680,244 @@ pub struct SyncConfig {
 
     #[serde(default = "default_server_url")]
     pub server_url: String,
+
+    /// How the TUI hears about changes made elsewhere
+    #[serde(default)]
+    pub transport: SyncTransport,
+
+    /// Keep the session tokens and encryption key in the system keyring
+    /// rather than the credentials file
+    #[serde(default = "default_true")]
+    pub keyring: bool,
+
+    /// Seconds a request to the server may take in all; 0 for no limit
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Seconds to wait for a connection to the server
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+
+    /// Times a request that failed in passing is sent again
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+
+    /// Milliseconds to wait before the first retry, doubled for each one
+    /// after it
+    #[serde(default = "default_retry_delay_ms")]
+    pub retry_delay_ms: u64,
+
+    /// Boards whose items stay in local storage and are never uploaded
+    #[serde(default)]
+    pub local_boards: Vec<String>,
+}
+
+/// Connection the TUI listens on for sync notifications
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncTransport {
+    /// Server-sent events, a long-lived HTTP response
+    #[default]
+    Sse,
+    /// A WebSocket, for proxies that buffer or block SSE
+    WebSocket,
 }
 
 fn default_server_url() -> String {
     "http://localhost:8080".to_string()
 }
 
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+fn default_retry_delay_ms() -> u64 {
+    500
+}
+
 impl Default for SyncConfig {
     fn default() -> Self {
         Self {
             enabled: false,
             server_url: default_server_url(),
+            transport: SyncTransport::default(),
+            keyring: true,
+            timeout_secs: default_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            retries: default_retries(),
+            retry_delay_ms: default_retry_delay_ms(),
+            local_boards: Vec::new(),
+        }
+    }
+}
+
+/// Focus (pomodoro) timer settings for the TUI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusConfig {
+    /// Length of a focus session in minutes
+    #[serde(default = "default_focus_minutes")]
+    pub minutes: u32,
+
+    /// Send a desktop notification when a session ends
+    #[serde(default)]
+    pub notify: bool,
+}
+
+fn default_focus_minutes() -> u32 {
+    25
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        Self {
+            minutes: default_focus_minutes(),
+            notify: false,
         }
     }
 }
 
+/// Cost of deriving encryption keys from passphrases, used for keys created
+/// from now on. Existing keys keep the cost they were created with until
+/// `tb sync rotate-key` or a new passphrase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionConfig {
+    /// Argon2id memory cost in KiB
+    #[serde(default = "default_kdf_memory_kib")]
+    pub kdf_memory_kib: u32,
+
+    /// Argon2id passes over the memory
+    #[serde(default = "default_kdf_iterations")]
+    pub kdf_iterations: u32,
+
+    /// Argon2id lanes
+    #[serde(default = "default_kdf_parallelism")]
+    pub kdf_parallelism: u32,
+}
+
+fn default_kdf_memory_kib() -> u32 {
+    KdfCost::default().memory_kib
+}
+
+fn default_kdf_iterations() -> u32 {
+    KdfCost::default().iterations
+}
+
+fn default_kdf_parallelism() -> u32 {
+    KdfCost::default().parallelism
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            kdf_memory_kib: default_kdf_memory_kib(),
+            kdf_iterations: default_kdf_iterations(),
+            kdf_parallelism: default_kdf_parallelism(),
+        }
+    }
+}
+
+impl EncryptionConfig {
+    /// The configured cost, checked to be usable
+    pub fn kdf_cost(&self) -> Result<KdfCost> {
+        let cost = KdfCost {
+            memory_kib: self.kdf_memory_kib,
+            iterations: self.kdf_iterations,
+            parallelism: self.kdf_parallelism,
+        };
+        cost.validate()
+            .map_err(|e| TaskbookError::General(format!("invalid encryption config: {e}")))?;
+        Ok(cost)
+    }
+}
+
+/// Shell commands run on item lifecycle events, each receiving the item
+/// as JSON on stdin
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HooksConfig {
+    /// Run after a task is created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_task_create: Option<String>,
+
+    /// Run after a task is marked complete
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_task_complete: Option<String>,
+
+    /// Run after an item is deleted (moved to the archive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_delete: Option<String>,
+}
+
+/// A CalDAV server that `tb caldav push` mirrors tasks to as VTODOs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaldavConfig {
+    /// The account's calendar home, e.g.
+    /// `https://cloud.example.com/remote.php/dav/calendars/alice/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// Calendar for each board, by the calendar's name in its URL; other
+    /// boards get a calendar named after them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub calendars: BTreeMap<String, String>,
+}
+
+/// Settings that replace the top-level ones while a profile is active.
+/// Anything left out falls back to the top-level value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taskbook_directory: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ThemeConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync: Option<SyncConfig>,
+}
+
+/// Name that selects the top-level settings rather than a profile
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profile applied by `Config::load()`, selected with `--profile` or
+/// `/profile`
+static ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+const THEME_ENV: &str = "TASKBOOK_THEME";
+const SYNC_ENV: &str = "TASKBOOK_SYNC";
+const SYNC_URL_ENV: &str = "TASKBOOK_SYNC_URL";
+const SORT_ENV: &str = "TASKBOOK_SORT";
+const VIEW_ENV: &str = "TASKBOOK_DEFAULT_VIEW";
+const DISPLAY_COMPLETE_ENV: &str = "TASKBOOK_DISPLAY_COMPLETE";
+const PAGER_ENV: &str = "TASKBOOK_PAGER";
+const PROFILE_ENV: &str = "TASKBOOK_PROFILE";
+
+/// Variables read by `Config::apply_env`
+const ENV_OVERRIDES: &[&str] = &[
+    THEME_ENV,
+    SYNC_ENV,
+    SYNC_URL_ENV,
+    SORT_ENV,
+    VIEW_ENV,
+    DISPLAY_COMPLETE_ENV,
+    PAGER_ENV,
+];
+
 /// Configuration settings for taskbook
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -224,6 +925,11 @@ pub struct Config {
     #[serde(default = "default_taskbook_directory")]
     pub taskbook_directory: String,
 
+    /// Use the nearest `.taskbook/` directory in the current directory or
+    /// its ancestors, like git finds `.git`
+    #[serde(default = "default_true")]
+    pub project_discovery: bool,
+
     #[serde(default = "default_true")]
     pub display_complete_tasks: bool,
 
@@ -233,20 +939,108 @@ pub struct Config {
     #[serde(default)]
     pub theme: ThemeConfig,
 
+    /// Colors the terminal can show; theme colors are mapped to them
+    #[serde(default)]
+    pub color_mode: ColorMode,
+
+    /// Glyphs for item states and markers
+    #[serde(default)]
+    pub icons: IconsConfig,
+
+    /// Descriptions too long for the terminal wrap or are truncated
+    #[serde(default)]
+    pub description_overflow: Overflow,
+
+    /// How much each item line shows
+    #[serde(default)]
+    pub display_density: DisplayDensity,
+
     #[serde(default)]
     pub sync: SyncConfig,
 
     #[serde(default)]
     pub sort_method: SortMethod,
 
+    /// Sort methods for individual boards, overriding `sort_method`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub board_sort: BTreeMap<String, SortMethod>,
+
+    /// Boards hidden from the board view and board pickers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archived_boards: Vec<String>,
+
+    /// strftime-style format for dates shown in timelines and messages
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+
+    /// strftime-style format for times of day shown in the journal
+    #[serde(default = "default_time_format")]
+    pub time_format: String,
+
+    /// Show "2 hours ago" style creation times in the timeline and journal
+    #[serde(default = "default_true")]
+    pub relative_timestamps: bool,
+
+    /// Whether the timeline has a heading per day, week or month
+    #[serde(default)]
+    pub timeline_group: TimelineGroup,
+
+    /// Ask before deleting a single item in the TUI; deleting several items
+    /// or clearing completed tasks always asks
+    #[serde(default = "default_true")]
+    pub confirm_delete: bool,
+
     #[serde(default)]
     pub default_view: ViewMode,
+
+    #[serde(default)]
+    pub focus: FocusConfig,
+
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+
+    #[serde(default)]
+    pub caldav: CaldavConfig,
+
+    /// Name written on comments; the login user name if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+
+    /// Command that long listings are piped through; empty or `cat`
+    /// disables paging
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pager: Option<String>,
+
+    /// Named `--list` term combinations, usable from both the CLI and the TUI
+    #[serde(default)]
+    pub saved_filters: BTreeMap<String, Vec<String>>,
+
+    /// Named contexts, each with its own storage directory, theme and sync
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileConfig>,
 }
 
 fn default_taskbook_directory() -> String {
     "~".to_string()
 }
 
+fn default_date_format() -> String {
+    "%a %b %d %Y".to_string()
+}
+
+fn default_time_format() -> String {
+    "%H:%M".to_string()
+}
+
+/// Whether chrono understands every specifier in `format`; formatting with
+/// an invalid one would panic
+fn is_valid_format(format: &str) -> bool {
+    StrftimeItems::new(format).all(|item| !matches!(item, Item::Error))
+}
+
 fn default_true() -> bool {
     true
 }
@@ -255,33 +1049,55 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             taskbook_directory: default_taskbook_directory(),
+            project_discovery: true,
             display_complete_tasks: true,
             display_progress_overview: true,
             theme: ThemeConfig::default(),
+            color_mode: ColorMode::default(),
+            icons: IconsConfig::default(),
+            description_overflow: Overflow::default(),
+            display_density: DisplayDensity::default(),
             sync: SyncConfig::default(),
             sort_method: SortMethod::default(),
+            board_sort: BTreeMap::new(),
+            archived_boards: Vec::new(),
+            date_format: default_date_format(),
+            time_format: default_time_format(),
+            relative_timestamps: true,
+            timeline_group: TimelineGroup::default(),
+            confirm_delete: true,
             default_view: ViewMode::default(),
+            focus: FocusConfig::default(),
+            hooks: HooksConfig::default(),
+            encryption: EncryptionConfig::default(),
+            caldav: CaldavConfig::default(),
+            author: None,
+            pager: None,
+            saved_filters: BTreeMap::new(),
+            profiles: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
-    /// Get the config file path (~/.taskbook.json)
-    fn config_file_path() -> PathBuf {
-        dirs::home_dir()
-            .expect("Could not find home directory")
-            .join(".taskbook.json")
+    /// Name written on comments: `author`, else the login user name
+    pub fn comment_author(&self) -> String {
+        self.author
+            .clone()
+            .filter(|name| !name.trim().is_empty())
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .unwrap_or_else(|| "me".to_string())
     }
 
-    /// Ensure the config file exists, creating it with defaults if not
-    fn ensure_config_file() -> Result<()> {
-        let config_path = Self::config_file_path();
-        if !config_path.exists() {
-            let default_config = Config::default();
-            let data = serde_json::to_string_pretty(&default_config)?;
-            fs::write(&config_path, data)?;
-        }
-        Ok(())
+    /// Get the config file path (see `paths::config_file`)
+    pub fn config_file_path() -> Result<PathBuf> {
+        paths::config_file()
+    }
+
+    /// Whether the config file has been written yet
+    pub fn file_exists() -> bool {
+        Self::config_file_path().is_ok_and(|path| path.exists())
     }
 
     /// Format a taskbook directory path, expanding ~ to home directory
@@ -299,13 +1115,103 @@ impl Config {
         }
     }
 
-    /// Load configuration from file, merging with defaults
+    /// Load configuration from file, merging with defaults and applying the
+    /// active profile.
+    ///
+    /// A missing file yields the defaults; the file is only written by `save()`.
     pub fn load() -> Result<Self> {
-        Self::ensure_config_file()?;
+        let mut config = Self::load_file()?;
+        if let Some(name) = active_profile() {
+            config.apply_profile(&name)?;
+        }
+        config.apply_env(env_value)?;
+        Ok(config)
+    }
+
+    fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let profile = self.profile(name)?.clone();
+        if let Some(dir) = profile.taskbook_directory {
+            self.taskbook_directory = Self::format_taskbook_dir(&dir)
+                .to_string_lossy()
+                .to_string();
+        }
+        if let Some(theme) = profile.theme {
+            self.theme = theme;
+        }
+        if let Some(sync) = profile.sync {
+            self.sync = sync;
+        }
+        Ok(())
+    }
+
+    /// Replace settings with those given in `TASKBOOK_*` environment
+    /// variables (`TASKBOOK_DIR` is handled by directory resolution)
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        if let Some(theme) = var(THEME_ENV) {
+            self.theme = ThemeConfig::Preset(theme);
+        }
+        if let Some(url) = var(SYNC_URL_ENV) {
+            self.sync.server_url = url;
+        }
+        if let Some(value) = var(SYNC_ENV) {
+            self.sync.enabled = parse_env_bool(SYNC_ENV, &value)?;
+        }
+        if let Some(value) = var(SORT_ENV) {
+            self.sort_method = parse_env_enum(SORT_ENV, &value, "id, priority, status or manual")?;
+        }
+        if let Some(value) = var(VIEW_ENV) {
+            self.default_view = parse_env_enum(
+                VIEW_ENV,
+                &value,
+                "board, timeline, archive, journal, calendar, today or stats",
+            )?;
+        }
+        if let Some(value) = var(DISPLAY_COMPLETE_ENV) {
+            self.display_complete_tasks = parse_env_bool(DISPLAY_COMPLETE_ENV, &value)?;
+        }
+        if let Some(pager) = var(PAGER_ENV) {
+            self.pager = Some(pager);
+        }
+        Ok(())
+    }
 
-        let config_path = Self::config_file_path();
-        let content = fs::read_to_string(&config_path)?;
-        let mut config: Config = serde_json::from_str(&content)?;
+    /// Put back the values from `loaded` for every setting taken from the
+    /// environment, so that `save()` never writes them to the file
+    fn unapply_env(&mut self, loaded: &Config, var: impl Fn(&str) -> Option<String>) {
+        if var(THEME_ENV).is_some() {
+            self.theme = loaded.theme.clone();
+        }
+        if var(SYNC_URL_ENV).is_some() {
+            self.sync.server_url = loaded.sync.server_url.clone();
+        }
+        if var(SYNC_ENV).is_some() {
+            self.sync.enabled = loaded.sync.enabled;
+        }
+        if var(SORT_ENV).is_some() {
+            self.sort_method = loaded.sort_method;
+        }
+        if var(VIEW_ENV).is_some() {
+            self.default_view = loaded.default_view;
+        }
+        if var(DISPLAY_COMPLETE_ENV).is_some() {
+            self.display_complete_tasks = loaded.display_complete_tasks;
+        }
+        if var(PAGER_ENV).is_some() {
+            self.pager = loaded.pager.clone();
+        }
+    }
+
+    /// Load the config file as written, without any profile applied
+    fn load_file() -> Result<Self> {
+        Self::load_file_from(&Self::config_file_path()?)
+    }
+
+    fn load_file_from(config_path: &Path) -> Result<Self> {
+        let mut config: Config = match fs::read_to_string(config_path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+            Err(e) => return Err(e.into()),
+        };
 
         // Expand ~ in taskbook_directory
         if config.taskbook_directory.starts_with('~') {
@@ -317,6 +1223,109 @@ impl Config {
         Ok(config)
     }
 
+    fn profile(&self, name: &str) -> Result<&ProfileConfig> {
+        self.profiles.get(name).ok_or_else(|| {
+            TaskbookError::General(format!(
+                "unknown profile '{name}' (add it under \"profiles\" in {})",
+                Self::config_file_path()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|_| "the config file".to_string())
+            ))
+        })
+    }
+
+    /// Switch the profile used by later `load()` calls; `None` or
+    /// `"default"` selects the top-level settings. Fails if the profile is
+    /// not defined in the config file.
+    pub fn use_profile(name: Option<&str>) -> Result<()> {
+        let name = name.filter(|n| *n != DEFAULT_PROFILE);
+        if let Some(name) = name {
+            Self::load_file()?.profile(name)?;
+        }
+        *ACTIVE_PROFILE.lock().unwrap_or_else(|e| e.into_inner()) = name.map(str::to_string);
+        Ok(())
+    }
+
+    /// Whether the active profile has its own sync settings, and so its own
+    /// login
+    pub fn profile_has_own_sync() -> bool {
+        active_profile().is_some_and(|name| {
+            Self::load_file()
+                .ok()
+                .and_then(|config| config.profiles.get(&name).map(|p| p.sync.is_some()))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Sort method for `board`: its `boardSort` entry, else `sortMethod`
+    pub fn sort_method_for(&self, board: &str) -> SortMethod {
+        self.board_sort
+            .iter()
+            .find(|(name, _)| board::board_eq(name, board))
+            .map_or(self.sort_method, |(_, method)| *method)
+    }
+
+    /// Sort `board` by `method`, or by `sortMethod` again when `None`
+    pub fn set_board_sort(&mut self, board: &str, method: Option<SortMethod>) {
+        self.board_sort
+            .retain(|name, _| !board::board_eq(name, board));
+        if let Some(method) = method {
+            self.board_sort.insert(board.to_string(), method);
+        }
+    }
+
+    pub fn is_board_archived(&self, board: &str) -> bool {
+        self.archived_boards
+            .iter()
+            .any(|name| board::board_eq(name, board))
+    }
+
+    /// Archive or unarchive `board`. Returns `false` if it already was.
+    pub fn set_board_archived(&mut self, board: &str, archived: bool) -> bool {
+        if self.is_board_archived(board) == archived {
+            return false;
+        }
+        if archived {
+            self.archived_boards.push(board.to_string());
+        } else {
+            self.archived_boards
+                .retain(|name| !board::board_eq(name, board));
+        }
+        true
+    }
+
+    /// Format a date with `dateFormat`, or the default if it is invalid
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        if is_valid_format(&self.date_format) {
+            date.format(&self.date_format).to_string()
+        } else {
+            date.format(&default_date_format()).to_string()
+        }
+    }
+
+    /// Heading for the timeline period starting on `start`: the date for
+    /// days, "Week 12, 2024" for weeks and "March 2024" for months
+    pub fn format_period(&self, group: TimelineGroup, start: NaiveDate) -> String {
+        match group {
+            TimelineGroup::Day => self.format_date(start),
+            TimelineGroup::Week => {
+                let week = start.iso_week();
+                format!("Week {}, {}", week.week(), week.year())
+            }
+            TimelineGroup::Month => start.format("%B %Y").to_string(),
+        }
+    }
+
+    /// Format a time of day with `timeFormat`, or the default if it is
+    /// invalid
+    pub fn format_time(&self, time: NaiveTime) -> String {
+        if is_valid_format(&self.time_format) {
+            time.format(&self.time_format).to_string()
+        } else {
+            time.format(&default_time_format()).to_string()
+        }
+    }
+
     /// Get the resolved taskbook directory path
     #[allow(dead_code)]
     pub fn get_taskbook_directory(&self) -> PathBuf {
@@ -334,18 +1343,68 @@ impl Config {
         }
     }
 
-    /// Save the configuration to file
+    /// Save the configuration to file.
+    ///
+    /// While a profile is active, the settings it overrides are written back
+    /// to the profile and the top-level values for them are left alone.
     pub fn save(&self) -> Result<()> {
-        let config_path = Self::config_file_path();
-        let data = serde_json::to_string_pretty(self)?;
-        fs::write(&config_path, data)?;
+        self.save_to(&Self::config_file_path()?)
+    }
+
+    /// Save to the config file at `config_path`, as [`save`](Self::save)
+    /// does to the usual one
+    pub fn save_to(&self, config_path: &Path) -> Result<()> {
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let profile = active_profile();
+        let mut config = self.clone();
+        if profile.is_some() || ENV_OVERRIDES.iter().any(|key| env_value(key).is_some()) {
+            let file = Self::load_file_from(config_path)?;
+            let mut loaded = file.clone();
+            if let Some(name) = &profile {
+                loaded.apply_profile(name)?;
+            }
+            config.unapply_env(&loaded, env_value);
+            if let Some(name) = &profile {
+                config = config.split_profile(name, file);
+            }
+        }
+
+        let data = serde_json::to_string_pretty(&config)?;
+        fs::write(config_path, data)?;
         Ok(())
     }
 
-    /// Enable sync with the given server URL and save
+    /// Move the settings overridden by profile `name` back into it,
+    /// restoring the top-level values from `base` (the file on disk)
+    fn split_profile(&self, name: &str, base: Config) -> Config {
+        fn split<T: Clone>(value: &mut T, base: T, slot: &mut Option<T>) {
+            if slot.is_some() {
+                *slot = Some(std::mem::replace(value, base));
+            }
+        }
+
+        let mut config = self.clone();
+        let mut profile = config.profiles.remove(name).unwrap_or_default();
+        split(
+            &mut config.taskbook_directory,
+            base.taskbook_directory,
+            &mut profile.taskbook_directory,
+        );
+        split(&mut config.theme, base.theme, &mut profile.theme);
+        split(&mut config.sync, base.sync, &mut profile.sync);
+        config.profiles.insert(name.to_string(), profile);
+        config
+    }
+
+    /// Enable sync with the given server URL and save. Under a profile the
+    /// sync settings (and so the login) belong to that profile.
     pub fn enable_sync(&mut self, server_url: &str) -> Result<()> {
         self.sync.enabled = true;
         self.sync.server_url = server_url.to_string();
+        self.claim_sync_for_profile();
         self.save()
     }
 
@@ -354,6 +1413,55 @@ impl Config {
         self.sync.enabled = false;
         self.save()
     }
+
+    fn claim_sync_for_profile(&mut self) {
+        if let Some(name) = active_profile() {
+            self.profiles.entry(name).or_default().sync = Some(self.sync.clone());
+        }
+    }
+}
+
+/// Profile named by `TASKBOOK_PROFILE`, used when `--profile` is not given
+pub fn profile_from_env() -> Option<String> {
+    env_value(PROFILE_ENV)
+}
+
+/// Value of an environment variable, treating empty as unset
+fn env_value(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+fn parse_env_bool(key: &str, value: &str) -> Result<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(TaskbookError::General(format!(
+            "invalid {key} value '{value}' (expected true or false)"
+        ))),
+    }
+}
+
+/// Parse a lowercase serde enum (`SortMethod`, `ViewMode`) from a variable
+fn parse_env_enum<T: serde::de::DeserializeOwned>(
+    key: &str,
+    value: &str,
+    expected: &str,
+) -> Result<T> {
+    serde_json::from_value(serde_json::Value::String(value.trim().to_lowercase())).map_err(|_| {
+        TaskbookError::General(format!(
+            "invalid {key} value '{value}' (expected {expected})"
+        ))
+    })
+}
+
+/// Name of the profile applied by `Config::load()`, if any
+pub fn active_profile() -> Option<String> {
+    ACTIVE_PROFILE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
 }
 
 #[cfg(test)]
@@ -367,6 +1475,9 @@ mod tests {
             (ViewMode::Timeline, "\"timeline\""),
             (ViewMode::Archive, "\"archive\""),
             (ViewMode::Journal, "\"journal\""),
+            (ViewMode::Calendar, "\"calendar\""),
+            (ViewMode::Today, "\"today\""),
+            (ViewMode::Stats, "\"stats\""),
         ] {
             let json = serde_json::to_string(&variant).unwrap();
             assert_eq!(json, expected_json);
@@ -387,5 +1498,287 @@ mod tests {
         }"#;
         let config: Config = serde_json::from_str(json).unwrap();
         assert_eq!(config.default_view, ViewMode::Board);
+        assert!(config.saved_filters.is_empty());
+    }
+
+    #[test]
+    fn saved_filters_round_trip() {
+        let mut config = Config::default();
+        config.saved_filters.insert(
+            "urgent".to_string(),
+            vec!["pending".to_string(), "+urgent".to_string()],
+        );
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains("\"savedFilters\""));
+        let deserialized: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            deserialized.saved_filters.get("urgent").unwrap(),
+            &vec!["pending".to_string(), "+urgent".to_string()]
+        );
+    }
+
+    #[test]
+    fn display_density_defaults_to_normal_and_cycles() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.display_density, DisplayDensity::Normal);
+
+        let config: Config = serde_json::from_str(r#"{"displayDensity": "compact"}"#).unwrap();
+        assert_eq!(config.display_density, DisplayDensity::Compact);
+        assert!(serde_json::to_string(&config)
+            .unwrap()
+            .contains(r#""displayDensity":"compact""#));
+
+        let mut density = DisplayDensity::Compact;
+        for expected in [
+            DisplayDensity::Normal,
+            DisplayDensity::Detailed,
+            DisplayDensity::Compact,
+        ] {
+            density = density.next();
+            assert_eq!(density, expected);
+        }
+    }
+
+    #[test]
+    fn focus_config_defaults_and_overrides() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.focus.minutes, 25);
+        assert!(!config.focus.notify);
+
+        let config: Config = serde_json::from_str(r#"{"focus": {"minutes": 50}}"#).unwrap();
+        assert_eq!(config.focus.minutes, 50);
+        assert!(!config.focus.notify);
+    }
+
+    #[test]
+    fn sync_transport_defaults_to_sse() {
+        let config: Config = serde_json::from_str(r#"{"sync": {"enabled": true}}"#).unwrap();
+        assert_eq!(config.sync.transport, SyncTransport::Sse);
+
+        let config: Config =
+            serde_json::from_str(r#"{"sync": {"transport": "websocket"}}"#).unwrap();
+        assert_eq!(config.sync.transport, SyncTransport::WebSocket);
+    }
+
+    #[test]
+    fn encryption_cost_defaults_and_is_checked() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.encryption.kdf_cost().unwrap(), KdfCost::default());
+
+        let config: Config =
+            serde_json::from_str(r#"{"encryption": {"kdfMemoryKib": 65536, "kdfIterations": 3}}"#)
+                .unwrap();
+        let cost = config.encryption.kdf_cost().unwrap();
+        assert_eq!((cost.memory_kib, cost.iterations), (65536, 3));
+
+        let config: Config =
+            serde_json::from_str(r#"{"encryption": {"kdfParallelism": 0}}"#).unwrap();
+        assert!(config.encryption.kdf_cost().is_err());
+    }
+
+    #[test]
+    fn sync_keyring_is_on_unless_turned_off() {
+        let config: Config = serde_json::from_str(r#"{"sync": {"enabled": true}}"#).unwrap();
+        assert!(config.sync.keyring);
+
+        let config: Config = serde_json::from_str(r#"{"sync": {"keyring": false}}"#).unwrap();
+        assert!(!config.sync.keyring);
+    }
+
+    #[test]
+    fn hooks_config_is_optional() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert!(config.hooks.on_task_create.is_none());
+
+        let config: Config =
+            serde_json::from_str(r#"{"hooks": {"onTaskComplete": "notify.sh"}}"#).unwrap();
+        assert_eq!(config.hooks.on_task_complete.as_deref(), Some("notify.sh"));
+        assert!(config.hooks.on_delete.is_none());
+
+        let json = serde_json::to_string(&Config::default()).unwrap();
+        assert!(json.contains(r#""hooks":{}"#));
+    }
+
+    #[test]
+    fn saving_under_a_profile_keeps_its_overrides_in_the_profile() {
+        let base: Config = serde_json::from_str(
+            r#"{"profiles": {"work": {"theme": "catppuccin-mocha", "sync": {"enabled": true}}}}"#,
+        )
+        .unwrap();
+        assert!(base.profiles["work"].taskbook_directory.is_none());
+
+        let mut loaded = base.clone();
+        loaded.theme = ThemeConfig::Preset("high-contrast".to_string());
+        loaded.sync.enabled = true;
+        loaded.sort_method = SortMethod::Priority;
+
+        let saved = loaded.split_profile("work", base);
+        let work = &saved.profiles["work"];
+        assert!(matches!(&saved.theme, ThemeConfig::Preset(name) if name == "default"));
+        assert!(!saved.sync.enabled);
+        assert_eq!(saved.sort_method, SortMethod::Priority);
+        assert!(matches!(&work.theme, Some(ThemeConfig::Preset(name)) if name == "high-contrast"));
+        assert!(work.sync.as_ref().unwrap().enabled);
+        assert!(work.taskbook_directory.is_none());
+    }
+
+    #[test]
+    fn environment_overrides_config_values() {
+        let vars = |key: &str| match key {
+            "TASKBOOK_THEME" => Some("catppuccin-latte".to_string()),
+            "TASKBOOK_SYNC" => Some("yes".to_string()),
+            "TASKBOOK_SYNC_URL" => Some("https://tasks.example.com".to_string()),
+            "TASKBOOK_SORT" => Some("Priority".to_string()),
+            _ => None,
+        };
+        let file = Config::default();
+        let mut config = file.clone();
+        config.apply_env(vars).unwrap();
+
+        assert!(matches!(&config.theme, ThemeConfig::Preset(name) if name == "catppuccin-latte"));
+        assert!(config.sync.enabled);
+        assert_eq!(config.sync.server_url, "https://tasks.example.com");
+        assert_eq!(config.sort_method, SortMethod::Priority);
+        assert_eq!(config.default_view, ViewMode::Board);
+
+        config.default_view = ViewMode::Today;
+        config.unapply_env(&file, vars);
+        assert!(matches!(&config.theme, ThemeConfig::Preset(name) if name == "default"));
+        assert!(!config.sync.enabled);
+        assert_eq!(config.sort_method, SortMethod::Id);
+        assert_eq!(config.default_view, ViewMode::Today);
+
+        let invalid = |key: &str| (key == "TASKBOOK_SORT").then(|| "size".to_string());
+        assert!(Config::default().apply_env(invalid).is_err());
+    }
+
+    #[test]
+    fn date_and_time_formats_fall_back_when_invalid() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        let time = NaiveTime::from_hms_opt(14, 7, 0).unwrap();
+
+        let mut config = Config::default();
+        assert_eq!(config.format_date(date), "Tue Mar 05 2024");
+        assert_eq!(config.format_time(time), "14:07");
+
+        config.date_format = "%d.%m.%Y".to_string();
+        config.time_format = "%I:%M %p".to_string();
+        assert_eq!(config.format_date(date), "05.03.2024");
+        assert_eq!(config.format_time(time), "02:07 PM");
+
+        config.date_format = "%Q".to_string();
+        assert_eq!(config.format_date(date), "Tue Mar 05 2024");
+    }
+
+    #[test]
+    fn timeline_groups_start_on_monday_and_the_first() {
+        let config = Config::default();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 7).unwrap();
+
+        let week = TimelineGroup::Week.start_of(date);
+        assert_eq!(week, NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        assert_eq!(
+            config.format_period(TimelineGroup::Week, week),
+            "Week 10, 2024"
+        );
+
+        let month = TimelineGroup::Month.start_of(date);
+        assert_eq!(month, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert_eq!(
+            config.format_period(TimelineGroup::Month, month),
+            "March 2024"
+        );
+
+        assert_eq!(TimelineGroup::Day.start_of(date), date);
+        assert_eq!(TimelineGroup::parse("Week"), Some(TimelineGroup::Week));
+        assert_eq!(TimelineGroup::parse("year"), None);
+    }
+
+    #[test]
+    fn board_sort_overrides_the_global_method() {
+        let mut config = Config {
+            sort_method: SortMethod::Status,
+            ..Config::default()
+        };
+        config.set_board_sort("coding", Some(SortMethod::Priority));
+
+        assert_eq!(config.sort_method_for("Coding"), SortMethod::Priority);
+        assert_eq!(config.sort_method_for("groceries"), SortMethod::Status);
+
+        config.set_board_sort("CODING", Some(SortMethod::Id));
+        assert_eq!(config.board_sort.len(), 1);
+        config.set_board_sort("coding", None);
+        assert_eq!(config.sort_method_for("coding"), SortMethod::Status);
+    }
+
+    #[test]
+    fn sort_methods_cycle_both_ways() {
+        for method in [
+            SortMethod::Id,
+            SortMethod::Priority,
+            SortMethod::Status,
+            SortMethod::Manual,
+        ] {
+            assert_eq!(method.prev().next(), method);
+            assert_eq!(method.next().prev(), method);
+        }
+        assert_eq!(SortMethod::Id.prev(), SortMethod::Manual);
+    }
+
+    #[test]
+    fn custom_theme_without_ui_colors_uses_the_defaults() {
+        let json = r#"{"theme": {
+            "muted": {"r": 1, "g": 1, "b": 1}, "success": {"r": 2, "g": 2, "b": 2},
+            "warning": {"r": 3, "g": 3, "b": 3}, "error": {"r": 4, "g": 4, "b": 4},
+            "info": {"r": 5, "g": 5, "b": 5}, "pending": {"r": 6, "g": 6, "b": 6},
+            "starred": {"r": 7, "g": 7, "b": 7}, "surface": {"r": 250, "g": 250, "b": 250}
+        }}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let colors = config.theme.resolve();
+        assert_eq!(colors.muted.r, 1);
+        assert_eq!(colors.ui.surface.r, 250);
+        assert_eq!(colors.ui.selection.r, UiColors::default().selection.r);
+        assert!(colors.ui.background.is_none());
+
+        assert!(ThemeColors::catppuccin_latte().ui.background.is_some());
+    }
+
+    #[test]
+    fn icons_come_from_a_preset_with_overrides() {
+        let config: Config = serde_json::from_str(r#"{"icons": "ascii"}"#).unwrap();
+        assert_eq!(config.icons.resolve().complete, "[x]");
+
+        let json = r#"{"icons": {"preset": "ascii", "star": "!!", "inProgress": ">"}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let icons = config.icons.resolve();
+        assert_eq!(icons.star, "!!");
+        assert_eq!(icons.in_progress, ">");
+        assert_eq!(icons.pending, "[ ]");
+
+        let json = r#"{"icons": {"note": "-"}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.icons.resolve().complete, Icons::unicode().complete);
+
+        assert_eq!(Config::default().icons.resolve(), Icons::unicode());
+        assert_eq!(Icons::from_name("Nerd Font"), Some(Icons::nerd_font()));
+    }
+
+    #[test]
+    fn missing_config_file_loads_defaults_without_writing_it() {
+        let dir = std::env::temp_dir().join(format!("tb-config-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("config.json");
+
+        let config = Config::load_file_from(&path).unwrap();
+        let defaults = Config::default();
+        let expected = Config {
+            taskbook_directory: defaults.get_taskbook_directory().display().to_string(),
+            ..defaults
+        };
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::to_value(expected).unwrap()
+        );
+        assert!(!path.exists());
+        assert!(!dir.exists());
     }
 }
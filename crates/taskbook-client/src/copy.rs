@@ -0,0 +1,213 @@
+//! What `tb --copy` puts on the clipboard: plain descriptions, a Markdown
+//! checklist, the items as JSON, or the links in them.
+
+use std::str::FromStr;
+
+use arboard::Clipboard;
+use taskbook_common::StorageItem;
+
+use crate::error::{Result, TaskbookError};
+
+/// How copied items are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// Descriptions (note titles), one per line
+    #[default]
+    Text,
+    /// Tasks as a checklist, notes as bullets with their body below
+    Markdown,
+    /// The items as stored, in an array
+    Json,
+    /// The links in the items, one per line
+    Url,
+}
+
+impl FromStr for CopyFormat {
+    type Err = TaskbookError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            "url" => Ok(Self::Url),
+            _ => Err(TaskbookError::General(format!(
+                "unknown copy format: {s} (use text, markdown, json or url)"
+            ))),
+        }
+    }
+}
+
+/// `items` written as `format`
+pub fn format_items(items: &[&StorageItem], format: CopyFormat) -> Result<String> {
+    match format {
+        CopyFormat::Text => Ok(items
+            .iter()
+            .map(|item| item.description())
+            .collect::<Vec<_>>()
+            .join("\n")),
+        CopyFormat::Markdown => Ok(items
+            .iter()
+            .map(|item| markdown(item))
+            .collect::<Vec<_>>()
+            .join("\n")),
+        CopyFormat::Json => Ok(serde_json::to_string_pretty(items)?),
+        CopyFormat::Url => {
+            let mut links: Vec<&str> = Vec::new();
+            for item in items {
+                for link in item_links(item) {
+                    if !links.contains(&link) {
+                        links.push(link);
+                    }
+                }
+            }
+            if links.is_empty() {
+                return Err(TaskbookError::General(
+                    "no links in the selected items".to_string(),
+                ));
+            }
+            Ok(links.join("\n"))
+        }
+    }
+}
+
+/// A note's title and body, as it reads in the editor
+pub fn full_note(item: &StorageItem) -> String {
+    match item.note_body() {
+        Some(body) => format!("{}\n\n{}", item.description(), body),
+        None => item.description().to_string(),
+    }
+}
+
+/// A Markdown checklist of a board: its name as a heading, then its items
+pub fn board_checklist(board: &str, items: &[&StorageItem]) -> String {
+    let mut checklist = format!("## {board}\n\n");
+    checklist.push_str(&format_items(items, CopyFormat::Markdown).unwrap_or_default());
+    checklist
+}
+
+/// Put `text` on the system clipboard
+pub fn set_clipboard(text: String) -> Result<()> {
+    let mut clipboard = Clipboard::new().map_err(|e| TaskbookError::Clipboard(e.to_string()))?;
+    clipboard
+        .set_text(text)
+        .map_err(|e| TaskbookError::Clipboard(e.to_string()))
+}
+
+fn markdown(item: &StorageItem) -> String {
+    match item {
+        StorageItem::Task(task) => {
+            let check = if task.is_complete { "x" } else { " " };
+            format!("- [{check}] {}", task.description)
+        }
+        StorageItem::Note(note) => {
+            let mut lines = vec![format!("- {}", note.title())];
+            // Indented, so the body stays part of the bullet
+            for line in note.body().unwrap_or_default().lines() {
+                lines.push(if line.is_empty() {
+                    String::new()
+                } else {
+                    format!("  {line}")
+                });
+            }
+            lines.join("\n")
+        }
+    }
+}
+
+/// The item's link (such as the issue a task was imported from), then the
+/// web addresses in its description and body
+pub(crate) fn item_links(item: &StorageItem) -> Vec<&str> {
+    let mut links: Vec<&str> = Vec::new();
+    if let Some(link) = item.as_task().and_then(|task| task.link.as_deref()) {
+        links.push(link);
+    }
+    let body = item.note_body().unwrap_or_default();
+    for link in find_urls(item.description()).chain(find_urls(body)) {
+        if !links.contains(&link) {
+            links.push(link);
+        }
+    }
+    links
+}
+
+/// The `http://` and `https://` addresses in `text`, without trailing
+/// punctuation or a closing bracket around them
+pub(crate) fn find_urls(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"'))
+        .filter_map(|word| {
+            let start = word.find("http://").or_else(|| word.find("https://"))?;
+            let mut url = &word[start..];
+            loop {
+                let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'']);
+                // A closing bracket belongs to the URL only if it opened one
+                url = match trimmed.strip_suffix(')') {
+                    Some(rest) if trimmed.matches(')').count() > trimmed.matches('(').count() => {
+                        rest
+                    }
+                    _ => break (trimmed.len() > trimmed.find("//")? + 2).then_some(trimmed),
+                };
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taskbook_common::{Note, Task};
+
+    fn items() -> Vec<StorageItem> {
+        let mut done = Task::new(
+            1,
+            "Review PR #42".to_string(),
+            vec!["coding".to_string()],
+            1,
+        );
+        done.is_complete = true;
+        let open = Task::new(
+            2,
+            "Read https://example.com/guide.".to_string(),
+            vec!["coding".to_string()],
+            1,
+        );
+        let note = Note::new_with_body(
+            3,
+            "Meeting".to_string(),
+            Some("Agenda\n\n(see https://wiki.example.com/Page_(x))".to_string()),
+            vec!["coding".to_string()],
+        );
+        vec![
+            StorageItem::Task(done),
+            StorageItem::Task(open),
+            StorageItem::Note(note),
+        ]
+    }
+
+    #[test]
+    fn markdown_checks_tasks_and_indents_note_bodies() {
+        let items = items();
+        let items: Vec<&StorageItem> = items.iter().collect();
+        assert_eq!(
+            board_checklist("coding", &items),
+            "## coding\n\n\
+             - [x] Review PR #42\n\
+             - [ ] Read https://example.com/guide.\n\
+             - Meeting\n  Agenda\n\n  (see https://wiki.example.com/Page_(x))"
+        );
+    }
+
+    #[test]
+    fn urls_are_found_without_surrounding_punctuation() {
+        let items = items();
+        let items: Vec<&StorageItem> = items.iter().collect();
+        assert_eq!(
+            format_items(&items, CopyFormat::Url).unwrap(),
+            "https://example.com/guide\nhttps://wiki.example.com/Page_(x)"
+        );
+        assert!(format_items(&items[..1], CopyFormat::Url).is_err());
+        assert_eq!(
+            find_urls("(https://a.example/x) and http:// alone").collect::<Vec<_>>(),
+            ["https://a.example/x"]
+        );
+    }
+}
@@ -1,78 +1,154 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use crate::error::Result;
+use crate::config::{active_profile, Config};
+use crate::error::{Result, TaskbookError};
+use crate::paths;
+
+/// Service the secrets are filed under in the system keyring
+const KEYRING_SERVICE: &str = "taskbook";
 
 /// Credentials for server authentication and encryption.
-/// Stored at ~/.taskbook/credentials.json
+/// Stored as `credentials.json` in the default data directory, or
+/// `credentials-<profile>.json` for a profile with its own sync settings.
+/// Unless `sync.keyring` is off, the tokens and encryption key go to the
+/// system keyring and the file only says where they are.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Credentials {
     pub server_url: String,
+    /// Session token; may be left empty when scripts use `TB_TOKEN`
+    #[serde(default)]
     pub token: String,
+    /// Renews the session once `token` expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
     pub encryption_key: String, // base64-encoded 32-byte key
 }
 
+/// The credentials file when the secrets are in the keyring
+#[derive(Serialize, Deserialize)]
+struct KeyringFile {
+    server_url: String,
+    #[serde(default)]
+    keyring: bool,
+}
+
+/// The parts of [`Credentials`] kept in the keyring
+#[derive(Serialize, Deserialize)]
+struct Secrets {
+    token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    encryption_key: String,
+}
+
 impl Credentials {
     fn credentials_path() -> Result<PathBuf> {
-        let home = dirs::home_dir().ok_or_else(|| {
-            crate::error::TaskbookError::General("could not find home directory".to_string())
-        })?;
-        Ok(home.join(".taskbook").join("credentials.json"))
+        let file_name = match active_profile() {
+            Some(profile) if Config::profile_has_own_sync() => {
+                format!("credentials-{profile}.json")
+            }
+            _ => "credentials.json".to_string(),
+        };
+        Ok(paths::data_dir()?.join(file_name))
     }
 
-    /// Load credentials from disk. Returns None if the file doesn't exist.
+    /// Load credentials, from the keyring if the file says they are there.
+    /// Returns None if the file doesn't exist. Credentials found in the
+    /// file are moved to the keyring when it is enabled and available.
     pub fn load() -> Result<Option<Self>> {
         let path = Self::credentials_path()?;
         if !path.exists() {
             return Ok(None);
         }
         let content = fs::read_to_string(&path)?;
+        let file: KeyringFile = serde_json::from_str(&content)?;
+        if file.keyring {
+            let secrets = read_secrets(&path).map_err(|e| {
+                TaskbookError::Auth(format!(
+                    "cannot read credentials from the system keyring ({e}) — \
+                     log in again with `tb sync login`"
+                ))
+            })?;
+            return Ok(Some(Self {
+                server_url: file.server_url,
+                token: secrets.token,
+                refresh_token: secrets.refresh_token,
+                encryption_key: secrets.encryption_key,
+            }));
+        }
+
         let creds: Credentials = serde_json::from_str(&content)?;
+        if Config::load_or_default().sync.keyring && creds.save_to_keyring(&path).is_ok() {
+            creds.write_keyring_file(&path)?;
+        }
         Ok(Some(creds))
     }
 
-    /// Save credentials to disk with restrictive permissions (0600).
+    /// Save credentials: the secrets to the keyring if enabled, and the file
+    /// with restrictive permissions (0600). Without a usable keyring the
+    /// secrets are written to the file, with a warning.
     pub fn save(&self) -> Result<()> {
         let path = Self::credentials_path()?;
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent)?;
+        if Config::load_or_default().sync.keyring {
+            match self.save_to_keyring(&path) {
+                Ok(()) => return self.write_keyring_file(&path),
+                Err(e) => eprintln!(
+                    "Warning: cannot use the system keyring ({e}), saving credentials to {} \
+                     instead. Set sync.keyring to false in the config to skip the keyring.",
+                    path.display()
+                ),
             }
         }
-        let json = serde_json::to_string_pretty(self)?;
-        fs::write(&path, json)?;
-
-        // Set file permissions to owner-only read/write (0600)
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
-        }
-
-        Ok(())
+        write_private(&path, &serde_json::to_string_pretty(self)?)
     }
 
-    /// Delete the credentials file.
+    /// Delete the credentials file and any secrets in the keyring.
     pub fn delete() -> Result<()> {
         let path = Self::credentials_path()?;
         if path.exists() {
+            let keyring = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<KeyringFile>(&content).ok())
+                .is_some_and(|file| file.keyring);
+            if keyring {
+                // Best-effort: the keyring may be locked or gone
+                let _ = keyring_entry(&path).and_then(|entry| entry.delete_credential());
+            }
             fs::remove_file(&path)?;
         }
         Ok(())
     }
 
+    fn save_to_keyring(&self, path: &Path) -> keyring::Result<()> {
+        let secrets = Secrets {
+            token: self.token.clone(),
+            refresh_token: self.refresh_token.clone(),
+            encryption_key: self.encryption_key.clone(),
+        };
+        let json = serde_json::to_string(&secrets)
+            .map_err(|e| keyring::Error::PlatformFailure(Box::new(e)))?;
+        keyring_entry(path)?.set_password(&json)
+    }
+
+    fn write_keyring_file(&self, path: &Path) -> Result<()> {
+        let file = KeyringFile {
+            server_url: self.server_url.clone(),
+            keyring: true,
+        };
+        write_private(path, &serde_json::to_string_pretty(&file)?)
+    }
+
     /// Decode the encryption key from base64.
     pub fn encryption_key_bytes(&self) -> Result<[u8; 32]> {
         use base64::Engine;
         let bytes = base64::engine::general_purpose::STANDARD
             .decode(&self.encryption_key)
-            .map_err(|e| {
-                crate::error::TaskbookError::General(format!("invalid encryption key: {e}"))
-            })?;
+            .map_err(|e| TaskbookError::General(format!("invalid encryption key: {e}")))?;
         if bytes.len() != 32 {
-            return Err(crate::error::TaskbookError::General(format!(
+            return Err(TaskbookError::General(format!(
                 "encryption key must be 32 bytes, got {}",
                 bytes.len()
             )));
@@ -82,3 +158,32 @@ impl Credentials {
         Ok(key)
     }
 }
+
+/// The keyring entry for the credentials file at `path`; the path tells
+/// profiles and data directories apart
+fn keyring_entry(path: &Path) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, &path.display().to_string())
+}
+
+fn read_secrets(path: &Path) -> keyring::Result<Secrets> {
+    let json = keyring_entry(path)?.get_password()?;
+    serde_json::from_str(&json).map_err(|e| keyring::Error::PlatformFailure(Box::new(e)))
+}
+
+/// Write `content` to `path`, readable by the owner only (0600)
+fn write_private(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    fs::write(path, content)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(())
+}
@@ -3,6 +3,7 @@ use std::path::{Path, PathBuf};
 
 use crate::config::Config;
 use crate::error::{Result, TaskbookError};
+use crate::paths;
 
 const TASKBOOK_DIR_NAME: &str = ".taskbook";
 const TASKBOOK_DIR_ENV: &str = "TASKBOOK_DIR";
@@ -15,21 +16,33 @@ fn home_dir() -> Result<PathBuf> {
 /// Resolve the taskbook directory with priority:
 /// 1. --taskbook-dir CLI flag (highest)
 /// 2. TASKBOOK_DIR environment variable
-/// 3. Config file taskbookDirectory
-/// 4. Default ~/.taskbook/ (lowest)
-pub fn resolve_taskbook_directory(cli_taskbook_dir: Option<&Path>) -> Result<PathBuf> {
+/// 3. Nearest `.taskbook/` in the current directory or its ancestors
+///    (unless `projectDiscovery` is off)
+/// 4. Config file taskbookDirectory
+/// 5. Default `$XDG_DATA_HOME/taskbook/` (lowest)
+pub fn resolve_taskbook_directory(
+    cli_taskbook_dir: Option<&Path>,
+    config: &Config,
+) -> Result<PathBuf> {
     // Try to resolve a custom directory
-    if let Some(custom_dir) = resolve_custom_directory(cli_taskbook_dir)? {
+    if let Some(custom_dir) = resolve_custom_directory(cli_taskbook_dir, config)? {
         return Ok(custom_dir);
     }
 
-    // Default to ~/.taskbook/
-    let home = home_dir()?;
-    Ok(home.join(TASKBOOK_DIR_NAME))
+    paths::data_dir()
 }
 
-fn resolve_custom_directory(cli_taskbook_dir: Option<&Path>) -> Result<Option<PathBuf>> {
-    let candidate = select_custom_directory_candidate(cli_taskbook_dir)?;
+fn resolve_custom_directory(
+    cli_taskbook_dir: Option<&Path>,
+    config: &Config,
+) -> Result<Option<PathBuf>> {
+    let candidate = select_custom_directory_candidate(
+        cli_taskbook_dir,
+        env::var(TASKBOOK_DIR_ENV).ok(),
+        env::current_dir().ok().as_deref(),
+        &home_dir()?,
+        config,
+    )?;
 
     let candidate = match candidate {
         Some(c) => c,
@@ -51,7 +64,16 @@ fn resolve_custom_directory(cli_taskbook_dir: Option<&Path>) -> Result<Option<Pa
     Ok(Some(resolved.join(TASKBOOK_DIR_NAME)))
 }
 
-fn select_custom_directory_candidate(cli_taskbook_dir: Option<&Path>) -> Result<Option<String>> {
+/// Pick the custom directory from the CLI flag, `env_dir` (the value of
+/// `TASKBOOK_DIR`), a project directory above `cwd` and the config, in
+/// that order
+fn select_custom_directory_candidate(
+    cli_taskbook_dir: Option<&Path>,
+    env_dir: Option<String>,
+    cwd: Option<&Path>,
+    home_path: &Path,
+    config: &Config,
+) -> Result<Option<String>> {
     // Priority 1: CLI flag
     if let Some(dir) = cli_taskbook_dir {
         let dir_str = dir.to_string_lossy().to_string();
@@ -62,25 +84,40 @@ fn select_custom_directory_candidate(cli_taskbook_dir: Option<&Path>) -> Result<
     }
 
     // Priority 2: Environment variable
-    if let Ok(env_dir) = env::var(TASKBOOK_DIR_ENV) {
+    if let Some(env_dir) = env_dir {
         if !env_dir.trim().is_empty() {
             return Ok(Some(env_dir));
         }
     }
 
-    // Priority 3: Config file
-    if let Ok(config) = Config::load() {
-        let config_dir = &config.taskbook_directory;
-        // Only use config dir if it's not the default home directory
-        let home = home_dir()?.to_string_lossy().to_string();
-        if config_dir != &home && config_dir != "~" {
-            return Ok(Some(config_dir.clone()));
+    // Priority 3: Project directory
+    if config.project_discovery {
+        if let Some(dir) = cwd.and_then(|cwd| discover_project_directory(cwd, home_path)) {
+            return Ok(Some(dir.to_string_lossy().to_string()));
         }
     }
 
+    // Priority 4: Config file
+    let config_dir = &config.taskbook_directory;
+    // Only use config dir if it's not the default home directory
+    let home = home_path.to_string_lossy().to_string();
+    if config_dir != &home && config_dir != "~" {
+        return Ok(Some(config_dir.clone()));
+    }
+
     Ok(None)
 }
 
+/// Nearest `.taskbook/` directory in `start` or its ancestors. The search
+/// stops at the home directory, whose `.taskbook/` is the legacy global one.
+fn discover_project_directory(start: &Path, home: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .take_while(|dir| *dir != home)
+        .map(|dir| dir.join(TASKBOOK_DIR_NAME))
+        .find(|candidate| candidate.is_dir())
+}
+
 fn parse_directory(directory: &str) -> PathBuf {
     let expanded = expand_directory(directory);
     PathBuf::from(&expanded)
@@ -118,3 +155,88 @@ fn assert_directory_exists(directory: &Path, display_path: &str) -> Result<()> {
 
     Err(TaskbookError::InvalidDirectory(display_path.to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn discovery_finds_nearest_project_below_home() {
+        let home = env::temp_dir().join(format!("tb-discover-{}", uuid::Uuid::new_v4()));
+        let project = home.join("code").join("project");
+        let nested = project.join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir_all(home.join(TASKBOOK_DIR_NAME)).unwrap();
+
+        assert_eq!(discover_project_directory(&nested, &home), None);
+
+        fs::create_dir_all(project.join(TASKBOOK_DIR_NAME)).unwrap();
+        assert_eq!(
+            discover_project_directory(&nested, &home),
+            Some(project.join(TASKBOOK_DIR_NAME))
+        );
+        assert_eq!(
+            discover_project_directory(&project, &home),
+            Some(project.join(TASKBOOK_DIR_NAME))
+        );
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn custom_directory_follows_the_documented_order() {
+        let home = env::temp_dir().join(format!("tb-order-{}", uuid::Uuid::new_v4()));
+        let project = home.join("project");
+        fs::create_dir_all(project.join(TASKBOOK_DIR_NAME)).unwrap();
+        let config = Config {
+            taskbook_directory: "/from/config".to_string(),
+            ..Config::default()
+        };
+        let select = |cli: Option<&Path>, env_dir: Option<&str>, config: &Config| {
+            select_custom_directory_candidate(
+                cli,
+                env_dir.map(str::to_string),
+                Some(&project),
+                &home,
+                config,
+            )
+            .unwrap()
+        };
+        let discovered = project
+            .join(TASKBOOK_DIR_NAME)
+            .to_string_lossy()
+            .to_string();
+
+        assert_eq!(
+            select(Some(Path::new("/from/cli")), Some("/from/env"), &config),
+            Some("/from/cli".to_string())
+        );
+        assert_eq!(
+            select(None, Some("/from/env"), &config),
+            Some("/from/env".to_string())
+        );
+        assert_eq!(select(None, Some(" "), &config), Some(discovered.clone()));
+        assert_eq!(select(None, None, &config), Some(discovered));
+
+        let no_discovery = Config {
+            project_discovery: false,
+            ..config.clone()
+        };
+        assert_eq!(
+            select(None, None, &no_discovery),
+            Some("/from/config".to_string())
+        );
+        let home_config = Config {
+            taskbook_directory: home.to_string_lossy().to_string(),
+            ..no_discovery
+        };
+        assert_eq!(select(None, None, &home_config), None);
+        assert!(matches!(
+            select_custom_directory_candidate(Some(Path::new(" ")), None, None, &home, &config),
+            Err(TaskbookError::MissingTaskbookDirValue)
+        ));
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
@@ -15,6 +15,13 @@ const NEW_NOTE_TEMPLATE: &str = "\
 // Delete all content (or leave only comments) to cancel.
 ";
 
+/// Footer appended when editing a task description
+const DESCRIPTION_FOOTER: &str = "\
+// Edit the task description above. Line breaks are joined with spaces.
+// Lines starting with // are comments and will be ignored.
+// Delete all content (or leave only comments) to cancel.
+";
+
 /// Result of parsing editor content
 #[derive(Debug)]
 pub struct NoteContent {
@@ -37,8 +44,14 @@ fn temp_file_path() -> PathBuf {
     env::temp_dir().join(format!("taskbook-note-{}.md", uuid))
 }
 
-/// Open the editor with the given content and return the edited content
+/// Open the editor with the given content and return the edited note content
 pub fn edit_in_external_editor(initial_content: &str) -> Result<Option<NoteContent>> {
+    let content = run_editor(initial_content)?;
+    parse_note_content(&content)
+}
+
+/// Write `initial_content` to a temp file, open it in the editor, and return the result
+fn run_editor(initial_content: &str) -> Result<String> {
     let temp_path = temp_file_path();
 
     // Write initial content to temp file
@@ -81,8 +94,7 @@ pub fn edit_in_external_editor(initial_content: &str) -> Result<Option<NoteConte
     // Clean up temp file
     let _ = fs::remove_file(&temp_path);
 
-    // Parse the content
-    parse_note_content(&content)
+    Ok(content)
 }
 
 /// Open editor for creating a new note
@@ -111,6 +123,32 @@ pub fn edit_existing_note_in_editor(
     edit_in_external_editor(&content)
 }
 
+/// Open editor for editing a task description
+pub fn edit_description_in_editor(description: &str) -> Result<Option<String>> {
+    let content = format!("{}\n\n{}", description, DESCRIPTION_FOOTER);
+    let edited = run_editor(&content)?;
+    Ok(parse_description_content(&edited))
+}
+
+/// Parse editor content into a single-line description
+/// - Lines starting with // are comments (ignored)
+/// - Remaining non-empty lines are trimmed and joined with spaces
+/// - Returns None if nothing but comments and whitespace remain
+fn parse_description_content(content: &str) -> Option<String> {
+    let description = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if description.is_empty() {
+        None
+    } else {
+        Some(description)
+    }
+}
+
 /// Parse editor content into title and body
 /// - Lines starting with // are comments (ignored)
 /// - First non-empty, non-comment line is the title
@@ -241,4 +279,18 @@ mod tests {
             Some("  Indented line\n    More indented")
         );
     }
+
+    #[test]
+    fn test_parse_description_joins_lines() {
+        let content = "Refactor the parser\n  and add tests\n\n// comment\n";
+        assert_eq!(
+            parse_description_content(content).as_deref(),
+            Some("Refactor the parser and add tests")
+        );
+    }
+
+    #[test]
+    fn test_parse_description_only_comments() {
+        assert!(parse_description_content(DESCRIPTION_FOOTER).is_none());
+    }
 }
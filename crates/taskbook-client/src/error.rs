@@ -29,9 +29,29 @@ pub enum TaskbookError {
     #[error("Network error: {0}")]
     Network(String),
 
+    /// The server could not be reached or was busy, and still was after
+    /// the configured retries
+    #[error("Server unavailable: {0}")]
+    Unavailable(String),
+
     #[error("Authentication error: {0}")]
     Auth(String),
 
+    #[error("Authentication error: two-factor code required")]
+    TotpRequired,
+
+    #[error("Authentication error: registration needs an invite code")]
+    InviteRequired,
+
+    #[error("{0}")]
+    QuotaExceeded(String),
+
+    #[error(
+        "cannot decrypt data from the server: it was encrypted with a different key. \
+         If the passphrase was changed on another device, log in again with `tb sync login`"
+    )]
+    WrongKey,
+
     #[error("{0}")]
     General(String),
 }
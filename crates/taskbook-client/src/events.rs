@@ -0,0 +1,36 @@
+//! Change notifications for code embedding [`Taskbook`](crate::Taskbook).
+
+use taskbook_common::StorageItem;
+
+/// A mutation made through `Taskbook`, carrying the item as it was saved.
+///
+/// Deleted items carry their state before they were moved to the archive.
+#[derive(Debug, Clone)]
+pub enum TaskbookEvent {
+    /// A task or note was created
+    Created(StorageItem),
+    /// An existing item changed (description, boards, priority, tags, ...)
+    Updated(StorageItem),
+    /// A task was checked as complete
+    Completed(StorageItem),
+    /// An item was moved to the archive
+    Deleted(StorageItem),
+    /// An archived item was moved back to the boards
+    Restored(StorageItem),
+}
+
+impl TaskbookEvent {
+    /// The item the event is about
+    pub fn item(&self) -> &StorageItem {
+        match self {
+            TaskbookEvent::Created(item)
+            | TaskbookEvent::Updated(item)
+            | TaskbookEvent::Completed(item)
+            | TaskbookEvent::Deleted(item)
+            | TaskbookEvent::Restored(item) => item,
+        }
+    }
+}
+
+/// Callback registered with [`Taskbook::subscribe`](crate::Taskbook::subscribe)
+pub type Subscriber = Box<dyn Fn(&TaskbookEvent)>;
@@ -0,0 +1,279 @@
+//! `tb sync export`: download everything the server holds for the account
+//! and decrypt it into one plain JSON file.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+
+use base64::Engine;
+use chrono::Local;
+use colored::Colorize;
+use serde::Serialize;
+use taskbook_common::encryption::unwrap_board_key;
+use taskbook_common::StorageItem;
+
+use crate::api_client::{AccountExport, ApiClient, BoardMember, EncryptedItemData};
+use crate::credentials::Credentials;
+use crate::error::{Result, TaskbookError};
+use crate::storage::decrypt_with;
+
+/// Newest server export layout this client can read
+const SUPPORTED_VERSION: u32 = 1;
+
+/// The decrypted export, as written to the file
+#[derive(Serialize)]
+struct PlainExport {
+    format: &'static str,
+    version: u32,
+    exported_at: String,
+    server: String,
+    account: serde_json::Value,
+    sessions: serde_json::Value,
+    api_tokens: serde_json::Value,
+    webhooks: serde_json::Value,
+    share_links: serde_json::Value,
+    items: Vec<StorageItem>,
+    archive: Vec<StorageItem>,
+    shared_boards: Vec<PlainBoard>,
+}
+
+#[derive(Serialize)]
+struct PlainBoard {
+    name: String,
+    owner: String,
+    is_owner: bool,
+    can_write: bool,
+    members: Vec<BoardMember>,
+    items: Vec<StorageItem>,
+}
+
+/// Export the account to `output`: a file name, `-` for stdout, or by
+/// default `taskbook-export-<date>.json` in the current directory.
+pub fn export(output: Option<&str>) -> Result<()> {
+    let creds = Credentials::load()?.ok_or_else(|| {
+        TaskbookError::Auth("not logged in — run `tb register` or `tb login` first".to_string())
+    })?;
+    let key = creds.encryption_key_bytes()?;
+    let export = ApiClient::from_credentials(&creds).export()?;
+    let plain = decrypt(export, &key, &creds.server_url)?;
+    let json = serde_json::to_string_pretty(&plain)?;
+
+    if output == Some("-") {
+        let mut stdout = std::io::stdout().lock();
+        // A reader that stops early, like `head`, is not an error
+        return match writeln!(stdout, "{json}") {
+            Err(e) if e.kind() != ErrorKind::BrokenPipe => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+
+    let path = output
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("taskbook-export-{}.json", Local::now().format("%Y-%m-%d")));
+    fs::write(&path, json + "\n")?;
+    // The file holds everything in plain text
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    let boards = plain.shared_boards.len();
+    println!(
+        "{} {} items, {} archived, {} shared board{} → {}",
+        "Exported".green(),
+        plain.items.len(),
+        plain.archive.len(),
+        boards,
+        if boards == 1 { "" } else { "s" },
+        path.bold()
+    );
+    println!(
+        "{}",
+        "The file is not encrypted; keep it somewhere safe.".dimmed()
+    );
+
+    Ok(())
+}
+
+/// Decrypt the server's export of the account at `server` with `key`
+fn decrypt(mut export: AccountExport, key: &[u8; 32], server: &str) -> Result<PlainExport> {
+    if export.version > SUPPORTED_VERSION {
+        return Err(TaskbookError::General(format!(
+            "the server's export format (version {}) is newer than this tb understands — upgrade tb",
+            export.version
+        )));
+    }
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let mut shared_boards = Vec::with_capacity(export.shared_boards.len());
+    for board in export.shared_boards {
+        let wrapped = engine
+            .decode(&board.wrapped_key)
+            .map_err(|e| TaskbookError::General(format!("invalid base64 board key: {e}")))?;
+        let board_key = unwrap_board_key(key, &wrapped)
+            .map_err(|e| TaskbookError::General(format!("cannot open shared board: {e}")))?;
+        let items = export.board_items.remove(&board.id).unwrap_or_default();
+        shared_boards.push(PlainBoard {
+            name: board_key.name,
+            owner: board.owner,
+            is_owner: board.is_owner,
+            can_write: board.can_write,
+            members: board.members,
+            items: decrypt_sorted(&board_key.key, &items)?,
+        });
+    }
+
+    Ok(PlainExport {
+        format: "taskbook-plain-export",
+        version: SUPPORTED_VERSION,
+        exported_at: export.exported_at,
+        server: server.to_string(),
+        account: export.account,
+        sessions: export.sessions,
+        api_tokens: export.api_tokens,
+        webhooks: export.webhooks,
+        share_links: export.share_links,
+        items: decrypt_sorted(key, &export.items)?,
+        archive: decrypt_sorted(key, &export.archive)?,
+        shared_boards,
+    })
+}
+
+/// Decrypt items and put them in id order
+fn decrypt_sorted(
+    key: &[u8; 32],
+    encrypted: &HashMap<String, EncryptedItemData>,
+) -> Result<Vec<StorageItem>> {
+    let mut items = decrypt_with(key, encrypted)?;
+    items.sort_by(|a, b| {
+        a.id()
+            .cmp(&b.id())
+            .then_with(|| a.timestamp().cmp(&b.timestamp()))
+    });
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use taskbook_common::encryption::{
+        generate_key, identity_public_key, wrap_board_key, BoardKey,
+    };
+    use taskbook_common::{Note, Task};
+
+    use super::*;
+    use crate::storage::encrypt_with;
+
+    /// An export as the server sends it, of `items` and `archive` encrypted
+    /// with `key`, and of `board` with its items
+    fn server_export(
+        key: &[u8; 32],
+        items: &[StorageItem],
+        archive: &[StorageItem],
+        board: &BoardKey,
+        board_items: &[StorageItem],
+    ) -> AccountExport {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let wrapped = wrap_board_key(&identity_public_key(key), board).unwrap();
+        serde_json::from_value(json!({
+            "format": "taskbook-export",
+            "version": 1,
+            "exported_at": "2026-03-01T09:00:00Z",
+            "account": {"username": "alice", "email": "alice@example.com"},
+            "sessions": [],
+            "api_tokens": [],
+            "webhooks": [],
+            "share_links": [],
+            "shared_boards": [{
+                "id": "b1",
+                "owner": "bob",
+                "is_owner": false,
+                "can_write": true,
+                "wrapped_key": engine.encode(wrapped),
+                "members": [{"username": "alice", "can_write": true}],
+            }],
+            "items": encrypt_with(key, items).unwrap(),
+            "archive": encrypt_with(key, archive).unwrap(),
+            "board_items": {"b1": encrypt_with(&board.key, board_items).unwrap()},
+        }))
+        .unwrap()
+    }
+
+    fn to_json(items: &[StorageItem]) -> serde_json::Value {
+        serde_json::to_value(items).unwrap()
+    }
+
+    #[test]
+    fn exported_items_decrypt_as_they_were() {
+        let key = generate_key();
+        let board = BoardKey {
+            name: "Family".to_string(),
+            key: generate_key(),
+        };
+        let items = vec![
+            StorageItem::Task(Task::new(
+                1,
+                "Fix bug".to_string(),
+                vec!["coding".to_string()],
+                2,
+            )),
+            StorageItem::Note(Note::new_with_body(
+                2,
+                "Packing list".to_string(),
+                Some("Passport\nCharger".to_string()),
+                vec!["My Board".to_string()],
+            )),
+        ];
+        let mut done = Task::new(3, "File taxes".to_string(), vec!["home".to_string()], 1);
+        done.is_complete = true;
+        let archive = vec![StorageItem::Task(done)];
+        let shared = vec![StorageItem::Task(Task::new(
+            1,
+            "Water plants".to_string(),
+            vec!["Family".to_string()],
+            1,
+        ))];
+
+        let export = server_export(&key, &items, &archive, &board, &shared);
+        let plain = decrypt(export, &key, "https://tasks.example.com").unwrap();
+
+        // Read back from the file as written
+        let written: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string_pretty(&plain).unwrap()).unwrap();
+        assert_eq!(written["format"], "taskbook-plain-export");
+        assert_eq!(written["server"], "https://tasks.example.com");
+        assert_eq!(written["account"]["username"], "alice");
+        assert_eq!(written["items"], to_json(&items));
+        assert_eq!(written["archive"], to_json(&archive));
+        let boards = written["shared_boards"].as_array().unwrap();
+        assert_eq!(boards.len(), 1);
+        assert_eq!(boards[0]["name"], "Family");
+        assert_eq!(boards[0]["owner"], "bob");
+        assert_eq!(boards[0]["items"], to_json(&shared));
+        let parsed: Vec<StorageItem> = serde_json::from_value(written["items"].clone()).unwrap();
+        assert_eq!(to_json(&parsed), to_json(&items));
+    }
+
+    #[test]
+    fn exports_from_a_newer_server_or_another_key_are_refused() {
+        let key = generate_key();
+        let board = BoardKey {
+            name: "Family".to_string(),
+            key: generate_key(),
+        };
+        let items = vec![StorageItem::Task(Task::new(
+            1,
+            "Fix bug".to_string(),
+            vec!["coding".to_string()],
+            1,
+        ))];
+
+        let mut export = server_export(&key, &items, &[], &board, &[]);
+        export.version = SUPPORTED_VERSION + 1;
+        assert!(decrypt(export, &key, "https://tasks.example.com").is_err());
+
+        let export = server_export(&key, &items, &[], &board, &[]);
+        assert!(decrypt(export, &generate_key(), "https://tasks.example.com").is_err());
+    }
+}
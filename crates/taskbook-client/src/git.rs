@@ -0,0 +1,206 @@
+//! `tb --hook install`: a git post-commit hook that links commits to the
+//! tasks their messages mention.
+//!
+//! A commit mentioning `tb:#12` is recorded on task 12; one that says
+//! `fixes tb:#12` (or closes, resolves, done) also checks it. The hook runs
+//! `tb --record-commit <hash>` in the repository, so a project taskbook
+//! (`.taskbook/` in the repository) is the one updated.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use colored::Colorize;
+use taskbook_common::LinkedCommit;
+
+use crate::error::{Result, TaskbookError};
+use crate::taskbook::Taskbook;
+
+const HOOK_NAME: &str = "post-commit";
+
+/// First comment line of the hook script, to recognize our own hook
+const HOOK_MARKER: &str = "# Installed by `tb --hook install`";
+
+/// How a commit message refers to a task
+const REFERENCE: &str = "tb:#";
+
+/// Words that, right before a reference, check the task
+const CLOSING_WORDS: [&str; 10] = [
+    "close", "closes", "closed", "fix", "fixes", "fixed", "resolve", "resolves", "resolved", "done",
+];
+
+/// Run git in the current directory and return its trimmed output
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| TaskbookError::General(format!("cannot run git: {e}")))?;
+    if !output.status.success() {
+        return Err(TaskbookError::General(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Where the current repository keeps its hooks (honoring core.hooksPath)
+fn hook_path() -> Result<PathBuf> {
+    let hooks = git(&["rev-parse", "--git-path", "hooks"])
+        .map_err(|_| TaskbookError::General("not inside a git repository".to_string()))?;
+    Ok(PathBuf::from(hooks).join(HOOK_NAME))
+}
+
+fn is_ours(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|script| script.contains(HOOK_MARKER))
+}
+
+fn hook_script() -> String {
+    // The full path, since git GUIs often run hooks without the user's PATH
+    let tb = std::env::current_exe()
+        .ok()
+        .map(|path| path.to_string_lossy().replace('"', "\\\""))
+        .unwrap_or_else(|| "tb".to_string());
+    format!(
+        "#!/bin/sh\n\
+         {HOOK_MARKER}: links commits that mention {REFERENCE}<id> to tasks\n\
+         \"{tb}\" --record-commit \"$(git rev-parse HEAD)\" || true\n"
+    )
+}
+
+/// Install the post-commit hook in the current repository
+pub fn install_hook() -> Result<()> {
+    let path = hook_path()?;
+    if path.exists() && !is_ours(&path) {
+        return Err(TaskbookError::General(format!(
+            "{} already exists; add `tb --record-commit \"$(git rev-parse HEAD)\"` to it instead",
+            path.display()
+        )));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, hook_script())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    }
+    println!(
+        "{}",
+        format!("Installed the {HOOK_NAME} hook in {}.", path.display())
+            .green()
+            .bold()
+    );
+    println!(
+        "{}",
+        format!(
+            "Mention {REFERENCE}12 in a commit message to link it to task 12, \
+             or fixes {REFERENCE}12 to also check it."
+        )
+        .dimmed()
+    );
+    Ok(())
+}
+
+/// Remove the hook installed by `install_hook`, leaving any other alone
+pub fn uninstall_hook() -> Result<()> {
+    let path = hook_path()?;
+    if !path.exists() {
+        println!("No {HOOK_NAME} hook is installed.");
+        return Ok(());
+    }
+    if !is_ours(&path) {
+        return Err(TaskbookError::General(format!(
+            "{} was not installed by tb; leaving it alone",
+            path.display()
+        )));
+    }
+    fs::remove_file(&path)?;
+    println!("{}", format!("Removed {}.", path.display()).green());
+    Ok(())
+}
+
+/// Record commit `hash` of the current repository on the tasks it mentions
+pub fn record_commit(hash: &str, taskbook_dir: Option<&Path>) -> Result<()> {
+    let show = git(&["show", "-s", "--format=%H%n%ct%n%B", hash])?;
+    let mut lines = show.lines();
+    let (Some(hash), Some(time)) = (lines.next(), lines.next()) else {
+        return Err(TaskbookError::General(format!("cannot read commit {hash}")));
+    };
+    let message: Vec<&str> = lines.collect();
+    let references = references(&message.join("\n"));
+    if references.is_empty() {
+        return Ok(());
+    }
+
+    let commit = LinkedCommit {
+        hash: hash.to_string(),
+        summary: message
+            .first()
+            .map(|s| s.trim())
+            .unwrap_or_default()
+            .to_string(),
+        committed_at: time.parse::<i64>().unwrap_or_default() * 1000,
+    };
+    let taskbook = Taskbook::new(taskbook_dir)?;
+    let (linked, checked) = taskbook.link_commit(&references, &commit)?;
+    if !linked.is_empty() {
+        let ids = |ids: &[u64]| {
+            ids.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut message = format!(
+            "tb: linked {} to {} {}",
+            &hash[..7.min(hash.len())],
+            if linked.len() == 1 { "task" } else { "tasks" },
+            ids(&linked)
+        );
+        if !checked.is_empty() {
+            message.push_str(&format!(", checked {}", ids(&checked)));
+        }
+        println!("{}", message.dimmed());
+    }
+    Ok(())
+}
+
+/// The task ids a commit message mentions, each with whether the mention
+/// closes it. A task mentioned more than once closes if any mention does.
+pub(crate) fn references(message: &str) -> Vec<(u64, bool)> {
+    let mut references: Vec<(u64, bool)> = Vec::new();
+    let mut previous = "";
+    for word in message.split_whitespace() {
+        let closes =
+            CLOSING_WORDS.contains(&previous.trim_end_matches(':').to_lowercase().as_str());
+        previous = word;
+        let Some(rest) = word.find(REFERENCE).map(|at| &word[at + REFERENCE.len()..]) else {
+            continue;
+        };
+        let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+        let Ok(id) = digits.parse::<u64>() else {
+            continue;
+        };
+        match references.iter_mut().find(|(known, _)| *known == id) {
+            Some(reference) => reference.1 |= closes,
+            None => references.push((id, closes)),
+        }
+    }
+    references
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_references_and_closing_words() {
+        let message = "Fix the crash on start (tb:#12)\n\n\
+                       Fixes tb:#3, closes: tb:#7. See also tb:#12 and tb:#x.";
+        assert_eq!(references(message), [(12, false), (3, true), (7, true)]);
+        assert_eq!(references("Mentions tb:#4, then done tb:#4"), [(4, true)]);
+        assert!(references("No task here, only #12").is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+//! Where search terms match in item text, so the matches can be
+//! highlighted in `tb --find` output and the TUI.
+
+use std::ops::Range;
+
+/// Byte ranges of `text` matching any of `terms`, ignoring case; sorted,
+/// with overlapping matches merged
+pub fn match_ranges<S: AsRef<str>>(text: &str, terms: &[S]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for term in terms {
+        let term: Vec<char> = term.as_ref().to_lowercase().chars().collect();
+        if term.is_empty() {
+            continue;
+        }
+        let mut next = 0;
+        for (start, _) in text.char_indices() {
+            if start < next {
+                continue;
+            }
+            if let Some(len) = match_len(&text[start..], &term) {
+                ranges.push(start..start + len);
+                next = start + len;
+            }
+        }
+    }
+
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Length in bytes of the start of `text` that is `term` (already
+/// lowercase) in any case
+fn match_len(text: &str, term: &[char]) -> Option<usize> {
+    let mut matched = 0;
+    for (i, c) in text.char_indices() {
+        for lower in c.to_lowercase() {
+            if matched == term.len() {
+                break;
+            }
+            if lower != term[matched] {
+                return None;
+            }
+            matched += 1;
+        }
+        if matched == term.len() {
+            return Some(i + c.len_utf8());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_ignore_case_and_merge() {
+        assert_eq!(
+            match_ranges("Write the docs, then DOCS again", &["docs"]),
+            vec![10..14, 21..25]
+        );
+        assert_eq!(match_ranges("documentation", &["doc", "cum"]), vec![0..5]);
+        assert_eq!(match_ranges("Größe", &["GRÖ"]), vec![0..4]);
+        assert!(match_ranges("milk", &[""]).is_empty());
+        assert!(match_ranges("milk", &["eggs"]).is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+//! What happened to an item, for `tb history`: the activity log kept in
+//! each item, and the earlier versions kept by the sync server.
+
+use std::collections::HashMap;
+
+use taskbook_common::StorageItem;
+
+use crate::attachments;
+use crate::storage::HistoryEntry;
+
+/// Drop versions that differ from the one before them only in display ID
+/// (older clients encrypted every item again on every save). Each kept
+/// version is dated from when its content was first saved. Newest first.
+pub fn collapse(versions: Vec<HistoryEntry>) -> Vec<HistoryEntry> {
+    let mut kept: Vec<HistoryEntry> = Vec::with_capacity(versions.len());
+    for version in versions.into_iter().rev() {
+        match kept.last_mut() {
+            Some(last) if same_content(&last.item, &version.item) => {
+                last.current |= version.current;
+                last.archived = version.archived;
+            }
+            _ => kept.push(version),
+        }
+    }
+    kept.reverse();
+    kept
+}
+
+fn same_content(a: &StorageItem, b: &StorageItem) -> bool {
+    let mut b = b.clone();
+    b.set_id(a.id());
+    serde_json::to_value(a).ok() == serde_json::to_value(&b).ok()
+}
+
+/// Add what changed since `previous` to the activity log of each item in
+/// `data`. Items are matched by UUID, so renumbering is not logged; an item
+/// new to `data` was created, or restored if it is in `archive`.
+pub fn log_activity(
+    previous: &HashMap<String, StorageItem>,
+    archive: &HashMap<String, StorageItem>,
+    data: &mut HashMap<String, StorageItem>,
+    now: i64,
+) {
+    let by_uuid = |items: &HashMap<String, StorageItem>| -> HashMap<String, StorageItem> {
+        items
+            .values()
+            .map(|item| (item.uuid().to_string(), item.clone()))
+            .collect()
+    };
+    let previous = by_uuid(previous);
+    let archived = by_uuid(archive);
+
+    for item in data.values_mut() {
+        let actions = match previous.get(item.uuid()) {
+            Some(older) => {
+                let mut actions = Vec::new();
+                if older.description() != item.description() {
+                    actions.push("edited".to_string());
+                }
+                actions.extend(changes(older, item));
+                actions
+            }
+            None if archived.contains_key(item.uuid()) => vec!["restored".to_string()],
+            None if item.activity().is_empty() => {
+                let created = item.timestamp();
+                item.log_activity(created, "created".to_string());
+                continue;
+            }
+            // Synced from elsewhere with its log
+            None => continue,
+        };
+        for action in actions {
+            item.log_activity(now, action);
+        }
+    }
+}
+
+/// What changed from `older` to `newer`, besides the description
+pub fn changes(older: &StorageItem, newer: &StorageItem) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    if let (Some(old), Some(new)) = (older.as_task(), newer.as_task()) {
+        if old.is_complete != new.is_complete {
+            changes.push(either(new.is_complete, "checked", "unchecked"));
+        }
+        if old.in_progress != new.in_progress {
+            changes.push(either(new.in_progress, "started", "paused"));
+        }
+        if old.priority != new.priority {
+            changes.push(format!("priority {}", new.priority));
+        }
+    }
+    if older.is_starred() != newer.is_starred() {
+        changes.push(either(newer.is_starred(), "starred", "unstarred"));
+    }
+    if older.boards() != newer.boards() {
+        let boards: Vec<String> = newer.boards().iter().map(|b| format!("@{b}")).collect();
+        changes.push(format!("moved to {}", boards.join(" ")));
+    }
+    if older.tags() != newer.tags() {
+        let added = newer.tags().iter().filter(|t| !older.tags().contains(t));
+        let removed = older.tags().iter().filter(|t| !newer.tags().contains(t));
+        let tags: Vec<String> = added
+            .map(|t| format!("+{t}"))
+            .chain(removed.map(|t| format!("-{t}")))
+            .collect();
+        changes.push(format!("tags {}", tags.join(" ")));
+    }
+    if older.due_date() != newer.due_date() {
+        changes.push(match newer.due_date() {
+            Some(due) => format!("due {due}"),
+            None => "due date removed".to_string(),
+        });
+    }
+    let comments = |item: &StorageItem| item.as_task().map_or(0, |task| task.comments.len());
+    if comments(newer) > comments(older) {
+        changes.push("commented".to_string());
+    }
+    for attachment in newer.attachments() {
+        if !older.attachments().contains(attachment) {
+            changes.push(format!(
+                "attached {}",
+                attachments::display_name(attachment)
+            ));
+        }
+    }
+    if older
+        .attachments()
+        .iter()
+        .any(|a| !newer.attachments().contains(a))
+    {
+        changes.push("removed attachment".to_string());
+    }
+    if older.note_body() != newer.note_body() {
+        changes.push("note body edited".to_string());
+    }
+
+    changes
+}
+
+fn either(on: bool, yes: &str, no: &str) -> String {
+    if on { yes } else { no }.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taskbook_common::Task;
+
+    fn entry(item: &StorageItem, saved_at: i64, current: bool) -> HistoryEntry {
+        HistoryEntry {
+            item: item.clone(),
+            archived: false,
+            saved_at,
+            current,
+        }
+    }
+
+    #[test]
+    fn collapse_keeps_first_save_of_each_content() {
+        let mut task =
+            StorageItem::Task(Task::new(1, "Fix logn bug".into(), vec!["work".into()], 1));
+        let first = task.clone();
+        task.set_id(4);
+        let renumbered = task.clone();
+        task.set_description("Fix login bug".into());
+
+        let versions = vec![
+            entry(&task, 300, true),
+            entry(&renumbered, 200, false),
+            entry(&first, 100, false),
+        ];
+        let kept = collapse(versions);
+
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].item.description(), "Fix login bug");
+        assert!(kept[0].current);
+        assert_eq!(kept[1].saved_at, 100);
+        assert!(!kept[1].current);
+    }
+
+    #[test]
+    fn changes_describe_other_fields() {
+        let old = StorageItem::Task(Task::new(1, "Ship it".into(), vec!["work".into()], 1));
+        let mut new = old.clone();
+        if let Some(task) = new.as_task_mut() {
+            task.is_complete = true;
+            task.priority = 3;
+        }
+        new.set_boards(vec!["release".into()]);
+        new.set_tags(vec!["urgent".into()]);
+
+        assert_eq!(
+            changes(&old, &new),
+            vec!["checked", "priority 3", "moved to @release", "tags +urgent"]
+        );
+        assert!(changes(&old, &old).is_empty());
+    }
+}
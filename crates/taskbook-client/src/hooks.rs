@@ -0,0 +1,152 @@
+//! Lifecycle hook scripts configured under `hooks` in the config file.
+//!
+//! Each hook is a shell command that receives the affected item as JSON on
+//! stdin and the event name in `TASKBOOK_EVENT`.
+
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+
+use taskbook_common::StorageItem;
+
+use crate::config::HooksConfig;
+use crate::error::{Result, TaskbookError};
+use crate::events::TaskbookEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    TaskCreate,
+    TaskComplete,
+    Delete,
+}
+
+impl HookEvent {
+    /// Hook triggered by a change event, if any
+    pub fn for_event(event: &TaskbookEvent) -> Option<Self> {
+        match event {
+            TaskbookEvent::Created(item) if item.is_task() => Some(HookEvent::TaskCreate),
+            TaskbookEvent::Completed(_) => Some(HookEvent::TaskComplete),
+            TaskbookEvent::Deleted(_) => Some(HookEvent::Delete),
+            _ => None,
+        }
+    }
+
+    /// Name of the event as written in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::TaskCreate => "onTaskCreate",
+            HookEvent::TaskComplete => "onTaskComplete",
+            HookEvent::Delete => "onDelete",
+        }
+    }
+
+    /// Configured command for this event, if any
+    pub fn command(self, hooks: &HooksConfig) -> Option<&str> {
+        let command = match self {
+            HookEvent::TaskCreate => &hooks.on_task_create,
+            HookEvent::TaskComplete => &hooks.on_task_complete,
+            HookEvent::Delete => &hooks.on_delete,
+        };
+        command.as_deref().filter(|c| !c.trim().is_empty())
+    }
+}
+
+/// Run a hook and wait for it, sharing the terminal with the hook's output.
+/// A non-zero exit status is reported as an error.
+pub fn run(command: &str, event: HookEvent, item: &StorageItem) -> Result<()> {
+    let child = spawn(command, event, item, false)?;
+    let status = child.wait_with_output()?.status;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(TaskbookError::General(format!(
+            "{} hook failed ({})",
+            event.name(),
+            status
+        )))
+    }
+}
+
+/// Run a hook in the background with its output discarded (for the TUI,
+/// where a slow script must not block input or draw over the screen).
+pub fn run_detached(command: &str, event: HookEvent, item: &StorageItem) {
+    if let Ok(child) = spawn(command, event, item, true) {
+        std::thread::spawn(move || {
+            let _ = child.wait_with_output();
+        });
+    }
+}
+
+fn spawn(command: &str, event: HookEvent, item: &StorageItem, quiet: bool) -> Result<Child> {
+    let payload = serde_json::to_vec(item)?;
+
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+
+    let output = || {
+        if quiet {
+            Stdio::null()
+        } else {
+            Stdio::inherit()
+        }
+    };
+    let mut child = shell
+        .env("TASKBOOK_EVENT", event.name())
+        .stdin(Stdio::piped())
+        .stdout(output())
+        .stderr(output())
+        .spawn()?;
+
+    // Hooks that ignore stdin may exit before reading it; that is fine
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(&payload);
+    }
+    Ok(child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taskbook_common::{Note, Task};
+
+    #[test]
+    fn hooks_follow_events() {
+        let task = StorageItem::Task(Task::new(1, "Task".to_string(), vec![], 1));
+        let note = StorageItem::Note(Note::new(2, "Note".to_string(), vec![]));
+
+        assert_eq!(
+            HookEvent::for_event(&TaskbookEvent::Created(task.clone())),
+            Some(HookEvent::TaskCreate)
+        );
+        assert_eq!(
+            HookEvent::for_event(&TaskbookEvent::Created(note.clone())),
+            None
+        );
+        assert_eq!(
+            HookEvent::for_event(&TaskbookEvent::Completed(task.clone())),
+            Some(HookEvent::TaskComplete)
+        );
+        assert_eq!(
+            HookEvent::for_event(&TaskbookEvent::Deleted(note)),
+            Some(HookEvent::Delete)
+        );
+        assert_eq!(HookEvent::for_event(&TaskbookEvent::Updated(task)), None);
+    }
+
+    #[test]
+    fn blank_commands_are_ignored() {
+        let hooks = HooksConfig {
+            on_task_create: Some("  ".to_string()),
+            on_delete: Some("cat".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(HookEvent::TaskCreate.command(&hooks), None);
+        assert_eq!(HookEvent::Delete.command(&hooks), Some("cat"));
+    }
+}
@@ -0,0 +1,438 @@
+//! Issues from GitHub and GitLab: open issues become tasks on a board named
+//! after the repository, with labels as tags and the issue's URL as the
+//! task's link. Importing again adds new issues, checks tasks whose issues
+//! were closed, and unchecks those whose issues were reopened.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use colored::Colorize;
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::Deserialize;
+use taskbook_common::board::{normalize_board_name, normalize_tag};
+use taskbook_common::priority::NORMAL;
+use taskbook_common::{StorageItem, Task};
+
+use crate::api_client::user_agent;
+use crate::error::{Result, TaskbookError};
+use crate::taskbook::Taskbook;
+
+use super::count;
+
+const GITHUB_API: &str = "https://api.github.com";
+const GITLAB_URL: &str = "https://gitlab.com";
+
+/// Issues asked for per page, the most either forge hands out
+const PAGE_SIZE: usize = 100;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Forge {
+    GitHub,
+    GitLab,
+}
+
+/// An open issue
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Issue {
+    number: u64,
+    title: String,
+    url: String,
+    labels: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubIssue {
+    number: u64,
+    title: String,
+    html_url: String,
+    #[serde(default)]
+    labels: Vec<GitHubLabel>,
+    /// Set on pull requests, which the issues API lists too
+    pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct GitHubLabel {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabIssue {
+    iid: u64,
+    title: String,
+    web_url: String,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+impl Forge {
+    fn name(self) -> &'static str {
+        match self {
+            Forge::GitHub => "GitHub",
+            Forge::GitLab => "GitLab",
+        }
+    }
+
+    /// Base URL of the API (GitHub) or the instance (GitLab), overridable
+    /// for GitHub Enterprise and self-hosted GitLab
+    fn base_url(self) -> String {
+        let (var, default) = match self {
+            Forge::GitHub => ("GITHUB_API_URL", GITHUB_API),
+            Forge::GitLab => ("GITLAB_URL", GITLAB_URL),
+        };
+        std::env::var(var)
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or_else(|| default.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    fn token(self) -> Option<String> {
+        let vars: &[&str] = match self {
+            Forge::GitHub => &["GITHUB_TOKEN", "GH_TOKEN"],
+            Forge::GitLab => &["GITLAB_TOKEN"],
+        };
+        vars.iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|token| !token.trim().is_empty())
+    }
+
+    /// Start of the web URL of every issue in `repo`
+    fn issue_url_prefix(self, repo: &str) -> String {
+        match self {
+            Forge::GitHub => {
+                let base = self.base_url();
+                let web = if base == GITHUB_API {
+                    "https://github.com"
+                } else {
+                    base.trim_end_matches("/api/v3")
+                };
+                format!("{web}/{repo}/issues/")
+            }
+            Forge::GitLab => format!("{}/{repo}/-/issues/", self.base_url()),
+        }
+    }
+
+    fn page_request(self, client: &Client, repo: &str, page: usize) -> RequestBuilder {
+        let token = self.token();
+        match self {
+            Forge::GitHub => {
+                let url = format!("{}/repos/{repo}/issues", self.base_url());
+                let request = client
+                    .get(url)
+                    .header("Accept", "application/vnd.github+json")
+                    .query(&[("state", "open")])
+                    .query(&[("per_page", PAGE_SIZE), ("page", page)]);
+                match token {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+            }
+            Forge::GitLab => {
+                let url = format!(
+                    "{}/api/v4/projects/{}/issues",
+                    self.base_url(),
+                    repo.replace('/', "%2F")
+                );
+                let request = client
+                    .get(url)
+                    .query(&[("state", "opened")])
+                    .query(&[("per_page", PAGE_SIZE), ("page", page)]);
+                match token {
+                    Some(token) => request.header("PRIVATE-TOKEN", token),
+                    None => request,
+                }
+            }
+        }
+    }
+
+    /// Read a page of issues, leaving out pull requests. Also returns how
+    /// many entries the page had, to tell whether another one follows.
+    fn parse_page(self, json: &str) -> Result<(Vec<Issue>, usize)> {
+        match self {
+            Forge::GitHub => {
+                let page: Vec<GitHubIssue> = serde_json::from_str(json)?;
+                let len = page.len();
+                let issues = page
+                    .into_iter()
+                    .filter(|issue| issue.pull_request.is_none())
+                    .map(|issue| Issue {
+                        number: issue.number,
+                        title: issue.title,
+                        url: issue.html_url,
+                        labels: issue.labels.into_iter().map(|label| label.name).collect(),
+                    })
+                    .collect();
+                Ok((issues, len))
+            }
+            Forge::GitLab => {
+                let page: Vec<GitLabIssue> = serde_json::from_str(json)?;
+                let len = page.len();
+                let issues = page
+                    .into_iter()
+                    .map(|issue| Issue {
+                        number: issue.iid,
+                        title: issue.title,
+                        url: issue.web_url,
+                        labels: issue.labels,
+                    })
+                    .collect();
+                Ok((issues, len))
+            }
+        }
+    }
+
+    fn fetch_open_issues(self, repo: &str) -> Result<Vec<Issue>> {
+        let client = Client::builder()
+            .user_agent(user_agent())
+            .timeout(TIMEOUT)
+            .build()
+            .map_err(|e| TaskbookError::Network(e.to_string()))?;
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let response = self
+                .page_request(&client, repo, page)
+                .send()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            let status = response.status();
+            let body = response
+                .text()
+                .map_err(|e| TaskbookError::Network(e.to_string()))?;
+            if !status.is_success() {
+                return Err(self.request_error(repo, status));
+            }
+            let (mut found, len) = self.parse_page(&body)?;
+            issues.append(&mut found);
+            if len < PAGE_SIZE {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    fn request_error(self, repo: &str, status: reqwest::StatusCode) -> TaskbookError {
+        let token_var = match self {
+            Forge::GitHub => "GITHUB_TOKEN",
+            Forge::GitLab => "GITLAB_TOKEN",
+        };
+        match status.as_u16() {
+            401 | 403 | 404 if self.token().is_none() => TaskbookError::Auth(format!(
+                "{} answered {status} for {repo}; for a private repository, set {token_var}",
+                self.name()
+            )),
+            401 | 403 => TaskbookError::Auth(format!(
+                "{} refused the token in {token_var} for {repo} ({status})",
+                self.name()
+            )),
+            404 => TaskbookError::General(format!("{} has no repository {repo}", self.name())),
+            _ => TaskbookError::Network(format!("{} answered {status} for {repo}", self.name())),
+        }
+    }
+}
+
+/// What an import changes
+#[derive(Debug, Default)]
+struct Plan {
+    new: Vec<Task>,
+    /// UUIDs of tasks to check (`true`) or uncheck
+    states: HashMap<String, bool>,
+}
+
+/// Compare the open issues with the tasks linked to issues of the repository
+fn plan(
+    issues: Vec<Issue>,
+    board: &str,
+    prefix: &str,
+    tasks: &[&Task],
+    archived: &[&Task],
+) -> Plan {
+    let in_repo = |task: &Task| {
+        task.link
+            .as_deref()
+            .is_some_and(|link| starts_with_ignore_case(link, prefix))
+    };
+    let known: HashSet<&str> = tasks
+        .iter()
+        .chain(archived)
+        .filter(|task| in_repo(task))
+        .filter_map(|task| task.link.as_deref())
+        .collect();
+    let open: HashSet<&str> = issues.iter().map(|issue| issue.url.as_str()).collect();
+
+    let mut plan = Plan::default();
+    for task in tasks.iter().filter(|task| in_repo(task)) {
+        let is_open = task.link.as_deref().is_some_and(|link| open.contains(link));
+        if task.is_complete == is_open {
+            plan.states.insert(task.uuid.clone(), !is_open);
+        }
+    }
+    for issue in issues
+        .iter()
+        .filter(|issue| !known.contains(issue.url.as_str()))
+    {
+        let tags = issue
+            .labels
+            .iter()
+            .map(|label| {
+                normalize_tag(label)
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join("-")
+            })
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        let mut task = Task::new_with_tags(
+            0,
+            format!("{} (#{})", issue.title.trim(), issue.number),
+            vec![board.to_string()],
+            NORMAL,
+            tags,
+        );
+        task.link = Some(issue.url.clone());
+        plan.new.push(task);
+    }
+    plan
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+/// Import the open issues of `repo` (`owner/name`, or `group/project` with
+/// subgroups on GitLab) and bring the tasks of earlier imports up to date
+pub(super) fn import(forge: Forge, repo: &str, taskbook_dir: Option<&Path>) -> Result<()> {
+    let repo = repo.trim().trim_matches('/');
+    let name = match repo.rsplit_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() => name,
+        _ => {
+            return Err(TaskbookError::General(format!(
+                "expected a repository as owner/name, not {repo:?}"
+            )))
+        }
+    };
+    let board = normalize_board_name(name);
+    let issues = forge.fetch_open_issues(repo)?;
+    let open = issues.len();
+
+    let taskbook = Taskbook::new(taskbook_dir)?;
+    let items = taskbook.get_all_items()?;
+    let archive = taskbook.get_all_archive_items()?;
+    let tasks: Vec<&Task> = items.values().filter_map(StorageItem::as_task).collect();
+    let archived: Vec<&Task> = archive.values().filter_map(StorageItem::as_task).collect();
+    let plan = plan(
+        issues,
+        &board,
+        &forge.issue_url_prefix(repo),
+        &tasks,
+        &archived,
+    );
+
+    let closed = plan.states.values().filter(|&&complete| complete).count();
+    let reopened = plan.states.len() - closed;
+    let new: Vec<StorageItem> = plan.new.into_iter().map(StorageItem::Task).collect();
+    let (imported, _, _) = taskbook.import_items(new, Vec::new())?;
+    taskbook.set_complete_by_uuid(&plan.states)?;
+
+    println!(
+        "{}",
+        format!(
+            "Imported {imported} of {} from {repo} into @{board}.",
+            count(open, "open issue", "open issues")
+        )
+        .green()
+        .bold()
+    );
+    if closed > 0 {
+        println!(
+            "{}",
+            format!(
+                "Checked {} closed.",
+                count(closed, "task whose issue was", "tasks whose issues were")
+            )
+            .dimmed()
+        );
+    }
+    if reopened > 0 {
+        println!(
+            "{}",
+            format!(
+                "Unchecked {} reopened.",
+                count(reopened, "task whose issue was", "tasks whose issues were")
+            )
+            .dimmed()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREFIX: &str = "https://github.com/acme/rocket/issues/";
+
+    fn issue(number: u64, labels: &[&str]) -> Issue {
+        Issue {
+            number,
+            title: format!("Issue {number}"),
+            url: format!("{PREFIX}{number}"),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+        }
+    }
+
+    fn linked(number: u64, complete: bool) -> Task {
+        let mut task = Task::new(1, format!("Issue {number}"), vec!["rocket".into()], NORMAL);
+        task.link = Some(format!("{PREFIX}{number}"));
+        task.is_complete = complete;
+        task
+    }
+
+    #[test]
+    fn github_pages_leave_out_pull_requests() {
+        let json = r#"[
+            {"number": 7, "title": "Crash on start", "html_url": "https://github.com/acme/rocket/issues/7",
+             "labels": [{"name": "bug"}, {"name": "good first issue"}]},
+            {"number": 8, "title": "Fix crash", "html_url": "https://github.com/acme/rocket/pull/8",
+             "labels": [], "pull_request": {"url": "https://api.github.com/repos/acme/rocket/pulls/8"}}
+        ]"#;
+        let (issues, len) = Forge::GitHub.parse_page(json).unwrap();
+        assert_eq!(len, 2);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].labels, ["bug", "good first issue"]);
+
+        let plan = plan(issues, "rocket", PREFIX, &[], &[]);
+        assert_eq!(plan.new[0].description, "Crash on start (#7)");
+        assert_eq!(plan.new[0].tags, ["bug", "good-first-issue"]);
+        assert_eq!(plan.new[0].boards, ["rocket"]);
+    }
+
+    #[test]
+    fn importing_again_follows_closed_and_reopened_issues() {
+        let still_open = linked(1, false);
+        let closed = linked(2, false);
+        let reopened = linked(3, true);
+        let archived = linked(4, true);
+        let mut elsewhere = linked(5, false);
+        elsewhere.link = Some("https://github.com/acme/other/issues/5".into());
+
+        let issues = vec![issue(1, &[]), issue(3, &[]), issue(4, &[]), issue(6, &[])];
+        let plan = plan(
+            issues,
+            "rocket",
+            PREFIX,
+            &[&still_open, &closed, &reopened, &elsewhere],
+            &[&archived],
+        );
+
+        assert_eq!(plan.new.len(), 1);
+        assert_eq!(plan.new[0].link.as_deref(), Some(&*format!("{PREFIX}6")));
+        assert_eq!(plan.states.len(), 2);
+        assert_eq!(plan.states.get(&closed.uuid), Some(&true));
+        assert_eq!(plan.states.get(&reopened.uuid), Some(&false));
+    }
+}
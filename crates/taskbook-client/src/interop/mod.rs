@@ -0,0 +1,222 @@
+//! `tb import` and `tb export --format`: moving items between taskbook and
+//! other task managers, and importing issues from GitHub and GitLab.
+
+mod issues;
+mod taskwarrior;
+mod todotxt;
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{ErrorKind, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use colored::Colorize;
+use taskbook_common::StorageItem;
+
+use crate::error::{Result, TaskbookError};
+use crate::taskbook::Taskbook;
+
+/// A file format of another task manager
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// JSON from `task export`
+    Taskwarrior,
+    /// A todo.txt file, one task per line
+    TodoTxt,
+    /// Open issues of a GitHub repository (import only)
+    GitHub,
+    /// Open issues of a GitLab project (import only)
+    GitLab,
+}
+
+impl Format {
+    /// The format a file name suggests: `.json` for Taskwarrior, `.txt` for
+    /// todo.txt
+    pub fn from_file_name(file: &str) -> Option<Self> {
+        match Path::new(file).extension()?.to_str()? {
+            "json" => Some(Self::Taskwarrior),
+            "txt" => Some(Self::TodoTxt),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = TaskbookError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "taskwarrior" | "tw" => Ok(Self::Taskwarrior),
+            "todotxt" | "todo.txt" => Ok(Self::TodoTxt),
+            "github" => Ok(Self::GitHub),
+            "gitlab" => Ok(Self::GitLab),
+            other => Err(TaskbookError::General(format!(
+                "unknown format {other:?} (expected taskwarrior, todotxt, github or gitlab)"
+            ))),
+        }
+    }
+}
+
+/// Items read from another task manager
+#[derive(Default)]
+pub(crate) struct Converted {
+    pub items: Vec<StorageItem>,
+    /// Finished items, which go to the archive
+    pub archive: Vec<StorageItem>,
+    /// Entries with no taskbook counterpart, such as deleted tasks
+    pub skipped: usize,
+    /// Annotations on tasks, which have nowhere to go
+    pub dropped_annotations: usize,
+}
+
+/// Import `file` (`-` for stdin) into the taskbook; for GitHub and GitLab,
+/// `file` is the repository
+pub fn import(format: Format, file: &str, taskbook_dir: Option<&Path>) -> Result<()> {
+    let mut converted = match format {
+        Format::Taskwarrior => taskwarrior::parse(&read_input(file)?)?,
+        Format::TodoTxt => todotxt::parse(&read_input(file)?),
+        Format::GitHub => return issues::import(issues::Forge::GitHub, file, taskbook_dir),
+        Format::GitLab => return issues::import(issues::Forge::GitLab, file, taskbook_dir),
+    };
+
+    let taskbook = Taskbook::new(taskbook_dir)?;
+    // todo.txt lines carry no IDs, so a line already imported is recognized
+    // by its description and boards
+    let mut unchanged = 0;
+    if format == Format::TodoTxt {
+        let known: HashSet<(String, Vec<String>)> = taskbook
+            .get_all_items()?
+            .into_values()
+            .chain(taskbook.get_all_archive_items()?.into_values())
+            .map(|item| line_key(&item))
+            .collect();
+        let before = converted.items.len();
+        converted
+            .items
+            .retain(|item| !known.contains(&line_key(item)));
+        unchanged = before - converted.items.len();
+    }
+    let (imported, archived, existing) =
+        taskbook.import_items(converted.items, converted.archive)?;
+    let existing = existing + unchanged;
+
+    println!(
+        "{}",
+        format!(
+            "Imported {} and {} into the archive.",
+            count(imported, "item", "items"),
+            count(archived, "finished one", "finished ones")
+        )
+        .green()
+        .bold()
+    );
+    if existing > 0 {
+        println!(
+            "{}",
+            format!(
+                "Skipped {} imported before.",
+                count(existing, "item", "items")
+            )
+            .dimmed()
+        );
+    }
+    if converted.skipped > 0 {
+        println!(
+            "{}",
+            format!(
+                "Skipped {} with no taskbook counterpart, such as deleted tasks.",
+                count(converted.skipped, "entry", "entries")
+            )
+            .dimmed()
+        );
+    }
+    if converted.dropped_annotations > 0 {
+        println!(
+            "{}",
+            format!(
+                "Left out {} on tasks; only notes keep text beyond a description.",
+                count(converted.dropped_annotations, "annotation", "annotations")
+            )
+            .yellow()
+        );
+    }
+    Ok(())
+}
+
+fn read_input(file: &str) -> Result<String> {
+    if file == "-" {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        Ok(input)
+    } else {
+        fs::read_to_string(file)
+            .map_err(|e| TaskbookError::General(format!("cannot read {file}: {e}")))
+    }
+}
+
+/// `n` and the noun for that many, as in "1 item" or "2 items"
+fn count(n: usize, one: &str, many: &str) -> String {
+    format!("{n} {}", if n == 1 { one } else { many })
+}
+
+fn line_key(item: &StorageItem) -> (String, Vec<String>) {
+    (item.description().to_string(), item.boards().to_vec())
+}
+
+/// Export every item to `file` (stdout by default). Taskwarrior exports
+/// include archived items; todo.txt ones hold the tasks on the boards only,
+/// done.txt being where todo.txt keeps its archive.
+pub fn export(format: Format, file: Option<&str>, taskbook_dir: Option<&Path>) -> Result<()> {
+    let taskbook = Taskbook::new(taskbook_dir)?;
+    let mut items: Vec<StorageItem> = taskbook.get_all_items()?.into_values().collect();
+    let mut archive: Vec<StorageItem> = taskbook.get_all_archive_items()?.into_values().collect();
+    items.sort_by_key(|item| item.id());
+    archive.sort_by_key(|item| item.id());
+
+    let (output, summary) = match format {
+        Format::Taskwarrior => (
+            taskwarrior::write(&items, &archive)?,
+            format!(
+                "Exported {} and {}",
+                count(items.len(), "item", "items"),
+                count(archive.len(), "archived item", "archived items")
+            ),
+        ),
+        Format::TodoTxt => {
+            let (output, notes) = todotxt::write(&items);
+            let summary = format!("Exported {}", count(items.len() - notes, "task", "tasks"));
+            if notes > 0 {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Left out {}, which todo.txt has no place for.",
+                        count(notes, "note", "notes")
+                    )
+                    .yellow()
+                );
+            }
+            (output, summary)
+        }
+        Format::GitHub | Format::GitLab => {
+            return Err(TaskbookError::General(
+                "issues can be imported, not exported".to_string(),
+            ))
+        }
+    };
+    match file.filter(|f| *f != "-") {
+        Some(path) => {
+            fs::write(path, output + "\n")?;
+            eprintln!("{}", format!("{summary} to {path}.").green().bold());
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            // A reader that stops early, like `head`, is not an error
+            match writeln!(stdout, "{output}") {
+                Err(e) if e.kind() != ErrorKind::BrokenPipe => return Err(e.into()),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
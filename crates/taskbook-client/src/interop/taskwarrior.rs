@@ -0,0 +1,368 @@
+//! Taskwarrior's JSON, as written by `task export` and read by `task import`.
+//!
+//! Projects map to boards, tags and priorities to their taskbook
+//! counterparts, and the UUID is kept, so importing the same file twice
+//! adds nothing the second time. Taskwarrior has no notes or stars: a note
+//! becomes a task tagged `note` with its body as annotations, and a starred
+//! item is tagged `next`, both of which turn back into the same on import.
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use taskbook_common::board::{normalize_board_name, normalize_tag, DEFAULT_BOARD};
+use taskbook_common::models::STORAGE_DATE_FORMAT;
+use taskbook_common::priority::{HIGH, MEDIUM, NORMAL};
+use taskbook_common::{Note, StorageItem, Task};
+
+use super::Converted;
+use crate::error::{Result, TaskbookError};
+
+/// Taskwarrior's date format, always in UTC
+const DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Tag marking a task that was a taskbook note
+const NOTE_TAG: &str = "note";
+
+/// Tag for starred items; Taskwarrior ranks `next` tasks first
+const STAR_TAG: &str = "next";
+
+#[derive(Serialize, Deserialize)]
+struct TwTask {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uuid: Option<String>,
+    description: String,
+    #[serde(default = "pending")]
+    status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Annotation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry: Option<String>,
+    description: String,
+}
+
+fn pending() -> String {
+    "pending".to_string()
+}
+
+/// Read `task export` output: a JSON array, or one task per line as
+/// older versions write it
+pub(super) fn parse(input: &str) -> Result<Converted> {
+    let tasks: Vec<TwTask> = if input.trim_start().starts_with('[') {
+        serde_json::from_str(input)?
+    } else {
+        input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line.trim().trim_end_matches(',')))
+            .collect::<std::result::Result<_, _>>()?
+    };
+
+    let mut converted = Converted::default();
+    for tw in tasks {
+        match tw.status.as_str() {
+            "pending" | "waiting" => {
+                let item = from_taskwarrior(tw, &mut converted)?;
+                converted.items.push(item);
+            }
+            "completed" => {
+                let item = from_taskwarrior(tw, &mut converted)?;
+                converted.archive.push(item);
+            }
+            // Deleted tasks, and the templates recurring tasks are made from
+            _ => converted.skipped += 1,
+        }
+    }
+    Ok(converted)
+}
+
+fn from_taskwarrior(tw: TwTask, converted: &mut Converted) -> Result<StorageItem> {
+    if tw.description.trim().is_empty() {
+        return Err(TaskbookError::General(
+            "a Taskwarrior task has no description".to_string(),
+        ));
+    }
+    let boards = vec![tw
+        .project
+        .as_deref()
+        .map(normalize_board_name)
+        .unwrap_or_else(|| DEFAULT_BOARD.to_string())];
+    let mut tags: Vec<String> = tw.tags.iter().map(|tag| normalize_tag(tag)).collect();
+    let is_note = take_tag(&mut tags, NOTE_TAG);
+    let is_starred = take_tag(&mut tags, STAR_TAG);
+    let entry = tw.entry.as_deref().and_then(parse_date);
+
+    let mut item = if is_note {
+        let body = tw
+            .annotations
+            .iter()
+            .map(|a| a.description.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut note = Note::new_with_tags(0, tw.description, boards, tags);
+        note.set_body((!body.is_empty()).then_some(body));
+        note.is_starred = is_starred;
+        StorageItem::Note(note)
+    } else {
+        let priority = match tw.priority.as_deref() {
+            Some("H") => HIGH,
+            Some("M") => MEDIUM,
+            _ => NORMAL,
+        };
+        let mut task = Task::new_with_tags(0, tw.description, boards, priority, tags);
+        task.is_starred = is_starred;
+        task.is_complete = tw.status == "completed";
+        task.completed_at = task
+            .is_complete
+            .then(|| tw.end.as_deref().and_then(parse_date).or(entry))
+            .flatten()
+            .map(|end| end.timestamp_millis());
+        task.in_progress = !task.is_complete && tw.start.is_some();
+        task.due = tw
+            .due
+            .as_deref()
+            .and_then(parse_date)
+            .map(|due| due.with_timezone(&Local).date_naive());
+        converted.dropped_annotations += tw.annotations.len();
+        StorageItem::Task(task)
+    };
+
+    if let Some(uuid) = tw.uuid.filter(|u| uuid::Uuid::parse_str(u).is_ok()) {
+        set_uuid(&mut item, uuid);
+    }
+    if let Some(entry) = entry {
+        set_created(&mut item, entry);
+    }
+    Ok(item)
+}
+
+/// Write items as a JSON array for `task import`. Archived items are
+/// written as completed if they are checked tasks, and as deleted otherwise.
+pub(super) fn write(items: &[StorageItem], archive: &[StorageItem]) -> Result<String> {
+    let now = Utc::now();
+    let tasks: Vec<TwTask> = items
+        .iter()
+        .map(|item| to_taskwarrior(item, false, now))
+        .chain(archive.iter().map(|item| to_taskwarrior(item, true, now)))
+        .collect();
+    Ok(serde_json::to_string_pretty(&tasks)?)
+}
+
+fn to_taskwarrior(item: &StorageItem, archived: bool, now: DateTime<Utc>) -> TwTask {
+    let entry = Utc
+        .timestamp_millis_opt(item.timestamp())
+        .single()
+        .unwrap_or(now);
+    let project = item
+        .boards()
+        .first()
+        .filter(|board| board.as_str() != DEFAULT_BOARD)
+        .cloned();
+
+    let mut tw = TwTask {
+        uuid: Some(item.uuid().to_string()),
+        description: item.description().to_string(),
+        status: pending(),
+        entry: Some(format_date(entry)),
+        modified: Some(format_date(now)),
+        start: None,
+        end: None,
+        due: None,
+        project,
+        tags: Vec::new(),
+        priority: None,
+        annotations: Vec::new(),
+    };
+
+    match item {
+        StorageItem::Task(task) => {
+            tw.tags = task.tags.clone();
+            tw.priority = match task.priority {
+                HIGH => Some("H".to_string()),
+                MEDIUM => Some("M".to_string()),
+                _ => None,
+            };
+            if task.is_complete {
+                tw.status = "completed".to_string();
+                let end = task
+                    .completed_at
+                    .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+                    .unwrap_or(entry);
+                tw.end = Some(format_date(end));
+            } else if archived {
+                tw.status = "deleted".to_string();
+                tw.end = Some(format_date(now));
+            } else if task.in_progress {
+                tw.start = Some(format_date(now));
+            }
+            tw.due = task.due.map(|due| format_date(local_midnight(due)));
+        }
+        StorageItem::Note(note) => {
+            tw.tags = note.tags.clone();
+            tw.tags.push(NOTE_TAG.to_string());
+            if archived {
+                tw.status = "deleted".to_string();
+                tw.end = Some(format_date(now));
+            }
+            tw.annotations = note
+                .body()
+                .unwrap_or_default()
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| Annotation {
+                    entry: Some(format_date(entry)),
+                    description: line.to_string(),
+                })
+                .collect();
+        }
+    }
+    if item.is_starred() {
+        tw.tags.push(STAR_TAG.to_string());
+    }
+    tw
+}
+
+/// Remove `tag` from `tags`, returning whether it was there
+fn take_tag(tags: &mut Vec<String>, tag: &str) -> bool {
+    let before = tags.len();
+    tags.retain(|t| t != tag);
+    tags.len() != before
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, DATE_FORMAT)
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
+
+/// The start of `day` in local time; Taskwarrior due dates are moments
+fn local_midnight(day: NaiveDate) -> DateTime<Utc> {
+    let midnight = day.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
+fn set_uuid(item: &mut StorageItem, uuid: String) {
+    match item {
+        StorageItem::Task(task) => task.uuid = uuid,
+        StorageItem::Note(note) => note.uuid = uuid,
+    }
+}
+
+fn set_created(item: &mut StorageItem, created: DateTime<Utc>) {
+    let date = created
+        .with_timezone(&Local)
+        .format(STORAGE_DATE_FORMAT)
+        .to_string();
+    let timestamp = created.timestamp_millis();
+    match item {
+        StorageItem::Task(task) => {
+            task.date = date;
+            task.timestamp = timestamp;
+        }
+        StorageItem::Note(note) => {
+            note.date = date;
+            note.timestamp = timestamp;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"[
+        {"id":1,"description":"Fix the gate","entry":"20240301T090000Z","modified":"20240302T090000Z",
+         "project":"home","priority":"H","status":"pending","tags":["diy","next"],
+         "uuid":"6f1c52c2-2a4c-4c2b-9d8e-0a4f0b3c1d2e","due":"20240310T230000Z","start":"20240302T090000Z",
+         "annotations":[{"entry":"20240301T091500Z","description":"Hinges are rusty"}],"urgency":12.3},
+        {"id":0,"description":"File taxes","end":"20240315T120000Z","entry":"20240201T090000Z",
+         "status":"completed","uuid":"0b8e1a4e-5f43-4e6a-8a2c-39c0f1b2d3e4"},
+        {"id":0,"description":"Old idea","entry":"20240101T090000Z","status":"deleted",
+         "uuid":"a1d7e3f9-1c2b-4d5e-8f90-1a2b3c4d5e6f"},
+        {"id":2,"description":"Packing list","entry":"20240301T090000Z","status":"pending",
+         "tags":["note"],"annotations":[{"description":"Passport"},{"description":"Charger"}]}
+    ]"#;
+
+    #[test]
+    fn imports_projects_tags_priorities_and_annotations() {
+        let converted = parse(EXPORT).unwrap();
+        assert_eq!(converted.items.len(), 2);
+        assert_eq!(converted.archive.len(), 1);
+        assert_eq!(converted.skipped, 1);
+        assert_eq!(converted.dropped_annotations, 1);
+
+        let task = converted.items[0].as_task().unwrap();
+        assert_eq!(task.uuid, "6f1c52c2-2a4c-4c2b-9d8e-0a4f0b3c1d2e");
+        assert_eq!(task.boards, ["home"]);
+        assert_eq!(task.tags, ["diy"]);
+        assert_eq!(task.priority, HIGH);
+        assert!(task.is_starred && task.in_progress && !task.is_complete);
+        assert!(task.due.is_some());
+        assert_eq!(
+            task.timestamp,
+            parse_date("20240301T090000Z").unwrap().timestamp_millis()
+        );
+
+        let done = converted.archive[0].as_task().unwrap();
+        assert!(done.is_complete);
+        assert_eq!(done.boards, [DEFAULT_BOARD]);
+        assert_eq!(
+            done.completed_at,
+            Some(parse_date("20240315T120000Z").unwrap().timestamp_millis())
+        );
+
+        let StorageItem::Note(note) = &converted.items[1] else {
+            panic!("a task tagged note is imported as a note");
+        };
+        assert_eq!(note.body(), Some("Passport\nCharger"));
+        assert!(note.tags.is_empty());
+    }
+
+    #[test]
+    fn exported_items_import_as_they_were() {
+        let converted = parse(EXPORT).unwrap();
+        let json = write(&converted.items, &converted.archive).unwrap();
+        let again = parse(&json).unwrap();
+
+        assert_eq!(again.items.len(), 2);
+        assert_eq!(again.archive.len(), 1);
+        for (before, after) in converted.items.iter().zip(&again.items) {
+            assert_eq!(before.uuid(), after.uuid());
+            assert_eq!(before.boards(), after.boards());
+            assert_eq!(before.is_starred(), after.is_starred());
+            assert_eq!(before.timestamp(), after.timestamp());
+        }
+        let task = again.items[0].as_task().unwrap();
+        assert_eq!(
+            (task.priority, task.tags.as_slice()),
+            (HIGH, &["diy".to_string()][..])
+        );
+        assert_eq!(task.due, converted.items[0].as_task().unwrap().due);
+    }
+}
@@ -0,0 +1,222 @@
+//! The todo.txt format: one task per line, as in
+//! `x 2024-03-15 2024-03-01 (A) Call the bank +finance @phone due:2024-03-20`.
+//!
+//! Priority letters map to priorities (`A` high, `B` medium, any other
+//! normal), `+project`s to boards and `@context`s to tags. The creation and
+//! completion dates, and a `due:` date, are kept. Notes have no place in
+//! todo.txt and are left out of exports.
+
+use chrono::{Local, NaiveDate, TimeZone};
+use taskbook_common::board::{normalize_board_name, normalize_tag, DEFAULT_BOARD};
+use taskbook_common::models::STORAGE_DATE_FORMAT;
+use taskbook_common::priority::{HIGH, MEDIUM, NORMAL};
+use taskbook_common::{StorageItem, Task};
+
+use super::Converted;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+const DUE_KEY: &str = "due:";
+
+/// Read todo.txt lines; blank lines are skipped
+pub(super) fn parse(input: &str) -> Converted {
+    let mut converted = Converted::default();
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
+        match parse_line(line) {
+            Some(task) => converted.items.push(StorageItem::Task(task)),
+            None => converted.skipped += 1,
+        }
+    }
+    converted
+}
+
+fn parse_line(line: &str) -> Option<Task> {
+    let mut words = line.split_whitespace().peekable();
+
+    let done = words.next_if_eq(&"x").is_some();
+    let completed = done.then(|| words.next_if(|w| is_date(w))).flatten();
+    let priority =
+        words
+            .next_if(|w| priority_letter(w).is_some())
+            .map(|w| match priority_letter(w) {
+                Some('A') => HIGH,
+                Some('B') => MEDIUM,
+                _ => NORMAL,
+            });
+    let created = words.next_if(|w| is_date(w)).and_then(parse_date);
+
+    let mut description = Vec::new();
+    let mut boards = Vec::new();
+    let mut tags = Vec::new();
+    let mut due = None;
+    for word in words {
+        if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            boards.push(normalize_board_name(project));
+        } else if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+            tags.push(normalize_tag(context));
+        } else if let Some(date) = word.strip_prefix(DUE_KEY).and_then(parse_date) {
+            due = Some(date);
+        } else {
+            description.push(word);
+        }
+    }
+    if description.is_empty() {
+        return None;
+    }
+    if boards.is_empty() {
+        boards.push(DEFAULT_BOARD.to_string());
+    }
+
+    let mut task = Task::new_with_tags(
+        0,
+        description.join(" "),
+        boards,
+        priority.unwrap_or(NORMAL),
+        tags,
+    );
+    task.due = due;
+    task.is_complete = done;
+    if let Some(created) = created {
+        task.date = created.format(STORAGE_DATE_FORMAT).to_string();
+        task.timestamp = local_midnight(created);
+    }
+    if done {
+        task.completed_at = Some(
+            completed
+                .and_then(parse_date)
+                .map(local_midnight)
+                .unwrap_or(task.timestamp),
+        );
+    }
+    Some(task)
+}
+
+/// Write the tasks among `items` as todo.txt lines, returning them and how
+/// many notes were left out
+pub(super) fn write(items: &[StorageItem]) -> (String, usize) {
+    let mut lines = Vec::new();
+    let mut notes = 0;
+    for item in items {
+        match item.as_task() {
+            Some(task) => lines.push(format_task(task)),
+            None => notes += 1,
+        }
+    }
+    (lines.join("\n"), notes)
+}
+
+fn format_task(task: &Task) -> String {
+    let mut words = Vec::new();
+    if task.is_complete {
+        words.push("x".to_string());
+        if let Some(completed) = task.completed_at.and_then(local_date) {
+            words.push(completed.format(DATE_FORMAT).to_string());
+        }
+    } else {
+        match task.priority {
+            HIGH => words.push("(A)".to_string()),
+            MEDIUM => words.push("(B)".to_string()),
+            _ => {}
+        }
+    }
+    if let Some(created) = local_date(task.timestamp) {
+        words.push(created.format(DATE_FORMAT).to_string());
+    }
+    words.push(task.description.clone());
+    words.extend(
+        task.boards
+            .iter()
+            .filter(|board| board.as_str() != DEFAULT_BOARD)
+            .map(|board| format!("+{}", board.replace(' ', "_"))),
+    );
+    words.extend(
+        task.tags
+            .iter()
+            .map(|tag| format!("@{}", tag.replace(' ', "_"))),
+    );
+    if let Some(due) = task.due {
+        words.push(format!("{DUE_KEY}{}", due.format(DATE_FORMAT)));
+    }
+    words.join(" ")
+}
+
+/// `(A)` through `(Z)`
+fn priority_letter(word: &str) -> Option<char> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_uppercase() => Some(c),
+        _ => None,
+    }
+}
+
+fn is_date(word: &str) -> bool {
+    parse_date(word).is_some()
+}
+
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, DATE_FORMAT).ok()
+}
+
+fn local_date(ms: i64) -> Option<NaiveDate> {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map(|t| t.date_naive())
+}
+
+/// The start of `day` in local time, in ms since the epoch
+fn local_midnight(day: NaiveDate) -> i64 {
+    let midnight = day.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.timestamp_millis())
+        .unwrap_or_else(|| midnight.and_utc().timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_priorities_projects_contexts_and_dates() {
+        let converted = parse(
+            "(A) 2024-03-01 Call the bank +finance @phone due:2024-03-20\n\
+             \n\
+             x 2024-03-15 2024-03-02 Renew passport +travel +admin\n\
+             (C) Water the plants\n",
+        );
+        assert_eq!(converted.items.len(), 3);
+
+        let call = converted.items[0].as_task().unwrap();
+        assert_eq!(call.description, "Call the bank");
+        assert_eq!(call.priority, HIGH);
+        assert_eq!(call.boards, ["finance"]);
+        assert_eq!(call.tags, ["phone"]);
+        assert_eq!(call.due, parse_date("2024-03-20"));
+        assert_eq!(local_date(call.timestamp), parse_date("2024-03-01"));
+
+        let renew = converted.items[1].as_task().unwrap();
+        assert!(renew.is_complete);
+        assert_eq!(renew.boards, ["travel", "admin"]);
+        assert_eq!(
+            renew.completed_at.and_then(local_date),
+            parse_date("2024-03-15")
+        );
+
+        let plants = converted.items[2].as_task().unwrap();
+        assert_eq!(plants.priority, NORMAL);
+        assert_eq!(plants.boards, [DEFAULT_BOARD]);
+    }
+
+    #[test]
+    fn written_lines_read_back_the_same() {
+        let line = "x 2024-03-15 2024-03-02 Renew passport +travel @admin due:2024-04-01";
+        let task = parse_line(line).unwrap();
+        assert_eq!(format_task(&task), line);
+
+        let line = "(B) 2024-03-01 Call the bank +finance";
+        assert_eq!(format_task(&parse_line(line).unwrap()), line);
+    }
+}
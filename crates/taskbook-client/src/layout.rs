@@ -0,0 +1,123 @@
+//! Fitting item lines to the terminal: widths that skip color codes, and
+//! word wrapping and truncation of descriptions.
+
+use std::io::{self, IsTerminal};
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns of the terminal, or `None` when output is not going to one
+pub fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::size()
+        .ok()
+        .map(|(columns, _)| columns as usize)
+        .filter(|columns| *columns > 0)
+}
+
+/// Columns `text` takes on screen, not counting ANSI escape sequences
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // `ESC [ parameters final-byte`
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            width += c.width().unwrap_or(0);
+        }
+    }
+    width
+}
+
+/// `text` in lines of at most `width` columns, broken between words; a
+/// word wider than a line is broken where the line ends
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+    for word in text.split_whitespace() {
+        let word_width = word.width();
+        if line_width > 0 && line_width + 1 + word_width <= width {
+            line.push(' ');
+            line.push_str(word);
+            line_width += 1 + word_width;
+            continue;
+        }
+        if line_width > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        let mut rest = word;
+        while rest.width() > width {
+            let (head, tail) = split_at_width(rest, width);
+            lines.push(head.to_string());
+            rest = tail;
+        }
+        line.push_str(rest);
+        line_width = rest.width();
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// `text` cut to at most `width` columns, ending with an ellipsis if
+/// anything was cut
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    if width <= 1 {
+        return "…".to_string();
+    }
+    let (head, _) = split_at_width(text, width - 1);
+    format!("{}…", head.trim_end())
+}
+
+/// `text` split after its first `width` columns; at least one character
+/// goes first, however wide
+fn split_at_width(text: &str, width: usize) -> (&str, &str) {
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width > width {
+            let at = if i == 0 { c.len_utf8() } else { i };
+            return text.split_at(at);
+        }
+        used += char_width;
+    }
+    (text, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_breaks_between_words_and_inside_long_ones() {
+        assert_eq!(
+            wrap("buy milk and eggs for the week", 12),
+            vec!["buy milk and", "eggs for the", "week"]
+        );
+        assert_eq!(
+            wrap("see https://example.com/a/long/path", 10),
+            vec!["see", "https://ex", "ample.com/", "a/long/pat", "h"]
+        );
+        assert_eq!(wrap("", 10), vec![""]);
+
+        assert_eq!(truncate("buy milk and eggs", 10), "buy milk…");
+        assert_eq!(truncate("buy milk", 10), "buy milk");
+        assert_eq!(truncate("日本語のテキスト", 7), "日本語…");
+
+        assert_eq!(display_width("\x1b[38;2;1;2;3mred\x1b[39m 日本"), 8);
+    }
+}
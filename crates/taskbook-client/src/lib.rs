@@ -0,0 +1,47 @@
+//! Library side of the `tb` client: the [`Taskbook`] facade over a storage
+//! backend, its configuration, and the change events it emits.
+
+mod agenda;
+mod api_client;
+mod attachments;
+pub mod auth;
+mod burndown;
+pub mod caldav;
+pub mod commands;
+pub mod config;
+pub mod copy;
+mod credentials;
+mod directory;
+mod editor;
+pub mod error;
+pub mod events;
+pub mod export;
+pub mod git;
+mod highlight;
+mod history;
+mod hooks;
+pub mod interop;
+mod layout;
+pub mod note_export;
+mod opener;
+pub mod pager;
+mod palette;
+pub mod passphrase;
+mod paths;
+mod relative_time;
+mod render;
+mod repair;
+mod report;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod sharing;
+pub mod storage;
+pub mod taskbook;
+pub mod timing;
+pub mod trash;
+pub mod tui;
+mod wiki;
+
+pub use error::{Result, TaskbookError};
+pub use events::TaskbookEvent;
+pub use taskbook::{Taskbook, TaskbookBuilder};
@@ -1,55 +1,166 @@
 use std::path::PathBuf;
 use std::process;
 
-use clap::Parser;
-
-mod api_client;
-mod auth;
-mod commands;
-mod config;
-mod credentials;
-mod directory;
-mod editor;
-mod error;
-mod render;
-mod storage;
-mod taskbook;
-mod tui;
+use clap::{ArgGroup, Parser, Subcommand};
+
+use taskbook_client::config::{self, Config};
+use taskbook_client::{
+    auth, caldav, commands, export, git, interop, note_export, pager, passphrase, sharing, timing,
+    trash, tui,
+};
 
 const HELP_TEXT: &str = r#"
   Usage
     $ tb [<options> ...]
+    $ tb <command> [<args> ...]
 
     Options
         none             Display board view
       --archive, -a      Display archived items
+      --attach           Attach files or URLs to an item, or list its attachments
       --begin, -b        Start/pause task
+      --body-from-stdin  Read the note body from stdin (with --note)
+      --board            List, archive or unarchive boards (list|archive|unarchive)
+      --burndown         Display open/done task trend for a board
+      --caldav-push      Mirror tasks to the CalDAV server in the config file
+      --cat              Print raw note body or task description
       --check, -c        Check/uncheck task
-      --clear            Delete all checked items
+      --clear            Delete all checked items (optionally of some boards)
+      --comment          Comment on a task, or list its comments
+      --dry-run          List what --clear would delete without deleting it
       --copy, -y         Copy item description
+      --as               Copy as text, markdown, json or url (with --copy)
+      --debug-timing     Print startup/storage timings to stderr
+      --decrypt-storage  Store local data unencrypted again
       --delete, -d       Delete item
+      --detach           Remove attachments from an item (by number, path or URL)
+      --due              Set or clear a task's due date
       --edit, -e         Edit item description
+      --editor           Edit the description in $EDITOR (with --edit)
       --edit-note        Edit note in external editor
+      --export-note      Write a note as an HTML page, or a PDF (--format pdf; optional file after the id)
+      --encrypt-storage  Encrypt local data with a passphrase (TB_PASSPHRASE skips the prompt)
       --find, -f         Search for items
+      --format           Export items for another task manager instead (taskwarrior, todotxt, with --export),
+                         or a note as html or pdf (with --export-note)
+      --from             Format to import (taskwarrior, todotxt), or github/gitlab for a repo's issues
+      --group            Group the timeline by day, week or month
       --help, -h         Display help message
+      --history          Show what happened to an item, and versions kept by the sync server
+      --journal          Edit today's journal note (or a day's: yesterday, 2024-05-01)
+      --hook             Install or remove the git hook linking commits to tasks (install|uninstall)
+      --import           Import items from another task manager's file (.json or .txt, or with --from)
       --list, -l         List items by attributes
-      --move, -m         Move item between boards
+      --log-for          List the git commits that mentioned a task (tb:#<id>)
+      --save-filter      Save the --list terms under a name
+      --use-filter       Apply a saved filter to --list
+      --move, -m         Move items between boards
+      --no-color         Print without colors (also NO_COLOR=1)
+      --no-pager         Print long listings without a pager
       --note, -n         Create note (opens editor if no description)
+      --open             Open an item's attachment or link (the first, or by number)
       --priority, -p     Update priority of task
+      --profile          Use a profile from the config file
+      --repair           Rebuild storage from whatever still parses (backs up first)
+      --renumber         Renumber ids to 1..n (with --repair)
+      --report           Summarize the last week or month (week|month)
+      --output           Report output format (text|json, with --report)
       --restore, -r      Restore items from archive
       --star, -s         Star/unstar item
       --tag              Add/remove tags on item
       --taskbook-dir     Define a custom taskbook directory
       --task, -t         Create task
       --timeline, -i     Display timeline view
+      --today            Display overdue, due, in-progress and starred items
       --version, -v      Display installed version
 
     Server commands
       --register         Register a new server account
+      --invite           Invite code, for servers that need one (with --register)
       --login            Log in to an existing account
+      --sso              Log in through the server's single sign-on provider (with --login)
+      --link-sso         Link a single sign-on account, to log in with --login --sso
       --logout           Log out and delete credentials
       --status           Show sync status
       --migrate          Push local data to server
+      --change-password  Change the account password (logs out other devices)
+      --set-passphrase   Derive the encryption key from a passphrase from now on
+      --change-passphrase
+                         Change the passphrase the encryption key is derived from
+      --rotate-key       Move to a new encryption key and encrypt everything again with it
+      --delete-account   Delete the account and all data stored on the server
+      --devices          List devices logged in to the account
+      --revoke-device    Log out another device by id (from --devices)
+      --audit            Show logins, password changes and other account activity
+      --export           Download and decrypt all server data into a JSON file
+      --trash            List deleted items the server still keeps
+      --restore-deleted  Restore deleted items by id (from --trash), or `all`
+      --tokens           List API tokens for scripts
+      --create-token     Create an API token (--read-only, --expires)
+      --revoke-token     Revoke an API token by id (from --tokens)
+      --webhooks         List URLs the server calls when items change
+      --add-webhook      Add a webhook URL; prints the secret payloads are signed with
+      --remove-webhook   Remove a webhook by id (from --webhooks)
+      --test-webhook     Send a test payload to a webhook by id (from --webhooks)
+      --enable-totp      Turn on two-factor authentication with an authenticator app
+      --disable-totp     Turn off two-factor authentication
+      --code             Two-factor code (with --login and --disable-totp)
+      --verify-email     Send a link to confirm the account's email address
+      --forgot-password  Email a password reset link (with --server and --email)
+      --share            Share a board with another account (list shared boards without args)
+      --unshare          Stop sharing a board, with one account or everyone
+      --read-only        Share without write access (with --share and --create-token)
+      --share-link       Create a public read-only link to a board (list links without args)
+      --expires          How long a link or token lasts, e.g. 12h, 7d, 2w (with --share-link, default 7d, and --create-token)
+      --revoke           Revoke a share link by token or URL (with --share-link)
+      --serve            Run a single-user sync server (builds with the `serve` feature)
+      --listen           Address for --serve to listen on (default 127.0.0.1:8080)
+      --database         Database file for --serve (default server.db in the data directory)
+
+    Command examples
+      $ tb add @coding Review PR #42 p:2
+      $ tb done 1 2
+      $ tb mv 3 cooking
+      $ tb edit 3 Merge PR #42
+      $ tb ls pending coding
+      $ tb tag 3 +urgent -later
+      $ tb due 3 tomorrow
+      $ tb comment 3 waiting on review
+      $ tb attach 3 ./spec.pdf
+      $ tb open 3 2
+      $ tb history 3
+      $ tb journal yesterday
+      $ tb hook install
+      $ tb log 12
+      $ tb timeline --group month
+      $ tb board archive travel
+      $ tb storage encrypt
+      $ task export | tb import - --from taskwarrior
+      $ tb export --format taskwarrior | task import
+      $ tb export-note 5 --format html
+      $ tb caldav push
+      $ tb import ~/todo/todo.txt
+      $ tb import acme/rocket --from github
+      $ tb share coding alice
+      $ tb share-link coding --expires 7d
+      $ tb sync status
+      $ tb sync change-password
+      $ tb sync set-passphrase
+      $ tb sync rotate-key
+      $ tb sync delete-account
+      $ tb sync devices --revoke 3f2c9a1e
+      $ tb sync audit
+      $ tb sync tokens --create backup --read-only
+      $ tb sync webhooks --add https://hooks.slack.com/services/T000/B000/XXXX
+      $ tb sync export backup.json
+      $ tb sync trash --restore 9c41d2e0
+      $ tb sync register --server http://localhost:8080 --invite K7QM-2XPA-HV9C-TR4E
+      $ tb sync login --server http://localhost:8080 --username user
+      $ tb sync login --sso --server https://tasks.example.com
+      $ tb sync link-sso
+      $ tb sync totp enable
+      $ tb sync forgot-password --email a@b.com
+      $ tb serve --listen 0.0.0.0:8080
 
     Examples
       $ tb
@@ -57,28 +168,83 @@ const HELP_TEXT: &str = r#"
       $ tb --begin 2 3
       $ tb --check 1 2
       $ tb --clear
+      $ tb --clear @coding --dry-run
       $ tb --copy 1 2 3
+      $ tb --copy 3 --as markdown
       $ tb --delete 4
       $ tb --edit @3 Merge PR #42
+      $ tb --edit @3 --editor
+      $ tb --history @3
       $ tb --find documentation
       $ tb --list pending coding
       $ tb --move @1 cooking
+      $ tb --move @1 @4 @7 coding
       $ tb --note @coding Mergesort worse-case O(nlogn)
+      $ cat notes.md | tb --note --body-from-stdin @coding Meeting notes
+      $ tb --cat @3 | less
       $ tb --priority @3 2
+      $ tb --priority @3 high
       $ tb --restore 4
       $ tb --star 2
       $ tb --task @coding @reviews Review PR #42
       $ tb --task @coding +urgent Improve documentation
       $ tb --task Make some buttercream
+      $ tb --task @work Send report due:fri
+      $ tb --due @3 tomorrow
+      $ tb --comment @3 "waiting on review"
+      $ tb --attach @3 ./spec.pdf https://example.com/design
+      $ tb --today
+      $ tb --journal
+      $ tb --report week
+      $ tb --burndown coding
+      $ tb --board archive travel
+      $ tb --board list
+      $ tb --report month --output json
       $ tb --tag @3 +urgent +frontend
       $ tb --tag @3 -urgent
       $ tb --list +urgent
+      $ tb --list --save-filter urgent pending +urgent
+      $ tb --list --use-filter urgent coding
       $ tb --timeline
+      $ tb --timeline --group week
+      $ tb --no-color --list pending
+      $ tb --profile work --today
+      $ tb --repair --renumber
+      $ tb --encrypt-storage
+      $ tb --import tasks.json --from taskwarrior
+      $ tb --export tasks.json --format taskwarrior
+      $ tb --export todo.txt --format todotxt
+      $ tb --export-note 5 --format pdf design.pdf
       $ tb --register --server http://localhost:8080 --username user --email a@b.com --password secret123
       $ tb --login --server http://localhost:8080 --username user --password secret123 --key <base64>
+      $ tb --login --sso --server https://tasks.example.com
+      $ tb --link-sso
       $ tb --logout
       $ tb --status
       $ tb --migrate
+      $ tb --change-password
+      $ tb --set-passphrase
+      $ tb --delete-account
+      $ tb --devices
+      $ tb --revoke-device 3f2c9a1e
+      $ tb --audit
+      $ tb --export
+      $ tb --trash
+      $ tb --restore-deleted all
+      $ tb --tokens
+      $ tb --create-token ci --expires 12w
+      $ tb --revoke-token 5b1e0c7d
+      $ tb --webhooks
+      $ tb --test-webhook 0d8f3a21
+      $ tb --enable-totp
+      $ tb --disable-totp --code 123456
+      $ tb --verify-email
+      $ tb --forgot-password --server http://localhost:8080 --email a@b.com
+      $ tb --share coding alice --read-only
+      $ tb --unshare coding alice
+      $ tb --share
+      $ tb --share-link coding --expires 2w
+      $ tb --share-link --revoke <token>
 "#;
 
 #[derive(Parser)]
@@ -86,9 +252,16 @@ const HELP_TEXT: &str = r#"
     name = "tb",
     version = env!("CARGO_PKG_VERSION"),
     about = "Tasks, boards & notes for the command-line habitat",
-    after_help = HELP_TEXT
+    after_help = HELP_TEXT,
+    args_conflicts_with_subcommands = true,
+    group(ArgGroup::new("read_only_target").args(["share", "create_token"]).multiple(true)),
+    group(ArgGroup::new("expiry_target").args(["share_link", "create_token"]).multiple(true)),
+    group(ArgGroup::new("export_target").args(["export", "export_note"]))
 )]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Input arguments (task description, IDs, search terms, etc.)
     #[arg(trailing_var_arg = true)]
     input: Vec<String>,
@@ -105,14 +278,27 @@ struct Cli {
     #[arg(short = 'c', long)]
     check: bool,
 
-    /// Delete all checked items
+    /// Delete all checked items, or those on the given boards
     #[arg(long)]
     clear: bool,
 
+    /// List what --clear would delete without deleting it
+    #[arg(long, requires = "clear")]
+    dry_run: bool,
+
     /// Copy item description to clipboard
     #[arg(short = 'y', long)]
     copy: bool,
 
+    /// What to copy with --copy: text, markdown, json or url
+    #[arg(
+        long = "as",
+        value_name = "FORMAT",
+        requires = "copy",
+        value_parser = ["text", "markdown", "md", "json", "url"]
+    )]
+    copy_as: Option<String>,
+
     /// Delete item
     #[arg(short = 'd', long)]
     delete: bool,
@@ -121,10 +307,18 @@ struct Cli {
     #[arg(short = 'e', long)]
     edit: bool,
 
+    /// Open the description in the external editor (with --edit)
+    #[arg(long, requires = "edit")]
+    editor: bool,
+
     /// Edit note in external editor
     #[arg(long)]
     edit_note: bool,
 
+    /// Write a note as a standalone HTML page, or a PDF with --format pdf
+    #[arg(long, value_name = "ID")]
+    export_note: Option<String>,
+
     /// Search for items
     #[arg(short = 'f', long)]
     find: bool,
@@ -133,7 +327,15 @@ struct Cli {
     #[arg(short = 'l', long)]
     list: bool,
 
-    /// Move item between boards
+    /// Save the --list terms as a named filter
+    #[arg(long, value_name = "NAME", requires = "list")]
+    save_filter: Option<String>,
+
+    /// Apply a saved filter to --list
+    #[arg(long, value_name = "NAME", requires = "list")]
+    use_filter: Option<String>,
+
+    /// Move items between boards
     #[arg(short = 'm', long)]
     r#move: bool,
 
@@ -141,6 +343,31 @@ struct Cli {
     #[arg(short = 'n', long)]
     note: bool,
 
+    /// Read the note body from stdin (with --note)
+    #[arg(long, requires = "note")]
+    body_from_stdin: bool,
+
+    /// Print the raw note body or task description, without styling
+    #[arg(long)]
+    cat: bool,
+
+    /// Show what happened to an item, and earlier versions kept by the sync server
+    #[arg(long)]
+    history: bool,
+
+    /// List the git commits that mentioned a task
+    #[arg(long, value_name = "ID")]
+    log_for: Option<String>,
+
+    /// Install or remove the git hook that links commits to tasks
+    #[arg(long, value_name = "ACTION", value_parser = ["install", "uninstall"])]
+    hook: Option<String>,
+
+    /// Link a commit of the current repository to the tasks it mentions
+    /// (run by the git hook)
+    #[arg(long, value_name = "HASH", hide = true)]
+    record_commit: Option<String>,
+
     /// Update priority of task
     #[arg(short = 'p', long)]
     priority: bool,
@@ -161,18 +388,98 @@ struct Cli {
     #[arg(short = 't', long)]
     task: bool,
 
+    /// Set or clear a task's due date
+    #[arg(long)]
+    due: bool,
+
+    /// Comment on a task, or list its comments
+    #[arg(long)]
+    comment: bool,
+
+    /// Edit today's (or a day's) note on the Journal board
+    #[arg(long)]
+    journal: bool,
+
+    /// Attach files or URLs to an item, or list its attachments
+    #[arg(long)]
+    attach: bool,
+
+    /// Remove attachments from an item
+    #[arg(long)]
+    detach: bool,
+
+    /// Open an item's attachment or link with the default application
+    #[arg(long)]
+    open: bool,
+
+    /// Display overdue, due today, in-progress and starred items
+    #[arg(long)]
+    today: bool,
+
     /// Display timeline view
     #[arg(short = 'i', long)]
     timeline: bool,
 
+    /// Group the timeline by day, week or month
+    #[arg(long, value_name = "PERIOD", value_parser = ["day", "week", "month"], requires = "timeline")]
+    group: Option<String>,
+
+    /// Display the open/done task trend for a board
+    #[arg(long)]
+    burndown: bool,
+
+    /// List boards, or archive or unarchive the named board
+    #[arg(long, value_name = "ACTION", value_parser = ["list", "archive", "unarchive"])]
+    board: Option<String>,
+
+    /// Summarize activity over the last week or month
+    #[arg(long, value_name = "PERIOD", value_parser = ["week", "month"])]
+    report: Option<String>,
+
+    /// Report output format
+    #[arg(long, value_name = "FORMAT", value_parser = ["text", "json"], requires = "report")]
+    output: Option<String>,
+
+    /// Import items from another task manager's file, `-` for stdin, or a
+    /// repository's issues with --from github/gitlab
+    #[arg(long, value_name = "FILE")]
+    import: Option<String>,
+
+    /// Format of the file to import, if its extension doesn't tell, or
+    /// github/gitlab to import a repository's open issues
+    #[arg(long, value_name = "FORMAT", value_parser = ["taskwarrior", "todotxt", "github", "gitlab"], requires = "import")]
+    from: Option<String>,
+
+    /// Export items for another task manager instead of the server's data,
+    /// or a note as html or pdf
+    #[arg(long, value_name = "FORMAT", value_parser = ["taskwarrior", "todotxt", "html", "pdf"], requires = "export_target")]
+    format: Option<String>,
+
     /// Define a custom taskbook directory
-    #[arg(long = "taskbook-dir", value_name = "PATH")]
+    #[arg(long = "taskbook-dir", value_name = "PATH", global = true)]
     taskbook_dir: Option<PathBuf>,
 
+    /// Use the storage directory, theme and sync settings of a profile
+    #[arg(long, value_name = "NAME", global = true)]
+    profile: Option<String>,
+
     /// Run in CLI mode (non-interactive)
-    #[arg(long)]
+    #[arg(long, global = true)]
     cli: bool,
 
+    /// Print startup and storage timings to stderr
+    #[arg(long, global = true)]
+    debug_timing: bool,
+
+    /// Print without colors. Colors are also off when NO_COLOR is set or
+    /// output is not a terminal.
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Print long listings directly instead of through the pager
+    #[arg(long, global = true)]
+    no_pager: bool,
+
     // --- Server commands ---
     /// Register a new server account
     #[arg(long)]
@@ -182,6 +489,14 @@ struct Cli {
     #[arg(long)]
     login: bool,
 
+    /// Log in through the server's single sign-on provider (with --login)
+    #[arg(long, requires = "login")]
+    sso: bool,
+
+    /// Link a single sign-on account, to log in with --login --sso
+    #[arg(long)]
+    link_sso: bool,
+
     /// Log out and delete credentials
     #[arg(long)]
     logout: bool,
@@ -194,6 +509,153 @@ struct Cli {
     #[arg(long)]
     migrate: bool,
 
+    /// Change the account password; other devices are logged out
+    #[arg(long)]
+    change_password: bool,
+
+    /// Derive the encryption key from a passphrase from now on
+    #[arg(long)]
+    set_passphrase: bool,
+
+    /// Change the passphrase the encryption key is derived from
+    #[arg(long)]
+    change_passphrase: bool,
+
+    /// Move to a new encryption key and encrypt everything again with it
+    #[arg(long)]
+    rotate_key: bool,
+
+    /// Delete the account and everything stored with it on the server
+    #[arg(long)]
+    delete_account: bool,
+
+    /// List devices logged in to the account
+    #[arg(long)]
+    devices: bool,
+
+    /// Log out another device, by the id shown by --devices
+    #[arg(long, value_name = "ID")]
+    revoke_device: Option<String>,
+
+    /// Show logins, password changes and other account activity
+    #[arg(long)]
+    audit: bool,
+
+    /// Download and decrypt everything stored on the server into a JSON file
+    #[arg(long, value_name = "FILE")]
+    export: Option<Option<String>>,
+
+    /// List items the server kept after they were deleted
+    #[arg(long)]
+    trash: bool,
+
+    /// Restore deleted items, by the ids shown by --trash, or `all`
+    #[arg(long, value_name = "ID", num_args = 1..)]
+    restore_deleted: Vec<String>,
+
+    /// List the account's API tokens
+    #[arg(long)]
+    tokens: bool,
+
+    /// Create an API token for scripts
+    #[arg(long, value_name = "NAME")]
+    create_token: Option<String>,
+
+    /// Revoke an API token, by the id shown by --tokens
+    #[arg(long, value_name = "ID")]
+    revoke_token: Option<String>,
+
+    /// List the account's webhooks
+    #[arg(long)]
+    webhooks: bool,
+
+    /// Add a URL the server sends a signed payload to when items change
+    #[arg(long, value_name = "URL")]
+    add_webhook: Option<String>,
+
+    /// Remove a webhook, by the id shown by --webhooks
+    #[arg(long, value_name = "ID")]
+    remove_webhook: Option<String>,
+
+    /// Send a test payload to a webhook, by the id shown by --webhooks
+    #[arg(long, value_name = "ID")]
+    test_webhook: Option<String>,
+
+    /// Turn on two-factor authentication with an authenticator app
+    #[arg(long)]
+    enable_totp: bool,
+
+    /// Turn off two-factor authentication
+    #[arg(long)]
+    disable_totp: bool,
+
+    /// Send a link to confirm the account's email address
+    #[arg(long)]
+    verify_email: bool,
+
+    /// Email a password reset link
+    #[arg(long)]
+    forgot_password: bool,
+
+    /// Share a board with another account, or list shared boards
+    #[arg(long)]
+    share: bool,
+
+    /// Stop sharing a board with one account, or with everyone
+    #[arg(long)]
+    unshare: bool,
+
+    /// Share without write access, or create a read-only API token
+    #[arg(long, requires = "read_only_target")]
+    read_only: bool,
+
+    /// Create a public read-only link to a board, or list links
+    #[arg(long)]
+    share_link: bool,
+
+    /// How long a share link or API token lasts, e.g. 12h, 7d or 2w
+    #[arg(long, value_name = "DURATION", requires = "expiry_target")]
+    expires: Option<String>,
+
+    /// Revoke a share link by token or URL
+    #[arg(long, value_name = "LINK", requires = "share_link")]
+    revoke: Option<String>,
+
+    /// Rebuild storage from whatever still parses, backing it up first
+    #[arg(long)]
+    repair: bool,
+
+    /// Renumber ids to close gaps (with --repair)
+    #[arg(long, requires = "repair")]
+    renumber: bool,
+
+    /// Encrypt local data with a passphrase
+    #[arg(long)]
+    encrypt_storage: bool,
+
+    /// Store local data unencrypted again
+    #[arg(long)]
+    decrypt_storage: bool,
+
+    /// Mirror tasks to the configured CalDAV server as VTODOs
+    #[arg(long)]
+    caldav_push: bool,
+
+    /// Run a single-user sync server for your own devices
+    #[cfg(feature = "serve")]
+    #[arg(long)]
+    serve: bool,
+
+    /// Address for --serve to listen on (default 127.0.0.1:8080)
+    #[cfg(feature = "serve")]
+    #[arg(long, value_name = "ADDR", requires = "serve")]
+    listen: Option<String>,
+
+    /// Database file for --serve (default server.db in the data directory)
+    #[cfg(feature = "serve")]
+    #[arg(long, value_name = "FILE", requires = "serve")]
+    database: Option<PathBuf>,
+
     /// Server URL for register/login
     #[arg(long)]
     server: Option<String>,
@@ -202,21 +664,748 @@ struct Cli {
     #[arg(long)]
     username: Option<String>,
 
-    /// Email for register
+    /// Email for register and --forgot-password
     #[arg(long)]
     email: Option<String>,
 
-    /// Password for register/login
+    /// Password for register/login (current password for --change-password,
+    /// --delete-account and two-factor changes)
     #[arg(long)]
     password: Option<String>,
 
     /// Encryption key (base64) for login
     #[arg(long)]
     key: Option<String>,
+
+    /// New password for --change-password
+    #[arg(long, requires = "change_password")]
+    new_password: Option<String>,
+
+    /// Invite code for --register, on servers that need one
+    #[arg(long, requires = "register")]
+    invite: Option<String>,
+
+    /// Two-factor code for --login and --disable-totp
+    #[arg(long)]
+    code: Option<String>,
+}
+
+/// Git-style subcommands, each an alias for one of the flags above
+#[derive(Subcommand)]
+enum Command {
+    /// Create a task
+    #[command(visible_alias = "task")]
+    Add {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        input: Vec<String>,
+    },
+
+    /// Create a note (opens editor if no description)
+    Note {
+        /// Read the note body from stdin
+        #[arg(long)]
+        body_from_stdin: bool,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        input: Vec<String>,
+    },
+
+    /// Check/uncheck tasks
+    #[command(visible_alias = "check")]
+    Done { ids: Vec<String> },
+
+    /// Start/pause tasks
+    #[command(visible_alias = "start")]
+    Begin { ids: Vec<String> },
+
+    /// Star/unstar items
+    Star { ids: Vec<String> },
+
+    /// Delete items
+    #[command(visible_alias = "delete")]
+    Rm { ids: Vec<String> },
+
+    /// Restore items from archive
+    Restore { ids: Vec<String> },
+
+    /// Copy item descriptions to the clipboard
+    Copy {
+        ids: Vec<String>,
+        /// Copy as text, markdown, json or url
+        #[arg(
+            long = "as",
+            value_name = "FORMAT",
+            value_parser = ["text", "markdown", "md", "json", "url"]
+        )]
+        format: Option<String>,
+    },
+
+    /// Print raw note body or task description
+    Cat { id: String },
+
+    /// Show what happened to an item, and earlier versions kept by the sync server
+    History { id: String },
+
+    /// List the git commits that mentioned a task
+    Log { id: String },
+
+    /// Install or remove the git hook that links commits to tasks
+    Hook {
+        #[arg(value_parser = ["install", "uninstall"])]
+        action: String,
+    },
+
+    /// Edit item description
+    Edit {
+        /// Open the description in the external editor
+        #[arg(long)]
+        editor: bool,
+        id: String,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        description: Vec<String>,
+    },
+
+    /// Move items between boards
+    #[command(name = "mv", visible_alias = "move")]
+    Move { id: String, boards: Vec<String> },
+
+    /// Update priority of task
+    #[command(visible_alias = "pri")]
+    Priority { id: String, level: String },
+
+    /// Add (+tag) or remove (-tag) tags on an item
+    Tag {
+        id: String,
+        #[arg(allow_hyphen_values = true)]
+        tags: Vec<String>,
+    },
+
+    /// Set or clear (none) a task's due date
+    Due { id: String, when: Vec<String> },
+
+    /// Comment on a task, or list its comments
+    Comment {
+        id: String,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        text: Vec<String>,
+    },
+
+    /// Edit today's note on the Journal board, creating it if missing
+    Journal {
+        /// Another day: yesterday, -2d or 2024-05-01
+        #[arg(allow_hyphen_values = true)]
+        date: Option<String>,
+    },
+
+    /// Attach files or URLs to an item, or list its attachments
+    Attach { id: String, targets: Vec<String> },
+
+    /// Remove attachments from an item, by number, path or URL
+    Detach { id: String, targets: Vec<String> },
+
+    /// Open an item's attachment or link (the first, or by number)
+    Open { id: String, number: Option<String> },
+
+    /// List items by attributes
+    #[command(visible_alias = "list")]
+    Ls {
+        /// Save the terms as a named filter
+        #[arg(long, value_name = "NAME")]
+        save_filter: Option<String>,
+        /// Apply a saved filter
+        #[arg(long, value_name = "NAME")]
+        use_filter: Option<String>,
+        terms: Vec<String>,
+    },
+
+    /// Search for items
+    #[command(visible_alias = "search")]
+    Find { terms: Vec<String> },
+
+    /// Display archived items
+    Archive,
+
+    /// Display timeline view
+    Timeline {
+        /// Group by day, week or month
+        #[arg(long, value_name = "PERIOD", value_parser = ["day", "week", "month"])]
+        group: Option<String>,
+    },
+
+    /// Display overdue, due today, in-progress and starred items
+    Today,
+
+    /// Delete all checked items, or those on the given boards
+    Clear {
+        boards: Vec<String>,
+        /// List what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Display the open/done task trend for a board
+    Burndown { board: Vec<String> },
+
+    /// List boards, or archive or unarchive one
+    Board {
+        #[arg(value_parser = ["list", "archive", "unarchive"])]
+        action: String,
+        name: Vec<String>,
+    },
+
+    /// Share a board with another account, or list shared boards
+    Share {
+        board: Option<String>,
+        user: Option<String>,
+        /// Share without write access
+        #[arg(long)]
+        read_only: bool,
+    },
+
+    /// Stop sharing a board with one account, or with everyone
+    Unshare { board: String, user: Option<String> },
+
+    /// Create a public read-only link to a board, or list links
+    ShareLink {
+        board: Option<String>,
+        /// How long the link lasts, e.g. 12h, 7d or 2w
+        #[arg(long, value_name = "DURATION")]
+        expires: Option<String>,
+        /// Revoke a link by token or URL
+        #[arg(long, value_name = "LINK", conflicts_with = "board")]
+        revoke: Option<String>,
+    },
+
+    /// Manage the server account
+    Sync {
+        #[command(subcommand)]
+        action: SyncCommand,
+    },
+
+    /// Rebuild storage from whatever still parses, backing it up first
+    Repair {
+        /// Renumber ids to close gaps
+        #[arg(long)]
+        renumber: bool,
+    },
+
+    /// Encrypt local data with a passphrase, or store it unencrypted again
+    Storage {
+        #[arg(value_parser = ["encrypt", "decrypt"])]
+        action: String,
+    },
+
+    /// Mirror tasks to the configured CalDAV server as VTODOs
+    Caldav {
+        #[arg(value_parser = ["push"])]
+        action: String,
+    },
+
+    /// Run a single-user sync server for your own devices
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Address to listen on (default 127.0.0.1:8080)
+        #[arg(long, value_name = "ADDR")]
+        listen: Option<String>,
+        /// Database file (default server.db in the data directory)
+        #[arg(long, value_name = "FILE")]
+        database: Option<PathBuf>,
+    },
+
+    /// Write a note as a standalone HTML page, or a PDF
+    ExportNote {
+        id: String,
+        /// File to write (default the note's title, `-` for stdout)
+        file: Option<String>,
+        /// Format to write
+        #[arg(long, value_name = "FORMAT", value_parser = ["html", "pdf"])]
+        format: Option<String>,
+    },
+
+    /// Import items from another task manager, or issues from GitHub/GitLab
+    Import {
+        /// File to read, `-` for stdin, or owner/repo with --from github|gitlab
+        file: String,
+        /// Format of the file, if its extension doesn't tell
+        #[arg(long, value_name = "FORMAT", value_parser = ["taskwarrior", "todotxt", "github", "gitlab"])]
+        from: Option<String>,
+    },
+
+    /// Export items for another task manager
+    Export {
+        /// File to write (default stdout)
+        file: Option<String>,
+        /// Format to write
+        #[arg(long, value_name = "FORMAT", value_parser = ["taskwarrior", "todotxt"])]
+        format: String,
+    },
+
+    /// Summarize activity over the last week or month
+    Report {
+        #[arg(value_parser = ["week", "month"])]
+        period: String,
+        /// Output format
+        #[arg(long, value_name = "FORMAT", value_parser = ["text", "json"])]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SyncCommand {
+    /// Register a new account
+    Register {
+        /// Server URL
+        #[arg(long)]
+        server: Option<String>,
+        /// Username
+        #[arg(long)]
+        username: Option<String>,
+        /// Email address
+        #[arg(long)]
+        email: Option<String>,
+        /// Invite code, if the server needs one
+        #[arg(long)]
+        invite: Option<String>,
+    },
+    /// Log in to an existing account
+    Login {
+        /// Server URL
+        #[arg(long)]
+        server: Option<String>,
+        /// Username
+        #[arg(long)]
+        username: Option<String>,
+        /// Two-factor code, if the account has two-factor authentication
+        #[arg(long)]
+        code: Option<String>,
+        /// Log in through the server's single sign-on provider; the first
+        /// sign-in creates the account
+        #[arg(long, conflicts_with_all = ["username", "code"])]
+        sso: bool,
+    },
+    /// Link a single sign-on account to this one, to log in with `login --sso`
+    LinkSso,
+    /// Show sync mode, credentials and storage used on the server
+    Status,
+    /// Change the account password; other devices are logged out
+    ChangePassword,
+    /// Derive the encryption key from a passphrase from now on; everything
+    /// on the server is encrypted again with the new key
+    SetPassphrase,
+    /// Change the passphrase the encryption key is derived from
+    ChangePassphrase,
+    /// Move to a new encryption key, derived again from the passphrase with
+    /// the configured cost, and encrypt everything again with it
+    RotateKey,
+    /// Delete the account and everything stored with it on the server
+    DeleteAccount,
+    /// List devices logged in to the account, or log one out
+    Devices {
+        /// Log out the device with this id
+        #[arg(long, value_name = "ID")]
+        revoke: Option<String>,
+    },
+    /// Show logins, failed logins, password changes and other account activity
+    Audit,
+    /// Download and decrypt everything stored on the server into a JSON file
+    Export {
+        /// File to write (default taskbook-export-<date>.json, `-` for stdout)
+        file: Option<String>,
+    },
+    /// List deleted items the server still keeps, or restore them
+    Trash {
+        /// Restore the items with these ids, or `all`
+        #[arg(long, value_name = "ID", num_args = 1..)]
+        restore: Vec<String>,
+    },
+    /// List API tokens for scripts, or create or revoke one
+    Tokens {
+        /// Create a token with this name
+        #[arg(long, value_name = "NAME", conflicts_with = "revoke")]
+        create: Option<String>,
+        /// Only allow reading (with --create)
+        #[arg(long, requires = "create")]
+        read_only: bool,
+        /// How long the token lasts, e.g. 30d or 12w (with --create; default never expires)
+        #[arg(long, value_name = "DURATION", requires = "create")]
+        expires: Option<String>,
+        /// Revoke the token with this id
+        #[arg(long, value_name = "ID")]
+        revoke: Option<String>,
+    },
+    /// List URLs the server calls when items change, or add, remove or test one
+    Webhooks {
+        /// Add a webhook for this URL
+        #[arg(long, value_name = "URL", conflicts_with_all = ["remove", "test"])]
+        add: Option<String>,
+        /// Remove the webhook with this id
+        #[arg(long, value_name = "ID", conflicts_with = "test")]
+        remove: Option<String>,
+        /// Send a test payload to the webhook with this id
+        #[arg(long, value_name = "ID")]
+        test: Option<String>,
+    },
+    /// Send a link to confirm the account's email address
+    VerifyEmail,
+    /// Email a password reset link
+    ForgotPassword {
+        /// Server URL (defaults to the one logged in to)
+        #[arg(long)]
+        server: Option<String>,
+        /// Email address of the account
+        #[arg(long)]
+        email: Option<String>,
+    },
+    /// Turn two-factor authentication on or off
+    Totp {
+        #[arg(value_parser = ["enable", "disable"])]
+        action: String,
+        /// Two-factor code, for disable
+        #[arg(long)]
+        code: Option<String>,
+    },
+}
+
+impl Command {
+    /// Translate the subcommand into the equivalent legacy flags and input
+    fn apply(self, cli: &mut Cli) {
+        match self {
+            Command::Add { input } => {
+                cli.task = true;
+                cli.input = input;
+            }
+            Command::Note {
+                body_from_stdin,
+                input,
+            } => {
+                cli.note = true;
+                cli.body_from_stdin = body_from_stdin;
+                cli.input = input;
+            }
+            Command::Done { ids } => {
+                cli.check = true;
+                cli.input = bare_ids(ids);
+            }
+            Command::Begin { ids } => {
+                cli.begin = true;
+                cli.input = bare_ids(ids);
+            }
+            Command::Star { ids } => {
+                cli.star = true;
+                cli.input = bare_ids(ids);
+            }
+            Command::Rm { ids } => {
+                cli.delete = true;
+                cli.input = bare_ids(ids);
+            }
+            Command::Restore { ids } => {
+                cli.restore = true;
+                cli.input = bare_ids(ids);
+            }
+            Command::Copy { ids, format } => {
+                cli.copy = true;
+                cli.copy_as = format;
+                cli.input = bare_ids(ids);
+            }
+            Command::Cat { id } => {
+                cli.cat = true;
+                cli.input = targeted(&id, Vec::new());
+            }
+            Command::History { id } => {
+                cli.history = true;
+                cli.input = targeted(&id, Vec::new());
+            }
+            Command::Log { id } => cli.log_for = Some(id),
+            Command::Hook { action } => cli.hook = Some(action),
+            Command::Edit {
+                editor,
+                id,
+                description,
+            } => {
+                cli.edit = true;
+                cli.editor = editor;
+                cli.input = targeted(&id, description);
+            }
+            Command::Move { id, boards } => {
+                cli.r#move = true;
+                // `tb mv 1 4 7 coding`: leading numbers before the last word are more ids
+                let extra = boards[..boards.len().saturating_sub(1)]
+                    .iter()
+                    .take_while(|word| word.trim_start_matches('@').parse::<u64>().is_ok())
+                    .count();
+                let mut input = targeted(&id, boards);
+                for word in &mut input[1..=extra] {
+                    *word = format!("@{}", word.trim_start_matches('@'));
+                }
+                cli.input = input;
+            }
+            Command::Priority { id, level } => {
+                cli.priority = true;
+                cli.input = targeted(&id, vec![level]);
+            }
+            Command::Tag { id, tags } => {
+                cli.tag = true;
+                cli.input = targeted(&id, tags);
+            }
+            Command::Due { id, when } => {
+                cli.due = true;
+                cli.input = targeted(&id, when);
+            }
+            Command::Comment { id, text } => {
+                cli.comment = true;
+                cli.input = targeted(&id, text);
+            }
+            Command::Journal { date } => {
+                cli.journal = true;
+                cli.input = date.into_iter().collect();
+            }
+            Command::Attach { id, targets } => {
+                cli.attach = true;
+                cli.input = targeted(&id, targets);
+            }
+            Command::Detach { id, targets } => {
+                cli.detach = true;
+                cli.input = targeted(&id, targets);
+            }
+            Command::Open { id, number } => {
+                cli.open = true;
+                cli.input = targeted(&id, number.into_iter().collect());
+            }
+            Command::Ls {
+                save_filter,
+                use_filter,
+                terms,
+            } => {
+                cli.list = true;
+                cli.save_filter = save_filter;
+                cli.use_filter = use_filter;
+                cli.input = terms;
+            }
+            Command::Find { terms } => {
+                cli.find = true;
+                cli.input = terms;
+            }
+            Command::Archive => cli.archive = true,
+            Command::Timeline { group } => {
+                cli.timeline = true;
+                cli.group = group;
+            }
+            Command::Today => cli.today = true,
+            Command::Clear { boards, dry_run } => {
+                cli.clear = true;
+                cli.dry_run = dry_run;
+                cli.input = boards;
+            }
+            Command::Burndown { board } => {
+                cli.burndown = true;
+                cli.input = board;
+            }
+            Command::Board { action, name } => {
+                cli.board = Some(action);
+                cli.input = name;
+            }
+            Command::Share {
+                board,
+                user,
+                read_only,
+            } => {
+                cli.share = true;
+                cli.read_only = read_only;
+                cli.input = board.into_iter().chain(user).collect();
+            }
+            Command::Unshare { board, user } => {
+                cli.unshare = true;
+                cli.input = std::iter::once(board).chain(user).collect();
+            }
+            Command::ShareLink {
+                board,
+                expires,
+                revoke,
+            } => {
+                cli.share_link = true;
+                cli.expires = expires;
+                cli.revoke = revoke;
+                cli.input = board.into_iter().collect();
+            }
+            Command::Sync { action } => match action {
+                SyncCommand::Register {
+                    server,
+                    username,
+                    email,
+                    invite,
+                } => {
+                    cli.register = true;
+                    cli.server = server.or(cli.server.take());
+                    cli.username = username.or(cli.username.take());
+                    cli.email = email.or(cli.email.take());
+                    cli.invite = invite.or(cli.invite.take());
+                }
+                SyncCommand::Login {
+                    server,
+                    username,
+                    code,
+                    sso,
+                } => {
+                    cli.login = true;
+                    cli.sso = sso;
+                    cli.server = server.or(cli.server.take());
+                    cli.username = username.or(cli.username.take());
+                    cli.code = code.or(cli.code.take());
+                }
+                SyncCommand::LinkSso => cli.link_sso = true,
+                SyncCommand::Status => cli.status = true,
+                SyncCommand::ChangePassword => cli.change_password = true,
+                SyncCommand::SetPassphrase => cli.set_passphrase = true,
+                SyncCommand::ChangePassphrase => cli.change_passphrase = true,
+                SyncCommand::RotateKey => cli.rotate_key = true,
+                SyncCommand::DeleteAccount => cli.delete_account = true,
+                SyncCommand::Devices { revoke } => {
+                    cli.devices = revoke.is_none();
+                    cli.revoke_device = revoke;
+                }
+                SyncCommand::Audit => cli.audit = true,
+                SyncCommand::Export { file } => cli.export = Some(file),
+                SyncCommand::Trash { restore } => {
+                    cli.trash = restore.is_empty();
+                    cli.restore_deleted = restore;
+                }
+                SyncCommand::Tokens {
+                    create,
+                    read_only,
+                    expires,
+                    revoke,
+                } => {
+                    cli.tokens = create.is_none() && revoke.is_none();
+                    cli.create_token = create;
+                    cli.read_only = read_only;
+                    cli.expires = expires;
+                    cli.revoke_token = revoke;
+                }
+                SyncCommand::Webhooks { add, remove, test } => {
+                    cli.webhooks = add.is_none() && remove.is_none() && test.is_none();
+                    cli.add_webhook = add;
+                    cli.remove_webhook = remove;
+                    cli.test_webhook = test;
+                }
+                SyncCommand::VerifyEmail => cli.verify_email = true,
+                SyncCommand::ForgotPassword { server, email } => {
+                    cli.forgot_password = true;
+                    cli.server = server.or(cli.server.take());
+                    cli.email = email.or(cli.email.take());
+                }
+                SyncCommand::Totp { action, code } => {
+                    cli.enable_totp = action == "enable";
+                    cli.disable_totp = action == "disable";
+                    cli.code = code.or(cli.code.take());
+                }
+            },
+            Command::Repair { renumber } => {
+                cli.repair = true;
+                cli.renumber = renumber;
+            }
+            Command::Storage { action } => {
+                cli.encrypt_storage = action == "encrypt";
+                cli.decrypt_storage = action == "decrypt";
+            }
+            Command::Caldav { .. } => cli.caldav_push = true,
+            #[cfg(feature = "serve")]
+            Command::Serve { listen, database } => {
+                cli.serve = true;
+                cli.listen = listen;
+                cli.database = database;
+            }
+            Command::Import { file, from } => {
+                cli.import = Some(file);
+                cli.from = from;
+            }
+            Command::ExportNote { id, file, format } => {
+                cli.export_note = Some(id);
+                cli.input = file.into_iter().collect();
+                cli.format = format;
+            }
+            Command::Export { file, format } => {
+                cli.export = Some(file);
+                cli.format = Some(format);
+            }
+            Command::Report { period, output } => {
+                cli.report = Some(period);
+                cli.output = output;
+            }
+        }
+    }
+}
+
+/// Accept `3` and `@3` alike for commands that take a list of ids
+fn bare_ids(ids: Vec<String>) -> Vec<String> {
+    ids.into_iter()
+        .map(|id| id.trim_start_matches('@').to_string())
+        .collect()
+}
+
+/// Build `@<id> <rest...>` input for commands that act on a single item
+fn targeted(id: &str, rest: Vec<String>) -> Vec<String> {
+    let mut input = vec![format!("@{}", id.trim_start_matches('@'))];
+    input.extend(rest);
+    input
+}
+
+/// `--share [<board> <user>]`, `--unshare <board> [<user>]` and
+/// `--share-link [<board>]`
+fn run_sharing(cli: &Cli) -> taskbook_client::Result<()> {
+    let usage = |text: &str| taskbook_client::TaskbookError::General(text.to_string());
+    if cli.share_link {
+        return match (cli.revoke.as_deref(), cli.input.as_slice()) {
+            (Some(link), []) => sharing::revoke_link(link),
+            (None, []) => sharing::list_links(),
+            (None, board) => sharing::create_link(&board.join(" "), cli.expires.as_deref()),
+            (Some(_), _) => Err(usage("usage: tb --share-link --revoke <token>")),
+        };
+    }
+    match (cli.share, cli.input.as_slice()) {
+        (true, []) => sharing::list(),
+        (true, [board, user]) => sharing::share(board, user, cli.read_only),
+        (true, _) => Err(usage("usage: tb --share <board> <user> [--read-only]")),
+        (false, [board]) => sharing::unshare(board, None),
+        (false, [board, user]) => sharing::unshare(board, Some(user)),
+        (false, _) => Err(usage("usage: tb --unshare <board> [<user>]")),
+    }
+}
+
+/// Turn a subcommand into the equivalent flags, and input given to the
+/// flags into the form the commands take
+fn normalize(mut cli: Cli) -> Cli {
+    if let Some(command) = cli.command.take() {
+        command.apply(&mut cli);
+    }
+    // `tb --clear @coding --dry-run`: flags after the boards land in the input
+    if cli.clear {
+        let before = cli.input.len();
+        cli.input.retain(|word| word != "--dry-run");
+        cli.dry_run |= cli.input.len() != before;
+    }
+    // `tb --history 3`, like `tb history 3`
+    if cli.history {
+        if let Some((id, rest)) = cli.input.split_first() {
+            cli.input = targeted(id, rest.to_vec());
+        }
+    }
+    cli
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let cli = normalize(Cli::parse());
+
+    if cli.no_color {
+        colored::control::set_override(false);
+    }
+    if cli.no_pager {
+        pager::disable();
+    }
+
+    let profile = cli.profile.clone().or_else(config::profile_from_env);
+    if let Err(e) = Config::use_profile(profile.as_deref()) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
 
     // Handle server commands first (interactive prompts for missing values)
     if cli.register {
@@ -225,6 +1414,7 @@ fn main() {
             cli.username.as_deref(),
             cli.email.as_deref(),
             cli.password.as_deref(),
+            cli.invite.as_deref(),
         ) {
             eprintln!("Error: {}", e);
             process::exit(1);
@@ -232,12 +1422,21 @@ fn main() {
         return;
     }
 
+    if cli.login && cli.sso {
+        if let Err(e) = auth::login_sso(cli.server.as_deref(), cli.key.as_deref()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     if cli.login {
         if let Err(e) = auth::login(
             cli.server.as_deref(),
             cli.username.as_deref(),
             cli.password.as_deref(),
             cli.key.as_deref(),
+            cli.code.as_deref(),
         ) {
             eprintln!("Error: {}", e);
             process::exit(1);
@@ -245,6 +1444,14 @@ fn main() {
         return;
     }
 
+    if cli.link_sso {
+        if let Err(e) = auth::link_sso() {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     if cli.logout {
         if let Err(e) = auth::logout() {
             eprintln!("Error: {}", e);
@@ -253,6 +1460,207 @@ fn main() {
         return;
     }
 
+    if cli.change_password {
+        if let Err(e) = auth::change_password(cli.password.as_deref(), cli.new_password.as_deref())
+        {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.set_passphrase {
+        if let Err(e) = passphrase::set_passphrase(&Config::load_or_default()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.change_passphrase {
+        if let Err(e) = passphrase::change_passphrase(&Config::load_or_default()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.rotate_key {
+        if let Err(e) = passphrase::rotate_key(&Config::load_or_default()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.delete_account {
+        if let Err(e) = auth::delete_account(cli.password.as_deref()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.devices || cli.revoke_device.is_some() {
+        let result = match cli.revoke_device.as_deref() {
+            Some(id) => auth::revoke_device(id),
+            None => auth::devices(),
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.audit {
+        if let Err(e) = auth::audit() {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(file) = &cli.import {
+        let format = match &cli.from {
+            Some(format) => format.parse(),
+            None => interop::Format::from_file_name(file).ok_or_else(|| {
+                taskbook_client::TaskbookError::General(format!(
+                    "cannot tell the format of {file}; pass --from taskwarrior or --from todotxt"
+                ))
+            }),
+        };
+        let result =
+            format.and_then(|format| interop::import(format, file, cli.taskbook_dir.as_deref()));
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(id) = &cli.export_note {
+        let format = match cli.format.as_deref() {
+            Some(format) => format.parse(),
+            None => Ok(note_export::NoteFormat::default()),
+        };
+        let result = format.and_then(|format| {
+            note_export::export_note(
+                id,
+                format,
+                cli.input.first().map(String::as_str),
+                cli.taskbook_dir.as_deref(),
+            )
+        });
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let (Some(file), Some(format)) = (&cli.export, &cli.format) {
+        let result = format.parse().and_then(|format| {
+            interop::export(format, file.as_deref(), cli.taskbook_dir.as_deref())
+        });
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(file) = &cli.export {
+        if let Err(e) = export::export(file.as_deref()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.trash || !cli.restore_deleted.is_empty() {
+        let result = if cli.restore_deleted.is_empty() {
+            trash::list()
+        } else {
+            trash::restore(&cli.restore_deleted)
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.tokens || cli.create_token.is_some() || cli.revoke_token.is_some() {
+        let result = match (cli.create_token.as_deref(), cli.revoke_token.as_deref()) {
+            (Some(name), None) => auth::create_token(name, cli.read_only, cli.expires.as_deref()),
+            (None, Some(id)) => auth::revoke_token(id),
+            (None, None) => auth::tokens(),
+            (Some(_), Some(_)) => Err(taskbook_client::TaskbookError::General(
+                "use --create-token or --revoke-token, not both".to_string(),
+            )),
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.webhooks
+        || cli.add_webhook.is_some()
+        || cli.remove_webhook.is_some()
+        || cli.test_webhook.is_some()
+    {
+        let result = match (
+            cli.add_webhook.as_deref(),
+            cli.remove_webhook.as_deref(),
+            cli.test_webhook.as_deref(),
+        ) {
+            (Some(url), None, None) => auth::add_webhook(url),
+            (None, Some(id), None) => auth::remove_webhook(id),
+            (None, None, Some(id)) => auth::test_webhook(id),
+            (None, None, None) => auth::webhooks(),
+            _ => Err(taskbook_client::TaskbookError::General(
+                "use one of --add-webhook, --remove-webhook and --test-webhook".to_string(),
+            )),
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.enable_totp || cli.disable_totp {
+        let result = if cli.enable_totp {
+            auth::enable_totp(cli.password.as_deref())
+        } else {
+            auth::disable_totp(cli.password.as_deref(), cli.code.as_deref())
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.verify_email {
+        if let Err(e) = auth::verify_email() {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.forgot_password {
+        if let Err(e) = auth::forgot_password(cli.server.as_deref(), cli.email.as_deref()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     if cli.status {
         if let Err(e) = auth::status() {
             eprintln!("Error: {}", e);
@@ -261,6 +1669,75 @@ fn main() {
         return;
     }
 
+    if cli.share || cli.unshare || cli.share_link {
+        if let Err(e) = run_sharing(&cli) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.repair {
+        if let Err(e) = commands::repair(cli.taskbook_dir, cli.renumber) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.encrypt_storage {
+        if let Err(e) = commands::encrypt_storage(cli.taskbook_dir) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.decrypt_storage {
+        if let Err(e) = commands::decrypt_storage(cli.taskbook_dir) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(action) = &cli.hook {
+        let result = match action.as_str() {
+            "install" => git::install_hook(),
+            _ => git::uninstall_hook(),
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(hash) = &cli.record_commit {
+        if let Err(e) = git::record_commit(hash, cli.taskbook_dir.as_deref()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if cli.caldav_push {
+        if let Err(e) = caldav::push(cli.taskbook_dir.as_deref()) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    #[cfg(feature = "serve")]
+    if cli.serve {
+        if let Err(e) = taskbook_client::serve::serve(cli.listen.as_deref(), cli.database) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     if cli.migrate {
         if let Err(e) = commands::migrate(cli.taskbook_dir) {
             eprintln!("Error: {}", e);
@@ -273,6 +1750,9 @@ fn main() {
     let has_action_flags = cli.archive
         || cli.task
         || cli.note
+        || cli.cat
+        || cli.history
+        || cli.log_for.is_some()
         || cli.check
         || cli.begin
         || cli.star
@@ -287,11 +1767,26 @@ fn main() {
         || cli.list
         || cli.clear
         || cli.timeline
-        || cli.tag;
+        || cli.tag
+        || cli.due
+        || cli.comment
+        || cli.journal
+        || cli.attach
+        || cli.detach
+        || cli.open
+        || cli.today
+        || cli.burndown
+        || cli.board.is_some()
+        || cli.report.is_some();
 
     // Run TUI if: no action flags, no CLI flag, and no input
     let run_tui = !cli.cli && !has_action_flags && cli.input.is_empty();
 
+    // Timing output would corrupt the TUI's alternate screen
+    if cli.debug_timing && !run_tui {
+        timing::enable();
+    }
+
     if run_tui {
         // Run interactive TUI
         if let Err(e) = tui::run(cli.taskbook_dir.as_deref()) {
@@ -306,26 +1801,66 @@ fn main() {
             cli.task,
             cli.restore,
             cli.note,
+            cli.body_from_stdin,
+            cli.cat,
+            cli.history,
+            cli.log_for,
             cli.delete,
             cli.check,
             cli.begin,
             cli.star,
             cli.priority,
             cli.copy,
+            cli.copy_as,
             cli.timeline,
+            cli.group,
             cli.find,
             cli.list,
+            cli.save_filter,
+            cli.use_filter,
             cli.edit,
+            cli.editor,
             cli.edit_note,
             cli.r#move,
             cli.clear,
+            cli.dry_run,
             cli.tag,
+            cli.due,
+            cli.comment,
+            cli.journal,
+            cli.attach,
+            cli.detach,
+            cli.open,
+            cli.today,
+            cli.burndown,
+            cli.board,
+            cli.report,
+            cli.output.as_deref() == Some("json"),
             cli.taskbook_dir,
         );
 
+        timing::mark("command finished");
+
         if let Err(e) = result {
             eprintln!("{}", e);
             process::exit(1);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(args: &[&str]) -> Vec<String> {
+        normalize(Cli::parse_from(args)).input
+    }
+
+    #[test]
+    fn history_takes_the_id_with_or_without_at() {
+        assert_eq!(input(&["tb", "--history", "3"]), vec!["@3"]);
+        assert_eq!(input(&["tb", "--history", "@3"]), vec!["@3"]);
+        assert_eq!(input(&["tb", "history", "3"]), vec!["@3"]);
+        assert_eq!(input(&["tb", "--check", "3"]), vec!["3"]);
+    }
+}
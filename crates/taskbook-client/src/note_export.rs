@@ -0,0 +1,223 @@
+//! `tb --export-note`: a note's Markdown body as a standalone HTML page, or
+//! a PDF printed from it by an external tool, to share outside the terminal.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+use colored::Colorize;
+use pulldown_cmark::{html, Options, Parser};
+use taskbook_common::Note;
+
+use crate::error::{Result, TaskbookError};
+use crate::taskbook::Taskbook;
+
+/// Tools that print an HTML file to PDF, tried in order: the command and
+/// the arguments before the input and output paths
+const PDF_TOOLS: &[(&str, &[&str])] = &[
+    ("weasyprint", &[]),
+    ("wkhtmltopdf", &["--quiet", "--enable-local-file-access"]),
+];
+
+/// What a note is exported as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NoteFormat {
+    #[default]
+    Html,
+    /// HTML printed by `weasyprint` or `wkhtmltopdf`
+    Pdf,
+}
+
+impl NoteFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+impl FromStr for NoteFormat {
+    type Err = TaskbookError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "html" => Ok(Self::Html),
+            "pdf" => Ok(Self::Pdf),
+            _ => Err(TaskbookError::General(format!(
+                "a note is exported as html or pdf, not {s}"
+            ))),
+        }
+    }
+}
+
+/// Write note `id` to `file` (`-` for stdout, HTML only), by default a file
+/// named after the note's title in the current directory
+pub fn export_note(
+    id: &str,
+    format: NoteFormat,
+    file: Option<&str>,
+    taskbook_dir: Option<&Path>,
+) -> Result<()> {
+    let taskbook = Taskbook::new(taskbook_dir)?;
+    let id: u64 = id
+        .trim_start_matches('@')
+        .parse()
+        .map_err(|_| TaskbookError::InvalidId(0))?;
+    let items = taskbook.get_all_items()?;
+    let note = match items.get(&id.to_string()) {
+        Some(item) => item
+            .as_note()
+            .ok_or_else(|| TaskbookError::General(format!("item {id} is a task, not a note")))?,
+        None => return Err(TaskbookError::InvalidId(id)),
+    };
+    let page = to_html(note);
+
+    if file == Some("-") {
+        if format == NoteFormat::Pdf {
+            return Err(TaskbookError::General(
+                "a PDF is written to a file, not stdout".to_string(),
+            ));
+        }
+        let mut stdout = std::io::stdout().lock();
+        write!(stdout, "{page}")?;
+        return Ok(());
+    }
+
+    let path = file.map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "{}.{}",
+            file_stem(note.title()),
+            format.extension()
+        ))
+    });
+    match format {
+        NoteFormat::Html => fs::write(&path, page)?,
+        NoteFormat::Pdf => print_pdf(&page, &path)?,
+    }
+    eprintln!(
+        "{}",
+        format!("Exported note {id} to {}.", path.display())
+            .green()
+            .bold()
+    );
+    Ok(())
+}
+
+/// The note as a standalone page: its title as the heading, then the body
+/// rendered from Markdown
+pub fn to_html(note: &Note) -> String {
+    let title = escape(note.title());
+    let mut body = String::new();
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    html::push_html(
+        &mut body,
+        Parser::new_ext(note.body().unwrap_or_default(), options),
+    );
+
+    format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{title}</title>
+<style>
+body {{ max-width: 46em; margin: 2em auto; padding: 0 1em; font: 16px/1.6 system-ui, sans-serif; color: #222; }}
+pre, code {{ font-family: ui-monospace, monospace; background: #f4f4f4; border-radius: 3px; }}
+pre {{ padding: .8em; overflow-x: auto; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #ccc; padding: .3em .6em; }}
+blockquote {{ margin-left: 0; padding-left: 1em; border-left: 3px solid #ccc; color: #555; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{body}</body>
+</html>
+"
+    )
+}
+
+/// Print `page` to `path` with the first PDF tool found on the PATH
+fn print_pdf(page: &str, path: &Path) -> Result<()> {
+    let html_path = std::env::temp_dir().join(format!("taskbook-note-{}.html", std::process::id()));
+    fs::write(&html_path, page)?;
+
+    let mut result = Err(TaskbookError::General(
+        "exporting a PDF needs weasyprint or wkhtmltopdf; \
+         export --format html and print it from a browser instead"
+            .to_string(),
+    ));
+    for (tool, args) in PDF_TOOLS {
+        let status = Command::new(tool)
+            .args(*args)
+            .arg(&html_path)
+            .arg(path)
+            .stdin(Stdio::null())
+            .status();
+        match status {
+            Ok(status) if status.success() => {
+                result = Ok(());
+                break;
+            }
+            Ok(status) => {
+                result = Err(TaskbookError::General(format!("{tool} failed ({status})")));
+                break;
+            }
+            // Not installed: try the next one
+            Err(_) => continue,
+        }
+    }
+    let _ = fs::remove_file(&html_path);
+    result
+}
+
+/// A file name from a note title: letters and digits, words joined by `-`
+fn file_stem(title: &str) -> String {
+    let stem = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    if stem.is_empty() {
+        "note".to_string()
+    } else {
+        stem
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_is_rendered_and_title_escaped() {
+        let note = Note::new_with_body(
+            5,
+            "Design <draft> & notes".to_string(),
+            Some("Some *emphasis*\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n- [x] done".to_string()),
+            vec!["work".to_string()],
+        );
+        let page = to_html(&note);
+        assert!(page.contains("<title>Design &lt;draft&gt; &amp; notes</title>"));
+        assert!(page.contains("<em>emphasis</em>"));
+        assert!(page.contains("<table>"));
+        assert!(page.contains("type=\"checkbox\""));
+        assert_eq!(file_stem("Design <draft> & notes"), "design-draft-notes");
+        assert_eq!(file_stem("!!"), "note");
+    }
+}
@@ -0,0 +1,36 @@
+//! Opening files and URLs with the platform's default application.
+
+use std::process::{Command, Stdio};
+
+use crate::error::{Result, TaskbookError};
+
+/// Open `target` (a path or URL) with `open`, `xdg-open` or `start`,
+/// without waiting for the application to exit
+pub fn open(target: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg(target);
+        command
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        // The empty argument is the window title `start` expects first
+        command.args(["/C", "start", ""]).arg(target);
+        command
+    } else {
+        let mut command = Command::new("xdg-open");
+        command.arg(target);
+        command
+    };
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| TaskbookError::General(format!("cannot open {target}: {e}")))?;
+    // Reap the opener off the caller's thread; it exits once it handed off
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Paging of long command-line output, like git does.
+//!
+//! The pager is `TASKBOOK_PAGER`, then the `pager` config setting, then
+//! `$PAGER`, then `less -R`. An empty command turns paging off.
+
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::config::Config;
+
+const DEFAULT_PAGER: &str = "less -R";
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Never page output (`tb --no-pager`)
+pub fn disable() {
+    DISABLED.store(true, Ordering::SeqCst);
+}
+
+/// Pager command to use, or `None` when output should not be paged
+pub fn command(config: &Config) -> Option<String> {
+    if DISABLED.load(Ordering::SeqCst) || !io::stdout().is_terminal() {
+        return None;
+    }
+    resolve(config.pager.clone(), env::var("PAGER").ok())
+}
+
+fn resolve(configured: Option<String>, env_pager: Option<String>) -> Option<String> {
+    let command = configured
+        .or(env_pager)
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let command = command.trim();
+    (!command.is_empty() && command != "cat").then(|| command.to_string())
+}
+
+/// Print `text`, through `command` if it is taller than the terminal.
+/// Falls back to printing directly if the pager cannot be started.
+pub fn show(text: &str, command: &str) {
+    let height = crossterm::terminal::size().map_or(usize::MAX, |(_, rows)| rows as usize);
+    if text.lines().count() < height || page(text, command).is_err() {
+        print!("{text}");
+    }
+}
+
+fn page(text: &str, command: &str) -> io::Result<()> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+
+    // Same defaults as git: keep colors, and leave the text on screen on exit
+    if env::var_os("LESS").is_none() {
+        shell.env("LESS", "FRX");
+    }
+    let mut child = shell.stdin(Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may quit before reading everything; that is fine
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_prefers_config_then_env_then_less() {
+        assert_eq!(
+            resolve(Some("more".to_string()), Some("most".to_string())),
+            Some("more".to_string())
+        );
+        assert_eq!(
+            resolve(None, Some("most".to_string())),
+            Some("most".to_string())
+        );
+        assert_eq!(resolve(None, None), Some("less -R".to_string()));
+        assert_eq!(resolve(Some(String::new()), Some("most".to_string())), None);
+        assert_eq!(resolve(None, Some("cat".to_string())), None);
+    }
+}
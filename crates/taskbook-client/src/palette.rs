@@ -0,0 +1,135 @@
+//! Theme colors on terminals without 24-bit color: the nearest entry of the
+//! 256-color or 16-color palette, for both the CLI output and the TUI.
+
+use crate::config::{ColorMode, Rgb};
+
+/// Levels of each channel in the 6×6×6 color cube, entries 16-231
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The colors the terminal shows: `mode` itself unless it is `Auto`, in
+/// which case it is guessed from `COLORTERM` and `TERM`
+pub fn detect(mode: ColorMode) -> ColorMode {
+    if mode != ColorMode::Auto {
+        return mode;
+    }
+    let var = |name: &str| std::env::var(name).unwrap_or_default().to_lowercase();
+    let colorterm = var("COLORTERM");
+    let term = var("TERM");
+    if colorterm == "truecolor"
+        || colorterm == "24bit"
+        || term.ends_with("-direct")
+        // Windows Terminal
+        || std::env::var_os("WT_SESSION").is_some()
+    {
+        ColorMode::TrueColor
+    } else if term.contains("256color") {
+        ColorMode::Ansi256
+    } else {
+        ColorMode::Ansi16
+    }
+}
+
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2) as u32;
+    d(a.r, b.r) + d(a.g, b.g) + d(a.b, b.b)
+}
+
+/// Index of the 256-color palette entry closest to `rgb`, from the color
+/// cube or the gray ramp; never one of the first 16, whose colors vary with
+/// the terminal's scheme
+pub fn nearest_256(rgb: Rgb) -> u8 {
+    let level = |c: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (i32::from(**level) - i32::from(c)).abs())
+            .map(|(i, _)| i as u8)
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.r), level(rgb.g), level(rgb.b));
+    let cube = Rgb::new(
+        CUBE_LEVELS[r as usize],
+        CUBE_LEVELS[g as usize],
+        CUBE_LEVELS[b as usize],
+    );
+    let cube_index = 16 + 36 * r + 6 * g + b;
+
+    // Gray ramp, entries 232-255: 8, 18, ... 238
+    let average = (u32::from(rgb.r) + u32::from(rgb.g) + u32::from(rgb.b)) / 3;
+    let step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * step;
+    let gray = Rgb::new(gray_level, gray_level, gray_level);
+
+    if distance(gray, rgb) < distance(cube, rgb) {
+        232 + step
+    } else {
+        cube_index
+    }
+}
+
+/// Index (0-15) of the basic color closest to `rgb` in hue. Grays go to
+/// black, white or one of the two grays by brightness; other colors to the
+/// basic color of their hue, the bright one unless `rgb` is dark.
+pub fn nearest_16(rgb: Rgb) -> u8 {
+    let max = rgb.r.max(rgb.g).max(rgb.b);
+    let min = rgb.r.min(rgb.g).min(rgb.b);
+    let value = f32::from(max) / 255.0;
+    let chroma = f32::from(max - min);
+    if max == 0 || chroma / f32::from(max) < 0.25 {
+        return match value {
+            v if v < 0.15 => 0,
+            v if v < 0.6 => 8,
+            v if v < 0.9 => 7,
+            _ => 15,
+        };
+    }
+
+    let (r, g, b) = (f32::from(rgb.r), f32::from(rgb.g), f32::from(rgb.b));
+    let hue = if max == rgb.r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == rgb.g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    // Red, yellow, green, cyan, blue, magenta, each 60 degrees around its hue
+    let base = match ((hue + 30.0) / 60.0) as u32 % 6 {
+        0 => 1,
+        1 => 3,
+        2 => 2,
+        3 => 6,
+        4 => 4,
+        _ => 5,
+    };
+    if value < 0.6 {
+        base
+    } else {
+        base + 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_map_to_the_nearest_palette_entry() {
+        assert_eq!(nearest_256(Rgb::new(255, 0, 0)), 196);
+        assert_eq!(nearest_256(Rgb::new(95, 135, 175)), 67);
+        // Grays use the ramp rather than the cube
+        assert_eq!(nearest_256(Rgb::new(140, 140, 140)), 245);
+        assert_eq!(nearest_256(Rgb::new(0, 0, 0)), 16);
+
+        // Pastels keep their hue rather than turning gray
+        assert_eq!(nearest_16(Rgb::new(252, 129, 129)), 9);
+        assert_eq!(nearest_16(Rgb::new(134, 239, 172)), 10);
+        assert_eq!(nearest_16(Rgb::new(253, 224, 71)), 11);
+        assert_eq!(nearest_16(Rgb::new(147, 197, 253)), 12);
+        assert_eq!(nearest_16(Rgb::new(128, 0, 0)), 1);
+        assert_eq!(nearest_16(Rgb::new(140, 140, 140)), 8);
+        assert_eq!(nearest_16(Rgb::new(255, 255, 255)), 15);
+        assert_eq!(nearest_16(Rgb::new(0, 0, 0)), 0);
+
+        assert_eq!(detect(ColorMode::Ansi16), ColorMode::Ansi16);
+    }
+}
@@ -0,0 +1,369 @@
+//! Encryption keys derived from a passphrase.
+//!
+//! A passphrase is easier to carry between devices than a random key. The
+//! key is derived from it with Argon2id; the salt and algorithm are kept on
+//! the server, so any device can derive the same key from the passphrase
+//! alone. The server never sees the passphrase or the key.
+//!
+//! Setting or changing the passphrase changes the key, so everything on the
+//! server is decrypted with the old key and uploaded again under the new
+//! one, and the keys of shared boards are wrapped again. `rotate-key` does
+//! the same to move to a fresh key, for example after raising the cost of
+//! the key derivation in the `encryption` config.
+
+use base64::Engine;
+use colored::Colorize;
+use taskbook_common::encryption::{
+    derive_key, generate_key, identity_public_key, unwrap_board_key, wrap_board_key, KeyParams,
+};
+
+use crate::api_client::ApiClient;
+use crate::auth::prompt_password;
+use crate::config::Config;
+use crate::credentials::Credentials;
+use crate::error::{Result, TaskbookError};
+use crate::sharing;
+use crate::storage::{decrypt_with, encrypt_with};
+
+/// Shortest passphrase accepted
+const MIN_PASSPHRASE_LEN: usize = 12;
+
+/// Derive the account's encryption key from a passphrase from now on.
+pub fn set_passphrase(config: &Config) -> Result<()> {
+    let mut creds = logged_in()?;
+    let client = ApiClient::from_credentials(&creds);
+    if client.me()?.key_params.is_some() {
+        return Err(TaskbookError::General(
+            "the account already has a passphrase — use `tb sync change-passphrase`".to_string(),
+        ));
+    }
+
+    let cost = config.encryption.kdf_cost()?;
+    let passphrase = new_passphrase("Passphrase: ", "Confirm passphrase: ")?;
+    let params = KeyParams::generate_with(cost);
+    let key = derive(&passphrase, &params)?;
+    rekey(&client, &mut creds, key, Some(&params))?;
+
+    println!("{}", "Passphrase set.".green().bold());
+    print_rekey_notes();
+    Ok(())
+}
+
+/// Replace the passphrase the encryption key is derived from.
+pub fn change_passphrase(config: &Config) -> Result<()> {
+    let mut creds = logged_in()?;
+    let client = ApiClient::from_credentials(&creds);
+    let params = key_params(&client)?.ok_or_else(|| {
+        TaskbookError::General(
+            "the account has no passphrase yet — use `tb sync set-passphrase`".to_string(),
+        )
+    })?;
+
+    let cost = config.encryption.kdf_cost()?;
+    check_passphrase(&creds, &params, "Current passphrase: ")?;
+
+    let passphrase = new_passphrase("New passphrase: ", "Confirm new passphrase: ")?;
+    let params = KeyParams::generate_with(cost);
+    let key = derive(&passphrase, &params)?;
+    rekey(&client, &mut creds, key, Some(&params))?;
+
+    println!("{}", "Passphrase changed.".green().bold());
+    print_rekey_notes();
+    Ok(())
+}
+
+/// Move the account to a new encryption key: derived again from the same
+/// passphrase with a fresh salt and the configured cost, or a new random key
+/// for an account without a passphrase.
+pub fn rotate_key(config: &Config) -> Result<()> {
+    let mut creds = logged_in()?;
+    let client = ApiClient::from_credentials(&creds);
+
+    match key_params(&client)? {
+        Some(params) => {
+            let cost = config.encryption.kdf_cost()?;
+            let passphrase = check_passphrase(&creds, &params, "Passphrase: ")?;
+            let params = KeyParams::generate_with(cost);
+            let key = derive(&passphrase, &params)?;
+            rekey(&client, &mut creds, key, Some(&params))?;
+
+            println!("{}", "Encryption key rotated.".green().bold());
+        }
+        None => {
+            let key = generate_key();
+            rekey(&client, &mut creds, key, None)?;
+
+            println!("{}", "Encryption key rotated.".green().bold());
+            println!();
+            println!(
+                "{}",
+                "Your new encryption key (save this — it cannot be recovered):".yellow()
+            );
+            println!();
+            println!("  {}", creds.encryption_key.bright_white().bold());
+            println!();
+        }
+    }
+    print_rekey_notes();
+    Ok(())
+}
+
+/// The encryption key for what was typed at login: either a key as printed
+/// by `tb register`, or the account's passphrase.
+pub(crate) fn resolve_key(client: &ApiClient, input: &str) -> Result<String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    if engine.decode(input).is_ok_and(|bytes| bytes.len() == 32) {
+        return Ok(input.to_string());
+    }
+
+    let params = key_params(client)?.ok_or_else(|| {
+        TaskbookError::Auth("not an encryption key, and the account has no passphrase".to_string())
+    })?;
+    Ok(engine.encode(derive(input, &params)?))
+}
+
+/// Check `key` against the identity the account published, which was
+/// derived from the key its data is encrypted with. Accounts that have not
+/// published one yet are let through.
+pub(crate) fn check_key(client: &ApiClient, key: &str) -> Result<()> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let Some(key) = engine
+        .decode(key)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+    else {
+        return Err(TaskbookError::Auth("invalid encryption key".to_string()));
+    };
+
+    let username = client.me()?.username;
+    match client.get_public_key(&username) {
+        Ok(published) if published != engine.encode(identity_public_key(&key)) => Err(
+            TaskbookError::Auth("wrong encryption key or passphrase for this account".to_string()),
+        ),
+        _ => Ok(()),
+    }
+}
+
+fn logged_in() -> Result<Credentials> {
+    Credentials::load()?.ok_or_else(|| {
+        TaskbookError::Auth("not logged in — run `tb register` or `tb login` first".to_string())
+    })
+}
+
+fn key_params(client: &ApiClient) -> Result<Option<KeyParams>> {
+    client
+        .me()?
+        .key_params
+        .map(|json| {
+            serde_json::from_str(&json).map_err(|e| {
+                TaskbookError::General(format!("invalid passphrase settings on the server: {e}"))
+            })
+        })
+        .transpose()
+}
+
+/// Ask for the passphrase and check it derives the key in use
+fn check_passphrase(creds: &Credentials, params: &KeyParams, message: &str) -> Result<String> {
+    let passphrase = prompt_password(message)?;
+    if derive(&passphrase, params)? != creds.encryption_key_bytes()? {
+        return Err(TaskbookError::Auth("wrong passphrase".to_string()));
+    }
+    Ok(passphrase)
+}
+
+fn derive(passphrase: &str, params: &KeyParams) -> Result<[u8; 32]> {
+    derive_key(passphrase, params)
+        .map_err(|e| TaskbookError::General(format!("cannot derive key: {e}")))
+}
+
+/// Ask for a new passphrase, twice
+pub(crate) fn new_passphrase(message: &str, confirm: &str) -> Result<String> {
+    let p1 = prompt_password(message)?;
+    if p1.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(TaskbookError::General(format!(
+            "the passphrase needs at least {MIN_PASSPHRASE_LEN} characters"
+        )));
+    }
+    let p2 = prompt_password(confirm)?;
+    if p1 != p2 {
+        return Err(TaskbookError::General(
+            "passphrases do not match".to_string(),
+        ));
+    }
+    Ok(p1)
+}
+
+/// Move the account from its current key to `new_key`, derived with
+/// `params` if it comes from a passphrase. If any write fails, those made
+/// are undone, so the account is not left split between two keys.
+fn rekey(
+    client: &ApiClient,
+    creds: &mut Credentials,
+    new_key: [u8; 32],
+    params: Option<&KeyParams>,
+) -> Result<()> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let old_key = creds.encryption_key_bytes()?;
+    let params_json = params
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| TaskbookError::General(format!("failed to serialize: {e}")))?;
+    let old_params_json = client.me()?.key_params;
+
+    // Decrypt everything first, so a wrong key fails before anything changes
+    let items = client.get_items(None)?.map(|t| t.items).unwrap_or_default();
+    let archive = client
+        .get_archive(None)?
+        .map(|t| t.items)
+        .unwrap_or_default();
+    let new_items = encrypt_with(&new_key, &decrypt_with(&old_key, &items)?)?;
+    let new_archive = encrypt_with(&new_key, &decrypt_with(&old_key, &archive)?)?;
+
+    let new_identity = identity_public_key(&new_key);
+    let board_keys = client
+        .list_boards()?
+        .into_iter()
+        .map(|board| {
+            let wrapped = engine
+                .decode(&board.wrapped_key)
+                .map_err(|e| TaskbookError::General(format!("invalid base64 board key: {e}")))?;
+            let key = unwrap_board_key(&old_key, &wrapped)
+                .map_err(|e| TaskbookError::General(format!("cannot open shared board: {e}")))?;
+            let rewrapped = wrap_board_key(&new_identity, &key)
+                .map_err(|e| TaskbookError::General(format!("encryption failed: {e}")))?;
+            Ok((board.id, board.wrapped_key, engine.encode(rewrapped)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut new_creds = creds.clone();
+    new_creds.encryption_key = engine.encode(new_key);
+
+    // The passphrase settings go first: until the new key is saved locally,
+    // a failure anywhere puts every step back
+    let mut steps = Vec::new();
+    if let Some(params_json) = &params_json {
+        steps.push(Step {
+            run: Box::new(|| client.put_key_params(Some(params_json))),
+            undo: Box::new(|| client.put_key_params(old_params_json.as_deref())),
+        });
+    }
+    steps.push(Step {
+        run: Box::new(|| client.put_archive(&new_archive)),
+        undo: Box::new(|| client.put_archive(&archive)),
+    });
+    steps.push(Step {
+        run: Box::new(|| client.put_items(&new_items)),
+        undo: Box::new(|| client.put_items(&items)),
+    });
+    for (board_id, old_wrapped, new_wrapped) in &board_keys {
+        steps.push(Step {
+            run: Box::new(move || client.put_board_key(board_id, new_wrapped)),
+            undo: Box::new(move || client.put_board_key(board_id, old_wrapped)),
+        });
+    }
+    steps.push(Step {
+        run: Box::new(|| sharing::publish_identity(client, &new_key)),
+        undo: Box::new(|| sharing::publish_identity(client, &old_key)),
+    });
+    steps.push(Step {
+        run: Box::new(|| new_creds.save()),
+        undo: Box::new(|| Ok(())),
+    });
+    run_steps(&steps)?;
+    drop(steps);
+
+    *creds = new_creds;
+    Ok(())
+}
+
+/// A write made while rekeying, and the write that undoes it
+struct Step<'a> {
+    run: Box<dyn Fn() -> Result<()> + 'a>,
+    undo: Box<dyn Fn() -> Result<()> + 'a>,
+}
+
+/// Run `steps` in order. If one fails, undo those that ran, newest first,
+/// and return its error.
+fn run_steps(steps: &[Step]) -> Result<()> {
+    for (done, step) in steps.iter().enumerate() {
+        if let Err(e) = (step.run)() {
+            for step in steps[..done].iter().rev() {
+                let _ = (step.undo)();
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+fn print_rekey_notes() {
+    println!(
+        "{}",
+        "Your data has been encrypted again with the new key.".dimmed()
+    );
+    println!(
+        "{}",
+        "Items in the trash and earlier versions of items stay under the old key and can no \
+         longer be read."
+            .dimmed()
+    );
+    println!(
+        "{}",
+        "Other devices need to log in again with `tb sync login`.".dimmed()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    fn step<'a>(log: &'a RefCell<Vec<String>>, name: &'a str, fails: bool) -> Step<'a> {
+        Step {
+            run: Box::new(move || {
+                log.borrow_mut().push(format!("run {name}"));
+                if fails {
+                    return Err(TaskbookError::General(format!("{name} failed")));
+                }
+                Ok(())
+            }),
+            undo: Box::new(move || {
+                log.borrow_mut().push(format!("undo {name}"));
+                Ok(())
+            }),
+        }
+    }
+
+    #[test]
+    fn a_failed_last_step_undoes_the_others_newest_first() {
+        let log = RefCell::new(Vec::new());
+        let steps = [
+            step(&log, "params", false),
+            step(&log, "items", false),
+            step(&log, "credentials", true),
+        ];
+
+        let err = run_steps(&steps).unwrap_err();
+        assert_eq!(err.to_string(), "credentials failed");
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "run params",
+                "run items",
+                "run credentials",
+                "undo items",
+                "undo params"
+            ]
+        );
+    }
+
+    #[test]
+    fn steps_that_succeed_are_not_undone() {
+        let log = RefCell::new(Vec::new());
+        let steps = [step(&log, "params", false), step(&log, "items", false)];
+
+        run_steps(&steps).unwrap();
+        assert_eq!(*log.borrow(), vec!["run params", "run items"]);
+    }
+}
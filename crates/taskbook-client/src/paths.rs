@@ -0,0 +1,118 @@
+//! Locations of the config file and the default data directory.
+//!
+//! Follows the XDG base directory layout: the config lives at
+//! `$XDG_CONFIG_HOME/taskbook/config.json` and data under
+//! `$XDG_DATA_HOME/taskbook/`, falling back to `~/.config` and
+//! `~/.local/share`. The legacy `~/.taskbook.json` and `~/.taskbook/` are
+//! moved there the first time they are found.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, TaskbookError};
+
+/// Overrides the config file location
+const CONFIG_ENV: &str = "TASKBOOK_CONFIG";
+const APP_DIR_NAME: &str = "taskbook";
+const CONFIG_FILE_NAME: &str = "config.json";
+const LEGACY_CONFIG_FILE_NAME: &str = ".taskbook.json";
+const LEGACY_DATA_DIR_NAME: &str = ".taskbook";
+
+fn home_dir() -> Result<PathBuf> {
+    dirs::home_dir()
+        .ok_or_else(|| TaskbookError::General("could not find home directory".to_string()))
+}
+
+/// Path of the config file: `TASKBOOK_CONFIG` if set, otherwise
+/// `$XDG_CONFIG_HOME/taskbook/config.json`
+pub fn config_file() -> Result<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_ENV).filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let home = home_dir()?;
+    let target = xdg_dir(env::var_os("XDG_CONFIG_HOME"), &home, ".config")
+        .join(APP_DIR_NAME)
+        .join(CONFIG_FILE_NAME);
+    Ok(migrate_legacy(&home.join(LEGACY_CONFIG_FILE_NAME), target))
+}
+
+/// Default data directory when no custom directory is configured:
+/// `$XDG_DATA_HOME/taskbook`
+pub fn data_dir() -> Result<PathBuf> {
+    let home = home_dir()?;
+    let target = xdg_dir(env::var_os("XDG_DATA_HOME"), &home, ".local/share").join(APP_DIR_NAME);
+    Ok(migrate_legacy(&home.join(LEGACY_DATA_DIR_NAME), target))
+}
+
+/// Base directory from an XDG variable; relative or empty values are
+/// ignored, as the spec requires
+fn xdg_dir(value: Option<OsString>, home: &Path, fallback: &str) -> PathBuf {
+    value
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| home.join(fallback))
+}
+
+/// Move `legacy` to `target` if only the legacy path exists. If the move
+/// fails (e.g. across filesystems), keep using the legacy path.
+fn migrate_legacy(legacy: &Path, target: PathBuf) -> PathBuf {
+    if target.exists() || !legacy.exists() {
+        return target;
+    }
+
+    let moved = target
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::rename(legacy, &target));
+    match moved {
+        Ok(()) => {
+            eprintln!("Moved {} to {}", legacy.display(), target.display());
+            target
+        }
+        Err(_) => legacy.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xdg_dir_ignores_relative_values() {
+        let home = Path::new("/home/me");
+        assert_eq!(
+            xdg_dir(Some("/xdg/config".into()), home, ".config"),
+            PathBuf::from("/xdg/config")
+        );
+        assert_eq!(
+            xdg_dir(Some("relative".into()), home, ".config"),
+            PathBuf::from("/home/me/.config")
+        );
+        assert_eq!(
+            xdg_dir(None, home, ".local/share"),
+            PathBuf::from("/home/me/.local/share")
+        );
+    }
+
+    #[test]
+    fn migrate_legacy_moves_only_when_target_is_missing() {
+        let root = env::temp_dir().join(format!("tb-paths-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let legacy = root.join(".taskbook.json");
+        let target = root.join("config").join("taskbook").join("config.json");
+
+        fs::write(&legacy, "{}").unwrap();
+        assert_eq!(migrate_legacy(&legacy, target.clone()), target);
+        assert!(target.exists());
+        assert!(!legacy.exists());
+
+        fs::write(&legacy, "{}").unwrap();
+        assert_eq!(migrate_legacy(&legacy, target.clone()), target);
+        assert!(legacy.exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,67 @@
+//! Human-readable creation times ("2 hours ago", "yesterday") for the
+//! timeline and journal.
+
+use chrono::{DateTime, Local, TimeZone};
+
+const MINUTE: i64 = 60 * 1000;
+const HOUR: i64 = 60 * MINUTE;
+
+/// Describe `timestamp` (ms since epoch) relative to `now`. Times within
+/// today are counted in minutes or hours, earlier ones in calendar days.
+pub fn format(timestamp: i64, now: DateTime<Local>) -> String {
+    let Some(then) = Local.timestamp_millis_opt(timestamp).single() else {
+        return String::new();
+    };
+
+    let elapsed = now.timestamp_millis() - timestamp;
+    let days = (now.date_naive() - then.date_naive()).num_days();
+
+    if elapsed < MINUTE {
+        "just now".to_string()
+    } else if days <= 0 && elapsed < HOUR {
+        plural(elapsed / MINUTE, "minute")
+    } else if days <= 0 {
+        plural(elapsed / HOUR, "hour")
+    } else if days == 1 {
+        "yesterday".to_string()
+    } else if days < 7 {
+        plural(days, "day")
+    } else if days < 30 {
+        plural(days / 7, "week")
+    } else if days < 365 {
+        plural(days / 30, "month")
+    } else {
+        plural(days / 365, "year")
+    }
+}
+
+fn plural(count: i64, unit: &str) -> String {
+    if count == 1 {
+        format!("1 {unit} ago")
+    } else {
+        format!("{count} {unit}s ago")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn formats_relative_to_now() {
+        let now = Local.with_ymd_and_hms(2024, 3, 5, 15, 0, 0).unwrap();
+        let ago = |d: Duration| format((now - d).timestamp_millis(), now);
+
+        assert_eq!(ago(Duration::seconds(20)), "just now");
+        assert_eq!(ago(Duration::minutes(1)), "1 minute ago");
+        assert_eq!(ago(Duration::minutes(45)), "45 minutes ago");
+        assert_eq!(ago(Duration::hours(2)), "2 hours ago");
+        assert_eq!(ago(Duration::hours(16)), "yesterday");
+        assert_eq!(ago(Duration::days(3)), "3 days ago");
+        assert_eq!(ago(Duration::days(15)), "2 weeks ago");
+        assert_eq!(ago(Duration::days(65)), "2 months ago");
+        assert_eq!(ago(Duration::days(400)), "1 year ago");
+        assert_eq!(ago(Duration::minutes(-5)), "just now");
+    }
+}
@@ -1,10 +1,48 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 
-use colored::{ColoredString, Colorize};
-
-use crate::config::{Config, Rgb, ThemeColors};
+use chrono::{Local, NaiveDate, TimeZone};
+use colored::{Color, ColoredString, Colorize};
+
+use crate::agenda::AgendaSection;
+use crate::attachments;
+use crate::burndown::{self, BurndownDay};
+use crate::config::{
+    ColorMode, Config, DisplayDensity, Icons, Overflow, Rgb, ThemeColors, TimelineGroup,
+};
+use crate::highlight;
+use crate::history;
+use crate::layout;
+use crate::pager;
+use crate::palette;
+use crate::relative_time;
+use crate::report::{Report, ReportPeriod};
+use crate::storage::HistoryEntry;
 use taskbook_common::board;
-use taskbook_common::StorageItem;
+use taskbook_common::due;
+use taskbook_common::priority;
+use taskbook_common::{Activity, Comment, LinkedCommit, StorageItem};
+
+/// Print a line to stdout (or the pager buffer) unless rendering is disabled
+macro_rules! out {
+    ($render:expr) => {
+        out!($render, "")
+    };
+    ($render:expr, $($arg:tt)*) => {
+        if $render.enabled {
+            $render.write_line(format!($($arg)*))
+        }
+    };
+}
+
+/// Print a line to stderr unless rendering is disabled
+macro_rules! err {
+    ($render:expr, $($arg:tt)*) => {
+        if $render.enabled {
+            eprintln!($($arg)*)
+        }
+    };
+}
 
 /// Statistics about items
 pub struct Stats {
@@ -15,6 +53,19 @@ pub struct Stats {
     pub notes: usize,
 }
 
+/// The non-empty parts, separated by spaces
+fn join_parts(parts: Vec<String>) -> String {
+    parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Narrowest a wrapped or truncated description gets, however long the
+/// rest of its line
+const MIN_DESCRIPTION_WIDTH: usize = 20;
+
 /// Item statistics for a group
 struct ItemStats {
     tasks: usize,
@@ -25,58 +76,156 @@ struct ItemStats {
 pub struct Render {
     config: Config,
     theme: ThemeColors,
+    /// Colors the terminal shows, detected when `colorMode` is auto
+    color_mode: ColorMode,
+    icons: Icons,
+    /// Terminal columns, or `None` when output is piped
+    width: Option<usize>,
+    /// Search terms to highlight in descriptions
+    highlight: RefCell<Vec<String>>,
+    /// When false, every output method is a no-op (library use)
+    enabled: bool,
+    /// Output held back for the pager, once a long listing has started
+    paged: RefCell<Option<(String, String)>>,
 }
 
 /// Trait extension for applying RGB colors
 trait RgbColorize {
-    fn rgb(self, color: Rgb) -> ColoredString;
+    /// `color`, or the nearest one the terminal shows in `mode`
+    fn rgb(self, color: Rgb, mode: ColorMode) -> ColoredString;
 }
 
 impl<S: AsRef<str>> RgbColorize for S {
-    fn rgb(self, color: Rgb) -> ColoredString {
-        self.as_ref().truecolor(color.r, color.g, color.b)
+    fn rgb(self, color: Rgb, mode: ColorMode) -> ColoredString {
+        let text = self.as_ref();
+        // `colored` has no 256-color codes, and would turn RGB into one of
+        // the basic colors whenever COLORTERM is unset
+        let code = match mode {
+            ColorMode::Ansi16 => {
+                return text.color(BASIC_COLORS[palette::nearest_16(color) as usize]);
+            }
+            ColorMode::Ansi256 => format!("38;5;{}", palette::nearest_256(color)),
+            ColorMode::TrueColor | ColorMode::Auto => {
+                format!("38;2;{};{};{}", color.r, color.g, color.b)
+            }
+        };
+        if !colored::control::SHOULD_COLORIZE.should_colorize() {
+            return text.normal();
+        }
+        ColoredString::from(format!("\x1b[{code}m{text}\x1b[39m").as_str())
     }
 }
 
+/// `colored`'s basic colors, in palette order
+const BASIC_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+    Color::BrightBlack,
+    Color::BrightRed,
+    Color::BrightGreen,
+    Color::BrightYellow,
+    Color::BrightBlue,
+    Color::BrightMagenta,
+    Color::BrightCyan,
+    Color::BrightWhite,
+];
+
 impl Render {
     pub fn new(config: Config) -> Self {
         let theme = config.theme.resolve();
-        Self { config, theme }
+        Self {
+            color_mode: palette::detect(config.color_mode),
+            icons: config.icons.resolve(),
+            width: layout::terminal_width(),
+            highlight: RefCell::new(Vec::new()),
+            config,
+            theme,
+            enabled: true,
+            paged: RefCell::new(None),
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Highlight matches of `terms` in the descriptions displayed from now
+    /// on; tag terms (`+tag`) are left out
+    pub fn highlight_matches(&self, terms: &[String]) {
+        *self.highlight.borrow_mut() = terms
+            .iter()
+            .filter(|term| !term.starts_with('+'))
+            .cloned()
+            .collect();
+    }
+
+    /// A renderer that prints nothing, for embedding `Taskbook` as a library
+    pub fn disabled(config: Config) -> Self {
+        let mut render = Self::new(config);
+        render.enabled = false;
+        render
+    }
+
+    fn write_line(&self, line: String) {
+        match self.paged.borrow_mut().as_mut() {
+            Some((_, buffer)) => {
+                buffer.push_str(&line);
+                buffer.push('\n');
+            }
+            None => println!("{line}"),
+        }
+    }
+
+    /// Hold back the rest of this command's stdout so that it can go
+    /// through the pager if it turns out taller than the terminal
+    fn start_paging(&self) {
+        if !self.enabled || self.paged.borrow().is_some() {
+            return;
+        }
+        if let Some(command) = pager::command(&self.config) {
+            *self.paged.borrow_mut() = Some((command, String::new()));
+        }
     }
 
     /// Apply muted color to text
     fn muted(&self, text: &str) -> ColoredString {
-        text.rgb(self.theme.muted)
+        text.rgb(self.theme.muted, self.color_mode)
     }
 
     /// Apply success color to text
     fn success(&self, text: &str) -> ColoredString {
-        text.rgb(self.theme.success)
+        text.rgb(self.theme.success, self.color_mode)
     }
 
     /// Apply warning color to text
     fn warning(&self, text: &str) -> ColoredString {
-        text.rgb(self.theme.warning)
+        text.rgb(self.theme.warning, self.color_mode)
     }
 
     /// Apply error color to text
     fn error(&self, text: &str) -> ColoredString {
-        text.rgb(self.theme.error)
+        text.rgb(self.theme.error, self.color_mode)
     }
 
     /// Apply info color to text
     fn info(&self, text: &str) -> ColoredString {
-        text.rgb(self.theme.info)
+        text.rgb(self.theme.info, self.color_mode)
     }
 
     /// Apply pending color to text
     fn pending(&self, text: &str) -> ColoredString {
-        text.rgb(self.theme.pending)
+        text.rgb(self.theme.pending, self.color_mode)
     }
 
     /// Apply starred color to text
     fn starred(&self, text: &str) -> ColoredString {
-        text.rgb(self.theme.starred)
+        text.rgb(self.theme.starred, self.color_mode)
     }
 
     fn color_boards(&self, boards: &[String]) -> String {
@@ -134,9 +283,28 @@ impl Render {
         }
     }
 
+    /// Due date label for open tasks, colored by urgency
+    fn get_due(&self, item: &StorageItem) -> String {
+        let Some(date) = item.due_date() else {
+            return String::new();
+        };
+        if item.as_task().is_some_and(|t| t.is_complete) {
+            return String::new();
+        }
+        let today = chrono::Local::now().date_naive();
+        let label = due::format_due(date, today);
+        if date < today {
+            self.error(&label).to_string()
+        } else if date == today {
+            self.warning(&label).to_string()
+        } else {
+            self.muted(&label).to_string()
+        }
+    }
+
     fn get_star(&self, item: &StorageItem) -> String {
         if item.is_starred() {
-            self.starred("★").to_string()
+            self.starred(&self.icons.star).to_string()
         } else {
             String::new()
         }
@@ -150,50 +318,169 @@ impl Render {
     }
 
     fn build_message(&self, item: &StorageItem) -> String {
-        if let Some(task) = item.as_task() {
-            let description = &task.description;
-            let priority = task.priority;
-
-            if !task.is_complete && priority > 1 {
-                let msg = if priority == 2 {
-                    self.warning(description).underline().to_string()
-                } else {
-                    self.error(description).underline().to_string()
-                };
-
-                let indicator = if priority == 2 {
-                    self.warning("(!)").to_string()
-                } else {
-                    self.error("(!!)").to_string()
-                };
-
-                format!("{} {}", msg, indicator)
-            } else if task.is_complete {
-                self.muted(description).strikethrough().to_string()
+        let mut parts = vec![self.style_description(item, item.description())];
+        parts.extend(self.build_message_extras(item));
+        parts.join(" ")
+    }
+
+    /// `text`, part or all of the item's description, styled by the item's
+    /// state and priority, with search matches in inverse video
+    fn style_description(&self, item: &StorageItem, text: &str) -> String {
+        let terms = self.highlight.borrow();
+        let mut styled = String::new();
+        let mut rest = 0;
+        for range in highlight::match_ranges(text, &terms) {
+            styled.push_str(
+                &self
+                    .description_style(item, &text[rest..range.start])
+                    .to_string(),
+            );
+            styled.push_str(
+                &self
+                    .description_style(item, &text[range.clone()])
+                    .reversed()
+                    .to_string(),
+            );
+            rest = range.end;
+        }
+        if rest < text.len() || text.is_empty() {
+            styled.push_str(&self.description_style(item, &text[rest..]).to_string());
+        }
+        styled
+    }
+
+    fn description_style(&self, item: &StorageItem, text: &str) -> ColoredString {
+        let Some(task) = item.as_task() else {
+            return text.normal();
+        };
+        if !task.is_complete && task.priority > 1 {
+            if task.priority == 2 {
+                self.warning(text).underline()
             } else {
-                description.to_string()
+                self.error(text).underline()
             }
+        } else if task.is_complete {
+            self.muted(text).strikethrough()
         } else {
+            text.normal()
+        }
+    }
+
+    /// Markers after the description: priority, note body, comment and
+    /// attachment counts
+    fn build_message_extras(&self, item: &StorageItem) -> Vec<String> {
+        let mut extras = Vec::new();
+        if let Some(task) = item.as_task() {
+            if !task.is_complete && task.priority == 2 {
+                extras.push(self.warning("(!)").to_string());
+            } else if !task.is_complete && task.priority > 2 {
+                extras.push(self.error("(!!)").to_string());
+            }
+            if !task.comments.is_empty() {
+                extras.push(
+                    self.muted(&format!("{}{}", self.icons.comments, task.comments.len()))
+                        .to_string(),
+                );
+            }
+        } else if item.note_has_body() {
             // Note: add [+] indicator if note has body content
-            let description = item.description();
-            if item.note_has_body() {
-                format!("{} {}", description, self.muted("[+]"))
-            } else {
-                description.to_string()
+            extras.push(self.muted("[+]").to_string());
+        }
+        if !item.attachments().is_empty() {
+            extras.push(
+                self.muted(&format!(
+                    "{}{}",
+                    self.icons.attachments,
+                    item.attachments().len()
+                ))
+                .to_string(),
+            );
+        }
+        extras
+    }
+
+    /// Print an item's line, fitting a description too long for the
+    /// terminal to it by the `descriptionOverflow` setting
+    fn print_item_line(&self, item: &StorageItem, prefix: &str, suffix: &str) {
+        let icon = self.get_item_icon(item);
+        let message = self.build_message(item);
+        let line = format!("{} {} {} {}", prefix, icon, message, suffix);
+        let Some(width) = self
+            .width
+            .filter(|width| layout::display_width(line.trim_end()) > *width)
+        else {
+            out!(self, "{}", line);
+            return;
+        };
+
+        let indent = layout::display_width(prefix) + layout::display_width(&icon) + 2;
+        let room = width.saturating_sub(indent).max(MIN_DESCRIPTION_WIDTH);
+        let mut tail = self.build_message_extras(item);
+        if !suffix.is_empty() {
+            tail.push(suffix.to_string());
+        }
+        let tail = tail.join(" ");
+        let tail_width = layout::display_width(&tail);
+        let description = item.description();
+
+        match self.config.description_overflow {
+            Overflow::Truncate => {
+                let fit = room
+                    .saturating_sub(tail_width + 1)
+                    .max(MIN_DESCRIPTION_WIDTH);
+                let text = self.style_description(item, &layout::truncate(description, fit));
+                out!(self, "{} {} {} {}", prefix, icon, text, tail);
+            }
+            Overflow::Wrap => {
+                let lines = layout::wrap(description, room);
+                let padding = " ".repeat(indent);
+                let last = lines.len() - 1;
+                for (i, text) in lines.iter().enumerate() {
+                    let start = if i == 0 {
+                        format!("{} {} ", prefix, icon)
+                    } else {
+                        padding.clone()
+                    };
+                    let styled = self.style_description(item, text);
+                    if i < last || tail.is_empty() {
+                        out!(self, "{}{}", start, styled);
+                    } else if layout::display_width(text) + 1 + tail_width <= room {
+                        out!(self, "{}{} {}", start, styled, tail);
+                    } else {
+                        out!(self, "{}{}", start, styled);
+                        out!(self, "{}{}", padding, tail);
+                    }
+                }
             }
         }
     }
 
-    fn display_title(&self, title: &str, items: &[&StorageItem]) {
-        let today = chrono::Local::now().format("%a %b %d %Y").to_string();
-        let display_title = if title == today {
-            format!("{} {}", title.underline(), self.muted("[Today]"))
-        } else {
-            title.underline().to_string()
+    fn display_title(&self, title: &str, marker: Option<&str>, correlation: &str) {
+        let display_title = match marker {
+            Some(marker) => format!(
+                "{} {}",
+                title.underline(),
+                self.muted(&format!("[{marker}]"))
+            ),
+            None => title.underline().to_string(),
         };
 
+        out!(self, "\n {} {}", display_title, correlation);
+    }
+
+    /// Completion for a week or month heading: `[3/7] 42% done`
+    fn get_period_correlation(&self, items: &[&StorageItem]) -> String {
+        let stats = self.get_item_stats(items);
         let correlation = self.get_correlation(items);
-        println!("\n {} {}", display_title, correlation);
+        if stats.tasks == 0 {
+            return correlation;
+        }
+        let percent = stats.complete * 100 / stats.tasks;
+        format!(
+            "{} {}",
+            correlation,
+            self.muted(&format!("{percent}% done"))
+        )
     }
 
     fn color_tags(&self, tags: &[String]) -> String {
@@ -207,72 +494,89 @@ impl Render {
     }
 
     fn display_item_by_board(&self, item: &StorageItem) {
-        let age = self.get_age(item.timestamp());
-        let star = self.get_star(item);
+        let density = self.config.display_density;
         let prefix = self.build_prefix(item);
-        let message = self.build_message(item);
-        let tags = self.color_tags(item.tags());
 
-        let mut suffix_parts: Vec<String> = Vec::new();
-        if !tags.is_empty() {
-            suffix_parts.push(tags);
-        }
-        if !age.is_empty() {
-            suffix_parts.push(age);
+        let mut suffix_parts = vec![self.color_tags(item.tags()), self.get_due(item)];
+        if density == DisplayDensity::Detailed {
+            suffix_parts.push(self.get_tracked_time(item));
+            suffix_parts.push(self.get_boards(item));
         }
-        if !star.is_empty() {
-            suffix_parts.push(star);
+        if density != DisplayDensity::Compact {
+            suffix_parts.push(self.get_age(item.timestamp()));
         }
-        let suffix = suffix_parts.join(" ");
+        suffix_parts.push(self.get_star(item));
+        let suffix = join_parts(suffix_parts);
 
-        let icon = self.get_item_icon(item);
-        println!("{} {} {} {}", prefix, icon, message, suffix);
+        self.print_item_line(item, &prefix, &suffix);
     }
 
     fn display_item_by_date(&self, item: &StorageItem) {
+        let density = self.config.display_density;
+        let prefix = self.build_prefix(item);
+
+        let mut suffix_parts = vec![self.color_tags(item.tags()), self.get_due(item)];
+        if density == DisplayDensity::Detailed {
+            suffix_parts.push(self.get_tracked_time(item));
+        }
+        if density != DisplayDensity::Compact {
+            suffix_parts.push(self.get_boards(item));
+        }
+        suffix_parts.push(self.get_star(item));
+        if density == DisplayDensity::Detailed {
+            suffix_parts.push(self.get_age(item.timestamp()));
+        }
+        if density != DisplayDensity::Compact && self.config.relative_timestamps {
+            let created = relative_time::format(item.timestamp(), chrono::Local::now());
+            suffix_parts.push(self.muted(&created).to_string());
+        }
+        let suffix = join_parts(suffix_parts);
+
+        self.print_item_line(item, &prefix, &suffix);
+    }
+
+    /// The boards an item is on, other than the default one
+    fn get_boards(&self, item: &StorageItem) -> String {
         let boards: Vec<String> = item
             .boards()
             .iter()
             .filter(|b| !board::board_eq(b, board::DEFAULT_BOARD))
             .map(|b| board::display_name(b))
             .collect();
-        let star = self.get_star(item);
-        let prefix = self.build_prefix(item);
-        let message = self.build_message(item);
-        let boards_str = self.color_boards(&boards);
-        let tags = self.color_tags(item.tags());
+        self.color_boards(&boards)
+    }
 
-        let mut suffix_parts: Vec<String> = Vec::new();
-        if !tags.is_empty() {
-            suffix_parts.push(tags);
-        }
-        if !boards_str.is_empty() {
-            suffix_parts.push(boards_str);
-        }
-        if !star.is_empty() {
-            suffix_parts.push(star);
+    /// Time logged in focus sessions, if any
+    fn get_tracked_time(&self, item: &StorageItem) -> String {
+        let minutes = item.as_task().map_or(0, |task| task.focused_seconds() / 60);
+        if minutes == 0 {
+            return String::new();
         }
-        let suffix = suffix_parts.join(" ");
-
-        let icon = self.get_item_icon(item);
-        println!("{} {} {} {}", prefix, icon, message, suffix);
+        self.muted(&format!(
+            "{} {}h{:02}m",
+            self.icons.timer,
+            minutes / 60,
+            minutes % 60
+        ))
+        .to_string()
     }
 
     fn get_item_icon(&self, item: &StorageItem) -> String {
         if let Some(task) = item.as_task() {
             if task.is_complete {
-                self.success("✔").to_string()
+                self.success(&self.icons.complete).to_string()
             } else if task.in_progress {
-                self.warning("…").to_string()
+                self.warning(&self.icons.in_progress).to_string()
             } else {
-                self.pending("☐").to_string()
+                self.pending(&self.icons.pending).to_string()
             }
         } else {
-            self.info("●").to_string()
+            self.info(&self.icons.note).to_string()
         }
     }
 
     pub fn display_by_board(&self, data: &HashMap<String, Vec<&StorageItem>>) {
+        self.start_paging();
         let mut boards: Vec<_> = data.keys().collect();
         boards.sort();
 
@@ -284,7 +588,7 @@ impl Render {
             }
 
             let display = board::display_name(board_key);
-            self.display_title(&display, items);
+            self.display_title(&display, None, &self.get_correlation(items));
 
             for item in items {
                 if item.is_task() {
@@ -299,10 +603,17 @@ impl Render {
         }
     }
 
-    pub fn display_by_date(&self, data: &HashMap<String, Vec<&StorageItem>>) {
-        // Sort dates chronologically (most recent first based on actual date parsing)
+    /// Display items grouped by the start of their `group` period
+    pub fn display_by_date(
+        &self,
+        data: &HashMap<NaiveDate, Vec<&StorageItem>>,
+        group: TimelineGroup,
+    ) {
+        self.start_paging();
+        // Most recent first
         let mut dates: Vec<_> = data.keys().collect();
         dates.sort_by(|a, b| b.cmp(a));
+        let current = group.start_of(chrono::Local::now().date_naive());
 
         for date in dates {
             let items = &data[date];
@@ -311,7 +622,14 @@ impl Render {
                 continue;
             }
 
-            self.display_title(date, items);
+            let title = self.config.format_period(group, *date);
+            let marker = (*date == current).then(|| group.current_label());
+            let correlation = if group == TimelineGroup::Day {
+                self.get_correlation(items)
+            } else {
+                self.get_period_correlation(items)
+            };
+            self.display_title(&title, marker, &correlation);
 
             for item in items {
                 if item.is_task() {
@@ -326,6 +644,323 @@ impl Render {
         }
     }
 
+    pub fn display_agenda(&self, sections: &[(AgendaSection, Vec<&StorageItem>)]) {
+        if sections.is_empty() {
+            out!(
+                self,
+                "\n {} Nothing overdue, due today, in progress, or starred",
+                self.success(&self.icons.success)
+            );
+            return;
+        }
+
+        for (section, items) in sections {
+            let count = self.muted(&format!("[{}]", items.len()));
+            out!(self, "\n {} {}", section.title().underline(), count);
+            for item in items {
+                self.display_item_by_date(item);
+            }
+        }
+    }
+
+    pub fn display_burndown(&self, board_name: Option<&str>, days: &[BurndownDay]) {
+        let (Some(first), Some(last)) = (days.first(), days.last()) else {
+            return;
+        };
+        let title = board_name
+            .map(board::display_name)
+            .unwrap_or_else(|| "All boards".to_string());
+        out!(
+            self,
+            "\n {} {}",
+            title.underline(),
+            self.muted(&format!(
+                "{} – {}",
+                first.date.format("%a %b %d"),
+                last.date.format("%a %b %d")
+            ))
+        );
+
+        let remaining: Vec<usize> = days.iter().map(|d| d.remaining).collect();
+        let completed: Vec<usize> = days.iter().map(|d| d.completed).collect();
+        let total_completed: usize = completed.iter().sum();
+
+        out!(
+            self,
+            "   {}  {}  {}",
+            self.muted("open"),
+            self.warning(&burndown::sparkline(&remaining)),
+            self.muted(&format!("{} → {}", first.remaining, last.remaining))
+        );
+        out!(
+            self,
+            "   {}  {}  {}",
+            self.muted("done"),
+            self.success(&burndown::sparkline(&completed)),
+            self.muted(&format!(
+                "{} total · {:.1}/day",
+                total_completed,
+                total_completed as f64 / days.len() as f64
+            ))
+        );
+
+        let trend = if last.remaining == 0 {
+            self.success(&format!("{} All tasks done", self.icons.success))
+                .to_string()
+        } else if last.remaining < first.remaining {
+            self.success("Trending toward done").to_string()
+        } else if last.remaining > first.remaining {
+            self.error("Open tasks are growing").to_string()
+        } else {
+            self.muted("No change in open tasks").to_string()
+        };
+        out!(self, "\n   {}\n", trend);
+    }
+
+    /// An item's activity log, newest first, then the versions kept by the
+    /// sync server (`None` for local storage)
+    pub fn display_history(
+        &self,
+        id: u64,
+        activity: &[Activity],
+        versions: Option<&[HistoryEntry]>,
+    ) {
+        out!(self, "\n {}", format!("History of item {id}").underline());
+        let now = Local::now();
+
+        for entry in activity.iter().rev() {
+            let at = Local
+                .timestamp_millis_opt(entry.at)
+                .single()
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            out!(
+                self,
+                "   {}  {}  {}",
+                at,
+                self.info(&entry.action),
+                self.muted(&relative_time::format(entry.at, now))
+            );
+        }
+        if activity.is_empty() {
+            out!(self, "   {}", self.muted("No activity recorded yet."));
+        }
+
+        let Some(versions) = versions else {
+            out!(self);
+            return;
+        };
+        out!(
+            self,
+            "\n {}",
+            "Versions kept by the sync server".underline()
+        );
+
+        for (index, version) in versions.iter().enumerate() {
+            let saved = Local
+                .timestamp_millis_opt(version.saved_at)
+                .single()
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            let mut header = format!(
+                "   {}  {}",
+                saved,
+                self.muted(&relative_time::format(version.saved_at, now))
+            );
+            if version.current {
+                header.push_str(&format!("  {}", self.success("current")));
+            }
+            if version.archived {
+                header.push_str(&format!("  {}", self.warning("archived")));
+            }
+            out!(self, "{}", header);
+
+            let item = &version.item;
+            match versions.get(index + 1) {
+                Some(older) => {
+                    if older.item.description() != item.description() {
+                        out!(
+                            self,
+                            "     {}",
+                            self.error(&format!("- {}", older.item.description()))
+                        );
+                        out!(
+                            self,
+                            "     {}",
+                            self.success(&format!("+ {}", item.description()))
+                        );
+                    }
+                    let changes = history::changes(&older.item, item);
+                    if !changes.is_empty() {
+                        out!(self, "     {}", self.info(&changes.join(" · ")));
+                    }
+                }
+                None => out!(self, "     {}", item.description()),
+            }
+        }
+
+        if versions.len() <= 1 {
+            out!(self, "\n   {}", self.muted("No earlier versions kept."));
+        }
+        out!(self);
+    }
+
+    pub fn display_comments(&self, id: u64, comments: &[Comment]) {
+        out!(self, "\n {}", format!("Comments on task {id}").underline());
+        let now = Local::now();
+        for comment in comments {
+            out!(
+                self,
+                "   {}  {}",
+                self.info(&comment.author),
+                self.muted(&relative_time::format(comment.timestamp, now))
+            );
+            for line in comment.text.lines() {
+                out!(self, "     {}", line);
+            }
+        }
+        if comments.is_empty() {
+            out!(
+                self,
+                "   {}",
+                self.muted(&format!(
+                    "No comments yet. Add one with tb --comment @{id} <text>."
+                ))
+            );
+        }
+        out!(self);
+    }
+
+    pub fn display_attachments(&self, id: u64, attachments: &[String]) {
+        out!(
+            self,
+            "\n {}",
+            format!("Attachments of item {id}").underline()
+        );
+        for (i, attachment) in attachments.iter().enumerate() {
+            out!(
+                self,
+                "   {} {}",
+                self.muted(&format!("{}.", i + 1)),
+                attachment
+            );
+        }
+        if attachments.is_empty() {
+            out!(
+                self,
+                "   {}",
+                self.muted(&format!(
+                    "No attachments yet. Add one with tb --attach @{id} <path|url>."
+                ))
+            );
+        }
+        out!(self);
+    }
+
+    pub fn display_commits(&self, id: u64, commits: &[LinkedCommit]) {
+        out!(self, "\n {}", format!("Commits for task {id}").underline());
+        let now = Local::now();
+        for commit in commits {
+            let short: String = commit.hash.chars().take(7).collect();
+            out!(
+                self,
+                "   {}  {}  {}",
+                self.info(&short),
+                commit.summary,
+                self.muted(&relative_time::format(commit.committed_at, now))
+            );
+        }
+        if commits.is_empty() {
+            out!(
+                self,
+                "   {}",
+                self.muted(&format!(
+                    "No commits yet. Mention tb:#{id} in a commit message with the git hook installed."
+                ))
+            );
+        }
+        out!(self);
+    }
+
+    pub fn display_report(&self, report: &Report) {
+        let period = match report.period {
+            ReportPeriod::Week => "Weekly report",
+            ReportPeriod::Month => "Monthly report",
+        };
+        out!(
+            self,
+            "\n {} {}",
+            period.underline(),
+            self.muted(&format!(
+                "{} – {}",
+                report.start.format("%a %b %d"),
+                report.end.format("%a %b %d %Y")
+            ))
+        );
+        out!(
+            self,
+            "   {} {}   {} {}",
+            self.info(&report.created.to_string()),
+            self.muted("created"),
+            self.success(&report.completed.to_string()),
+            self.muted("completed")
+        );
+
+        if let Some(ref times) = report.completion_hours {
+            out!(
+                self,
+                "   {} {} {} {} {} {}",
+                self.muted("time to complete: median"),
+                format_hours(times.p50),
+                self.muted("· p90"),
+                format_hours(times.p90),
+                self.muted("· max"),
+                format_hours(times.max)
+            );
+        }
+
+        if !report.busiest_boards.is_empty() {
+            out!(self, "\n {}", "Busiest boards".underline());
+            for activity in &report.busiest_boards {
+                out!(
+                    self,
+                    "   {} {}",
+                    activity.board,
+                    self.muted(&format!(
+                        "{} created · {} completed",
+                        activity.created, activity.completed
+                    ))
+                );
+            }
+        }
+
+        out!(
+            self,
+            "\n {} {}",
+            "Outstanding high priority".underline(),
+            self.muted(&format!("[{}]", report.high_priority.len()))
+        );
+        if report.high_priority.is_empty() {
+            out!(self, "   {} None", self.success(&self.icons.success));
+        }
+        for item in &report.high_priority {
+            let age = if item.age_days > 0 {
+                format!(" {}d", item.age_days)
+            } else {
+                String::new()
+            };
+            out!(
+                self,
+                "   {} {} {}{}",
+                self.muted(&format!("{}.", item.id)),
+                self.error(&item.description),
+                self.muted(&item.boards.join(" ")),
+                self.muted(&age)
+            );
+        }
+        out!(self);
+    }
+
     pub fn display_stats(&self, stats: &Stats) {
         if !self.config.display_progress_overview {
             return;
@@ -360,50 +995,59 @@ impl Render {
         );
 
         if stats.pending + stats.in_progress + stats.complete + stats.notes == 0 {
-            println!("\n  Type `tb --help` to get started");
+            out!(self, "\n  Type `tb --help` to get started");
         }
 
-        println!(
+        out!(
+            self,
             "\n  {}",
             self.muted(&format!("{} of all tasks complete.", percent_str))
         );
-        println!("  {} {}\n", status, notes_status);
+        out!(self, "  {} {}\n", status, notes_status);
     }
 
     #[allow(dead_code)]
     pub fn invalid_custom_app_dir(&self, path: &str) {
-        eprintln!(
+        err!(
+            self,
             "\n {} Custom app directory was not found on your system: {}",
-            self.error("✖"),
+            self.error(&self.icons.error),
             self.error(path)
         );
     }
 
     #[allow(dead_code)]
     pub fn missing_taskbook_dir_flag_value(&self) {
-        eprintln!(
+        err!(
+            self,
             "\n  {} Please provide a value for --taskbook-dir or remove the flag.",
-            self.error("✖")
+            self.error(&self.icons.error)
         );
     }
 
     pub fn invalid_id(&self, id: u64) {
-        eprintln!(
+        err!(
+            self,
             "\n {} Unable to find item with id: {}",
-            self.error("✖"),
+            self.error(&self.icons.error),
             self.muted(&id.to_string())
         );
     }
 
     pub fn invalid_ids_number(&self) {
-        eprintln!(
+        err!(
+            self,
             "\n {} More than one ids were given as input",
-            self.error("✖")
+            self.error(&self.icons.error)
         );
     }
 
     pub fn invalid_priority(&self) {
-        eprintln!("\n {} Priority can only be 1, 2 or 3", self.error("✖"));
+        err!(
+            self,
+            "\n {} Priority can only be 1, 2, 3 or normal, medium, high",
+            self.error(&self.icons.error)
+        );
     }
 
     /// Format IDs as comma-separated string
@@ -420,9 +1064,10 @@ impl Render {
             return;
         }
         let word = if ids.len() > 1 { plural } else { singular };
-        println!(
+        out!(
+            self,
             "\n {} {} {}: {}",
-            self.success("✔"),
+            self.success(&self.icons.success),
             action,
             word,
             self.muted(&self.format_ids(ids))
@@ -454,31 +1099,144 @@ impl Render {
     }
 
     pub fn missing_boards(&self) {
-        eprintln!("\n {} No boards were given as input", self.error("✖"));
+        err!(
+            self,
+            "\n {} No boards were given as input",
+            self.error(&self.icons.error)
+        );
+    }
+
+    pub fn hook_failed(&self, message: &str) {
+        err!(self, "\n {} {}", self.warning(&self.icons.warning), message);
+    }
+
+    pub fn missing_board(&self, name: &str) {
+        err!(
+            self,
+            "\n {} No board named {} was found",
+            self.error(&self.icons.error),
+            self.muted(&board::display_name(name))
+        );
     }
 
     pub fn missing_desc(&self) {
-        eprintln!("\n {} No description was given as input", self.error("✖"));
+        err!(
+            self,
+            "\n {} No description was given as input",
+            self.error(&self.icons.error)
+        );
+    }
+
+    pub fn missing_due(&self) {
+        err!(
+            self,
+            "\n {} No due date was given as input. Use e.g. today, fri, +3, 2024-05-01 or none.",
+            self.error(&self.icons.error)
+        );
+    }
+
+    pub fn invalid_due(&self, spec: &str) {
+        err!(
+            self,
+            "\n {} Unable to understand due date: {}",
+            self.error(&self.icons.error),
+            self.muted(spec)
+        );
+    }
+
+    pub fn success_due(&self, id: u64, due: Option<chrono::NaiveDate>) {
+        match due {
+            Some(date) => out!(
+                self,
+                "\n {} Set due date of task {} to {}",
+                self.success(&self.icons.success),
+                self.muted(&id.to_string()),
+                self.info(&self.config.format_date(date))
+            ),
+            None => out!(
+                self,
+                "\n {} Cleared due date of task: {}",
+                self.success(&self.icons.success),
+                self.muted(&id.to_string())
+            ),
+        }
     }
 
     pub fn missing_id(&self) {
-        eprintln!("\n {} No id was given as input", self.error("✖"));
+        err!(
+            self,
+            "\n {} No id was given as input",
+            self.error(&self.icons.error)
+        );
     }
 
     pub fn success_create(&self, id: u64, is_task: bool) {
         let item_type = if is_task { "task:" } else { "note:" };
-        println!(
+        out!(
+            self,
             "\n {} Created {} {}",
-            self.success("✔"),
+            self.success(&self.icons.success),
             item_type,
             self.muted(&id.to_string())
         );
     }
 
+    pub fn success_comment(&self, id: u64) {
+        out!(
+            self,
+            "\n {} Commented on task: {}",
+            self.success(&self.icons.success),
+            self.muted(&id.to_string())
+        );
+    }
+
+    pub fn success_attach(&self, id: u64, attachments: &[String]) {
+        if attachments.is_empty() {
+            out!(
+                self,
+                "\n {} Already attached to item: {}",
+                self.success(&self.icons.success),
+                self.muted(&id.to_string())
+            );
+            return;
+        }
+        for attachment in attachments {
+            out!(
+                self,
+                "\n {} Attached {} to item: {}",
+                self.success(&self.icons.success),
+                attachments::display_name(attachment),
+                self.muted(&id.to_string())
+            );
+        }
+    }
+
+    pub fn success_open(&self, target: &str) {
+        out!(
+            self,
+            "\n {} Opened {}",
+            self.success(&self.icons.success),
+            attachments::display_name(target)
+        );
+    }
+
+    pub fn success_detach(&self, id: u64, attachments: &[String]) {
+        for attachment in attachments {
+            out!(
+                self,
+                "\n {} Removed {} from item: {}",
+                self.success(&self.icons.success),
+                attachments::display_name(attachment),
+                self.muted(&id.to_string())
+            );
+        }
+    }
+
     pub fn success_edit(&self, id: u64) {
-        println!(
+        out!(
+            self,
             "\n {} Updated description of item: {}",
-            self.success("✔"),
+            self.success(&self.icons.success),
             self.muted(&id.to_string())
         );
     }
@@ -487,25 +1245,29 @@ impl Render {
         self.print_mark_message(ids, "Deleted", "item", "items");
     }
 
-    pub fn success_move(&self, id: u64, boards: &[String]) {
+    pub fn success_move(&self, ids: &[u64], boards: &[String]) {
         let boards_str = boards.join(", ");
-        println!(
-            "\n {} Move item: {} to {}",
-            self.success("✔"),
-            self.muted(&id.to_string()),
+        out!(
+            self,
+            "\n {} Move {}: {} to {}",
+            self.success(&self.icons.success),
+            if ids.len() == 1 { "item" } else { "items" },
+            self.muted(&self.format_ids(ids)),
             self.muted(&boards_str)
         );
     }
 
     pub fn success_priority(&self, id: u64, level: u8) {
+        let name = priority::priority_name(level);
         let level_str = match level {
-            3 => self.error("high").to_string(),
-            2 => self.warning("medium").to_string(),
-            _ => self.success("normal").to_string(),
+            priority::HIGH => self.error(name).to_string(),
+            priority::MEDIUM => self.warning(name).to_string(),
+            _ => self.success(name).to_string(),
         };
-        println!(
+        out!(
+            self,
             "\n {} Updated priority of task: {} to {}",
-            self.success("✔"),
+            self.success(&self.icons.success),
             self.muted(&id.to_string()),
             level_str
         );
@@ -523,21 +1285,80 @@ impl Render {
         if ids.is_empty() {
             return;
         }
-        println!(
+        out!(
+            self,
             "\n {} Deleted all checked items: {}",
-            self.success("✔"),
+            self.success(&self.icons.success),
             self.muted(&self.format_ids(ids))
         );
     }
 
+    /// What `--clear --dry-run` would delete, below the listing of it
+    pub fn clear_preview(&self, ids: &[u64]) {
+        if ids.is_empty() {
+            out!(self, "\n  {}", self.muted("No checked items to delete."));
+            return;
+        }
+        out!(
+            self,
+            "\n  Would delete {} checked item(s): {}",
+            ids.len(),
+            self.muted(&self.format_ids(ids))
+        );
+    }
+
+    pub fn success_save_filter(&self, name: &str, terms: &[String]) {
+        out!(
+            self,
+            "\n {} Saved filter {}: {}",
+            self.success(&self.icons.success),
+            self.info(name),
+            self.muted(&terms.join(" "))
+        );
+    }
+
+    pub fn success_board_archived(&self, name: &str, archived: bool, changed: bool) {
+        let display = board::display_name(name);
+        let message = match (archived, changed) {
+            (true, true) => format!("Archived board {}", self.info(&display)),
+            (false, true) => format!("Unarchived board {}", self.info(&display)),
+            (true, false) => format!("Board {} is already archived", self.info(&display)),
+            (false, false) => format!("Board {} is not archived", self.info(&display)),
+        };
+        out!(self, "\n {} {}", self.success(&self.icons.success), message);
+    }
+
+    /// One line per board: name, item count and whether it is archived
+    pub fn display_board_list(&self, boards: &[(String, usize, bool)]) {
+        out!(self);
+        for (name, count, archived) in boards {
+            let items = if *count == 1 { "item" } else { "items" };
+            let line = format!(
+                "  {} {}",
+                board::display_name(name),
+                self.muted(&format!("{count} {items}"))
+            );
+            if *archived {
+                out!(self, "{} {}", line, self.muted("[archived]"));
+            } else {
+                out!(self, "{}", line);
+            }
+        }
+    }
+
+    pub fn edit_cancelled(&self) {
+        out!(self, "\n {} Edit cancelled", self.muted("○"));
+    }
+
     pub fn note_cancelled(&self) {
-        println!("\n {} Note creation cancelled", self.muted("○"));
+        out!(self, "\n {} Note creation cancelled", self.muted("○"));
     }
 
     pub fn missing_tags(&self) {
-        eprintln!(
+        err!(
+            self,
             "\n {} No tags were given as input. Use +tag to add or -tag to remove.",
-            self.error("✖")
+            self.error(&self.icons.error)
         );
     }
 
@@ -548,9 +1369,10 @@ impl Render {
                 .map(|t| format!("+{}", t))
                 .collect::<Vec<_>>()
                 .join(", ");
-            println!(
+            out!(
+                self,
                 "\n {} Added tags {} to item: {}",
-                self.success("✔"),
+                self.success(&self.icons.success),
                 self.info(&tags_str),
                 self.muted(&id.to_string())
             );
@@ -561,12 +1383,33 @@ impl Render {
                 .map(|t| format!("-{}", t))
                 .collect::<Vec<_>>()
                 .join(", ");
-            println!(
+            out!(
+                self,
                 "\n {} Removed tags {} from item: {}",
-                self.success("✔"),
+                self.success(&self.icons.success),
                 self.warning(&tags_str),
                 self.muted(&id.to_string())
             );
         }
     }
 }
+
+/// Format a duration in hours as `45m`, `5.2h`, or `3.1d`
+fn format_hours(hours: f64) -> String {
+    if hours < 1.0 {
+        format!("{}m", (hours * 60.0).round() as u64)
+    } else if hours < 48.0 {
+        format!("{:.1}h", hours)
+    } else {
+        format!("{:.1}d", hours / 24.0)
+    }
+}
+
+impl Drop for Render {
+    /// Show output held back for the pager once the command is done
+    fn drop(&mut self) {
+        if let Some((command, buffer)) = self.paged.get_mut().take() {
+            pager::show(&buffer, &command);
+        }
+    }
+}
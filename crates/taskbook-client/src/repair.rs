@@ -0,0 +1,210 @@
+//! Salvage and resequencing for `tb --repair`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json::Value;
+use taskbook_common::StorageItem;
+
+/// What a repair pass changed in one storage file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RepairStats {
+    /// Items written back
+    pub kept: usize,
+    /// Entries that parsed as JSON but not as an item
+    pub skipped: usize,
+    /// Copies of an item (same uuid) that were dropped
+    pub duplicates: usize,
+    /// Items whose id changed
+    pub reassigned: usize,
+    /// The file was not valid JSON and items were recovered by scanning it
+    pub salvaged: bool,
+}
+
+/// Rebuild items from raw storage file content.
+///
+/// Keys are rewritten to match each item's id. Items sharing an id keep it
+/// in creation order and later ones move to the next free id; with
+/// `renumber`, ids are also compacted to `1..=n`.
+pub fn repair(content: &str, renumber: bool) -> (HashMap<String, StorageItem>, RepairStats) {
+    let mut stats = RepairStats::default();
+    let items = salvage(content, &mut stats);
+    let mut items = dedup_by_uuid(items, &mut stats);
+
+    items.sort_by(|a, b| {
+        a.id()
+            .cmp(&b.id())
+            .then_with(|| a.timestamp().cmp(&b.timestamp()))
+    });
+
+    if renumber {
+        for (index, item) in items.iter_mut().enumerate() {
+            let id = index as u64 + 1;
+            if item.id() != id {
+                item.set_id(id);
+                stats.reassigned += 1;
+            }
+        }
+    } else {
+        let mut used = HashSet::new();
+        let mut conflicting = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            if item.id() == 0 || !used.insert(item.id()) {
+                conflicting.push(index);
+            }
+        }
+        let mut next = used.iter().max().copied().unwrap_or(0);
+        for index in conflicting {
+            next += 1;
+            items[index].set_id(next);
+            stats.reassigned += 1;
+        }
+    }
+
+    stats.kept = items.len();
+    let items = items
+        .into_iter()
+        .map(|item| (item.id().to_string(), item))
+        .collect();
+    (items, stats)
+}
+
+/// Parse every item that still reads, keeping entries with duplicate keys
+fn salvage(content: &str, stats: &mut RepairStats) -> Vec<StorageItem> {
+    if content.trim().is_empty() {
+        return Vec::new();
+    }
+
+    if let Ok(Entries(entries)) = serde_json::from_str::<Entries>(content) {
+        return entries
+            .into_iter()
+            .filter_map(|(_, value)| match serde_json::from_value(value) {
+                Ok(item) => Some(item),
+                Err(_) => {
+                    stats.skipped += 1;
+                    None
+                }
+            })
+            .collect();
+    }
+
+    // Not valid JSON (e.g. a truncated write): pick out each object that
+    // parses as an item, resuming after it.
+    stats.salvaged = true;
+    let mut items = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = content.get(pos..).and_then(|rest| rest.find('{')) {
+        let start = pos + offset;
+        let mut stream = serde_json::Deserializer::from_str(&content[start..]).into_iter::<Value>();
+        match stream.next() {
+            Some(Ok(value)) => match serde_json::from_value::<StorageItem>(value) {
+                Ok(item) => {
+                    items.push(item);
+                    pos = start + stream.byte_offset();
+                }
+                Err(_) => pos = start + 1,
+            },
+            _ => pos = start + 1,
+        }
+    }
+    items
+}
+
+/// Keep the last copy of each item, as a JSON reader would for duplicate keys
+fn dedup_by_uuid(items: Vec<StorageItem>, stats: &mut RepairStats) -> Vec<StorageItem> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut unique: Vec<StorageItem> = Vec::new();
+    for item in items {
+        match seen.get(item.uuid()) {
+            Some(&index) => {
+                unique[index] = item;
+                stats.duplicates += 1;
+            }
+            None => {
+                seen.insert(item.uuid().to_string(), unique.len());
+                unique.push(item);
+            }
+        }
+    }
+    unique
+}
+
+/// A JSON object read as a list of entries, so duplicate keys survive
+struct Entries(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a JSON object of items")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taskbook_common::Task;
+
+    fn task_json(id: u64, uuid: &str, description: &str) -> String {
+        let mut task = Task::new(id, description.to_string(), vec!["work".to_string()], 1);
+        task.uuid = uuid.to_string();
+        serde_json::to_string(&StorageItem::Task(task)).unwrap()
+    }
+
+    #[test]
+    fn repair_removes_duplicates_and_reassigns_clashing_ids() {
+        let content = format!(
+            r#"{{"1": {}, "1": {}, "2": {}, "5": {}, "9": {{"bogus": true}}}}"#,
+            task_json(1, "a", "Old copy"),
+            task_json(1, "a", "New copy"),
+            task_json(1, "b", "Clash"),
+            task_json(5, "c", "Five"),
+        );
+
+        let (items, stats) = repair(&content, false);
+
+        assert_eq!(stats.duplicates, 1);
+        assert_eq!(stats.skipped, 1);
+        assert_eq!(stats.reassigned, 1);
+        assert_eq!(stats.kept, 3);
+        assert!(!stats.salvaged);
+        assert_eq!(items["1"].description(), "New copy");
+        assert_eq!(items["5"].description(), "Five");
+        assert_eq!(items["6"].description(), "Clash");
+        assert_eq!(items["6"].id(), 6);
+    }
+
+    #[test]
+    fn repair_salvages_truncated_files_and_renumbers() {
+        let content = format!(
+            r#"{{"3": {}, "7": {}, "8": {{"_id": 8, "descri"#,
+            task_json(3, "a", "Three"),
+            task_json(7, "b", "Seven"),
+        );
+
+        let (items, stats) = repair(&content, true);
+
+        assert!(stats.salvaged);
+        assert_eq!(stats.kept, 2);
+        assert_eq!(stats.reassigned, 2);
+        assert_eq!(items["1"].description(), "Three");
+        assert_eq!(items["2"].description(), "Seven");
+    }
+}
@@ -0,0 +1,232 @@
+//! Productivity reports for `tb --report week|month`.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Local, NaiveDate, TimeZone};
+use serde::Serialize;
+use taskbook_common::{board, StorageItem};
+
+/// Number of boards listed under "busiest boards"
+const BUSIEST_BOARDS: usize = 5;
+
+/// Reporting window, ending today
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportPeriod {
+    Week,
+    Month,
+}
+
+impl ReportPeriod {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "week" => Some(ReportPeriod::Week),
+            "month" => Some(ReportPeriod::Month),
+            _ => None,
+        }
+    }
+
+    fn days(self) -> i64 {
+        match self {
+            ReportPeriod::Week => 7,
+            ReportPeriod::Month => 30,
+        }
+    }
+}
+
+/// Hours from creation to completion for tasks completed in the period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompletionTimes {
+    pub p50: f64,
+    pub p90: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BoardActivity {
+    pub board: String,
+    pub created: usize,
+    pub completed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutstandingItem {
+    pub id: u64,
+    pub description: String,
+    pub boards: Vec<String>,
+    #[serde(rename = "ageDays")]
+    pub age_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub period: ReportPeriod,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub created: usize,
+    pub completed: usize,
+    #[serde(rename = "completionHours")]
+    pub completion_hours: Option<CompletionTimes>,
+    #[serde(rename = "busiestBoards")]
+    pub busiest_boards: Vec<BoardActivity>,
+    #[serde(rename = "highPriority")]
+    pub high_priority: Vec<OutstandingItem>,
+}
+
+impl Report {
+    /// Build a report over `items` (active and archived) for the period
+    /// ending on `today`. Outstanding high-priority tasks come from `active`
+    /// only, since archived items are no longer on any board.
+    pub fn build<'a>(
+        period: ReportPeriod,
+        today: NaiveDate,
+        items: impl IntoIterator<Item = &'a StorageItem>,
+        active: impl IntoIterator<Item = &'a StorageItem>,
+    ) -> Self {
+        let start = today - Duration::days(period.days() - 1);
+        let start_ms = Local
+            .from_local_datetime(&start.and_hms_opt(0, 0, 0).unwrap_or_default())
+            .earliest()
+            .map(|dt| dt.timestamp_millis())
+            .unwrap_or(0);
+        let now_ms = Local::now().timestamp_millis();
+
+        let mut created = 0;
+        let mut completed = 0;
+        let mut durations: Vec<f64> = Vec::new();
+        let mut boards: HashMap<String, BoardActivity> = HashMap::new();
+
+        for item in items {
+            let was_created = item.timestamp() >= start_ms;
+            let completed_at = item
+                .as_task()
+                .filter(|t| t.is_complete)
+                .and_then(|t| t.completed_at)
+                .filter(|at| *at >= start_ms);
+
+            if !was_created && completed_at.is_none() {
+                continue;
+            }
+            if was_created {
+                created += 1;
+            }
+            if let Some(at) = completed_at {
+                completed += 1;
+                durations.push((at - item.timestamp()).max(0) as f64 / 3_600_000.0);
+            }
+
+            for name in item.boards() {
+                let entry = boards
+                    .entry(board::normalize_board_name(name))
+                    .or_insert_with(|| BoardActivity {
+                        board: board::display_name(name),
+                        created: 0,
+                        completed: 0,
+                    });
+                if was_created {
+                    entry.created += 1;
+                }
+                if completed_at.is_some() {
+                    entry.completed += 1;
+                }
+            }
+        }
+
+        let mut busiest_boards: Vec<BoardActivity> = boards.into_values().collect();
+        busiest_boards.sort_by(|a, b| {
+            (b.created + b.completed)
+                .cmp(&(a.created + a.completed))
+                .then_with(|| a.board.cmp(&b.board))
+        });
+        busiest_boards.truncate(BUSIEST_BOARDS);
+
+        let mut high_priority: Vec<OutstandingItem> = active
+            .into_iter()
+            .filter_map(|item| {
+                let task = item.as_task()?;
+                (task.priority == 3 && !task.is_complete).then(|| OutstandingItem {
+                    id: task.id,
+                    description: task.description.clone(),
+                    boards: task.boards.iter().map(|b| board::display_name(b)).collect(),
+                    age_days: (now_ms - task.timestamp).max(0) / 86_400_000,
+                })
+            })
+            .collect();
+        high_priority.sort_by_key(|item| item.id);
+
+        Self {
+            period,
+            start,
+            end: today,
+            created,
+            completed,
+            completion_hours: completion_times(&mut durations),
+            busiest_boards,
+            high_priority,
+        }
+    }
+}
+
+/// Nearest-rank percentiles of the given durations
+fn completion_times(durations: &mut [f64]) -> Option<CompletionTimes> {
+    if durations.is_empty() {
+        return None;
+    }
+    durations.sort_by(|a, b| a.total_cmp(b));
+    let rank = |p: f64| {
+        let index = (p * durations.len() as f64).ceil() as usize;
+        durations[index.clamp(1, durations.len()) - 1]
+    };
+    Some(CompletionTimes {
+        p50: rank(0.5),
+        p90: rank(0.9),
+        max: durations[durations.len() - 1],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taskbook_common::Task;
+
+    fn task(id: u64, board: &str, age_hours: i64) -> Task {
+        let mut task = Task::new(id, format!("Task {}", id), vec![board.to_string()], 1);
+        task.timestamp = Local::now().timestamp_millis() - age_hours * 3_600_000;
+        task
+    }
+
+    #[test]
+    fn completion_times_use_nearest_rank() {
+        let mut durations = vec![4.0, 1.0, 3.0, 2.0];
+        let times = completion_times(&mut durations).unwrap();
+        assert_eq!(times.p50, 2.0);
+        assert_eq!(times.p90, 4.0);
+        assert_eq!(times.max, 4.0);
+        assert_eq!(completion_times(&mut []), None);
+    }
+
+    #[test]
+    fn report_counts_created_and_completed_in_period() {
+        let today = Local::now().date_naive();
+
+        let mut done = task(1, "work", 2);
+        done.set_complete(true);
+        let mut urgent = task(2, "work", 1);
+        urgent.priority = 3;
+        let old = task(3, "home", 24 * 60);
+
+        let items: Vec<StorageItem> = [done, urgent, old]
+            .into_iter()
+            .map(StorageItem::Task)
+            .collect();
+        let report = Report::build(ReportPeriod::Week, today, &items, &items);
+
+        assert_eq!(report.created, 2);
+        assert_eq!(report.completed, 1);
+        assert!(report.completion_hours.is_some());
+        assert_eq!(report.busiest_boards.len(), 1);
+        assert_eq!(report.busiest_boards[0].created, 2);
+        assert_eq!(report.high_priority.len(), 1);
+        assert_eq!(report.high_priority[0].id, 2);
+    }
+}
@@ -0,0 +1,54 @@
+//! `tb serve`: a sync server for one's own devices, without deploying
+//! taskbook-server and Postgres.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use colored::Colorize;
+
+use taskbook_server::embedded::DEFAULT_LISTEN;
+
+use crate::error::{Result, TaskbookError};
+use crate::paths;
+
+const DATABASE_FILE: &str = "server.db";
+
+/// Serve sync on `listen` from `database` until interrupted
+pub fn serve(listen: Option<&str>, database: Option<PathBuf>) -> Result<()> {
+    let listen = listen.unwrap_or(DEFAULT_LISTEN);
+    let addr: SocketAddr = listen.parse().map_err(|_| {
+        TaskbookError::General(format!(
+            "invalid address {listen:?}; use host:port, e.g. {DEFAULT_LISTEN}"
+        ))
+    })?;
+    let database = match database {
+        Some(path) => path,
+        None => paths::data_dir()?.join(DATABASE_FILE),
+    };
+    if let Some(dir) = database.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let host = if addr.ip().is_unspecified() {
+        "<this machine's address>".to_string()
+    } else {
+        addr.ip().to_string()
+    };
+    println!(
+        "{}",
+        format!("Serving taskbook sync on {addr}").green().bold()
+    );
+    println!("{}", format!("Database: {}", database.display()).dimmed());
+    println!(
+        "{}",
+        format!(
+            "The first account registered is the only one: \
+             tb sync register --server http://{host}:{}",
+            addr.port()
+        )
+        .dimmed()
+    );
+    println!("{}", "Press Ctrl+C to stop.".dimmed());
+
+    taskbook_server::embedded::serve(&database, addr).map_err(TaskbookError::General)
+}
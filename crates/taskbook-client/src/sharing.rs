@@ -0,0 +1,382 @@
+//! Sharing boards with other accounts on the same server.
+//!
+//! Each shared board has its own key, which is wrapped for every member with
+//! the X25519 identity derived from their encryption key. Sharing a board
+//! moves its items from the owner's items onto the board; stopping sharing
+//! moves them back.
+//!
+//! Share links are for people without an account: a snapshot of a board,
+//! encrypted with a one-off key that is only part of the link's URL.
+
+use base64::Engine;
+use chrono::{DateTime, Duration, Local};
+use colored::Colorize;
+use serde::Serialize;
+use taskbook_common::board::{board_eq, display_name, normalize_board_name};
+use taskbook_common::encryption::{
+    encrypt_json, generate_key, identity_public_key, unwrap_board_key, wrap_board_key, BoardKey,
+};
+use taskbook_common::StorageItem;
+
+use crate::api_client::{ApiClient, EncryptedItemData, RetryPolicy, SharedBoard};
+use crate::config::Config;
+use crate::credentials::Credentials;
+use crate::error::{Result, TaskbookError};
+use crate::storage::{RemoteStorage, StorageBackend};
+
+/// How long a share link lasts unless `--expires` says otherwise
+const DEFAULT_LINK_EXPIRY: &str = "7d";
+
+/// What a share link shows, as read by the page the server serves for it
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BoardSnapshot<'a> {
+    board: String,
+    /// ms since epoch
+    generated_at: i64,
+    items: Vec<&'a StorageItem>,
+}
+
+/// A logged-in session with sync enabled
+struct Session {
+    server_url: String,
+    client: ApiClient,
+    key: [u8; 32],
+    retry: RetryPolicy,
+}
+
+impl Session {
+    fn open() -> Result<Self> {
+        let config = Config::load_or_default();
+        if !config.sync.enabled {
+            return Err(TaskbookError::General(
+                "sharing boards needs sync — run `tb --login` first".to_string(),
+            ));
+        }
+        let creds = Credentials::load()?.ok_or_else(|| {
+            TaskbookError::Auth("not logged in — run `tb register` or `tb login` first".to_string())
+        })?;
+        let retry = RetryPolicy::from_config(&config.sync);
+        Ok(Self {
+            client: ApiClient::from_credentials(&creds).with_retry_policy(retry),
+            key: creds.encryption_key_bytes()?,
+            server_url: creds.server_url,
+            retry,
+        })
+    }
+
+    /// The user's items, read and written through the server
+    fn storage(&self) -> Result<RemoteStorage> {
+        RemoteStorage::new(&self.server_url, self.retry)
+    }
+
+    /// Shared boards the user is a member of, with their unwrapped keys
+    fn boards(&self) -> Result<Vec<(SharedBoard, BoardKey)>> {
+        let engine = base64::engine::general_purpose::STANDARD;
+        self.client
+            .list_boards()?
+            .into_iter()
+            .map(|board| {
+                let wrapped = engine.decode(&board.wrapped_key).map_err(|e| {
+                    TaskbookError::General(format!("invalid base64 board key: {e}"))
+                })?;
+                let key = unwrap_board_key(&self.key, &wrapped).map_err(|e| {
+                    TaskbookError::General(format!("cannot open shared board: {e}"))
+                })?;
+                Ok((board, key))
+            })
+            .collect()
+    }
+
+    /// The shared board called `name`, preferring one the user owns
+    fn find(&self, name: &str) -> Result<Option<(SharedBoard, BoardKey)>> {
+        let mut matching: Vec<_> = self
+            .boards()?
+            .into_iter()
+            .filter(|(_, key)| board_eq(&key.name, name))
+            .collect();
+        matching.sort_by_key(|(board, _)| !board.is_owner);
+        Ok(matching.into_iter().next())
+    }
+
+    /// Wrap `board` for `username`'s published identity
+    fn wrap_for(&self, username: &str, board: &BoardKey) -> Result<String> {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let public_key = engine
+            .decode(self.client.get_public_key(username)?)
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| {
+                TaskbookError::General(format!("{username} has an invalid public key"))
+            })?;
+        let wrapped = wrap_board_key(&public_key, board)
+            .map_err(|e| TaskbookError::General(format!("encryption failed: {e}")))?;
+        Ok(engine.encode(wrapped))
+    }
+}
+
+/// Publish the identity derived from `key`, so others can share with us
+pub(crate) fn publish_identity(client: &ApiClient, key: &[u8; 32]) -> Result<()> {
+    let public_key = identity_public_key(key);
+    client.put_public_key(&base64::engine::general_purpose::STANDARD.encode(public_key))
+}
+
+/// Share `board` with `username`, creating the shared board on first use.
+pub fn share(board: &str, username: &str, read_only: bool) -> Result<()> {
+    let session = Session::open()?;
+    let name = normalize_board_name(board);
+    publish_identity(&session.client, &session.key)?;
+
+    let (board_id, board_key) = match session.find(&name)? {
+        Some((shared, _)) if !shared.is_owner => {
+            return Err(TaskbookError::General(format!(
+                "{} is owned by {} — only the owner can share it",
+                display_name(&name),
+                shared.owner
+            )));
+        }
+        Some((shared, key)) => (shared.id, key),
+        None => {
+            let key = BoardKey {
+                name: name.clone(),
+                key: generate_key(),
+            };
+            let wrapped = wrap_board_key(&identity_public_key(&session.key), &key)
+                .map_err(|e| TaskbookError::General(format!("encryption failed: {e}")))?;
+            let engine = base64::engine::general_purpose::STANDARD;
+            let id = session.client.create_board(&engine.encode(wrapped))?;
+            (id, key)
+        }
+    };
+
+    let wrapped = session.wrap_for(username, &board_key)?;
+    session
+        .client
+        .share_board(&board_id, username, &wrapped, !read_only)?;
+
+    // Read everything and write it back, which moves the board's items
+    // from the owner's items onto the shared board
+    let storage = session.storage()?;
+    storage.set(&storage.get()?)?;
+
+    let access = if read_only { "read-only" } else { "read-write" };
+    println!(
+        "{}",
+        format!("Shared {} with {username} ({access}).", display_name(&name)).green()
+    );
+    Ok(())
+}
+
+/// Remove `username` from `board`. Without a username the owner stops
+/// sharing the board altogether, and any other member leaves it.
+pub fn unshare(board: &str, username: Option<&str>) -> Result<()> {
+    let session = Session::open()?;
+    let name = normalize_board_name(board);
+    let (shared, _) = session
+        .find(&name)?
+        .ok_or_else(|| TaskbookError::General(format!("{} is not shared", display_name(&name))))?;
+
+    match username {
+        Some(username) => {
+            session.client.unshare_board(&shared.id, username)?;
+            println!(
+                "{}",
+                format!("Stopped sharing {} with {username}.", display_name(&name)).green()
+            );
+        }
+        None if shared.is_owner => {
+            // Read the board's items before deleting it, then save them
+            // back as the owner's own items
+            let storage = session.storage()?;
+            let items = storage.get()?;
+            session.client.delete_board(&shared.id)?;
+            session.storage()?.set(&items)?;
+            println!(
+                "{}",
+                format!("Stopped sharing {}.", display_name(&name)).green()
+            );
+        }
+        None => {
+            let me = session.client.me()?;
+            session.client.unshare_board(&shared.id, &me.username)?;
+            println!("{}", format!("Left {}.", display_name(&name)).green());
+        }
+    }
+    Ok(())
+}
+
+/// List shared boards and their members.
+pub fn list() -> Result<()> {
+    let session = Session::open()?;
+    let boards = session.boards()?;
+
+    if boards.is_empty() {
+        println!("{}", "No shared boards.".dimmed());
+        return Ok(());
+    }
+
+    for (board, key) in boards {
+        let owner = if board.is_owner {
+            "yours".to_string()
+        } else {
+            format!("shared by {}", board.owner)
+        };
+        let access = if board.can_write { "" } else { ", read-only" };
+        println!(
+            "{} {}",
+            display_name(&key.name).bold(),
+            format!("({owner}{access})").dimmed()
+        );
+        for member in board.members.iter().filter(|m| m.username != board.owner) {
+            let access = if member.can_write {
+                "read-write"
+            } else {
+                "read-only"
+            };
+            println!("  {} {}", member.username, access.dimmed());
+        }
+    }
+    Ok(())
+}
+
+/// Parse a link or token lifetime such as `12h`, `7d` or `2w`
+pub(crate) fn parse_expiry(spec: &str) -> Option<Duration> {
+    let spec = spec.trim().to_lowercase();
+    let unit = spec.chars().last()?;
+    let count: i64 = spec[..spec.len() - unit.len_utf8()]
+        .parse()
+        .ok()
+        .filter(|count| *count > 0)?;
+    match unit {
+        'h' => Some(Duration::hours(count)),
+        'd' => Some(Duration::days(count)),
+        'w' => Some(Duration::weeks(count)),
+        _ => None,
+    }
+}
+
+pub(crate) fn local_time(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| {
+            time.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// Create a read-only link to a snapshot of `board`.
+pub fn create_link(board: &str, expires: Option<&str>) -> Result<()> {
+    let session = Session::open()?;
+    let name = normalize_board_name(board);
+    let spec = expires.unwrap_or(DEFAULT_LINK_EXPIRY);
+    let lifetime = parse_expiry(spec).ok_or_else(|| {
+        TaskbookError::General(format!(
+            "invalid expiry '{spec}' — use hours, days or weeks, e.g. 12h, 7d or 2w"
+        ))
+    })?;
+
+    let items = session.storage()?.get()?;
+    let on_board: Vec<&StorageItem> = items
+        .values()
+        .filter(|item| item.boards().iter().any(|b| board_eq(b, &name)))
+        .collect();
+    if on_board.is_empty() {
+        return Err(TaskbookError::General(format!(
+            "{} has no items to share",
+            display_name(&name)
+        )));
+    }
+
+    let snapshot = BoardSnapshot {
+        board: display_name(&name),
+        generated_at: Local::now().timestamp_millis(),
+        items: on_board,
+    };
+    let key = generate_key();
+    let encrypted = encrypt_json(&key, &snapshot)
+        .map_err(|e| TaskbookError::General(format!("encryption failed: {e}")))?;
+    let engine = base64::engine::general_purpose::STANDARD;
+    let link = session.client.create_link(
+        &EncryptedItemData {
+            data: engine.encode(&encrypted.data),
+            nonce: engine.encode(&encrypted.nonce),
+        },
+        lifetime.num_seconds(),
+    )?;
+
+    let fragment = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(key);
+    println!(
+        "{}",
+        format!(
+            "Read-only link to {} (until {}):",
+            display_name(&name),
+            local_time(&link.expires_at)
+        )
+        .green()
+    );
+    println!();
+    println!(
+        "  {}/s/{}#{}",
+        session.server_url.trim_end_matches('/'),
+        link.token,
+        fragment
+    );
+    println!();
+    println!(
+        "{}",
+        "The link shows the board as it is now. Anyone with it can read it.".dimmed()
+    );
+    Ok(())
+}
+
+/// List share links that have not expired yet.
+pub fn list_links() -> Result<()> {
+    let session = Session::open()?;
+    let links = session.client.list_links()?;
+
+    if links.is_empty() {
+        println!("{}", "No share links.".dimmed());
+        return Ok(());
+    }
+
+    for link in links {
+        println!(
+            "{} {}",
+            link.token,
+            format!(
+                "created {}, expires {}",
+                local_time(&link.created_at),
+                local_time(&link.expires_at)
+            )
+            .dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Revoke a share link, given its token or the whole link.
+pub fn revoke_link(link: &str) -> Result<()> {
+    let session = Session::open()?;
+    let token = link.split('#').next().unwrap_or(link);
+    let token = token.rsplit('/').next().unwrap_or(token);
+    session.client.delete_link(token)?;
+    println!("{}", "Share link revoked.".green());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_expiry_accepts_hours_days_and_weeks() {
+        assert_eq!(parse_expiry("12h"), Some(Duration::hours(12)));
+        assert_eq!(parse_expiry("7d"), Some(Duration::days(7)));
+        assert_eq!(parse_expiry("2W"), Some(Duration::weeks(2)));
+        assert_eq!(parse_expiry("0d"), None);
+        assert_eq!(parse_expiry("7"), None);
+        assert_eq!(parse_expiry("d"), None);
+        assert_eq!(parse_expiry("1y"), None);
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+
+use taskbook_common::board::{board_eq, normalize_board_name};
+use taskbook_common::StorageItem;
+
+use super::remote::assign_ids;
+use super::{HistoryEntry, Revision, SharedBoardInfo, StorageBackend};
+use crate::error::Result;
+
+type Items = HashMap<String, StorageItem>;
+
+/// Synced storage with some boards kept off the server.
+///
+/// Items on a local-only board (`sync.localBoards`) are read from and
+/// written to local storage, everything else goes to the server. An item on
+/// both kinds of board stays local. The local storage files also still
+/// hold what was there before sync was turned on; those items are left as
+/// they are, so turning sync off again shows the local-only boards among
+/// them.
+pub struct HybridStorage {
+    remote: Box<dyn StorageBackend>,
+    local: Box<dyn StorageBackend>,
+    local_boards: Vec<String>,
+}
+
+impl HybridStorage {
+    pub fn new(
+        remote: Box<dyn StorageBackend>,
+        local: Box<dyn StorageBackend>,
+        local_boards: &[String],
+    ) -> Self {
+        Self {
+            remote,
+            local,
+            local_boards: local_boards.to_vec(),
+        }
+    }
+
+    fn is_local(&self, item: &StorageItem) -> bool {
+        is_local_only(&self.local_boards, item)
+    }
+
+    /// The server's items with the local-only ones from `local`. A copy of
+    /// a local-only item still on the server, from before its board was
+    /// made local-only, gives way to the local one, and is removed from the
+    /// server on the next save.
+    fn merge(&self, remote: Items, local: Items) -> Items {
+        let mut by_uuid: HashMap<String, StorageItem> = remote
+            .into_values()
+            .map(|item| (item.uuid().to_string(), item))
+            .collect();
+        for item in local.into_values().filter(|item| self.is_local(item)) {
+            by_uuid.insert(item.uuid().to_string(), item);
+        }
+        assign_ids(by_uuid.into_values().collect())
+    }
+
+    /// Split `data` into what goes to the server, and what the local files
+    /// hold afterwards: the local-only items, and the other items already
+    /// there. The local-only items keep their IDs; the others make way.
+    fn split(&self, data: &Items, stored: Items) -> (Items, Items) {
+        let (mut local, remote): (Items, Items) = data
+            .iter()
+            .map(|(id, item)| (id.clone(), item.clone()))
+            .partition(|(_, item)| self.is_local(item));
+
+        let uuids: HashSet<String> = local.values().map(|item| item.uuid().to_string()).collect();
+        let mut next_id = local.values().map(|item| item.id()).max().unwrap_or(0) + 1;
+        let mut others: Vec<StorageItem> = stored
+            .into_values()
+            .filter(|item| !self.is_local(item) && !uuids.contains(item.uuid()))
+            .collect();
+        others.sort_by_key(|item| item.id());
+        for mut item in others {
+            if local.contains_key(&item.id().to_string()) {
+                item.set_id(next_id.max(item.id()));
+            }
+            next_id = next_id.max(item.id() + 1);
+            local.insert(item.id().to_string(), item);
+        }
+        (remote, local)
+    }
+}
+
+/// Whether `item` is on one of `local_boards`, as named in the config
+pub(crate) fn is_local_only(local_boards: &[String], item: &StorageItem) -> bool {
+    item.boards().iter().any(|board| {
+        local_boards
+            .iter()
+            .any(|local| board_eq(&normalize_board_name(local), board))
+    })
+}
+
+impl StorageBackend for HybridStorage {
+    fn get(&self) -> Result<Items> {
+        Ok(self.merge(self.remote.get()?, self.local.get()?))
+    }
+
+    fn get_archive(&self) -> Result<Items> {
+        Ok(self.merge(self.remote.get_archive()?, self.local.get_archive()?))
+    }
+
+    fn set(&self, data: &Items) -> Result<()> {
+        let (remote, local) = self.split(data, self.local.get()?);
+        self.local.set(&local)?;
+        self.remote.set(&remote)
+    }
+
+    fn set_archive(&self, data: &Items) -> Result<()> {
+        let (remote, local) = self.split(data, self.local.get_archive()?);
+        self.local.set_archive(&local)?;
+        self.remote.set_archive(&remote)
+    }
+
+    fn revision(&self, archive: bool) -> Option<Revision> {
+        // Changes on the server arrive as events instead
+        self.local.revision(archive)
+    }
+
+    fn shared_boards(&self) -> Vec<SharedBoardInfo> {
+        self.remote.shared_boards()
+    }
+
+    fn keeps_history(&self) -> bool {
+        true
+    }
+
+    fn item_history(&self, uuid: &str) -> Result<Vec<HistoryEntry>> {
+        self.remote.item_history(uuid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use taskbook_common::Task;
+
+    fn task(id: u64, description: &str, board: &str) -> StorageItem {
+        StorageItem::Task(Task::new(id, description.into(), vec![board.into()], 1))
+    }
+
+    fn by_id(items: Vec<StorageItem>) -> Items {
+        items
+            .into_iter()
+            .map(|item| (item.id().to_string(), item))
+            .collect()
+    }
+
+    #[test]
+    fn local_only_boards_never_reach_the_server() {
+        let remote = MemoryStorage::new();
+        let stale = task(1, "Cached before sync", "work");
+        let local = MemoryStorage::with_items(by_id(vec![stale.clone()]), Items::new());
+        let storage = HybridStorage::new(
+            Box::new(remote.clone()),
+            Box::new(local.clone()),
+            &["@Private".to_string()],
+        );
+
+        let data = by_id(vec![
+            task(1, "Ship release", "work"),
+            task(2, "Doctor's appointment", "private"),
+        ]);
+        storage.set(&data).unwrap();
+
+        let uploaded = remote.get().unwrap();
+        assert_eq!(uploaded.len(), 1);
+        assert!(uploaded.values().all(|item| item.boards() == ["work"]));
+
+        // The private item joins what was already stored locally
+        let stored = local.get().unwrap();
+        assert_eq!(stored.len(), 2);
+        assert!(stored.values().any(|item| item.uuid() == stale.uuid()));
+
+        let merged = storage.get().unwrap();
+        assert_eq!(merged.len(), 2);
+        assert!(merged
+            .values()
+            .any(|item| item.description() == "Doctor's appointment"));
+        assert!(!merged.values().any(|item| item.uuid() == stale.uuid()));
+    }
+}
@@ -3,12 +3,33 @@ use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 
 use fs2::FileExt;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::error::Result;
-use taskbook_common::StorageItem;
+use crate::api_client::EncryptedItemData;
+use crate::config::Config;
+use crate::error::{Result, TaskbookError};
+use crate::repair::{self, RepairStats};
+use taskbook_common::{fill_legacy_uuid, StorageItem};
 
-use super::StorageBackend;
+use super::local_key;
+use super::{Revision, StorageBackend};
+
+/// Outcome of repairing one storage file
+#[derive(Debug)]
+pub struct RepairSummary {
+    pub file: PathBuf,
+    /// Copy of the original file, if it existed
+    pub backup: Option<PathBuf>,
+    pub stats: RepairStats,
+}
+
+/// A storage or archive file in an encrypted directory
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EncryptedFile {
+    encrypted: EncryptedItemData,
+}
 
 /// Local file-based storage with atomic writes and file locking
 pub struct LocalStorage {
@@ -18,10 +39,42 @@ pub struct LocalStorage {
     temp_dir: PathBuf,
     storage_file: PathBuf,
     archive_file: PathBuf,
+    /// Key the files are encrypted with, if the directory is encrypted
+    key: Option<[u8; 32]>,
+    /// Whether plain files are read even though the directory is encrypted
+    allow_plain: bool,
 }
 
 impl LocalStorage {
-    pub fn new(taskbook_dir: &Path) -> Result<Self> {
+    /// Open the storage in `taskbook_dir`. If it is encrypted, this asks for
+    /// the passphrase unless the keyring (if `config` allows it) or
+    /// `TB_PASSPHRASE` has it.
+    pub fn new(taskbook_dir: &Path, config: &Config) -> Result<Self> {
+        let mut storage = Self::open(taskbook_dir, None)?;
+        storage.key = local_key::unlock(taskbook_dir, config)?;
+        Ok(storage)
+    }
+
+    /// Open the storage in `taskbook_dir`, encrypting it with `key`
+    pub fn with_key(taskbook_dir: &Path, key: [u8; 32]) -> Result<Self> {
+        Self::open(taskbook_dir, Some(key))
+    }
+
+    /// Open the storage in `taskbook_dir` with plain files, whether or not
+    /// it is encrypted
+    pub(crate) fn plain(taskbook_dir: &Path) -> Result<Self> {
+        Self::open(taskbook_dir, None)
+    }
+
+    /// Also read files that are plain although the directory is encrypted,
+    /// as they are when encrypting or decrypting it was interrupted.
+    /// Otherwise such a file is an error rather than trusted.
+    pub(crate) fn allowing_plain_files(mut self) -> Self {
+        self.allow_plain = true;
+        self
+    }
+
+    fn open(taskbook_dir: &Path, key: Option<[u8; 32]>) -> Result<Self> {
         let main_app_dir = taskbook_dir.to_path_buf();
         let storage_dir = main_app_dir.join("storage");
         let archive_dir = main_app_dir.join("archive");
@@ -36,6 +89,8 @@ impl LocalStorage {
             temp_dir,
             storage_file,
             archive_file,
+            key,
+            allow_plain: false,
         };
 
         storage.ensure_directories()?;
@@ -92,17 +147,92 @@ impl LocalStorage {
         Ok(lock_file)
     }
 
+    /// Rebuild the storage and archive files from whatever still parses,
+    /// copying each original to a timestamped backup first
+    pub fn repair(&self, renumber: bool) -> Result<Vec<RepairSummary>> {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        [&self.storage_file, &self.archive_file]
+            .into_iter()
+            .map(|path| {
+                let _lock = self.lock_file(path)?;
+                let content = if path.exists() {
+                    self.read_content(path)?
+                } else {
+                    String::new()
+                };
+
+                let backup = if path.exists() {
+                    let backup = path.with_extension(format!("json.bak-{}", stamp));
+                    fs::copy(path, &backup)?;
+                    Some(backup)
+                } else {
+                    None
+                };
+
+                let (items, stats) = repair::repair(&content, renumber);
+                self.write_json_file(path, &items)?;
+                Ok(RepairSummary {
+                    file: path.clone(),
+                    backup,
+                    stats,
+                })
+            })
+            .collect()
+    }
+
+    /// The JSON in `path`, decrypted if it is encrypted
+    fn read_content(&self, path: &Path) -> Result<String> {
+        let content = fs::read_to_string(path)?;
+        let Ok(file) = serde_json::from_str::<EncryptedFile>(&content) else {
+            if self.key.is_some() && !self.allow_plain {
+                return Err(TaskbookError::General(format!(
+                    "{} is not encrypted, but local storage is; if `tb storage encrypt` \
+                     or `tb storage decrypt` was interrupted, run `tb storage decrypt`",
+                    path.display()
+                )));
+            }
+            return Ok(content);
+        };
+        let key = self.key.as_ref().ok_or_else(|| {
+            TaskbookError::General(format!(
+                "{} is encrypted, but its key file is missing",
+                path.display()
+            ))
+        })?;
+        let value: serde_json::Value = local_key::open(key, &file.encrypted)
+            .map_err(|e| TaskbookError::General(format!("{}: {e}", path.display())))?;
+        Ok(value.to_string())
+    }
+
+    /// Read the items in `path`. Items saved before items had UUIDs are
+    /// given one derived from their content, which is kept on the next
+    /// write.
     fn read_json_file(&self, path: &Path) -> Result<HashMap<String, StorageItem>> {
         if !path.exists() {
             return Ok(HashMap::new());
         }
-        let content = fs::read_to_string(path)?;
-        let data: HashMap<String, StorageItem> = serde_json::from_str(&content)?;
-        Ok(data)
+        let content = self.read_content(path)?;
+        let corrupted = |e: serde_json::Error| {
+            TaskbookError::General(format!(
+                "{} is corrupted ({}); run `tb --repair` to recover it",
+                path.display(),
+                e
+            ))
+        };
+        let mut value: serde_json::Value = serde_json::from_str(&content).map_err(corrupted)?;
+        if let Some(items) = value.as_object_mut() {
+            items.values_mut().for_each(fill_legacy_uuid);
+        }
+        serde_json::from_value(value).map_err(corrupted)
     }
 
     fn write_json_file(&self, path: &Path, data: &HashMap<String, StorageItem>) -> Result<()> {
-        let json = serde_json::to_string_pretty(data)?;
+        let json = match &self.key {
+            Some(key) => serde_json::to_string(&EncryptedFile {
+                encrypted: local_key::seal(key, data)?,
+            })?,
+            None => serde_json::to_string_pretty(data)?,
+        };
         let temp_file = self.get_temp_file(path);
         fs::write(&temp_file, json)?;
         fs::rename(&temp_file, path)?;
@@ -130,4 +260,80 @@ impl StorageBackend for LocalStorage {
         let _lock = self.lock_file(&self.archive_file)?;
         self.write_json_file(&self.archive_file, data)
     }
+
+    fn revision(&self, archive: bool) -> Option<Revision> {
+        let file = if archive {
+            &self.archive_file
+        } else {
+            &self.storage_file
+        };
+        Some(Revision::of(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taskbook_common::Task;
+
+    #[test]
+    fn encrypted_files_round_trip_and_hide_their_items() {
+        let root = std::env::temp_dir().join(format!("tb-local-{}", Uuid::new_v4()));
+        let task = StorageItem::Task(Task::new(
+            1,
+            "Call Acme Corp".into(),
+            vec!["work".into()],
+            1,
+        ));
+        let data = HashMap::from([(task.uuid().to_string(), task)]);
+
+        // Plain files are not read once there is a key, unless asked for
+        LocalStorage::plain(&root).unwrap().set(&data).unwrap();
+        let storage = LocalStorage::with_key(&root, [7; 32]).unwrap();
+        assert!(storage.get().is_err());
+        let storage = storage.allowing_plain_files();
+        assert_eq!(storage.get().unwrap().len(), 1);
+
+        storage.set(&data).unwrap();
+        let raw = fs::read_to_string(root.join("storage").join("storage.json")).unwrap();
+        assert!(!raw.contains("Acme"));
+        assert_eq!(storage.get().unwrap().len(), 1);
+
+        assert!(LocalStorage::plain(&root).unwrap().get().is_err());
+        assert!(LocalStorage::with_key(&root, [8; 32])
+            .unwrap()
+            .get()
+            .is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn legacy_items_keep_the_uuid_they_are_given() {
+        let root = std::env::temp_dir().join(format!("tb-local-{}", Uuid::new_v4()));
+        let task = StorageItem::Task(Task::new(1, "Water plants".into(), vec!["home".into()], 1));
+        let mut legacy = serde_json::to_value(&task).unwrap();
+        legacy.as_object_mut().unwrap().remove("_uuid");
+        let storage = LocalStorage::plain(&root).unwrap();
+        fs::write(
+            root.join("storage").join("storage.json"),
+            serde_json::json!({ "1": legacy }).to_string(),
+        )
+        .unwrap();
+
+        let first = storage.get().unwrap();
+        let second = storage.get().unwrap();
+        assert_eq!(first["1"].uuid(), second["1"].uuid());
+        assert_ne!(first["1"].uuid(), task.uuid());
+
+        // Reading leaves the file alone; the next write keeps the UUID
+        let file = root.join("storage").join("storage.json");
+        assert!(!fs::read_to_string(&file).unwrap().contains("_uuid"));
+        storage.set(&first).unwrap();
+        assert!(fs::read_to_string(&file)
+            .unwrap()
+            .contains(first["1"].uuid()));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
 }
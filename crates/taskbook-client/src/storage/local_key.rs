@@ -0,0 +1,162 @@
+//! The key an encrypted local storage directory is encrypted with.
+//!
+//! It is derived from a passphrase with Argon2id. `encryption.json` in the
+//! taskbook directory holds the salt, and a known value encrypted with the
+//! key to tell a wrong passphrase from damaged files. The passphrase comes
+//! from `TB_PASSPHRASE` or a prompt, and the derived key is remembered in
+//! the system keyring unless `sync.keyring` is off.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use taskbook_common::encryption::{
+    decrypt_json, derive_key, encrypt_json, EncryptedItem, KeyParams,
+};
+
+use crate::api_client::EncryptedItemData;
+use crate::auth::prompt_password;
+use crate::config::Config;
+use crate::error::{Result, TaskbookError};
+
+/// Environment variable with the storage passphrase, for scripts
+pub const PASSPHRASE_VAR: &str = "TB_PASSPHRASE";
+
+const KEY_FILE: &str = "encryption.json";
+
+/// Known plaintext encrypted into the key file
+const CHECK_VALUE: &str = "taskbook";
+
+const KEYRING_SERVICE: &str = "taskbook";
+
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    #[serde(flatten)]
+    params: KeyParams,
+    /// [`CHECK_VALUE`] encrypted with the key
+    check: EncryptedItemData,
+}
+
+impl KeyFile {
+    fn accepts(&self, key: &[u8; 32]) -> bool {
+        open::<String>(key, &self.check).is_ok_and(|value| value == CHECK_VALUE)
+    }
+}
+
+fn key_file(dir: &Path) -> PathBuf {
+    dir.join(KEY_FILE)
+}
+
+/// Whether the storage in `dir` is encrypted
+pub(crate) fn is_encrypted(dir: &Path) -> bool {
+    key_file(dir).exists()
+}
+
+/// The key for the storage in `dir`, or None if it is not encrypted
+pub(crate) fn unlock(dir: &Path, config: &Config) -> Result<Option<[u8; 32]>> {
+    let path = key_file(dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file: KeyFile = serde_json::from_str(&fs::read_to_string(&path)?)
+        .map_err(|e| TaskbookError::General(format!("{} is corrupted ({e})", path.display())))?;
+
+    if let Some(key) = remembered(dir, config).filter(|key| file.accepts(key)) {
+        return Ok(Some(key));
+    }
+
+    let passphrase = match std::env::var(PASSPHRASE_VAR) {
+        Ok(passphrase) if !passphrase.is_empty() => passphrase,
+        _ => prompt_password("Storage passphrase: ")?,
+    };
+    let key = derive(&passphrase, &file.params)?;
+    if !file.accepts(&key) {
+        return Err(TaskbookError::Auth("wrong storage passphrase".to_string()));
+    }
+    remember(dir, config, &key);
+    Ok(Some(key))
+}
+
+/// Start encrypting the storage in `dir` with a key derived from
+/// `passphrase`, and return the key. The storage files are left as they
+/// are, to be rewritten with the key.
+pub(crate) fn create(dir: &Path, passphrase: &str, config: &Config) -> Result<[u8; 32]> {
+    let cost = config.encryption.kdf_cost()?;
+    let params = KeyParams::generate_with(cost);
+    let key = derive(passphrase, &params)?;
+    let file = KeyFile {
+        params,
+        check: seal(&key, CHECK_VALUE)?,
+    };
+    fs::write(key_file(dir), serde_json::to_string_pretty(&file)?)?;
+    remember(dir, config, &key);
+    Ok(key)
+}
+
+/// Stop encrypting the storage in `dir`; its files must already be plain.
+pub(crate) fn remove(dir: &Path) -> Result<()> {
+    if let Ok(entry) = keyring_entry(dir) {
+        let _ = entry.delete_credential();
+    }
+    fs::remove_file(key_file(dir))?;
+    Ok(())
+}
+
+/// Encrypt `value` as JSON
+pub(super) fn seal<T: Serialize + ?Sized>(key: &[u8; 32], value: &T) -> Result<EncryptedItemData> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let encrypted = encrypt_json(key, value)
+        .map_err(|e| TaskbookError::General(format!("encryption failed: {e}")))?;
+    Ok(EncryptedItemData {
+        data: engine.encode(&encrypted.data),
+        nonce: engine.encode(&encrypted.nonce),
+    })
+}
+
+/// Decrypt a value encrypted by [`seal`]
+pub(super) fn open<T: DeserializeOwned>(key: &[u8; 32], sealed: &EncryptedItemData) -> Result<T> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let encrypted = EncryptedItem {
+        data: engine
+            .decode(&sealed.data)
+            .map_err(|e| TaskbookError::General(format!("invalid base64 data: {e}")))?,
+        nonce: engine
+            .decode(&sealed.nonce)
+            .map_err(|e| TaskbookError::General(format!("invalid base64 nonce: {e}")))?,
+    };
+    decrypt_json(key, &encrypted)
+        .map_err(|e| TaskbookError::General(format!("decryption failed: {e}")))
+}
+
+fn derive(passphrase: &str, params: &KeyParams) -> Result<[u8; 32]> {
+    derive_key(passphrase, params)
+        .map_err(|e| TaskbookError::General(format!("cannot derive key: {e}")))
+}
+
+fn keyring_entry(dir: &Path) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, &format!("storage:{}", dir.display()))
+}
+
+/// The key saved in the keyring for `dir`, if any
+fn remembered(dir: &Path, config: &Config) -> Option<[u8; 32]> {
+    if !config.sync.keyring {
+        return None;
+    }
+    let saved = keyring_entry(dir).ok()?.get_password().ok()?;
+    base64::engine::general_purpose::STANDARD
+        .decode(saved)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+}
+
+/// Save the key for `dir` in the keyring, if there is one. Best-effort:
+/// without it the passphrase is asked for every time.
+fn remember(dir: &Path, config: &Config, key: &[u8; 32]) {
+    if config.sync.keyring {
+        if let Ok(entry) = keyring_entry(dir) {
+            let _ = entry.set_password(&base64::engine::general_purpose::STANDARD.encode(key));
+        }
+    }
+}
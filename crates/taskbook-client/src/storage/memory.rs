@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::error::Result;
+use taskbook_common::StorageItem;
+
+use super::StorageBackend;
+
+type Items = HashMap<String, StorageItem>;
+
+/// In-memory storage for tests and embedding; nothing touches the disk.
+///
+/// Clones share the same items, so a caller can keep a handle to inspect
+/// what a `Taskbook` saved after boxing the backend.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    data: Rc<RefCell<Items>>,
+    archive: Rc<RefCell<Items>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start with the given active and archived items
+    pub fn with_items(data: Items, archive: Items) -> Self {
+        Self {
+            data: Rc::new(RefCell::new(data)),
+            archive: Rc::new(RefCell::new(archive)),
+        }
+    }
+}
+
+impl StorageBackend for MemoryStorage {
+    fn get(&self) -> Result<Items> {
+        Ok(self.data.borrow().clone())
+    }
+
+    fn get_archive(&self) -> Result<Items> {
+        Ok(self.archive.borrow().clone())
+    }
+
+    fn set(&self, data: &Items) -> Result<()> {
+        *self.data.borrow_mut() = data.clone();
+        Ok(())
+    }
+
+    fn set_archive(&self, data: &Items) -> Result<()> {
+        *self.archive.borrow_mut() = data.clone();
+        Ok(())
+    }
+}
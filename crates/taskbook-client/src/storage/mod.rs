@@ -1,18 +1,108 @@
+mod hybrid;
 mod local;
+mod local_key;
+mod memory;
 mod remote;
 
-pub use local::LocalStorage;
+pub(crate) use hybrid::is_local_only;
+pub use hybrid::HybridStorage;
+pub use local::{LocalStorage, RepairSummary};
+pub use local_key::PASSPHRASE_VAR;
+pub(crate) use local_key::{
+    create as create_storage_key, is_encrypted, remove as remove_storage_key,
+};
+pub use memory::MemoryStorage;
 pub use remote::RemoteStorage;
+pub(crate) use remote::{decrypt_one, decrypt_with, encrypt_with};
 
 use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
 
-use crate::error::Result;
+use crate::error::{Result, TaskbookError};
 use taskbook_common::StorageItem;
 
+/// A board shared between server accounts, as seen by one of its members
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedBoardInfo {
+    pub name: String,
+    pub owner: String,
+    /// Whether the current user owns the board
+    pub is_owner: bool,
+    /// Whether the current user may change the board's items
+    pub can_write: bool,
+    /// Every member, the owner included, with their write access
+    pub members: Vec<(String, bool)>,
+}
+
+/// A version of an item, as kept by the sync server
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub item: StorageItem,
+    pub archived: bool,
+    /// When this version was saved (ms since epoch)
+    pub saved_at: i64,
+    /// Whether this is the version stored now
+    pub current: bool,
+}
+
+/// What a stored file looked like when it was last read or written, to tell
+/// whether someone else has changed it since
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revision {
+    modified: Option<SystemTime>,
+    len: u64,
+    /// Each write renames a new file into place, so this changes even when
+    /// the modification time and length do not
+    #[cfg(unix)]
+    inode: u64,
+}
+
+impl Revision {
+    /// The revision of the file at `path`; a missing file has one too
+    pub fn of(path: &Path) -> Self {
+        let metadata = fs::metadata(path).ok();
+        Self {
+            modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+            len: metadata.as_ref().map_or(0, |m| m.len()),
+            #[cfg(unix)]
+            inode: metadata.as_ref().map_or(0, |m| {
+                use std::os::unix::fs::MetadataExt;
+                m.ino()
+            }),
+        }
+    }
+}
+
 /// Trait abstracting storage backends (local file, remote server, etc.)
 pub trait StorageBackend {
     fn get(&self) -> Result<HashMap<String, StorageItem>>;
     fn get_archive(&self) -> Result<HashMap<String, StorageItem>>;
     fn set(&self, data: &HashMap<String, StorageItem>) -> Result<()>;
     fn set_archive(&self, data: &HashMap<String, StorageItem>) -> Result<()>;
+
+    /// Revision of the stored active items (or archived ones), or `None` if
+    /// the backend cannot tell when they change. Data read from a backend
+    /// that can tell is read again once the revision moves on.
+    fn revision(&self, _archive: bool) -> Option<Revision> {
+        None
+    }
+
+    /// Boards shared with other accounts, as of the last read
+    fn shared_boards(&self) -> Vec<SharedBoardInfo> {
+        Vec::new()
+    }
+
+    /// Whether `item_history` has earlier versions to give
+    fn keeps_history(&self) -> bool {
+        false
+    }
+
+    /// Versions of the item with this UUID, newest first
+    fn item_history(&self, _uuid: &str) -> Result<Vec<HistoryEntry>> {
+        Err(TaskbookError::General(
+            "item history is kept by the sync server; this taskbook is stored locally".to_string(),
+        ))
+    }
 }
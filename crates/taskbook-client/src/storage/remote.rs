@@ -1,103 +1,391 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 
 use base64::Engine;
-use taskbook_common::encryption::{decrypt_item, encrypt_item, EncryptedItem};
+use chrono::DateTime;
+use taskbook_common::board::board_eq;
+use taskbook_common::encryption::{
+    decrypt_item, encrypt_item, unwrap_board_key, BoardKey, EncryptedItem,
+};
+use taskbook_common::error::CommonError;
 use taskbook_common::StorageItem;
 
-use super::StorageBackend;
-use crate::api_client::{ApiClient, EncryptedItemData};
+use super::{HistoryEntry, SharedBoardInfo, StorageBackend};
+use crate::api_client::{ApiClient, EncryptedItemData, RetryPolicy, TaggedItems};
 use crate::credentials::Credentials;
 use crate::error::{Result, TaskbookError};
 
+/// A shared board as last read from the server
+struct SharedBoardState {
+    id: String,
+    key: BoardKey,
+    info: SharedBoardInfo,
+    /// The board's items as last read or written, to tell whether a save
+    /// changes them
+    snapshot: Snapshot,
+}
+
+/// Items by UUID, without their display IDs (which are reassigned on
+/// every read)
+type Snapshot = HashMap<String, serde_json::Value>;
+
+/// An item as last read or written, and its ciphertext
+type Sealed = (serde_json::Value, EncryptedItemData);
+
 /// Remote storage backend that communicates with a taskbook server.
 /// All data is encrypted client-side before being sent to the server.
+///
+/// Items on a board shared with other accounts are stored with that board
+/// under the board's own key instead of with the user's items.
 pub struct RemoteStorage {
     client: ApiClient,
     encryption_key: [u8; 32],
+    shared: RefCell<Option<Vec<SharedBoardState>>>,
+    /// The user's own items by UUID, so that unchanged items are uploaded
+    /// as they were: the server keeps a revision whenever an item's data
+    /// changes, and encrypting again always changes it
+    sealed: RefCell<HashMap<String, Sealed>>,
+    /// The user's items (`false`) and archive (`true`) as last fetched, which
+    /// the server only sends again once they change
+    fetched: RefCell<HashMap<bool, TaggedItems>>,
 }
 
 impl RemoteStorage {
-    pub fn new(server_url: &str) -> Result<Self> {
+    /// Storage on `server_url` for the saved login, with requests timed out
+    /// and retried by `policy`
+    pub fn new(server_url: &str, policy: RetryPolicy) -> Result<Self> {
         let creds = Credentials::load()?.ok_or_else(|| {
             TaskbookError::Auth("not logged in — run `tb register` or `tb login` first".to_string())
         })?;
 
         let encryption_key = creds.encryption_key_bytes()?;
-        let client = ApiClient::new(server_url, Some(&creds.token));
+        let client = ApiClient::for_credentials(server_url, &creds).with_retry_policy(policy);
 
         Ok(Self {
             client,
             encryption_key,
+            shared: RefCell::new(None),
+            sealed: RefCell::new(HashMap::new()),
+            fetched: RefCell::new(HashMap::new()),
         })
     }
 
-    fn decrypt_items(
-        &self,
-        encrypted: &HashMap<String, EncryptedItemData>,
-    ) -> Result<HashMap<String, StorageItem>> {
+    /// Shared boards the user is a member of, with their keys unwrapped.
+    /// Boards the user owns come first, so they win a name clash.
+    fn load_shared_boards(&self) -> Result<Vec<SharedBoardState>> {
         let engine = base64::engine::general_purpose::STANDARD;
-        let mut result = HashMap::new();
+        let mut boards = Vec::new();
 
-        for (key, item_data) in encrypted {
-            let data = engine
-                .decode(&item_data.data)
-                .map_err(|e| TaskbookError::General(format!("invalid base64 data: {e}")))?;
-            let nonce = engine
-                .decode(&item_data.nonce)
-                .map_err(|e| TaskbookError::General(format!("invalid base64 nonce: {e}")))?;
+        for board in self.client.list_boards()? {
+            let wrapped = engine
+                .decode(&board.wrapped_key)
+                .map_err(|e| TaskbookError::General(format!("invalid base64 board key: {e}")))?;
+            let key = unwrap_board_key(&self.encryption_key, &wrapped)
+                .map_err(|e| TaskbookError::General(format!("cannot open shared board: {e}")))?;
 
-            let encrypted_item = EncryptedItem { data, nonce };
-            let item = decrypt_item(&self.encryption_key, &encrypted_item)
-                .map_err(|e| TaskbookError::General(format!("decryption failed: {e}")))?;
+            boards.push(SharedBoardState {
+                id: board.id,
+                info: SharedBoardInfo {
+                    name: key.name.clone(),
+                    owner: board.owner,
+                    is_owner: board.is_owner,
+                    can_write: board.can_write,
+                    members: board
+                        .members
+                        .into_iter()
+                        .map(|member| (member.username, member.can_write))
+                        .collect(),
+                },
+                key,
+                snapshot: Snapshot::new(),
+            });
+        }
+
+        boards.sort_by_key(|board| !board.info.is_owner);
+        Ok(boards)
+    }
 
-            result.insert(key.clone(), item);
+    /// The user's items or archive, from the server unless they are
+    /// unchanged since last fetched.
+    fn fetch_own(&self, archived: bool) -> Result<HashMap<String, EncryptedItemData>> {
+        let mut fetched = self.fetched.borrow_mut();
+        let etag = fetched.get(&archived).and_then(|f| f.etag.clone());
+        let fresh = if archived {
+            self.client.get_archive(etag.as_deref())?
+        } else {
+            self.client.get_items(etag.as_deref())?
+        };
+        match fresh {
+            Some(items) => {
+                fetched.insert(archived, items.clone());
+                Ok(items.items)
+            }
+            None => fetched
+                .get(&archived)
+                .map(|f| f.items.clone())
+                .ok_or_else(|| {
+                    TaskbookError::Network("server sent 304 for an uncached request".to_string())
+                }),
         }
+    }
 
-        Ok(result)
+    /// Decrypt the user's items fetched from the server.
+    fn decrypt_own(
+        &self,
+        encrypted: &HashMap<String, EncryptedItemData>,
+    ) -> Result<Vec<StorageItem>> {
+        let items = decrypt_with(&self.encryption_key, encrypted)?;
+        let mut sealed = self.sealed.borrow_mut();
+        for item in &items {
+            if let Some(data) = encrypted.get(item.uuid()) {
+                sealed.insert(item.uuid().to_string(), (to_json(item)?, data.clone()));
+            }
+        }
+        Ok(items)
     }
 
-    fn encrypt_items(
+    /// Encrypt the user's items, reusing the ciphertext of those unchanged
+    /// since they were read or written.
+    fn encrypt_own<'a>(
         &self,
-        items: &HashMap<String, StorageItem>,
+        items: impl IntoIterator<Item = &'a StorageItem>,
     ) -> Result<HashMap<String, EncryptedItemData>> {
-        let engine = base64::engine::general_purpose::STANDARD;
+        let mut sealed = self.sealed.borrow_mut();
         let mut result = HashMap::new();
 
-        for (key, item) in items {
-            let encrypted = encrypt_item(&self.encryption_key, item)
-                .map_err(|e| TaskbookError::General(format!("encryption failed: {e}")))?;
-
-            result.insert(
-                key.clone(),
-                EncryptedItemData {
-                    data: engine.encode(&encrypted.data),
-                    nonce: engine.encode(&encrypted.nonce),
-                },
-            );
+        for item in items {
+            let value = to_json(item)?;
+            let data = match sealed.get(item.uuid()) {
+                Some((known, data)) if *known == value => data.clone(),
+                _ => {
+                    let data = encrypt_with(&self.encryption_key, [item])?
+                        .remove(item.uuid())
+                        .expect("the item was just encrypted");
+                    sealed.insert(item.uuid().to_string(), (value, data.clone()));
+                    data
+                }
+            };
+            result.insert(item.uuid().to_string(), data);
         }
 
         Ok(result)
     }
 }
 
+fn to_json(item: &StorageItem) -> Result<serde_json::Value> {
+    serde_json::to_value(item)
+        .map_err(|e| TaskbookError::General(format!("serialization failed: {e}")))
+}
+
+/// Index of the shared board `item` belongs on, if any
+fn route(boards: &[SharedBoardState], item: &StorageItem) -> Option<usize> {
+    item.boards().iter().find_map(|name| {
+        boards
+            .iter()
+            .position(|board| board_eq(&board.key.name, name))
+    })
+}
+
+fn snapshot(items: &[&StorageItem]) -> Result<Snapshot> {
+    items
+        .iter()
+        .map(|item| {
+            let mut value = to_json(item)?;
+            if let Some(object) = value.as_object_mut() {
+                object.remove("_id");
+            }
+            Ok((item.uuid().to_string(), value))
+        })
+        .collect()
+}
+
+/// Key items by display ID.
+///
+/// The server keys items by UUID, so two devices (or two accounts sharing a
+/// board) may have handed out the same numeric ID; later duplicates are
+/// given fresh IDs. The renumbering is the same on every read, and the new
+/// IDs of the user's own items are uploaded with the next save, whose
+/// items no longer match what was read.
+pub(super) fn assign_ids(mut items: Vec<StorageItem>) -> HashMap<String, StorageItem> {
+    items.sort_by(|a, b| {
+        a.id()
+            .cmp(&b.id())
+            .then_with(|| a.timestamp().cmp(&b.timestamp()))
+            .then_with(|| a.uuid().cmp(b.uuid()))
+    });
+    let mut next_id = items.iter().map(|item| item.id()).max().unwrap_or(0) + 1;
+
+    let mut result = HashMap::with_capacity(items.len());
+    for mut item in items {
+        if result.contains_key(&item.id().to_string()) {
+            item.set_id(next_id);
+            next_id += 1;
+        }
+        result.insert(item.id().to_string(), item);
+    }
+    result
+}
+
+/// Decrypt items stored under `key`, the user's or a shared board's
+pub(crate) fn decrypt_with(
+    key: &[u8; 32],
+    encrypted: &HashMap<String, EncryptedItemData>,
+) -> Result<Vec<StorageItem>> {
+    encrypted
+        .values()
+        .map(|item_data| decrypt_one(key, item_data))
+        .collect()
+}
+
+/// Decrypt one item. Fails with [`TaskbookError::WrongKey`] if it was
+/// encrypted under another key.
+pub(crate) fn decrypt_one(key: &[u8; 32], item_data: &EncryptedItemData) -> Result<StorageItem> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let data = engine
+        .decode(&item_data.data)
+        .map_err(|e| TaskbookError::General(format!("invalid base64 data: {e}")))?;
+    let nonce = engine
+        .decode(&item_data.nonce)
+        .map_err(|e| TaskbookError::General(format!("invalid base64 nonce: {e}")))?;
+
+    decrypt_item(key, &EncryptedItem { data, nonce }).map_err(|e| match e {
+        CommonError::DecryptionFailed => TaskbookError::WrongKey,
+        e => TaskbookError::General(format!("decryption failed: {e}")),
+    })
+}
+
+pub(crate) fn encrypt_with<'a>(
+    key: &[u8; 32],
+    items: impl IntoIterator<Item = &'a StorageItem>,
+) -> Result<HashMap<String, EncryptedItemData>> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let mut result = HashMap::new();
+
+    for item in items {
+        let encrypted = encrypt_item(key, item)
+            .map_err(|e| TaskbookError::General(format!("encryption failed: {e}")))?;
+
+        result.insert(
+            item.uuid().to_string(),
+            EncryptedItemData {
+                data: engine.encode(&encrypted.data),
+                nonce: engine.encode(&encrypted.nonce),
+            },
+        );
+    }
+
+    Ok(result)
+}
+
 impl StorageBackend for RemoteStorage {
     fn get(&self) -> Result<HashMap<String, StorageItem>> {
-        let encrypted = self.client.get_items()?;
-        self.decrypt_items(&encrypted)
+        let own = self.decrypt_own(&self.fetch_own(false)?)?;
+        let mut boards = self.load_shared_boards()?;
+
+        // An item stored both with the user and on a shared board (a save
+        // interrupted while sharing) is kept once, from the board
+        let mut by_uuid: HashMap<String, StorageItem> = own
+            .into_iter()
+            .map(|item| (item.uuid().to_string(), item))
+            .collect();
+        for board in &mut boards {
+            let items = decrypt_with(&board.key.key, &self.client.get_board_items(&board.id)?)?;
+            board.snapshot = snapshot(&items.iter().collect::<Vec<_>>())?;
+            for item in items {
+                by_uuid.insert(item.uuid().to_string(), item);
+            }
+        }
+
+        *self.shared.borrow_mut() = Some(boards);
+        Ok(assign_ids(by_uuid.into_values().collect()))
     }
 
     fn get_archive(&self) -> Result<HashMap<String, StorageItem>> {
-        let encrypted = self.client.get_archive()?;
-        self.decrypt_items(&encrypted)
+        let encrypted = self.fetch_own(true)?;
+        Ok(assign_ids(self.decrypt_own(&encrypted)?))
     }
 
     fn set(&self, data: &HashMap<String, StorageItem>) -> Result<()> {
-        let encrypted = self.encrypt_items(data)?;
+        if self.shared.borrow().is_none() {
+            self.get()?;
+        }
+        let mut shared = self.shared.borrow_mut();
+        let boards = shared.as_mut().expect("shared boards were just loaded");
+
+        let mut own = Vec::new();
+        let mut routed: Vec<Vec<&StorageItem>> = vec![Vec::new(); boards.len()];
+        for item in data.values() {
+            match route(boards, item) {
+                Some(index) => routed[index].push(item),
+                None => own.push(item),
+            }
+        }
+
+        // Check every read-only board before writing anything
+        let mut changed = Vec::new();
+        for (index, items) in routed.iter().enumerate() {
+            let board = &boards[index];
+            let current = snapshot(items)?;
+            if current == board.snapshot {
+                continue;
+            }
+            if !board.info.can_write {
+                return Err(TaskbookError::General(format!(
+                    "@{} is shared with you read-only by {}",
+                    board.info.name, board.info.owner
+                )));
+            }
+            changed.push((index, current));
+        }
+
+        for (index, current) in changed {
+            let board = &mut boards[index];
+            let encrypted = encrypt_with(&board.key.key, routed[index].iter().copied())?;
+            self.client.put_board_items(&board.id, &encrypted)?;
+            board.snapshot = current;
+        }
+
+        let encrypted = self.encrypt_own(own)?;
         self.client.put_items(&encrypted)
     }
 
     fn set_archive(&self, data: &HashMap<String, StorageItem>) -> Result<()> {
-        let encrypted = self.encrypt_items(data)?;
+        let encrypted = self.encrypt_own(data.values())?;
         self.client.put_archive(&encrypted)
     }
+
+    fn shared_boards(&self) -> Vec<SharedBoardInfo> {
+        self.shared
+            .borrow()
+            .iter()
+            .flatten()
+            .map(|board| board.info.clone())
+            .collect()
+    }
+
+    fn keeps_history(&self) -> bool {
+        true
+    }
+
+    fn item_history(&self, uuid: &str) -> Result<Vec<HistoryEntry>> {
+        let mut history = Vec::new();
+        for version in self.client.get_item_history(uuid)? {
+            // Versions saved before the encryption key changed stay
+            // encrypted under the old one
+            let item = match decrypt_one(&self.encryption_key, &version.item) {
+                Err(TaskbookError::WrongKey) if !version.current => continue,
+                result => result?,
+            };
+            let saved_at = DateTime::parse_from_rfc3339(&version.saved_at)
+                .map(|t| t.timestamp_millis())
+                .unwrap_or_default();
+            history.push(HistoryEntry {
+                item,
+                archived: version.archived,
+                saved_at,
+                current: version.current,
+            });
+        }
+        Ok(history)
+    }
 }
@@ -1,16 +1,36 @@
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use arboard::Clipboard;
+use chrono::NaiveDate;
 
-use crate::config::Config;
+use crate::agenda;
+use crate::api_client::RetryPolicy;
+use crate::attachments;
+use crate::burndown;
+use crate::config::{Config, HooksConfig, TimelineGroup};
+use crate::copy::{self, CopyFormat};
 use crate::directory::resolve_taskbook_directory;
 use crate::editor;
 use crate::error::{Result, TaskbookError};
+use crate::events::{Subscriber, TaskbookEvent};
+use crate::history;
+use crate::hooks::{self, HookEvent};
+use crate::opener;
 use crate::render::{Render, Stats};
-use crate::storage::{LocalStorage, RemoteStorage, StorageBackend};
+use crate::report::{Report, ReportPeriod};
+use crate::storage::{
+    HybridStorage, LocalStorage, RemoteStorage, Revision, SharedBoardInfo, StorageBackend,
+};
+use crate::timing;
 use taskbook_common::board::{self, DEFAULT_BOARD};
-use taskbook_common::{Note, StorageItem, Task};
+use taskbook_common::due;
+use taskbook_common::priority;
+use taskbook_common::{Comment, FocusSession, LinkedCommit, Note, StorageItem, Task};
+
+/// Board the daily journal notes of `tb --journal` are kept on
+pub const JOURNAL_BOARD: &str = "Journal";
 
 struct CreateOptions {
     boards: Vec<String>,
@@ -18,43 +38,303 @@ struct CreateOptions {
     id: u64,
     priority: u8,
     tags: Vec<String>,
+    due: Option<NaiveDate>,
 }
 
+/// Items read from storage, with the storage revision they were read at
+type Cache = RefCell<Option<(HashMap<String, StorageItem>, Option<Revision>)>>;
+
 pub struct Taskbook {
     storage: Box<dyn StorageBackend>,
     render: Render,
+    hooks: HooksConfig,
+    subscribers: Vec<Subscriber>,
+    /// Cached active items and the revision they were read at; `None` until
+    /// first read or after `reload()`
+    cache: Cache,
+    /// Cached archived items and the revision they were read at; `None`
+    /// until first read or after `reload()`
+    archive_cache: Cache,
+    /// Boards of the cached active items, computed on first use
+    boards_cache: RefCell<Option<Vec<String>>>,
 }
 
-impl Taskbook {
-    pub fn new(taskbook_dir: Option<&Path>) -> Result<Self> {
-        let config = Config::load_or_default();
+/// Configures and creates a [`Taskbook`].
+///
+/// By default the config is read from the config file, storage is chosen
+/// from it (local files or the sync server), and output is printed to the
+/// terminal, exactly like `Taskbook::new`.
+pub struct TaskbookBuilder {
+    config: Option<Config>,
+    storage: Option<Box<dyn StorageBackend>>,
+    taskbook_dir: Option<PathBuf>,
+    render: bool,
+}
 
-        let storage: Box<dyn StorageBackend> = if config.sync.enabled {
-            Box::new(RemoteStorage::new(&config.sync.server_url)?)
+impl Default for TaskbookBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskbookBuilder {
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            storage: None,
+            taskbook_dir: None,
+            render: true,
+        }
+    }
+
+    /// Use this config instead of loading the config file
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Use this storage backend instead of the one selected by the config
+    pub fn storage(mut self, storage: Box<dyn StorageBackend>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Override the local storage directory (like `--taskbook-dir`)
+    pub fn taskbook_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.taskbook_dir = Some(dir.into());
+        self
+    }
+
+    /// Enable or disable terminal output (enabled by default)
+    pub fn render(mut self, enabled: bool) -> Self {
+        self.render = enabled;
+        self
+    }
+
+    pub fn build(self) -> Result<Taskbook> {
+        let config = match self.config {
+            Some(config) => config,
+            None => {
+                let config = Config::load_or_default();
+                timing::mark("config loaded");
+                config
+            }
+        };
+
+        let storage: Box<dyn StorageBackend> = match self.storage {
+            Some(storage) => storage,
+            None if config.sync.enabled && !config.sync.local_boards.is_empty() => {
+                let resolved_dir =
+                    resolve_taskbook_directory(self.taskbook_dir.as_deref(), &config)?;
+                Box::new(HybridStorage::new(
+                    Box::new(RemoteStorage::new(
+                        &config.sync.server_url,
+                        RetryPolicy::from_config(&config.sync),
+                    )?),
+                    Box::new(LocalStorage::new(&resolved_dir, &config)?),
+                    &config.sync.local_boards,
+                ))
+            }
+            None if config.sync.enabled => Box::new(RemoteStorage::new(
+                &config.sync.server_url,
+                RetryPolicy::from_config(&config.sync),
+            )?),
+            None => {
+                let resolved_dir =
+                    resolve_taskbook_directory(self.taskbook_dir.as_deref(), &config)?;
+                Box::new(LocalStorage::new(&resolved_dir, &config)?)
+            }
+        };
+        timing::mark("storage ready");
+
+        let hooks = config.hooks.clone();
+        let render = if self.render {
+            Render::new(config)
         } else {
-            let resolved_dir = resolve_taskbook_directory(taskbook_dir)?;
-            Box::new(LocalStorage::new(&resolved_dir)?)
+            Render::disabled(config)
         };
 
-        let render = Render::new(config);
+        Ok(Taskbook {
+            storage,
+            render,
+            hooks,
+            subscribers: Vec::new(),
+            cache: RefCell::new(None),
+            archive_cache: RefCell::new(None),
+            boards_cache: RefCell::new(None),
+        })
+    }
+}
+
+impl Taskbook {
+    pub fn new(taskbook_dir: Option<&Path>) -> Result<Self> {
+        let mut builder = Self::builder();
+        if let Some(dir) = taskbook_dir {
+            builder = builder.taskbook_dir(dir);
+        }
+        builder.build()
+    }
 
-        Ok(Self { storage, render })
+    pub fn builder() -> TaskbookBuilder {
+        TaskbookBuilder::new()
+    }
+
+    /// Drop cached data so the next read goes back to the storage backend.
+    ///
+    /// Call this when the underlying storage may have been changed by
+    /// someone else (another device via sync). Local files changed by
+    /// another process are noticed without it.
+    pub fn reload(&self) {
+        self.cache.borrow_mut().take();
+        self.archive_cache.borrow_mut().take();
+        self.boards_cache.borrow_mut().take();
+    }
+
+    /// Register a callback invoked after every change saved through this
+    /// `Taskbook`, in the order the callbacks were registered
+    pub fn subscribe(&mut self, subscriber: Subscriber) {
+        self.subscribers.push(subscriber);
+    }
+
+    /// Notify subscribers and run the matching hook, reporting hook failures
+    /// without failing the operation that triggered it
+    fn emit(&self, event: TaskbookEvent) {
+        self.notify_subscribers(&event);
+        if let Some((hook, command)) = self.hook_for(&event) {
+            if let Err(e) = hooks::run(command, hook, event.item()) {
+                self.render.hook_failed(&e.to_string());
+            }
+        }
+    }
+
+    /// Notify subscribers and run the matching hook in the background (for TUI)
+    fn emit_silent(&self, event: TaskbookEvent) {
+        self.notify_subscribers(&event);
+        if let Some((hook, command)) = self.hook_for(&event) {
+            hooks::run_detached(command, hook, event.item());
+        }
+    }
+
+    /// Emit `Updated` for each of `ids` after their changes were saved
+    fn emit_updated(&self, data: &HashMap<String, StorageItem>, ids: &[u64]) {
+        for item in ids.iter().filter_map(|id| data.get(&id.to_string())) {
+            self.emit(TaskbookEvent::Updated(item.clone()));
+        }
+    }
+
+    /// Emit `Updated` for each of `ids` without CLI output (for TUI)
+    fn emit_updated_silent(&self, data: &HashMap<String, StorageItem>, ids: &[u64]) {
+        for item in ids.iter().filter_map(|id| data.get(&id.to_string())) {
+            self.emit_silent(TaskbookEvent::Updated(item.clone()));
+        }
+    }
+
+    fn notify_subscribers(&self, event: &TaskbookEvent) {
+        for subscriber in &self.subscribers {
+            subscriber(event);
+        }
+    }
+
+    fn hook_for(&self, event: &TaskbookEvent) -> Option<(HookEvent, &str)> {
+        let hook = HookEvent::for_event(event)?;
+        hook.command(&self.hooks).map(|command| (hook, command))
     }
 
     fn get_data(&self) -> Result<HashMap<String, StorageItem>> {
-        self.storage.get()
+        let revision = self.storage.revision(false);
+        if !Self::is_fresh(&self.cache, &revision) {
+            self.boards_cache.borrow_mut().take();
+        }
+        Self::read_cached(&self.cache, revision, || self.storage.get())
     }
 
     fn get_archive(&self) -> Result<HashMap<String, StorageItem>> {
-        self.storage.get_archive()
+        let revision = self.storage.revision(true);
+        Self::read_cached(&self.archive_cache, revision, || self.storage.get_archive())
     }
 
     fn save(&self, data: &HashMap<String, StorageItem>) -> Result<()> {
-        self.storage.set(data)
+        self.boards_cache.borrow_mut().take();
+        // Log what changed since the last read into each item's activity
+        let mut data = data.clone();
+        if let Some((previous, _)) = self.cache.borrow().as_ref() {
+            let archive = self.archive_cache.borrow();
+            let no_archive = HashMap::new();
+            history::log_activity(
+                previous,
+                archive.as_ref().map_or(&no_archive, |(items, _)| items),
+                &mut data,
+                chrono::Utc::now().timestamp_millis(),
+            );
+        }
+        Self::write_cached(
+            &self.cache,
+            &data,
+            |d| self.storage.set(d),
+            || self.storage.revision(false),
+        )
     }
 
     fn save_archive(&self, data: &HashMap<String, StorageItem>) -> Result<()> {
-        self.storage.set_archive(data)
+        let mut data = data.clone();
+        if let Some((previous, _)) = self.archive_cache.borrow().as_ref() {
+            let archived: HashSet<&str> = previous.values().map(|item| item.uuid()).collect();
+            let now = chrono::Utc::now().timestamp_millis();
+            for item in data.values_mut() {
+                if !archived.contains(item.uuid()) {
+                    item.log_activity(now, "archived".to_string());
+                }
+            }
+        }
+        Self::write_cached(
+            &self.archive_cache,
+            &data,
+            |d| self.storage.set_archive(d),
+            || self.storage.revision(true),
+        )
+    }
+
+    /// Whether `cache` holds what is stored now, as far as the backend can tell
+    fn is_fresh(cache: &Cache, revision: &Option<Revision>) -> bool {
+        matches!(&*cache.borrow(), Some((_, cached)) if cached == revision)
+    }
+
+    fn read_cached(
+        cache: &Cache,
+        revision: Option<Revision>,
+        load: impl FnOnce() -> Result<HashMap<String, StorageItem>>,
+    ) -> Result<HashMap<String, StorageItem>> {
+        if Self::is_fresh(cache, &revision) {
+            if let Some((ref data, _)) = *cache.borrow() {
+                return Ok(data.clone());
+            }
+        }
+        // `revision` was taken before reading, so a write in between makes
+        // the next read go back to storage rather than trust this one
+        let data = load()?;
+        timing::mark("storage read");
+        *cache.borrow_mut() = Some((data.clone(), revision));
+        Ok(data)
+    }
+
+    fn write_cached(
+        cache: &Cache,
+        data: &HashMap<String, StorageItem>,
+        store: impl FnOnce(&HashMap<String, StorageItem>) -> Result<()>,
+        revision: impl FnOnce() -> Option<Revision>,
+    ) -> Result<()> {
+        match store(data) {
+            Ok(()) => {
+                timing::mark("storage write");
+                *cache.borrow_mut() = Some((data.clone(), revision()));
+                Ok(())
+            }
+            Err(e) => {
+                // The backend state is unknown after a failed write
+                cache.borrow_mut().take();
+                Err(e)
+            }
+        }
     }
 
     fn generate_id(&self, data: &HashMap<String, StorageItem>) -> u64 {
@@ -114,7 +394,17 @@ impl Taskbook {
         Ok(unique_ids)
     }
 
+    /// Boards of `data`, leaving out archived ones
     fn get_boards(&self, data: &HashMap<String, StorageItem>) -> Vec<String> {
+        let config = self.render.config();
+        self.all_boards(data)
+            .into_iter()
+            .filter(|b| !config.is_board_archived(b))
+            .collect()
+    }
+
+    /// Boards of `data`, including archived ones
+    fn all_boards(&self, data: &HashMap<String, StorageItem>) -> Vec<String> {
         let mut boards = vec![DEFAULT_BOARD.to_string()];
 
         // Iterate items in ID order for deterministic board discovery
@@ -137,6 +427,17 @@ impl Taskbook {
         boards
     }
 
+    /// Boards of all active items, memoized until the next write or `reload()`.
+    /// `data` must be the current result of `get_data()`.
+    fn stored_boards(&self, data: &HashMap<String, StorageItem>) -> Vec<String> {
+        if let Some(ref boards) = *self.boards_cache.borrow() {
+            return boards.clone();
+        }
+        let boards = self.get_boards(data);
+        *self.boards_cache.borrow_mut() = Some(boards.clone());
+        boards
+    }
+
     fn get_options(&self, input: &[String]) -> Result<CreateOptions> {
         if input.is_empty() {
             self.render.missing_desc();
@@ -146,7 +447,7 @@ impl Taskbook {
         let data = self.get_data()?;
         let id = self.generate_id(&data);
 
-        let (boards, description, priority, tags) = board::parse_cli_input(input);
+        let (boards, description, priority, tags, due) = board::parse_cli_input(input);
 
         Ok(CreateOptions {
             boards,
@@ -154,11 +455,12 @@ impl Taskbook {
             id,
             priority,
             tags,
+            due,
         })
     }
 
     fn get_stats(&self, data: &HashMap<String, StorageItem>) -> Stats {
-        let mut complete = 0;
+        let mut complete: usize = 0;
         let mut in_progress = 0;
         let mut pending = 0;
         let mut notes = 0;
@@ -178,11 +480,7 @@ impl Taskbook {
         }
 
         let total = complete + pending + in_progress;
-        let percent = if total == 0 {
-            0
-        } else {
-            (complete * 100 / total) as u32
-        };
+        let percent = (complete * 100).checked_div(total).unwrap_or(0) as u32;
 
         Stats {
             percent,
@@ -233,46 +531,60 @@ impl Taskbook {
         false
     }
 
-    fn filter_task(data: &mut HashMap<String, StorageItem>) {
-        data.retain(|_, item| item.is_task());
-    }
-
-    fn filter_note(data: &mut HashMap<String, StorageItem>) {
-        data.retain(|_, item| !item.is_task());
-    }
-
-    fn filter_starred(data: &mut HashMap<String, StorageItem>) {
-        data.retain(|_, item| item.is_starred());
+    /// Check an item against a single `--list` attribute keyword.
+    /// Unknown keywords match every item.
+    fn matches_attribute(item: &StorageItem, attr: &str) -> bool {
+        match attr {
+            "star" | "starred" => item.is_starred(),
+            "done" | "checked" | "complete" => {
+                item.as_task().map(|t| t.is_complete).unwrap_or(false)
+            }
+            "progress" | "started" | "begun" => {
+                item.as_task().map(|t| t.in_progress).unwrap_or(false)
+            }
+            "pending" | "unchecked" | "incomplete" => item
+                .as_task()
+                .map(|t| !t.is_complete && !t.in_progress)
+                .unwrap_or(false),
+            "todo" | "task" | "tasks" => item.is_task(),
+            "note" | "notes" => !item.is_task(),
+            _ => true,
+        }
     }
 
-    fn filter_complete(data: &mut HashMap<String, StorageItem>) {
-        data.retain(|_, item| item.as_task().map(|t| t.is_complete).unwrap_or(false));
+    fn filter_by_attributes(&self, attrs: &[String], data: &mut HashMap<String, StorageItem>) {
+        data.retain(|_, item| attrs.iter().all(|attr| Self::matches_attribute(item, attr)));
     }
 
-    fn filter_in_progress(data: &mut HashMap<String, StorageItem>) {
-        data.retain(|_, item| item.as_task().map(|t| t.in_progress).unwrap_or(false));
-    }
+    /// Check whether an item satisfies a set of `--list` terms: attribute
+    /// keywords, `+tags`, and board names (an item must be on at least one
+    /// of the named boards).
+    pub fn matches_list_terms(item: &StorageItem, terms: &[String], boards: &[String]) -> bool {
+        let mut has_board_terms = false;
+        let mut on_named_board = false;
 
-    fn filter_pending(data: &mut HashMap<String, StorageItem>) {
-        data.retain(|_, item| {
-            item.as_task()
-                .map(|t| !t.is_complete && !t.in_progress)
-                .unwrap_or(false)
-        });
-    }
+        for term in terms {
+            if term.starts_with('+') && term.len() > 1 {
+                let tag = board::normalize_tag(term);
+                if !item.tags().iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                    return false;
+                }
+                continue;
+            }
 
-    fn filter_by_attributes(&self, attrs: &[String], data: &mut HashMap<String, StorageItem>) {
-        for attr in attrs {
-            match attr.as_str() {
-                "star" | "starred" => Self::filter_starred(data),
-                "done" | "checked" | "complete" => Self::filter_complete(data),
-                "progress" | "started" | "begun" => Self::filter_in_progress(data),
-                "pending" | "unchecked" | "incomplete" => Self::filter_pending(data),
-                "todo" | "task" | "tasks" => Self::filter_task(data),
-                "note" | "notes" => Self::filter_note(data),
-                _ => {}
+            let normalized = board::normalize_board_name(term);
+            if boards.iter().any(|b| board::board_eq(b, &normalized)) {
+                has_board_terms = true;
+                on_named_board |= item
+                    .boards()
+                    .iter()
+                    .any(|b| board::board_eq(b, &normalized));
+            } else if !Self::matches_attribute(item, term) {
+                return false;
             }
         }
+
+        !has_board_terms || on_named_board
     }
 
     fn group_by_board<'a>(
@@ -293,16 +605,23 @@ impl Taskbook {
         grouped
     }
 
+    /// Group items by the first day of the `group` period they were
+    /// created in
     fn group_by_date<'a>(
         &self,
         data: &'a HashMap<String, StorageItem>,
-    ) -> HashMap<String, Vec<&'a StorageItem>> {
-        let mut grouped: HashMap<String, Vec<&StorageItem>> = HashMap::new();
+        group: TimelineGroup,
+    ) -> HashMap<NaiveDate, Vec<&'a StorageItem>> {
+        let mut grouped: HashMap<NaiveDate, Vec<&StorageItem>> = HashMap::new();
 
         for item in data.values() {
-            let date = item.date().to_string();
+            let date = group.start_of(item.created_date().unwrap_or_default());
             grouped.entry(date).or_default().push(item);
         }
+        // Oldest first, so a period lists the same way on every run
+        for items in grouped.values_mut() {
+            items.sort_by_key(|item| (item.timestamp(), item.id()));
+        }
 
         grouped
     }
@@ -312,27 +631,23 @@ impl Taskbook {
         let archive_id = self.generate_id(&archive);
 
         let mut item = item;
-        match &mut item {
-            StorageItem::Task(t) => t.id = archive_id,
-            StorageItem::Note(n) => n.id = archive_id,
-        }
+        item.set_id(archive_id);
 
         archive.insert(archive_id.to_string(), item);
         self.save_archive(&archive)
     }
 
-    fn save_item_to_storage(&self, item: StorageItem) -> Result<()> {
+    /// Add an archived item back to the boards under a fresh id, returning it
+    fn save_item_to_storage(&self, item: StorageItem) -> Result<StorageItem> {
         let mut data = self.get_data()?;
         let restore_id = self.generate_id(&data);
 
         let mut item = item;
-        match &mut item {
-            StorageItem::Task(t) => t.id = restore_id,
-            StorageItem::Note(n) => n.id = restore_id,
-        }
+        item.set_id(restore_id);
 
-        data.insert(restore_id.to_string(), item);
-        self.save(&data)
+        data.insert(restore_id.to_string(), item.clone());
+        self.save(&data)?;
+        Ok(item)
     }
 
     // Public API methods for TUI access
@@ -350,7 +665,138 @@ impl Taskbook {
     /// Get all boards (for TUI)
     pub fn get_all_boards(&self) -> Result<Vec<String>> {
         let data = self.get_data()?;
-        Ok(self.get_boards(&data))
+        Ok(self.stored_boards(&data))
+    }
+
+    /// Boards shared with other accounts, as of the last read from storage
+    pub fn get_shared_boards(&self) -> Vec<SharedBoardInfo> {
+        self.storage.shared_boards()
+    }
+
+    /// Add items from another task manager under fresh ids, skipping any
+    /// whose UUID is already stored. Returns how many were added to the
+    /// boards and to the archive, and how many were skipped. Subscribers
+    /// hear about each new item; hooks are not run.
+    pub fn import_items(
+        &self,
+        items: Vec<StorageItem>,
+        archive: Vec<StorageItem>,
+    ) -> Result<(usize, usize, usize)> {
+        let mut data = self.get_data()?;
+        let mut archived = self.get_archive()?;
+        let mut known: HashSet<String> = data
+            .values()
+            .chain(archived.values())
+            .map(|item| item.uuid().to_string())
+            .collect();
+
+        let mut created = Vec::new();
+        let mut skipped = 0;
+        for mut item in items {
+            if !known.insert(item.uuid().to_string()) {
+                skipped += 1;
+                continue;
+            }
+            let id = self.generate_id(&data);
+            item.set_id(id);
+            data.insert(id.to_string(), item.clone());
+            created.push(item);
+        }
+        let mut archived_count = 0;
+        for mut item in archive {
+            if !known.insert(item.uuid().to_string()) {
+                skipped += 1;
+                continue;
+            }
+            let id = self.generate_id(&archived);
+            item.set_id(id);
+            archived.insert(id.to_string(), item);
+            archived_count += 1;
+        }
+
+        if archived_count > 0 {
+            self.save_archive(&archived)?;
+        }
+        if !created.is_empty() {
+            self.save(&data)?;
+        }
+        let count = created.len();
+        for item in created {
+            self.notify_subscribers(&TaskbookEvent::Created(item));
+        }
+        Ok((count, archived_count, skipped))
+    }
+
+    /// Check or uncheck the tasks with the given UUIDs, to follow another
+    /// system's state. Tasks already in that state are left alone. Returns
+    /// how many changed; like `import_items`, only subscribers hear about
+    /// them.
+    pub fn set_complete_by_uuid(&self, states: &HashMap<String, bool>) -> Result<usize> {
+        let mut data = self.get_data()?;
+        let mut events = Vec::new();
+        for item in data.values_mut() {
+            let Some(&complete) = states.get(item.uuid()) else {
+                continue;
+            };
+            if let Some(task) = item.as_task_mut().filter(|t| t.is_complete != complete) {
+                task.in_progress = false;
+                task.set_complete(complete);
+                events.push(if complete {
+                    TaskbookEvent::Completed(item.clone())
+                } else {
+                    TaskbookEvent::Updated(item.clone())
+                });
+            }
+        }
+        if !events.is_empty() {
+            self.save(&data)?;
+        }
+        for event in &events {
+            self.notify_subscribers(event);
+        }
+        Ok(events.len())
+    }
+
+    /// Record `commit` on the tasks it references, checking those it closes
+    /// (`(id, closes)` pairs). References to missing ids or to notes are
+    /// ignored, and a commit already recorded on a task is not added again.
+    /// Returns the ids linked and the ids checked.
+    pub fn link_commit(
+        &self,
+        references: &[(u64, bool)],
+        commit: &LinkedCommit,
+    ) -> Result<(Vec<u64>, Vec<u64>)> {
+        let mut data = self.get_data()?;
+        let mut linked = Vec::new();
+        let mut checked = Vec::new();
+        let mut events = Vec::new();
+        for &(id, closes) in references {
+            let Some(item) = data.get_mut(&id.to_string()) else {
+                continue;
+            };
+            let Some(task) = item.as_task_mut() else {
+                continue;
+            };
+            if !task.commits.iter().any(|c| c.hash == commit.hash) {
+                task.commits.push(commit.clone());
+            }
+            linked.push(id);
+            if closes && !task.is_complete {
+                task.in_progress = false;
+                task.set_complete(true);
+                checked.push(id);
+                events.push(TaskbookEvent::Completed(item.clone()));
+            } else {
+                events.push(TaskbookEvent::Updated(item.clone()));
+            }
+        }
+        if !events.is_empty() {
+            self.save(&data)?;
+        }
+        for event in events {
+            self.emit(event);
+        }
+        Ok((linked, checked))
     }
 
     // Silent methods for TUI (no render output)
@@ -380,9 +826,10 @@ impl Taskbook {
 
         let mut data = self.get_data()?;
         let id = self.generate_id(&data);
-        let task = Task::new_with_tags(id, description, boards, priority, tags);
-        data.insert(id.to_string(), StorageItem::Task(task));
+        let task = StorageItem::Task(Task::new_with_tags(id, description, boards, priority, tags));
+        data.insert(id.to_string(), task.clone());
         self.save(&data)?;
+        self.emit_silent(TaskbookEvent::Created(task));
         Ok(id)
     }
 
@@ -405,14 +852,14 @@ impl Taskbook {
 
         let mut data = self.get_data()?;
         let id = self.generate_id(&data);
-        let note = Note::new_with_tags(id, description, boards, tags);
-        data.insert(id.to_string(), StorageItem::Note(note));
+        let note = StorageItem::Note(Note::new_with_tags(id, description, boards, tags));
+        data.insert(id.to_string(), note.clone());
         self.save(&data)?;
+        self.emit_silent(TaskbookEvent::Created(note));
         Ok(id)
     }
 
     /// Create a note with title and body (for TUI)
-    #[allow(dead_code)]
     pub fn create_note_with_body_direct(
         &self,
         boards: Vec<String>,
@@ -425,9 +872,10 @@ impl Taskbook {
 
         let mut data = self.get_data()?;
         let id = self.generate_id(&data);
-        let note = Note::new_with_body(id, title, body, boards);
-        data.insert(id.to_string(), StorageItem::Note(note));
+        let note = StorageItem::Note(Note::new_with_body(id, title, body, boards));
+        data.insert(id.to_string(), note.clone());
         self.save(&data)?;
+        self.emit_silent(TaskbookEvent::Created(note));
         Ok(id)
     }
 
@@ -443,7 +891,9 @@ impl Taskbook {
             }
         }
 
-        self.save(&data)
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &[id]);
+        Ok(())
     }
 
     /// Check tasks without CLI output (for TUI)
@@ -452,16 +902,26 @@ impl Taskbook {
         let existing_ids = self.get_ids(&data);
         let validated_ids = self.validate_ids_silent(ids, &existing_ids)?;
 
+        let mut events = Vec::new();
         for id in validated_ids {
             if let Some(item) = data.get_mut(&id.to_string()) {
                 if let Some(task) = item.as_task_mut() {
                     task.in_progress = false;
-                    task.is_complete = !task.is_complete;
+                    task.set_complete(!task.is_complete);
+                    events.push(if task.is_complete {
+                        TaskbookEvent::Completed(item.clone())
+                    } else {
+                        TaskbookEvent::Updated(item.clone())
+                    });
                 }
             }
         }
 
-        self.save(&data)
+        self.save(&data)?;
+        for event in events {
+            self.emit_silent(event);
+        }
+        Ok(())
     }
 
     /// Begin tasks without CLI output (for TUI)
@@ -470,16 +930,18 @@ impl Taskbook {
         let existing_ids = self.get_ids(&data);
         let validated_ids = self.validate_ids_silent(ids, &existing_ids)?;
 
-        for id in validated_ids {
+        for id in &validated_ids {
             if let Some(item) = data.get_mut(&id.to_string()) {
                 if let Some(task) = item.as_task_mut() {
-                    task.is_complete = false;
+                    task.set_complete(false);
                     task.in_progress = !task.in_progress;
                 }
             }
         }
 
-        self.save(&data)
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &validated_ids);
+        Ok(())
     }
 
     /// Star items without CLI output (for TUI)
@@ -488,14 +950,16 @@ impl Taskbook {
         let existing_ids = self.get_ids(&data);
         let validated_ids = self.validate_ids_silent(ids, &existing_ids)?;
 
-        for id in validated_ids {
+        for id in &validated_ids {
             if let Some(item) = data.get_mut(&id.to_string()) {
                 let new_starred = !item.is_starred();
                 item.set_starred(new_starred);
             }
         }
 
-        self.save(&data)
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &validated_ids);
+        Ok(())
     }
 
     /// Delete items without CLI output (for TUI)
@@ -504,13 +968,19 @@ impl Taskbook {
         let existing_ids = self.get_ids(&data);
         let validated_ids = self.validate_ids_silent(ids, &existing_ids)?;
 
+        let mut deleted = Vec::new();
         for id in validated_ids {
             if let Some(item) = data.remove(&id.to_string()) {
-                self.save_item_to_archive(item)?;
+                self.save_item_to_archive(item.clone())?;
+                deleted.push(item);
             }
         }
 
-        self.save(&data)
+        self.save(&data)?;
+        for item in deleted {
+            self.emit_silent(TaskbookEvent::Deleted(item));
+        }
+        Ok(())
     }
 
     /// Restore items without CLI output (for TUI)
@@ -519,13 +989,18 @@ impl Taskbook {
         let archive_ids = self.get_ids(&archive);
         let validated_ids = self.validate_ids_silent(ids, &archive_ids)?;
 
+        let mut restored = Vec::new();
         for id in validated_ids {
             if let Some(item) = archive.remove(&id.to_string()) {
-                self.save_item_to_storage(item)?;
+                restored.push(self.save_item_to_storage(item)?);
             }
         }
 
-        self.save_archive(&archive)
+        self.save_archive(&archive)?;
+        for item in restored {
+            self.emit_silent(TaskbookEvent::Restored(item));
+        }
+        Ok(())
     }
 
     /// Edit description without CLI output (for TUI)
@@ -538,24 +1013,93 @@ impl Taskbook {
             item.set_description(new_desc.to_string());
         }
 
-        self.save(&data)
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &[id]);
+        Ok(())
     }
 
-    /// Move to board without CLI output (for TUI)
-    pub fn move_boards_silent(&self, id: u64, boards: Vec<String>) -> Result<()> {
+    /// Set or clear a task's due date without CLI output (for TUI)
+    pub fn set_due_silent(&self, id: u64, due: Option<NaiveDate>) -> Result<()> {
         let mut data = self.get_data()?;
         let existing_ids = self.get_ids(&data);
         self.validate_ids_silent(&[id], &existing_ids)?;
 
+        match data.get_mut(&id.to_string()).and_then(|i| i.as_task_mut()) {
+            Some(task) => task.due = due,
+            None => return Err(TaskbookError::General("Only tasks have due dates".into())),
+        }
+
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &[id]);
+        Ok(())
+    }
+
+    /// Comment on a task without CLI output (for TUI)
+    pub fn add_comment_silent(&self, id: u64, text: &str) -> Result<()> {
+        let mut data = self.get_data()?;
+        let existing_ids = self.get_ids(&data);
+        self.validate_ids_silent(&[id], &existing_ids)?;
+        self.push_comment(&mut data, id, text)?;
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &[id]);
+        Ok(())
+    }
+
+    fn push_comment(
+        &self,
+        data: &mut HashMap<String, StorageItem>,
+        id: u64,
+        text: &str,
+    ) -> Result<()> {
+        match data.get_mut(&id.to_string()).and_then(|i| i.as_task_mut()) {
+            Some(task) => task.comments.push(Comment {
+                author: self.render.config().comment_author(),
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                text: text.trim().to_string(),
+            }),
+            None => return Err(TaskbookError::General("Only tasks have comments".into())),
+        }
+        Ok(())
+    }
+
+    /// Log a focus session on a task and pause it (for TUI)
+    pub fn log_focus_session_silent(&self, id: u64, session: FocusSession) -> Result<()> {
+        let mut data = self.get_data()?;
+        let existing_ids = self.get_ids(&data);
+        self.validate_ids_silent(&[id], &existing_ids)?;
+
+        match data.get_mut(&id.to_string()).and_then(|i| i.as_task_mut()) {
+            Some(task) => {
+                task.in_progress = false;
+                task.focus_sessions.push(session);
+            }
+            None => return Err(TaskbookError::General("Only tasks can be focused".into())),
+        }
+
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &[id]);
+        Ok(())
+    }
+
+    /// Move to board without CLI output (for TUI)
+    pub fn move_boards_silent(&self, ids: &[u64], boards: Vec<String>) -> Result<()> {
+        let mut data = self.get_data()?;
+        let existing_ids = self.get_ids(&data);
+        let ids = self.validate_ids_silent(ids, &existing_ids)?;
+
         let normalized: Vec<String> = boards
             .into_iter()
             .map(|b| board::normalize_board_name(&b))
             .collect();
-        if let Some(item) = data.get_mut(&id.to_string()) {
-            item.set_boards(normalized);
+        for id in &ids {
+            if let Some(item) = data.get_mut(&id.to_string()) {
+                item.set_boards(normalized.clone());
+            }
         }
 
-        self.save(&data)
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &ids);
+        Ok(())
     }
 
     /// Update priority without CLI output (for TUI)
@@ -570,68 +1114,185 @@ impl Taskbook {
             }
         }
 
-        self.save(&data)
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &[id]);
+        Ok(())
     }
 
-    /// Clear completed without CLI output (for TUI)
-    pub fn clear_silent(&self) -> Result<usize> {
-        let data = self.get_data()?;
-        let mut ids_to_delete: Vec<u64> = Vec::new();
+    /// Number the items in `order` by their place in it, for boards sorted
+    /// by hand, without CLI output (for TUI)
+    pub fn reorder_silent(&self, order: &[u64]) -> Result<()> {
+        let mut data = self.get_data()?;
+        let existing_ids = self.get_ids(&data);
+        let ids = self.validate_ids_silent(order, &existing_ids)?;
 
-        for (id, item) in &data {
-            if let Some(task) = item.as_task() {
-                if task.is_complete {
-                    if let Ok(id) = id.parse::<u64>() {
-                        ids_to_delete.push(id);
-                    }
+        let mut changed = Vec::new();
+        for (position, id) in ids.iter().enumerate() {
+            if let Some(item) = data.get_mut(&id.to_string()) {
+                let position = Some(position as u64);
+                if item.position() != position {
+                    item.set_position(position);
+                    changed.push(*id);
                 }
             }
         }
 
-        if ids_to_delete.is_empty() {
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &changed);
+        Ok(())
+    }
+
+    /// Archive checked tasks without CLI output (for TUI), only those on
+    /// `board` if one is given
+    pub fn clear_silent(&self, board: Option<&str>) -> Result<usize> {
+        let mut data = self.get_data()?;
+        let boards: Vec<String> = board.map(board::normalize_board_name).into_iter().collect();
+        let ids = Self::checked_ids(&data, &boards);
+        if ids.is_empty() {
             return Ok(0);
         }
 
-        let count = ids_to_delete.len();
-        let mut data = self.get_data()?;
-        for id in &ids_to_delete {
+        let mut deleted = Vec::new();
+        for id in &ids {
             if let Some(item) = data.remove(&id.to_string()) {
-                self.save_item_to_archive(item)?;
+                self.save_item_to_archive(item.clone())?;
+                deleted.push(item);
+            }
+        }
+        self.save(&data)?;
+        for item in deleted {
+            self.emit_silent(TaskbookEvent::Deleted(item));
+        }
+        Ok(ids.len())
+    }
+
+    /// Ids of the tasks `clear_silent` would archive (for TUI)
+    pub fn checked_ids_silent(&self, board: Option<&str>) -> Result<Vec<u64>> {
+        let data = self.get_data()?;
+        let boards: Vec<String> = board.map(board::normalize_board_name).into_iter().collect();
+        Ok(Self::checked_ids(&data, &boards))
+    }
+
+    /// Items `ids` as they are now, to put back with `undo_silent` (for TUI)
+    pub fn snapshot_silent(&self, ids: &[u64]) -> Result<Vec<StorageItem>> {
+        let data = self.get_data()?;
+        Ok(ids
+            .iter()
+            .filter_map(|id| data.get(&id.to_string()).cloned())
+            .collect())
+    }
+
+    /// Put items taken by `snapshot_silent` back as they were, taking them
+    /// out of the archive if they were deleted or cleared since. Items keep
+    /// their uuid in the archive, so they are matched by it; one whose id was
+    /// given to another item since comes back under a fresh id (for TUI)
+    pub fn undo_silent(&self, snapshot: &[StorageItem]) -> Result<Vec<u64>> {
+        let mut data = self.get_data()?;
+        let mut archive = self.get_archive()?;
+        let uuids: HashSet<&str> = snapshot.iter().map(|item| item.uuid()).collect();
+        let archived: HashSet<String> = archive
+            .values()
+            .filter(|item| uuids.contains(item.uuid()))
+            .map(|item| item.uuid().to_string())
+            .collect();
+        archive.retain(|_, item| !archived.contains(item.uuid()));
+        data.retain(|_, item| !uuids.contains(item.uuid()));
+
+        // Items whose id is still free go back first, so none loses it to
+        // one that needs a fresh id
+        let (free, taken): (Vec<&StorageItem>, Vec<&StorageItem>) = snapshot
+            .iter()
+            .partition(|item| !data.contains_key(&item.id().to_string()));
+        let mut ids = Vec::new();
+        for item in free.into_iter().chain(taken) {
+            let mut item = item.clone();
+            if data.contains_key(&item.id().to_string()) {
+                item.set_id(self.generate_id(&data));
             }
+            ids.push(item.id());
+            data.insert(item.id().to_string(), item);
+        }
+
+        if !archived.is_empty() {
+            self.save_archive(&archive)?;
         }
         self.save(&data)?;
-        Ok(count)
+        for item in ids.iter().filter_map(|id| data.get(&id.to_string())) {
+            if archived.contains(item.uuid()) {
+                self.emit_silent(TaskbookEvent::Restored(item.clone()));
+            } else {
+                self.emit_silent(TaskbookEvent::Updated(item.clone()));
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Ids of checked tasks, in order, only those on one of `boards` if
+    /// any are given
+    fn checked_ids(data: &HashMap<String, StorageItem>, boards: &[String]) -> Vec<u64> {
+        let mut ids: Vec<u64> = data
+            .iter()
+            .filter(|(_, item)| item.as_task().is_some_and(|task| task.is_complete))
+            .filter(|(_, item)| {
+                boards.is_empty()
+                    || item
+                        .boards()
+                        .iter()
+                        .any(|b| boards.iter().any(|board| board::board_eq(b, board)))
+            })
+            .filter_map(|(id, _)| id.parse().ok())
+            .collect();
+        ids.sort_unstable();
+        ids
     }
 
     /// Copy to clipboard without CLI output (for TUI)
-    pub fn copy_to_clipboard_silent(&self, ids: &[u64]) -> Result<()> {
+    pub fn copy_to_clipboard_silent(&self, ids: &[u64], format: CopyFormat) -> Result<()> {
         let data = self.get_data()?;
         let existing_ids = self.get_ids(&data);
         let validated_ids = self.validate_ids_silent(ids, &existing_ids)?;
 
-        let descriptions: Vec<String> = validated_ids
+        let items: Vec<&StorageItem> = validated_ids
             .iter()
             .filter_map(|id| data.get(&id.to_string()))
-            .map(|item| item.description().to_string())
             .collect();
-
-        if descriptions.is_empty() {
+        if items.is_empty() {
             return Err(TaskbookError::NoItemsToCopy);
         }
 
-        let mut clipboard =
-            Clipboard::new().map_err(|e| TaskbookError::Clipboard(e.to_string()))?;
-        clipboard
-            .set_text(descriptions.join("\n"))
-            .map_err(|e| TaskbookError::Clipboard(e.to_string()))?;
+        copy::set_clipboard(copy::format_items(&items, format)?)
+    }
 
-        Ok(())
+    /// Copy a note's title and body, without CLI output (for TUI)
+    pub fn copy_note_silent(&self, id: u64) -> Result<()> {
+        let data = self.get_data()?;
+        let item = data
+            .get(&id.to_string())
+            .ok_or(TaskbookError::InvalidId(id))?;
+        copy::set_clipboard(copy::full_note(item))
+    }
+
+    /// Copy a board's items as a Markdown checklist, without CLI output
+    /// (for TUI). Returns how many items were copied.
+    pub fn copy_board_silent(&self, board_name: &str) -> Result<usize> {
+        let data = self.get_data()?;
+        let mut items: Vec<&StorageItem> = data
+            .values()
+            .filter(|item| item.boards().iter().any(|b| board::board_eq(b, board_name)))
+            .collect();
+        if items.is_empty() {
+            return Err(TaskbookError::NoItemsToCopy);
+        }
+        items.sort_by_key(|item| item.id());
+
+        copy::set_clipboard(copy::board_checklist(board_name, &items))?;
+        Ok(items.len())
     }
 
     /// Rename a board across all items (for TUI)
     pub fn rename_board_silent(&self, old_name: &str, new_name: &str) -> Result<usize> {
         let mut data = self.get_data()?;
-        let mut count = 0;
+        let mut renamed = Vec::new();
         let normalized_new = board::normalize_board_name(new_name);
 
         for item in data.values_mut() {
@@ -648,20 +1309,21 @@ impl Taskbook {
                     })
                     .collect();
                 item.set_boards(new_boards);
-                count += 1;
+                renamed.push(item.id());
             }
         }
 
-        if count > 0 {
+        if !renamed.is_empty() {
             self.save(&data)?;
+            self.emit_updated_silent(&data, &renamed);
         }
 
-        Ok(count)
+        Ok(renamed.len())
     }
 
     // Public API methods
 
-    pub fn create_note(&self, desc: &[String]) -> Result<()> {
+    pub fn create_note(&self, desc: &[String], body: Option<String>) -> Result<()> {
         let CreateOptions {
             boards,
             description,
@@ -675,14 +1337,62 @@ impl Taskbook {
             return Err(TaskbookError::InvalidId(0));
         }
 
-        let note = Note::new_with_tags(id, description, boards, tags);
+        let mut note = Note::new_with_tags(id, description, boards, tags);
+        note.set_body(body);
+        let note = StorageItem::Note(note);
         let mut data = self.get_data()?;
-        data.insert(id.to_string(), StorageItem::Note(note));
+        data.insert(id.to_string(), note.clone());
         self.save(&data)?;
         self.render.success_create(id, false);
+        self.emit(TaskbookEvent::Created(note));
+        Ok(())
+    }
+
+    /// `tb --journal [date]`: edit the day's note on the journal board, by
+    /// default today's. A new one is only saved if the editor is.
+    pub fn open_journal(&self, input: &[String]) -> Result<()> {
+        let today = chrono::Local::now().date_naive();
+        let date = match input.first().map(|word| word.to_lowercase()) {
+            None => today,
+            Some(word) if word == "yesterday" => today - chrono::Duration::days(1),
+            Some(word) => due::parse_due(&word, today)
+                .ok_or_else(|| TaskbookError::General(format!("not a date: {word}")))?,
+        };
+
+        if let Some(id) = self.journal_note(date)? {
+            return self.edit_note_in_editor(&[format!("@{id}")]);
+        }
+        match editor::edit_existing_note_in_editor(&journal_title(date), None)? {
+            Some(content) => {
+                let id = self.create_note_with_body_direct(
+                    vec![JOURNAL_BOARD.to_string()],
+                    content.title,
+                    content.body,
+                )?;
+                self.render.success_create(id, false);
+            }
+            None => self.render.note_cancelled(),
+        }
         Ok(())
     }
 
+    /// The journal note of `date`, if there is one: a note on the journal
+    /// board whose title starts with the date
+    pub fn journal_note(&self, date: NaiveDate) -> Result<Option<u64>> {
+        let title = journal_title(date);
+        Ok(self
+            .get_data()?
+            .values()
+            .filter(|item| !item.is_task() && item.description().starts_with(&title))
+            .filter(|item| {
+                item.boards()
+                    .iter()
+                    .any(|b| board::board_eq(b, JOURNAL_BOARD))
+            })
+            .map(|item| item.id())
+            .min())
+    }
+
     /// Create a note using external editor
     pub fn create_note_with_editor(&self) -> Result<()> {
         let content = editor::create_note_in_editor()?;
@@ -691,15 +1401,16 @@ impl Taskbook {
             Some(note_content) => {
                 let mut data = self.get_data()?;
                 let id = self.generate_id(&data);
-                let note = Note::new_with_body(
+                let note = StorageItem::Note(Note::new_with_body(
                     id,
                     note_content.title,
                     note_content.body,
                     vec![DEFAULT_BOARD.to_string()],
-                );
-                data.insert(id.to_string(), StorageItem::Note(note));
+                ));
+                data.insert(id.to_string(), note.clone());
                 self.save(&data)?;
                 self.render.success_create(id, false);
+                self.emit(TaskbookEvent::Created(note));
                 Ok(())
             }
             None => {
@@ -709,9 +1420,12 @@ impl Taskbook {
         }
     }
 
-    /// Edit an existing note in external editor
-    pub fn edit_note_in_editor(&self, input: &[String]) -> Result<()> {
-        // Parse the ID from input (format: @<id>)
+    /// Parse and validate a single `@<id>` target from input
+    fn parse_target_id(
+        &self,
+        input: &[String],
+        data: &HashMap<String, StorageItem>,
+    ) -> Result<u64> {
         let targets: Vec<&String> = input.iter().filter(|x| x.starts_with('@')).collect();
 
         if targets.is_empty() {
@@ -724,14 +1438,40 @@ impl Taskbook {
             return Err(TaskbookError::InvalidId(0));
         }
 
-        let target = targets[0];
-        let id_str = target.trim_start_matches('@');
+        let id_str = targets[0].trim_start_matches('@');
         let id: u64 = id_str.parse().map_err(|_| TaskbookError::InvalidId(0))?;
 
-        let data = self.get_data()?;
-        let existing_ids = self.get_ids(&data);
+        let existing_ids = self.get_ids(data);
         let validated_ids = self.validate_ids(&[id], &existing_ids)?;
-        let id = validated_ids[0];
+        Ok(validated_ids[0])
+    }
+
+    /// Write an item's raw text to stdout without any styling, for piping.
+    /// Notes print their body (or title if they have none), tasks their description.
+    pub fn print_item_content(&self, input: &[String]) -> Result<()> {
+        let data = self.get_data()?;
+        let id = self.parse_target_id(input, &data)?;
+        let item = data
+            .get(&id.to_string())
+            .ok_or(TaskbookError::InvalidId(id))?;
+
+        let content = match item.note_body() {
+            Some(body) if item.note_has_body() => body,
+            _ => item.description(),
+        };
+
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(content.as_bytes())?;
+        if !content.ends_with('\n') {
+            stdout.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Edit an existing note in external editor
+    pub fn edit_note_in_editor(&self, input: &[String]) -> Result<()> {
+        let data = self.get_data()?;
+        let id = self.parse_target_id(input, &data)?;
 
         // Get the current note
         let item = data
@@ -754,6 +1494,7 @@ impl Taskbook {
                 }
                 self.save(&data)?;
                 self.render.success_edit(id);
+                self.emit_updated(&data, &[id]);
                 Ok(())
             }
             None => {
@@ -770,6 +1511,7 @@ impl Taskbook {
             id,
             priority,
             tags,
+            due,
         } = self.get_options(desc)?;
 
         if description.is_empty() {
@@ -777,35 +1519,31 @@ impl Taskbook {
             return Err(TaskbookError::InvalidId(0));
         }
 
-        let task = Task::new_with_tags(id, description, boards, priority, tags);
+        let mut task = Task::new_with_tags(id, description, boards, priority, tags);
+        task.due = due;
+        let task = StorageItem::Task(task);
         let mut data = self.get_data()?;
-        data.insert(id.to_string(), StorageItem::Task(task));
+        data.insert(id.to_string(), task.clone());
         self.save(&data)?;
         self.render.success_create(id, true);
+        self.emit(TaskbookEvent::Created(task));
         Ok(())
     }
 
-    pub fn copy_to_clipboard(&self, ids: &[u64]) -> Result<()> {
+    pub fn copy_to_clipboard(&self, ids: &[u64], format: CopyFormat) -> Result<()> {
         let data = self.get_data()?;
         let existing_ids = self.get_ids(&data);
         let validated_ids = self.validate_ids(ids, &existing_ids)?;
 
-        let mut descriptions = Vec::new();
-        for id in &validated_ids {
-            if let Some(item) = data.get(&id.to_string()) {
-                descriptions.push(item.description().to_string());
-            }
-        }
-
-        if descriptions.is_empty() {
+        let items: Vec<&StorageItem> = validated_ids
+            .iter()
+            .filter_map(|id| data.get(&id.to_string()))
+            .collect();
+        if items.is_empty() {
             return Err(TaskbookError::NoItemsToCopy);
         }
 
-        let mut clipboard =
-            Clipboard::new().map_err(|e| TaskbookError::Clipboard(e.to_string()))?;
-        clipboard
-            .set_text(descriptions.join("\n"))
-            .map_err(|e| TaskbookError::Clipboard(e.to_string()))?;
+        copy::set_clipboard(copy::format_items(&items, format)?)?;
 
         self.render.success_copy_to_clipboard(&validated_ids);
         Ok(())
@@ -823,7 +1561,7 @@ impl Taskbook {
             if let Some(item) = data.get_mut(&id.to_string()) {
                 if let Some(task) = item.as_task_mut() {
                     task.in_progress = false;
-                    task.is_complete = !task.is_complete;
+                    task.set_complete(!task.is_complete);
                     if task.is_complete {
                         checked.push(*id);
                     } else {
@@ -836,6 +1574,12 @@ impl Taskbook {
         self.save(&data)?;
         self.render.mark_complete(&checked);
         self.render.mark_incomplete(&unchecked);
+        for id in &checked {
+            if let Some(item) = data.get(&id.to_string()) {
+                self.emit(TaskbookEvent::Completed(item.clone()));
+            }
+        }
+        self.emit_updated(&data, &unchecked);
         Ok(())
     }
 
@@ -850,7 +1594,7 @@ impl Taskbook {
         for id in &validated_ids {
             if let Some(item) = data.get_mut(&id.to_string()) {
                 if let Some(task) = item.as_task_mut() {
-                    task.is_complete = false;
+                    task.set_complete(false);
                     task.in_progress = !task.in_progress;
                     if task.in_progress {
                         started.push(*id);
@@ -864,6 +1608,7 @@ impl Taskbook {
         self.save(&data)?;
         self.render.mark_started(&started);
         self.render.mark_paused(&paused);
+        self.emit_updated(&data, &validated_ids);
         Ok(())
     }
 
@@ -872,36 +1617,45 @@ impl Taskbook {
         let existing_ids = self.get_ids(&data);
         let validated_ids = self.validate_ids(ids, &existing_ids)?;
 
+        let mut deleted = Vec::new();
         for id in &validated_ids {
             if let Some(item) = data.remove(&id.to_string()) {
-                self.save_item_to_archive(item)?;
+                self.save_item_to_archive(item.clone())?;
+                deleted.push(item);
             }
         }
 
         self.save(&data)?;
         self.render.success_delete(&validated_ids);
+        for item in deleted {
+            self.emit(TaskbookEvent::Deleted(item));
+        }
         Ok(())
     }
 
     pub fn display_archive(&self) -> Result<()> {
         let archive = self.get_archive()?;
-        let grouped = self.group_by_date(&archive);
-        self.render.display_by_date(&grouped);
+        let group = self.render.config().timeline_group;
+        let grouped = self.group_by_date(&archive, group);
+        self.render.display_by_date(&grouped, group);
         Ok(())
     }
 
     pub fn display_by_board(&self) -> Result<()> {
         let data = self.get_data()?;
-        let boards = self.get_boards(&data);
+        let boards = self.stored_boards(&data);
         let grouped = self.group_by_board(&data, &boards);
         self.render.display_by_board(&grouped);
         Ok(())
     }
 
-    pub fn display_by_date(&self) -> Result<()> {
+    /// Display the timeline, grouped by `group` or else by the
+    /// `timelineGroup` setting
+    pub fn display_by_date(&self, group: Option<TimelineGroup>) -> Result<()> {
         let data = self.get_data()?;
-        let grouped = self.group_by_date(&data);
-        self.render.display_by_date(&grouped);
+        let group = group.unwrap_or(self.render.config().timeline_group);
+        let grouped = self.group_by_date(&data, group);
+        self.render.display_by_date(&grouped, group);
         Ok(())
     }
 
@@ -952,6 +1706,299 @@ impl Taskbook {
 
         self.save(&data)?;
         self.render.success_edit(id);
+        self.emit_updated(&data, &[id]);
+        Ok(())
+    }
+
+    /// Edit an item's description in the external editor
+    pub fn edit_description_in_editor(&self, input: &[String]) -> Result<()> {
+        let data = self.get_data()?;
+        let id = self.parse_target_id(input, &data)?;
+        let description = data
+            .get(&id.to_string())
+            .map(|item| item.description().to_string())
+            .ok_or(TaskbookError::InvalidId(id))?;
+
+        match editor::edit_description_in_editor(&description)? {
+            Some(new_desc) => {
+                let mut data = self.get_data()?;
+                if let Some(item) = data.get_mut(&id.to_string()) {
+                    item.set_description(new_desc);
+                }
+                self.save(&data)?;
+                self.render.success_edit(id);
+                self.emit_updated(&data, &[id]);
+            }
+            None => self.render.edit_cancelled(),
+        }
+        Ok(())
+    }
+
+    /// Set (`tb --due @3 fri`) or clear (`tb --due @3 none`) a task's due date
+    pub fn update_due(&self, input: &[String]) -> Result<()> {
+        let mut data = self.get_data()?;
+        let id = self.parse_target_id(input, &data)?;
+
+        let spec = input
+            .iter()
+            .find(|x| !x.starts_with('@'))
+            .map(|s| s.trim_start_matches(due::DUE_PREFIX));
+        let new_due = match spec {
+            None => {
+                self.render.missing_due();
+                return Err(TaskbookError::InvalidId(0));
+            }
+            Some("none" | "clear") => None,
+            Some(spec) => {
+                let today = chrono::Local::now().date_naive();
+                match due::parse_due(spec, today) {
+                    Some(date) => Some(date),
+                    None => {
+                        self.render.invalid_due(spec);
+                        return Err(TaskbookError::General(format!(
+                            "invalid due date: {}",
+                            spec
+                        )));
+                    }
+                }
+            }
+        };
+
+        match data.get_mut(&id.to_string()).and_then(|i| i.as_task_mut()) {
+            Some(task) => task.due = new_due,
+            None => return Err(TaskbookError::General("Only tasks have due dates".into())),
+        }
+
+        self.save(&data)?;
+        self.render.success_due(id, new_due);
+        self.emit_updated(&data, &[id]);
+        Ok(())
+    }
+
+    /// Comment on a task: `@<id> <text>`. Without text, list its comments.
+    pub fn add_comment(&self, input: &[String]) -> Result<()> {
+        let mut data = self.get_data()?;
+        // Only the first word names the task, so the text may mention @someone
+        let Some((target, words)) = input.split_first() else {
+            self.render.missing_id();
+            return Err(TaskbookError::InvalidId(0));
+        };
+        let id = self.parse_target_id(std::slice::from_ref(target), &data)?;
+
+        let text = words.join(" ");
+        if text.trim().is_empty() {
+            let task = data
+                .get(&id.to_string())
+                .and_then(|item| item.as_task())
+                .ok_or_else(|| TaskbookError::General("Only tasks have comments".into()))?;
+            self.render.display_comments(id, &task.comments);
+            return Ok(());
+        }
+
+        self.push_comment(&mut data, id, &text)?;
+        self.save(&data)?;
+        self.render.success_comment(id);
+        self.emit_updated(&data, &[id]);
+        Ok(())
+    }
+
+    /// Attach files or URLs to an item: `@<id> <path|url>...`. Without any,
+    /// list the item's attachments.
+    pub fn attach(&self, input: &[String]) -> Result<()> {
+        let mut data = self.get_data()?;
+        let Some((target, targets)) = input.split_first() else {
+            self.render.missing_id();
+            return Err(TaskbookError::InvalidId(0));
+        };
+        let id = self.parse_target_id(std::slice::from_ref(target), &data)?;
+
+        if targets.is_empty() {
+            let attachments = data[&id.to_string()].attachments();
+            self.render.display_attachments(id, attachments);
+            return Ok(());
+        }
+
+        let added = push_attachments(&mut data, id, targets)?;
+        self.save(&data)?;
+        self.render.success_attach(id, &added);
+        self.emit_updated(&data, &[id]);
+        Ok(())
+    }
+
+    /// Attach files or URLs to an item, returning the new ones (for TUI)
+    pub fn attach_silent(&self, id: u64, targets: &[String]) -> Result<Vec<String>> {
+        let mut data = self.get_data()?;
+        let existing_ids = self.get_ids(&data);
+        self.validate_ids_silent(&[id], &existing_ids)?;
+        let added = push_attachments(&mut data, id, targets)?;
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &[id]);
+        Ok(added)
+    }
+
+    /// Remove attachments from an item: `@<id> <number|path|url>...`, by
+    /// the number `--attach @<id>` lists them with, or as attached
+    pub fn detach(&self, input: &[String]) -> Result<()> {
+        let mut data = self.get_data()?;
+        let Some((target, targets)) = input.split_first() else {
+            self.render.missing_id();
+            return Err(TaskbookError::InvalidId(0));
+        };
+        let id = self.parse_target_id(std::slice::from_ref(target), &data)?;
+        let Some(item) = data.get_mut(&id.to_string()) else {
+            return Err(TaskbookError::InvalidId(id));
+        };
+
+        let mut removed = Vec::new();
+        for target in targets {
+            let attachments = item.attachments();
+            let found = match target.parse::<usize>() {
+                Ok(number) => attachments.get(number.wrapping_sub(1)).cloned(),
+                Err(_) => attachments
+                    .iter()
+                    .find(|a| *a == target || attachments::resolve(target).is_ok_and(|r| **a == r))
+                    .cloned(),
+            };
+            let Some(found) = found else {
+                return Err(TaskbookError::General(format!(
+                    "item {id} has no attachment {target}"
+                )));
+            };
+            item.attachments_mut().retain(|a| *a != found);
+            removed.push(found);
+        }
+        if removed.is_empty() {
+            return Err(TaskbookError::General(
+                "usage: tb --detach @<id> <number|path|url>...".into(),
+            ));
+        }
+
+        self.save(&data)?;
+        self.render.success_detach(id, &removed);
+        self.emit_updated(&data, &[id]);
+        Ok(())
+    }
+
+    /// Open an item's attachment or link with the default application:
+    /// `@<id> [number]`, the first one without a number
+    pub fn open(&self, input: &[String]) -> Result<()> {
+        let data = self.get_data()?;
+        let Some((target, rest)) = input.split_first() else {
+            self.render.missing_id();
+            return Err(TaskbookError::InvalidId(0));
+        };
+        // `tb --open 3` reads as naturally as `tb --open @3`
+        let target = format!("@{}", target.trim_start_matches('@'));
+        let id = self.parse_target_id(&[target], &data)?;
+        let number = match rest.first() {
+            Some(n) => n
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| TaskbookError::General(format!("not a number: {n}")))?,
+            None => 1,
+        };
+
+        let targets = attachments::openable(&data[&id.to_string()]);
+        let Some(target) = targets.get(number - 1) else {
+            return Err(TaskbookError::General(if targets.is_empty() {
+                format!("item {id} has no attachments or links")
+            } else {
+                format!("item {id} has no attachment or link {number}")
+            }));
+        };
+        opener::open(target)?;
+        self.render.success_open(target);
+        Ok(())
+    }
+
+    /// Display overdue, due today, in-progress, and starred items
+    pub fn display_agenda(&self) -> Result<()> {
+        let data = self.get_data()?;
+        let today = chrono::Local::now().date_naive();
+        let sections = agenda::group(data.values(), today);
+        self.render.display_agenda(&sections);
+        Ok(())
+    }
+
+    /// Show open and completed task counts per day for a board (or all
+    /// boards when `input` is empty)
+    pub fn display_burndown(&self, input: &[String]) -> Result<()> {
+        let data = self.get_data()?;
+        let name = input.join(" ");
+        let board_name = (!name.trim().is_empty()).then(|| board::normalize_board_name(&name));
+
+        if let Some(ref board_name) = board_name {
+            let boards = self.stored_boards(&data);
+            if !boards.iter().any(|b| board::board_eq(b, board_name)) {
+                self.render.missing_board(board_name);
+                return Err(TaskbookError::General(format!(
+                    "board not found: {board_name}"
+                )));
+            }
+        }
+
+        let today = chrono::Local::now().date_naive();
+        let days = burndown::build(
+            data.values(),
+            board_name.as_deref(),
+            today,
+            burndown::BURNDOWN_DAYS,
+        );
+        self.render.display_burndown(board_name.as_deref(), &days);
+        Ok(())
+    }
+
+    /// Show an item's activity log, then the versions of it kept by the
+    /// sync server and what changed between them
+    pub fn display_history(&self, input: &[String]) -> Result<()> {
+        let data = self.get_data()?;
+        let id = self.parse_target_id(input, &data)?;
+        let item = data
+            .get(&id.to_string())
+            .ok_or(TaskbookError::InvalidId(id))?;
+
+        let versions = if self.storage.keeps_history() {
+            Some(history::collapse(self.storage.item_history(item.uuid())?))
+        } else {
+            None
+        };
+        self.render
+            .display_history(id, item.activity(), versions.as_deref());
+        Ok(())
+    }
+
+    /// List the git commits that referenced a task
+    pub fn display_commits(&self, input: &[String]) -> Result<()> {
+        let data = self.get_data()?;
+        let id = self.parse_target_id(input, &data)?;
+        let task = data
+            .get(&id.to_string())
+            .ok_or(TaskbookError::InvalidId(id))?
+            .as_task()
+            .ok_or_else(|| TaskbookError::General("Only tasks have commits".into()))?;
+
+        self.render.display_commits(id, &task.commits);
+        Ok(())
+    }
+
+    /// Summarize activity over the last week or month, as text or JSON
+    pub fn display_report(&self, period: ReportPeriod, json: bool) -> Result<()> {
+        let data = self.get_data()?;
+        let archive = self.get_archive()?;
+        let today = chrono::Local::now().date_naive();
+        let report = Report::build(
+            period,
+            today,
+            data.values().chain(archive.values()),
+            data.values(),
+        );
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            self.render.display_report(&report);
+        }
         Ok(())
     }
 
@@ -965,15 +2012,18 @@ impl Taskbook {
             }
         }
 
-        let boards = self.get_boards(&result);
+        // Matches on archived boards are still shown
+        let boards = self.all_boards(&result);
         let grouped = self.group_by_board(&result, &boards);
+        self.render.highlight_matches(terms);
         self.render.display_by_board(&grouped);
         Ok(())
     }
 
     pub fn list_by_attributes(&self, terms: &[String]) -> Result<()> {
         let data = self.get_data()?;
-        let stored_boards = self.get_boards(&data);
+        // Archived boards can still be listed by name
+        let stored_boards = self.all_boards(&data);
 
         let mut boards: Vec<String> = Vec::new();
         let mut attributes: Vec<String> = Vec::new();
@@ -1022,35 +2072,115 @@ impl Taskbook {
         Ok(())
     }
 
-    pub fn move_boards(&self, input: &[String]) -> Result<()> {
-        let targets: Vec<&String> = input.iter().filter(|x| x.starts_with('@')).collect();
+    /// Archive (or unarchive) the board named by `input`, hiding it from the
+    /// board view and board pickers. Its items are kept.
+    pub fn set_board_archived(&self, input: &[String], archived: bool) -> Result<()> {
+        let name = input.join(" ");
+        if name.trim().is_empty() {
+            self.render.missing_boards();
+            return Err(TaskbookError::General("no board given".to_string()));
+        }
+        let name = board::normalize_board_name(&name);
+        if board::board_eq(&name, DEFAULT_BOARD) {
+            return Err(TaskbookError::General(format!(
+                "{DEFAULT_BOARD} cannot be archived"
+            )));
+        }
 
-        if targets.is_empty() {
-            self.render.missing_id();
-            return Err(TaskbookError::InvalidId(0));
+        let mut config = Config::load()?;
+        let data = self.get_data()?;
+        let known = self
+            .all_boards(&data)
+            .into_iter()
+            .chain(config.archived_boards.iter().cloned())
+            .find(|b| board::board_eq(b, &name));
+        let Some(name) = known else {
+            self.render.missing_board(&name);
+            return Err(TaskbookError::General(format!("board not found: {name}")));
+        };
+
+        let changed = config.set_board_archived(&name, archived);
+        config.save()?;
+        self.render.success_board_archived(&name, archived, changed);
+        Ok(())
+    }
+
+    /// List all boards with their item counts, archived ones included
+    pub fn display_board_list(&self) -> Result<()> {
+        let data = self.get_data()?;
+        let config = self.render.config();
+        let mut boards = self.all_boards(&data);
+        for name in &config.archived_boards {
+            if !boards.iter().any(|b| board::board_eq(b, name)) {
+                boards.push(name.clone());
+            }
         }
 
-        if targets.len() > 1 {
-            self.render.invalid_ids_number();
-            return Err(TaskbookError::InvalidId(0));
+        let rows: Vec<(String, usize, bool)> = boards
+            .into_iter()
+            .map(|name| {
+                let count = data
+                    .values()
+                    .filter(|item| item.boards().iter().any(|b| board::board_eq(b, &name)))
+                    .count();
+                let archived = config.is_board_archived(&name);
+                (name, count, archived)
+            })
+            .collect();
+        self.render.display_board_list(&rows);
+        Ok(())
+    }
+
+    /// Look up the terms of a saved filter
+    pub fn saved_filter(&self, name: &str) -> Result<Vec<String>> {
+        let config = Config::load_or_default();
+        config
+            .saved_filters
+            .get(name)
+            .cloned()
+            .ok_or_else(|| TaskbookError::General(format!("No saved filter named '{}'", name)))
+    }
+
+    /// Persist a set of `--list` terms under the given name
+    pub fn save_filter(&self, name: &str, terms: &[String]) -> Result<()> {
+        let mut config = Config::load()?;
+        config
+            .saved_filters
+            .insert(name.to_string(), terms.to_vec());
+        config.save()?;
+        self.render.success_save_filter(name, terms);
+        Ok(())
+    }
+
+    /// Move the items given as `@id` to the other words' boards
+    pub fn move_boards(&self, input: &[String]) -> Result<()> {
+        // The last word is always a board, so `@2024` can still name one
+        let (mut targets, mut board_words): (Vec<&String>, Vec<&String>) = (Vec::new(), Vec::new());
+        if let Some((last, rest)) = input.split_last() {
+            (targets, board_words) = rest
+                .iter()
+                .partition(|word| Self::parse_target(word).is_some());
+            board_words.push(last);
         }
+        let ids: Vec<u64> = targets
+            .iter()
+            .filter_map(|w| Self::parse_target(w))
+            .collect();
 
-        let target = targets[0];
-        let id_str = target.trim_start_matches('@');
-        let id: u64 = id_str.parse().map_err(|_| TaskbookError::InvalidId(0))?;
+        if ids.is_empty() {
+            self.render.missing_id();
+            return Err(TaskbookError::InvalidId(0));
+        }
 
         let mut data = self.get_data()?;
         let existing_ids = self.get_ids(&data);
-        let validated_ids = self.validate_ids(&[id], &existing_ids)?;
-        let id = validated_ids[0];
+        let ids = self.validate_ids(&ids, &existing_ids)?;
 
         let mut boards: Vec<String> = Vec::new();
-        for word in input {
-            if word != target {
-                let normalized = board::normalize_board_name(word);
-                if !boards.iter().any(|b| board::board_eq(b, &normalized)) {
-                    boards.push(normalized);
-                }
+        for word in board_words {
+            let normalized = board::normalize_board_name(word);
+            if !boards.iter().any(|b| board::board_eq(b, &normalized)) {
+                boards.push(normalized);
             }
         }
 
@@ -1059,29 +2189,41 @@ impl Taskbook {
             return Err(TaskbookError::InvalidId(0));
         }
 
-        if let Some(item) = data.get_mut(&id.to_string()) {
-            item.set_boards(boards.clone());
+        for id in &ids {
+            if let Some(item) = data.get_mut(&id.to_string()) {
+                item.set_boards(boards.clone());
+            }
         }
 
         self.save(&data)?;
         let display_boards: Vec<String> = boards.iter().map(|b| board::display_name(b)).collect();
-        self.render.success_move(id, &display_boards);
+        self.render.success_move(&ids, &display_boards);
+        self.emit_updated(&data, &ids);
         Ok(())
     }
 
+    /// The id in an `@id` word
+    fn parse_target(word: &str) -> Option<u64> {
+        word.strip_prefix('@')?.parse().ok()
+    }
+
     pub fn restore_items(&self, ids: &[u64]) -> Result<()> {
         let mut archive = self.get_archive()?;
         let archive_ids = self.get_ids(&archive);
         let validated_ids = self.validate_ids(ids, &archive_ids)?;
 
+        let mut restored = Vec::new();
         for id in &validated_ids {
             if let Some(item) = archive.remove(&id.to_string()) {
-                self.save_item_to_storage(item)?;
+                restored.push(self.save_item_to_storage(item)?);
             }
         }
 
         self.save_archive(&archive)?;
         self.render.success_restore(&validated_ids);
+        for item in restored {
+            self.emit(TaskbookEvent::Restored(item));
+        }
         Ok(())
     }
 
@@ -1108,14 +2250,15 @@ impl Taskbook {
         self.save(&data)?;
         self.render.mark_starred(&starred);
         self.render.mark_unstarred(&unstarred);
+        self.emit_updated(&data, &validated_ids);
         Ok(())
     }
 
     pub fn update_priority(&self, input: &[String]) -> Result<()> {
         let level = input
             .iter()
-            .find(|x| matches!(x.as_str(), "1" | "2" | "3"))
-            .map(|s| s.parse::<u8>().unwrap());
+            .filter(|x| !x.starts_with('@'))
+            .find_map(|x| priority::parse_priority(x));
 
         let level = match level {
             Some(l) => l,
@@ -1154,36 +2297,57 @@ impl Taskbook {
 
         self.save(&data)?;
         self.render.success_priority(id, level);
+        self.emit_updated(&data, &[id]);
         Ok(())
     }
 
-    pub fn clear(&self) -> Result<()> {
-        let data = self.get_data()?;
-        let mut ids_to_delete: Vec<u64> = Vec::new();
-
-        for (id, item) in &data {
-            if let Some(task) = item.as_task() {
-                if task.is_complete {
-                    if let Ok(id) = id.parse::<u64>() {
-                        ids_to_delete.push(id);
-                    }
-                }
+    /// Archive checked tasks, only those on the boards in `input` if any
+    /// are given. With `dry_run`, list them instead.
+    pub fn clear(&self, input: &[String], dry_run: bool) -> Result<()> {
+        let mut data = self.get_data()?;
+        let mut boards: Vec<String> = Vec::new();
+        for word in input {
+            let normalized = board::normalize_board_name(word);
+            if !boards.iter().any(|b| board::board_eq(b, &normalized)) {
+                boards.push(normalized);
             }
         }
+        let ids_to_delete = Self::checked_ids(&data, &boards);
+
+        if dry_run {
+            let checked: HashMap<String, StorageItem> = ids_to_delete
+                .iter()
+                .filter_map(|id| data.get_key_value(&id.to_string()))
+                .map(|(id, item)| (id.clone(), item.clone()))
+                .collect();
+            let shown = if boards.is_empty() {
+                self.all_boards(&checked)
+            } else {
+                boards
+            };
+            self.render
+                .display_by_board(&self.group_by_board(&checked, &shown));
+            self.render.clear_preview(&ids_to_delete);
+            return Ok(());
+        }
 
         if ids_to_delete.is_empty() {
             return Ok(());
         }
 
         // Delete items without the success message (we'll use success_clear instead)
-        let mut data = self.get_data()?;
+        let mut deleted = Vec::new();
         for id in &ids_to_delete {
             if let Some(item) = data.remove(&id.to_string()) {
-                self.save_item_to_archive(item)?;
+                self.save_item_to_archive(item.clone())?;
+                deleted.push(item);
             }
         }
         self.save(&data)?;
         self.render.success_clear(&ids_to_delete);
+        for item in deleted {
+            self.emit(TaskbookEvent::Deleted(item));
+        }
         Ok(())
     }
 
@@ -1255,6 +2419,7 @@ impl Taskbook {
 
         self.save(&data)?;
         self.render.success_tag(id, &add_tags, &remove_tags);
+        self.emit_updated(&data, &[id]);
         Ok(())
     }
 
@@ -1283,6 +2448,366 @@ impl Taskbook {
             item.set_tags(current_tags);
         }
 
-        self.save(&data)
+        self.save(&data)?;
+        self.emit_updated_silent(&data, &[id]);
+        Ok(())
+    }
+}
+
+/// Title of the journal note of `date`, such as `2024-05-01`
+pub fn journal_title(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Resolve `targets` and add the ones not attached to the item yet
+fn push_attachments(
+    data: &mut HashMap<String, StorageItem>,
+    id: u64,
+    targets: &[String],
+) -> Result<Vec<String>> {
+    let item = data
+        .get_mut(&id.to_string())
+        .ok_or(TaskbookError::InvalidId(id))?;
+    let mut added = Vec::new();
+    for target in targets {
+        let attachment = attachments::resolve(target)?;
+        if !item.attachments().contains(&attachment) {
+            item.attachments_mut().push(attachment.clone());
+            added.push(attachment);
+        }
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn in_memory(storage: &MemoryStorage) -> Taskbook {
+        Taskbook::builder()
+            .config(Config::default())
+            .storage(Box::new(storage.clone()))
+            .render(false)
+            .build()
+            .unwrap()
+    }
+
+    fn words(input: &str) -> Vec<String> {
+        input.split_whitespace().map(String::from).collect()
+    }
+
+    /// Counts reads and can be made to fail writes, to watch the cache
+    #[derive(Clone, Default)]
+    struct CountingStorage {
+        inner: MemoryStorage,
+        reads: Rc<Cell<usize>>,
+        fail_writes: Rc<Cell<bool>>,
+    }
+
+    impl StorageBackend for CountingStorage {
+        fn get(&self) -> Result<HashMap<String, StorageItem>> {
+            self.reads.set(self.reads.get() + 1);
+            self.inner.get()
+        }
+
+        fn get_archive(&self) -> Result<HashMap<String, StorageItem>> {
+            self.inner.get_archive()
+        }
+
+        fn set(&self, data: &HashMap<String, StorageItem>) -> Result<()> {
+            if self.fail_writes.get() {
+                return Err(TaskbookError::General("write failed".to_string()));
+            }
+            self.inner.set(data)
+        }
+
+        fn set_archive(&self, data: &HashMap<String, StorageItem>) -> Result<()> {
+            self.inner.set_archive(data)
+        }
+    }
+
+    #[test]
+    fn builder_uses_injected_storage() {
+        let storage = MemoryStorage::new();
+        let taskbook = in_memory(&storage);
+
+        taskbook
+            .create_task(&words("@work Write report p:3"))
+            .unwrap();
+        taskbook.delete_items(&[1]).unwrap();
+        taskbook.restore_items(&[1]).unwrap();
+
+        let data = storage.get().unwrap();
+        let task = data["1"].as_task().unwrap();
+        assert_eq!(task.description, "Write report");
+        assert_eq!(task.priority, 3);
+        assert!(storage.get_archive().unwrap().is_empty());
+    }
+
+    #[test]
+    fn archived_boards_are_left_out_of_board_lists() {
+        let storage = MemoryStorage::new();
+        let taskbook = Taskbook::builder()
+            .config(Config {
+                archived_boards: vec!["Travel".to_string()],
+                ..Config::default()
+            })
+            .storage(Box::new(storage.clone()))
+            .render(false)
+            .build()
+            .unwrap();
+
+        taskbook.create_task(&words("@travel Book hotel")).unwrap();
+        taskbook.create_task(&words("@work Write report")).unwrap();
+
+        assert_eq!(
+            taskbook.get_all_boards().unwrap(),
+            vec![DEFAULT_BOARD.to_string(), "work".to_string()]
+        );
+        assert_eq!(taskbook.get_all_items().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn clear_can_be_limited_to_boards_and_previewed() {
+        let storage = MemoryStorage::new();
+        let taskbook = in_memory(&storage);
+
+        taskbook.create_task(&words("@coding Fix bug")).unwrap();
+        taskbook.create_task(&words("@coding Write docs")).unwrap();
+        taskbook.create_task(&words("@home Water plants")).unwrap();
+        taskbook.check_tasks(&[1, 3]).unwrap();
+
+        taskbook.clear(&words("@Coding"), true).unwrap();
+        assert_eq!(storage.get().unwrap().len(), 3);
+
+        taskbook.clear(&words("@coding"), false).unwrap();
+        let data = storage.get().unwrap();
+        assert!(!data.contains_key("1"));
+        assert!(data.contains_key("2") && data.contains_key("3"));
+
+        assert_eq!(taskbook.checked_ids_silent(None).unwrap(), vec![3]);
+        assert_eq!(taskbook.clear_silent(Some("coding")).unwrap(), 0);
+        assert_eq!(taskbook.clear_silent(None).unwrap(), 1);
+        assert_eq!(storage.get_archive().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn undo_puts_back_edited_and_deleted_items() {
+        let storage = MemoryStorage::new();
+        let taskbook = in_memory(&storage);
+
+        taskbook.create_task(&words("@coding Fix bug")).unwrap();
+        taskbook.create_task(&words("@coding Write docs")).unwrap();
+
+        let snapshot = taskbook.snapshot_silent(&[1]).unwrap();
+        taskbook.edit_description_silent(1, "Fix the bug").unwrap();
+        taskbook.check_tasks_silent(&[1]).unwrap();
+        assert_eq!(taskbook.undo_silent(&snapshot).unwrap(), vec![1]);
+        let task = storage.get().unwrap()["1"].as_task().unwrap().clone();
+        assert_eq!(task.description, "Fix bug");
+        assert!(!task.is_complete);
+
+        let snapshot = taskbook.snapshot_silent(&[1, 2]).unwrap();
+        taskbook.delete_items_silent(&[1, 2]).unwrap();
+        assert_eq!(storage.get_archive().unwrap().len(), 2);
+        taskbook.create_task(&words("Water plants")).unwrap();
+        assert_eq!(taskbook.undo_silent(&snapshot).unwrap(), vec![2, 3]);
+        let data = storage.get().unwrap();
+        assert_eq!(data["1"].description(), "Water plants");
+        assert_eq!(data["2"].description(), "Write docs");
+        assert_eq!(data["3"].description(), "Fix bug");
+        assert!(storage.get_archive().unwrap().is_empty());
+    }
+
+    #[test]
+    fn journal_notes_are_found_by_date_on_the_journal_board() {
+        let storage = MemoryStorage::new();
+        let taskbook = in_memory(&storage);
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+
+        taskbook
+            .create_note(&words("@work 2024-05-01"), None)
+            .unwrap();
+        assert_eq!(taskbook.journal_note(date).unwrap(), None);
+
+        let id = taskbook
+            .create_note_with_body_direct(
+                vec!["journal".to_string()],
+                format!("{} Wednesday", journal_title(date)),
+                Some("Shipped it".to_string()),
+            )
+            .unwrap();
+        assert_eq!(taskbook.journal_note(date).unwrap(), Some(id));
+        assert_eq!(
+            taskbook.journal_note(date.succ_opt().unwrap()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn activity_log_records_each_change() {
+        let storage = MemoryStorage::new();
+        let taskbook = in_memory(&storage);
+
+        taskbook.create_task(&words("@work Ship it")).unwrap();
+        taskbook.star_items(&[1]).unwrap();
+        taskbook.move_boards(&words("@1 release")).unwrap();
+        taskbook.check_tasks(&[1]).unwrap();
+        taskbook.delete_items(&[1]).unwrap();
+        taskbook.restore_items(&[1]).unwrap();
+
+        let data = storage.get().unwrap();
+        let actions: Vec<&str> = data["1"]
+            .activity()
+            .iter()
+            .map(|entry| entry.action.as_str())
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                "created",
+                "starred",
+                "moved to @release",
+                "checked",
+                "archived",
+                "restored"
+            ]
+        );
+    }
+
+    #[test]
+    fn subscribers_see_each_change() {
+        let storage = MemoryStorage::new();
+        let mut taskbook = in_memory(&storage);
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&seen);
+        taskbook.subscribe(Box::new(move |event| {
+            let kind = match event {
+                TaskbookEvent::Created(_) => "created",
+                TaskbookEvent::Updated(_) => "updated",
+                TaskbookEvent::Completed(_) => "completed",
+                TaskbookEvent::Deleted(_) => "deleted",
+                TaskbookEvent::Restored(_) => "restored",
+            };
+            sink.borrow_mut().push((kind, event.item().id()));
+        }));
+
+        taskbook.create_task(&words("First")).unwrap();
+        taskbook.star_items(&[1]).unwrap();
+        taskbook.check_tasks(&[1]).unwrap();
+        taskbook.check_tasks_silent(&[1]).unwrap();
+        taskbook.delete_items_silent(&[1]).unwrap();
+
+        assert_eq!(
+            *seen.borrow(),
+            vec![
+                ("created", 1),
+                ("updated", 1),
+                ("completed", 1),
+                ("updated", 1),
+                ("deleted", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_come_from_the_cache_until_reload() {
+        let storage = CountingStorage::default();
+        let taskbook = Taskbook::builder()
+            .config(Config::default())
+            .storage(Box::new(storage.clone()))
+            .render(false)
+            .build()
+            .unwrap();
+
+        taskbook.create_task(&words("First")).unwrap();
+        taskbook.get_all_items().unwrap();
+        taskbook.get_all_items().unwrap();
+        assert_eq!(storage.reads.get(), 1);
+
+        // Written data is cached as written
+        taskbook.create_task(&words("Second")).unwrap();
+        assert_eq!(taskbook.get_all_items().unwrap().len(), 2);
+        assert_eq!(storage.reads.get(), 1);
+
+        // A change made outside this Taskbook shows up only after reload
+        storage.inner.set(&HashMap::new()).unwrap();
+        assert_eq!(taskbook.get_all_items().unwrap().len(), 2);
+        taskbook.reload();
+        assert!(taskbook.get_all_items().unwrap().is_empty());
+        assert_eq!(storage.reads.get(), 2);
+    }
+
+    #[test]
+    fn failed_writes_drop_the_cache() {
+        let storage = CountingStorage::default();
+        let taskbook = Taskbook::builder()
+            .config(Config::default())
+            .storage(Box::new(storage.clone()))
+            .render(false)
+            .build()
+            .unwrap();
+
+        taskbook.create_task(&words("First")).unwrap();
+        storage.fail_writes.set(true);
+        assert!(taskbook.create_task(&words("Second")).is_err());
+        storage.fail_writes.set(false);
+
+        let reads = storage.reads.get();
+        assert_eq!(taskbook.get_all_items().unwrap().len(), 1);
+        assert_eq!(storage.reads.get(), reads + 1);
+    }
+
+    #[test]
+    fn writes_from_another_taskbook_on_the_same_files_are_kept() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let open = || {
+            Taskbook::builder()
+                .config(Config::default())
+                .storage(Box::new(LocalStorage::plain(dir.path()).unwrap()))
+                .render(false)
+                .build()
+                .unwrap()
+        };
+        let first = open();
+        let second = open();
+
+        first.create_task(&words("First")).unwrap();
+        second.create_task(&words("Second")).unwrap();
+        first.create_task(&words("Third")).unwrap();
+
+        let data = LocalStorage::plain(dir.path()).unwrap().get().unwrap();
+        let mut descriptions: Vec<&str> = data.values().map(|item| item.description()).collect();
+        descriptions.sort_unstable();
+        assert_eq!(descriptions, ["First", "Second", "Third"]);
+        assert_eq!(first.get_all_items().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn items_in_a_period_are_ordered_by_creation_then_id() {
+        let taskbook = in_memory(&MemoryStorage::new());
+        let task = |id: u64, timestamp: i64| {
+            let mut task = Task::new(id, format!("Task {id}"), vec![DEFAULT_BOARD.into()], 1);
+            task.timestamp += timestamp;
+            StorageItem::Task(task)
+        };
+
+        for _ in 0..8 {
+            let data: HashMap<String, StorageItem> = [task(1, 300), task(2, 100), task(3, 100)]
+                .into_iter()
+                .map(|item| (item.id().to_string(), item))
+                .collect();
+            let grouped = taskbook.group_by_date(&data, TimelineGroup::Week);
+            let ids: Vec<Vec<u64>> = grouped
+                .values()
+                .map(|items| items.iter().map(|item| item.id()).collect())
+                .collect();
+            assert_eq!(ids, vec![vec![2, 3, 1]]);
+        }
     }
 }
@@ -0,0 +1,22 @@
+//! Opt-in timing output for `tb --debug-timing`
+
+use std::sync::OnceLock;
+use std::time::Instant;
+
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// Start reporting timings, measured from this call
+pub fn enable() {
+    let _ = START.set(Instant::now());
+}
+
+/// Print the time elapsed since `enable()` to stderr. No-op unless enabled.
+pub fn mark(label: &str) {
+    if let Some(start) = START.get() {
+        eprintln!(
+            "[timing] {:>8.2}ms  {}",
+            start.elapsed().as_secs_f64() * 1000.0,
+            label
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! `tb sync trash`: items the server kept after an upload left them out,
+//! and putting them back.
+
+use chrono::{DateTime, Local};
+use colored::Colorize;
+
+use crate::api_client::{ApiClient, TrashedItem};
+use crate::credentials::Credentials;
+use crate::error::{Result, TaskbookError};
+use crate::relative_time;
+use crate::sharing;
+use crate::storage::decrypt_one;
+
+/// Characters of an item key shown, and enough to restore it by
+const SHORT_KEY_LEN: usize = 8;
+
+fn logged_in() -> Result<(Credentials, ApiClient)> {
+    let creds = Credentials::load()?.ok_or_else(|| {
+        TaskbookError::Auth("not logged in — run `tb register` or `tb login` first".to_string())
+    })?;
+    let client = ApiClient::from_credentials(&creds);
+    Ok((creds, client))
+}
+
+/// List the items in the trash, newest first.
+pub fn list() -> Result<()> {
+    let (creds, client) = logged_in()?;
+    let trash = client.get_trash()?;
+    if trash.items.is_empty() {
+        println!(
+            "The trash is empty. Deleted items are kept for {} days.",
+            trash.retention_days
+        );
+        return Ok(());
+    }
+
+    let key = creds.encryption_key_bytes()?;
+    let now = Local::now();
+    let mut unreadable = 0;
+
+    for trashed in &trash.items {
+        let item = match decrypt_one(&key, &trashed.item) {
+            Ok(item) => item,
+            Err(TaskbookError::WrongKey) => {
+                unreadable += 1;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let short_key: String = trashed.key.chars().take(SHORT_KEY_LEN).collect();
+        let deleted = DateTime::parse_from_rfc3339(&trashed.deleted_at)
+            .map(|t| relative_time::format(t.timestamp_millis(), now))
+            .unwrap_or_else(|_| trashed.deleted_at.clone());
+        let mut line = format!("{}  {}", short_key.bold(), item.description());
+        for board in item.boards() {
+            line.push_str(&format!(" {}", format!("@{board}").cyan()));
+        }
+        if trashed.archived {
+            line.push_str(&format!("  {}", "(archived)".yellow()));
+        }
+        println!(
+            "{line}  {}",
+            format!(
+                "deleted {deleted}, kept until {}",
+                sharing::local_time(&trashed.purge_at)
+            )
+            .dimmed()
+        );
+    }
+    if unreadable > 0 {
+        println!(
+            "{}",
+            format!(
+                "{unreadable} deleted item{} encrypted with an earlier key cannot be shown.",
+                if unreadable == 1 { " was" } else { "s were" }
+            )
+            .yellow()
+        );
+    }
+    println!();
+    println!(
+        "{}",
+        "Restore with `tb sync trash --restore <id>...` or `--restore all`.".dimmed()
+    );
+
+    Ok(())
+}
+
+/// Restore items by the ids shown by [`list`], or everything for `all`.
+pub fn restore(ids: &[String]) -> Result<()> {
+    let (creds, client) = logged_in()?;
+    let trash = client.get_trash()?;
+    let key = creds.encryption_key_bytes()?;
+    // Items encrypted with an earlier key would make the items unreadable
+    let readable = |trashed: &TrashedItem| {
+        !matches!(
+            decrypt_one(&key, &trashed.item),
+            Err(TaskbookError::WrongKey)
+        )
+    };
+
+    let keys = if ids.iter().any(|id| id == "all") {
+        if trash.items.iter().all(readable) {
+            None
+        } else {
+            Some(
+                trash
+                    .items
+                    .iter()
+                    .filter(|trashed| readable(trashed))
+                    .map(|trashed| trashed.key.clone())
+                    .collect(),
+            )
+        }
+    } else {
+        let mut keys: Vec<String> = Vec::with_capacity(ids.len());
+        for id in ids {
+            let mut matching: Vec<&TrashedItem> = trash
+                .items
+                .iter()
+                .filter(|trashed| trashed.key.starts_with(id.as_str()))
+                .collect();
+            matching.dedup_by(|a, b| a.key == b.key);
+            match matching.as_slice() {
+                [trashed] if !readable(trashed) => {
+                    return Err(TaskbookError::General(format!(
+                        "item {id} was encrypted with an earlier key and cannot be restored"
+                    )))
+                }
+                [trashed] => keys.push(trashed.key.clone()),
+                [] => {
+                    return Err(TaskbookError::General(format!(
+                        "no item in the trash with id {id}"
+                    )))
+                }
+                _ => {
+                    return Err(TaskbookError::General(format!(
+                        "more than one item in the trash has an id starting with {id}"
+                    )))
+                }
+            }
+        }
+        Some(keys)
+    };
+
+    let restored = client.restore_trash(keys.as_deref())?;
+    println!(
+        "{}",
+        format!(
+            "Restored {restored} item{}.",
+            if restored == 1 { "" } else { "s" }
+        )
+        .green()
+    );
+
+    Ok(())
+}
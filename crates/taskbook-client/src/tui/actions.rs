@@ -1,18 +1,84 @@
+use chrono::NaiveDate;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
+use crate::attachments;
+use crate::config::{self, SortMethod, ThemeColors, ThemeConfig};
+use crate::copy::CopyFormat;
 use crate::editor;
 use crate::error::Result;
+use crate::opener;
+use crate::taskbook::{journal_title, JOURNAL_BOARD};
+use crate::wiki;
 use taskbook_common::board;
 
-use super::app::{App, PendingAction, PopupState, StatusKind, ViewMode};
+use super::app::{
+    sort_items_by, App, HistorySearch, InlineEdit, ListRow, PendingAction, PendingCount,
+    PopupState, ScrollAlign, SidebarState, StatusKind, ViewMode,
+};
 use super::autocomplete;
 use super::command_parser::{self, ParsedCommand};
 use super::input_handler::{handle_text_input, InputResult};
+use super::settings::{self, Setting};
+use super::setup::SetupStep;
+use super::theme::TuiTheme;
 
-/// Handle a key event
+/// Handle a key event, then report a config that could not be saved
 pub fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<()> {
-    // 1. Help popup → scroll with j/k/arrows, dismiss with q/Esc/other
-    if let Some(PopupState::Help { ref mut scroll }) = app.popup {
+    let result = dispatch_key(app, key);
+    // Last, so that the key's own status message doesn't hide it
+    if let Some(e) = app.config_save_error.take() {
+        app.set_status(format!("Could not save settings: {e}"), StatusKind::Error);
+    }
+    result
+}
+
+/// Handle a key event
+fn dispatch_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    // 0. First-run setup → until finished or skipped
+    if app.setup.is_some() {
+        return handle_setup_key(app, key);
+    }
+
+    // 1. Popup → scroll with j/k/arrows, dismiss with q/Esc/other; the
+    //    item popup opens attachments and links by number, and follows
+    //    links between notes with Tab and Enter
+    if let Some(PopupState::Activity {
+        id, ref mut link, ..
+    }) = app.popup
+    {
+        let targets = app
+            .items
+            .get(&id.to_string())
+            .map(|item| wiki::jump_targets(item, &app.items))
+            .unwrap_or_default();
+        match key.code {
+            KeyCode::Char(c @ '1'..='9') => {
+                return open_target(app, id, c as usize - '0' as usize);
+            }
+            KeyCode::Tab if !targets.is_empty() => {
+                *link = (*link + 1) % targets.len();
+                return Ok(());
+            }
+            KeyCode::BackTab if !targets.is_empty() => {
+                *link = (*link + targets.len() - 1) % targets.len();
+                return Ok(());
+            }
+            KeyCode::Enter if !targets.is_empty() => {
+                let target = targets[*link % targets.len()];
+                return jump_to_note(app, target);
+            }
+            _ => {}
+        }
+    }
+    if let Some(PopupState::Settings { selected }) = app.popup {
+        return handle_settings_key(app, key, selected);
+    }
+    if let Some(PopupState::Theme { selected }) = app.popup {
+        return handle_theme_key(app, key, selected);
+    }
+    if let Some(PopupState::Help { ref mut scroll } | PopupState::Activity { ref mut scroll, .. }) =
+        app.popup
+    {
         match key.code {
             KeyCode::Char('j') | KeyCode::Down => {
                 *scroll = scroll.saturating_add(1);
@@ -27,37 +93,101 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<()> {
         return Ok(());
     }
 
-    // 2. Pending confirm → Enter/Esc only
+    // 2. Pending confirm → y/n, Enter/Esc, or `a` for a single delete
     if app.command_line.pending_confirm.is_some() {
         return handle_confirm_key(app, key);
     }
 
-    // 3. Command line focused → handle command line input
+    // 3. Description being edited in place
+    if app.inline_edit.is_some() {
+        return handle_inline_edit_key(app, key);
+    }
+
+    // 4. Command line focused → handle command line input
     if app.command_line.focused {
         return handle_command_line_key(app, key);
     }
 
-    // 4. Normal mode shortcuts
+    // 5. Normal mode shortcuts
     handle_shortcut_key(app, key)
 }
 
-/// Handle keys when a confirmation is pending
+/// Edit the description in the selected row: Enter saves it as typed, Esc
+/// leaves it as it was
+fn handle_inline_edit_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(edit) = app.inline_edit.as_mut() else {
+        return Ok(());
+    };
+    match handle_text_input(key, &edit.input, edit.cursor) {
+        InputResult::Cancel => {
+            app.inline_edit = None;
+            app.set_status("Edit cancelled".to_string(), StatusKind::Info);
+        }
+        InputResult::Submit => {
+            let trimmed = edit.input.trim();
+            if trimmed.is_empty() {
+                app.set_status("Description cannot be empty".to_string(), StatusKind::Error);
+                return Ok(());
+            }
+            let (id, description) = (edit.id, trimmed.to_string());
+            app.inline_edit = None;
+            let unchanged = app
+                .items
+                .get(&id.to_string())
+                .is_some_and(|item| item.description() == description);
+            if unchanged {
+                app.set_status("Nothing changed".to_string(), StatusKind::Info);
+            } else {
+                edit_description(app, id, &description)?;
+            }
+        }
+        InputResult::Changed { input, cursor } => {
+            edit.input = input;
+            edit.cursor = cursor;
+        }
+        InputResult::Ignored => {}
+    }
+    Ok(())
+}
+
+/// Handle keys when a confirmation is pending: `y` or Enter confirms, `n`
+/// or Esc cancels, and `a` confirms a single delete and stops asking for them
 fn handle_confirm_key(app: &mut App, key: KeyEvent) -> Result<()> {
     match key.code {
-        KeyCode::Enter => {
+        KeyCode::Char('a')
+            if matches!(
+                &app.command_line.pending_confirm,
+                Some(PendingAction::Delete { ids }) if ids.len() == 1
+            ) =>
+        {
+            if let Some(PendingAction::Delete { ids }) = app.command_line.pending_confirm.take() {
+                app.config.confirm_delete = false;
+                app.save_config();
+                delete_items(app, &ids)?;
+                app.set_status(
+                    format!(
+                        "Deleted item {}; single deletes won't ask again (see settings)",
+                        ids[0]
+                    ),
+                    StatusKind::Success,
+                );
+            }
+            app.deactivate_command_line();
+        }
+        KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
             if let Some(action) = app.command_line.pending_confirm.take() {
                 match action {
                     PendingAction::Delete { ids } => {
                         delete_items(app, &ids)?;
                     }
-                    PendingAction::Clear => {
-                        clear_completed(app)?;
+                    PendingAction::Clear { board } => {
+                        clear_completed(app, board.as_deref())?;
                     }
                 }
             }
             app.deactivate_command_line();
         }
-        KeyCode::Esc => {
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
             app.command_line.pending_confirm = None;
             app.deactivate_command_line();
         }
@@ -66,8 +196,221 @@ fn handle_confirm_key(app: &mut App, key: KeyEvent) -> Result<()> {
     Ok(())
 }
 
+/// Settings popup: j/k move between rows, h/l (or Enter) change the value,
+/// Esc or q close it
+fn handle_settings_key(app: &mut App, key: KeyEvent, selected: usize) -> Result<()> {
+    let count = Setting::ALL.len();
+    let delta = match key.code {
+        KeyCode::Char('j') | KeyCode::Down => {
+            app.popup = Some(PopupState::Settings {
+                selected: (selected + 1) % count,
+            });
+            return Ok(());
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            app.popup = Some(PopupState::Settings {
+                selected: (selected + count - 1) % count,
+            });
+            return Ok(());
+        }
+        KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter | KeyCode::Char(' ') => 1,
+        KeyCode::Char('h') | KeyCode::Left => -1,
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char(',') => {
+            app.popup = None;
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+    if let Some(&setting) = Setting::ALL.get(selected) {
+        if let Err(e) = change_setting(app, setting, delta) {
+            app.set_status(e.to_string(), StatusKind::Error);
+        }
+    }
+    Ok(())
+}
+
+/// Theme picker: j/k preview the next or previous theme, Enter keeps it,
+/// Esc or q go back to the saved one
+fn handle_theme_key(app: &mut App, key: KeyEvent, selected: usize) -> Result<()> {
+    let count = ThemeColors::PRESETS.len();
+    let selected = match key.code {
+        KeyCode::Char('j') | KeyCode::Down => (selected + 1) % count,
+        KeyCode::Char('k') | KeyCode::Up => (selected + count - 1) % count,
+        KeyCode::Enter => {
+            let name = ThemeColors::PRESETS[selected];
+            app.set_theme(name);
+            app.popup = None;
+            app.set_status(format!("Theme: {}", name), StatusKind::Success);
+            return Ok(());
+        }
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.theme = TuiTheme::new(&app.config.theme.resolve(), app.config.color_mode);
+            app.popup = None;
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+    if let Some(colors) = ThemeColors::from_name(ThemeColors::PRESETS[selected]) {
+        app.theme = TuiTheme::new(&colors, app.config.color_mode);
+    }
+    app.popup = Some(PopupState::Theme { selected });
+    Ok(())
+}
+
+/// Open the theme picker on the current theme
+fn open_theme_picker(app: &mut App) {
+    let selected = settings::theme_index(&app.config.theme).unwrap_or(0);
+    app.popup = Some(PopupState::Theme { selected });
+}
+
+/// Change `setting` by one step forward or back, apply it and save
+fn change_setting(app: &mut App, setting: Setting, delta: isize) -> Result<()> {
+    match setting {
+        Setting::Theme => {
+            let name = settings::cycle_theme(&app.config.theme, delta);
+            app.set_theme(name);
+        }
+        Setting::Sort => {
+            let method = if delta < 0 {
+                app.sort_method.prev()
+            } else {
+                app.sort_method.next()
+            };
+            app.set_sort_method(method);
+        }
+        Setting::Completed => app.toggle_hide_completed(),
+        Setting::ConfirmDelete => {
+            app.config.confirm_delete = !app.config.confirm_delete;
+            app.save_config();
+        }
+        Setting::Density => {
+            // Three densities: going back one is going forward two
+            let steps = if delta < 0 { 2 } else { 1 };
+            for _ in 0..steps {
+                app.config.display_density = app.config.display_density.next();
+            }
+            app.save_config();
+        }
+        Setting::View => {
+            let view = settings::cycle_view(app.config.default_view, delta);
+            app.clear_board_filter();
+            // Remembered as the view to open in
+            app.set_view(view)?;
+        }
+        Setting::Sync => {
+            let enabled = !app.config.sync.enabled;
+            app.set_sync(enabled)?;
+        }
+    }
+    Ok(())
+}
+
+/// Setup wizard: j/k choose, typing fills text steps, Enter goes on,
+/// Shift+Tab goes back, Esc skips the rest with the defaults
+fn handle_setup_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(setup) = app.setup.as_mut() else {
+        return Ok(());
+    };
+    match key.code {
+        KeyCode::Esc => {
+            app.setup = None;
+            app.theme = TuiTheme::new(&app.config.theme.resolve(), app.config.color_mode);
+            app.write_config()?;
+            app.set_status(
+                "Setup skipped; press , to change settings any time".to_string(),
+                StatusKind::Info,
+            );
+            return Ok(());
+        }
+        KeyCode::Enter => {
+            if !setup.next() {
+                return finish_setup(app);
+            }
+        }
+        KeyCode::BackTab => setup.previous(),
+        _ => {
+            if let Some(input) = setup.input_mut() {
+                match key.code {
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    _ => {}
+                }
+            } else {
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => setup.choose(1),
+                    KeyCode::Char('k') | KeyCode::Up => setup.choose(-1),
+                    KeyCode::Backspace => setup.previous(),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Preview the theme being picked
+    if let Some(setup) = &app.setup {
+        if setup.step == SetupStep::Theme {
+            if let Some(colors) = ThemeColors::from_name(setup.theme_name()) {
+                app.theme = TuiTheme::new(&colors, app.config.color_mode);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Save the wizard's choices to the config and start on the first board
+fn finish_setup(app: &mut App) -> Result<()> {
+    let Some(setup) = app.setup.take() else {
+        return Ok(());
+    };
+    app.config.theme = ThemeConfig::Preset(setup.theme_name().to_string());
+    app.theme = TuiTheme::new(&app.config.theme.resolve(), app.config.color_mode);
+    app.config.default_view = setup.view_mode();
+    // Sync is turned on by logging in, which needs a password prompt
+    if setup.sync && !setup.server_url.trim().is_empty() {
+        app.config.sync.server_url = setup.server_url.trim().to_string();
+    }
+    app.write_config()?;
+
+    let board = board::normalize_board_name(&setup.board);
+    let id = app.taskbook.create_note_with_body_direct(
+        vec![board],
+        "Welcome to taskbook".to_string(),
+        Some(
+            "Press t to add a task and n to add a note, c to check a task, \
+             / for commands and ? for all the keys. Delete this note with d."
+                .to_string(),
+        ),
+    )?;
+    app.view = setup.view_mode();
+    app.refresh_items()?;
+    app.select_item(id)?;
+
+    let message = if setup.sync {
+        format!(
+            "All set. To sync, quit and run tb --register (or tb --login) --server {}",
+            app.config.sync.server_url
+        )
+    } else {
+        "All set. Press ? for help".to_string()
+    };
+    app.set_status(message, StatusKind::Success);
+    Ok(())
+}
+
 /// Handle keys when the command line is focused
 fn handle_command_line_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    if app.command_line.history_search.is_some() {
+        return handle_history_search_key(app, key);
+    }
+    if key.code == KeyCode::Char('r') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        app.command_line.history_search = Some(HistorySearch::default());
+        app.command_line.suggestions.clear();
+        app.command_line.selected_suggestion = None;
+        return Ok(());
+    }
+
     // Tab accepts the selected suggestion
     if key.code == KeyCode::Tab {
         accept_suggestion(app);
@@ -100,6 +443,7 @@ fn handle_command_line_key(app: &mut App, key: KeyEvent) -> Result<()> {
                 app.command_line.cursor = entry.chars().count();
                 app.command_line.input = entry;
                 autocomplete::update_suggestions(app);
+                app.update_live_search();
             }
             return Ok(());
         }
@@ -128,6 +472,7 @@ fn handle_command_line_key(app: &mut App, key: KeyEvent) -> Result<()> {
                     app.command_line.input = entry;
                 }
                 autocomplete::update_suggestions(app);
+                app.update_live_search();
             }
             return Ok(());
         }
@@ -140,16 +485,10 @@ fn handle_command_line_key(app: &mut App, key: KeyEvent) -> Result<()> {
 
     match handle_text_input(key, &input, cursor) {
         InputResult::Cancel => {
+            app.cancel_live_search();
             app.deactivate_command_line();
         }
-        InputResult::Submit => {
-            let input = app.command_line.input.clone();
-            app.push_history(input.clone());
-            app.deactivate_command_line();
-            if !input.trim().is_empty() {
-                execute_input(app, &input)?;
-            }
-        }
+        InputResult::Submit => submit_command_line(app)?,
         InputResult::Changed {
             input: new_input,
             cursor: new_cursor,
@@ -157,6 +496,7 @@ fn handle_command_line_key(app: &mut App, key: KeyEvent) -> Result<()> {
             app.command_line.input = new_input;
             app.command_line.cursor = new_cursor;
             autocomplete::update_suggestions(app);
+            app.update_live_search();
         }
         InputResult::Ignored => {}
     }
@@ -164,6 +504,71 @@ fn handle_command_line_key(app: &mut App, key: KeyEvent) -> Result<()> {
     Ok(())
 }
 
+/// Run what the command line holds, remembering it in the history
+fn submit_command_line(app: &mut App) -> Result<()> {
+    let input = app.command_line.input.clone();
+    let saved = app.push_history(input.clone());
+    app.deactivate_command_line();
+    if !input.trim().is_empty() {
+        execute_input(app, &input)?;
+    }
+    if let Err(e) = saved {
+        app.set_status(
+            format!("Could not save the command history: {e}"),
+            StatusKind::Error,
+        );
+    }
+    Ok(())
+}
+
+/// Ctrl+R history search: typing narrows it, Ctrl+R again finds an older
+/// match, Enter runs the match, Tab or an arrow key takes it to edit, Esc
+/// goes back to the input as it was
+fn handle_history_search_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(mut search) = app.command_line.history_search.take() else {
+        return Ok(());
+    };
+    let history = &app.command_history;
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => return Ok(()),
+        KeyCode::Char('g') if ctrl => return Ok(()),
+        KeyCode::Char('r') if ctrl => {
+            let before = search.found.unwrap_or(history.len());
+            if let Some(found) = search.find(history, before) {
+                search.found = Some(found);
+            }
+        }
+        KeyCode::Enter | KeyCode::Tab | KeyCode::Left | KeyCode::Right => {
+            // Without a match, back to the input as it was
+            let Some(command) = search.found.and_then(|i| history.get(i)).cloned() else {
+                return Ok(());
+            };
+            app.command_line.cursor = command.chars().count();
+            app.command_line.input = command;
+            if key.code == KeyCode::Enter {
+                return submit_command_line(app);
+            }
+            autocomplete::update_suggestions(app);
+            app.update_live_search();
+            return Ok(());
+        }
+        KeyCode::Backspace => {
+            search.query.pop();
+            search.found = search.find(history, history.len());
+        }
+        KeyCode::Char(c) if !ctrl => {
+            search.query.push(c);
+            // Stay on the command shown while it still matches
+            let before = search.found.map_or(history.len(), |found| found + 1);
+            search.found = search.find(history, before);
+        }
+        _ => {}
+    }
+    app.command_line.history_search = Some(search);
+    Ok(())
+}
+
 /// Accept the currently selected suggestion
 fn accept_suggestion(app: &mut App) {
     let selected = app.command_line.selected_suggestion.unwrap_or(0);
@@ -174,6 +579,7 @@ fn accept_suggestion(app: &mut App) {
         app.command_line.selected_suggestion = None;
         // Re-trigger suggestions for the new input
         autocomplete::update_suggestions(app);
+        app.update_live_search();
     }
 }
 
@@ -196,17 +602,21 @@ fn execute_command(app: &mut App, cmd: ParsedCommand) -> Result<()> {
             description,
             priority,
             tags,
+            due,
         } => {
             let board_name = board
                 .map(|b| board::normalize_board_name(&b))
                 .or_else(|| app.filter.board_filter.clone())
                 .unwrap_or_else(|| "my board".to_string());
-            app.taskbook.create_task_direct_with_tags(
+            let id = app.taskbook.create_task_direct_with_tags(
                 vec![board_name.clone()],
                 description,
                 priority,
                 tags,
             )?;
+            if due.is_some() {
+                app.taskbook.set_due_silent(id, due)?;
+            }
             app.refresh_items()?;
             let display = board::display_name(&board_name);
             app.set_status(format!("Task created in {}", display), StatusKind::Success);
@@ -242,16 +652,40 @@ fn execute_command(app: &mut App, cmd: ParsedCommand) -> Result<()> {
         ParsedCommand::Edit { id, description } => {
             edit_description(app, id, &description)?;
         }
-        ParsedCommand::Move { id, board } => {
-            move_to_board(app, id, &board)?;
+        ParsedCommand::Comment { id, text } => {
+            app.taskbook.add_comment_silent(id, &text)?;
+            app.refresh_items()?;
+            app.set_status(format!("Commented on task {}", id), StatusKind::Success);
+        }
+        ParsedCommand::Attach { id, target } => {
+            let added = app.taskbook.attach_silent(id, &[target])?;
+            app.refresh_items()?;
+            let message = match added.first() {
+                Some(attachment) => format!(
+                    "Attached {} to item {}",
+                    attachments::display_name(attachment),
+                    id
+                ),
+                None => format!("Already attached to item {}", id),
+            };
+            app.set_status(message, StatusKind::Success);
+        }
+        ParsedCommand::Open { id, number } => {
+            open_target(app, id, number)?;
+        }
+        ParsedCommand::Move { ids, board } => {
+            move_to_board(app, &ids, &board)?;
         }
         ParsedCommand::Delete { ids } => {
-            app.command_line.pending_confirm = Some(PendingAction::Delete { ids });
+            request_delete(app, ids)?;
+        }
+        ParsedCommand::Reorder { id, up } => {
+            if let Some(id) = id.or_else(|| app.selected_id()) {
+                reorder_item(app, id, up, 1)?;
+            }
         }
         ParsedCommand::Search { term } => {
-            app.filter.search_term = Some(term.clone());
-            app.update_display_order();
-            app.selected_index = 0;
+            app.set_search(Some(term.clone()));
             let count = app.display_order.len();
             app.set_status(
                 format!("Search: \"{}\" ({} matches)", term, count),
@@ -261,6 +695,9 @@ fn execute_command(app: &mut App, cmd: ParsedCommand) -> Result<()> {
         ParsedCommand::Priority { id, level } => {
             set_priority(app, id, level)?;
         }
+        ParsedCommand::Due { id, due } => {
+            set_due(app, id, due)?;
+        }
         ParsedCommand::Check { ids } => {
             for id in &ids {
                 toggle_check(app, *id)?;
@@ -279,8 +716,26 @@ fn execute_command(app: &mut App, cmd: ParsedCommand) -> Result<()> {
         ParsedCommand::Tag { id, add, remove } => {
             update_tags(app, id, &add, &remove)?;
         }
-        ParsedCommand::Clear => {
-            app.command_line.pending_confirm = Some(PendingAction::Clear);
+        ParsedCommand::TagFilter { tag: Some(tag) } => {
+            let display = board::display_tag(&tag);
+            app.set_tag_filter(Some(tag));
+            let count = app.display_order.len();
+            app.set_status(
+                format!("Tag: {} ({} items)", display, count),
+                StatusKind::Info,
+            );
+        }
+        ParsedCommand::TagFilter { tag: None } => {
+            app.set_tag_filter(None);
+            app.set_status("Tag filter cleared".to_string(), StatusKind::Info);
+        }
+        ParsedCommand::Clear { board, dry_run } => {
+            let board = board.map(|b| board::normalize_board_name(&b));
+            if dry_run {
+                preview_clear(app, board.as_deref())?;
+            } else {
+                app.command_line.pending_confirm = Some(PendingAction::Clear { board });
+            }
         }
         ParsedCommand::RenameBoard { old_name, new_name } => {
             rename_board(app, &old_name, &new_name)?;
@@ -301,6 +756,21 @@ fn execute_command(app: &mut App, cmd: ParsedCommand) -> Result<()> {
             app.clear_board_filter();
             app.set_view(ViewMode::Journal)?;
         }
+        ParsedCommand::JournalNote => {
+            open_journal_note(app)?;
+        }
+        ParsedCommand::Calendar => {
+            app.clear_board_filter();
+            app.set_view(ViewMode::Calendar)?;
+        }
+        ParsedCommand::Today => {
+            app.clear_board_filter();
+            app.set_view(ViewMode::Today)?;
+        }
+        ParsedCommand::Stats => {
+            app.clear_board_filter();
+            app.set_view(ViewMode::Stats)?;
+        }
         ParsedCommand::Sort => {
             app.cycle_sort_method();
             app.set_status(
@@ -308,6 +778,43 @@ fn execute_command(app: &mut App, cmd: ParsedCommand) -> Result<()> {
                 StatusKind::Info,
             );
         }
+        ParsedCommand::SortBoard { board, method } => {
+            let name = board::normalize_board_name(&board);
+            match app
+                .boards
+                .iter()
+                .find(|b| board::board_eq(b, &name))
+                .cloned()
+            {
+                Some(name) => {
+                    app.set_board_sort(&name, method);
+                    let method = app.config.sort_method_for(&name);
+                    app.set_status(
+                        format!(
+                            "Sort {}: {}",
+                            board::display_name(&name),
+                            method.display_name()
+                        ),
+                        StatusKind::Info,
+                    );
+                }
+                None => app.set_status(
+                    format!("No board named {}", board::display_name(&name)),
+                    StatusKind::Error,
+                ),
+            }
+        }
+        ParsedCommand::Group { group } => {
+            let group = group.unwrap_or_else(|| app.config.timeline_group.next());
+            app.set_timeline_group(group);
+            app.set_status(
+                format!(
+                    "Timeline grouped by {}",
+                    group.display_name().to_lowercase()
+                ),
+                StatusKind::Info,
+            );
+        }
         ParsedCommand::HideDone => {
             app.toggle_hide_completed();
             let msg = if app.filter.hide_completed {
@@ -317,9 +824,62 @@ fn execute_command(app: &mut App, cmd: ParsedCommand) -> Result<()> {
             };
             app.set_status(msg.to_string(), StatusKind::Info);
         }
+        ParsedCommand::Filter { name: Some(name) } => {
+            if app.apply_saved_filter(&name) {
+                let count = app.display_order.len();
+                app.set_status(
+                    format!("Filter: {} ({} items)", name, count),
+                    StatusKind::Info,
+                );
+            } else {
+                app.set_status(
+                    format!("No saved filter named '{}'", name),
+                    StatusKind::Error,
+                );
+            }
+        }
+        ParsedCommand::Filter { name: None } => {
+            app.clear_saved_filter();
+            app.set_status("Filter cleared".to_string(), StatusKind::Info);
+        }
+        ParsedCommand::Profile { name: Some(name) } => match app.switch_profile(Some(&name)) {
+            Ok(()) => app.set_status(format!("Profile: {}", name), StatusKind::Success),
+            Err(e) => app.set_status(e.to_string(), StatusKind::Error),
+        },
+        ParsedCommand::Profile { name: None } => {
+            let current = config::active_profile();
+            let mut names = vec![config::DEFAULT_PROFILE.to_string()];
+            names.extend(app.config.profiles.keys().cloned());
+            app.set_status(
+                format!(
+                    "Profile: {} (available: {})",
+                    current.as_deref().unwrap_or(config::DEFAULT_PROFILE),
+                    names.join(", ")
+                ),
+                StatusKind::Info,
+            );
+        }
         ParsedCommand::Help => {
             app.popup = Some(PopupState::Help { scroll: 0 });
         }
+        ParsedCommand::Theme { name: Some(name) } => match ThemeColors::preset_name(&name) {
+            Some(name) => {
+                app.set_theme(name);
+                app.set_status(format!("Theme: {}", name), StatusKind::Success);
+            }
+            None => app.set_status(
+                format!(
+                    "Unknown theme '{}' (available: {})",
+                    name,
+                    ThemeColors::PRESETS.join(", ")
+                ),
+                StatusKind::Error,
+            ),
+        },
+        ParsedCommand::Theme { name: None } => open_theme_picker(app),
+        ParsedCommand::Settings => {
+            app.popup = Some(PopupState::Settings { selected: 0 });
+        }
         ParsedCommand::Quit => {
             app.quit();
         }
@@ -327,8 +887,149 @@ fn execute_command(app: &mut App, cmd: ParsedCommand) -> Result<()> {
     Ok(())
 }
 
+/// Whether `key` uses a count typed before it, rather than the count being
+/// a view number
+fn takes_count(view: ViewMode, calendar_items: bool, key: KeyEvent) -> bool {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return false;
+    }
+    let calendar_grid = view == ViewMode::Calendar && !calendar_items;
+    match key.code {
+        KeyCode::Char('j' | 'k') | KeyCode::Up | KeyCode::Down => true,
+        KeyCode::Char('G') => true,
+        KeyCode::Char('J' | 'K') => view == ViewMode::Board,
+        KeyCode::Char('d') => view != ViewMode::Archive,
+        KeyCode::Char('h' | 'l')
+        | KeyCode::Left
+        | KeyCode::Right
+        | KeyCode::PageUp
+        | KeyCode::PageDown => calendar_grid,
+        _ => false,
+    }
+}
+
+/// Handle the board picker while it has focus. Returns `true` if the key
+/// was consumed.
+///
+/// j/k and g/G move between boards, filtering the list as they go; Enter,
+/// Esc, l or Right hand the keys back to the list, and B closes the picker.
+fn handle_sidebar_key(app: &mut App, key: KeyEvent, count: usize) -> bool {
+    let picked = app.picked_board();
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => app.pick_board(picked + count),
+        KeyCode::Char('k') | KeyCode::Up => app.pick_board(picked.saturating_sub(count)),
+        KeyCode::Char('g') => app.pick_board(0),
+        KeyCode::Char('G') => app.pick_board(app.boards.len()),
+        KeyCode::Enter | KeyCode::Esc | KeyCode::Char('l') | KeyCode::Right => {
+            app.sidebar.focused = false;
+        }
+        KeyCode::Char('B') => app.sidebar = SidebarState::default(),
+        _ => return false,
+    }
+    true
+}
+
 /// Handle shortcut keys in normal (unfocused) mode
+/// Handle calendar navigation. Returns `true` if the key was consumed.
+///
+/// While the month grid has focus, arrows and h/j/k/l move between days and
+/// PageUp/PageDown between months; Enter moves focus to the day's items and
+/// Esc moves it back.
+fn handle_calendar_key(app: &mut App, key: KeyEvent, count: usize) -> bool {
+    if app.calendar.focus_items {
+        if key.code == KeyCode::Esc {
+            app.calendar.focus_items = false;
+            return true;
+        }
+        return false;
+    }
+
+    let days = count as i64;
+    let months = count as i32;
+    match key.code {
+        KeyCode::Char('h') | KeyCode::Left => app.move_calendar_day(-days),
+        KeyCode::Char('l') | KeyCode::Right => app.move_calendar_day(days),
+        KeyCode::Char('k') | KeyCode::Up => app.move_calendar_day(-7 * days),
+        KeyCode::Char('j') | KeyCode::Down => app.move_calendar_day(7 * days),
+        KeyCode::PageUp => app.move_calendar_month(-months),
+        KeyCode::PageDown => app.move_calendar_month(months),
+        KeyCode::Enter => {
+            if app.display_order.is_empty() {
+                app.set_status("No items on this day".to_string(), StatusKind::Info);
+            } else {
+                app.calendar.focus_items = true;
+                app.selected_index = 0;
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
 fn handle_shortcut_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let pending = app.pending_key.take();
+    // Second key of `dd`, deleting the selected item, or with a count as
+    // in `3dd` it and the rows below; any other key drops the `d`
+    if pending == Some('d') {
+        let n = app.count.take().map_or(1, |count| count.value);
+        if key.code == KeyCode::Char('d') && app.view != ViewMode::Archive {
+            let ids: Vec<u64> = app
+                .display_order
+                .iter()
+                .skip(app.selected_index)
+                .take(n)
+                .filter_map(ListRow::item_id)
+                .collect();
+            if !ids.is_empty() {
+                request_delete(app, ids)?;
+            }
+        }
+        return Ok(());
+    }
+    // Second key of `zz`, `zt`, `zb`, or of `za`, `zM`, `zR` folding boards;
+    // any other key drops the `z`
+    if pending == Some('z') {
+        let align = match key.code {
+            KeyCode::Char('z') => Some(ScrollAlign::Center),
+            KeyCode::Char('t') => Some(ScrollAlign::Top),
+            KeyCode::Char('b') => Some(ScrollAlign::Bottom),
+            _ => None,
+        };
+        app.list_scroll.align.set(align);
+        let folded = match key.code {
+            KeyCode::Char('a') if app.view == ViewMode::Board => app.toggle_collapsed(),
+            KeyCode::Char('M') if app.view == ViewMode::Board => app.set_all_collapsed(true),
+            KeyCode::Char('R') if app.view == ViewMode::Board => app.set_all_collapsed(false),
+            _ => true,
+        };
+        if !folded {
+            app.set_status(
+                "Boards stay open while filtering or searching".to_string(),
+                StatusKind::Info,
+            );
+        }
+        return Ok(());
+    }
+
+    // Digits count the key after them (`5j`, `3dd`, `12G`). A lone digit
+    // switches to its view straight away, and only becomes a count when
+    // another digit or a key that takes one follows
+    if let KeyCode::Char(c @ '0'..='9') = key.code {
+        if !key.modifiers.contains(KeyModifiers::CONTROL) && (c != '0' || app.count.is_some()) {
+            return app.push_count_digit(c as usize - '0' as usize);
+        }
+    }
+    if app.count.is_some() && key.code == KeyCode::Esc {
+        app.count = None;
+        return Ok(());
+    }
+    let (view, calendar_items) = (app.view, app.calendar.focus_items);
+    let count = app.take_count(|before| match before {
+        Some(before) => takes_count(before.view, before.calendar_items, key),
+        None => takes_count(view, calendar_items, key),
+    })?;
+    let n = count.unwrap_or(1);
+
     // Ctrl+D / Ctrl+U for half-page navigation
     if key.modifiers.contains(KeyModifiers::CONTROL) {
         match key.code {
@@ -345,26 +1046,47 @@ fn handle_shortcut_key(app: &mut App, key: KeyEvent) -> Result<()> {
         return Ok(());
     }
 
+    if app.view == ViewMode::Calendar && handle_calendar_key(app, key, n) {
+        return Ok(());
+    }
+    if app.view == ViewMode::Board && app.sidebar.focused && handle_sidebar_key(app, key, n) {
+        return Ok(());
+    }
+
     match key.code {
         // Quit
         KeyCode::Char('q') => app.quit(),
         KeyCode::Esc => {
-            if app.filter.search_term.is_some() {
-                app.filter.search_term = None;
-                app.update_display_order();
-                app.selected_index = 0;
+            if !app.marked.is_empty() {
+                app.marked.clear();
+                app.set_status("Marks cleared".to_string(), StatusKind::Info);
+            } else if app.filter.search_term.is_some() {
+                app.set_search(None);
                 app.set_status("Search cleared".to_string(), StatusKind::Info);
+            } else if app.filter.tag_filter.is_some() {
+                app.set_tag_filter(None);
+                app.set_status("Tag filter cleared".to_string(), StatusKind::Info);
             } else if app.filter.board_filter.is_some() {
                 app.clear_board_filter();
                 app.set_status("Filter cleared".to_string(), StatusKind::Info);
+            } else if !app.filter.attributes.is_empty() {
+                app.clear_saved_filter();
+                app.set_status("Filter cleared".to_string(), StatusKind::Info);
             }
         }
 
         // Navigation
-        KeyCode::Char('j') | KeyCode::Down => app.select_next(),
-        KeyCode::Char('k') | KeyCode::Up => app.select_previous(),
+        KeyCode::Char('j') | KeyCode::Down => app.select_down_by(n),
+        KeyCode::Char('k') | KeyCode::Up => app.select_up_by(n),
         KeyCode::Char('g') => app.select_first(),
-        KeyCode::Char('G') => app.select_last(),
+        // `G` goes to the last item, `12G` to the twelfth
+        KeyCode::Char('G') => match count {
+            Some(n) => {
+                app.select_first();
+                app.select_down_by(n - 1);
+            }
+            None => app.select_last(),
+        },
         KeyCode::PageDown => {
             let page = app.content_height.max(1) as usize;
             app.select_down_by(page);
@@ -373,6 +1095,7 @@ fn handle_shortcut_key(app: &mut App, key: KeyEvent) -> Result<()> {
             let page = app.content_height.max(1) as usize;
             app.select_up_by(page);
         }
+        KeyCode::Char('z') => app.pending_key = Some('z'),
 
         // Enter to open note in editor or filter by board
         KeyCode::Enter => {
@@ -389,28 +1112,28 @@ fn handle_shortcut_key(app: &mut App, key: KeyEvent) -> Result<()> {
             }
         }
 
-        // View switching
-        KeyCode::Char('1') => {
-            app.clear_board_filter();
-            app.set_view(ViewMode::Board)?;
-        }
-        KeyCode::Char('2') => {
-            app.clear_board_filter();
-            app.set_view(ViewMode::Timeline)?;
-        }
-        KeyCode::Char('3') => {
-            app.clear_board_filter();
-            app.set_view(ViewMode::Archive)?;
+        // Reorder within the board, sorting it by hand
+        KeyCode::Char('J') | KeyCode::Char('K') if app.view == ViewMode::Board => {
+            if let Some(id) = app.selected_id() {
+                reorder_item(app, id, key.code == KeyCode::Char('K'), n)?;
+            }
         }
-        KeyCode::Char('4') => {
-            app.clear_board_filter();
-            app.set_view(ViewMode::Journal)?;
+
+        // Board picker, opened over the board view
+        KeyCode::Char('B') => {
+            app.set_view(ViewMode::Board)?;
+            app.sidebar.open = true;
+            app.sidebar.focused = true;
         }
 
         // Help
         KeyCode::Char('?') => {
             app.popup = Some(PopupState::Help { scroll: 0 });
         }
+        KeyCode::Char('T') => open_theme_picker(app),
+        KeyCode::Char(',') => {
+            app.popup = Some(PopupState::Settings { selected: 0 });
+        }
 
         // Slash or Tab activates command line
         KeyCode::Char('/') | KeyCode::Tab => {
@@ -427,6 +1150,10 @@ fn handle_shortcut_key(app: &mut App, key: KeyEvent) -> Result<()> {
                 autocomplete::update_suggestions(app);
             }
         }
+        // Next/previous search match, while a search is on
+        KeyCode::Char('n') if app.filter.search_term.is_some() => app.select_match(1),
+        KeyCode::Char('N') if app.filter.search_term.is_some() => app.select_match(-1),
+
         KeyCode::Char('n') if app.view != ViewMode::Archive => {
             if let Some(ref board) = app.filter.board_filter.clone() {
                 app.activate_command_line(&format!("/note @{} ", board));
@@ -436,15 +1163,60 @@ fn handle_shortcut_key(app: &mut App, key: KeyEvent) -> Result<()> {
             }
         }
         KeyCode::Char('e') if app.view != ViewMode::Archive => {
+            if let Some(item) = app.selected_item() {
+                let input = item.description().to_string();
+                app.inline_edit = Some(InlineEdit {
+                    id: item.id(),
+                    cursor: input.chars().count(),
+                    input,
+                });
+            }
+        }
+        KeyCode::Char('E') if app.view != ViewMode::Archive => {
             if let Some(item) = app.selected_item() {
                 let id = item.id();
-                let desc = item.description().to_string();
-                app.activate_command_line(&format!("/edit @{} {}", id, desc));
+                if item.is_task() {
+                    edit_task_external(app, id)?;
+                } else {
+                    edit_note_external(app, id)?;
+                }
             }
         }
-        KeyCode::Char('m') if app.view != ViewMode::Archive => {
+        // Space opens a collapsed board, and marks an item
+        KeyCode::Char(' ') if matches!(app.selected_row(), Some(ListRow::Board(_))) => {
+            app.toggle_collapsed();
+        }
+        KeyCode::Char(' ') if app.view != ViewMode::Archive => {
+            if let Some(id) = app.selected_id() {
+                if !app.marked.remove(&id) {
+                    app.marked.insert(id);
+                }
+                app.select_next();
+            }
+        }
+        KeyCode::Char('i') => {
             if let Some(id) = app.selected_id() {
-                app.activate_command_line(&format!("/move @{} @", id));
+                app.popup = Some(PopupState::Activity {
+                    id,
+                    scroll: 0,
+                    link: 0,
+                });
+            }
+        }
+        KeyCode::Char('o') => {
+            if let Some(id) = app.selected_id() {
+                open_target(app, id, 1)?;
+            }
+        }
+        KeyCode::Char('m') if app.view != ViewMode::Archive => {
+            let ids: Vec<u64> = if app.marked.is_empty() {
+                app.selected_id().into_iter().collect()
+            } else {
+                app.marked.iter().copied().collect()
+            };
+            if !ids.is_empty() {
+                let targets: Vec<String> = ids.iter().map(|id| format!("@{}", id)).collect();
+                app.activate_command_line(&format!("/move {} @", targets.join(" ")));
                 autocomplete::update_suggestions(app);
             }
         }
@@ -455,13 +1227,18 @@ fn handle_shortcut_key(app: &mut App, key: KeyEvent) -> Result<()> {
                 }
             }
         }
+        // `dd` deletes, keeping the count for the second `d`
         KeyCode::Char('d') if app.view != ViewMode::Archive => {
-            if let Some(id) = app.selected_id() {
-                app.command_line.pending_confirm = Some(PendingAction::Delete { ids: vec![id] });
-            }
+            app.pending_key = Some('d');
+            app.count = count.map(|value| PendingCount {
+                value,
+                before: None,
+            });
         }
         KeyCode::Char('C') if app.view != ViewMode::Archive => {
-            app.command_line.pending_confirm = Some(PendingAction::Clear);
+            // Only the board being looked at, when filtering by one
+            let board = app.filter.board_filter.clone();
+            app.command_line.pending_confirm = Some(PendingAction::Clear { board });
         }
 
         // Direct action shortcuts (no command line needed)
@@ -480,16 +1257,40 @@ fn handle_shortcut_key(app: &mut App, key: KeyEvent) -> Result<()> {
                 toggle_star(app, id)?;
             }
         }
+        KeyCode::Char('F') if app.view != ViewMode::Archive => {
+            if app.focus.is_some() {
+                app.finish_focus();
+            } else if let Some(id) = app.selected_id() {
+                app.start_focus(id)?;
+            }
+        }
         KeyCode::Char('r') if app.view == ViewMode::Archive => {
             if let Some(id) = app.selected_id() {
                 restore_item(app, id)?;
             }
         }
+        KeyCode::Char('u') => app.undo()?,
         KeyCode::Char('y') => {
             if let Some(id) = app.selected_id() {
                 copy_to_clipboard(app, id)?;
             }
         }
+        KeyCode::Char('Y') => {
+            if let Some(item) = app.selected_item() {
+                if item.is_task() {
+                    if let Some(board) = app.get_board_for_selected() {
+                        copy_board(app, &board)?;
+                    }
+                } else {
+                    let id = item.id();
+                    app.taskbook.copy_note_silent(id)?;
+                    app.set_status(
+                        format!("Copied note {} to clipboard", id),
+                        StatusKind::Success,
+                    );
+                }
+            }
+        }
 
         // Cycle sort method
         KeyCode::Char('S') if app.view == ViewMode::Board => {
@@ -499,6 +1300,18 @@ fn handle_shortcut_key(app: &mut App, key: KeyEvent) -> Result<()> {
                 StatusKind::Info,
             );
         }
+        // Cycle timeline grouping
+        KeyCode::Char('S') if matches!(app.view, ViewMode::Timeline | ViewMode::Archive) => {
+            let group = app.config.timeline_group.next();
+            app.set_timeline_group(group);
+            app.set_status(
+                format!(
+                    "Timeline grouped by {}",
+                    group.display_name().to_lowercase()
+                ),
+                StatusKind::Info,
+            );
+        }
         // Toggle hide completed
         KeyCode::Char('h') if app.view != ViewMode::Archive => {
             app.toggle_hide_completed();
@@ -521,6 +1334,7 @@ fn handle_shortcut_key(app: &mut App, key: KeyEvent) -> Result<()> {
 fn toggle_check(app: &mut App, id: u64) -> Result<()> {
     if let Some(item) = app.items.get(&id.to_string()) {
         if item.is_task() {
+            app.push_undo(format!("check of task {}", id), &[id])?;
             app.taskbook.check_tasks_silent(&[id])?;
             app.refresh_items()?;
             app.set_status(format!("Toggled task {}", id), StatusKind::Success);
@@ -532,6 +1346,7 @@ fn toggle_check(app: &mut App, id: u64) -> Result<()> {
 fn toggle_begin(app: &mut App, id: u64) -> Result<()> {
     if let Some(item) = app.items.get(&id.to_string()) {
         if item.is_task() {
+            app.push_undo(format!("begin of task {}", id), &[id])?;
             app.taskbook.begin_tasks_silent(&[id])?;
             app.refresh_items()?;
             app.set_status(
@@ -544,6 +1359,7 @@ fn toggle_begin(app: &mut App, id: u64) -> Result<()> {
 }
 
 fn toggle_star(app: &mut App, id: u64) -> Result<()> {
+    app.push_undo(format!("star of item {}", id), &[id])?;
     app.taskbook.star_items_silent(&[id])?;
     app.refresh_items()?;
     app.set_status(format!("Toggled star for item {}", id), StatusKind::Success);
@@ -551,26 +1367,109 @@ fn toggle_star(app: &mut App, id: u64) -> Result<()> {
 }
 
 fn edit_description(app: &mut App, id: u64, new_desc: &str) -> Result<()> {
+    app.push_undo(format!("edit of item {}", id), &[id])?;
     app.taskbook.edit_description_silent(id, new_desc)?;
     app.refresh_items()?;
     app.set_status(format!("Updated item {}", id), StatusKind::Success);
     Ok(())
 }
 
-fn move_to_board(app: &mut App, id: u64, board: &str) -> Result<()> {
+fn move_to_board(app: &mut App, ids: &[u64], board: &str) -> Result<()> {
     let board_name = board::normalize_board_name(board);
+    app.push_undo(format!("move of {}", items_label(ids)), ids)?;
     app.taskbook
-        .move_boards_silent(id, vec![board_name.clone()])?;
+        .move_boards_silent(ids, vec![board_name.clone()])?;
+    app.marked.clear();
     app.refresh_items()?;
     let display = board::display_name(&board_name);
     app.set_status(
-        format!("Moved item {} to {}", id, display),
+        format!("Moved {} to {}", items_label(ids), display),
+        StatusKind::Success,
+    );
+    Ok(())
+}
+
+/// Move item `id` `steps` places up or down among the items its board shows,
+/// sorting the board by hand, from the order it is shown in, if it isn't yet
+fn reorder_item(app: &mut App, id: u64, up: bool, steps: usize) -> Result<()> {
+    if app.view != ViewMode::Board {
+        app.set_status(
+            "Items are reordered in the board view".to_string(),
+            StatusKind::Error,
+        );
+        return Ok(());
+    }
+    let Some(board_name) = app.board_shown_under(id) else {
+        app.set_status(format!("Item {} not found", id), StatusKind::Error);
+        return Ok(());
+    };
+
+    let method = app.config.sort_method_for(&board_name);
+    let mut board_items: Vec<_> = app
+        .items
+        .values()
+        .filter(|item| {
+            item.boards()
+                .iter()
+                .any(|b| board::board_eq(b, &board_name))
+        })
+        .collect();
+    sort_items_by(&mut board_items, method);
+    let mut order: Vec<u64> = board_items.iter().map(|item| item.id()).collect();
+    let shown: Vec<u64> = board_items
+        .iter()
+        .filter(|item| app.should_show_item(item))
+        .map(|item| item.id())
+        .collect();
+
+    let Some(from) = shown.iter().position(|&shown_id| shown_id == id) else {
+        return Ok(());
+    };
+    let to = if up {
+        from.saturating_sub(steps)
+    } else {
+        (from + steps).min(shown.len() - 1)
+    };
+    if to == from {
+        let end = if up { "top" } else { "bottom" };
+        app.set_status(
+            format!("Item {} is already at the {}", id, end),
+            StatusKind::Info,
+        );
+        return Ok(());
+    }
+
+    // Put it next to the shown item it passes, wherever hidden items are
+    let passed = shown[to];
+    order.retain(|&other| other != id);
+    let at = order
+        .iter()
+        .position(|&other| other == passed)
+        .unwrap_or(order.len());
+    order.insert(if up { at } else { at + 1 }, id);
+
+    let direction = if up { "up" } else { "down" };
+    app.push_undo(format!("move of item {} {}", id, direction), &order)?;
+    app.taskbook.reorder_silent(&order)?;
+    if method != SortMethod::Manual {
+        app.set_board_sort(&board_name, Some(SortMethod::Manual));
+    }
+    app.refresh_items()?;
+    app.select_item(id)?;
+    app.set_status(
+        format!(
+            "Moved item {} {} in {}",
+            id,
+            direction,
+            board::display_name(&board_name)
+        ),
         StatusKind::Success,
     );
     Ok(())
 }
 
 fn set_priority(app: &mut App, id: u64, priority: u8) -> Result<()> {
+    app.push_undo(format!("priority of task {}", id), &[id])?;
     app.taskbook.update_priority_silent(id, priority)?;
     app.refresh_items()?;
     app.set_status(
@@ -580,7 +1479,34 @@ fn set_priority(app: &mut App, id: u64, priority: u8) -> Result<()> {
     Ok(())
 }
 
+fn set_due(app: &mut App, id: u64, due: Option<NaiveDate>) -> Result<()> {
+    if !app.items.get(&id.to_string()).is_some_and(|i| i.is_task()) {
+        app.set_status(format!("Item {} is not a task", id), StatusKind::Error);
+        return Ok(());
+    }
+    app.push_undo(format!("due date of task {}", id), &[id])?;
+    app.taskbook.set_due_silent(id, due)?;
+    app.refresh_items()?;
+    let message = match due {
+        Some(date) => format!("Task {} due {}", id, app.config.format_date(date)),
+        None => format!("Cleared due date of task {}", id),
+    };
+    app.set_status(message, StatusKind::Success);
+    Ok(())
+}
+
+/// Delete `ids`, asking first unless it is a single item and `confirmDelete`
+/// is off
+fn request_delete(app: &mut App, ids: Vec<u64>) -> Result<()> {
+    if ids.len() == 1 && !app.config.confirm_delete {
+        return delete_items(app, &ids);
+    }
+    app.command_line.pending_confirm = Some(PendingAction::Delete { ids });
+    Ok(())
+}
+
 fn delete_items(app: &mut App, ids: &[u64]) -> Result<()> {
+    app.push_undo(format!("delete of {}", items_label(ids)), ids)?;
     app.taskbook.delete_items_silent(ids)?;
     app.refresh_items()?;
     app.set_status(
@@ -590,6 +1516,13 @@ fn delete_items(app: &mut App, ids: &[u64]) -> Result<()> {
     Ok(())
 }
 
+/// "item 7", or "items 7, 8" for several
+fn items_label(ids: &[u64]) -> String {
+    let list: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    let noun = if ids.len() == 1 { "item" } else { "items" };
+    format!("{} {}", noun, list.join(", "))
+}
+
 fn restore_item(app: &mut App, id: u64) -> Result<()> {
     app.taskbook.restore_items_silent(&[id])?;
     app.set_view(ViewMode::Archive)?;
@@ -597,8 +1530,59 @@ fn restore_item(app: &mut App, id: u64) -> Result<()> {
     Ok(())
 }
 
+/// Select a linked note and show its popup in place of the current one
+fn jump_to_note(app: &mut App, id: u64) -> Result<()> {
+    if app.select_item(id)? {
+        app.popup = Some(PopupState::Activity {
+            id,
+            scroll: 0,
+            link: 0,
+        });
+    } else {
+        app.popup = None;
+        app.set_status(format!("Note {} is not shown here", id), StatusKind::Error);
+    }
+    Ok(())
+}
+
+fn open_target(app: &mut App, id: u64, number: usize) -> Result<()> {
+    let targets: Vec<String> = app
+        .items
+        .get(&id.to_string())
+        .map(|item| {
+            attachments::openable(item)
+                .into_iter()
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    let Some(target) = targets.get(number - 1) else {
+        let message = if targets.is_empty() {
+            format!("Item {} has no attachments or links", id)
+        } else {
+            format!("Item {} has no attachment or link {}", id, number)
+        };
+        app.set_status(message, StatusKind::Error);
+        return Ok(());
+    };
+
+    opener::open(target)?;
+    let mut message = format!("Opened {}", attachments::display_name(target));
+    if targets.len() > 1 {
+        message.push_str(&format!(
+            " ({} of {}, /open @{} <n> for the others)",
+            number,
+            targets.len(),
+            id
+        ));
+    }
+    app.set_status(message, StatusKind::Success);
+    Ok(())
+}
+
 fn copy_to_clipboard(app: &mut App, id: u64) -> Result<()> {
-    app.taskbook.copy_to_clipboard_silent(&[id])?;
+    app.taskbook
+        .copy_to_clipboard_silent(&[id], CopyFormat::Text)?;
     app.set_status(
         format!("Copied item {} to clipboard", id),
         StatusKind::Success,
@@ -606,7 +1590,21 @@ fn copy_to_clipboard(app: &mut App, id: u64) -> Result<()> {
     Ok(())
 }
 
+fn copy_board(app: &mut App, board: &str) -> Result<()> {
+    let count = app.taskbook.copy_board_silent(board)?;
+    app.set_status(
+        format!(
+            "Copied {} as a Markdown checklist ({} items)",
+            board::display_name(board),
+            count
+        ),
+        StatusKind::Success,
+    );
+    Ok(())
+}
+
 fn update_tags(app: &mut App, id: u64, add: &[String], remove: &[String]) -> Result<()> {
+    app.push_undo(format!("tags of item {}", id), &[id])?;
     app.taskbook.update_tags_silent(id, add, remove)?;
     app.refresh_items()?;
     let mut parts = Vec::new();
@@ -633,16 +1631,41 @@ fn update_tags(app: &mut App, id: u64, add: &[String], remove: &[String]) -> Res
     Ok(())
 }
 
-fn clear_completed(app: &mut App) -> Result<()> {
-    let count = app.taskbook.clear_silent()?;
+fn clear_completed(app: &mut App, board: Option<&str>) -> Result<()> {
+    let ids = app.taskbook.checked_ids_silent(board)?;
+    app.push_undo(format!("clear of {}", items_label(&ids)), &ids)?;
+    let count = app.taskbook.clear_silent(board)?;
     app.refresh_items()?;
+    let scope = board
+        .map(|b| format!(" on {}", board::display_name(b)))
+        .unwrap_or_default();
     app.set_status(
-        format!("Cleared {} completed task(s)", count),
+        format!("Cleared {} completed task(s){}", count, scope),
         StatusKind::Success,
     );
     Ok(())
 }
 
+fn preview_clear(app: &mut App, board: Option<&str>) -> Result<()> {
+    let ids = app.taskbook.checked_ids_silent(board)?;
+    let scope = board
+        .map(|b| format!(" on {}", board::display_name(b)))
+        .unwrap_or_default();
+    let message = if ids.is_empty() {
+        format!("No completed tasks{} to clear", scope)
+    } else {
+        let ids_str: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        format!(
+            "Would clear {} completed task(s){}: {}",
+            ids.len(),
+            scope,
+            ids_str.join(", ")
+        )
+    };
+    app.set_status(message, StatusKind::Info);
+    Ok(())
+}
+
 fn rename_board(app: &mut App, old_name: &str, new_name: &str) -> Result<()> {
     let new_board = board::normalize_board_name(new_name);
     let count = app.taskbook.rename_board_silent(old_name, &new_board)?;
@@ -666,6 +1689,66 @@ fn rename_board(app: &mut App, old_name: &str, new_name: &str) -> Result<()> {
     Ok(())
 }
 
+fn edit_task_external(app: &mut App, id: u64) -> Result<()> {
+    let description = match app.items.get(&id.to_string()) {
+        Some(item) => item.description().to_string(),
+        None => return Ok(()),
+    };
+
+    // Suspend TUI to run external editor
+    let guard = super::suspend_tui()?;
+    let content = editor::edit_description_in_editor(&description);
+    guard.resume()?;
+    app.needs_full_redraw = true;
+
+    match content? {
+        Some(new_desc) => {
+            app.push_undo(format!("edit of task {}", id), &[id])?;
+            app.taskbook.edit_description_silent(id, &new_desc)?;
+            app.refresh_items()?;
+            app.set_status(format!("Updated task {}", id), StatusKind::Success);
+        }
+        None => {
+            app.set_status("Edit cancelled".to_string(), StatusKind::Info);
+        }
+    }
+
+    Ok(())
+}
+
+/// Edit today's journal note, or write a new one in the editor and save it
+/// on the journal board
+fn open_journal_note(app: &mut App) -> Result<()> {
+    let today = chrono::Local::now().date_naive();
+    if let Some(id) = app.taskbook.journal_note(today)? {
+        edit_note_external(app, id)?;
+        app.select_item(id)?;
+        return Ok(());
+    }
+
+    let guard = super::suspend_tui()?;
+    let content = editor::edit_existing_note_in_editor(&journal_title(today), None);
+    guard.resume()?;
+    app.needs_full_redraw = true;
+
+    match content? {
+        Some(note_content) => {
+            let id = app.taskbook.create_note_with_body_direct(
+                vec![JOURNAL_BOARD.to_string()],
+                note_content.title,
+                note_content.body,
+            )?;
+            app.refresh_items()?;
+            app.select_item(id)?;
+            app.set_status(format!("Created journal note {}", id), StatusKind::Success);
+        }
+        None => {
+            app.set_status("Journal note cancelled".to_string(), StatusKind::Info);
+        }
+    }
+    Ok(())
+}
+
 fn edit_note_external(app: &mut App, id: u64) -> Result<()> {
     let item = app.items.get(&id.to_string());
     let note = match item.and_then(|i| i.as_note()) {
@@ -693,6 +1776,7 @@ fn edit_note_external(app: &mut App, id: u64) -> Result<()> {
 
     match content? {
         Some(note_content) => {
+            app.push_undo(format!("edit of note {}", id), &[id])?;
             app.taskbook
                 .edit_description_silent(id, &note_content.title)?;
             app.taskbook.edit_note_body_silent(id, note_content.body)?;
@@ -706,3 +1790,364 @@ fn edit_note_external(app: &mut App, id: u64) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DisplayDensity;
+    use crate::tui::app::tests::test_app;
+    use crate::tui::widgets::item_row::{render_item_line, ItemRowOptions};
+
+    fn press(app: &mut App, code: KeyCode) {
+        handle_key_event(app, KeyEvent::new(code, KeyModifiers::NONE)).unwrap();
+    }
+
+    fn ctrl(app: &mut App, c: char) {
+        handle_key_event(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)).unwrap();
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    fn description(app: &App, id: u64) -> &str {
+        app.items[&id.to_string()].description()
+    }
+
+    fn ids(app: &App) -> Vec<u64> {
+        let mut ids: Vec<u64> = app.items.values().map(|item| item.id()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn settings_changed_in_the_tui_are_saved_to_the_config_file() {
+        let mut app = test_app(&["@coding Fix bug"]);
+
+        press(&mut app, KeyCode::Char('2'));
+        assert_eq!(app.view, ViewMode::Timeline);
+        let saved: config::Config =
+            serde_json::from_str(&std::fs::read_to_string(app.config_file()).unwrap()).unwrap();
+        assert_eq!(saved.default_view, ViewMode::Timeline);
+        assert!(app
+            .status_message
+            .as_ref()
+            .is_none_or(|msg| msg.kind != StatusKind::Error));
+    }
+
+    #[test]
+    fn settings_that_cannot_be_saved_are_reported() {
+        let mut app = test_app(&["@coding Fix bug"]);
+        // A directory where the config file should be
+        std::fs::create_dir_all(app.config_file()).unwrap();
+
+        press(&mut app, KeyCode::Char('2'));
+        assert_eq!(app.view, ViewMode::Timeline);
+        let msg = app.status_message.as_ref().unwrap();
+        assert_eq!(msg.kind, StatusKind::Error);
+        assert!(msg.text.starts_with("Could not save settings"));
+        assert!(app.config_save_error.is_none());
+    }
+
+    #[test]
+    fn the_density_setting_cycles_both_ways_and_is_saved() {
+        let mut app = test_app(&["@coding Fix bug"]);
+        press(&mut app, KeyCode::Char(','));
+        let row = Setting::ALL
+            .iter()
+            .position(|&setting| setting == Setting::Density)
+            .unwrap();
+        for _ in 0..row {
+            press(&mut app, KeyCode::Char('j'));
+        }
+
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.config.display_density, DisplayDensity::Detailed);
+        let saved: config::Config =
+            serde_json::from_str(&std::fs::read_to_string(app.config_file()).unwrap()).unwrap();
+        assert_eq!(saved.display_density, DisplayDensity::Detailed);
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.config.display_density, DisplayDensity::Compact);
+        press(&mut app, KeyCode::Char('h'));
+        assert_eq!(app.config.display_density, DisplayDensity::Detailed);
+        press(&mut app, KeyCode::Char('h'));
+        assert_eq!(app.config.display_density, DisplayDensity::Normal);
+    }
+
+    #[test]
+    fn the_density_decides_what_item_rows_show() {
+        let mut app = test_app(&["@coding Fix bug"]);
+        app.config.relative_timestamps = true;
+        let item = app.selected_item().unwrap().clone();
+        let row = |options: ItemRowOptions, density| {
+            let line = render_item_line(&app, &item, false, &options.with_density(density));
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect::<String>()
+        };
+        let created = crate::relative_time::format(item.timestamp(), chrono::Local::now());
+
+        // The board view leaves out boards unless detailed
+        assert!(!row(ItemRowOptions::for_board_view(), DisplayDensity::Normal).contains("coding"));
+        assert!(row(ItemRowOptions::for_board_view(), DisplayDensity::Detailed).contains("coding"));
+
+        // The timeline shows boards and creation times unless compact
+        for density in [DisplayDensity::Normal, DisplayDensity::Detailed] {
+            let text = row(ItemRowOptions::for_timeline_view(), density);
+            assert!(text.contains("coding") && text.contains(&created));
+        }
+        let compact = row(ItemRowOptions::for_timeline_view(), DisplayDensity::Compact);
+        assert!(compact.contains("Fix bug"));
+        assert!(!compact.contains("coding") && !compact.contains(&created));
+    }
+
+    #[test]
+    fn deletes_wait_for_y_and_are_dropped_by_n_or_esc() {
+        let mut app = test_app(&["@coding Fix bug", "@coding Write docs"]);
+
+        for cancel in [KeyCode::Char('n'), KeyCode::Esc] {
+            press(&mut app, KeyCode::Char('d'));
+            assert!(app.command_line.pending_confirm.is_none());
+            press(&mut app, KeyCode::Char('d'));
+            assert!(app.command_line.pending_confirm.is_some());
+            press(&mut app, cancel);
+            assert!(app.command_line.pending_confirm.is_none());
+            assert_eq!(ids(&app), vec![1, 2]);
+        }
+
+        // A key other than a second `d` drops the first, along with itself
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('j'));
+        assert!(app.command_line.pending_confirm.is_none());
+        assert_eq!(app.selected_index, 0);
+
+        // Other keys leave the question open
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('j'));
+        assert!(app.command_line.pending_confirm.is_some());
+        let selected = app.selected_id().unwrap();
+        press(&mut app, KeyCode::Char('y'));
+        assert!(app.command_line.pending_confirm.is_none());
+        assert_eq!(ids(&app).len(), 1);
+        assert!(!ids(&app).contains(&selected));
+        assert!(app.config.confirm_delete);
+    }
+
+    #[test]
+    fn a_deletes_and_stops_asking_for_single_deletes() {
+        let mut app = test_app(&["@coding Fix bug", "@coding Write docs", "@coding Ship"]);
+
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('a'));
+        assert!(app.command_line.pending_confirm.is_none());
+        assert_eq!(ids(&app).len(), 2);
+        assert!(!app.config.confirm_delete);
+
+        // With confirmDelete off a single delete goes straight through...
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('d'));
+        assert!(app.command_line.pending_confirm.is_none());
+        assert_eq!(ids(&app).len(), 1);
+
+        // ...but several items are still asked about, and `a` is not an answer
+        let mut app = test_app(&["@coding Fix bug", "@coding Write docs"]);
+        app.config.confirm_delete = false;
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('d'));
+        assert!(matches!(
+            &app.command_line.pending_confirm,
+            Some(PendingAction::Delete { ids }) if ids.len() == 2
+        ));
+        press(&mut app, KeyCode::Char('a'));
+        assert!(app.command_line.pending_confirm.is_some());
+        press(&mut app, KeyCode::Enter);
+        assert!(ids(&app).is_empty());
+    }
+
+    #[test]
+    fn inline_edits_save_on_enter_exactly_as_typed() {
+        let mut app = test_app(&["@coding Fix bug"]);
+
+        press(&mut app, KeyCode::Char('e'));
+        let edit = app.inline_edit.as_ref().unwrap();
+        assert_eq!(
+            (edit.id, edit.input.as_str(), edit.cursor),
+            (1, "Fix bug", 7)
+        );
+
+        // Keys type rather than act while editing
+        press(&mut app, KeyCode::Left);
+        press(&mut app, KeyCode::Left);
+        press(&mut app, KeyCode::Left);
+        type_text(&mut app, "the ");
+        ctrl(&mut app, 'a');
+        type_text(&mut app, "d ");
+        ctrl(&mut app, 'e');
+        type_text(&mut app, " in /sync @home");
+        assert_eq!(app.inline_edit.as_ref().unwrap().cursor, 28);
+        assert_eq!(ids(&app), vec![1]);
+
+        press(&mut app, KeyCode::Enter);
+        assert!(app.inline_edit.is_none());
+        assert_eq!(description(&app, 1), "d Fix the bug in /sync @home");
+        press(&mut app, KeyCode::Char('u'));
+        assert_eq!(description(&app, 1), "Fix bug");
+    }
+
+    #[test]
+    fn inline_edits_are_dropped_by_esc_and_refused_when_empty() {
+        let mut app = test_app(&["@coding Fix bug"]);
+
+        press(&mut app, KeyCode::Char('e'));
+        type_text(&mut app, " now");
+        press(&mut app, KeyCode::Esc);
+        assert!(app.inline_edit.is_none());
+        assert_eq!(description(&app, 1), "Fix bug");
+
+        press(&mut app, KeyCode::Char('e'));
+        ctrl(&mut app, 'u');
+        type_text(&mut app, "   ");
+        press(&mut app, KeyCode::Enter);
+        assert!(app.inline_edit.is_some());
+        assert_eq!(
+            app.status_message.as_ref().map(|msg| msg.kind),
+            Some(StatusKind::Error)
+        );
+        assert_eq!(description(&app, 1), "Fix bug");
+
+        type_text(&mut app, "Fix it");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(description(&app, 1), "Fix it");
+    }
+
+    #[test]
+    fn counts_repeat_the_motion_after_them() {
+        let mut app = test_app(&[
+            "@coding One",
+            "@coding Two",
+            "@coding Three",
+            "@coding Four",
+            "@coding Five",
+            "@coding Six",
+        ]);
+
+        // A lone digit switches view at once, and back when a motion follows
+        press(&mut app, KeyCode::Char('3'));
+        assert_eq!(app.count.as_ref().map(|count| count.value), Some(3));
+        assert_eq!(app.view, ViewMode::Archive);
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.view, ViewMode::Board);
+        assert_eq!(app.selected_index, 3);
+        assert!(app.count.is_none());
+
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.selected_index, 1);
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.selected_index, 2);
+
+        press(&mut app, KeyCode::Char('1'));
+        press(&mut app, KeyCode::Char('0'));
+        assert_eq!(app.view, ViewMode::Board);
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.selected_index, 5);
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Char('G'));
+        assert_eq!(app.selected_index, 1);
+    }
+
+    #[test]
+    fn counts_end_at_any_other_key() {
+        let mut app = test_app(&["@coding One", "@coding Two", "@coding Three"]);
+
+        // Esc drops the count
+        press(&mut app, KeyCode::Char('1'));
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.count.is_none());
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.selected_index, 1);
+
+        // A key that takes no count leaves the view the digit switched to,
+        // then acts
+        let hidden = app.filter.hide_completed;
+        press(&mut app, KeyCode::Char('2'));
+        assert_eq!(app.view, ViewMode::Timeline);
+        press(&mut app, KeyCode::Char('h'));
+        assert!(app.count.is_none());
+        assert_eq!(app.view, ViewMode::Timeline);
+        assert_ne!(app.filter.hide_completed, hidden);
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.selected_index, 1);
+    }
+
+    #[test]
+    fn counts_delete_that_many_rows_with_dd() {
+        let mut app = test_app(&[
+            "@coding One",
+            "@coding Two",
+            "@coding Three",
+            "@coding Four",
+        ]);
+        app.config.confirm_delete = false;
+
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('3'));
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.view, ViewMode::Board);
+        assert_eq!(ids(&app).len(), 4);
+        press(&mut app, KeyCode::Char('d'));
+        assert!(matches!(
+            &app.command_line.pending_confirm,
+            Some(PendingAction::Delete { ids }) if ids.len() == 3
+        ));
+    }
+
+    #[test]
+    fn the_board_picker_takes_the_keys_until_handed_back() {
+        let mut app = test_app(&[
+            "@coding Fix bug",
+            "@coding Write docs",
+            "@home Water plants",
+        ]);
+        app.set_view(ViewMode::Timeline).unwrap();
+
+        press(&mut app, KeyCode::Char('B'));
+        assert_eq!(app.view, ViewMode::Board);
+        assert!(app.sidebar.open && app.sidebar.focused);
+
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.picked_board(), 1);
+        press(&mut app, KeyCode::Char('G'));
+        assert_eq!(app.picked_board(), app.boards.len());
+        press(&mut app, KeyCode::Char('g'));
+        assert!(app.filter.board_filter.is_none());
+        // Boards are "My Board", then @coding and @home
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.picked_board(), 2);
+        assert_eq!(app.display_order.len(), 2);
+
+        // Handed back, j moves in the list again
+        press(&mut app, KeyCode::Enter);
+        assert!(app.sidebar.open && !app.sidebar.focused);
+        let first = app.selected_index;
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.picked_board(), 2);
+        assert_eq!(app.selected_index, first + 1);
+
+        press(&mut app, KeyCode::Char('B'));
+        assert!(app.sidebar.focused);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.sidebar.open && !app.sidebar.focused);
+        press(&mut app, KeyCode::Char('B'));
+        press(&mut app, KeyCode::Char('B'));
+        assert!(!app.sidebar.open && !app.sidebar.focused);
+    }
+}
@@ -1,15 +1,20 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
+use chrono::{Months, NaiveDate, TimeZone};
 use serde::{Deserialize, Serialize};
 
-use crate::config::{Config, SortMethod};
-use crate::error::Result;
+use crate::agenda;
+use crate::config::{self, Config, Icons, SortMethod, ThemeConfig, TimelineGroup};
+use crate::credentials::Credentials;
+use crate::error::{Result, TaskbookError};
 use crate::render::Stats;
+use crate::storage::SharedBoardInfo;
 use crate::taskbook::Taskbook;
 use taskbook_common::board;
-use taskbook_common::StorageItem;
+use taskbook_common::{FocusSession, StorageItem};
 
 /// Sort items by the given method
 pub fn sort_items_by(items: &mut [&StorageItem], method: SortMethod) {
@@ -44,10 +49,19 @@ pub fn sort_items_by(items: &mut [&StorageItem], method: SortMethod) {
                     .then_with(|| a.id().cmp(&b.id()))
             });
         }
+        SortMethod::Manual => {
+            // Items never moved by hand go last
+            items.sort_by_key(|item| (item.position().is_none(), item.position(), item.id()));
+        }
     }
 }
 
+use super::command_parser;
+use super::focus::{self, FocusTimer};
+use super::session::{self, Session};
+use super::setup::SetupState;
 use super::theme::TuiTheme;
+use super::widgets::journal_view::journal_timestamp;
 
 /// Main application state
 pub struct App {
@@ -59,30 +73,54 @@ pub struct App {
     pub selected_index: usize,
     /// List of boards for navigation
     pub boards: Vec<String>,
+    /// Boards shared with other accounts (remote storage only)
+    pub shared_boards: Vec<SharedBoardInfo>,
     /// Cached items grouped by board/date
     pub items: HashMap<String, StorageItem>,
-    /// Active popup/dialog state (Help only)
+    /// Active popup/dialog state (help or an item's activity)
     pub popup: Option<PopupState>,
     /// Command line state
     pub command_line: CommandLineState,
     /// Status message (success/error feedback)
     pub status_message: Option<StatusMessage>,
+    /// Why the config could not be saved during the current key, shown
+    /// once the key has been handled
+    pub config_save_error: Option<String>,
     /// Filter state
     pub filter: FilterState,
+    /// Calendar view state
+    pub calendar: CalendarState,
+    /// Board picker down the left of the board view
+    pub sidebar: SidebarState,
+    /// Running focus session, if any
+    pub focus: Option<FocusTimer>,
+    /// First-run setup wizard, shown over everything until finished
+    pub setup: Option<SetupState>,
     /// Application running flag
     pub running: bool,
     /// Theme colors for rendering
     pub theme: TuiTheme,
+    /// Glyphs for item states and markers
+    pub icons: Icons,
     /// Configuration
     pub config: Config,
     /// Current sort method for items within boards
     pub sort_method: SortMethod,
-    /// Flat list of item IDs in display order (for navigation)
-    pub display_order: Vec<u64>,
+    /// Rows in display order (for navigation): items, and in the board view
+    /// the headers of collapsed boards
+    pub display_order: Vec<ListRow>,
     /// Cached statistics (recalculated on refresh)
     cached_stats: Stats,
+    /// Cached dashboard statistics (recalculated on refresh)
+    cached_dashboard: DashboardStats,
     /// Flag to request a full terminal redraw (e.g. after suspend/resume)
     pub needs_full_redraw: bool,
+    /// Flag to reconnect sync notifications after switching profile
+    pub sync_changed: bool,
+    /// `--taskbook-dir` override, kept for rebuilding on profile switch
+    taskbook_dir: Option<PathBuf>,
+    /// Config file settings are saved to, if not the usual one
+    config_file: Option<PathBuf>,
     /// Last known content area height (updated each render frame)
     pub content_height: u16,
     /// Command history (most recent last)
@@ -91,6 +129,22 @@ pub struct App {
     pub history_index: Option<usize>,
     /// Saved input before browsing history
     pub history_saved_input: String,
+    /// `/search` being typed, filtering the list as it changes
+    pub live_search: Option<LiveSearch>,
+    /// Items marked with Space, moved together by `m`
+    pub marked: BTreeSet<u64>,
+    /// Scroll position of the item list
+    pub list_scroll: ListScroll,
+    /// First key of a two-key command (`zz`, `dd`) waiting for its second
+    pub pending_key: Option<char>,
+    /// Digits typed before a motion, as in `5j` or `3dd`
+    pub count: Option<PendingCount>,
+    /// Boards showing only their header in the board view
+    pub collapsed_boards: Vec<String>,
+    /// Changes `u` can undo, the latest last
+    pub undo_stack: Vec<UndoEntry>,
+    /// Description being edited in place with `e`
+    pub inline_edit: Option<InlineEdit>,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -101,11 +155,77 @@ pub enum ViewMode {
     Timeline,
     Archive,
     Journal,
+    Calendar,
+    Today,
+    Stats,
+}
+
+impl ViewMode {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            ViewMode::Board => "Board",
+            ViewMode::Timeline => "Timeline",
+            ViewMode::Archive => "Archive",
+            ViewMode::Journal => "Journal",
+            ViewMode::Calendar => "Calendar",
+            ViewMode::Today => "Today",
+            ViewMode::Stats => "Stats",
+        }
+    }
+
+    /// The view the number key `n` switches to
+    pub fn from_number(n: usize) -> Option<Self> {
+        match n {
+            1 => Some(ViewMode::Board),
+            2 => Some(ViewMode::Timeline),
+            3 => Some(ViewMode::Archive),
+            4 => Some(ViewMode::Journal),
+            5 => Some(ViewMode::Calendar),
+            6 => Some(ViewMode::Today),
+            7 => Some(ViewMode::Stats),
+            _ => None,
+        }
+    }
+}
+
+/// A row of the list the selection can rest on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListRow {
+    Item(u64),
+    /// Header of a collapsed board, standing for its items
+    Board(String),
+}
+
+impl ListRow {
+    pub fn item_id(&self) -> Option<u64> {
+        match self {
+            ListRow::Item(id) => Some(*id),
+            ListRow::Board(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum PopupState {
-    Help { scroll: u16 },
+    Help {
+        scroll: u16,
+    },
+    /// Activity log of the item with this id
+    Activity {
+        id: u64,
+        scroll: u16,
+        /// Which linked note Enter jumps to (see `wiki::jump_targets`)
+        link: usize,
+    },
+    /// Settings, with the row at this index selected
+    Settings {
+        selected: usize,
+    },
+    /// Theme picker, previewing the preset at this index of
+    /// `ThemeColors::PRESETS` until Enter keeps it or Esc goes back
+    Theme {
+        selected: usize,
+    },
 }
 
 /// Command line state for the bottom input bar
@@ -123,6 +243,38 @@ pub struct CommandLineState {
     pub selected_suggestion: Option<usize>,
     /// Pending confirmation action
     pub pending_confirm: Option<PendingAction>,
+    /// Ctrl+R search through the history, shown in place of the input
+    pub history_search: Option<HistorySearch>,
+}
+
+/// An item's description being edited in its row, taken as typed rather
+/// than parsed as a command
+#[derive(Debug, Clone)]
+pub struct InlineEdit {
+    pub id: u64,
+    pub input: String,
+    /// Cursor position (character index)
+    pub cursor: usize,
+}
+
+/// A Ctrl+R search back through the command history
+#[derive(Debug, Clone, Default)]
+pub struct HistorySearch {
+    /// Text to look for, as typed so far
+    pub query: String,
+    /// Position in the history of the command shown
+    pub found: Option<usize>,
+}
+
+impl HistorySearch {
+    /// Position of the newest command before `before` containing the
+    /// query, ignoring case
+    pub fn find(&self, history: &[String], before: usize) -> Option<usize> {
+        let query = self.query.to_lowercase();
+        history[..before.min(history.len())]
+            .iter()
+            .rposition(|command| command.to_lowercase().contains(&query))
+    }
 }
 
 /// An autocomplete suggestion
@@ -143,26 +295,198 @@ pub enum SuggestionKind {
     Command,
     Board,
     Item,
+    Tag,
 }
 
 /// An action waiting for confirmation
 #[derive(Debug, Clone)]
 pub enum PendingAction {
-    Delete { ids: Vec<u64> },
-    Clear,
+    Delete {
+        ids: Vec<u64>,
+    },
+    /// Clear completed tasks, only those on `board` if given
+    Clear {
+        board: Option<String>,
+    },
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct FilterState {
-    #[allow(dead_code)]
+    /// `--list` style terms from an applied saved filter
     pub attributes: Vec<String>,
     pub search_term: Option<String>,
     /// Filter to show only items from this board
     pub board_filter: Option<String>,
+    /// Filter to show only items with this tag, set by `/tag <name>`
+    pub tag_filter: Option<String>,
     /// Hide completed tasks
     pub hide_completed: bool,
 }
 
+/// A `/search` being typed into the command line
+#[derive(Debug, Clone)]
+pub struct LiveSearch {
+    /// Search before typing began, put back on Esc
+    pub previous: Option<String>,
+    /// When to apply the term typed so far; `None` once it is applied
+    pub due: Option<Instant>,
+}
+
+/// Scroll position of the item list, kept between frames so the list only
+/// moves as far as it must to keep the selection in view. Cells, because
+/// the list is laid out while drawing, from a shared `App`.
+#[derive(Debug, Default)]
+pub struct ListScroll {
+    /// First line shown
+    pub offset: Cell<usize>,
+    /// Where to put the selected line in the next frame
+    pub align: Cell<Option<ScrollAlign>>,
+}
+
+/// Where `zt`, `zz` and `zb` put the selected line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollAlign {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// A count being typed in front of a key
+#[derive(Debug, Clone)]
+pub struct PendingCount {
+    pub value: usize,
+    /// Where the list was before a lone digit switched to the view with
+    /// its number, to go back to if the digit turns out to be a count
+    pub before: Option<ViewState>,
+}
+
+/// A view and where the selection was in it
+#[derive(Debug, Clone)]
+pub struct ViewState {
+    pub view: ViewMode,
+    board_filter: Option<String>,
+    selected_index: usize,
+    scroll_offset: usize,
+    /// Whether the calendar's item list had focus rather than its grid
+    pub calendar_items: bool,
+    sidebar_focused: bool,
+}
+
+/// A change made in the TUI and the items it changed, as they were before
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    /// What was done, as in "delete of item 7"
+    pub label: String,
+    pub snapshot: Vec<StorageItem>,
+}
+
+/// Most changes `u` can undo
+const UNDO_LIMIT: usize = 50;
+
+/// Largest count a key takes
+pub const MAX_COUNT: usize = 9999;
+
+/// How long typing must pause before the list follows a `/search`
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Number of days covered by the dashboard's completion sparkline
+pub const DASHBOARD_DAYS: usize = 30;
+
+/// Extended statistics shown in the stats dashboard
+#[derive(Debug, Clone, Default)]
+pub struct DashboardStats {
+    /// `(board, complete, total)` task counts for each board with tasks
+    pub boards: Vec<(String, usize, usize)>,
+    /// Tasks completed on each of the last `DASHBOARD_DAYS` days, oldest first
+    pub completed_per_day: Vec<u64>,
+    /// Average age of pending tasks, in days
+    pub average_pending_age: Option<f64>,
+    /// Pending task counts by priority: normal, medium, high
+    pub priority_counts: [u64; 3],
+}
+
+impl DashboardStats {
+    fn from_items(items: &HashMap<String, StorageItem>, boards: &[String]) -> Self {
+        let now = chrono::Local::now();
+        let today = now.date_naive();
+        let day_ms = 24 * 60 * 60 * 1000;
+
+        let mut board_counts: Vec<(String, usize, usize)> =
+            boards.iter().map(|name| (name.clone(), 0, 0)).collect();
+        let mut completed_per_day = vec![0u64; DASHBOARD_DAYS];
+        let mut pending_age_total = 0i64;
+        let mut pending_count = 0i64;
+        let mut priority_counts = [0u64; 3];
+
+        for task in items.values().filter_map(|item| item.as_task()) {
+            for (name, complete, total) in board_counts.iter_mut() {
+                if task.boards.iter().any(|b| board::board_eq(b, name)) {
+                    *total += 1;
+                    if task.is_complete {
+                        *complete += 1;
+                    }
+                }
+            }
+
+            if task.is_complete {
+                let completed_on = task.completed_at.and_then(|ms| {
+                    chrono::Local
+                        .timestamp_millis_opt(ms)
+                        .single()
+                        .map(|dt| dt.date_naive())
+                });
+                if let Some(date) = completed_on {
+                    let days_ago = (today - date).num_days();
+                    if (0..DASHBOARD_DAYS as i64).contains(&days_ago) {
+                        completed_per_day[DASHBOARD_DAYS - 1 - days_ago as usize] += 1;
+                    }
+                }
+            } else {
+                pending_age_total += (now.timestamp_millis() - task.timestamp).max(0) / day_ms;
+                pending_count += 1;
+                let slot = task.priority.clamp(1, 3) as usize - 1;
+                priority_counts[slot] += 1;
+            }
+        }
+
+        board_counts.retain(|(_, _, total)| *total > 0);
+
+        Self {
+            boards: board_counts,
+            completed_per_day,
+            average_pending_age: (pending_count > 0)
+                .then(|| pending_age_total as f64 / pending_count as f64),
+            priority_counts,
+        }
+    }
+}
+
+/// Calendar view state
+#[derive(Debug, Clone)]
+pub struct CalendarState {
+    /// Day highlighted in the month grid
+    pub day: NaiveDate,
+    /// Whether the day's item list has focus (after Enter)
+    pub focus_items: bool,
+}
+
+impl Default for CalendarState {
+    fn default() -> Self {
+        Self {
+            day: chrono::Local::now().date_naive(),
+            focus_items: false,
+        }
+    }
+}
+
+/// Board picker state
+#[derive(Debug, Clone, Default)]
+pub struct SidebarState {
+    pub open: bool,
+    /// Whether the picker has the keys, rather than the list
+    pub focused: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct StatusMessage {
     pub text: String,
@@ -182,33 +506,76 @@ impl App {
     pub fn new(taskbook_dir: Option<&Path>) -> Result<Self> {
         let taskbook = Taskbook::new(taskbook_dir)?;
         let config = Config::load_or_default();
-        let theme = TuiTheme::from(&config.theme.resolve());
+        let mut app = Self::with_taskbook(taskbook, config, None, taskbook_dir)?;
+
+        // First run: no config file written yet and nothing stored
+        if !Config::file_exists()
+            && app.items.is_empty()
+            && app.taskbook.get_all_archive_items()?.is_empty()
+        {
+            app.setup = Some(SetupState::new(&app.config));
+        }
+
+        Ok(app)
+    }
+
+    /// The app over `taskbook`, back where the last session in
+    /// `taskbook_dir` left off, saving settings to `config_file` (or the
+    /// usual config file)
+    fn with_taskbook(
+        taskbook: Taskbook,
+        config: Config,
+        config_file: Option<PathBuf>,
+        taskbook_dir: Option<&Path>,
+    ) -> Result<Self> {
+        let theme = TuiTheme::new(&config.theme.resolve(), config.color_mode);
+        let icons = config.icons.resolve();
 
         let initial_view = config.default_view;
+        let command_history = session::load_history(taskbook_dir, &config);
 
         let mut app = Self {
             taskbook,
             view: initial_view,
             selected_index: 0,
             boards: Vec::new(),
+            shared_boards: Vec::new(),
             items: HashMap::new(),
             popup: None,
             command_line: CommandLineState::default(),
             status_message: None,
+            config_save_error: None,
             filter: FilterState {
                 hide_completed: !config.display_complete_tasks,
                 ..Default::default()
             },
+            calendar: CalendarState::default(),
+            sidebar: SidebarState::default(),
+            focus: None,
+            setup: None,
             running: true,
             theme,
+            icons,
             sort_method: config.sort_method,
             config,
             display_order: Vec::new(),
             needs_full_redraw: false,
+            sync_changed: false,
+            taskbook_dir: taskbook_dir.map(Path::to_path_buf),
+            config_file,
             content_height: 20,
-            command_history: Vec::new(),
+            command_history,
             history_index: None,
             history_saved_input: String::new(),
+            live_search: None,
+            marked: BTreeSet::new(),
+            list_scroll: ListScroll::default(),
+            pending_key: None,
+            count: None,
+            collapsed_boards: Vec::new(),
+            undo_stack: Vec::new(),
+            inline_edit: None,
+            cached_dashboard: DashboardStats::default(),
             cached_stats: Stats {
                 percent: 0,
                 complete: 0,
@@ -227,6 +594,8 @@ impl App {
             app.recalculate_stats();
         }
 
+        app.restore_session();
+
         Ok(app)
     }
 
@@ -234,6 +603,7 @@ impl App {
     pub fn refresh_items(&mut self) -> Result<()> {
         self.items = self.taskbook.get_all_items()?;
         self.boards = self.taskbook.get_all_boards()?;
+        self.shared_boards = self.taskbook.get_shared_boards();
         self.update_display_order();
         self.recalculate_stats();
 
@@ -245,9 +615,16 @@ impl App {
         Ok(())
     }
 
+    /// Sharing details for `board`, if it is shared with other accounts
+    pub fn shared_board(&self, board: &str) -> Option<&SharedBoardInfo> {
+        self.shared_boards
+            .iter()
+            .find(|shared| board::board_eq(&shared.name, board))
+    }
+
     /// Recalculate cached statistics
     fn recalculate_stats(&mut self) {
-        let mut complete = 0;
+        let mut complete: usize = 0;
         let mut in_progress = 0;
         let mut pending = 0;
         let mut notes = 0;
@@ -267,11 +644,7 @@ impl App {
         }
 
         let total = complete + pending + in_progress;
-        let percent = if total == 0 {
-            0
-        } else {
-            (complete * 100 / total) as u32
-        };
+        let percent = (complete * 100).checked_div(total).unwrap_or(0) as u32;
 
         self.cached_stats = Stats {
             percent,
@@ -280,6 +653,7 @@ impl App {
             pending,
             notes,
         };
+        self.cached_dashboard = DashboardStats::from_items(&self.items, &self.boards);
     }
 
     /// Check if an item should be shown based on current filters
@@ -291,6 +665,16 @@ impl App {
                 }
             }
         }
+        if !self.filter.attributes.is_empty()
+            && !Taskbook::matches_list_terms(item, &self.filter.attributes, &self.boards)
+        {
+            return false;
+        }
+        if let Some(ref tag) = self.filter.tag_filter {
+            if !item.tags().iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                return false;
+            }
+        }
         if let Some(ref term) = self.filter.search_term {
             let term_lower = term.to_lowercase();
             let in_description = item.description().to_lowercase().contains(&term_lower);
@@ -333,10 +717,18 @@ impl App {
                                 && self.should_show_item(item)
                         })
                         .collect();
-                    sort_items_by(&mut board_items, self.sort_method);
+                    if board_items.is_empty() {
+                        continue;
+                    }
+                    if self.is_collapsed(board) {
+                        self.display_order.push(ListRow::Board(board.clone()));
+                        continue;
+                    }
+                    sort_items_by(&mut board_items, self.config.sort_method_for(board));
                     for item in board_items {
-                        if !self.display_order.contains(&item.id()) {
-                            self.display_order.push(item.id());
+                        let row = ListRow::Item(item.id());
+                        if !self.display_order.contains(&row) {
+                            self.display_order.push(row);
                         }
                     }
                 }
@@ -354,11 +746,11 @@ impl App {
                         .then_with(|| a.id().cmp(&b.id()))
                 });
                 for item in items {
-                    self.display_order.push(item.id());
+                    self.display_order.push(ListRow::Item(item.id()));
                 }
             }
             ViewMode::Journal => {
-                // Order by date (newest first like timeline), then by ID
+                // Newest first by completion (or creation) time, then by ID
                 // Journal always shows completed tasks - only apply search filter
                 let mut items: Vec<_> = self
                     .items
@@ -383,12 +775,44 @@ impl App {
                     })
                     .collect();
                 items.sort_by(|a, b| {
-                    b.timestamp()
-                        .cmp(&a.timestamp())
+                    journal_timestamp(b)
+                        .cmp(&journal_timestamp(a))
                         .then_with(|| a.id().cmp(&b.id()))
                 });
                 for item in items {
-                    self.display_order.push(item.id());
+                    self.display_order.push(ListRow::Item(item.id()));
+                }
+            }
+            ViewMode::Calendar => {
+                // Items due or created on the selected day, by ID
+                let day = self.calendar.day;
+                let mut items: Vec<_> = self
+                    .items
+                    .values()
+                    .filter(|item| {
+                        (item.due_date() == Some(day) || item.created_date() == Some(day))
+                            && self.should_show_item(item)
+                    })
+                    .collect();
+                items.sort_by_key(|item| item.id());
+                for item in items {
+                    self.display_order.push(ListRow::Item(item.id()));
+                }
+            }
+            ViewMode::Stats => {
+                // The dashboard has no selectable items
+            }
+            ViewMode::Today => {
+                // Agenda order: overdue, due today, in progress, starred
+                let today = chrono::Local::now().date_naive();
+                let visible = self
+                    .items
+                    .values()
+                    .filter(|item| self.should_show_item(item));
+                for (_, items) in agenda::group(visible, today) {
+                    for item in items {
+                        self.display_order.push(ListRow::Item(item.id()));
+                    }
                 }
             }
         }
@@ -396,17 +820,155 @@ impl App {
 
     /// Cycle through sort methods and persist to config
     pub fn cycle_sort_method(&mut self) {
-        self.sort_method = self.sort_method.next();
-        self.config.sort_method = self.sort_method;
-        let _ = self.config.save();
+        self.set_sort_method(self.sort_method.next());
+    }
+
+    /// Sort by `method` and persist to config
+    pub fn set_sort_method(&mut self, method: SortMethod) {
+        self.sort_method = method;
+        self.config.sort_method = method;
+        self.save_config();
+        self.update_display_order();
+    }
+
+    /// Sort one board by `method` (or by the global method again when
+    /// `None`) and persist to config
+    pub fn set_board_sort(&mut self, board: &str, method: Option<SortMethod>) {
+        self.config.set_board_sort(board, method);
+        self.save_config();
         self.update_display_order();
     }
 
+    /// Group the timeline by day, week or month and persist to config
+    pub fn set_timeline_group(&mut self, group: TimelineGroup) {
+        self.config.timeline_group = group;
+        self.save_config();
+    }
+
+    /// Switch to another profile (`None` for the top-level settings),
+    /// reopening storage and reloading the profile's theme. On failure the
+    /// current profile stays active.
+    pub fn switch_profile(&mut self, name: Option<&str>) -> Result<()> {
+        if self.focus.is_some() {
+            return Err(TaskbookError::General(
+                "stop the focus session before switching profile".to_string(),
+            ));
+        }
+        let previous = config::active_profile();
+        Config::use_profile(name)?;
+        let taskbook = match Taskbook::new(self.taskbook_dir.as_deref()) {
+            Ok(taskbook) => taskbook,
+            Err(e) => {
+                let _ = Config::use_profile(previous.as_deref());
+                return Err(e);
+            }
+        };
+
+        self.taskbook = taskbook;
+        self.config = Config::load_or_default();
+        self.theme = TuiTheme::new(&self.config.theme.resolve(), self.config.color_mode);
+        self.icons = self.config.icons.resolve();
+        self.sort_method = self.config.sort_method;
+        self.filter = FilterState {
+            hide_completed: !self.config.display_complete_tasks,
+            ..Default::default()
+        };
+        self.selected_index = 0;
+        self.sync_changed = true;
+        self.reload_view()
+    }
+
+    /// Turn sync on or off and reopen storage to match. Turning it on needs
+    /// the credentials saved by `tb --login`.
+    pub fn set_sync(&mut self, enabled: bool) -> Result<()> {
+        if enabled {
+            let creds = Credentials::load()?.ok_or_else(|| {
+                TaskbookError::General(format!(
+                    "not logged in; quit and run tb --login --server {}",
+                    self.config.sync.server_url
+                ))
+            })?;
+            self.config.enable_sync(&creds.server_url)?;
+        } else {
+            self.config.disable_sync()?;
+        }
+
+        match Taskbook::new(self.taskbook_dir.as_deref()) {
+            Ok(taskbook) => self.taskbook = taskbook,
+            Err(e) => {
+                // Keep using the storage that is still open
+                self.config.sync.enabled = !enabled;
+                let _ = self.write_config();
+                return Err(e);
+            }
+        }
+        self.selected_index = 0;
+        self.sync_changed = true;
+        self.reload_view()
+    }
+
+    /// Remember items `ids` as they are, before a change described by
+    /// `label`, so `u` can undo it
+    pub fn push_undo(&mut self, label: String, ids: &[u64]) -> Result<()> {
+        let snapshot = self.taskbook.snapshot_silent(ids)?;
+        if snapshot.is_empty() {
+            return Ok(());
+        }
+        self.undo_stack.push(UndoEntry { label, snapshot });
+        if self.undo_stack.len() > UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        Ok(())
+    }
+
+    /// Put back the items the latest change changed, as they were before it
+    pub fn undo(&mut self) -> Result<()> {
+        let Some(entry) = self.undo_stack.pop() else {
+            self.set_status("Nothing to undo".to_string(), StatusKind::Info);
+            return Ok(());
+        };
+        let ids = self.taskbook.undo_silent(&entry.snapshot)?;
+        self.reload_view()?;
+        if let [id] = ids[..] {
+            if let Some(index) = self
+                .display_order
+                .iter()
+                .position(|row| row.item_id() == Some(id))
+            {
+                self.selected_index = index;
+            }
+        }
+        self.set_status(format!("Undid {}", entry.label), StatusKind::Success);
+        Ok(())
+    }
+
+    /// Load the items of the current view again after reopening storage
+    fn reload_view(&mut self) -> Result<()> {
+        // Changes to the storage that was open can't be undone in this one
+        self.undo_stack.clear();
+        if self.view == ViewMode::Archive {
+            self.items = self.taskbook.get_all_archive_items()?;
+            self.boards = self.taskbook.get_all_boards()?;
+            self.update_display_order();
+            self.recalculate_stats();
+        } else {
+            self.refresh_items()?;
+        }
+        Ok(())
+    }
+
+    /// Switch to the preset theme `name` and persist to config
+    pub fn set_theme(&mut self, name: &str) {
+        self.config.theme = ThemeConfig::Preset(name.to_string());
+        self.theme = TuiTheme::new(&self.config.theme.resolve(), self.config.color_mode);
+        self.save_config();
+    }
+
     /// Toggle hide completed tasks
     pub fn toggle_hide_completed(&mut self) {
         self.filter.hide_completed = !self.filter.hide_completed;
         self.config.display_complete_tasks = !self.filter.hide_completed;
-        let _ = self.config.save();
+        self.save_config();
         self.update_display_order();
         // Clamp selection
         if !self.display_order.is_empty() && self.selected_index >= self.display_order.len() {
@@ -427,6 +989,79 @@ impl App {
         self.update_display_order();
     }
 
+    /// Items each board shows with the filters other than the board filter,
+    /// for the board picker; the first entry is every board together
+    pub fn board_counts(&self) -> Vec<(Option<String>, usize)> {
+        let shown: Vec<&StorageItem> = self
+            .items
+            .values()
+            .filter(|item| self.should_show_item(item))
+            .collect();
+        let mut counts = vec![(None, shown.len())];
+        for name in &self.boards {
+            let count = shown
+                .iter()
+                .filter(|item| item.boards().iter().any(|b| board::board_eq(b, name)))
+                .count();
+            counts.push((Some(name.clone()), count));
+        }
+        counts
+    }
+
+    /// Row of the board picker for the board filter: 0 for every board,
+    /// then the boards in order
+    pub fn picked_board(&self) -> usize {
+        self.filter
+            .board_filter
+            .as_ref()
+            .and_then(|filter| self.boards.iter().position(|b| board::board_eq(b, filter)))
+            .map_or(0, |index| index + 1)
+    }
+
+    /// Filter the board view by the board on row `index` of the board picker
+    pub fn pick_board(&mut self, index: usize) {
+        let index = index.min(self.boards.len());
+        if index == self.picked_board() {
+            return;
+        }
+        let board = index.checked_sub(1).map(|i| self.boards[i].clone());
+        self.set_board_filter(board);
+    }
+
+    /// Put back the board filter and selection from when the TUI last
+    /// closed, as far as the boards and items still exist
+    fn restore_session(&mut self) {
+        let session = Session::load(self.taskbook_dir.as_deref(), &self.config);
+        self.collapsed_boards = session
+            .collapsed_boards
+            .into_iter()
+            .filter(|name| self.boards.iter().any(|b| board::board_eq(b, name)))
+            .collect();
+        self.update_display_order();
+        if let Some(filter) = session.board_filter {
+            if self.boards.iter().any(|b| board::board_eq(b, &filter)) {
+                self.set_board_filter(Some(filter));
+            }
+        }
+        if let Some(index) = session.selected_item.and_then(|id| {
+            self.display_order
+                .iter()
+                .position(|row| *row == ListRow::Item(id))
+        }) {
+            self.selected_index = index;
+        }
+    }
+
+    /// Save the board filter and selection for the next start
+    pub fn save_session(&self) -> Result<()> {
+        let session = Session {
+            board_filter: self.filter.board_filter.clone(),
+            collapsed_boards: self.collapsed_boards.clone(),
+            selected_item: self.selected_id(),
+        };
+        session.save(self.taskbook_dir.as_deref(), &self.config)
+    }
+
     /// Clear board filter
     pub fn clear_board_filter(&mut self) {
         self.filter.board_filter = None;
@@ -434,9 +1069,128 @@ impl App {
         self.update_display_order();
     }
 
+    /// Apply a saved filter from config. Returns false if no filter has that name.
+    pub fn apply_saved_filter(&mut self, name: &str) -> bool {
+        let Some(terms) = self.config.saved_filters.get(name) else {
+            return false;
+        };
+        self.filter.attributes = terms.clone();
+        self.selected_index = 0;
+        self.update_display_order();
+        true
+    }
+
+    /// Show only items tagged `tag`, or every item again when `None`
+    pub fn set_tag_filter(&mut self, tag: Option<String>) {
+        self.filter.tag_filter = tag;
+        self.selected_index = 0;
+        self.update_display_order();
+    }
+
+    /// Clear the applied saved filter
+    pub fn clear_saved_filter(&mut self) {
+        self.filter.attributes.clear();
+        self.selected_index = 0;
+        self.update_display_order();
+    }
+
+    /// Whether a filter or search keeps every board open
+    fn boards_held_open(&self) -> bool {
+        self.filter.board_filter.is_some()
+            || self.filter.tag_filter.is_some()
+            || self.filter.search_term.is_some()
+    }
+
+    /// Whether the board view shows only the header of `board`. Filtering
+    /// by a board or tag, or searching, opens every board.
+    pub fn is_collapsed(&self, board: &str) -> bool {
+        !self.boards_held_open()
+            && self
+                .collapsed_boards
+                .iter()
+                .any(|name| board::board_eq(name, board))
+    }
+
+    /// The board the selected row is shown under in the board view
+    pub fn selected_board(&self) -> Option<String> {
+        match self.selected_row()? {
+            ListRow::Board(name) => Some(name.clone()),
+            ListRow::Item(id) => self.board_shown_under(*id),
+        }
+    }
+
+    /// The first board, in board view order, that item `id` is on
+    pub fn board_shown_under(&self, id: u64) -> Option<String> {
+        let item = self.items.get(&id.to_string())?;
+        self.boards
+            .iter()
+            .find(|name| item.boards().iter().any(|b| board::board_eq(b, name)))
+            .cloned()
+    }
+
+    /// Collapse the board of the selected item, or open the selected
+    /// collapsed board. Returns false when filtering or searching keeps
+    /// every board open.
+    pub fn toggle_collapsed(&mut self) -> bool {
+        if self.boards_held_open() {
+            return false;
+        }
+        let Some(name) = self.selected_board() else {
+            return true;
+        };
+        if self.is_collapsed(&name) {
+            self.collapsed_boards
+                .retain(|collapsed| !board::board_eq(collapsed, &name));
+        } else {
+            self.collapsed_boards.push(name.clone());
+        }
+        self.update_display_order();
+        self.select_board(&name);
+        true
+    }
+
+    /// Collapse every board, or open them all. Returns false when filtering
+    /// or searching keeps every board open.
+    pub fn set_all_collapsed(&mut self, collapsed: bool) -> bool {
+        if self.boards_held_open() {
+            return false;
+        }
+        let selected = self.selected_board();
+        self.collapsed_boards = if collapsed {
+            self.boards.clone()
+        } else {
+            Vec::new()
+        };
+        self.update_display_order();
+        self.selected_index = 0;
+        if let Some(name) = selected {
+            self.select_board(&name);
+        }
+        true
+    }
+
+    /// Select the header of `board` if it is collapsed, or else its first item
+    fn select_board(&mut self, name: &str) {
+        let index = self.display_order.iter().position(|row| match row {
+            ListRow::Board(board) => board::board_eq(board, name),
+            ListRow::Item(id) => self
+                .items
+                .get(&id.to_string())
+                .is_some_and(|item| item.boards().iter().any(|b| board::board_eq(b, name))),
+        });
+        if let Some(index) = index {
+            self.selected_index = index;
+        }
+    }
+
     /// Get the currently selected item ID
     pub fn selected_id(&self) -> Option<u64> {
-        self.display_order.get(self.selected_index).copied()
+        self.selected_row().and_then(ListRow::item_id)
+    }
+
+    /// Get the currently selected row
+    pub fn selected_row(&self) -> Option<&ListRow> {
+        self.display_order.get(self.selected_index)
     }
 
     /// Get the currently selected item
@@ -445,6 +1199,34 @@ impl App {
             .and_then(|id| self.items.get(&id.to_string()))
     }
 
+    /// Select the item with this id. If the search, a filter or the view
+    /// hides it, they are cleared and the board view shown. Returns false if
+    /// it is not shown even then.
+    pub fn select_item(&mut self, id: u64) -> Result<bool> {
+        let row = ListRow::Item(id);
+        if !self.display_order.contains(&row) {
+            if !matches!(self.view, ViewMode::Board | ViewMode::Timeline) {
+                self.set_view(ViewMode::Board)?;
+            }
+            self.filter.search_term = None;
+            self.filter.board_filter = None;
+            self.filter.attributes.clear();
+            if let Some(item) = self.items.get(&id.to_string()) {
+                let boards = item.boards().to_vec();
+                self.collapsed_boards
+                    .retain(|name| !boards.iter().any(|b| board::board_eq(b, name)));
+            }
+            self.update_display_order();
+        }
+        match self.display_order.iter().position(|shown| *shown == row) {
+            Some(index) => {
+                self.selected_index = index;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
     /// Move selection up
     pub fn select_previous(&mut self) {
         if self.selected_index > 0 {
@@ -483,6 +1265,31 @@ impl App {
         }
     }
 
+    /// Move the calendar cursor by `days` and show that day's items
+    pub fn move_calendar_day(&mut self, days: i64) {
+        self.set_calendar_day(self.calendar.day + chrono::Duration::days(days));
+    }
+
+    /// Move the calendar cursor by whole months, clamping to the month's last day
+    pub fn move_calendar_month(&mut self, months: i32) {
+        let delta = Months::new(months.unsigned_abs());
+        let day = if months < 0 {
+            self.calendar.day.checked_sub_months(delta)
+        } else {
+            self.calendar.day.checked_add_months(delta)
+        };
+        if let Some(day) = day {
+            self.set_calendar_day(day);
+        }
+    }
+
+    fn set_calendar_day(&mut self, day: NaiveDate) {
+        self.calendar.day = day;
+        self.calendar.focus_items = false;
+        self.selected_index = 0;
+        self.update_display_order();
+    }
+
     /// Set status message
     pub fn set_status(&mut self, text: String, kind: StatusKind) {
         self.status_message = Some(StatusMessage {
@@ -492,6 +1299,22 @@ impl App {
         });
     }
 
+    /// Save the config. A failure is kept for the key handler to report:
+    /// the change still applies, but only until the TUI is closed.
+    pub fn save_config(&mut self) {
+        if let Err(e) = self.write_config() {
+            self.config_save_error = Some(e.to_string());
+        }
+    }
+
+    /// Save the config to the file it came from
+    pub fn write_config(&self) -> Result<()> {
+        match &self.config_file {
+            Some(path) => self.config.save_to(path),
+            None => self.config.save(),
+        }
+    }
+
     /// Tick - called periodically for time-based updates
     pub fn tick(&mut self) {
         // Clear expired status messages
@@ -500,6 +1323,221 @@ impl App {
                 self.status_message = None;
             }
         }
+
+        if self.focus.as_ref().is_some_and(|f| f.is_finished()) {
+            self.finish_focus();
+        }
+
+        self.apply_live_search();
+    }
+
+    /// Start a count with `digit`. A digit that numbers a view switches to
+    /// it at once, and [`push_count_digit`](Self::push_count_digit) or
+    /// [`take_count`](Self::take_count) go back should it be a count after
+    /// all.
+    pub fn start_count(&mut self, digit: usize) -> Result<()> {
+        let before = ViewMode::from_number(digit).map(|_| self.view_state());
+        self.switch_to_view_number(digit)?;
+        self.count = Some(PendingCount {
+            value: digit,
+            before,
+        });
+        Ok(())
+    }
+
+    /// Add `digit` to the count being typed
+    pub fn push_count_digit(&mut self, digit: usize) -> Result<()> {
+        let Some(count) = self.count.take() else {
+            return self.start_count(digit);
+        };
+        if let Some(before) = count.before {
+            self.restore_view(before)?;
+        }
+        self.count = Some(PendingCount {
+            value: (count.value * 10 + digit).min(MAX_COUNT),
+            before: None,
+        });
+        Ok(())
+    }
+
+    /// The count typed before `key`, if `key` takes it, going back to the
+    /// view the count switched away from. Any other key drops the count.
+    pub fn take_count(
+        &mut self,
+        takes_count: impl FnOnce(Option<&ViewState>) -> bool,
+    ) -> Result<Option<usize>> {
+        let Some(count) = self.count.take() else {
+            return Ok(None);
+        };
+        if !takes_count(count.before.as_ref()) {
+            return Ok(None);
+        }
+        if let Some(before) = count.before {
+            self.restore_view(before)?;
+        }
+        Ok(Some(count.value))
+    }
+
+    fn view_state(&self) -> ViewState {
+        ViewState {
+            view: self.view,
+            board_filter: self.filter.board_filter.clone(),
+            selected_index: self.selected_index,
+            scroll_offset: self.list_scroll.offset.get(),
+            calendar_items: self.calendar.focus_items,
+            sidebar_focused: self.sidebar.focused,
+        }
+    }
+
+    fn restore_view(&mut self, state: ViewState) -> Result<()> {
+        self.set_view(state.view)?;
+        self.set_board_filter(state.board_filter);
+        self.selected_index = state.selected_index;
+        self.list_scroll.offset.set(state.scroll_offset);
+        self.calendar.focus_items = state.calendar_items;
+        self.sidebar.focused = state.sidebar_focused;
+        Ok(())
+    }
+
+    /// Open the view the number key `n` stands for, leaving any board filter
+    pub fn switch_to_view_number(&mut self, n: usize) -> Result<()> {
+        if let Some(view) = ViewMode::from_number(n) {
+            self.clear_board_filter();
+            self.set_view(view)?;
+        }
+        Ok(())
+    }
+
+    /// Filter by `term`, or stop filtering when `None`
+    pub fn set_search(&mut self, term: Option<String>) {
+        if self.filter.search_term == term {
+            return;
+        }
+        self.filter.search_term = term;
+        self.update_display_order();
+        self.selected_index = 0;
+    }
+
+    /// Follow the command line after it changes: while it holds a
+    /// `/search`, the term is applied once typing pauses
+    pub fn update_live_search(&mut self) {
+        let typing = command_parser::search_term(&self.command_line.input).is_some();
+        let due = Some(Instant::now() + SEARCH_DEBOUNCE);
+        match (self.live_search.as_mut(), typing) {
+            (Some(live), true) => live.due = due,
+            (None, true) => {
+                self.live_search = Some(LiveSearch {
+                    previous: self.filter.search_term.clone(),
+                    due,
+                });
+            }
+            (Some(_), false) => self.cancel_live_search(),
+            (None, false) => {}
+        }
+    }
+
+    /// Apply the term being typed, if typing has paused long enough
+    fn apply_live_search(&mut self) {
+        let Some(live) = self.live_search.as_mut() else {
+            return;
+        };
+        if !live.due.is_some_and(|due| Instant::now() >= due) {
+            return;
+        }
+        live.due = None;
+        let term = command_parser::search_term(&self.command_line.input)
+            .filter(|term| !term.is_empty())
+            .map(str::to_string);
+        self.set_search(term);
+    }
+
+    /// Put back the search from before the `/search` was typed
+    pub fn cancel_live_search(&mut self) {
+        if let Some(live) = self.live_search.take() {
+            self.set_search(live.previous);
+        }
+    }
+
+    /// Select the search match `delta` away from the selected one, wrapping
+    /// around
+    pub fn select_match(&mut self, delta: isize) {
+        let count = self.display_order.len();
+        if count == 0 {
+            self.set_status("No matches".to_string(), StatusKind::Info);
+            return;
+        }
+        self.selected_index =
+            (self.selected_index as isize + delta).rem_euclid(count as isize) as usize;
+        self.set_status(
+            format!("Match {} of {}", self.selected_index + 1, count),
+            StatusKind::Info,
+        );
+    }
+
+    /// Start a focus session on a task, beginning it if needed
+    pub fn start_focus(&mut self, id: u64) -> Result<()> {
+        let Some(task) = self.items.get(&id.to_string()).and_then(|i| i.as_task()) else {
+            self.set_status("Only tasks can be focused".to_string(), StatusKind::Error);
+            return Ok(());
+        };
+        if task.is_complete {
+            self.set_status(format!("Task {} is already done", id), StatusKind::Error);
+            return Ok(());
+        }
+
+        let description = task.description.clone();
+        if !task.in_progress {
+            self.taskbook.begin_tasks_silent(&[id])?;
+            self.refresh_items()?;
+        }
+
+        let minutes = self.config.focus.minutes;
+        self.focus = Some(FocusTimer::start(id, description, minutes));
+        self.set_status(
+            format!("Focusing on task {} for {} min", id, minutes.max(1)),
+            StatusKind::Info,
+        );
+        Ok(())
+    }
+
+    /// End the running focus session, logging the time spent and pausing
+    /// the task. Called when the timer runs out or the user stops it early.
+    pub fn finish_focus(&mut self) {
+        let Some(focus) = self.focus.take() else {
+            return;
+        };
+        let finished = focus.is_finished();
+        let spent = focus.elapsed();
+        let session = FocusSession {
+            started_at: focus.started_at,
+            seconds: spent.as_secs(),
+        };
+
+        if let Err(e) = self
+            .taskbook
+            .log_focus_session_silent(focus.task_id, session)
+            .and_then(|_| self.refresh_items())
+        {
+            self.set_status(
+                format!("Could not log focus session: {}", e),
+                StatusKind::Error,
+            );
+            return;
+        }
+
+        let message = if finished {
+            format!("Focus session on task {} complete", focus.task_id)
+        } else {
+            format!(
+                "Focus on task {} stopped after {}",
+                focus.task_id,
+                focus::format_clock(spent)
+            )
+        };
+        if finished && self.config.focus.notify {
+            focus::notify("Focus session complete", &focus.description);
+        }
+        self.set_status(message, StatusKind::Success);
     }
 
     /// Get stats for the current view (returns cached value)
@@ -507,15 +1545,23 @@ impl App {
         &self.cached_stats
     }
 
+    /// Get cached dashboard statistics
+    pub fn get_dashboard(&self) -> &DashboardStats {
+        &self.cached_dashboard
+    }
+
     /// Switch view mode
     pub fn set_view(&mut self, view: ViewMode) -> Result<()> {
         if self.view != view {
             self.view = view;
             self.selected_index = 0;
+            self.list_scroll.offset.set(0);
+            self.calendar.focus_items = false;
+            self.sidebar.focused = false;
 
             // Persist the view choice
             self.config.default_view = view;
-            let _ = self.config.save();
+            self.save_config();
 
             // Reload data for archive view
             if view == ViewMode::Archive {
@@ -545,24 +1591,176 @@ impl App {
         self.command_line = CommandLineState::default();
         self.history_index = None;
         self.history_saved_input.clear();
+        self.live_search = None;
     }
 
-    /// Push a command to history (deduplicates consecutive)
-    pub fn push_history(&mut self, cmd: String) {
-        if !cmd.trim().is_empty() {
-            // Don't duplicate if same as last entry
-            if self.command_history.last().map(|s| s.as_str()) != Some(cmd.trim()) {
-                self.command_history.push(cmd.trim().to_string());
-            }
-            // Cap history at 50 entries
-            if self.command_history.len() > 50 {
-                self.command_history.remove(0);
-            }
+    /// Push a command to history (deduplicates consecutive), and save it
+    pub fn push_history(&mut self, cmd: String) -> Result<()> {
+        if cmd.trim().is_empty() {
+            return Ok(());
+        }
+        // Don't duplicate if same as last entry
+        if self.command_history.last().map(|s| s.as_str()) != Some(cmd.trim()) {
+            self.command_history.push(cmd.trim().to_string());
+        }
+        if self.command_history.len() > session::HISTORY_LIMIT {
+            self.command_history.remove(0);
         }
+        session::save_history(
+            self.taskbook_dir.as_deref(),
+            &self.config,
+            &self.command_history,
+        )
     }
 
     /// Quit the application
     pub fn quit(&mut self) {
+        // Keep the time spent in an interrupted focus session
+        self.finish_focus();
         self.running = false;
     }
 }
+
+#[cfg(test)]
+pub(super) mod tests {
+    use std::ops::{Deref, DerefMut};
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    /// An [`App`] for a test, with a directory of its own for the config
+    /// file and the session, removed when dropped
+    pub(in crate::tui) struct TestApp {
+        app: App,
+        _dir: TempDir,
+    }
+
+    impl TestApp {
+        /// The file the app saves its config to
+        pub(in crate::tui) fn config_file(&self) -> &Path {
+            self.app.config_file.as_deref().unwrap()
+        }
+    }
+
+    impl Deref for TestApp {
+        type Target = App;
+
+        fn deref(&self) -> &App {
+            &self.app
+        }
+    }
+
+    impl DerefMut for TestApp {
+        fn deref_mut(&mut self) -> &mut App {
+            &mut self.app
+        }
+    }
+
+    /// An app over tasks created from `tasks` (as in `tb --task`), kept in
+    /// memory. There is no session to restore.
+    pub(in crate::tui) fn test_app(tasks: &[&str]) -> TestApp {
+        let taskbook = Taskbook::builder()
+            .config(Config::default())
+            .storage(Box::new(MemoryStorage::new()))
+            .render(false)
+            .build()
+            .unwrap();
+        for task in tasks {
+            let words: Vec<String> = task.split_whitespace().map(String::from).collect();
+            taskbook.create_task(&words).unwrap();
+        }
+        let dir = TempDir::new().unwrap();
+        let app = App::with_taskbook(
+            taskbook,
+            Config::default(),
+            Some(dir.path().join("config.json")),
+            Some(&dir.path().join("taskbook")),
+        )
+        .unwrap();
+        TestApp { app, _dir: dir }
+    }
+
+    #[test]
+    fn collapsing_a_board_selects_its_header() {
+        let mut app = test_app(&[
+            "@coding Fix bug",
+            "@coding Write docs",
+            "@home Water plants",
+        ]);
+        let coding = app.board_shown_under(1).unwrap();
+        app.select_item(2).unwrap();
+
+        assert!(app.toggle_collapsed());
+        assert!(app.is_collapsed(&coding));
+        assert_eq!(app.selected_row(), Some(&ListRow::Board(coding.clone())));
+        assert!(!app.display_order.contains(&ListRow::Item(1)));
+        assert!(app.display_order.contains(&ListRow::Item(3)));
+
+        // Opening it again selects its first item
+        assert!(app.toggle_collapsed());
+        assert!(!app.is_collapsed(&coding));
+        assert_eq!(app.selected_id(), Some(1));
+    }
+
+    #[test]
+    fn boards_stay_open_while_filtering() {
+        let mut app = test_app(&["@coding Fix bug", "@home Water plants"]);
+        let home = app.board_shown_under(2).unwrap();
+        app.select_item(2).unwrap();
+
+        assert!(app.set_all_collapsed(true));
+        assert_eq!(app.selected_row(), Some(&ListRow::Board(home.clone())));
+        assert!(app.display_order.iter().all(|row| row.item_id().is_none()));
+
+        app.set_search(Some("plants".to_string()));
+        assert!(!app.is_collapsed(&home));
+        assert!(!app.toggle_collapsed());
+        app.set_search(None);
+        assert!(app.is_collapsed(&home));
+
+        assert!(app.set_all_collapsed(false));
+        assert!(app.collapsed_boards.is_empty());
+        assert_eq!(app.display_order.len(), 2);
+    }
+
+    #[test]
+    fn the_board_picker_filters_by_the_row_picked() {
+        let mut app = test_app(&[
+            "@coding Fix bug",
+            "@coding Write docs",
+            "@home Water plants",
+        ]);
+        let boards = app.boards.clone();
+        assert_eq!(app.picked_board(), 0);
+        assert_eq!(app.board_counts()[0], (None, 3));
+
+        app.pick_board(1);
+        assert_eq!(app.filter.board_filter.as_ref(), Some(&boards[0]));
+        assert_eq!(app.picked_board(), 1);
+        assert_eq!(app.selected_index, 0);
+
+        // Past the last board stays on it
+        app.pick_board(boards.len() + 5);
+        assert_eq!(app.picked_board(), boards.len());
+        assert_eq!(app.filter.board_filter.as_ref(), boards.last());
+
+        app.pick_board(0);
+        assert!(app.filter.board_filter.is_none());
+        assert_eq!(app.display_order.len(), 3);
+    }
+
+    #[test]
+    fn changing_view_takes_the_focus_from_the_board_picker() {
+        let mut app = test_app(&["@coding Fix bug"]);
+        app.sidebar = SidebarState {
+            open: true,
+            focused: true,
+        };
+
+        app.set_view(ViewMode::Timeline).unwrap();
+        assert!(app.sidebar.open);
+        assert!(!app.sidebar.focused);
+    }
+}
@@ -1,3 +1,7 @@
+use std::collections::BTreeSet;
+
+use crate::config::ThemeColors;
+
 use super::app::{App, Suggestion, SuggestionKind};
 use taskbook_common::board;
 
@@ -6,29 +10,44 @@ const COMMANDS: &[(&str, &str)] = &[
     ("task", "Create a new task"),
     ("note", "Create a new note"),
     ("edit", "Edit item description"),
-    ("move", "Move item to board"),
+    ("comment", "Comment on a task"),
+    ("attach", "Attach a file or URL to an item"),
+    ("open", "Open an item's attachment or link"),
+    ("move", "Move items to a board"),
     ("delete", "Delete items"),
+    ("moveup", "Move an item up its board"),
+    ("movedown", "Move an item down its board"),
     ("search", "Search/filter items"),
     ("priority", "Set task priority"),
+    ("due", "Set/clear task due date"),
     ("check", "Toggle task check"),
     ("star", "Toggle star"),
     ("begin", "Toggle in-progress"),
-    ("tag", "Add/remove tags on item"),
+    ("tag", "Filter by tag, or add/remove tags on item"),
     ("clear", "Clear completed tasks"),
     ("rename-board", "Rename a board"),
     ("board", "Switch to board view"),
     ("timeline", "Switch to timeline view"),
     ("archive", "Switch to archive view"),
-    ("journal", "Switch to journal view"),
-    ("sort", "Cycle sort method"),
+    ("journal", "Journal view; /journal new for today's note"),
+    ("calendar", "Switch to calendar view"),
+    ("today", "Switch to today view"),
+    ("stats", "Switch to stats dashboard"),
+    ("sort", "Cycle sort method, or sort one board"),
+    ("group", "Group timeline by day/week/month"),
     ("hide-done", "Toggle hide completed"),
+    ("filter", "Apply/clear a saved filter"),
+    ("profile", "Switch profile"),
+    ("settings", "Change theme, sort, view and sync"),
+    ("theme", "Switch theme, or pick one with a preview"),
     ("help", "Show help"),
     ("quit", "Quit application"),
 ];
 
 /// Commands that accept item ID references (@<id>)
 const ITEM_COMMANDS: &[&str] = &[
-    "check", "star", "begin", "delete", "edit", "move", "priority", "tag",
+    "check", "star", "begin", "delete", "edit", "comment", "attach", "open", "move", "moveup",
+    "movedown", "priority", "due", "tag",
 ];
 
 const MAX_SUGGESTIONS: usize = 8;
@@ -71,6 +90,17 @@ pub fn update_suggestions(app: &mut App) {
             }
             // Otherwise it's a board reference
             suggest_boards(app, after_at);
+        } else if let Some(partial) = last_token.strip_prefix('+') {
+            suggest_tags(app, "+", partial);
+        } else if command == "tag" && last_token.starts_with('-') {
+            // /tag @<id> -tag — removing one of the tags in use
+            suggest_tags(app, "-", &last_token[1..]);
+        } else if command == "theme" {
+            suggest_themes(app, &text_to_cursor[space_pos + 1..]);
+        } else if command == "tag" && !text_to_cursor[space_pos + 1..].contains(' ') {
+            // /tag <name> filters by a tag; /tag @<id> edits an item's tags
+            suggest_tags(app, "", &last_token);
+            suggest_items(app, &last_token);
         } else if ITEM_COMMANDS.contains(&command) {
             // Check if we should suggest items for this argument position
             if should_suggest_items(command, &text_to_cursor, last_space) {
@@ -90,10 +120,17 @@ fn should_suggest_items(command: &str, text_to_cursor: &str, _last_space: usize)
 
     match command {
         // /edit @<id> <description> — only suggest for the first argument
-        "edit" => args.len() <= 1,
-        // /move @<id> @<board> — only suggest for the first argument
-        "move" => args.len() <= 1,
-        // /priority @<id> <1-3> — only suggest for the first argument
+        "edit" | "comment" => args.len() <= 1,
+        // /move @<id>... @<board> — suggest while only ids precede the cursor
+        "move" => args
+            .iter()
+            .rev()
+            .skip(usize::from(!text_to_cursor.ends_with(' ')))
+            .all(|arg| {
+                arg.strip_prefix('@')
+                    .is_some_and(|id| id.parse::<u64>().is_ok())
+            }),
+        // /priority @<id> <level> — only suggest for the first argument
         "priority" => args.len() <= 1,
         // /tag @<id> +tag1 -tag2 — only suggest for the first argument
         "tag" => args.len() <= 1,
@@ -119,6 +156,20 @@ fn suggest_commands(app: &mut App, partial: &str) {
     }
 }
 
+fn suggest_themes(app: &mut App, partial: &str) {
+    let partial_lower = partial.trim_start().to_lowercase();
+    for name in ThemeColors::PRESETS {
+        if name.starts_with(&partial_lower) {
+            app.command_line.suggestions.push(Suggestion {
+                display: name.to_string(),
+                completion: format!("/theme {}", name),
+                description: None,
+                kind: SuggestionKind::Command,
+            });
+        }
+    }
+}
+
 fn suggest_boards(app: &mut App, partial: &str) {
     let partial_lower = partial.to_lowercase();
     for b in &app.boards.clone() {
@@ -157,8 +208,47 @@ fn suggest_boards(app: &mut App, partial: &str) {
     }
 }
 
+/// Tags in use on the items loaded, starting with `partial`, completed as
+/// `prefix` and the tag in place of the token being typed
+fn suggest_tags(app: &mut App, prefix: &str, partial: &str) {
+    let partial_lower = partial.to_lowercase();
+    let tags: BTreeSet<String> = app
+        .items
+        .values()
+        .flat_map(|item| item.tags())
+        .map(|tag| tag.to_lowercase())
+        .filter(|tag| tag.starts_with(&partial_lower))
+        .collect();
+
+    let input_chars: Vec<char> = app.command_line.input.chars().collect();
+    let cursor = app.command_line.cursor.min(input_chars.len());
+    let last_space = input_chars[..cursor]
+        .iter()
+        .rposition(|c| *c == ' ')
+        .unwrap_or(0);
+    let before_token: String = input_chars[..last_space + 1].iter().collect();
+    let after_cursor: String = input_chars[cursor..].iter().collect();
+
+    for tag in tags {
+        let count = app
+            .items
+            .values()
+            .filter(|item| item.tags().iter().any(|t| t.eq_ignore_ascii_case(&tag)))
+            .count();
+        app.command_line.suggestions.push(Suggestion {
+            display: board::display_tag(&tag),
+            completion: format!("{}{}{} {}", before_token, prefix, tag, after_cursor),
+            description: Some(format!("{} item(s)", count)),
+            kind: SuggestionKind::Tag,
+        });
+        if app.command_line.suggestions.len() >= MAX_SUGGESTIONS {
+            break;
+        }
+    }
+}
+
 fn suggest_items(app: &mut App, partial: &str) {
-    if partial.is_empty() {
+    if partial.is_empty() || app.command_line.suggestions.len() >= MAX_SUGGESTIONS {
         return;
     }
 
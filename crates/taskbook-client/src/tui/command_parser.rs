@@ -1,3 +1,8 @@
+use chrono::NaiveDate;
+use taskbook_common::{board, due, priority};
+
+use crate::config::{SortMethod, TimelineGroup};
+
 /// Parsed command from the command line input
 #[derive(Debug, Clone)]
 pub enum ParsedCommand {
@@ -6,6 +11,7 @@ pub enum ParsedCommand {
         description: String,
         priority: u8,
         tags: Vec<String>,
+        due: Option<NaiveDate>,
     },
     Note {
         board: Option<String>,
@@ -16,13 +22,31 @@ pub enum ParsedCommand {
         id: u64,
         description: String,
     },
-    Move {
+    Comment {
+        id: u64,
+        text: String,
+    },
+    Attach {
+        id: u64,
+        target: String,
+    },
+    /// Open an item's attachment or link (1-based)
+    Open {
         id: u64,
+        number: usize,
+    },
+    Move {
+        ids: Vec<u64>,
         board: String,
     },
     Delete {
         ids: Vec<u64>,
     },
+    /// Move an item (the selected one when `None`) up or down its board
+    Reorder {
+        id: Option<u64>,
+        up: bool,
+    },
     Search {
         term: String,
     },
@@ -30,6 +54,10 @@ pub enum ParsedCommand {
         id: u64,
         level: u8,
     },
+    Due {
+        id: u64,
+        due: Option<NaiveDate>,
+    },
     Check {
         ids: Vec<u64>,
     },
@@ -44,7 +72,14 @@ pub enum ParsedCommand {
         add: Vec<String>,
         remove: Vec<String>,
     },
-    Clear,
+    /// Show only items with `tag`, or every item again when `None`
+    TagFilter {
+        tag: Option<String>,
+    },
+    Clear {
+        board: Option<String>,
+        dry_run: bool,
+    },
     RenameBoard {
         old_name: String,
         new_name: String,
@@ -53,9 +88,34 @@ pub enum ParsedCommand {
     Timeline,
     Archive,
     Journal,
+    /// Edit today's note on the journal board, creating it if missing
+    JournalNote,
+    Calendar,
+    Today,
+    Stats,
     Sort,
+    /// Set the sort method of one board, or clear it when `None`
+    SortBoard {
+        board: String,
+        method: Option<SortMethod>,
+    },
+    /// Set the timeline grouping, or cycle it when `None`
+    Group {
+        group: Option<TimelineGroup>,
+    },
     HideDone,
+    Filter {
+        name: Option<String>,
+    },
+    Profile {
+        name: Option<String>,
+    },
     Help,
+    Settings,
+    /// Switch to a preset theme, or pick one when `None`
+    Theme {
+        name: Option<String>,
+    },
     Quit,
 }
 
@@ -87,8 +147,13 @@ pub fn parse_command(input: &str) -> Result<ParsedCommand, ParseError> {
         "task" => parse_task(args),
         "note" => parse_note(args),
         "edit" => parse_edit(args),
+        "comment" => parse_comment(args),
+        "attach" => parse_attach(args),
+        "open" => parse_open(args),
         "move" => parse_move(args),
         "delete" => parse_id_list(args).map(|ids| ParsedCommand::Delete { ids }),
+        "moveup" => parse_reorder(args, true),
+        "movedown" => parse_reorder(args, false),
         "search" => {
             let term = args.trim().to_string();
             if term.is_empty() {
@@ -100,19 +165,49 @@ pub fn parse_command(input: &str) -> Result<ParsedCommand, ParseError> {
             }
         }
         "priority" => parse_priority(args),
+        "due" => parse_due(args),
         "check" => parse_id_list(args).map(|ids| ParsedCommand::Check { ids }),
         "star" => parse_id_list(args).map(|ids| ParsedCommand::Star { ids }),
         "begin" => parse_id_list(args).map(|ids| ParsedCommand::Begin { ids }),
         "tag" => parse_tag(args),
-        "clear" => Ok(ParsedCommand::Clear),
+        "clear" => parse_clear(args),
         "rename-board" => parse_rename_board(args),
         "board" => Ok(ParsedCommand::Board),
         "timeline" => Ok(ParsedCommand::Timeline),
         "archive" => Ok(ParsedCommand::Archive),
-        "journal" => Ok(ParsedCommand::Journal),
-        "sort" => Ok(ParsedCommand::Sort),
+        "journal" => match args.trim() {
+            "" => Ok(ParsedCommand::Journal),
+            "new" | "today" => Ok(ParsedCommand::JournalNote),
+            _ => Err(ParseError {
+                message: "Usage: /journal [new]".to_string(),
+            }),
+        },
+        "calendar" => Ok(ParsedCommand::Calendar),
+        "today" => Ok(ParsedCommand::Today),
+        "stats" => Ok(ParsedCommand::Stats),
+        "sort" => parse_sort(args),
+        "group" => parse_group(args),
         "hide-done" => Ok(ParsedCommand::HideDone),
+        "filter" => {
+            let name = args.trim();
+            Ok(ParsedCommand::Filter {
+                name: (!name.is_empty()).then(|| name.to_string()),
+            })
+        }
+        "profile" => {
+            let name = args.trim();
+            Ok(ParsedCommand::Profile {
+                name: (!name.is_empty()).then(|| name.to_string()),
+            })
+        }
         "help" => Ok(ParsedCommand::Help),
+        "settings" => Ok(ParsedCommand::Settings),
+        "theme" => {
+            let name = args.trim();
+            Ok(ParsedCommand::Theme {
+                name: (!name.is_empty()).then(|| name.to_string()),
+            })
+        }
         "quit" | "q" => Ok(ParsedCommand::Quit),
         _ => Err(ParseError {
             message: format!("Unknown command: /{}", cmd),
@@ -120,11 +215,101 @@ pub fn parse_command(input: &str) -> Result<ParsedCommand, ParseError> {
     }
 }
 
+/// The term typed so far, if `input` is a `/search` being typed
+pub fn search_term(input: &str) -> Option<&str> {
+    let rest = input.trim_start().strip_prefix('/')?;
+    let (cmd, term) = rest.split_once(' ')?;
+    cmd.eq_ignore_ascii_case("search").then_some(term.trim())
+}
+
+fn parse_reorder(args: &str, up: bool) -> Result<ParsedCommand, ParseError> {
+    if args.trim().is_empty() {
+        return Ok(ParsedCommand::Reorder { id: None, up });
+    }
+    match parse_id_list(args)?.as_slice() {
+        [id] => Ok(ParsedCommand::Reorder { id: Some(*id), up }),
+        _ => Err(ParseError {
+            message: format!("Usage: /move{} [@id]", if up { "up" } else { "down" }),
+        }),
+    }
+}
+
+fn parse_sort(args: &str) -> Result<ParsedCommand, ParseError> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(ParsedCommand::Sort);
+    }
+
+    let usage = || ParseError {
+        message: "Usage: /sort [@board id|priority|status|manual|default]".to_string(),
+    };
+
+    // Board name (supports @"quoted name"), then the method
+    let (board, rest) = if args.starts_with('@') {
+        extract_at_board(args).ok_or_else(usage)?
+    } else {
+        let end = args.find(char::is_whitespace).unwrap_or(args.len());
+        (args[..end].to_string(), &args[end..])
+    };
+
+    let method = match rest.trim() {
+        "" => return Err(usage()),
+        "default" => None,
+        method => Some(SortMethod::parse(method).ok_or_else(usage)?),
+    };
+
+    Ok(ParsedCommand::SortBoard { board, method })
+}
+
+fn parse_clear(args: &str) -> Result<ParsedCommand, ParseError> {
+    let usage = || ParseError {
+        message: "Usage: /clear [@board] [--dry-run]".to_string(),
+    };
+    let mut rest = args.trim();
+    let mut dry_run = false;
+    if let Some(before) = rest.strip_suffix("--dry-run") {
+        dry_run = true;
+        rest = before.trim_end();
+    }
+    if let Some(after) = rest.strip_prefix("--dry-run") {
+        dry_run = true;
+        rest = after.trim_start();
+    }
+
+    // Board name (supports @"quoted name")
+    let board = match rest {
+        "" => None,
+        _ if rest.starts_with('@') => {
+            let (board, tail) = extract_at_board(rest).ok_or_else(usage)?;
+            if !tail.trim().is_empty() {
+                return Err(usage());
+            }
+            Some(board)
+        }
+        _ if !rest.contains(char::is_whitespace) => Some(rest.to_string()),
+        _ => return Err(usage()),
+    };
+
+    Ok(ParsedCommand::Clear { board, dry_run })
+}
+
+fn parse_group(args: &str) -> Result<ParsedCommand, ParseError> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(ParsedCommand::Group { group: None });
+    }
+    TimelineGroup::parse(args)
+        .map(|group| ParsedCommand::Group { group: Some(group) })
+        .ok_or_else(|| ParseError {
+            message: "Usage: /group [day|week|month]".to_string(),
+        })
+}
+
 fn parse_task(args: &str) -> Result<ParsedCommand, ParseError> {
     let args = args.trim();
     if args.is_empty() {
         return Err(ParseError {
-            message: "Usage: /task [@board] description [p:1-3]".to_string(),
+            message: "Usage: /task [@board] description [p:1-3|high] [due:<when>]".to_string(),
         });
     }
 
@@ -138,16 +323,16 @@ fn parse_task(args: &str) -> Result<ParsedCommand, ParseError> {
     };
 
     let mut priority = 1u8;
+    let mut due = None;
     let mut desc_parts = Vec::new();
     let mut tags = Vec::new();
+    let today = chrono::Local::now().date_naive();
 
     for token in rest.split_whitespace() {
-        if let Some(p) = token.strip_prefix("p:") {
-            if let Ok(v) = p.parse::<u8>() {
-                if (1..=3).contains(&v) {
-                    priority = v;
-                }
-            }
+        if let Some(date) = due::parse_due_token(token, today) {
+            due = Some(date);
+        } else if let Some(level) = priority::parse_priority_token(token) {
+            priority = level;
         } else if token.starts_with('+') && token.len() > 1 {
             let tag = token[1..].to_lowercase();
             if !tags.iter().any(|t: &String| t.eq_ignore_ascii_case(&tag)) {
@@ -170,6 +355,7 @@ fn parse_task(args: &str) -> Result<ParsedCommand, ParseError> {
         description,
         priority,
         tags,
+        due,
     })
 }
 
@@ -238,23 +424,90 @@ fn parse_edit(args: &str) -> Result<ParsedCommand, ParseError> {
     })
 }
 
-fn parse_move(args: &str) -> Result<ParsedCommand, ParseError> {
+fn parse_comment(args: &str) -> Result<ParsedCommand, ParseError> {
     let args = args.trim();
+    // Expect @<id> <text>
+    let (id_token, text) = args.split_once(' ').unwrap_or((args, ""));
+    let id = parse_at_id(id_token)?;
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(ParseError {
+            message: "Usage: /comment @<id> <text>".to_string(),
+        });
+    }
 
-    // Extract the ID (first token)
-    let id_end = args.find(char::is_whitespace).ok_or(ParseError {
-        message: "Usage: /move @<id> @<board>".to_string(),
-    })?;
+    Ok(ParsedCommand::Comment {
+        id,
+        text: text.to_string(),
+    })
+}
 
-    let id_token = &args[..id_end];
-    let rest = args[id_end..].trim();
+fn parse_attach(args: &str) -> Result<ParsedCommand, ParseError> {
+    let args = args.trim();
+    // Expect @<id> <path|url>; the path may contain spaces
+    let (id_token, target) = args.split_once(' ').unwrap_or((args, ""));
+    let id = parse_at_id(id_token)?;
+    let target = target.trim();
+    if target.is_empty() {
+        return Err(ParseError {
+            message: "Usage: /attach @<id> <path|url>".to_string(),
+        });
+    }
 
+    Ok(ParsedCommand::Attach {
+        id,
+        target: target.to_string(),
+    })
+}
+
+fn parse_open(args: &str) -> Result<ParsedCommand, ParseError> {
+    let args = args.trim();
+    // Expect @<id> [number]
+    let (id_token, number) = args.split_once(' ').unwrap_or((args, ""));
     let id = parse_at_id(id_token)?;
+    let number = match number.trim() {
+        "" => 1,
+        n => n
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| ParseError {
+                message: "Usage: /open @<id> [number]".to_string(),
+            })?,
+    };
 
+    Ok(ParsedCommand::Open { id, number })
+}
+
+fn parse_move(args: &str) -> Result<ParsedCommand, ParseError> {
+    let usage = || ParseError {
+        message: "Usage: /move @<id> [@<id>...] @<board>".to_string(),
+    };
+    let mut rest = args.trim();
+
+    // Leading @<id> tokens are the items; the last token is always the board,
+    // so `/move @1 @2024` moves item 1 to board 2024
+    let mut ids = Vec::new();
+    while let Some((token, tail)) = rest.split_once(char::is_whitespace) {
+        match parse_at_id(token) {
+            Ok(id) => {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+                rest = tail.trim_start();
+            }
+            Err(_) => break,
+        }
+    }
+
+    if ids.is_empty() {
+        // Report a bad first token rather than the usage line
+        let first = rest.split_whitespace().next().ok_or_else(usage)?;
+        parse_at_id(first)?;
+        return Err(usage());
+    }
     if rest.is_empty() {
-        return Err(ParseError {
-            message: "Usage: /move @<id> @<board>".to_string(),
-        });
+        return Err(usage());
     }
 
     // Extract board name (supports @"quoted name")
@@ -278,7 +531,7 @@ fn parse_move(args: &str) -> Result<ParsedCommand, ParseError> {
         });
     }
 
-    Ok(ParsedCommand::Move { id, board })
+    Ok(ParsedCommand::Move { ids, board })
 }
 
 fn parse_priority(args: &str) -> Result<ParsedCommand, ParseError> {
@@ -286,22 +539,37 @@ fn parse_priority(args: &str) -> Result<ParsedCommand, ParseError> {
     let tokens: Vec<&str> = args.split_whitespace().collect();
     if tokens.len() < 2 {
         return Err(ParseError {
-            message: "Usage: /priority @<id> <1-3>".to_string(),
+            message: "Usage: /priority @<id> <1-3|normal|medium|high>".to_string(),
         });
     }
 
     let id = parse_at_id(tokens[0])?;
-    let level = tokens[1].parse::<u8>().map_err(|_| ParseError {
-        message: "Priority must be 1, 2, or 3".to_string(),
+    let level = priority::parse_priority(tokens[1]).ok_or_else(|| ParseError {
+        message: "Priority must be 1, 2, 3 or normal, medium, high".to_string(),
     })?;
 
-    if !(1..=3).contains(&level) {
+    Ok(ParsedCommand::Priority { id, level })
+}
+
+fn parse_due(args: &str) -> Result<ParsedCommand, ParseError> {
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    if tokens.len() < 2 {
         return Err(ParseError {
-            message: "Priority must be 1, 2, or 3".to_string(),
+            message: "Usage: /due @<id> <when|none>".to_string(),
         });
     }
 
-    Ok(ParsedCommand::Priority { id, level })
+    let id = parse_at_id(tokens[0])?;
+    if tokens[1].eq_ignore_ascii_case("none") {
+        return Ok(ParsedCommand::Due { id, due: None });
+    }
+
+    let today = chrono::Local::now().date_naive();
+    let due = due::parse_due(tokens[1], today).ok_or_else(|| ParseError {
+        message: format!("Unknown due date: {}", tokens[1]),
+    })?;
+
+    Ok(ParsedCommand::Due { id, due: Some(due) })
 }
 
 fn parse_rename_board(args: &str) -> Result<ParsedCommand, ParseError> {
@@ -396,15 +664,24 @@ fn extract_at_board(input: &str) -> Option<(String, &str)> {
     }
 }
 
+/// `/tag <name>` filters by a tag, `/tag` alone clears the filter, and
+/// `/tag @<id> +tag1 -tag2` adds and removes tags on an item
 fn parse_tag(args: &str) -> Result<ParsedCommand, ParseError> {
-    let args = args.trim();
-    if args.is_empty() {
-        return Err(ParseError {
-            message: "Usage: /tag @<id> +tag1 +tag2 -tag3".to_string(),
-        });
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    match tokens.first() {
+        None => return Ok(ParsedCommand::TagFilter { tag: None }),
+        Some(first) if !first.starts_with('@') => {
+            let tag = board::normalize_tag(first);
+            if tokens.len() > 1 || tag.is_empty() {
+                return Err(ParseError {
+                    message: "Usage: /tag <name>, or /tag @<id> +tag1 -tag2".to_string(),
+                });
+            }
+            return Ok(ParsedCommand::TagFilter { tag: Some(tag) });
+        }
+        Some(_) => {}
     }
 
-    let tokens: Vec<&str> = args.split_whitespace().collect();
     if tokens.len() < 2 {
         return Err(ParseError {
             message: "Usage: /tag @<id> +tag1 -tag2".to_string(),
@@ -577,18 +854,112 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_comment() {
+        match parse_command("/comment @3 waiting on @bob").unwrap() {
+            ParsedCommand::Comment { id, text } => {
+                assert_eq!(id, 3);
+                assert_eq!(text, "waiting on @bob");
+            }
+            _ => panic!("Expected Comment"),
+        }
+        assert!(parse_command("/comment @3").is_err());
+    }
+
+    #[test]
+    fn test_parse_journal() {
+        assert!(matches!(
+            parse_command("/journal").unwrap(),
+            ParsedCommand::Journal
+        ));
+        assert!(matches!(
+            parse_command("/journal new").unwrap(),
+            ParsedCommand::JournalNote
+        ));
+        assert!(parse_command("/journal old").is_err());
+    }
+
+    #[test]
+    fn test_parse_attach_and_open() {
+        match parse_command("/attach @3 ~/My Docs/spec.pdf").unwrap() {
+            ParsedCommand::Attach { id, target } => {
+                assert_eq!(id, 3);
+                assert_eq!(target, "~/My Docs/spec.pdf");
+            }
+            _ => panic!("Expected Attach"),
+        }
+        assert!(parse_command("/attach @3").is_err());
+
+        assert!(matches!(
+            parse_command("/open @3").unwrap(),
+            ParsedCommand::Open { id: 3, number: 1 }
+        ));
+        assert!(matches!(
+            parse_command("/open @3 2").unwrap(),
+            ParsedCommand::Open { id: 3, number: 2 }
+        ));
+        assert!(parse_command("/open @3 0").is_err());
+    }
+
     #[test]
     fn test_parse_move_quoted_board() {
         let result = parse_command("/move @1 @\"MiST: IT-Leder\"").unwrap();
         match result {
-            ParsedCommand::Move { id, board } => {
-                assert_eq!(id, 1);
+            ParsedCommand::Move { ids, board } => {
+                assert_eq!(ids, vec![1]);
                 assert_eq!(board, "MiST: IT-Leder");
             }
             _ => panic!("Expected Move"),
         }
     }
 
+    #[test]
+    fn test_parse_move_several_items() {
+        let result = parse_command("/move @1 @4 @7 @coding").unwrap();
+        match result {
+            ParsedCommand::Move { ids, board } => {
+                assert_eq!(ids, vec![1, 4, 7]);
+                assert_eq!(board, "coding");
+            }
+            _ => panic!("Expected Move"),
+        }
+        // A numeric board name is still the last token
+        match parse_command("/move @1 @2024").unwrap() {
+            ParsedCommand::Move { ids, board } => {
+                assert_eq!(ids, vec![1]);
+                assert_eq!(board, "2024");
+            }
+            _ => panic!("Expected Move"),
+        }
+        assert!(parse_command("/move @1").is_err());
+    }
+
+    #[test]
+    fn test_parse_clear_board_and_dry_run() {
+        assert!(matches!(
+            parse_command("/clear").unwrap(),
+            ParsedCommand::Clear {
+                board: None,
+                dry_run: false
+            }
+        ));
+        match parse_command("/clear @\"Side project\" --dry-run").unwrap() {
+            ParsedCommand::Clear { board, dry_run } => {
+                assert_eq!(board.as_deref(), Some("Side project"));
+                assert!(dry_run);
+            }
+            _ => panic!("Expected Clear"),
+        }
+        assert!(matches!(
+            parse_command("/clear --dry-run").unwrap(),
+            ParsedCommand::Clear {
+                board: None,
+                dry_run: true
+            }
+        ));
+        assert!(parse_command("/clear @a @b").is_err());
+    }
+
     #[test]
     fn test_parse_rename_board_quoted() {
         let result = parse_command("/rename-board @\"Old Board\" @\"New Board Name\"").unwrap();
@@ -614,4 +985,166 @@ mod tests {
             _ => panic!("Expected Task"),
         }
     }
+
+    #[test]
+    fn test_parse_tag() {
+        match parse_command("/tag @3 +Work -home").unwrap() {
+            ParsedCommand::Tag { id, add, remove } => {
+                assert_eq!(id, 3);
+                assert_eq!(add, vec!["work"]);
+                assert_eq!(remove, vec!["home"]);
+            }
+            _ => panic!("Expected Tag"),
+        }
+        match parse_command("/tag +Work").unwrap() {
+            ParsedCommand::TagFilter { tag } => assert_eq!(tag.as_deref(), Some("work")),
+            _ => panic!("Expected TagFilter"),
+        }
+        match parse_command("/tag").unwrap() {
+            ParsedCommand::TagFilter { tag } => assert_eq!(tag, None),
+            _ => panic!("Expected TagFilter"),
+        }
+        assert!(parse_command("/tag work home").is_err());
+        assert!(parse_command("/tag @3").is_err());
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        match parse_command("/filter urgent").unwrap() {
+            ParsedCommand::Filter { name } => assert_eq!(name.as_deref(), Some("urgent")),
+            _ => panic!("Expected Filter"),
+        }
+        match parse_command("/filter").unwrap() {
+            ParsedCommand::Filter { name } => assert_eq!(name, None),
+            _ => panic!("Expected Filter"),
+        }
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        match parse_command("/profile work").unwrap() {
+            ParsedCommand::Profile { name } => assert_eq!(name.as_deref(), Some("work")),
+            _ => panic!("Expected Profile"),
+        }
+        match parse_command("/profile").unwrap() {
+            ParsedCommand::Profile { name } => assert_eq!(name, None),
+            _ => panic!("Expected Profile"),
+        }
+    }
+
+    #[test]
+    fn test_parse_sort_board() {
+        assert!(matches!(
+            parse_command("/sort").unwrap(),
+            ParsedCommand::Sort
+        ));
+        match parse_command("/sort @\"Dev Ops\" priority").unwrap() {
+            ParsedCommand::SortBoard { board, method } => {
+                assert_eq!(board, "Dev Ops");
+                assert_eq!(method, Some(SortMethod::Priority));
+            }
+            _ => panic!("Expected SortBoard"),
+        }
+        match parse_command("/sort groceries default").unwrap() {
+            ParsedCommand::SortBoard { board, method } => {
+                assert_eq!(board, "groceries");
+                assert_eq!(method, None);
+            }
+            _ => panic!("Expected SortBoard"),
+        }
+        assert!(parse_command("/sort coding").is_err());
+        assert!(parse_command("/sort coding size").is_err());
+    }
+
+    #[test]
+    fn test_parse_reorder() {
+        assert!(matches!(
+            parse_command("/moveup").unwrap(),
+            ParsedCommand::Reorder { id: None, up: true }
+        ));
+        assert!(matches!(
+            parse_command("/movedown @7").unwrap(),
+            ParsedCommand::Reorder {
+                id: Some(7),
+                up: false
+            }
+        ));
+        assert!(parse_command("/movedown @7 @8").is_err());
+    }
+
+    #[test]
+    fn test_parse_group() {
+        match parse_command("/group month").unwrap() {
+            ParsedCommand::Group { group } => assert_eq!(group, Some(TimelineGroup::Month)),
+            _ => panic!("Expected Group"),
+        }
+        match parse_command("/group").unwrap() {
+            ParsedCommand::Group { group } => assert_eq!(group, None),
+            _ => panic!("Expected Group"),
+        }
+        assert!(parse_command("/group year").is_err());
+    }
+
+    #[test]
+    fn test_parse_task_with_due() {
+        let tomorrow = chrono::Local::now().date_naive() + chrono::Duration::days(1);
+        match parse_command("/task Ship release due:tomorrow p:2").unwrap() {
+            ParsedCommand::Task {
+                description,
+                priority,
+                due,
+                ..
+            } => {
+                assert_eq!(description, "Ship release");
+                assert_eq!(priority, 2);
+                assert_eq!(due, Some(tomorrow));
+            }
+            _ => panic!("Expected Task"),
+        }
+    }
+
+    #[test]
+    fn test_parse_due_and_today() {
+        match parse_command("/due @4 none").unwrap() {
+            ParsedCommand::Due { id, due } => {
+                assert_eq!(id, 4);
+                assert_eq!(due, None);
+            }
+            _ => panic!("Expected Due"),
+        }
+        assert!(parse_command("/due @4 someday").is_err());
+        assert!(matches!(
+            parse_command("/today").unwrap(),
+            ParsedCommand::Today
+        ));
+        assert!(matches!(
+            parse_command("/calendar").unwrap(),
+            ParsedCommand::Calendar
+        ));
+        assert!(matches!(
+            parse_command("/stats").unwrap(),
+            ParsedCommand::Stats
+        ));
+    }
+
+    #[test]
+    fn test_parse_theme() {
+        match parse_command("/theme catppuccin-mocha").unwrap() {
+            ParsedCommand::Theme { name } => assert_eq!(name.as_deref(), Some("catppuccin-mocha")),
+            _ => panic!("Expected Theme"),
+        }
+        assert!(matches!(
+            parse_command("/theme").unwrap(),
+            ParsedCommand::Theme { name: None }
+        ));
+    }
+
+    #[test]
+    fn test_search_term_while_typing() {
+        assert_eq!(search_term("/search milk "), Some("milk"));
+        assert_eq!(search_term("/SEARCH "), Some(""));
+        assert_eq!(search_term("/search"), None);
+        assert_eq!(search_term("/searching x"), None);
+        assert_eq!(search_term("/task milk"), None);
+    }
 }
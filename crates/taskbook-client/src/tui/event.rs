@@ -1,11 +1,18 @@
 use std::io::BufRead;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc};
 use std::thread;
 use std::time::Duration;
 
 use crossterm::event::{self, KeyEvent};
+use serde::Deserialize;
+use tungstenite::client::IntoClientRequest;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::Message;
 
+use crate::api_client::ApiClient;
+use crate::config::SyncTransport;
+use crate::credentials::Credentials;
 use crate::error::{Result, TaskbookError};
 
 /// Terminal events
@@ -18,7 +25,7 @@ pub enum Event {
     Resize(u16, u16),
     /// Periodic tick for UI updates
     Tick,
-    /// Remote data changed (received via SSE)
+    /// Remote data changed (received via SSE or WebSocket)
     DataChanged { archived: bool },
 }
 
@@ -46,37 +53,55 @@ pub fn drain_input_buffer() {
 
 /// Event handler with background thread
 pub struct EventHandler {
+    sender: mpsc::Sender<Event>,
     receiver: mpsc::Receiver<Event>,
     #[allow(dead_code)]
     handler: thread::JoinHandle<()>,
+    sync_handler: Option<SyncListener>,
+}
+
+/// Where the TUI listens for sync notifications
+pub struct SyncServer {
+    pub server_url: String,
+    pub token: String,
+    pub transport: SyncTransport,
+}
+
+/// Background sync connection, stopped when replaced or dropped
+struct SyncListener {
+    stop: Arc<AtomicBool>,
     #[allow(dead_code)]
-    sse_handler: Option<thread::JoinHandle<()>>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl Drop for SyncListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
 }
 
 impl EventHandler {
     /// Create a new event handler with the given tick rate in milliseconds
     pub fn new(tick_rate: u64) -> Self {
         let (sender, receiver) = mpsc::channel();
-        let handler = spawn_input_thread(sender, tick_rate);
+        let handler = spawn_input_thread(sender.clone(), tick_rate);
 
         Self {
+            sender,
             receiver,
             handler,
-            sse_handler: None,
+            sync_handler: None,
         }
     }
 
-    /// Create an event handler that also listens for SSE sync notifications.
-    pub fn new_with_sse(tick_rate: u64, server_url: String, token: String) -> Self {
-        let (sender, receiver) = mpsc::channel();
-        let handler = spawn_input_thread(sender.clone(), tick_rate);
-        let sse_handler = spawn_sse_thread(sender, server_url, token);
-
-        Self {
-            receiver,
-            handler,
-            sse_handler: Some(sse_handler),
-        }
+    /// Listen for sync notifications from `server`, replacing any previous
+    /// connection; `None` stops listening.
+    pub fn set_sync(&mut self, server: Option<SyncServer>) {
+        self.sync_handler = server.map(|server| {
+            let stop = Arc::new(AtomicBool::new(false));
+            let handle = spawn_sync_thread(self.sender.clone(), server, stop.clone());
+            SyncListener { stop, handle }
+        });
     }
 
     /// Get the next event, blocking until one is available
@@ -102,18 +127,15 @@ fn spawn_input_thread(sender: mpsc::Sender<Event>, tick_rate: u64) -> thread::Jo
             if EVENT_POLLING_PAUSED.load(Ordering::SeqCst) {
                 continue;
             }
-            match event::read() {
-                Ok(event::Event::Key(key)) => {
-                    if sender.send(Event::Key(key)).is_err() {
-                        break;
-                    }
+            let forwarded = match event::read() {
+                Ok(event::Event::Key(key)) => Some(Event::Key(key)),
+                Ok(event::Event::Resize(width, height)) => Some(Event::Resize(width, height)),
+                _ => None,
+            };
+            if let Some(ev) = forwarded {
+                if sender.send(ev).is_err() {
+                    break;
                 }
-                Ok(event::Event::Resize(width, height)) => {
-                    if sender.send(Event::Resize(width, height)).is_err() {
-                        break;
-                    }
-                }
-                _ => {}
             }
         } else if sender.send(Event::Tick).is_err() {
             break;
@@ -121,59 +143,206 @@ fn spawn_input_thread(sender: mpsc::Sender<Event>, tick_rate: u64) -> thread::Jo
     })
 }
 
-fn spawn_sse_thread(
+/// Why a sync connection ended
+enum Disconnect {
+    /// Replaced by another connection, or the TUI closed
+    Stopped,
+    /// The session expired
+    Unauthorized,
+    /// Connection failed or was lost
+    Lost,
+}
+
+fn spawn_sync_thread(
     sender: mpsc::Sender<Event>,
-    server_url: String,
-    token: String,
+    mut server: SyncServer,
+    stop: Arc<AtomicBool>,
 ) -> thread::JoinHandle<()> {
     thread::spawn(move || {
-        let client = reqwest::blocking::Client::new();
-        let url = format!("{}/api/v1/events", server_url.trim_end_matches('/'));
+        // Heartbeats arrive every 15 seconds, so a read that waits longer
+        // than this is on a dead connection
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(45))
+            .build()
+            .unwrap_or_default();
+        // Sent when reconnecting, so the server replays what was missed
+        let mut last_event_id = None;
 
         loop {
-            let resp = client
-                .get(&url)
-                .header("Authorization", format!("Bearer {}", token))
-                .header("Accept", "text/event-stream")
-                .send();
-
-            match resp {
-                Ok(response) if response.status().is_success() => {
-                    let reader = std::io::BufReader::new(response);
-                    let mut current_event = String::new();
-                    let mut current_data = String::new();
-
-                    for line in reader.lines() {
-                        let line = match line {
-                            Ok(l) => l,
-                            Err(_) => break, // Connection lost
-                        };
-
-                        if let Some(val) = line.strip_prefix("event:") {
-                            current_event = val.trim().to_string();
-                        } else if let Some(val) = line.strip_prefix("data:") {
-                            current_data = val.trim().to_string();
-                        } else if line.is_empty() && !current_event.is_empty() {
-                            // End of SSE frame — dispatch event
-                            if current_event == "data_changed" {
-                                let archived = current_data == "archive";
-                                if sender.send(Event::DataChanged { archived }).is_err() {
-                                    return; // TUI closed
-                                }
-                            }
-                            current_event.clear();
-                            current_data.clear();
-                        }
+            let disconnect = match server.transport {
+                SyncTransport::Sse => {
+                    listen_sse(&client, &server, &mut last_event_id, &sender, &stop)
+                }
+                SyncTransport::WebSocket => listen_websocket(&server, &sender, &stop),
+            };
+            match disconnect {
+                Disconnect::Stopped => return,
+                Disconnect::Unauthorized => {
+                    // Renew the session for the next attempt
+                    if let Some(renewed) = renewed_token(&server.server_url) {
+                        server.token = renewed;
                     }
                 }
-                _ => {} // Connection failed or non-success status
+                Disconnect::Lost => {}
             }
 
             // Reconnect after delay; exit if TUI has closed (sender dropped)
             thread::sleep(Duration::from_secs(5));
-            if sender.send(Event::Tick).is_err() {
+            if stop.load(Ordering::SeqCst) || sender.send(Event::Tick).is_err() {
                 return;
             }
         }
     })
 }
+
+fn listen_sse(
+    client: &reqwest::blocking::Client,
+    server: &SyncServer,
+    last_event_id: &mut Option<String>,
+    sender: &mpsc::Sender<Event>,
+    stop: &AtomicBool,
+) -> Disconnect {
+    let url = format!("{}/api/v1/events", server.server_url.trim_end_matches('/'));
+    let mut request = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", server.token))
+        .header("Accept", "text/event-stream");
+    if let Some(id) = last_event_id.as_deref() {
+        request = request.header("Last-Event-ID", id);
+    }
+    let resp = request.send();
+
+    let response = match resp {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) if response.status() == reqwest::StatusCode::UNAUTHORIZED => {
+            return Disconnect::Unauthorized
+        }
+        _ => return Disconnect::Lost, // Connection failed or non-success status
+    };
+
+    let reader = std::io::BufReader::new(response);
+    let mut current_event = String::new();
+    let mut current_data = String::new();
+    let mut current_id = None;
+
+    for line in reader.lines() {
+        if stop.load(Ordering::SeqCst) {
+            return Disconnect::Stopped; // Replaced by another connection
+        }
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => break, // Connection lost
+        };
+
+        if let Some(val) = line.strip_prefix("event:") {
+            current_event = val.trim().to_string();
+        } else if let Some(val) = line.strip_prefix("id:") {
+            current_id = Some(val.trim().to_string());
+        } else if let Some(val) = line.strip_prefix("data:") {
+            current_data = val.trim().to_string();
+        } else if line.is_empty() && !current_event.is_empty() {
+            // End of SSE frame — dispatch event
+            if current_id.is_some() {
+                *last_event_id = current_id.take();
+            }
+            if current_event == "data_changed" {
+                let archived = current_data == "archive";
+                if sender.send(Event::DataChanged { archived }).is_err() {
+                    return Disconnect::Stopped; // TUI closed
+                }
+            }
+            current_event.clear();
+            current_data.clear();
+        }
+    }
+    Disconnect::Lost
+}
+
+/// A message from the server on `/api/v1/ws`
+#[derive(Deserialize)]
+struct SocketMessage {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: String,
+}
+
+fn listen_websocket(
+    server: &SyncServer,
+    sender: &mpsc::Sender<Event>,
+    stop: &AtomicBool,
+) -> Disconnect {
+    let base = server.server_url.trim_end_matches('/');
+    let url = match base.split_once("://") {
+        Some(("https", rest)) => format!("wss://{rest}/api/v1/ws"),
+        Some((_, rest)) => format!("ws://{rest}/api/v1/ws"),
+        None => return Disconnect::Lost,
+    };
+    let Ok(mut request) = url.into_client_request() else {
+        return Disconnect::Lost;
+    };
+    let Ok(auth) = format!("Bearer {}", server.token).parse() else {
+        return Disconnect::Lost;
+    };
+    request.headers_mut().insert("Authorization", auth);
+
+    let mut socket = match tungstenite::connect(request) {
+        Ok((socket, _)) => socket,
+        Err(tungstenite::Error::Http(response)) if response.status() == 401 => {
+            return Disconnect::Unauthorized
+        }
+        Err(_) => return Disconnect::Lost,
+    };
+    // Wake up now and then to see whether the connection was replaced
+    let stream = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => Some(stream),
+        MaybeTlsStream::Rustls(stream) => Some(stream.get_ref()),
+        _ => None,
+    };
+    if let Some(stream) = stream {
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+    }
+
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            let _ = socket.close(None);
+            return Disconnect::Stopped; // Replaced by another connection
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let Ok(message) = serde_json::from_str::<SocketMessage>(&text) else {
+                    continue;
+                };
+                if message.kind == "data_changed" {
+                    let archived = message.data == "archive";
+                    if sender.send(Event::DataChanged { archived }).is_err() {
+                        return Disconnect::Stopped; // TUI closed
+                    }
+                }
+            }
+            Ok(Message::Close(_)) => return Disconnect::Lost,
+            // Pings are answered by tungstenite
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                // Nothing arrived; send any queued pong
+                let _ = socket.flush();
+            }
+            Err(_) => return Disconnect::Lost,
+        }
+    }
+}
+
+/// A new session token from the saved refresh token, if it can be renewed
+fn renewed_token(server_url: &str) -> Option<String> {
+    let creds = Credentials::load().ok()??;
+    let client = ApiClient::for_credentials(server_url, &creds);
+    match client.refresh_session() {
+        Ok(true) => client.token(),
+        _ => None,
+    }
+}
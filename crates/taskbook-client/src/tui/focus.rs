@@ -0,0 +1,70 @@
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// A running focus (pomodoro) session on a task
+#[derive(Debug, Clone)]
+pub struct FocusTimer {
+    pub task_id: u64,
+    pub description: String,
+    /// Wall-clock start, logged with the session (ms since epoch)
+    pub started_at: i64,
+    started: Instant,
+    duration: Duration,
+}
+
+impl FocusTimer {
+    pub fn start(task_id: u64, description: String, minutes: u32) -> Self {
+        Self {
+            task_id,
+            description,
+            started_at: chrono::Utc::now().timestamp_millis(),
+            started: Instant::now(),
+            duration: Duration::from_secs(u64::from(minutes.max(1)) * 60),
+        }
+    }
+
+    /// Time spent so far, capped at the session length
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed().min(self.duration)
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.started.elapsed())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.remaining().is_zero()
+    }
+}
+
+/// Format a duration as `MM:SS`
+pub fn format_clock(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+/// Best-effort desktop notification; failures are ignored since the
+/// status bar already reports the end of the session.
+pub fn notify(title: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {:?} with title {:?}",
+            body, title
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg(title).arg(body);
+        command
+    };
+
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Wait off the UI thread so the child is reaped without blocking input
+    std::thread::spawn(move || {
+        let _ = command.status();
+    });
+}
@@ -3,7 +3,11 @@ mod app;
 mod autocomplete;
 mod command_parser;
 mod event;
+mod focus;
 mod input_handler;
+mod session;
+mod settings;
+mod setup;
 mod theme;
 mod ui;
 pub mod widgets;
@@ -107,6 +111,8 @@ pub fn run(taskbook_dir: Option<&Path>) -> Result<()> {
     // Create app and run
     let mut app = App::new(taskbook_dir)?;
     let res = run_app(&mut terminal, &mut app);
+    // Losing the place to reopen at is not worth an error on exit
+    let _ = app.save_session();
 
     // Restore terminal
     disable_raw_mode().map_err(|e| TaskbookError::Tui(e.to_string()))?;
@@ -124,7 +130,8 @@ pub fn run(taskbook_dir: Option<&Path>) -> Result<()> {
 }
 
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
-    let events = create_event_handler(&app.config);
+    let mut events = event::EventHandler::new(250);
+    events.set_sync(sync_server(&app.config));
 
     while app.running {
         // Force full redraw if requested (e.g. after returning from external editor)
@@ -142,6 +149,10 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
         match events.next()? {
             event::Event::Key(key) => {
                 actions::handle_key_event(app, key)?;
+                if app.sync_changed {
+                    app.sync_changed = false;
+                    events.set_sync(sync_server(&app.config));
+                }
             }
             event::Event::Tick => {
                 app.tick();
@@ -149,12 +160,21 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
             event::Event::Resize(_, _) => {}
             event::Event::DataChanged { archived } => {
                 use app::ViewMode;
+                app.taskbook.reload();
                 match (app.view, archived) {
                     (ViewMode::Archive, true) => {
                         app.items = app.taskbook.get_all_archive_items()?;
                         app.update_display_order();
                     }
-                    (ViewMode::Board | ViewMode::Timeline | ViewMode::Journal, false) => {
+                    (
+                        ViewMode::Board
+                        | ViewMode::Timeline
+                        | ViewMode::Journal
+                        | ViewMode::Calendar
+                        | ViewMode::Today
+                        | ViewMode::Stats,
+                        false,
+                    ) => {
                         app.refresh_items()?;
                     }
                     _ => {} // Data will be loaded when user switches views
@@ -166,12 +186,16 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
     Ok(())
 }
 
-/// Create the appropriate event handler based on sync configuration.
-fn create_event_handler(config: &Config) -> event::EventHandler {
+/// Server to listen to for sync notifications, if sync is on
+fn sync_server(config: &Config) -> Option<event::SyncServer> {
     if config.sync.enabled {
         if let Ok(Some(creds)) = Credentials::load() {
-            return event::EventHandler::new_with_sse(250, creds.server_url, creds.token);
+            return Some(event::SyncServer {
+                server_url: creds.server_url,
+                token: creds.token,
+                transport: config.sync.transport,
+            });
         }
     }
-    event::EventHandler::new(250)
+    None
 }
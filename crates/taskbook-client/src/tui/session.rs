@@ -0,0 +1,217 @@
+//! Where the TUI was when it closed, so it opens there again: the board
+//! filter, the collapsed boards and the selected item, kept in
+//! `tui-state.json` in the taskbook directory, and the command line history,
+//! in `tui-history`. Neither is kept while local storage is encrypted, as
+//! board names and commands tell what the items say. The view and the sort
+//! method are settings, saved to the config as they change.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::directory::resolve_taskbook_directory;
+use crate::error::Result;
+use crate::storage;
+
+const STATE_FILE: &str = "tui-state.json";
+
+/// One command per line, oldest first
+const HISTORY_FILE: &str = "tui-history";
+
+/// Most commands the history keeps
+pub const HISTORY_LIMIT: usize = 200;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    /// Board the views were narrowed to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board_filter: Option<String>,
+    /// Boards showing only their header
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collapsed_boards: Vec<String>,
+    /// Id of the selected item
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected_item: Option<u64>,
+}
+
+/// The file `name` in the taskbook directory, and whether it may be kept:
+/// not when local storage is encrypted
+fn session_file(
+    taskbook_dir: Option<&Path>,
+    config: &Config,
+    name: &str,
+) -> Result<(PathBuf, bool)> {
+    let dir = resolve_taskbook_directory(taskbook_dir, config)?;
+    Ok((dir.join(name), !storage::is_encrypted(&dir)))
+}
+
+/// Remove a file that may not be kept, if it is there
+fn remove(path: PathBuf) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Commands entered in earlier sessions, oldest first
+pub fn load_history(taskbook_dir: Option<&Path>, config: &Config) -> Vec<String> {
+    let raw = session_file(taskbook_dir, config, HISTORY_FILE)
+        .ok()
+        .filter(|&(_, kept)| kept)
+        .and_then(|(path, _)| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    let history: Vec<String> = raw
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+    let skip = history.len().saturating_sub(HISTORY_LIMIT);
+    history.into_iter().skip(skip).collect()
+}
+
+/// Save the latest commands; if local storage is encrypted, remove the
+/// history kept from before instead
+pub fn save_history(
+    taskbook_dir: Option<&Path>,
+    config: &Config,
+    history: &[String],
+) -> Result<()> {
+    let (path, kept) = session_file(taskbook_dir, config, HISTORY_FILE)?;
+    if !kept {
+        return remove(path);
+    }
+    let skip = history.len().saturating_sub(HISTORY_LIMIT);
+    let mut raw = history[skip..].join("\n");
+    raw.push('\n');
+    fs::write(path, raw)?;
+    Ok(())
+}
+
+impl Session {
+    /// The state saved when the TUI last closed; empty if there is none,
+    /// it cannot be read or local storage is encrypted
+    pub fn load(taskbook_dir: Option<&Path>, config: &Config) -> Self {
+        session_file(taskbook_dir, config, STATE_FILE)
+            .ok()
+            .filter(|&(_, kept)| kept)
+            .and_then(|(path, _)| fs::read_to_string(path).ok())
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the state; if local storage is encrypted, remove the state
+    /// kept from before instead
+    pub fn save(&self, taskbook_dir: Option<&Path>, config: &Config) -> Result<()> {
+        let (path, kept) = session_file(taskbook_dir, config, STATE_FILE)?;
+        if !kept {
+            return remove(path);
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{EncryptionConfig, SyncConfig};
+
+    #[test]
+    fn session_round_trips_through_the_taskbook_directory() {
+        let root = std::env::temp_dir().join(format!("tb-session-{}", uuid::Uuid::new_v4()));
+        let dir = root.join(".taskbook");
+        fs::create_dir_all(&dir).unwrap();
+        let config = Config::default();
+
+        assert_eq!(Session::load(Some(&dir), &config), Session::default());
+        let session = Session {
+            board_filter: Some("@coding".to_string()),
+            collapsed_boards: vec!["@reading".to_string()],
+            selected_item: Some(4),
+        };
+        session.save(Some(&dir), &config).unwrap();
+        assert_eq!(Session::load(Some(&dir), &config), session);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn history_keeps_the_latest_commands() {
+        let root = std::env::temp_dir().join(format!("tb-session-{}", uuid::Uuid::new_v4()));
+        let dir = root.join(".taskbook");
+        fs::create_dir_all(&dir).unwrap();
+        let config = Config::default();
+
+        assert!(load_history(Some(&dir), &config).is_empty());
+        let history = vec!["/search milk".to_string(), "/board @home".to_string()];
+        save_history(Some(&dir), &config, &history).unwrap();
+        assert_eq!(load_history(Some(&dir), &config), history);
+
+        let history: Vec<String> = (0..HISTORY_LIMIT + 5)
+            .map(|i| format!("/search {i}"))
+            .collect();
+        save_history(Some(&dir), &config, &history).unwrap();
+        let loaded = load_history(Some(&dir), &config);
+        assert_eq!(loaded, history[5..]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    /// A config whose storage key is cheap to derive, and kept out of the
+    /// system keyring
+    fn encryption_config() -> Config {
+        Config {
+            sync: SyncConfig {
+                keyring: false,
+                ..Default::default()
+            },
+            encryption: EncryptionConfig {
+                kdf_memory_kib: 8,
+                kdf_iterations: 1,
+                kdf_parallelism: 1,
+            },
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn history_is_not_kept_in_encrypted_storage() {
+        let root = std::env::temp_dir().join(format!("tb-session-{}", uuid::Uuid::new_v4()));
+        let dir = root.join(".taskbook");
+        fs::create_dir_all(&dir).unwrap();
+        let config = encryption_config();
+        let history = vec!["/search payroll".to_string()];
+        save_history(Some(&dir), &config, &history).unwrap();
+
+        storage::create_storage_key(&dir, "correct horse battery", &config).unwrap();
+        assert!(load_history(Some(&dir), &config).is_empty());
+        save_history(Some(&dir), &config, &history).unwrap();
+        assert!(!dir.join(HISTORY_FILE).exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn session_is_not_kept_in_encrypted_storage() {
+        let root = std::env::temp_dir().join(format!("tb-session-{}", uuid::Uuid::new_v4()));
+        let dir = root.join(".taskbook");
+        fs::create_dir_all(&dir).unwrap();
+        let config = encryption_config();
+        let session = Session {
+            board_filter: Some("@payroll".to_string()),
+            collapsed_boards: vec!["@layoffs".to_string()],
+            selected_item: Some(4),
+        };
+        session.save(Some(&dir), &config).unwrap();
+
+        storage::create_storage_key(&dir, "correct horse battery", &config).unwrap();
+        assert_eq!(Session::load(Some(&dir), &config), Session::default());
+        session.save(Some(&dir), &config).unwrap();
+        assert!(!dir.join(STATE_FILE).exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
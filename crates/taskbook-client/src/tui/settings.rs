@@ -0,0 +1,138 @@
+use crate::config::{Config, ThemeColors, ThemeConfig};
+
+use super::app::ViewMode;
+use super::setup::VIEWS;
+
+/// A row of the settings popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Theme,
+    Sort,
+    Completed,
+    ConfirmDelete,
+    Density,
+    View,
+    Sync,
+}
+
+impl Setting {
+    /// The rows, in the order the popup lists them
+    pub const ALL: &'static [Setting] = &[
+        Setting::Theme,
+        Setting::Sort,
+        Setting::Completed,
+        Setting::ConfirmDelete,
+        Setting::Density,
+        Setting::View,
+        Setting::Sync,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::Theme => "Theme",
+            Setting::Sort => "Sort items by",
+            Setting::Completed => "Completed tasks",
+            Setting::ConfirmDelete => "Confirm deletes",
+            Setting::Density => "Density",
+            Setting::View => "View",
+            Setting::Sync => "Sync",
+        }
+    }
+
+    /// Shown under the list for the selected row
+    pub fn description(self) -> &'static str {
+        match self {
+            Setting::Theme => "Colors of the TUI and the CLI output.",
+            Setting::Sort => "Order of items within each board.",
+            Setting::Completed => "Whether checked tasks stay on the boards.",
+            Setting::ConfirmDelete => {
+                "Whether deleting one item asks first; deleting several always does."
+            }
+            Setting::Density => "How much each item line shows: ages, boards, time logged.",
+            Setting::View => "The view shown now, and when taskbook opens next.",
+            Setting::Sync => "Turning sync on uses the account from tb --login.",
+        }
+    }
+
+    /// The current value, as shown in the popup
+    pub fn value(self, config: &Config) -> String {
+        match self {
+            Setting::Theme => match &config.theme {
+                ThemeConfig::Preset(name) => name.clone(),
+                ThemeConfig::Custom(_) => "custom".to_string(),
+            },
+            Setting::Sort => config.sort_method.display_name().to_string(),
+            Setting::Completed => if config.display_complete_tasks {
+                "shown"
+            } else {
+                "hidden"
+            }
+            .to_string(),
+            Setting::ConfirmDelete => if config.confirm_delete {
+                "always"
+            } else {
+                "several items only"
+            }
+            .to_string(),
+            Setting::Density => config.display_density.display_name().to_string(),
+            Setting::View => config.default_view.display_name().to_string(),
+            Setting::Sync if config.sync.enabled => format!("on, {}", config.sync.server_url),
+            Setting::Sync => "off, this computer only".to_string(),
+        }
+    }
+}
+
+/// The preset `delta` steps from `theme` in `ThemeColors::PRESETS`,
+/// wrapping around; a custom theme counts as just before the first
+pub fn cycle_theme(theme: &ThemeConfig, delta: isize) -> &'static str {
+    let current = theme_index(theme);
+    let len = ThemeColors::PRESETS.len() as isize;
+    let next = match current {
+        Some(index) => (index as isize + delta).rem_euclid(len),
+        None if delta < 0 => len - 1,
+        None => 0,
+    };
+    ThemeColors::PRESETS[next as usize]
+}
+
+/// Position of `theme` in `ThemeColors::PRESETS`, if it is a preset
+pub fn theme_index(theme: &ThemeConfig) -> Option<usize> {
+    match theme {
+        ThemeConfig::Preset(name) => {
+            let name = ThemeColors::preset_name(name)?;
+            ThemeColors::PRESETS
+                .iter()
+                .position(|preset| *preset == name)
+        }
+        ThemeConfig::Custom(_) => None,
+    }
+}
+
+/// The view `delta` steps from `view` among those the TUI can open in
+pub fn cycle_view(view: ViewMode, delta: isize) -> ViewMode {
+    let current = VIEWS.iter().position(|(mode, _)| *mode == view);
+    let len = VIEWS.len() as isize;
+    let next = match current {
+        Some(index) => (index as isize + delta).rem_euclid(len),
+        None => 0,
+    };
+    VIEWS[next as usize].0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_and_views_cycle_around() {
+        let theme = ThemeConfig::Preset("High Contrast".to_string());
+        assert_eq!(cycle_theme(&theme, 1), "default");
+        assert_eq!(cycle_theme(&theme, -1), "catppuccin-latte");
+        let custom = ThemeConfig::Custom(ThemeColors::default());
+        assert_eq!(cycle_theme(&custom, 1), "default");
+
+        assert_eq!(cycle_view(ViewMode::Stats, 1), ViewMode::Board);
+        assert_eq!(cycle_view(ViewMode::Board, -1), ViewMode::Stats);
+        assert_eq!(cycle_view(ViewMode::Archive, 1), ViewMode::Board);
+    }
+}
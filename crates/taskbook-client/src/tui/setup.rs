@@ -0,0 +1,156 @@
+use crate::config::{Config, ThemeColors};
+
+use super::app::ViewMode;
+
+/// Views offered as the one the TUI opens in
+pub const VIEWS: &[(ViewMode, &str)] = &[
+    (ViewMode::Board, "Board: items grouped by board"),
+    (
+        ViewMode::Timeline,
+        "Timeline: items by the day they were added",
+    ),
+    (
+        ViewMode::Today,
+        "Today: overdue, due today, in progress and starred",
+    ),
+    (ViewMode::Journal, "Journal: what was done each day"),
+    (ViewMode::Calendar, "Calendar: a month of due dates"),
+    (ViewMode::Stats, "Stats: progress at a glance"),
+];
+
+/// Where items are kept, as offered on the sync step
+pub const STORAGE_CHOICES: &[&str] = &[
+    "On this computer only",
+    "Synced with a taskbook server (log in after setup)",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupStep {
+    Theme,
+    View,
+    Sync,
+    /// Only when syncing was chosen
+    ServerUrl,
+    Board,
+}
+
+/// The first-run setup wizard: what has been chosen so far
+#[derive(Debug, Clone)]
+pub struct SetupState {
+    pub step: SetupStep,
+    /// Index into `ThemeColors::PRESETS`
+    pub theme: usize,
+    /// Index into `VIEWS`
+    pub view: usize,
+    pub sync: bool,
+    pub server_url: String,
+    pub board: String,
+}
+
+impl SetupState {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            step: SetupStep::Theme,
+            theme: 0,
+            view: 0,
+            sync: config.sync.enabled,
+            server_url: config.sync.server_url.clone(),
+            board: taskbook_common::board::DEFAULT_BOARD.to_string(),
+        }
+    }
+
+    /// Position of the current step, and the number of steps, counting the
+    /// server step only when syncing
+    pub fn progress(&self) -> (usize, usize) {
+        let total = if self.sync { 5 } else { 4 };
+        let number = match self.step {
+            SetupStep::Theme => 1,
+            SetupStep::View => 2,
+            SetupStep::Sync => 3,
+            SetupStep::ServerUrl => 4,
+            SetupStep::Board => total,
+        };
+        (number, total)
+    }
+
+    pub fn theme_name(&self) -> &'static str {
+        ThemeColors::PRESETS[self.theme]
+    }
+
+    pub fn view_mode(&self) -> ViewMode {
+        VIEWS[self.view].0
+    }
+
+    /// Move the choice on a list step by `delta`, wrapping around
+    pub fn choose(&mut self, delta: isize) {
+        let (index, len) = match self.step {
+            SetupStep::Theme => (self.theme, ThemeColors::PRESETS.len()),
+            SetupStep::View => (self.view, VIEWS.len()),
+            SetupStep::Sync => (usize::from(self.sync), STORAGE_CHOICES.len()),
+            SetupStep::ServerUrl | SetupStep::Board => return,
+        };
+        let next = (index as isize + delta).rem_euclid(len as isize) as usize;
+        match self.step {
+            SetupStep::Theme => self.theme = next,
+            SetupStep::View => self.view = next,
+            SetupStep::Sync => self.sync = next == 1,
+            SetupStep::ServerUrl | SetupStep::Board => {}
+        }
+    }
+
+    /// The text being typed on an input step
+    pub fn input_mut(&mut self) -> Option<&mut String> {
+        match self.step {
+            SetupStep::ServerUrl => Some(&mut self.server_url),
+            SetupStep::Board => Some(&mut self.board),
+            _ => None,
+        }
+    }
+
+    /// Go to the next step. Returns false on the last one.
+    pub fn next(&mut self) -> bool {
+        self.step = match self.step {
+            SetupStep::Theme => SetupStep::View,
+            SetupStep::View => SetupStep::Sync,
+            SetupStep::Sync if self.sync => SetupStep::ServerUrl,
+            SetupStep::Sync | SetupStep::ServerUrl => SetupStep::Board,
+            SetupStep::Board => return false,
+        };
+        true
+    }
+
+    pub fn previous(&mut self) {
+        self.step = match self.step {
+            SetupStep::Theme | SetupStep::View => SetupStep::Theme,
+            SetupStep::Sync => SetupStep::View,
+            SetupStep::ServerUrl => SetupStep::Sync,
+            SetupStep::Board if self.sync => SetupStep::ServerUrl,
+            SetupStep::Board => SetupStep::Sync,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_step_only_when_syncing() {
+        let mut setup = SetupState::new(&Config::default());
+        setup.next();
+        setup.next();
+        assert_eq!(setup.step, SetupStep::Sync);
+        assert!(setup.next());
+        assert_eq!(setup.step, SetupStep::Board);
+        assert_eq!(setup.progress(), (4, 4));
+
+        setup.previous();
+        setup.choose(1);
+        assert!(setup.sync);
+        setup.next();
+        assert_eq!(setup.step, SetupStep::ServerUrl);
+        setup.next();
+        assert_eq!(setup.progress(), (5, 5));
+        assert!(!setup.next());
+    }
+}
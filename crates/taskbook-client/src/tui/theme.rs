@@ -1,6 +1,7 @@
 use ratatui::style::{Color, Modifier, Style};
 
-use crate::config::ThemeColors;
+use crate::config::{ColorMode, Rgb, ThemeColors};
+use crate::palette;
 
 /// TUI theme with ratatui styles
 #[derive(Debug, Clone)]
@@ -19,55 +20,111 @@ pub struct TuiTheme {
     pub item_id: Style,
     pub completed_text: Style,
     pub board_name: Style,
+    /// Tag chips on item rows
+    pub tag: Style,
+    /// Task descriptions
+    pub text: Style,
+    /// Note titles
+    pub note_text: Style,
+    /// Popup background
+    pub popup: Style,
+    /// Autocomplete menu entries, and the selected one
+    pub menu: Style,
+    pub menu_selected: Style,
+    /// Command line cursor
+    pub cursor: Style,
+    pub scrollbar: Style,
+    /// Search matches in descriptions
+    pub search_match: Style,
+    /// Whole screen, when the theme sets a background
+    pub background: Option<Style>,
 }
 
-impl From<&ThemeColors> for TuiTheme {
-    fn from(colors: &ThemeColors) -> Self {
+/// `rgb`, or the nearest color the terminal shows in `mode`
+pub fn color(rgb: Rgb, mode: ColorMode) -> Color {
+    match mode {
+        ColorMode::Ansi16 => BASIC_COLORS[palette::nearest_16(rgb) as usize],
+        ColorMode::Ansi256 => Color::Indexed(palette::nearest_256(rgb)),
+        ColorMode::TrueColor | ColorMode::Auto => Color::Rgb(rgb.r, rgb.g, rgb.b),
+    }
+}
+
+/// The basic colors, in palette order
+const BASIC_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+impl TuiTheme {
+    /// Styles from `colors`, mapped to what the terminal shows when
+    /// `mode` (the `colorMode` setting) or its detection says it has
+    /// fewer than 24-bit colors
+    pub fn new(colors: &ThemeColors, mode: ColorMode) -> Self {
+        let mode = palette::detect(mode);
+        let color = |rgb: Rgb| color(rgb, mode);
+        let ui = &colors.ui;
         Self {
-            muted: Style::default().fg(Color::Rgb(colors.muted.r, colors.muted.g, colors.muted.b)),
-            success: Style::default().fg(Color::Rgb(
-                colors.success.r,
-                colors.success.g,
-                colors.success.b,
-            )),
-            warning: Style::default().fg(Color::Rgb(
-                colors.warning.r,
-                colors.warning.g,
-                colors.warning.b,
-            )),
-            error: Style::default().fg(Color::Rgb(colors.error.r, colors.error.g, colors.error.b)),
-            info: Style::default().fg(Color::Rgb(colors.info.r, colors.info.g, colors.info.b)),
-            pending: Style::default().fg(Color::Rgb(
-                colors.pending.r,
-                colors.pending.g,
-                colors.pending.b,
-            )),
-            starred: Style::default().fg(Color::Rgb(
-                colors.starred.r,
-                colors.starred.g,
-                colors.starred.b,
-            )),
+            muted: Style::default().fg(color(colors.muted)),
+            success: Style::default().fg(color(colors.success)),
+            warning: Style::default().fg(color(colors.warning)),
+            error: Style::default().fg(color(colors.error)),
+            info: Style::default().fg(color(colors.info)),
+            pending: Style::default().fg(color(colors.pending)),
+            starred: Style::default().fg(color(colors.starred)),
             selected: Style::default()
-                .bg(Color::Rgb(50, 50, 70))
+                .bg(color(ui.selection))
                 .add_modifier(Modifier::BOLD),
-            border: Style::default().fg(Color::Rgb(80, 80, 100)),
+            border: Style::default().fg(color(ui.border)),
             title: Style::default()
-                .fg(Color::White)
+                .fg(color(ui.heading))
                 .add_modifier(Modifier::BOLD),
             // Board headers - use info color for better visibility
             header: Style::default()
-                .fg(Color::Rgb(colors.info.r, colors.info.g, colors.info.b))
+                .fg(color(colors.info))
                 .add_modifier(Modifier::BOLD),
             // Item IDs - brighter than muted
-            item_id: Style::default().fg(Color::Rgb(180, 180, 200)),
-            // Completed task text - same color as normal text with strikethrough
+            item_id: Style::default().fg(color(ui.item_id)),
+            // Completed task text - struck through
             completed_text: Style::default()
-                .fg(Color::Rgb(140, 140, 160))
+                .fg(color(ui.completed))
                 .add_modifier(Modifier::CROSSED_OUT),
             // Board name in headers
             board_name: Style::default()
-                .fg(Color::Rgb(colors.info.r, colors.info.g, colors.info.b))
+                .fg(color(colors.info))
+                .add_modifier(Modifier::BOLD),
+            tag: Style::default()
+                .fg(color(colors.info))
+                .bg(color(ui.surface)),
+            text: Style::default().fg(color(ui.text)),
+            note_text: Style::default().fg(color(ui.note_text)),
+            popup: Style::default().fg(color(ui.text)).bg(color(ui.surface)),
+            menu: Style::default().fg(color(ui.text)).bg(color(ui.menu)),
+            menu_selected: Style::default()
+                .fg(color(ui.text))
+                .bg(color(ui.menu_selected))
                 .add_modifier(Modifier::BOLD),
+            cursor: Style::default().fg(color(ui.surface)).bg(color(ui.cursor)),
+            scrollbar: Style::default().fg(color(ui.scrollbar)),
+            search_match: Style::default()
+                .fg(color(ui.search_match.unwrap_or(colors.warning)))
+                .add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            background: ui
+                .background
+                .map(|rgb| Style::default().fg(color(ui.text)).bg(color(rgb))),
         }
     }
 }
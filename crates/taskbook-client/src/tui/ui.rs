@@ -1,22 +1,39 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Frame,
 };
 
-use crate::config::SortMethod;
+use crate::config::{SortMethod, TimelineGroup};
+use taskbook_common::board;
 
 use super::app::{App, PopupState, ViewMode};
 use super::widgets::{
-    board_view::render_board_view, command_line::render_autocomplete,
-    command_line::render_command_line, help_popup::render_help_popup,
-    journal_view::render_journal_view, status_bar::render_stats_line,
+    activity_popup::render_activity_popup,
+    board_sidebar::{render_board_sidebar, sidebar_width},
+    board_view::render_board_view,
+    calendar_view::render_calendar_view,
+    command_line::render_autocomplete,
+    command_line::render_command_line,
+    help_popup::render_help_popup,
+    journal_view::render_journal_view,
+    settings_popup::render_settings_popup,
+    setup_wizard::render_setup_wizard,
+    stats_view::render_stats_view,
+    status_bar::render_stats_line,
+    theme_picker::render_theme_picker,
     timeline_view::render_timeline_view,
+    today_view::render_today_view,
 };
 
 /// Render the entire UI
 pub fn render(frame: &mut Frame, app: &mut App) {
+    // Themes with their own background paint the whole screen
+    if let Some(background) = app.theme.background {
+        frame.render_widget(Block::default().style(background), frame.area());
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -36,24 +53,21 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     // Render autocomplete overlay on top of content area
     render_autocomplete(frame, app, chunks[1]);
 
-    // Render popup if active (Help only)
+    // Render popup if active
     if let Some(ref popup) = app.popup {
         render_popup(frame, app, popup);
     }
+
+    if let Some(ref setup) = app.setup {
+        render_setup_wizard(frame, app, setup);
+    }
 }
 
 fn render_header(frame: &mut Frame, app: &App, area: Rect) {
-    let view_name = match app.view {
-        ViewMode::Board => "Board View",
-        ViewMode::Timeline => "Timeline View",
-        ViewMode::Archive => "Archive View",
-        ViewMode::Journal => "Journal View",
-    };
-
     let mut spans = vec![
         Span::styled("  taskbook", app.theme.title),
         Span::raw("  "),
-        Span::styled(view_name, app.theme.muted),
+        Span::styled(format!("{} View", app.view.display_name()), app.theme.muted),
     ];
 
     // Show board filter indicator
@@ -63,6 +77,16 @@ fn render_header(frame: &mut Frame, app: &App, area: Rect) {
         spans.push(Span::styled(" (Esc to clear)", app.theme.muted));
     }
 
+    // Show tag filter indicator
+    if let Some(ref tag) = app.filter.tag_filter {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            format!("[Tag: {}]", board::display_tag(tag)),
+            app.theme.info,
+        ));
+        spans.push(Span::styled(" (Esc to clear)", app.theme.muted));
+    }
+
     // Show sort indicator (only in board view, only when not default)
     if app.view == ViewMode::Board && app.sort_method != SortMethod::Id {
         spans.push(Span::raw("  "));
@@ -72,6 +96,17 @@ fn render_header(frame: &mut Frame, app: &App, area: Rect) {
         ));
     }
 
+    // Show timeline grouping indicator (only when not default)
+    if matches!(app.view, ViewMode::Timeline | ViewMode::Archive)
+        && app.config.timeline_group != TimelineGroup::Day
+    {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            format!("[Group: {}]", app.config.timeline_group.display_name()),
+            app.theme.info,
+        ));
+    }
+
     // Show hide completed indicator
     if app.filter.hide_completed {
         spans.push(Span::raw("  "));
@@ -88,10 +123,22 @@ fn render_content(frame: &mut Frame, app: &App, area: Rect) {
         .borders(Borders::TOP | Borders::BOTTOM)
         .border_style(app.theme.border);
 
-    let inner = block.inner(area);
+    let mut inner = block.inner(area);
     frame.render_widget(block, area);
 
-    if app.display_order.is_empty() {
+    // Board picker down the left of the board view
+    if app.view == ViewMode::Board && app.sidebar.open {
+        let width = sidebar_width(app, inner.width);
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(width), Constraint::Min(1)])
+            .split(inner);
+        render_board_sidebar(frame, app, columns[0]);
+        inner = columns[1];
+    }
+
+    // The calendar grid and dashboard are shown even without selectable items
+    if app.display_order.is_empty() && !matches!(app.view, ViewMode::Calendar | ViewMode::Stats) {
         let empty_msg = match app.view {
             ViewMode::Board => {
                 "No tasks or notes. Press 't' to create a task or 'n' to create a note."
@@ -99,6 +146,8 @@ fn render_content(frame: &mut Frame, app: &App, area: Rect) {
             ViewMode::Timeline => "No tasks or notes.",
             ViewMode::Journal => "Journal is empty.",
             ViewMode::Archive => "Archive is empty.",
+            ViewMode::Calendar | ViewMode::Stats => "",
+            ViewMode::Today => "Nothing overdue, due today, in progress, or starred.",
         };
         let paragraph = Paragraph::new(empty_msg)
             .style(app.theme.muted)
@@ -111,13 +160,44 @@ fn render_content(frame: &mut Frame, app: &App, area: Rect) {
         ViewMode::Board => render_board_view(frame, app, inner),
         ViewMode::Timeline | ViewMode::Archive => render_timeline_view(frame, app, inner),
         ViewMode::Journal => render_journal_view(frame, app, inner),
+        ViewMode::Calendar => render_calendar_view(frame, app, inner),
+        ViewMode::Today => render_today_view(frame, app, inner),
+        ViewMode::Stats => render_stats_view(frame, app, inner),
     }
 }
 
 fn render_popup(frame: &mut Frame, app: &App, popup: &PopupState) {
     match popup {
         PopupState::Help { scroll } => render_help_popup(frame, app, *scroll),
+        PopupState::Activity { id, scroll, link } => {
+            render_activity_popup(frame, app, *id, *scroll, *link)
+        }
+        PopupState::Settings { selected } => render_settings_popup(frame, app, *selected),
+        PopupState::Theme { selected } => render_theme_picker(frame, app, *selected),
+    }
+}
+
+/// Scrollbar on the right border of a popup whose `lines` of text are
+/// scrolled down by `scroll`, when they don't all fit
+pub fn render_scrollbar(frame: &mut Frame, app: &App, area: Rect, lines: usize, scroll: u16) {
+    let visible = area.height.saturating_sub(2) as usize;
+    if lines <= visible {
+        return;
     }
+    let mut state = ScrollbarState::new(lines.saturating_sub(visible)).position(scroll as usize);
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .track_symbol(None)
+        .thumb_style(app.theme.scrollbar);
+    frame.render_stateful_widget(
+        scrollbar,
+        area.inner(Margin {
+            vertical: 1,
+            horizontal: 0,
+        }),
+        &mut state,
+    );
 }
 
 /// Helper function to create a centered rect
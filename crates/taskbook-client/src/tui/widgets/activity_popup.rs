@@ -0,0 +1,184 @@
+use chrono::{Local, TimeZone};
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::attachments;
+use crate::relative_time;
+use crate::tui::app::App;
+use crate::tui::ui::{centered_rect, render_scrollbar};
+use crate::wiki;
+
+/// A note's body and the notes linking to it, an item's attachments and
+/// links, a task's comments, then what happened to the item, newest first
+/// (`tb --history` without the versions kept by the sync server). `link`
+/// is the linked note Enter jumps to.
+pub fn render_activity_popup(frame: &mut Frame, app: &App, id: u64, scroll: u16, link: usize) {
+    let Some(item) = app.items.get(&id.to_string()) else {
+        return;
+    };
+    let area = centered_rect(64, 20, frame.area());
+
+    let block = Block::default()
+        .title(format!(" Item {} ", id))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border)
+        .style(app.theme.popup);
+
+    let title_style = app.theme.title;
+
+    let mut text = vec![Line::from("")];
+    text.push(Line::from(Span::styled(
+        format!("  {}", item.description()),
+        title_style,
+    )));
+    text.push(Line::from(""));
+
+    let now = Local::now();
+    let section_style = app.theme.title;
+    // `[[Note]]` links in the body, and the notes linking here; the one
+    // Enter jumps to is highlighted
+    let targets = wiki::jump_targets(item, &app.items);
+    let jump_to = targets.get(link % targets.len().max(1)).copied();
+    let link_style = |target: Option<u64>| match target {
+        Some(target) if Some(target) == jump_to => app
+            .theme
+            .info
+            .add_modifier(Modifier::UNDERLINED | Modifier::REVERSED),
+        Some(_) => app.theme.info.add_modifier(Modifier::UNDERLINED),
+        None => app.theme.muted,
+    };
+    let body = item.note_body().unwrap_or_default();
+    for line in body.lines() {
+        let mut spans = vec![Span::raw("  ")];
+        let mut rest = 0;
+        for (range, title) in wiki::links(line) {
+            spans.push(Span::raw(line[rest..range.start].to_string()));
+            let target = wiki::find_note(title, &app.items).map(|note| note.id());
+            spans.push(Span::styled(
+                line[range.clone()].to_string(),
+                link_style(target),
+            ));
+            rest = range.end;
+        }
+        spans.push(Span::raw(line[rest..].to_string()));
+        text.push(Line::from(spans));
+    }
+    if !body.is_empty() {
+        text.push(Line::from(""));
+    }
+    let backlinks = wiki::backlinks(item, &app.items);
+    if !backlinks.is_empty() {
+        text.push(Line::from(Span::styled("  Linked from", section_style)));
+        for note in &backlinks {
+            text.push(Line::from(vec![
+                Span::styled(format!("  {}. ", note.id()), app.theme.muted),
+                Span::styled(note.description().to_string(), link_style(Some(note.id()))),
+            ]));
+        }
+        text.push(Line::from(""));
+    }
+
+    // Numbered as `/open` and the number keys open them
+    let openable = attachments::openable(item);
+    let has_sections = !body.is_empty()
+        || !backlinks.is_empty()
+        || !openable.is_empty()
+        || item.as_task().is_some_and(|t| !t.comments.is_empty());
+    let attached = item.attachments().len();
+    for (i, target) in openable.iter().enumerate() {
+        if i == 0 || i == attached {
+            if i > 0 {
+                text.push(Line::from(""));
+            }
+            let section = if i < attached {
+                "  Attachments"
+            } else {
+                "  Links"
+            };
+            text.push(Line::from(Span::styled(section, section_style)));
+        }
+        let style = if attachments::is_url(target) {
+            Style::default().add_modifier(Modifier::UNDERLINED)
+        } else {
+            Style::default()
+        };
+        text.push(Line::from(vec![
+            Span::styled(format!("  {}. ", i + 1), app.theme.muted),
+            Span::styled(target.to_string(), style),
+        ]));
+    }
+    if !openable.is_empty() {
+        text.push(Line::from(""));
+    }
+
+    if let Some(task) = item.as_task().filter(|t| !t.comments.is_empty()) {
+        text.push(Line::from(Span::styled("  Comments", section_style)));
+        for comment in &task.comments {
+            text.push(Line::from(vec![
+                Span::styled(format!("  {}", comment.author), app.theme.info),
+                Span::styled(
+                    format!("  {}", relative_time::format(comment.timestamp, now)),
+                    app.theme.muted,
+                ),
+            ]));
+            for line in comment.text.lines() {
+                text.push(Line::from(format!("    {}", line)));
+            }
+        }
+        text.push(Line::from(""));
+    }
+    if has_sections {
+        text.push(Line::from(Span::styled("  Activity", section_style)));
+    }
+
+    for entry in item.activity().iter().rev() {
+        let at = Local
+            .timestamp_millis_opt(entry.at)
+            .single()
+            .map(|dt| {
+                format!(
+                    "{} {}",
+                    app.config.format_date(dt.date_naive()),
+                    app.config.format_time(dt.time())
+                )
+            })
+            .unwrap_or_default();
+        text.push(Line::from(vec![
+            Span::styled(format!("  {}  ", at), app.theme.muted),
+            Span::styled(entry.action.clone(), app.theme.info),
+            Span::styled(
+                format!("  {}", relative_time::format(entry.at, now)),
+                app.theme.muted,
+            ),
+        ]));
+    }
+    if item.activity().is_empty() {
+        text.push(Line::from(Span::styled(
+            "  No activity recorded yet.",
+            app.theme.muted,
+        )));
+    }
+
+    text.push(Line::from(""));
+    let mut hint = String::from("  j/k to scroll");
+    if !openable.is_empty() {
+        hint.push_str(" · 1-9 to open");
+    }
+    if !targets.is_empty() {
+        hint.push_str(" · Tab/Enter to follow a link");
+    }
+    hint.push_str(" · any other key to close");
+    text.push(Line::from(Span::styled(hint, app.theme.muted)));
+
+    let lines = text.len();
+    frame.render_widget(Clear, area);
+    frame.render_widget(block.clone(), area);
+    let inner = block.inner(area);
+    let paragraph = Paragraph::new(text).scroll((scroll, 0));
+    frame.render_widget(paragraph, inner);
+    render_scrollbar(frame, app, area, lines, scroll);
+}
@@ -0,0 +1,78 @@
+use ratatui::{
+    layout::Rect,
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::layout;
+use crate::tui::app::App;
+use taskbook_common::board;
+
+/// Narrowest and widest the board picker gets
+const MIN_WIDTH: u16 = 16;
+const MAX_WIDTH: u16 = 32;
+
+/// Columns the board picker takes: wide enough for the longest board name
+/// and its count, within limits, and never most of `available`
+pub fn sidebar_width(app: &App, available: u16) -> u16 {
+    let longest = app
+        .boards
+        .iter()
+        .map(|name| board::display_name(name).width())
+        .max()
+        .unwrap_or(0);
+    // Marker, name, gap, count, border
+    let wanted = (longest + 10).clamp(MIN_WIDTH as usize, MAX_WIDTH as usize) as u16;
+    wanted.min(available / 3)
+}
+
+/// Every board with the number of items it shows; the row of the board
+/// being filtered by is highlighted, and selected while the picker has focus
+pub fn render_board_sidebar(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::RIGHT)
+        .border_style(app.theme.border);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let picked = app.picked_board();
+    let width = inner.width as usize;
+    let mut lines: Vec<Line> = Vec::new();
+    for (i, (name, count)) in app.board_counts().into_iter().enumerate() {
+        let label = match name {
+            Some(name) => board::display_name(&name),
+            None => "All boards".to_string(),
+        };
+        let count = count.to_string();
+        let marker = if i == picked && app.sidebar.focused {
+            ">"
+        } else {
+            " "
+        };
+        let label_width = width.saturating_sub(count.len() + 3);
+        let label = layout::truncate(&label, label_width);
+        let gap = label_width.saturating_sub(label.width()) + 1;
+
+        let mut line = Line::from(vec![
+            Span::styled(marker, app.theme.info),
+            Span::styled(label, app.theme.board_name),
+            Span::raw(" ".repeat(gap)),
+            Span::styled(count, app.theme.muted),
+        ]);
+        if i == picked {
+            line = line.style(if app.sidebar.focused {
+                app.theme.selected
+            } else {
+                app.theme.info.add_modifier(Modifier::BOLD)
+            });
+        }
+        lines.push(line);
+    }
+
+    // Keep the picked board in view
+    let scroll = (picked + 1).saturating_sub(inner.height as usize);
+    frame.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), inner);
+}
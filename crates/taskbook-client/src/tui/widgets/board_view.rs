@@ -4,7 +4,7 @@ use ratatui::{
     Frame,
 };
 
-use crate::tui::app::{sort_items_by, App};
+use crate::tui::app::{sort_items_by, App, ListRow};
 use taskbook_common::board;
 use taskbook_common::StorageItem;
 
@@ -13,8 +13,8 @@ use super::render_scrollable_list;
 
 pub fn render_board_view(frame: &mut Frame, app: &App, area: Rect) {
     let mut lines: Vec<Line> = Vec::new();
-    let mut item_line_map: Vec<Option<u64>> = Vec::new();
-    let row_options = ItemRowOptions::for_board_view();
+    let mut item_line_map: Vec<Option<ListRow>> = Vec::new();
+    let row_options = ItemRowOptions::for_board_view().with_density(app.config.display_density);
 
     // Determine which boards to show (respect filter)
     let boards_to_show: Vec<&String> = if let Some(ref filter_board) = app.filter.board_filter {
@@ -65,12 +65,48 @@ pub fn render_board_view(frame: &mut Frame, app: &App, area: Rect) {
         }
         first_group = false;
 
-        let stats_text = if total_tasks > 0 {
+        let sort_method = app.config.sort_method_for(board);
+        let mut stats_text = if total_tasks > 0 {
             format!(" [{}/{}]", complete_tasks, total_tasks)
         } else {
             String::new()
         };
+        if sort_method != app.sort_method {
+            stats_text.push_str(&format!(" [Sort: {}]", sort_method.display_name()));
+        }
+        if let Some(shared) = app.shared_board(board) {
+            let access = if shared.can_write { "" } else { ", read-only" };
+            if shared.is_owner {
+                stats_text.push_str(" [shared]");
+            } else {
+                stats_text.push_str(&format!(" [shared by {}{access}]", shared.owner));
+            }
+        }
         let display = board::display_name(board);
+
+        // A collapsed board is just its header, which takes the selection
+        if app.is_collapsed(board) {
+            let row = ListRow::Board(board.clone());
+            let is_selected = app.selected_row() == Some(&row);
+            stats_text.push_str(&format!(" ({} hidden)", visible_items.len()));
+            let marker = if is_selected {
+                Span::styled("> ", app.theme.info)
+            } else {
+                Span::raw("  ")
+            };
+            let mut line = Line::from(vec![
+                marker,
+                Span::styled(display, app.theme.board_name),
+                Span::styled(stats_text, app.theme.muted),
+            ]);
+            if is_selected {
+                line = line.style(app.theme.selected);
+            }
+            lines.push(line);
+            item_line_map.push(Some(row));
+            continue;
+        }
+
         lines.push(Line::from(vec![
             Span::raw("  "),
             Span::styled(display, app.theme.board_name),
@@ -80,15 +116,22 @@ pub fn render_board_view(frame: &mut Frame, app: &App, area: Rect) {
 
         // Sort items using configured method
         let mut sorted_items = visible_items;
-        sort_items_by(&mut sorted_items, app.sort_method);
+        sort_items_by(&mut sorted_items, sort_method);
 
         for item in sorted_items {
             let is_selected = app.selected_id() == Some(item.id());
             let line = render_item_line(app, item, is_selected, &row_options);
             lines.push(line);
-            item_line_map.push(Some(item.id()));
+            item_line_map.push(Some(ListRow::Item(item.id())));
         }
     }
 
-    render_scrollable_list(frame, area, lines, &item_line_map, app.selected_id());
+    render_scrollable_list(
+        frame,
+        area,
+        lines,
+        &item_line_map,
+        app.selected_row(),
+        &app.list_scroll,
+    );
 }
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Months, NaiveDate};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::tui::app::{App, ListRow};
+
+use super::item_row::{render_item_line, ItemRowOptions};
+use super::render_scrollable_list;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Per-day counts shown in a calendar cell
+#[derive(Default)]
+struct DayCounts {
+    due: usize,
+    overdue: bool,
+    created: usize,
+    completed: usize,
+}
+
+pub fn render_calendar_view(frame: &mut Frame, app: &App, area: Rect) {
+    let day = app.calendar.day;
+    let today = chrono::Local::now().date_naive();
+    let first = day.with_day(1).unwrap_or(day);
+    let days_in_month = first
+        .checked_add_months(Months::new(1))
+        .map(|next| (next - first).num_days() as u32)
+        .unwrap_or(31);
+    let offset = first.weekday().num_days_from_monday();
+    let weeks = (offset + days_in_month).div_ceil(7);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2 + weeks as u16 * 2), Constraint::Min(0)])
+        .split(area);
+
+    // Due, created, and completed counts (completed among items created that
+    // day, as in the timeline date headers)
+    let mut counts: HashMap<NaiveDate, DayCounts> = HashMap::new();
+    for item in app.items.values().filter(|item| app.should_show_item(item)) {
+        let complete = item.as_task().is_some_and(|t| t.is_complete);
+        if let Some(due) = item.due_date() {
+            let entry = counts.entry(due).or_default();
+            entry.due += 1;
+            entry.overdue |= due < today && !complete;
+        }
+        if let Some(created) = item.created_date() {
+            let entry = counts.entry(created).or_default();
+            entry.created += 1;
+            if complete {
+                entry.completed += 1;
+            }
+        }
+    }
+
+    let cell_width = (chunks[0].width / 7).max(4) as usize;
+    let mut lines: Vec<Line> = Vec::new();
+
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!("  {}", first.format("%B %Y")),
+            app.theme.header.add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("   d due · c created · {} completed", app.icons.complete),
+            app.theme.muted,
+        ),
+    ]));
+
+    let mut weekday_spans = Vec::new();
+    for name in WEEKDAYS {
+        weekday_spans.push(Span::styled(pad(name, cell_width), app.theme.muted));
+    }
+    lines.push(Line::from(weekday_spans));
+
+    for week in 0..weeks {
+        let mut number_spans = Vec::new();
+        let mut count_spans = Vec::new();
+
+        for weekday in 0..7 {
+            let cell = week * 7 + weekday;
+            if cell < offset || cell - offset >= days_in_month {
+                number_spans.push(Span::raw(" ".repeat(cell_width)));
+                count_spans.push(Span::raw(" ".repeat(cell_width)));
+                continue;
+            }
+            let date = first + chrono::Duration::days((cell - offset) as i64);
+
+            let number_style = if date == day {
+                app.theme.selected.add_modifier(Modifier::BOLD)
+            } else if date == today {
+                app.theme.header.add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            number_spans.push(Span::styled(
+                pad(&date.day().to_string(), cell_width),
+                number_style,
+            ));
+
+            let mut used = 1;
+            count_spans.push(Span::raw(" "));
+            if let Some(day_counts) = counts.get(&date) {
+                let parts = [
+                    (
+                        day_counts.due,
+                        "d",
+                        if day_counts.overdue {
+                            app.theme.error
+                        } else {
+                            app.theme.warning
+                        },
+                    ),
+                    (day_counts.created, "c", app.theme.info),
+                    (
+                        day_counts.completed,
+                        app.icons.complete.as_str(),
+                        app.theme.success,
+                    ),
+                ];
+                for (count, suffix, style) in parts {
+                    if count == 0 {
+                        continue;
+                    }
+                    let text = format!("{}{} ", count, suffix);
+                    used += text.chars().count();
+                    count_spans.push(Span::styled(text, style));
+                }
+            }
+            count_spans.push(Span::raw(" ".repeat(cell_width.saturating_sub(used))));
+        }
+
+        lines.push(Line::from(number_spans));
+        lines.push(Line::from(count_spans));
+    }
+
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+    render_day_items(frame, app, chunks[1]);
+}
+
+/// List the selected day's items below the month grid
+fn render_day_items(frame: &mut Frame, app: &App, area: Rect) {
+    let row_options = ItemRowOptions::for_timeline_view().with_density(app.config.display_density);
+    let focused = app.calendar.focus_items;
+    let selected_id = if focused { app.selected_id() } else { None };
+
+    let mut lines: Vec<Line> = vec![Line::from(Span::styled(
+        format!(
+            "  {} [{}]",
+            app.config.format_date(app.calendar.day),
+            app.display_order.len()
+        ),
+        app.theme.header,
+    ))];
+    let mut item_line_map: Vec<Option<ListRow>> = vec![None];
+
+    if app.display_order.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No items due or created on this day.",
+            app.theme.muted,
+        )));
+        item_line_map.push(None);
+    }
+
+    for id in app.display_order.iter().filter_map(ListRow::item_id) {
+        if let Some(item) = app.items.get(&id.to_string()) {
+            let is_selected = selected_id == Some(item.id());
+            lines.push(render_item_line(app, item, is_selected, &row_options));
+            item_line_map.push(Some(ListRow::Item(item.id())));
+        }
+    }
+
+    render_scrollable_list(
+        frame,
+        area,
+        lines,
+        &item_line_map,
+        selected_id.map(ListRow::Item).as_ref(),
+        &app.list_scroll,
+    );
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!(" {:<width$}", text, width = width.saturating_sub(1))
+}
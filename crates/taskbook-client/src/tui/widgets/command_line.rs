@@ -1,12 +1,13 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Clear, Paragraph},
     Frame,
 };
 
-use crate::tui::app::{App, PendingAction, SuggestionKind};
+use crate::tui::app::{App, HistorySearch, PendingAction, SuggestionKind};
+use taskbook_common::board;
 
 /// Render the command line at the bottom of the screen
 pub fn render_command_line(frame: &mut Frame, app: &App, area: Rect) {
@@ -16,7 +17,11 @@ pub fn render_command_line(frame: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
-    if app.command_line.focused {
+    if let Some(ref edit) = app.inline_edit {
+        render_inline_edit_hint(frame, app, area, edit.id);
+    } else if let Some(ref search) = app.command_line.history_search {
+        render_history_search(frame, app, area, search);
+    } else if app.command_line.focused {
         render_input(frame, app, area);
     } else {
         render_placeholder(frame, app, area);
@@ -24,9 +29,9 @@ pub fn render_command_line(frame: &mut Frame, app: &App, area: Rect) {
 }
 
 /// Render the unfocused command line with placeholder text
-fn render_placeholder(frame: &mut Frame, _app: &App, area: Rect) {
-    let prompt_style = Style::default().fg(Color::Rgb(80, 80, 100));
-    let placeholder_style = Style::default().fg(Color::Rgb(100, 100, 120));
+fn render_placeholder(frame: &mut Frame, app: &App, area: Rect) {
+    let prompt_style = app.theme.border;
+    let placeholder_style = app.theme.muted;
 
     let line = Line::from(vec![
         Span::styled("  > ", prompt_style),
@@ -36,6 +41,23 @@ fn render_placeholder(frame: &mut Frame, _app: &App, area: Rect) {
     frame.render_widget(Paragraph::new(line), area);
 }
 
+/// What the keys do while a description is edited in its row
+fn render_inline_edit_hint(frame: &mut Frame, app: &App, area: Rect, id: u64) {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let line = Line::from(vec![
+        Span::styled(
+            format!("  Editing item {}  ", id),
+            app.theme.info.add_modifier(Modifier::BOLD),
+        ),
+        Span::styled("[Enter]", bold),
+        Span::raw(" Save  "),
+        Span::styled("[Esc]", bold),
+        Span::raw(" Cancel"),
+    ]);
+
+    frame.render_widget(Paragraph::new(line), area);
+}
+
 /// Render the active input line
 fn render_input(frame: &mut Frame, app: &App, area: Rect) {
     let input = &app.command_line.input;
@@ -66,7 +88,7 @@ fn render_input(frame: &mut Frame, app: &App, area: Rect) {
     let cursor_char = after_chars.first().copied().unwrap_or(' ');
     let after: String = after_chars.iter().skip(1).collect();
 
-    let cursor_style = Style::default().bg(Color::White).fg(Color::Black);
+    let cursor_style = app.theme.cursor;
     let prompt_style = app.theme.info.add_modifier(Modifier::BOLD);
 
     let line = Line::from(vec![
@@ -79,6 +101,27 @@ fn render_input(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(Paragraph::new(line), area);
 }
 
+/// Render a Ctrl+R history search: what is typed, and the command found
+fn render_history_search(frame: &mut Frame, app: &App, area: Rect, search: &HistorySearch) {
+    let prompt_style = app.theme.info.add_modifier(Modifier::BOLD);
+    let mut spans = vec![
+        Span::styled("  history ", prompt_style),
+        Span::raw(search.query.clone()),
+        Span::styled(" ", app.theme.cursor),
+        Span::raw("  "),
+    ];
+    match search.found.and_then(|i| app.command_history.get(i)) {
+        Some(command) => spans.push(Span::styled(command.clone(), app.theme.text)),
+        None if search.query.is_empty() => spans.push(Span::styled(
+            "type to search, Ctrl+R for older, Enter to run, Tab to edit",
+            app.theme.muted,
+        )),
+        None => spans.push(Span::styled("no match", app.theme.muted)),
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
 /// Render inline confirmation prompt
 fn render_confirm(frame: &mut Frame, app: &App, area: Rect, action: &PendingAction) {
     let message = match action {
@@ -89,20 +132,29 @@ fn render_confirm(frame: &mut Frame, app: &App, area: Rect, action: &PendingActi
                 format!("Delete {} items?", ids.len())
             }
         }
-        PendingAction::Clear => "Clear all completed tasks?".to_string(),
+        PendingAction::Clear { board: None } => "Clear all completed tasks?".to_string(),
+        PendingAction::Clear { board: Some(board) } => {
+            format!("Clear completed tasks on {}?", board::display_name(board))
+        }
     };
 
     let bold = Style::default().add_modifier(Modifier::BOLD);
 
-    let line = Line::from(vec![
+    let mut spans = vec![
         Span::raw("  "),
         Span::styled(&message, app.theme.warning),
         Span::raw("  "),
-        Span::styled("[Enter]", bold),
+        Span::styled("[y/Enter]", bold),
         Span::raw(" Confirm  "),
-        Span::styled("[Esc]", bold),
+        Span::styled("[n/Esc]", bold),
         Span::raw(" Cancel"),
-    ]);
+    ];
+    if matches!(action, PendingAction::Delete { ids } if ids.len() == 1) {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled("[a]", bold));
+        spans.push(Span::raw(" Don't ask again"));
+    }
+    let line = Line::from(spans);
 
     frame.render_widget(Paragraph::new(line), area);
 }
@@ -127,16 +179,14 @@ pub fn render_autocomplete(frame: &mut Frame, app: &App, content_area: Rect) {
     // Clear the area behind the dropdown
     frame.render_widget(Clear, dropdown_area);
 
-    let autocomplete_bg = Style::default().bg(Color::Rgb(40, 40, 55));
-    let autocomplete_selected = Style::default()
-        .bg(Color::Rgb(60, 60, 90))
-        .add_modifier(Modifier::BOLD);
-    let autocomplete_hint = Style::default()
-        .fg(Color::Rgb(120, 120, 140))
-        .bg(Color::Rgb(40, 40, 55));
-    let autocomplete_hint_selected = Style::default()
-        .fg(Color::Rgb(150, 150, 170))
-        .bg(Color::Rgb(60, 60, 90));
+    let autocomplete_bg = app.theme.menu;
+    let autocomplete_selected = app.theme.menu_selected;
+    let autocomplete_hint = app.theme.menu.patch(app.theme.muted);
+    let autocomplete_hint_selected = app
+        .theme
+        .menu_selected
+        .patch(app.theme.muted)
+        .remove_modifier(Modifier::BOLD);
 
     let selected = app.command_line.selected_suggestion;
 
@@ -158,6 +208,7 @@ pub fn render_autocomplete(frame: &mut Frame, app: &App, content_area: Rect) {
             SuggestionKind::Command => "/",
             SuggestionKind::Board => "@",
             SuggestionKind::Item => "·",
+            SuggestionKind::Tag => "+",
         };
 
         let mut spans = vec![
@@ -1,12 +1,12 @@
 use ratatui::{
-    style::{Color, Modifier, Style},
+    style::Modifier,
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 
 use crate::tui::app::App;
-use crate::tui::ui::centered_rect;
+use crate::tui::ui::{centered_rect, render_scrollbar};
 
 pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
     let area = centered_rect(58, 42, frame.area());
@@ -15,18 +15,12 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
         .title(" Keybindings & Commands ")
         .borders(Borders::ALL)
         .border_style(app.theme.border)
-        .style(Style::default().bg(Color::Black));
+        .style(app.theme.popup);
 
-    let key_style = Style::default()
-        .fg(Color::Yellow)
-        .add_modifier(Modifier::BOLD);
+    let key_style = app.theme.warning.add_modifier(Modifier::BOLD);
     let desc_style = app.theme.muted;
-    let section_style = Style::default()
-        .fg(Color::White)
-        .add_modifier(Modifier::BOLD);
-    let cmd_style = Style::default()
-        .fg(Color::Cyan)
-        .add_modifier(Modifier::BOLD);
+    let section_style = app.theme.title;
+    let cmd_style = app.theme.info.add_modifier(Modifier::BOLD);
 
     let text = vec![
         Line::from(""),
@@ -47,10 +41,26 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
             Span::styled("    Ctrl+U/D     ", key_style),
             Span::styled("Half-page up/down", desc_style),
         ]),
+        Line::from(vec![
+            Span::styled("    zz zt zb     ", key_style),
+            Span::styled("Scroll selection to center/top/bottom", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    za zM zR     ", key_style),
+            Span::styled("Collapse/expand board, all, none", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    5j 3dd 12G   ", key_style),
+            Span::styled("Count: move 5, delete 3, go to row 12", desc_style),
+        ]),
         Line::from(vec![
             Span::styled("    Enter        ", key_style),
             Span::styled("Filter board / Edit note", desc_style),
         ]),
+        Line::from(vec![
+            Span::styled("    B            ", key_style),
+            Span::styled("Board picker (j/k pick, Enter back to list)", desc_style),
+        ]),
         Line::from(""),
         Line::from(Span::styled("  Quick Actions", section_style)),
         Line::from(vec![
@@ -61,31 +71,74 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
             Span::styled("    b            ", key_style),
             Span::styled("Toggle in-progress", desc_style),
         ]),
+        Line::from(vec![
+            Span::styled("    F            ", key_style),
+            Span::styled("Start/stop focus timer on task", desc_style),
+        ]),
         Line::from(vec![
             Span::styled("    s            ", key_style),
             Span::styled("Toggle star", desc_style),
         ]),
         Line::from(vec![
-            Span::styled("    d            ", key_style),
+            Span::styled("    dd           ", key_style),
             Span::styled("Delete selected (confirm)", desc_style),
         ]),
+        Line::from(vec![
+            Span::styled("    Space        ", key_style),
+            Span::styled("Mark item for a move; open a collapsed board", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    J/K          ", key_style),
+            Span::styled("Move item down/up its board (sorts by hand)", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    u            ", key_style),
+            Span::styled("Undo the last change", desc_style),
+        ]),
         Line::from(vec![
             Span::styled("    y            ", key_style),
             Span::styled("Copy to clipboard", desc_style),
         ]),
+        Line::from(vec![
+            Span::styled("    Y            ", key_style),
+            Span::styled("Copy full note, or task's board as checklist", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    i            ", key_style),
+            Span::styled("Show details; Tab/Enter follow [[note]] links", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    o            ", key_style),
+            Span::styled("Open the item's first attachment or link", desc_style),
+        ]),
         Line::from(vec![
             Span::styled("    r            ", key_style),
             Span::styled("Restore from archive", desc_style),
         ]),
+        Line::from(vec![
+            Span::styled("    e            ", key_style),
+            Span::styled("Edit description in place (Enter saves)", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    E            ", key_style),
+            Span::styled("Edit in external editor", desc_style),
+        ]),
         Line::from(vec![
             Span::styled("    C            ", key_style),
-            Span::styled("Clear all completed (confirm)", desc_style),
+            Span::styled("Clear completed, of filtered board (confirm)", desc_style),
         ]),
         Line::from(""),
         Line::from(Span::styled("  Views & Filters", section_style)),
         Line::from(vec![
-            Span::styled("    1-4          ", key_style),
-            Span::styled("Board / Timeline / Archive / Journal", desc_style),
+            Span::styled("    1-7          ", key_style),
+            Span::styled(
+                "Board/Timeline/Archive/Journal/Calendar/Today/Stats",
+                desc_style,
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("    ←↑↓→ Enter   ", key_style),
+            Span::styled("Calendar: move day, open day's items", desc_style),
         ]),
         Line::from(vec![
             Span::styled("    h            ", key_style),
@@ -95,9 +148,25 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
             Span::styled("    S            ", key_style),
             Span::styled("Cycle sort (ID/Priority/Status)", desc_style),
         ]),
+        Line::from(vec![
+            Span::styled("    S (timeline) ", key_style),
+            Span::styled("Group by day/week/month", desc_style),
+        ]),
         Line::from(vec![
             Span::styled("    Esc          ", key_style),
-            Span::styled("Clear search/filter", desc_style),
+            Span::styled("Clear marks, search or filter", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    n  N         ", key_style),
+            Span::styled("Next/previous search match", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    T            ", key_style),
+            Span::styled("Pick a theme, previewed as you move", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    ,            ", key_style),
+            Span::styled("Settings (theme, sort, view, sync)", desc_style),
         ]),
         Line::from(""),
         Line::from(Span::styled("  Command Line Shortcuts", section_style)),
@@ -111,15 +180,11 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
         ]),
         Line::from(vec![
             Span::styled("    n            ", key_style),
-            Span::styled("→ /note @...", desc_style),
-        ]),
-        Line::from(vec![
-            Span::styled("    e            ", key_style),
-            Span::styled("→ /edit @<id> <desc>", desc_style),
+            Span::styled("→ /note @... (unless searching)", desc_style),
         ]),
         Line::from(vec![
             Span::styled("    m            ", key_style),
-            Span::styled("→ /move @<id> @...", desc_style),
+            Span::styled("→ /move @<id>... @... (marked items)", desc_style),
         ]),
         Line::from(vec![
             Span::styled("    p            ", key_style),
@@ -129,7 +194,7 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
         Line::from(Span::styled("  Slash Commands", section_style)),
         Line::from(vec![
             Span::styled("    /task        ", cmd_style),
-            Span::styled("@board +tag Description p:2", desc_style),
+            Span::styled("@board +tag Description p:2 due:fri", desc_style),
         ]),
         Line::from(vec![
             Span::styled("                 ", cmd_style),
@@ -143,9 +208,21 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
             Span::styled("    /edit        ", cmd_style),
             Span::styled("@<id> New description", desc_style),
         ]),
+        Line::from(vec![
+            Span::styled("    /comment     ", cmd_style),
+            Span::styled("@<id> Text (see them with i)", desc_style),
+        ]),
         Line::from(vec![
             Span::styled("    /move        ", cmd_style),
-            Span::styled("@<id> @board", desc_style),
+            Span::styled("@<id> [@<id>...] @board", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    /moveup      ", cmd_style),
+            Span::styled("[@<id>] Also /movedown; sorts board by hand", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    /due         ", cmd_style),
+            Span::styled("@<id> <when|none>", desc_style),
         ]),
         Line::from(vec![
             Span::styled("    /delete      ", cmd_style),
@@ -153,7 +230,38 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
         ]),
         Line::from(vec![
             Span::styled("    /search      ", cmd_style),
-            Span::styled("<term>", desc_style),
+            Span::styled("<term> Filters as you type", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    /tag         ", cmd_style),
+            Span::styled(
+                "[name] Show only tagged items; @<id> +a -b edits",
+                desc_style,
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("    /filter      ", cmd_style),
+            Span::styled("[name] Apply/clear saved filter", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    /sort        ", cmd_style),
+            Span::styled("[@board id|priority|status|manual|default]", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    /group       ", cmd_style),
+            Span::styled("[day|week|month] Group timeline", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    /settings    ", cmd_style),
+            Span::styled("Change settings; saved as they change", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    /theme       ", cmd_style),
+            Span::styled("[name] Switch theme, or pick one", desc_style),
+        ]),
+        Line::from(vec![
+            Span::styled("    /profile     ", cmd_style),
+            Span::styled("[name] Switch/show profile", desc_style),
         ]),
         Line::from(vec![
             Span::styled("    /tag         ", cmd_style),
@@ -161,7 +269,7 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
         ]),
         Line::from(vec![
             Span::styled("    /clear       ", cmd_style),
-            Span::styled("Clear completed tasks", desc_style),
+            Span::styled("[@board] [--dry-run] Clear completed tasks", desc_style),
         ]),
         Line::from(vec![
             Span::styled("    /rename-board", cmd_style),
@@ -176,6 +284,10 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
             Span::styled("    ↑/↓          ", key_style),
             Span::styled("Navigate suggestions / history", desc_style),
         ]),
+        Line::from(vec![
+            Span::styled("    Ctrl+R       ", key_style),
+            Span::styled("Search history (kept between sessions)", desc_style),
+        ]),
         Line::from(vec![
             Span::styled("    q            ", key_style),
             Span::styled("Quit", desc_style),
@@ -187,9 +299,11 @@ pub fn render_help_popup(frame: &mut Frame, app: &App, scroll: u16) {
         )),
     ];
 
+    let lines = text.len();
     frame.render_widget(Clear, area);
     frame.render_widget(block.clone(), area);
     let inner = block.inner(area);
     let paragraph = Paragraph::new(text).scroll((scroll, 0));
     frame.render_widget(paragraph, inner);
+    render_scrollbar(frame, app, area, lines, scroll);
 }
@@ -1,16 +1,25 @@
+use std::ops::Range;
+
 use ratatui::{
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
 };
 
+use crate::config::DisplayDensity;
+use crate::copy;
+use crate::highlight;
+use crate::relative_time;
 use crate::tui::app::App;
 use taskbook_common::board;
+use taskbook_common::due;
 use taskbook_common::StorageItem;
 
 /// Options for rendering an item row
 pub struct ItemRowOptions {
     pub show_boards: bool,
     pub show_age: bool,
+    /// "2 hours ago" creation time, if `relativeTimestamps` is on
+    pub show_relative_time: bool,
 }
 
 impl ItemRowOptions {
@@ -18,6 +27,7 @@ impl ItemRowOptions {
         Self {
             show_boards: false,
             show_age: true,
+            show_relative_time: false,
         }
     }
 
@@ -25,6 +35,25 @@ impl ItemRowOptions {
         Self {
             show_boards: true,
             show_age: false,
+            show_relative_time: true,
+        }
+    }
+
+    /// These options, with more or less shown for the `displayDensity`
+    /// setting
+    pub fn with_density(self, density: DisplayDensity) -> Self {
+        match density {
+            DisplayDensity::Compact => Self {
+                show_boards: false,
+                show_age: false,
+                show_relative_time: false,
+            },
+            DisplayDensity::Normal => self,
+            DisplayDensity::Detailed => Self {
+                show_boards: true,
+                show_age: true,
+                show_relative_time: true,
+            },
         }
     }
 }
@@ -38,29 +67,34 @@ pub fn render_item_line(
 ) -> Line<'static> {
     let mut spans: Vec<Span> = Vec::new();
 
-    // Selection indicator + Item ID
+    // Mark + selection indicator + Item ID
+    if app.marked.contains(&item.id()) {
+        spans.push(Span::styled("*", app.theme.warning));
+    } else {
+        spans.push(Span::raw(" "));
+    }
     if is_selected {
-        spans.push(Span::styled(format!(" >{} ", item.id()), app.theme.info));
+        spans.push(Span::styled(format!(">{} ", item.id()), app.theme.info));
     } else {
-        spans.push(Span::styled(format!("  {} ", item.id()), app.theme.item_id));
+        spans.push(Span::styled(format!(" {} ", item.id()), app.theme.item_id));
     }
 
     // Icon
     let (icon, icon_style) = if let Some(task) = item.as_task() {
         if task.is_complete {
-            ("✔", app.theme.success)
+            (&app.icons.complete, app.theme.success)
         } else if task.in_progress {
-            ("…", app.theme.warning)
+            (&app.icons.in_progress, app.theme.warning)
         } else {
-            ("☐", app.theme.pending)
+            (&app.icons.pending, app.theme.pending)
         }
     } else {
-        ("●", app.theme.info)
+        (&app.icons.note, app.theme.info)
     };
     spans.push(Span::styled(format!("{} ", icon), icon_style));
 
     // Description
-    let desc = item.description().to_string();
+    let desc = item.description();
     let desc_style = if let Some(task) = item.as_task() {
         if task.is_complete {
             app.theme.completed_text
@@ -69,28 +103,55 @@ pub fn render_item_line(
         } else if task.priority == 2 {
             app.theme.warning
         } else {
-            Style::default().fg(Color::White)
+            app.theme.text
         }
     } else {
-        Style::default().fg(Color::Rgb(200, 200, 220))
+        app.theme.note_text
     };
-    spans.push(Span::styled(desc, desc_style));
+    match app.inline_edit {
+        Some(ref edit) if edit.id == item.id() => {
+            push_edit_input(app, &mut spans, &edit.input, edit.cursor);
+        }
+        _ => push_description(app, &mut spans, desc, desc_style),
+    }
 
     // Note body indicator
     if item.note_has_body() {
         spans.push(Span::styled(" [...]", app.theme.muted));
     }
 
-    // Tags
-    let tags = item.tags();
-    if !tags.is_empty() {
-        spans.push(Span::raw(" "));
-        for (i, tag) in tags.iter().enumerate() {
-            if i > 0 {
-                spans.push(Span::raw(" "));
-            }
-            spans.push(Span::styled(format!("+{}", tag), app.theme.info));
+    // Comment count
+    if let Some(task) = item.as_task().filter(|t| !t.comments.is_empty()) {
+        spans.push(Span::styled(
+            format!(" {}{}", app.icons.comments, task.comments.len()),
+            app.theme.muted,
+        ));
+    }
+
+    // Attachment count
+    if !item.attachments().is_empty() {
+        spans.push(Span::styled(
+            format!(" {}{}", app.icons.attachments, item.attachments().len()),
+            app.theme.muted,
+        ));
+    }
+
+    // Tags, as chips; the one filtered by stands out
+    for tag in item.tags() {
+        let mut style = app.theme.tag;
+        if app
+            .filter
+            .tag_filter
+            .as_ref()
+            .is_some_and(|filter| filter.eq_ignore_ascii_case(tag))
+        {
+            style = style.add_modifier(Modifier::BOLD);
         }
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!(" {} ", board::display_tag(tag)),
+            style,
+        ));
     }
 
     // Boards (for timeline view)
@@ -117,9 +178,41 @@ pub fn render_item_line(
         }
     }
 
+    // Due date (hidden once a task is complete)
+    if let Some(task) = item.as_task().filter(|t| !t.is_complete) {
+        if let Some(date) = task.due {
+            let today = chrono::Local::now().date_naive();
+            let due_style = if date < today {
+                app.theme.error
+            } else if date == today {
+                app.theme.warning
+            } else {
+                app.theme.muted
+            };
+            spans.push(Span::styled(
+                format!(" {}", due::format_due(date, today)),
+                due_style,
+            ));
+        }
+    }
+
+    // Time logged in focus sessions
+    if let Some(task) = item.as_task() {
+        let minutes = task.focused_seconds() / 60;
+        if minutes > 0 {
+            spans.push(Span::styled(
+                format!(" {} {}h{:02}m", app.icons.timer, minutes / 60, minutes % 60),
+                app.theme.muted,
+            ));
+        }
+    }
+
     // Star
     if item.is_starred() {
-        spans.push(Span::styled(" ★", app.theme.starred));
+        spans.push(Span::styled(
+            format!(" {}", app.icons.star),
+            app.theme.starred,
+        ));
     }
 
     // Age (for board view)
@@ -131,6 +224,14 @@ pub fn render_item_line(
         }
     }
 
+    if options.show_relative_time && app.config.relative_timestamps {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            relative_time::format(item.timestamp(), chrono::Local::now()),
+            app.theme.muted,
+        ));
+    }
+
     let mut line = Line::from(spans);
     if is_selected {
         line = line.style(app.theme.selected);
@@ -155,3 +256,59 @@ fn calculate_age(timestamp: i64) -> String {
         format!("{}y", days / 365)
     }
 }
+
+/// A description being edited, with the cursor shown on the character it
+/// is before
+fn push_edit_input(app: &App, spans: &mut Vec<Span<'static>>, input: &str, cursor: usize) {
+    let before: String = input.chars().take(cursor).collect();
+    let mut after = input.chars().skip(cursor);
+    let at = after.next().unwrap_or(' ');
+    spans.push(Span::styled(before, app.theme.text));
+    spans.push(Span::styled(at.to_string(), app.theme.cursor));
+    spans.push(Span::styled(after.collect::<String>(), app.theme.text));
+}
+
+/// `text` in `style`, with the web addresses in it underlined and the
+/// matches of the search term highlighted
+pub fn push_description(app: &App, spans: &mut Vec<Span<'static>>, text: &str, style: Style) {
+    let urls: Vec<Range<usize>> = copy::find_urls(text)
+        .map(|url| {
+            let start = url.as_ptr() as usize - text.as_ptr() as usize;
+            start..start + url.len()
+        })
+        .collect();
+    let matches = match app.filter.search_term {
+        Some(ref term) => highlight::match_ranges(text, &[term]),
+        None => Vec::new(),
+    };
+    if text.is_empty() {
+        spans.push(Span::styled(String::new(), style));
+        return;
+    }
+
+    // Split wherever a link or a match starts or ends
+    let mut bounds: Vec<usize> = urls
+        .iter()
+        .chain(&matches)
+        .flat_map(|range| [range.start, range.end])
+        .chain([0, text.len()])
+        .collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+    for pair in bounds.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let within = |ranges: &[Range<usize>]| {
+            ranges
+                .iter()
+                .any(|range| range.start <= start && end <= range.end)
+        };
+        let mut segment_style = style;
+        if within(&urls) {
+            segment_style = segment_style.add_modifier(Modifier::UNDERLINED);
+        }
+        if within(&matches) {
+            segment_style = segment_style.patch(app.theme.search_match);
+        }
+        spans.push(Span::styled(text[start..end].to_string(), segment_style));
+    }
+}
@@ -1,41 +1,74 @@
 use std::collections::HashMap;
 
-use chrono::{Local, TimeZone};
+use chrono::{Local, NaiveDate, TimeZone};
 use ratatui::{
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::Modifier,
     text::{Line, Span},
     Frame,
 };
 
-use crate::tui::app::App;
+use crate::burndown;
+use crate::relative_time;
+use crate::tui::app::{App, ListRow};
 use taskbook_common::StorageItem;
 
+use super::item_row::push_description;
 use super::render_scrollable_list;
 
+/// When an item shows up in the journal: when a checked task was
+/// completed, otherwise when the item was created
+pub fn journal_timestamp(item: &StorageItem) -> i64 {
+    item.as_task()
+        .filter(|task| task.is_complete)
+        .and_then(|task| task.completed_at)
+        .unwrap_or_else(|| item.timestamp())
+}
+
 pub fn render_journal_view(frame: &mut Frame, app: &App, area: Rect) {
     let mut lines: Vec<Line> = Vec::new();
-    let mut item_line_map: Vec<Option<u64>> = Vec::new();
-
-    // Group items by date
-    let mut grouped: HashMap<String, Vec<&StorageItem>> = HashMap::new();
+    let mut item_line_map: Vec<Option<ListRow>> = Vec::new();
+
+    // Velocity header: tasks completed and still open per day
+    let days = burndown::build(
+        app.items.values(),
+        None,
+        chrono::Local::now().date_naive(),
+        burndown::BURNDOWN_DAYS,
+    );
+    let completed: Vec<usize> = days.iter().map(|d| d.completed).collect();
+    let remaining: Vec<usize> = days.iter().map(|d| d.remaining).collect();
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!("  Last {} days  done ", burndown::BURNDOWN_DAYS),
+            app.theme.muted,
+        ),
+        Span::styled(burndown::sparkline(&completed), app.theme.success),
+        Span::styled(
+            format!(" {}  open ", completed.iter().sum::<usize>()),
+            app.theme.muted,
+        ),
+        Span::styled(burndown::sparkline(&remaining), app.theme.warning),
+        Span::styled(
+            format!(" {}", remaining.last().copied().unwrap_or(0)),
+            app.theme.muted,
+        ),
+    ]));
+    lines.push(Line::from(""));
+    item_line_map.extend([None, None]);
+
+    // Group items by the day they were completed, or created if still open
+    let mut grouped: HashMap<NaiveDate, Vec<&StorageItem>> = HashMap::new();
     for item in app.items.values() {
-        let date = item.date().to_string();
+        let date = local_date(journal_timestamp(item)).unwrap_or_default();
         grouped.entry(date).or_default().push(item);
     }
 
-    // Sort dates (oldest first - chronological for journal)
-    let mut dates: Vec<String> = grouped.keys().cloned().collect();
-    dates.sort_by(|a, b| {
-        let items_a = grouped.get(a).unwrap();
-        let items_b = grouped.get(b).unwrap();
-        // Use the maximum timestamp in the group to represent the group date
-        let ts_a = items_a.iter().map(|i| i.timestamp()).max().unwrap_or(0);
-        let ts_b = items_b.iter().map(|i| i.timestamp()).max().unwrap_or(0);
-        ts_b.cmp(&ts_a) // Newest first
-    });
+    // Sort dates (newest first)
+    let mut dates: Vec<NaiveDate> = grouped.keys().copied().collect();
+    dates.sort_by(|a, b| b.cmp(a));
 
-    let today = chrono::Local::now().format("%a %b %d %Y").to_string();
+    let today = chrono::Local::now().date_naive();
 
     let mut first_group = true;
     for date in dates {
@@ -70,6 +103,7 @@ pub fn render_journal_view(frame: &mut Frame, app: &App, area: Rect) {
         first_group = false;
 
         let is_today = date == today;
+        let date = app.config.format_date(date);
         let date_header = if is_today {
             format!("  {} [Today]", date)
         } else {
@@ -84,22 +118,22 @@ pub fn render_journal_view(frame: &mut Frame, app: &App, area: Rect) {
         lines.push(Line::from(Span::styled(date_header, header_style)));
         item_line_map.push(None);
 
-        // Sort items by timestamp (newest first), then by ID (asc) to match display order
+        // Newest first, then by ID (asc) to match display order
         let mut sorted_items = visible_items;
         sorted_items.sort_by(|a, b| {
-            b.timestamp()
-                .cmp(&a.timestamp())
+            journal_timestamp(b)
+                .cmp(&journal_timestamp(a))
                 .then_with(|| a.id().cmp(&b.id()))
         });
 
         for item in sorted_items {
             let is_selected = app.selected_id() == Some(item.id());
 
-            // Format time
+            // Completion time for checked tasks, creation time otherwise
             let time_str = Local
-                .timestamp_millis_opt(item.timestamp())
+                .timestamp_millis_opt(journal_timestamp(item))
                 .single()
-                .map(|dt| dt.format("%H:%M").to_string())
+                .map(|dt| app.config.format_time(dt.time()))
                 .unwrap_or_else(|| "??:??".to_string());
 
             let time_span = Span::styled(format!("  {} ", time_str), app.theme.muted);
@@ -115,11 +149,11 @@ pub fn render_journal_view(frame: &mut Frame, app: &App, area: Rect) {
                 } else if task.in_progress {
                     app.theme.warning
                 } else {
-                    Style::default().fg(Color::White)
+                    app.theme.text
                 }
             } else {
                 // Note title
-                Style::default().fg(Color::Rgb(200, 200, 220))
+                app.theme.note_text
             };
 
             let mut title_spans = vec![time_span];
@@ -127,22 +161,36 @@ pub fn render_journal_view(frame: &mut Frame, app: &App, area: Rect) {
             // Add icon for tasks
             if let Some(task) = item.as_task() {
                 let (icon, icon_style) = if task.is_complete {
-                    ("✔", app.theme.success)
+                    (&app.icons.complete, app.theme.success)
                 } else if task.in_progress {
-                    ("…", app.theme.warning)
+                    (&app.icons.in_progress, app.theme.warning)
                 } else {
-                    ("☐", app.theme.pending)
+                    (&app.icons.pending, app.theme.pending)
                 };
                 title_spans.push(Span::styled(format!("{} ", icon), icon_style));
             } else {
                 // Note icon
-                title_spans.push(Span::styled("● ", app.theme.info));
+                title_spans.push(Span::styled(format!("{} ", app.icons.note), app.theme.info));
             }
 
-            title_spans.push(Span::styled(item.description().to_string(), desc_style));
+            push_description(app, &mut title_spans, item.description(), desc_style);
+            // Tasks finished on a later day say when they were added
+            let shown_on = local_date(journal_timestamp(item));
+            if let Some(created) = item.created_date().filter(|&d| Some(d) != shown_on) {
+                title_spans.push(Span::styled(
+                    format!(" added {}", app.config.format_date(created)),
+                    app.theme.muted,
+                ));
+            }
+            if app.config.relative_timestamps {
+                title_spans.push(Span::styled(
+                    format!(" {}", relative_time::format(item.timestamp(), Local::now())),
+                    app.theme.muted,
+                ));
+            }
 
             lines.push(Line::from(title_spans));
-            item_line_map.push(Some(item.id()));
+            item_line_map.push(Some(ListRow::Item(item.id())));
 
             // Render body if present (for notes)
             if let Some(note) = item.as_note() {
@@ -158,13 +206,26 @@ pub fn render_journal_view(frame: &mut Frame, app: &App, area: Rect) {
                             Span::raw("        "),
                             Span::styled(line.to_string(), body_style),
                         ]));
-                        item_line_map.push(Some(item.id()));
+                        item_line_map.push(Some(ListRow::Item(item.id())));
                     }
                 }
             }
-
         }
     }
 
-    render_scrollable_list(frame, area, lines, &item_line_map, app.selected_id());
+    render_scrollable_list(
+        frame,
+        area,
+        lines,
+        &item_line_map,
+        app.selected_row(),
+        &app.list_scroll,
+    );
+}
+
+fn local_date(ms: i64) -> Option<NaiveDate> {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map(|dt| dt.date_naive())
 }
@@ -1,10 +1,18 @@
+pub mod activity_popup;
+pub mod board_sidebar;
 pub mod board_view;
+pub mod calendar_view;
 pub mod command_line;
 pub mod help_popup;
 pub mod item_row;
 pub mod journal_view;
+pub mod settings_popup;
+pub mod setup_wizard;
+pub mod stats_view;
 pub mod status_bar;
+pub mod theme_picker;
 pub mod timeline_view;
+pub mod today_view;
 
 use ratatui::{
     layout::Rect,
@@ -13,26 +21,35 @@ use ratatui::{
     Frame,
 };
 
-/// Shared scrollable list renderer used by board, timeline, and journal views.
+use super::app::{ListRow, ListScroll, ScrollAlign};
+
+/// Lines kept in view above and below the selected one when scrolling
+const SCROLL_MARGIN: usize = 2;
+
+/// Shared scrollable list renderer used by board, timeline, journal, and today views.
 pub(crate) fn render_scrollable_list(
     frame: &mut Frame,
     area: Rect,
     lines: Vec<Line<'static>>,
-    item_line_map: &[Option<u64>],
-    selected_id: Option<u64>,
+    item_line_map: &[Option<ListRow>],
+    selected: Option<&ListRow>,
+    scroll: &ListScroll,
 ) {
-    // Fall back to the top of the list when the selected item is not visible
+    // Fall back to the top of the list when the selected row is not visible
     // (e.g., filtered out or nothing selected).
     let selected_line = item_line_map
         .iter()
-        .position(|id| *id == selected_id)
+        .position(|row| row.as_ref() == selected)
         .unwrap_or(0);
 
-    let scroll_offset = if selected_line >= area.height as usize {
-        selected_line.saturating_sub(area.height as usize / 2)
-    } else {
-        0
-    };
+    let scroll_offset = scroll_offset(
+        scroll.offset.get(),
+        scroll.align.take(),
+        selected_line,
+        area.height as usize,
+        lines.len(),
+    );
+    scroll.offset.set(scroll_offset);
 
     let paragraph = Paragraph::new(lines.clone()).scroll((scroll_offset as u16, 0));
     frame.render_widget(paragraph, area);
@@ -45,3 +62,26 @@ pub(crate) fn render_scrollable_list(
         frame.render_stateful_widget(scrollbar, area, &mut scrollbar_state);
     }
 }
+
+/// First line to show of a list `len` lines long in `height` rows, moving on
+/// from `offset` only as far as it takes to keep `selected_line` in view,
+/// unless `align` asks for it somewhere
+fn scroll_offset(
+    offset: usize,
+    align: Option<ScrollAlign>,
+    selected_line: usize,
+    height: usize,
+    len: usize,
+) -> usize {
+    let height = height.max(1);
+    let margin = SCROLL_MARGIN.min(height.saturating_sub(1) / 2);
+    let offset = match align {
+        Some(ScrollAlign::Top) => selected_line.saturating_sub(margin),
+        Some(ScrollAlign::Center) => selected_line.saturating_sub(height / 2),
+        Some(ScrollAlign::Bottom) => (selected_line + margin + 1).saturating_sub(height),
+        None if selected_line < offset + margin => selected_line.saturating_sub(margin),
+        None if selected_line + margin >= offset + height => selected_line + margin + 1 - height,
+        None => offset,
+    };
+    offset.min(len.saturating_sub(height))
+}
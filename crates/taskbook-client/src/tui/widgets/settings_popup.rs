@@ -0,0 +1,68 @@
+use ratatui::{
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::config::Config;
+use crate::tui::app::App;
+use crate::tui::settings::Setting;
+use crate::tui::ui::centered_rect;
+
+/// Settings, changed in place and saved as they change
+pub fn render_settings_popup(frame: &mut Frame, app: &App, selected: usize) {
+    let area = centered_rect(64, 16, frame.area());
+
+    let block = Block::default()
+        .title(" Settings ")
+        .borders(Borders::ALL)
+        .border_style(app.theme.border)
+        .style(app.theme.popup);
+
+    let label_style = app.theme.text;
+    let selected_style = app.theme.info.add_modifier(Modifier::BOLD);
+
+    let mut text = vec![Line::from("")];
+    for (i, setting) in Setting::ALL.iter().enumerate() {
+        let value = setting.value(&app.config);
+        if i == selected {
+            text.push(Line::from(vec![
+                Span::styled(format!("  > {:<17}", setting.label()), selected_style),
+                Span::styled(format!("‹ {} ›", value), selected_style),
+            ]));
+        } else {
+            text.push(Line::from(vec![
+                Span::styled(format!("    {:<17}", setting.label()), label_style),
+                Span::styled(format!("  {}", value), app.theme.muted),
+            ]));
+        }
+    }
+
+    if let Some(setting) = Setting::ALL.get(selected) {
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            format!("  {}", setting.description()),
+            app.theme.muted,
+        )));
+    }
+
+    let path = Config::config_file_path()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(
+        format!("  Saved to {}", path),
+        app.theme.muted,
+    )));
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(
+        "  j/k choose · h/l or Enter change · Esc close",
+        app.theme.muted,
+    )));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block.clone(), area);
+    let inner = block.inner(area);
+    frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), inner);
+}
@@ -0,0 +1,111 @@
+use ratatui::{
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::config::{Config, ThemeColors};
+use crate::tui::app::App;
+use crate::tui::setup::{SetupState, SetupStep, STORAGE_CHOICES, VIEWS};
+use crate::tui::ui::centered_rect;
+
+/// The first-run setup wizard, one question at a time
+pub fn render_setup_wizard(frame: &mut Frame, app: &App, setup: &SetupState) {
+    let area = centered_rect(64, 18, frame.area());
+    let (number, total) = setup.progress();
+
+    let block = Block::default()
+        .title(" Welcome to taskbook ")
+        .title_bottom(format!(" Step {} of {} ", number, total))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border)
+        .style(app.theme.popup);
+
+    let question_style = app.theme.title;
+
+    let (question, explanation) = match setup.step {
+        SetupStep::Theme => ("Pick a theme", "It is shown as you move through the list."),
+        SetupStep::View => (
+            "Which view should taskbook open in?",
+            "Keys 1-7 switch views any time.",
+        ),
+        SetupStep::Sync => (
+            "Where should your items be kept?",
+            "Synced items are end-to-end encrypted on the server.",
+        ),
+        SetupStep::ServerUrl => (
+            "Address of the taskbook server",
+            "Type it, then press Enter.",
+        ),
+        SetupStep::Board => (
+            "Name your first board",
+            "Boards group tasks and notes; a welcome note starts this one.",
+        ),
+    };
+
+    let mut text = vec![
+        Line::from(""),
+        Line::from(Span::styled(format!("  {}", question), question_style)),
+        Line::from(Span::styled(format!("  {}", explanation), app.theme.muted)),
+        Line::from(""),
+    ];
+
+    let choices: Vec<&str> = match setup.step {
+        SetupStep::Theme => ThemeColors::PRESETS.to_vec(),
+        SetupStep::View => VIEWS.iter().map(|(_, label)| *label).collect(),
+        SetupStep::Sync => STORAGE_CHOICES.to_vec(),
+        SetupStep::ServerUrl | SetupStep::Board => Vec::new(),
+    };
+    let chosen = match setup.step {
+        SetupStep::Theme => setup.theme,
+        SetupStep::View => setup.view,
+        SetupStep::Sync => usize::from(setup.sync),
+        SetupStep::ServerUrl | SetupStep::Board => 0,
+    };
+    for (i, choice) in choices.iter().enumerate() {
+        if i == chosen {
+            text.push(Line::from(Span::styled(
+                format!("  > {}", choice),
+                app.theme.info.add_modifier(Modifier::BOLD),
+            )));
+        } else {
+            text.push(Line::from(format!("    {}", choice)));
+        }
+    }
+
+    let input = match setup.step {
+        SetupStep::ServerUrl => Some(&setup.server_url),
+        SetupStep::Board => Some(&setup.board),
+        _ => None,
+    };
+    if let Some(input) = input {
+        text.push(Line::from(vec![
+            Span::styled("  > ", app.theme.info),
+            Span::styled(input.clone(), app.theme.text),
+            Span::styled("_", app.theme.info.add_modifier(Modifier::SLOW_BLINK)),
+        ]));
+    }
+
+    text.push(Line::from(""));
+    let hint = if input.is_some() {
+        "  Enter to continue · Shift+Tab back · Esc to skip setup"
+    } else {
+        "  j/k to choose · Enter to continue · Backspace back · Esc to skip setup"
+    };
+    text.push(Line::from(Span::styled(hint, app.theme.muted)));
+    if setup.step == SetupStep::Board {
+        let path = Config::config_file_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        text.push(Line::from(Span::styled(
+            format!("  Settings are saved to {}", path),
+            app.theme.muted,
+        )));
+    }
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block.clone(), area);
+    let inner = block.inner(area);
+    frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), inner);
+}
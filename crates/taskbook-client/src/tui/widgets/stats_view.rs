@@ -0,0 +1,161 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Gauge, LineGauge, Paragraph, Sparkline},
+    Frame,
+};
+
+use crate::tui::app::{App, DASHBOARD_DAYS};
+use taskbook_common::board;
+
+pub fn render_stats_view(frame: &mut Frame, app: &App, area: Rect) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(6),
+            Constraint::Min(0),
+        ])
+        .split(area);
+
+    render_overall(frame, app, rows[0]);
+    render_completions(frame, app, rows[1]);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(rows[2]);
+
+    render_boards(frame, app, columns[0]);
+    render_priorities(frame, app, columns[1]);
+}
+
+fn section_block<'a>(app: &App, title: String) -> Block<'a> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(app.theme.border)
+        .title(Span::styled(
+            format!(" {} ", title),
+            app.theme.header.add_modifier(Modifier::BOLD),
+        ))
+}
+
+fn render_overall(frame: &mut Frame, app: &App, area: Rect) {
+    let stats = app.get_stats();
+    let total = stats.complete + stats.in_progress + stats.pending;
+    let gauge = Gauge::default()
+        .block(section_block(app, "Overall".to_string()))
+        .gauge_style(app.theme.success)
+        .percent(stats.percent.min(100) as u16)
+        .label(format!(
+            "{}% · {}/{} done · {} in progress · {} notes",
+            stats.percent, stats.complete, total, stats.in_progress, stats.notes
+        ));
+    frame.render_widget(gauge, area);
+}
+
+fn render_completions(frame: &mut Frame, app: &App, area: Rect) {
+    let per_day = &app.get_dashboard().completed_per_day;
+    let total: u64 = per_day.iter().sum();
+    let sparkline = Sparkline::default()
+        .block(section_block(
+            app,
+            format!(
+                "Completed per day, last {} days ({})",
+                DASHBOARD_DAYS, total
+            ),
+        ))
+        .style(app.theme.success)
+        .data(per_day);
+    frame.render_widget(sparkline, area);
+}
+
+fn render_boards(frame: &mut Frame, app: &App, area: Rect) {
+    let block = section_block(app, "Boards".to_string());
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let boards = &app.get_dashboard().boards;
+    if boards.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No tasks yet.").style(app.theme.muted),
+            inner,
+        );
+        return;
+    }
+
+    let label_width = boards
+        .iter()
+        .map(|(name, _, _)| board::display_name(name).chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(inner.width as usize / 2);
+
+    for (row, (name, complete, total)) in boards.iter().enumerate() {
+        if row as u16 >= inner.height {
+            break;
+        }
+        let line_area = Rect {
+            y: inner.y + row as u16,
+            height: 1,
+            ..inner
+        };
+        let ratio = *complete as f64 / (*total).max(1) as f64;
+        let gauge = LineGauge::default()
+            .filled_style(app.theme.success)
+            .unfilled_style(app.theme.muted)
+            .ratio(ratio)
+            .label(Line::from(vec![
+                Span::styled(
+                    format!(
+                        "{:<width$} ",
+                        board::display_name(name),
+                        width = label_width
+                    ),
+                    app.theme.board_name,
+                ),
+                Span::styled(format!("{:>4}/{:<4}", complete, total), app.theme.muted),
+            ]));
+        frame.render_widget(gauge, line_area);
+    }
+}
+
+fn render_priorities(frame: &mut Frame, app: &App, area: Rect) {
+    let dashboard = app.get_dashboard();
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(area);
+
+    let [normal, medium, high] = dashboard.priority_counts;
+    let bars = [
+        Bar::default()
+            .label(Line::from("Normal"))
+            .value(normal)
+            .style(app.theme.pending),
+        Bar::default()
+            .label(Line::from("Medium"))
+            .value(medium)
+            .style(app.theme.warning),
+        Bar::default()
+            .label(Line::from("High"))
+            .value(high)
+            .style(app.theme.error),
+    ];
+    let chart = BarChart::default()
+        .block(section_block(app, "Pending by priority".to_string()))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(8)
+        .bar_gap(2)
+        .value_style(app.theme.title);
+    frame.render_widget(chart, rows[0]);
+
+    let age = match dashboard.average_pending_age {
+        Some(days) => format!("{:.1} days", days),
+        None => "no pending tasks".to_string(),
+    };
+    let paragraph = Paragraph::new(Span::styled(age, app.theme.info))
+        .block(section_block(app, "Average pending age".to_string()));
+    frame.render_widget(paragraph, rows[1]);
+}
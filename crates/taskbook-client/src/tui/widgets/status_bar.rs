@@ -1,5 +1,5 @@
 use ratatui::{
-    style::{Color, Modifier, Style},
+    style::Modifier,
     text::{Line, Span},
     widgets::Paragraph,
     Frame,
@@ -8,6 +8,7 @@ use ratatui::{
 use ratatui::layout::Rect;
 
 use crate::tui::app::{App, StatusKind, ViewMode};
+use crate::tui::focus::format_clock;
 
 /// Render the single-line stats/status bar
 pub fn render_stats_line(frame: &mut Frame, app: &App, area: Rect) {
@@ -25,6 +26,11 @@ pub fn render_stats_line(frame: &mut Frame, app: &App, area: Rect) {
 
     // Search indicator
     if let Some(ref term) = app.filter.search_term {
+        let hint = if app.command_line.focused {
+            "  (Enter to keep, Esc to cancel)"
+        } else {
+            "  (n/N next/previous, Esc to clear)"
+        };
         let search_line = Line::from(vec![
             Span::raw("  "),
             Span::styled("Search: ", app.theme.info),
@@ -32,7 +38,11 @@ pub fn render_stats_line(frame: &mut Frame, app: &App, area: Rect) {
                 format!("\"{}\"", term),
                 app.theme.info.add_modifier(Modifier::BOLD),
             ),
-            Span::styled("  (Esc to clear)", app.theme.muted),
+            Span::styled(
+                format!("  {} matches", app.display_order.len()),
+                app.theme.info,
+            ),
+            Span::styled(hint, app.theme.muted),
         ]);
         frame.render_widget(Paragraph::new(search_line), area);
         return;
@@ -42,8 +52,9 @@ pub fn render_stats_line(frame: &mut Frame, app: &App, area: Rect) {
     if app.config.display_progress_overview {
         let stats = app.get_stats();
 
-        let mut spans = vec![
-            Span::raw("  "),
+        let mut spans = vec![Span::raw("  ")];
+        append_focus(app, &mut spans);
+        spans.extend([
             Span::styled(format!("{}%", stats.percent), app.theme.success),
             Span::styled(" done", app.theme.muted),
             Span::styled(" | ", app.theme.muted),
@@ -58,7 +69,7 @@ pub fn render_stats_line(frame: &mut Frame, app: &App, area: Rect) {
             Span::styled(" · ", app.theme.muted),
             Span::styled(format!("{}", stats.notes), app.theme.info),
             Span::styled(" notes", app.theme.muted),
-        ];
+        ]);
 
         // Append key hints on the right
         append_key_hints(app, &mut spans);
@@ -70,17 +81,36 @@ pub fn render_stats_line(frame: &mut Frame, app: &App, area: Rect) {
 
     // No progress overview — show just key hints
     let mut spans = vec![Span::raw("  ")];
+    append_focus(app, &mut spans);
     append_key_hints(app, &mut spans);
     let line = Line::from(spans);
     frame.render_widget(Paragraph::new(line), area);
 }
 
+/// Focus timer countdown, shown ahead of the stats while a session runs
+fn append_focus<'a>(app: &'a App, spans: &mut Vec<Span<'a>>) {
+    if let Some(ref focus) = app.focus {
+        spans.push(Span::styled(
+            format!("{} {} ", app.icons.timer, format_clock(focus.remaining())),
+            app.theme.warning.add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::styled(
+            format!("task {}", focus.task_id),
+            app.theme.muted,
+        ));
+        spans.push(Span::styled(" | ", app.theme.muted));
+    }
+}
+
 fn append_key_hints<'a>(app: &'a App, spans: &mut Vec<Span<'a>>) {
-    let key_style = Style::default()
-        .fg(Color::Yellow)
-        .add_modifier(Modifier::BOLD);
+    let key_style = app.theme.warning.add_modifier(Modifier::BOLD);
     let sep_style = app.theme.muted;
 
+    // Count typed so far, waiting for its key
+    if let Some(count) = &app.count {
+        spans.push(Span::styled(format!("  {}", count.value), key_style));
+    }
+
     spans.push(Span::styled("  ?", key_style));
     spans.push(Span::styled(" Help", sep_style));
     spans.push(Span::styled(" │ ", sep_style));
@@ -0,0 +1,64 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::config::ThemeColors;
+use crate::palette;
+use crate::tui::app::App;
+use crate::tui::settings;
+use crate::tui::theme::color;
+use crate::tui::ui::centered_rect;
+
+/// The preset themes, each with a swatch of its colors; the whole screen
+/// previews the selected one
+pub fn render_theme_picker(frame: &mut Frame, app: &App, selected: usize) {
+    let height = ThemeColors::PRESETS.len() as u16 + 6;
+    let area = centered_rect(52, height, frame.area());
+
+    let block = Block::default()
+        .title(" Theme ")
+        .borders(Borders::ALL)
+        .border_style(app.theme.border)
+        .style(app.theme.popup);
+
+    let saved = settings::theme_index(&app.config.theme);
+    let mode = palette::detect(app.config.color_mode);
+    let mut text = vec![Line::from("")];
+    for (i, name) in ThemeColors::PRESETS.iter().enumerate() {
+        let colors = ThemeColors::from_name(name).unwrap_or_default();
+        let (marker, style) = if i == selected {
+            ("  > ", app.theme.info.add_modifier(Modifier::BOLD))
+        } else {
+            ("    ", app.theme.text)
+        };
+        let mut spans = vec![Span::styled(format!("{}{:<22}", marker, name), style)];
+        for rgb in [
+            colors.success,
+            colors.warning,
+            colors.error,
+            colors.info,
+            colors.pending,
+            colors.muted,
+        ] {
+            spans.push(Span::styled("██", Style::default().fg(color(rgb, mode))));
+        }
+        if saved == Some(i) {
+            spans.push(Span::styled("  saved", app.theme.muted));
+        }
+        text.push(Line::from(spans));
+    }
+
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(
+        "  j/k preview · Enter keep · Esc cancel",
+        app.theme.muted,
+    )));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block.clone(), area);
+    let inner = block.inner(area);
+    frame.render_widget(Paragraph::new(text), inner);
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use chrono::NaiveDate;
 use ratatui::{
     layout::Rect,
     style::Modifier,
@@ -7,7 +8,8 @@ use ratatui::{
     Frame,
 };
 
-use crate::tui::app::App;
+use crate::config::TimelineGroup;
+use crate::tui::app::{App, ListRow};
 use taskbook_common::StorageItem;
 
 use super::item_row::{render_item_line, ItemRowOptions};
@@ -15,33 +17,29 @@ use super::render_scrollable_list;
 
 pub fn render_timeline_view(frame: &mut Frame, app: &App, area: Rect) {
     let mut lines: Vec<Line> = Vec::new();
-    let mut item_line_map: Vec<Option<u64>> = Vec::new();
-    let row_options = ItemRowOptions::for_timeline_view();
+    let mut item_line_map: Vec<Option<ListRow>> = Vec::new();
+    let row_options = ItemRowOptions::for_timeline_view().with_density(app.config.display_density);
 
-    // Group items by date
-    let mut grouped: HashMap<String, Vec<&StorageItem>> = HashMap::new();
+    // Group items by the day, week or month they were created in, keyed by
+    // the first day of that period
+    let group = app.config.timeline_group;
+    let mut grouped: HashMap<NaiveDate, Vec<&StorageItem>> = HashMap::new();
     for item in app.items.values() {
-        let date = item.date().to_string();
+        let date = group.start_of(item.created_date().unwrap_or_default());
         grouped.entry(date).or_default().push(item);
     }
 
     // Sort dates (newest first)
-    let mut dates: Vec<String> = grouped.keys().cloned().collect();
-    dates.sort_by(|a, b| {
-        let items_a = grouped.get(a).unwrap();
-        let items_b = grouped.get(b).unwrap();
-        let ts_a = items_a.first().map(|i| i.timestamp()).unwrap_or(0);
-        let ts_b = items_b.first().map(|i| i.timestamp()).unwrap_or(0);
-        ts_b.cmp(&ts_a)
-    });
+    let mut dates: Vec<NaiveDate> = grouped.keys().copied().collect();
+    dates.sort_by(|a, b| b.cmp(a));
 
-    let today = chrono::Local::now().format("%a %b %d %Y").to_string();
+    let current = group.start_of(chrono::Local::now().date_naive());
 
     let mut first_group = true;
     for date in dates {
         let date_items = grouped.get(&date).unwrap();
 
-        // Count stats for this date (always count all tasks)
+        // Count stats for this period (always count all tasks)
         let total_tasks: usize = date_items.iter().filter(|i| i.is_task()).count();
         let complete_tasks: usize = date_items
             .iter()
@@ -68,20 +66,21 @@ pub fn render_timeline_view(frame: &mut Frame, app: &App, area: Rect) {
         }
         first_group = false;
 
-        let is_today = date == today;
-        let date_header = if total_tasks > 0 {
-            if is_today {
-                format!("  {} [Today] [{}/{}]", date, complete_tasks, total_tasks)
-            } else {
-                format!("  {} [{}/{}]", date, complete_tasks, total_tasks)
+        let is_current = date == current;
+        let mut date_header = format!("  {}", app.config.format_period(group, date));
+        if is_current {
+            date_header.push_str(&format!(" [{}]", group.current_label()));
+        }
+        if total_tasks > 0 {
+            date_header.push_str(&format!(" [{}/{}]", complete_tasks, total_tasks));
+            // Longer periods also show how much of them got done
+            if group != TimelineGroup::Day {
+                let percent = complete_tasks * 100 / total_tasks;
+                date_header.push_str(&format!(" {}% done", percent));
             }
-        } else if is_today {
-            format!("  {} [Today]", date)
-        } else {
-            format!("  {}", date)
-        };
+        }
 
-        let header_style = if is_today {
+        let header_style = if is_current {
             app.theme.header.add_modifier(Modifier::BOLD)
         } else {
             app.theme.header
@@ -97,9 +96,16 @@ pub fn render_timeline_view(frame: &mut Frame, app: &App, area: Rect) {
             let is_selected = app.selected_id() == Some(item.id());
             let line = render_item_line(app, item, is_selected, &row_options);
             lines.push(line);
-            item_line_map.push(Some(item.id()));
+            item_line_map.push(Some(ListRow::Item(item.id())));
         }
     }
 
-    render_scrollable_list(frame, area, lines, &item_line_map, app.selected_id());
+    render_scrollable_list(
+        frame,
+        area,
+        lines,
+        &item_line_map,
+        app.selected_row(),
+        &app.list_scroll,
+    );
 }
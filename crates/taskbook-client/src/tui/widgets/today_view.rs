@@ -0,0 +1,57 @@
+use ratatui::{
+    layout::Rect,
+    style::Modifier,
+    text::{Line, Span},
+    Frame,
+};
+
+use crate::agenda::{self, AgendaSection};
+use crate::tui::app::{App, ListRow};
+
+use super::item_row::{render_item_line, ItemRowOptions};
+use super::render_scrollable_list;
+
+pub fn render_today_view(frame: &mut Frame, app: &App, area: Rect) {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut item_line_map: Vec<Option<ListRow>> = Vec::new();
+    let row_options = ItemRowOptions::for_timeline_view().with_density(app.config.display_density);
+
+    let today = chrono::Local::now().date_naive();
+    let visible = app.items.values().filter(|item| app.should_show_item(item));
+
+    let mut first_group = true;
+    for (section, items) in agenda::group(visible, today) {
+        // Section header (blank separator between groups, not before first)
+        if !first_group {
+            lines.push(Line::from(""));
+            item_line_map.push(None);
+        }
+        first_group = false;
+
+        let header_style = match section {
+            AgendaSection::Overdue => app.theme.error.add_modifier(Modifier::BOLD),
+            _ => app.theme.header,
+        };
+        lines.push(Line::from(Span::styled(
+            format!("  {} [{}]", section.title(), items.len()),
+            header_style,
+        )));
+        item_line_map.push(None);
+
+        for item in items {
+            let is_selected = app.selected_id() == Some(item.id());
+            let line = render_item_line(app, item, is_selected, &row_options);
+            lines.push(line);
+            item_line_map.push(Some(ListRow::Item(item.id())));
+        }
+    }
+
+    render_scrollable_list(
+        frame,
+        area,
+        lines,
+        &item_line_map,
+        app.selected_row(),
+        &app.list_scroll,
+    );
+}
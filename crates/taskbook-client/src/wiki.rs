@@ -0,0 +1,143 @@
+//! `[[Note Title]]` links between notes: finding them in a body, the notes
+//! they name, and the notes linking back.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use taskbook_common::StorageItem;
+
+/// The `[[...]]` links in `text`: where each is, brackets included, and the
+/// title it names
+pub fn links(text: &str) -> Vec<(Range<usize>, &str)> {
+    let mut links = Vec::new();
+    let mut from = 0;
+    while let Some(open) = text[from..].find("[[").map(|i| from + i) {
+        let Some(close) = text[open + 2..].find("]]").map(|i| open + 2 + i) else {
+            break;
+        };
+        let title = text[open + 2..close].trim();
+        // `[[` on one line and `]]` on a later one is not a link
+        if title.is_empty() || title.contains('\n') || title.contains("[[") {
+            from = open + 2;
+            continue;
+        }
+        links.push((open..close + 2, title));
+        from = close + 2;
+    }
+    links
+}
+
+/// The note titled `title`, ignoring case
+pub fn find_note<'a>(
+    title: &str,
+    items: &'a HashMap<String, StorageItem>,
+) -> Option<&'a StorageItem> {
+    let title = title.to_lowercase();
+    items
+        .values()
+        .filter(|item| !item.is_task() && item.description().to_lowercase() == title)
+        // Duplicate titles: the oldest note wins, so a link doesn't move
+        .min_by_key(|item| item.id())
+}
+
+/// The notes `item`'s body links to, in the order they are first linked
+pub fn linked_notes<'a>(
+    item: &StorageItem,
+    items: &'a HashMap<String, StorageItem>,
+) -> Vec<&'a StorageItem> {
+    let mut notes: Vec<&StorageItem> = Vec::new();
+    for (_, title) in links(item.note_body().unwrap_or_default()) {
+        if let Some(note) = find_note(title, items) {
+            if note.id() != item.id() && !notes.iter().any(|n| n.id() == note.id()) {
+                notes.push(note);
+            }
+        }
+    }
+    notes
+}
+
+/// The notes whose body links to `item`, by id
+pub fn backlinks<'a>(
+    item: &StorageItem,
+    items: &'a HashMap<String, StorageItem>,
+) -> Vec<&'a StorageItem> {
+    if item.is_task() {
+        return Vec::new();
+    }
+    let mut notes: Vec<&StorageItem> = items
+        .values()
+        .filter(|other| other.id() != item.id())
+        .filter(|other| {
+            links(other.note_body().unwrap_or_default())
+                .iter()
+                .any(|(_, title)| find_note(title, items).is_some_and(|n| n.id() == item.id()))
+        })
+        .collect();
+    notes.sort_by_key(|note| note.id());
+    notes
+}
+
+/// Where a note's links lead, in the order the item popup lists them: the
+/// notes it links to, then the ones linking back
+pub fn jump_targets(item: &StorageItem, items: &HashMap<String, StorageItem>) -> Vec<u64> {
+    let mut ids: Vec<u64> = linked_notes(item, items).iter().map(|n| n.id()).collect();
+    for note in backlinks(item, items) {
+        if !ids.contains(&note.id()) {
+            ids.push(note.id());
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use taskbook_common::{Note, Task};
+
+    fn items() -> HashMap<String, StorageItem> {
+        let notes = [
+            Note::new_with_body(
+                1,
+                "Rust".into(),
+                Some("See [[Ownership]] and [[ borrowing ]], not [[Missing]].".into()),
+                vec![],
+            ),
+            Note::new_with_body(
+                2,
+                "Ownership".into(),
+                Some("Back to [[rust]]".into()),
+                vec![],
+            ),
+            Note::new(3, "Borrowing".into(), vec![]),
+        ];
+        let mut items: HashMap<String, StorageItem> = notes
+            .into_iter()
+            .map(|note| (note.id.to_string(), StorageItem::Note(note)))
+            .collect();
+        let task = Task::new(4, "Ownership".into(), vec![], 1);
+        items.insert("4".into(), StorageItem::Task(task));
+        items
+    }
+
+    #[test]
+    fn links_are_found_with_their_position() {
+        let text = "a [[One]] b [[]] [[Two\nlines]] [[ Three ]]";
+        let found = links(text);
+        let titles: Vec<&str> = found.iter().map(|(_, title)| *title).collect();
+        assert_eq!(titles, ["One", "Three"]);
+        assert_eq!(&text[found[0].0.clone()], "[[One]]");
+    }
+
+    #[test]
+    fn notes_link_by_title_and_link_back() {
+        let items = items();
+        let rust = &items["1"];
+        let ids = |notes: Vec<&StorageItem>| notes.iter().map(|n| n.id()).collect::<Vec<_>>();
+
+        // Titles match notes only, ignoring case; unknown titles are skipped
+        assert_eq!(ids(linked_notes(rust, &items)), [2, 3]);
+        assert_eq!(ids(backlinks(rust, &items)), [2]);
+        assert_eq!(ids(backlinks(&items["3"], &items)), [1]);
+        assert_eq!(jump_targets(rust, &items), [2, 3]);
+    }
+}
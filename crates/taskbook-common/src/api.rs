@@ -29,12 +29,17 @@ pub struct RegisterRequest {
     pub username: String,
     pub email: String,
     pub password: String,
+    /// Needed when the server only lets invited people register
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invite_code: Option<String>,
 }
 
 /// Response from POST /api/v1/register
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RegisterResponse {
     pub token: String,
+    /// Gets a new session token once this one expires, from POST /api/v1/refresh
+    pub refresh_token: String,
 }
 
 /// Request body for POST /api/v1/login
@@ -42,12 +47,31 @@ pub struct RegisterResponse {
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    /// Needed once two-factor authentication is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp_code: Option<String>,
 }
 
 /// Response from POST /api/v1/login
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub token: String,
+    /// Gets a new session token once this one expires, from POST /api/v1/refresh
+    pub refresh_token: String,
+}
+
+/// Request body for POST /api/v1/refresh
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// Response from POST /api/v1/refresh. Both tokens are new; the old refresh
+/// token no longer works.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshResponse {
+    pub token: String,
+    pub refresh_token: String,
 }
 
 /// Response from GET /api/v1/me
@@ -55,6 +79,208 @@ pub struct LoginResponse {
 pub struct MeResponse {
     pub username: String,
     pub email: String,
+    pub email_verified: bool,
+    /// JSON [`crate::encryption::KeyParams`] when the encryption key is
+    /// derived from a passphrase
+    pub key_params: Option<String>,
+}
+
+/// Request body for PUT /api/v1/me/key-params
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyParamsRequest {
+    /// `null` when the account goes back to a random key
+    pub key_params: Option<String>,
+}
+
+/// Request body for PATCH /api/v1/me/password
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Response from PATCH /api/v1/me/password. All earlier sessions are ended;
+/// this is the only valid one.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangePasswordResponse {
+    pub token: String,
+    /// Gets a new session token once this one expires, from POST /api/v1/refresh
+    pub refresh_token: String,
+}
+
+/// Request body for DELETE /api/v1/me
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+}
+
+/// Request body for POST /api/v1/me/totp
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnrollTotpRequest {
+    pub password: String,
+}
+
+/// Response from POST /api/v1/me/totp
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnrollTotpResponse {
+    /// Base32-encoded secret
+    pub secret: String,
+    /// `otpauth://` URI for authenticator apps
+    pub uri: String,
+}
+
+/// Request body for POST /api/v1/me/totp/confirm
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmTotpRequest {
+    pub code: String,
+}
+
+/// Request body for DELETE /api/v1/me/totp
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DisableTotpRequest {
+    pub password: String,
+    pub code: String,
+}
+
+/// Response from POST /api/v1/me/email/verify
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendVerificationResponse {
+    /// The address the link was sent to
+    pub email: String,
+}
+
+/// Request body for POST /api/v1/email/verify
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+/// Request body for POST /api/v1/password/forgot
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+/// Request body for POST /api/v1/password/reset
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp_code: Option<String>,
+}
+
+/// Response from POST /api/v1/oidc/start and POST /api/v1/oidc/link
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OidcStartResponse {
+    /// The provider's sign-in page, to open in a browser
+    pub authorize_url: String,
+    pub poll_token: String,
+    /// Seconds until the sign-in expires
+    pub expires_in: i64,
+}
+
+/// Request body for POST /api/v1/oidc/poll
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OidcPollRequest {
+    pub poll_token: String,
+}
+
+/// Response from POST /api/v1/oidc/poll
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum OidcPollResponse {
+    /// The browser has not come back from the provider yet
+    Pending,
+    /// Signed in; `created` is set when the sign-in created the account
+    Complete {
+        token: String,
+        refresh_token: String,
+        created: bool,
+    },
+    /// The provider account was linked to the caller's
+    Linked,
+}
+
+/// A session (logged-in device), as listed by GET /api/v1/sessions
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub expires_at: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    /// Whether this is the session the request was made with
+    pub current: bool,
+}
+
+/// Response from GET /api/v1/sessions
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionsResponse {
+    pub sessions: Vec<SessionInfo>,
+}
+
+/// An entry in the account's audit log, as listed by GET /api/v1/me/audit
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// `login`, `login_failed`, `password_changed`, `password_reset`,
+    /// `session_revoked`, `data_exported` or `sso_linked`
+    pub event: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: String,
+}
+
+/// Response from GET /api/v1/me/audit: the latest 100 entries, newest first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditResponse {
+    pub events: Vec<AuditEntry>,
+}
+
+/// Request body for POST /api/v1/tokens
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    /// `read` or `write`
+    pub scope: String,
+    /// Seconds until the token expires; never if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<i64>,
+}
+
+/// Response from POST /api/v1/tokens. The token is not shown again.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTokenResponse {
+    pub id: String,
+    pub token: String,
+    pub expires_at: Option<String>,
+}
+
+/// An API token, as listed by GET /api/v1/tokens
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenInfo {
+    pub id: String,
+    pub name: String,
+    pub scope: String,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+/// Response from GET /api/v1/tokens
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokensResponse {
+    pub tokens: Vec<TokenInfo>,
+}
+
+/// Response from GET /api/v1/me/usage
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageResponse {
+    /// Encrypted bytes stored: items, owned shared boards and share links
+    pub used_bytes: i64,
+    /// Bytes the account may store; `None` for no limit
+    pub quota_bytes: Option<i64>,
 }
 
 /// Response from GET /api/v1/health
@@ -62,3 +288,257 @@ pub struct MeResponse {
 pub struct HealthResponse {
     pub status: String,
 }
+
+/// Request body for PUT /api/v1/me/public-key
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicKeyRequest {
+    /// Base64-encoded X25519 public key
+    pub public_key: String,
+}
+
+/// Response from GET /api/v1/users/:username/public-key
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicKeyResponse {
+    pub public_key: String,
+}
+
+/// A member of a shared board
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardMember {
+    pub username: String,
+    pub can_write: bool,
+}
+
+/// A shared board the requesting user is a member of
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedBoard {
+    pub id: String,
+    pub owner: String,
+    pub is_owner: bool,
+    pub can_write: bool,
+    /// Base64-encoded board key, wrapped for the requesting user
+    pub wrapped_key: String,
+    pub members: Vec<BoardMember>,
+}
+
+/// Response from GET /api/v1/boards
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoardsResponse {
+    pub boards: Vec<SharedBoard>,
+}
+
+/// Request body for POST /api/v1/boards
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateBoardRequest {
+    /// Base64-encoded board key, wrapped for the owner
+    pub wrapped_key: String,
+}
+
+/// Response from POST /api/v1/boards
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateBoardResponse {
+    pub id: String,
+}
+
+/// Request body for PUT /api/v1/boards/:id/key: the caller's own copy of
+/// the board key, wrapped for their new identity
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoardKeyRequest {
+    pub wrapped_key: String,
+}
+
+/// Request body for PUT /api/v1/boards/:id/members
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShareBoardRequest {
+    pub username: String,
+    /// Base64-encoded board key, wrapped for the new member
+    pub wrapped_key: String,
+    pub can_write: bool,
+}
+
+/// Request body for POST /api/v1/links
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateLinkRequest {
+    /// Base64-encoded board snapshot, encrypted with the link's key
+    pub data: String,
+    /// Base64-encoded 12-byte AES-GCM nonce
+    pub nonce: String,
+    /// Seconds until the link expires
+    pub expires_in: i64,
+}
+
+/// Response from POST /api/v1/links
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateLinkResponse {
+    pub token: String,
+    /// RFC 3339 timestamp
+    pub expires_at: String,
+}
+
+/// A share link, as listed by GET /api/v1/links
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkInfo {
+    pub token: String,
+    pub created_at: String,
+    pub expires_at: String,
+}
+
+/// Response from GET /api/v1/links
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinksResponse {
+    pub links: Vec<LinkInfo>,
+}
+
+/// Response from GET /api/v1/links/:token (no authentication)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkDataResponse {
+    pub data: String,
+    pub nonce: String,
+    pub expires_at: String,
+}
+
+/// An item in GET /api/v1/items/trash
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashedItem {
+    pub key: String,
+    #[serde(flatten)]
+    pub item: EncryptedItemData,
+    /// Whether it was removed from the archive
+    pub archived: bool,
+    pub deleted_at: String,
+    /// When it is deleted for good
+    pub purge_at: String,
+}
+
+/// Response from GET /api/v1/items/trash
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashResponse {
+    pub items: Vec<TrashedItem>,
+    pub retention_days: i64,
+}
+
+/// Request body for POST /api/v1/items/trash/restore
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RestoreRequest {
+    /// Keys of the items to restore; all of them if missing
+    #[serde(default)]
+    pub keys: Option<Vec<String>>,
+}
+
+/// Response from POST /api/v1/items/trash/restore
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RestoreResponse {
+    pub restored: u64,
+}
+
+/// A version of an item in GET /api/v1/items/:key/history
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemVersion {
+    #[serde(flatten)]
+    pub item: EncryptedItemData,
+    pub archived: bool,
+    /// When this version was uploaded
+    pub saved_at: String,
+    /// Whether this is the version stored now (possibly in the trash)
+    pub current: bool,
+}
+
+/// Response from GET /api/v1/items/:key/history
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryResponse {
+    pub key: String,
+    /// Newest first
+    pub versions: Vec<ItemVersion>,
+}
+
+/// Account details in GET /api/v1/me/export
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportAccount {
+    pub username: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub is_admin: bool,
+    pub two_factor_enabled: bool,
+    pub created_at: String,
+    pub last_active_at: Option<String>,
+    /// Base64-encoded X25519 public key, if published
+    pub public_key: Option<String>,
+    pub used_bytes: i64,
+    pub quota_bytes: Option<i64>,
+}
+
+/// Response from GET /api/v1/me/export: everything the server holds for the
+/// account, with items still encrypted
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountExport {
+    /// Always `taskbook-export`
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    pub account: ExportAccount,
+    /// Sessions with `created_at`, `last_used_at`, `user_agent` and `ip`
+    pub sessions: Vec<serde_json::Value>,
+    /// API tokens with `name`, `scope`, `created_at`, `last_used_at` and
+    /// `expires_at`
+    pub api_tokens: Vec<serde_json::Value>,
+    /// Unexpired share links with `created_at` and `expires_at`
+    pub share_links: Vec<serde_json::Value>,
+    pub shared_boards: Vec<SharedBoard>,
+    pub items: HashMap<String, EncryptedItemData>,
+    pub archive: HashMap<String, EncryptedItemData>,
+    /// Items of each shared board, by board id
+    pub board_items: HashMap<String, HashMap<String, EncryptedItemData>>,
+}
+
+/// An account, as listed by GET /api/v1/admin/users (admins only)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminUserInfo {
+    pub username: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub is_admin: bool,
+    pub disabled: bool,
+    pub created_at: String,
+    pub last_active_at: Option<String>,
+    pub items: i64,
+    /// Encrypted bytes stored: items, owned shared boards and share links
+    pub storage_bytes: i64,
+    /// The account's storage limit, `None` for no limit
+    pub quota_bytes: Option<i64>,
+    /// Whether the quota was set for this account rather than the default
+    pub custom_quota: bool,
+}
+
+/// Response from GET /api/v1/admin/users
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminUsersResponse {
+    pub users: Vec<AdminUserInfo>,
+}
+
+/// Request body for PUT /api/v1/admin/users/:username/disabled
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetDisabledRequest {
+    pub disabled: bool,
+}
+
+/// Request body for PUT /api/v1/admin/users/:username/quota
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetQuotaRequest {
+    /// `None` to use the server default
+    pub quota_bytes: Option<i64>,
+}
+
+/// Request body for POST /api/v1/admin/purge
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PurgeRequest {
+    pub inactive_days: i64,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Response from POST /api/v1/admin/purge
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PurgeResponse {
+    /// Accounts deleted, or that would be on a dry run
+    pub users: Vec<String>,
+}
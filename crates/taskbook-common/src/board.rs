@@ -3,6 +3,11 @@
 //! Board names are stored **without** the `@` prefix. The `@` is added back
 //! only at the display layer via [`display_name`].
 
+use chrono::NaiveDate;
+
+use crate::due;
+use crate::priority;
+
 /// The default board name used when no board is specified.
 pub const DEFAULT_BOARD: &str = "My Board";
 
@@ -41,25 +46,31 @@ pub fn display_name(board: &str) -> String {
     }
 }
 
-/// Parse CLI input words into (boards, description, priority, tags).
+/// Parse CLI input words into (boards, description, priority, tags, due date).
 ///
 /// Words starting with `@` (and longer than 1 char) are treated as board names.
 /// Words starting with `+` (and longer than 1 char) are treated as tags.
-/// Words matching `p:1`, `p:2`, `p:3` set priority.
+/// Words matching `p:1`-`p:3` or a level name (`p:high`) set priority
+/// (see [`priority::parse_priority`]).
+/// Valid `due:<spec>` words set the due date (see [`due::parse_due`]).
 /// Everything else is the description.
 ///
 /// If no boards are found, defaults to [`DEFAULT_BOARD`].
-pub fn parse_cli_input(input: &[String]) -> (Vec<String>, String, u8, Vec<String>) {
+pub fn parse_cli_input(
+    input: &[String],
+) -> (Vec<String>, String, u8, Vec<String>, Option<NaiveDate>) {
+    let today = chrono::Local::now().date_naive();
     let mut boards = Vec::new();
     let mut tags = Vec::new();
     let mut desc = Vec::new();
     let mut priority: u8 = 1;
+    let mut due_date = None;
 
     for word in input {
-        if is_priority_opt(word) {
-            if let Ok(p) = word.trim_start_matches("p:").parse::<u8>() {
-                priority = p;
-            }
+        if let Some(date) = due::parse_due_token(word, today) {
+            due_date = Some(date);
+        } else if let Some(level) = priority::parse_priority_token(word) {
+            priority = level;
         } else if word.starts_with('@') && word.len() > 1 {
             boards.push(normalize_board_name(word));
         } else if word.starts_with('+') && word.len() > 1 {
@@ -84,7 +95,7 @@ pub fn parse_cli_input(input: &[String]) -> (Vec<String>, String, u8, Vec<String
         }
     }
 
-    (deduped, desc.join(" "), priority, tags)
+    (deduped, desc.join(" "), priority, tags, due_date)
 }
 
 /// Normalize a raw tag name: strip leading `+`, trim whitespace, lowercase.
@@ -97,10 +108,6 @@ pub fn display_tag(tag: &str) -> String {
     format!("+{}", tag)
 }
 
-fn is_priority_opt(s: &str) -> bool {
-    matches!(s, "p:1" | "p:2" | "p:3")
-}
-
 /// Deserialize a list of board names, normalizing each one.
 ///
 /// Used as `#[serde(deserialize_with = "...")]` on the `boards` field
@@ -168,7 +175,7 @@ mod tests {
     #[test]
     fn test_parse_cli_input_basic() {
         let input: Vec<String> = vec!["@coding".into(), "Fix".into(), "bug".into()];
-        let (boards, desc, priority, tags) = parse_cli_input(&input);
+        let (boards, desc, priority, tags, _) = parse_cli_input(&input);
         assert_eq!(boards, vec!["coding"]);
         assert_eq!(desc, "Fix bug");
         assert_eq!(priority, 1);
@@ -178,7 +185,7 @@ mod tests {
     #[test]
     fn test_parse_cli_input_with_priority() {
         let input: Vec<String> = vec!["@coding".into(), "Fix".into(), "bug".into(), "p:3".into()];
-        let (boards, desc, priority, _) = parse_cli_input(&input);
+        let (boards, desc, priority, _, _) = parse_cli_input(&input);
         assert_eq!(boards, vec!["coding"]);
         assert_eq!(desc, "Fix bug");
         assert_eq!(priority, 3);
@@ -187,7 +194,7 @@ mod tests {
     #[test]
     fn test_parse_cli_input_no_board_defaults() {
         let input: Vec<String> = vec!["Simple".into(), "task".into()];
-        let (boards, desc, priority, _) = parse_cli_input(&input);
+        let (boards, desc, priority, _, _) = parse_cli_input(&input);
         assert_eq!(boards, vec![DEFAULT_BOARD]);
         assert_eq!(desc, "Simple task");
         assert_eq!(priority, 1);
@@ -196,7 +203,7 @@ mod tests {
     #[test]
     fn test_parse_cli_input_dedup_boards() {
         let input: Vec<String> = vec!["@coding".into(), "@Coding".into(), "task".into()];
-        let (boards, desc, _, _) = parse_cli_input(&input);
+        let (boards, desc, _, _, _) = parse_cli_input(&input);
         assert_eq!(boards, vec!["coding"]);
         assert_eq!(desc, "task");
     }
@@ -205,15 +212,28 @@ mod tests {
     fn test_parse_cli_input_priority_parsing() {
         for p in 1..=3u8 {
             let input: Vec<String> = vec!["task".into(), format!("p:{p}")];
-            let (_, _, priority, _) = parse_cli_input(&input);
+            let (_, _, priority, _, _) = parse_cli_input(&input);
             assert_eq!(priority, p, "expected priority {p}");
         }
     }
 
+    #[test]
+    fn test_parse_cli_input_priority_names() {
+        let input: Vec<String> = vec!["task".into(), "p:high".into()];
+        let (_, desc, priority, _, _) = parse_cli_input(&input);
+        assert_eq!(desc, "task");
+        assert_eq!(priority, 3);
+
+        let input: Vec<String> = vec!["task".into(), "p:4".into()];
+        let (_, desc, priority, _, _) = parse_cli_input(&input);
+        assert_eq!(desc, "task p:4");
+        assert_eq!(priority, 1);
+    }
+
     #[test]
     fn test_parse_cli_input_multiple_boards() {
         let input: Vec<String> = vec!["@coding".into(), "@reviews".into(), "task".into()];
-        let (boards, desc, _, _) = parse_cli_input(&input);
+        let (boards, desc, _, _, _) = parse_cli_input(&input);
         assert_eq!(boards, vec!["coding", "reviews"]);
         assert_eq!(desc, "task");
     }
@@ -228,7 +248,7 @@ mod tests {
             "login".into(),
             "bug".into(),
         ];
-        let (boards, desc, _, tags) = parse_cli_input(&input);
+        let (boards, desc, _, tags, _) = parse_cli_input(&input);
         assert_eq!(boards, vec!["coding"]);
         assert_eq!(desc, "Fix login bug");
         assert_eq!(tags, vec!["urgent", "frontend"]);
@@ -237,7 +257,7 @@ mod tests {
     #[test]
     fn test_parse_cli_input_dedup_tags() {
         let input: Vec<String> = vec!["+urgent".into(), "+Urgent".into(), "task".into()];
-        let (_, _, _, tags) = parse_cli_input(&input);
+        let (_, _, _, tags, _) = parse_cli_input(&input);
         assert_eq!(tags, vec!["urgent"]);
     }
 
@@ -252,4 +272,17 @@ mod tests {
     fn test_display_tag() {
         assert_eq!(display_tag("urgent"), "+urgent");
     }
+
+    #[test]
+    fn test_parse_cli_input_with_due() {
+        let input: Vec<String> = vec![
+            "@work".into(),
+            "Ship".into(),
+            "release".into(),
+            "due:2024-05-01".into(),
+        ];
+        let (_, desc, _, _, due) = parse_cli_input(&input);
+        assert_eq!(desc, "Ship release");
+        assert_eq!(due, NaiveDate::from_ymd_opt(2024, 5, 1));
+    }
 }
@@ -0,0 +1,128 @@
+//! Due date parsing and formatting shared by the CLI and TUI.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+/// Prefix for due date tokens in item input, e.g. `due:tomorrow`
+pub const DUE_PREFIX: &str = "due:";
+
+/// Parse a due date specification relative to `today`.
+///
+/// Accepts `today`, `tomorrow`, `+N` / `Nd` (days from today), weekday
+/// names (`mon`, `friday`; the next such day, never today) and ISO dates
+/// (`2024-05-01`).
+pub fn parse_due(spec: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let spec = spec.trim().to_lowercase();
+
+    match spec.as_str() {
+        "today" | "tod" => return Some(today),
+        "tomorrow" | "tom" => return Some(today + Duration::days(1)),
+        _ => {}
+    }
+
+    let days = spec
+        .strip_prefix('+')
+        .or_else(|| spec.strip_suffix('d'))
+        .and_then(|n| n.parse::<i64>().ok());
+    if let Some(days) = days {
+        return Some(today + Duration::days(days));
+    }
+
+    if let Some(weekday) = parse_weekday(&spec) {
+        let ahead = (7 + weekday.num_days_from_monday() as i64
+            - today.weekday().num_days_from_monday() as i64)
+            % 7;
+        let ahead = if ahead == 0 { 7 } else { ahead };
+        return Some(today + Duration::days(ahead));
+    }
+
+    NaiveDate::parse_from_str(&spec, "%Y-%m-%d").ok()
+}
+
+/// Parse a `due:<spec>` input token. Returns `None` if the word is not a
+/// due token or the spec is not understood.
+pub fn parse_due_token(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+    word.strip_prefix(DUE_PREFIX)
+        .and_then(|spec| parse_due(spec, today))
+}
+
+/// Short human-readable form of a due date relative to `today`
+pub fn format_due(due: NaiveDate, today: NaiveDate) -> String {
+    let days = (due - today).num_days();
+    match days {
+        0 => "due today".to_string(),
+        1 => "due tomorrow".to_string(),
+        2..=6 => format!("due in {}d", days),
+        -1 => "overdue 1d".to_string(),
+        d if d < 0 => format!("overdue {}d", -d),
+        _ => format!("due {}", due.format("%b %d")),
+    }
+}
+
+fn parse_weekday(spec: &str) -> Option<Weekday> {
+    let weekday = match spec {
+        "mon" | "monday" => Weekday::Mon,
+        "tue" | "tues" | "tuesday" => Weekday::Tue,
+        "wed" | "wednesday" => Weekday::Wed,
+        "thu" | "thurs" | "thursday" => Weekday::Thu,
+        "fri" | "friday" => Weekday::Fri,
+        "sat" | "saturday" => Weekday::Sat,
+        "sun" | "sunday" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_due_relative_keywords() {
+        let today = date(2024, 5, 1);
+        assert_eq!(parse_due("today", today), Some(today));
+        assert_eq!(parse_due("Tomorrow", today), Some(date(2024, 5, 2)));
+        assert_eq!(parse_due("+3", today), Some(date(2024, 5, 4)));
+        assert_eq!(parse_due("10d", today), Some(date(2024, 5, 11)));
+    }
+
+    #[test]
+    fn test_parse_due_weekday_is_next_occurrence() {
+        // 2024-05-01 is a Wednesday
+        let today = date(2024, 5, 1);
+        assert_eq!(parse_due("fri", today), Some(date(2024, 5, 3)));
+        assert_eq!(parse_due("wednesday", today), Some(date(2024, 5, 8)));
+        assert_eq!(parse_due("mon", today), Some(date(2024, 5, 6)));
+    }
+
+    #[test]
+    fn test_parse_due_iso_and_invalid() {
+        let today = date(2024, 5, 1);
+        assert_eq!(parse_due("2024-12-24", today), Some(date(2024, 12, 24)));
+        assert_eq!(parse_due("someday", today), None);
+        assert_eq!(parse_due("2024-13-01", today), None);
+    }
+
+    #[test]
+    fn test_parse_due_token() {
+        let today = date(2024, 5, 1);
+        assert_eq!(
+            parse_due_token("due:tomorrow", today),
+            Some(date(2024, 5, 2))
+        );
+        assert_eq!(parse_due_token("tomorrow", today), None);
+        assert_eq!(parse_due_token("due:", today), None);
+    }
+
+    #[test]
+    fn test_format_due() {
+        let today = date(2024, 5, 1);
+        assert_eq!(format_due(today, today), "due today");
+        assert_eq!(format_due(date(2024, 5, 2), today), "due tomorrow");
+        assert_eq!(format_due(date(2024, 4, 28), today), "overdue 3d");
+        assert_eq!(format_due(date(2024, 6, 15), today), "due Jun 15");
+    }
+}
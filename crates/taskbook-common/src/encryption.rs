@@ -1,8 +1,20 @@
 use aes_gcm::aead::{Aead, OsRng};
 use aes_gcm::{AeadCore, Aes256Gcm, Key, KeyInit, Nonce};
+use argon2::Argon2;
+use base64::Engine as _;
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
 
 use crate::error::CommonError;
-use crate::StorageItem;
+use crate::{fill_legacy_uuid, StorageItem};
+
+const NONCE_LEN: usize = 12;
+const PUBLIC_KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
 
 /// An encrypted item with its ciphertext and nonce.
 pub struct EncryptedItem {
@@ -18,12 +30,132 @@ pub fn generate_key() -> [u8; 32] {
     bytes
 }
 
+/// Most memory a key derivation may use, so a key header from elsewhere
+/// (such as the server) cannot exhaust the device: 1 GiB
+const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+
+/// Most passes a key derivation may make
+const MAX_KDF_ITERATIONS: u32 = 64;
+
+/// Argon2id cost of deriving a key. The defaults are the `argon2` crate's:
+/// 19 MiB, 2 passes, 1 lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KdfCost {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfCost {
+    fn default() -> Self {
+        Self {
+            memory_kib: argon2::Params::DEFAULT_M_COST,
+            iterations: argon2::Params::DEFAULT_T_COST,
+            parallelism: argon2::Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfCost {
+    fn params(&self) -> Result<argon2::Params, CommonError> {
+        if self.memory_kib > MAX_KDF_MEMORY_KIB {
+            return Err(CommonError::KeyDerivation(format!(
+                "memory cost {} KiB is over the limit of {MAX_KDF_MEMORY_KIB} KiB",
+                self.memory_kib
+            )));
+        }
+        if self.iterations > MAX_KDF_ITERATIONS {
+            return Err(CommonError::KeyDerivation(format!(
+                "{} iterations is over the limit of {MAX_KDF_ITERATIONS}",
+                self.iterations
+            )));
+        }
+        argon2::Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| CommonError::KeyDerivation(format!("invalid cost: {e}")))
+    }
+
+    /// Check that keys can be derived at this cost
+    pub fn validate(&self) -> Result<(), CommonError> {
+        self.params().map(|_| ())
+    }
+}
+
+/// How an encryption key is derived from a passphrase: the header stored
+/// next to whatever the key encrypts (on the server as JSON), so every
+/// device derives the same key from the same passphrase, and headers
+/// written with older settings keep working when the defaults change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyParams {
+    /// Key derivation function; only `argon2id` so far
+    pub kdf: String,
+    /// Random salt, base64-encoded
+    pub salt: String,
+    /// Missing from headers written before the cost was configurable, which
+    /// used the defaults
+    #[serde(flatten)]
+    pub cost: KdfCost,
+}
+
+impl KeyParams {
+    /// Parameters for a new passphrase, with a fresh salt and the default cost
+    pub fn generate() -> Self {
+        Self::generate_with(KdfCost::default())
+    }
+
+    /// Parameters for a new passphrase, with a fresh salt
+    pub fn generate_with(cost: KdfCost) -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            kdf: "argon2id".to_string(),
+            salt: base64::engine::general_purpose::STANDARD.encode(salt),
+            cost,
+        }
+    }
+}
+
+/// Derive a 256-bit encryption key from `passphrase` with Argon2id.
+pub fn derive_key(passphrase: &str, params: &KeyParams) -> Result<[u8; 32], CommonError> {
+    if params.kdf != "argon2id" {
+        return Err(CommonError::KeyDerivation(format!(
+            "unknown key derivation function {}",
+            params.kdf
+        )));
+    }
+    let salt = base64::engine::general_purpose::STANDARD
+        .decode(&params.salt)
+        .map_err(|e| CommonError::KeyDerivation(format!("invalid salt: {e}")))?;
+
+    let argon2 = Argon2::new(
+        argon2::Algorithm::Argon2id,
+        argon2::Version::V0x13,
+        params.cost.params()?,
+    );
+    let mut key = [0u8; 32];
+    argon2
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| CommonError::KeyDerivation(e.to_string()))?;
+    Ok(key)
+}
+
 /// Encrypt a `StorageItem` using AES-256-GCM.
 ///
 /// The item is serialized to JSON, then encrypted with a random 12-byte nonce.
 /// The nonce is returned alongside the ciphertext so it can be stored for decryption.
 pub fn encrypt_item(key: &[u8; 32], item: &StorageItem) -> Result<EncryptedItem, CommonError> {
-    let plaintext = serde_json::to_vec(item).map_err(CommonError::Json)?;
+    encrypt_json(key, item)
+}
+
+/// Encrypt any value as JSON using AES-256-GCM, like [`encrypt_item`].
+///
+/// The output is the same as WebCrypto's AES-GCM (ciphertext followed by the
+/// 16-byte tag), so a browser holding the key can decrypt it.
+pub fn encrypt_json<T: Serialize + ?Sized>(
+    key: &[u8; 32],
+    value: &T,
+) -> Result<EncryptedItem, CommonError> {
+    let plaintext = serde_json::to_vec(value).map_err(CommonError::Json)?;
     let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
     let nonce = Aes256Gcm::generate_nonce(OsRng);
     let ciphertext = cipher
@@ -37,10 +169,25 @@ pub fn encrypt_item(key: &[u8; 32], item: &StorageItem) -> Result<EncryptedItem,
 }
 
 /// Decrypt an `EncryptedItem` back into a `StorageItem` using AES-256-GCM.
+///
+/// An item uploaded before items had UUIDs gets the same [`legacy_uuid`]
+/// each time it is decrypted.
+///
+/// [`legacy_uuid`]: crate::legacy_uuid
 pub fn decrypt_item(key: &[u8; 32], encrypted: &EncryptedItem) -> Result<StorageItem, CommonError> {
-    if encrypted.nonce.len() != 12 {
+    let mut value: serde_json::Value = decrypt_json(key, encrypted)?;
+    fill_legacy_uuid(&mut value);
+    serde_json::from_value(value).map_err(CommonError::Json)
+}
+
+/// Decrypt a value encrypted with [`encrypt_json`].
+pub fn decrypt_json<T: DeserializeOwned>(
+    key: &[u8; 32],
+    encrypted: &EncryptedItem,
+) -> Result<T, CommonError> {
+    if encrypted.nonce.len() != NONCE_LEN {
         return Err(CommonError::InvalidNonce {
-            expected: 12,
+            expected: NONCE_LEN,
             got: encrypted.nonce.len(),
         });
     }
@@ -51,8 +198,93 @@ pub fn decrypt_item(key: &[u8; 32], encrypted: &EncryptedItem) -> Result<Storage
         .decrypt(nonce, encrypted.data.as_ref())
         .map_err(|_| CommonError::DecryptionFailed)?;
 
-    let item: StorageItem = serde_json::from_slice(&plaintext).map_err(CommonError::Json)?;
-    Ok(item)
+    serde_json::from_slice(&plaintext).map_err(CommonError::Json)
+}
+
+/// Name and key of a shared board, as wrapped for each member.
+///
+/// Items on a shared board are encrypted with the board's own key instead of
+/// the member's key, so every member can read them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardKey {
+    pub name: String,
+    pub key: [u8; 32],
+}
+
+/// X25519 secret derived from a user's encryption key. Every device holding
+/// the key derives the same identity, so nothing new has to be stored.
+fn identity_secret(user_key: &[u8; 32]) -> StaticSecret {
+    let mut bytes = [0u8; 32];
+    Hkdf::<Sha256>::new(None, user_key)
+        .expand(b"taskbook identity", &mut bytes)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    StaticSecret::from(bytes)
+}
+
+/// Public half of the identity derived from `user_key`, published on the
+/// server so other users can share boards with this one.
+pub fn identity_public_key(user_key: &[u8; 32]) -> [u8; 32] {
+    PublicKey::from(&identity_secret(user_key)).to_bytes()
+}
+
+/// AES key for one wrapped board key, from the X25519 shared secret
+fn wrapping_key(shared: &[u8], ephemeral: &[u8; 32], recipient: &[u8; 32]) -> [u8; 32] {
+    let mut salt = [0u8; 2 * PUBLIC_KEY_LEN];
+    salt[..PUBLIC_KEY_LEN].copy_from_slice(ephemeral);
+    salt[PUBLIC_KEY_LEN..].copy_from_slice(recipient);
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(b"taskbook board key", &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+/// Encrypt a board key so that only the holder of `recipient`'s identity can
+/// read it.
+///
+/// Uses an ephemeral X25519 key agreement; the result is the ephemeral
+/// public key, the nonce and the AES-256-GCM ciphertext, concatenated.
+pub fn wrap_board_key(recipient: &[u8; 32], board: &BoardKey) -> Result<Vec<u8>, CommonError> {
+    let plaintext = serde_json::to_vec(board).map_err(CommonError::Json)?;
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral).to_bytes();
+    let shared = ephemeral.diffie_hellman(&PublicKey::from(*recipient));
+
+    let key = wrapping_key(shared.as_bytes(), &ephemeral_public, recipient);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let nonce = Aes256Gcm::generate_nonce(OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_ref())
+        .map_err(|_| CommonError::DecryptionFailed)?;
+
+    let mut wrapped = Vec::with_capacity(PUBLIC_KEY_LEN + NONCE_LEN + ciphertext.len());
+    wrapped.extend_from_slice(&ephemeral_public);
+    wrapped.extend_from_slice(&nonce);
+    wrapped.extend_from_slice(&ciphertext);
+    Ok(wrapped)
+}
+
+/// Decrypt a board key wrapped for the identity derived from `user_key`.
+pub fn unwrap_board_key(user_key: &[u8; 32], wrapped: &[u8]) -> Result<BoardKey, CommonError> {
+    if wrapped.len() <= PUBLIC_KEY_LEN + NONCE_LEN {
+        return Err(CommonError::DecryptionFailed);
+    }
+    let (ephemeral_public, rest) = wrapped.split_at(PUBLIC_KEY_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let mut ephemeral = [0u8; PUBLIC_KEY_LEN];
+    ephemeral.copy_from_slice(ephemeral_public);
+
+    let secret = identity_secret(user_key);
+    let recipient = PublicKey::from(&secret).to_bytes();
+    let shared = secret.diffie_hellman(&PublicKey::from(ephemeral));
+
+    let key = wrapping_key(shared.as_bytes(), &ephemeral, &recipient);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| CommonError::DecryptionFailed)?;
+
+    serde_json::from_slice(&plaintext).map_err(CommonError::Json)
 }
 
 #[cfg(test)]
@@ -89,6 +321,25 @@ mod tests {
         assert!(decrypted.is_task());
     }
 
+    #[test]
+    fn test_decrypt_legacy_item_keeps_its_uuid() {
+        let key = generate_key();
+        let mut legacy = serde_json::to_value(StorageItem::Task(Task::new(
+            7,
+            "Old task".to_string(),
+            vec!["My Board".to_string()],
+            1,
+        )))
+        .unwrap();
+        legacy.as_object_mut().unwrap().remove("_uuid");
+        let encrypted = encrypt_json(&key, &legacy).unwrap();
+
+        let first = decrypt_item(&key, &encrypted).unwrap();
+        let second = decrypt_item(&key, &encrypted).unwrap();
+        assert_eq!(first.uuid(), second.uuid());
+        assert_eq!(first.uuid(), crate::legacy_uuid(&legacy));
+    }
+
     #[test]
     fn test_encrypt_decrypt_note_roundtrip() {
         let key = generate_key();
@@ -155,4 +406,91 @@ mod tests {
         // Ciphertext should differ (due to different nonces)
         assert_ne!(enc1.data, enc2.data);
     }
+
+    #[test]
+    fn test_board_key_roundtrip_for_recipient_only() {
+        let alice = generate_key();
+        let mallory = generate_key();
+        let board = BoardKey {
+            name: "coding".to_string(),
+            key: generate_key(),
+        };
+
+        let wrapped = wrap_board_key(&identity_public_key(&alice), &board).unwrap();
+        assert_eq!(unwrap_board_key(&alice, &wrapped).unwrap(), board);
+        assert!(unwrap_board_key(&mallory, &wrapped).is_err());
+        assert!(unwrap_board_key(&alice, &wrapped[..40]).is_err());
+    }
+
+    #[test]
+    fn test_derive_key_depends_on_passphrase_and_salt() {
+        let params = KeyParams::generate();
+        let key = derive_key("correct horse battery", &params).unwrap();
+        assert_eq!(derive_key("correct horse battery", &params).unwrap(), key);
+        assert_ne!(derive_key("correct horse batter", &params).unwrap(), key);
+        assert_ne!(
+            derive_key("correct horse battery", &KeyParams::generate()).unwrap(),
+            key
+        );
+
+        let unknown = KeyParams {
+            kdf: "md5".to_string(),
+            ..params
+        };
+        assert!(derive_key("correct horse battery", &unknown).is_err());
+    }
+
+    #[test]
+    fn test_key_params_carry_their_cost() {
+        // Headers from before the cost was stored use the defaults
+        let old: KeyParams =
+            serde_json::from_str(r#"{"kdf": "argon2id", "salt": "c2FsdHNhbHRzYWx0c2FsdA=="}"#)
+                .unwrap();
+        assert_eq!(old.cost, KdfCost::default());
+        let explicit = KeyParams {
+            cost: KdfCost::default(),
+            ..old.clone()
+        };
+        let json = serde_json::to_string(&explicit).unwrap();
+        assert!(json.contains(r#""memory_kib":19456"#));
+        assert_eq!(
+            derive_key("pass", &old).unwrap(),
+            derive_key("pass", &serde_json::from_str(&json).unwrap()).unwrap()
+        );
+
+        let cheaper = KeyParams {
+            cost: KdfCost {
+                memory_kib: 8 * 1024,
+                iterations: 1,
+                parallelism: 1,
+            },
+            ..old.clone()
+        };
+        assert_ne!(
+            derive_key("pass", &cheaper).unwrap(),
+            derive_key("pass", &old).unwrap()
+        );
+
+        let huge = KdfCost {
+            memory_kib: u32::MAX,
+            ..KdfCost::default()
+        };
+        assert!(huge.validate().is_err());
+        assert!(KdfCost {
+            parallelism: 0,
+            ..KdfCost::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_identity_is_derived_from_the_user_key() {
+        let key = generate_key();
+        assert_eq!(identity_public_key(&key), identity_public_key(&key));
+        assert_ne!(
+            identity_public_key(&key),
+            identity_public_key(&generate_key())
+        );
+    }
 }
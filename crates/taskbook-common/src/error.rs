@@ -10,6 +10,9 @@ pub enum CommonError {
 
     #[error("Decryption failed: ciphertext authentication error")]
     DecryptionFailed,
+
+    #[error("Key derivation failed: {0}")]
+    KeyDerivation(String),
 }
 
 pub type CommonResult<T> = std::result::Result<T, CommonError>;
@@ -1,8 +1,13 @@
 pub mod api;
 pub mod board;
+pub mod due;
 pub mod encryption;
 pub mod error;
 pub mod models;
+pub mod priority;
 
 pub use error::{CommonError, CommonResult};
-pub use models::{Item, Note, StorageItem, Task};
+pub use models::{
+    fill_legacy_uuid, legacy_uuid, Activity, Comment, FocusSession, Item, LinkedCommit, Note,
+    StorageItem, Task,
+};
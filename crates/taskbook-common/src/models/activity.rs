@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Most entries kept in an item's activity log; older ones are dropped
+pub const MAX_ACTIVITY: usize = 100;
+
+/// Something that happened to an item, such as being checked or moved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Activity {
+    /// When it happened (ms since epoch)
+    pub at: i64,
+
+    /// What happened: "created", "checked", "moved to @work" and so on
+    pub action: String,
+}
@@ -1,6 +1,7 @@
 /// Common trait for all items (tasks and notes)
 pub trait Item {
     fn id(&self) -> u64;
+    fn uuid(&self) -> &str;
     fn date(&self) -> &str;
     fn timestamp(&self) -> i64;
     fn description(&self) -> &str;
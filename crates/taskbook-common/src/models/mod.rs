@@ -1,12 +1,50 @@
+mod activity;
 mod item;
 mod note;
 mod task;
 
+pub use activity::{Activity, MAX_ACTIVITY};
 pub use item::Item;
 pub use note::Note;
-pub use task::Task;
+pub use task::{Comment, FocusSession, LinkedCommit, Task};
 
 use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Format of the stored `_date` field, kept as written by the original
+/// Node.js taskbook. Display code formats `created_date()` instead.
+pub const STORAGE_DATE_FORMAT: &str = "%a %b %d %Y";
+
+/// Generate a new random item UUID.
+///
+/// Also used as the serde default so items written before UUIDs existed
+/// get one on load; it is persisted on the next write.
+pub(crate) fn generate_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// UUID for a stored item written before UUIDs existed, derived from its
+/// stored JSON so that it is the same on every read until the item is
+/// next written.
+pub fn legacy_uuid(item: &serde_json::Value) -> String {
+    let digest = Sha256::digest(item.to_string().as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_custom_bytes(bytes)
+        .into_uuid()
+        .to_string()
+}
+
+/// Give a stored item written before UUIDs existed its [`legacy_uuid`],
+/// before it is deserialized. Items that have a UUID are left as they are.
+pub fn fill_legacy_uuid(item: &mut serde_json::Value) {
+    if item.get("_uuid").is_none() {
+        let uuid = legacy_uuid(item);
+        if let Some(fields) = item.as_object_mut() {
+            fields.insert("_uuid".to_string(), uuid.into());
+        }
+    }
+}
 
 /// Unified storage item that can be either a Task or Note.
 ///
@@ -51,6 +89,13 @@ impl Item for StorageItem {
         }
     }
 
+    fn uuid(&self) -> &str {
+        match self {
+            StorageItem::Task(t) => &t.uuid,
+            StorageItem::Note(n) => &n.uuid,
+        }
+    }
+
     fn date(&self) -> &str {
         match self {
             StorageItem::Task(t) => &t.date,
@@ -109,6 +154,20 @@ impl StorageItem {
         }
     }
 
+    pub fn uuid(&self) -> &str {
+        match self {
+            StorageItem::Task(t) => &t.uuid,
+            StorageItem::Note(n) => &n.uuid,
+        }
+    }
+
+    pub fn set_id(&mut self, id: u64) {
+        match self {
+            StorageItem::Task(t) => t.id = id,
+            StorageItem::Note(n) => n.id = id,
+        }
+    }
+
     pub fn date(&self) -> &str {
         match self {
             StorageItem::Task(t) => &t.date,
@@ -183,6 +242,21 @@ impl StorageItem {
         }
     }
 
+    /// Place on its boards when they are sorted by hand
+    pub fn position(&self) -> Option<u64> {
+        match self {
+            StorageItem::Task(t) => t.position,
+            StorageItem::Note(n) => n.position,
+        }
+    }
+
+    pub fn set_position(&mut self, position: Option<u64>) {
+        match self {
+            StorageItem::Task(t) => t.position = position,
+            StorageItem::Note(n) => n.position = position,
+        }
+    }
+
     pub fn as_task(&self) -> Option<&Task> {
         match self {
             StorageItem::Task(t) => Some(t),
@@ -222,6 +296,62 @@ impl StorageItem {
         }
     }
 
+    /// Files and URLs attached to the item
+    pub fn attachments(&self) -> &[String] {
+        match self {
+            StorageItem::Task(t) => &t.attachments,
+            StorageItem::Note(n) => &n.attachments,
+        }
+    }
+
+    pub fn attachments_mut(&mut self) -> &mut Vec<String> {
+        match self {
+            StorageItem::Task(t) => &mut t.attachments,
+            StorageItem::Note(n) => &mut n.attachments,
+        }
+    }
+
+    /// What happened to the item, oldest first
+    pub fn activity(&self) -> &[Activity] {
+        match self {
+            StorageItem::Task(t) => &t.activity,
+            StorageItem::Note(n) => &n.activity,
+        }
+    }
+
+    /// Add `action` to the activity log, dropping the oldest entries past
+    /// `MAX_ACTIVITY`
+    pub fn log_activity(&mut self, at: i64, action: String) {
+        let log = match self {
+            StorageItem::Task(t) => &mut t.activity,
+            StorageItem::Note(n) => &mut n.activity,
+        };
+        log.push(Activity { at, action });
+        if log.len() > MAX_ACTIVITY {
+            log.drain(..log.len() - MAX_ACTIVITY);
+        }
+    }
+
+    /// Local calendar date the item was created on, from its timestamp
+    /// (or the stored `_date` if the timestamp is out of range). Use this
+    /// rather than `date()` to group or compare items by day.
+    pub fn created_date(&self) -> Option<chrono::NaiveDate> {
+        use chrono::TimeZone;
+        chrono::Local
+            .timestamp_millis_opt(self.timestamp())
+            .single()
+            .map(|dt| dt.date_naive())
+            .or_else(|| chrono::NaiveDate::parse_from_str(self.date(), STORAGE_DATE_FORMAT).ok())
+    }
+
+    /// Due date of a task; notes have none
+    pub fn due_date(&self) -> Option<chrono::NaiveDate> {
+        match self {
+            StorageItem::Task(t) => t.due,
+            StorageItem::Note(_) => None,
+        }
+    }
+
     /// Get the note body content, if this is a note with body
     pub fn note_body(&self) -> Option<&str> {
         match self {
@@ -3,6 +3,7 @@ use std::borrow::Cow;
 use serde::{Deserialize, Serialize};
 
 use super::item::Item;
+use super::{generate_uuid, Activity, STORAGE_DATE_FORMAT};
 use crate::board;
 
 /// A note item (non-task)
@@ -11,6 +12,10 @@ pub struct Note {
     #[serde(rename = "_id")]
     pub id: u64,
 
+    /// Stable identity across archive/restore and devices; `id` is only a display handle
+    #[serde(rename = "_uuid", default = "generate_uuid")]
+    pub uuid: String,
+
     #[serde(rename = "_date")]
     pub date: String,
 
@@ -35,6 +40,19 @@ pub struct Note {
 
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
+
+    /// Place on its boards when they are sorted by hand; items without one
+    /// follow, by ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<u64>,
+
+    /// Files (absolute paths) and URLs attached to the note
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<String>,
+
+    /// What happened to the note, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub activity: Vec<Activity>,
 }
 
 impl Note {
@@ -42,7 +60,8 @@ impl Note {
         let now = chrono::Local::now();
         Self {
             id,
-            date: now.format("%a %b %d %Y").to_string(),
+            uuid: generate_uuid(),
+            date: now.format(STORAGE_DATE_FORMAT).to_string(),
             timestamp: now.timestamp_millis(),
             is_task_flag: false,
             description,
@@ -50,6 +69,9 @@ impl Note {
             is_starred: false,
             boards,
             tags: Vec::new(),
+            position: None,
+            attachments: Vec::new(),
+            activity: Vec::new(),
         }
     }
 
@@ -63,7 +85,8 @@ impl Note {
         let now = chrono::Local::now();
         Self {
             id,
-            date: now.format("%a %b %d %Y").to_string(),
+            uuid: generate_uuid(),
+            date: now.format(STORAGE_DATE_FORMAT).to_string(),
             timestamp: now.timestamp_millis(),
             is_task_flag: false,
             description,
@@ -71,6 +94,9 @@ impl Note {
             is_starred: false,
             boards,
             tags: Vec::new(),
+            position: None,
+            attachments: Vec::new(),
+            activity: Vec::new(),
         }
     }
 
@@ -123,6 +149,10 @@ impl Item for Note {
         self.id
     }
 
+    fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
     fn date(&self) -> &str {
         &self.date
     }
@@ -1,6 +1,8 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
 use super::item::Item;
+use super::{generate_uuid, Activity, STORAGE_DATE_FORMAT};
 use crate::board;
 
 /// A task item with completion status and priority
@@ -9,6 +11,10 @@ pub struct Task {
     #[serde(rename = "_id")]
     pub id: u64,
 
+    /// Stable identity across archive/restore and devices; `id` is only a display handle
+    #[serde(rename = "_uuid", default = "generate_uuid")]
+    pub uuid: String,
+
     #[serde(rename = "_date")]
     pub date: String,
 
@@ -29,13 +35,94 @@ pub struct Task {
     #[serde(rename = "inProgress")]
     pub in_progress: bool,
 
+    /// When the task was last checked off (ms since epoch); cleared when unchecked
+    #[serde(
+        rename = "completedAt",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub completed_at: Option<i64>,
+
     pub priority: u8,
 
+    #[serde(rename = "dueDate", default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+
     #[serde(deserialize_with = "board::deserialize_boards")]
     pub boards: Vec<String>,
 
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
+
+    /// Place on its boards when they are sorted by hand; items without one
+    /// follow, by ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<u64>,
+
+    /// Web page the task stands for, such as an imported issue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+
+    /// Focus sessions spent on this task
+    #[serde(
+        rename = "focusSessions",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub focus_sessions: Vec<FocusSession>,
+
+    /// Git commits that referenced this task
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<LinkedCommit>,
+
+    /// Files (absolute paths) and URLs attached to the task
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<String>,
+
+    /// Comments left on the task, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<Comment>,
+
+    /// What happened to the task, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub activity: Vec<Activity>,
+}
+
+/// A completed focus (pomodoro) session logged against a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FocusSession {
+    /// Session start (ms since epoch)
+    #[serde(rename = "startedAt")]
+    pub started_at: i64,
+
+    /// Time spent, in seconds
+    pub seconds: u64,
+}
+
+/// A git commit whose message referenced a task (`tb:#12`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkedCommit {
+    /// Full commit hash
+    pub hash: String,
+
+    /// First line of the commit message
+    pub summary: String,
+
+    /// Commit time (ms since epoch)
+    #[serde(rename = "committedAt")]
+    pub committed_at: i64,
+}
+
+/// A remark left on a task, such as what it is waiting on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Comment {
+    /// Who wrote it (the `author` config entry, or the login user name)
+    pub author: String,
+
+    /// When it was written (ms since epoch)
+    pub timestamp: i64,
+
+    pub text: String,
 }
 
 impl Task {
@@ -44,16 +131,26 @@ impl Task {
         let now = chrono::Local::now();
         Self {
             id,
-            date: now.format("%a %b %d %Y").to_string(),
+            uuid: generate_uuid(),
+            date: now.format(STORAGE_DATE_FORMAT).to_string(),
             timestamp: now.timestamp_millis(),
             is_task_flag: true,
             description,
             is_starred: false,
             is_complete: false,
             in_progress: false,
+            completed_at: None,
             priority: priority.clamp(1, 3),
+            due: None,
             boards,
             tags: Vec::new(),
+            position: None,
+            link: None,
+            focus_sessions: Vec::new(),
+            commits: Vec::new(),
+            attachments: Vec::new(),
+            comments: Vec::new(),
+            activity: Vec::new(),
         }
     }
 
@@ -69,6 +166,21 @@ impl Task {
         task.tags = tags;
         task
     }
+
+    /// Total time logged in focus sessions, in seconds
+    pub fn focused_seconds(&self) -> u64 {
+        self.focus_sessions.iter().map(|s| s.seconds).sum()
+    }
+
+    /// Mark the task complete or incomplete, recording when it was completed.
+    pub fn set_complete(&mut self, complete: bool) {
+        if complete && !self.is_complete {
+            self.completed_at = Some(chrono::Utc::now().timestamp_millis());
+        } else if !complete {
+            self.completed_at = None;
+        }
+        self.is_complete = complete;
+    }
 }
 
 impl Item for Task {
@@ -76,6 +188,10 @@ impl Item for Task {
         self.id
     }
 
+    fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
     fn date(&self) -> &str {
         &self.date
     }
@@ -108,6 +224,7 @@ impl Item for Task {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::StorageItem;
 
     #[test]
     fn test_task_is_task_uses_flag() {
@@ -116,6 +233,20 @@ mod tests {
         assert!(task.is_task_flag);
     }
 
+    #[test]
+    fn test_created_date_falls_back_to_stored_date() {
+        let mut task = Task::new(1, "Test".to_string(), vec!["My Board".to_string()], 1);
+        let today = chrono::Local::now().date_naive();
+        assert_eq!(StorageItem::Task(task.clone()).created_date(), Some(today));
+
+        task.timestamp = i64::MAX;
+        task.date = "Tue Mar 05 2024".to_string();
+        assert_eq!(
+            StorageItem::Task(task).created_date(),
+            NaiveDate::from_ymd_opt(2024, 3, 5)
+        );
+    }
+
     #[test]
     fn test_priority_clamped_to_range() {
         let low = Task::new(1, "Test".to_string(), vec!["My Board".to_string()], 0);
@@ -127,4 +258,63 @@ mod tests {
         let mid = Task::new(3, "Test".to_string(), vec!["My Board".to_string()], 2);
         assert_eq!(mid.priority, 2);
     }
+
+    #[test]
+    fn test_uuid_generated_and_preserved() {
+        let task = Task::new(1, "Test".to_string(), vec!["My Board".to_string()], 1);
+        assert_eq!(task.uuid.len(), 36);
+
+        let json = serde_json::to_string(&task).unwrap();
+        let deserialized: Task = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.uuid, task.uuid);
+    }
+
+    #[test]
+    fn test_legacy_task_without_uuid_gets_one() {
+        let json = r#"{
+            "_id": 1,
+            "_date": "Mon Jan 01 2024",
+            "_timestamp": 1704067200000,
+            "_isTask": true,
+            "description": "Old task",
+            "isStarred": false,
+            "isComplete": false,
+            "inProgress": false,
+            "priority": 1,
+            "boards": ["My Board"]
+        }"#;
+        let task: Task = serde_json::from_str(json).unwrap();
+        assert!(!task.uuid.is_empty());
+    }
+
+    #[test]
+    fn test_due_date_serialization() {
+        let mut task = Task::new(1, "Test".to_string(), vec!["My Board".to_string()], 1);
+        let json = serde_json::to_string(&task).unwrap();
+        assert!(!json.contains("dueDate"));
+
+        task.due = NaiveDate::from_ymd_opt(2024, 5, 1);
+        let json = serde_json::to_string(&task).unwrap();
+        assert!(json.contains("\"dueDate\":\"2024-05-01\""));
+        let deserialized: Task = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.due, task.due);
+    }
+
+    #[test]
+    fn test_set_complete_records_timestamp() {
+        let mut task = Task::new(1, "Test".to_string(), vec!["My Board".to_string()], 1);
+        assert_eq!(task.completed_at, None);
+
+        task.set_complete(true);
+        let completed_at = task.completed_at.expect("completion time recorded");
+        assert!(completed_at >= task.timestamp);
+
+        // Completing again keeps the original time
+        task.set_complete(true);
+        assert_eq!(task.completed_at, Some(completed_at));
+
+        task.set_complete(false);
+        assert!(!task.is_complete);
+        assert_eq!(task.completed_at, None);
+    }
 }
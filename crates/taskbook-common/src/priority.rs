@@ -0,0 +1,65 @@
+//! Task priority levels and their names, shared by the CLI and TUI.
+
+/// Prefix for priority tokens in item input, e.g. `p:high`
+pub const PRIORITY_PREFIX: &str = "p:";
+
+pub const NORMAL: u8 = 1;
+pub const MEDIUM: u8 = 2;
+pub const HIGH: u8 = 3;
+
+/// Parse a priority level: `1`-`3` or a name (`normal`/`low`, `medium`/`med`,
+/// `high`), case-insensitively.
+pub fn parse_priority(spec: &str) -> Option<u8> {
+    match spec.trim().to_lowercase().as_str() {
+        "1" | "normal" | "low" => Some(NORMAL),
+        "2" | "medium" | "med" => Some(MEDIUM),
+        "3" | "high" => Some(HIGH),
+        _ => None,
+    }
+}
+
+/// Parse a `p:<level>` token from item input
+pub fn parse_priority_token(word: &str) -> Option<u8> {
+    word.strip_prefix(PRIORITY_PREFIX).and_then(parse_priority)
+}
+
+/// Name of a priority level
+pub fn priority_name(level: u8) -> &'static str {
+    match level {
+        HIGH => "high",
+        MEDIUM => "medium",
+        _ => "normal",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_priority_names_and_digits() {
+        assert_eq!(parse_priority("1"), Some(NORMAL));
+        assert_eq!(parse_priority("low"), Some(NORMAL));
+        assert_eq!(parse_priority("Normal"), Some(NORMAL));
+        assert_eq!(parse_priority("med"), Some(MEDIUM));
+        assert_eq!(parse_priority("2"), Some(MEDIUM));
+        assert_eq!(parse_priority("HIGH"), Some(HIGH));
+        assert_eq!(parse_priority("4"), None);
+        assert_eq!(parse_priority("urgent"), None);
+    }
+
+    #[test]
+    fn test_parse_priority_token() {
+        assert_eq!(parse_priority_token("p:high"), Some(HIGH));
+        assert_eq!(parse_priority_token("p:2"), Some(MEDIUM));
+        assert_eq!(parse_priority_token("p:"), None);
+        assert_eq!(parse_priority_token("high"), None);
+    }
+
+    #[test]
+    fn test_priority_name() {
+        assert_eq!(priority_name(HIGH), "high");
+        assert_eq!(priority_name(MEDIUM), "medium");
+        assert_eq!(priority_name(NORMAL), "normal");
+    }
+}
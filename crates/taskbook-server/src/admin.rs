@@ -0,0 +1,294 @@
+//! `tb-server admin`: server administration from the command line.
+//!
+//! Most commands log in to a running server as an admin and call the admin
+//! endpoints, so they work from anywhere the API is reachable. `promote` and
+//! `demote` change the database directly instead, which is how the first
+//! admin is made.
+
+use std::io::{self, BufRead, Write};
+
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::error::{Result, ServerError};
+use crate::handlers::admin::{AdminUserInfo, AdminUsersResponse, PurgeResponse};
+use crate::quota::format_bytes;
+
+/// Order of `admin users`
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum SortBy {
+    Name,
+    /// Most storage first
+    Storage,
+    /// Least recently active first
+    Activity,
+}
+
+/// `tb-server admin promote|demote <username>`
+pub async fn set_admin(pool: &DbPool, username: &str, is_admin: bool) -> Result<()> {
+    let result = sqlx::query("UPDATE users SET is_admin = $1 WHERE username = $2")
+        .bind(is_admin)
+        .bind(username)
+        .execute(pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServerError::NotFound(format!("no user {username}")));
+    }
+    if is_admin {
+        println!("{username} is now an admin.");
+    } else {
+        println!("{username} is no longer an admin.");
+    }
+    Ok(())
+}
+
+/// A logged-in session with the server's admin API
+pub struct AdminClient {
+    http: Client,
+    server: String,
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct SessionsResponse {
+    sessions: Vec<SessionInfo>,
+}
+
+#[derive(Deserialize)]
+struct SessionInfo {
+    id: Uuid,
+    current: bool,
+}
+
+impl AdminClient {
+    /// Log in as `username`. The password is read from `TB_ADMIN_PASSWORD`,
+    /// or asked for, as is a two-factor code when the account needs one.
+    pub async fn login(server: &str, username: &str) -> std::result::Result<Self, String> {
+        let http = Client::builder()
+            .user_agent(concat!("tb-server/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| format!("failed to create HTTP client: {e}"))?;
+        let server = server.trim_end_matches('/').to_string();
+        let password = match std::env::var("TB_ADMIN_PASSWORD") {
+            Ok(password) => password,
+            Err(_) => rpassword::prompt_password(format!("Password for {username}: "))
+                .map_err(|e| format!("failed to read password: {e}"))?,
+        };
+
+        let mut body = json!({ "username": username, "password": password });
+        loop {
+            let resp = http
+                .post(format!("{server}/api/v1/login"))
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| format!("could not reach {server}: {e}"))?;
+            if resp.status() == StatusCode::UNAUTHORIZED && body.get("totp_code").is_none() {
+                let error: Value = resp.json().await.unwrap_or_default();
+                if error["totp_required"].as_bool() == Some(true) {
+                    body["totp_code"] = prompt("Two-factor code: ")?.into();
+                    continue;
+                }
+                return Err(error_message(&error, StatusCode::UNAUTHORIZED));
+            }
+            let LoginResponse { token } = parse(resp).await?;
+            return Ok(Self {
+                http,
+                server,
+                token,
+            });
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{path}", self.server))
+            .bearer_auth(&self.token)
+    }
+
+    pub async fn users(&self) -> std::result::Result<Vec<AdminUserInfo>, String> {
+        let resp = send(self.request(Method::GET, "/api/v1/admin/users")).await?;
+        let AdminUsersResponse { users } = parse(resp).await?;
+        Ok(users)
+    }
+
+    pub async fn set_disabled(
+        &self,
+        username: &str,
+        disabled: bool,
+    ) -> std::result::Result<(), String> {
+        let path = format!("/api/v1/admin/users/{username}/disabled");
+        let resp = send(
+            self.request(Method::PUT, &path)
+                .json(&json!({ "disabled": disabled })),
+        )
+        .await?;
+        check(resp).await
+    }
+
+    pub async fn set_quota(
+        &self,
+        username: &str,
+        quota_bytes: Option<i64>,
+    ) -> std::result::Result<(), String> {
+        let path = format!("/api/v1/admin/users/{username}/quota");
+        let resp = send(
+            self.request(Method::PUT, &path)
+                .json(&json!({ "quota_bytes": quota_bytes })),
+        )
+        .await?;
+        check(resp).await
+    }
+
+    pub async fn purge(
+        &self,
+        inactive_days: i64,
+        dry_run: bool,
+    ) -> std::result::Result<Vec<String>, String> {
+        let resp = send(
+            self.request(Method::POST, "/api/v1/admin/purge")
+                .json(&json!({ "inactive_days": inactive_days, "dry_run": dry_run })),
+        )
+        .await?;
+        let PurgeResponse { users } = parse(resp).await?;
+        Ok(users)
+    }
+
+    /// End the session this client logged in with, leaving the account's
+    /// other devices logged in.
+    pub async fn logout(self) -> std::result::Result<(), String> {
+        let resp = send(self.request(Method::GET, "/api/v1/sessions")).await?;
+        let SessionsResponse { sessions } = parse(resp).await?;
+        if let Some(session) = sessions.iter().find(|s| s.current) {
+            let path = format!("/api/v1/sessions/{}", session.id);
+            check(send(self.request(Method::DELETE, &path)).await?).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn send(request: RequestBuilder) -> std::result::Result<reqwest::Response, String> {
+    request
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {e}"))
+}
+
+async fn check(resp: reqwest::Response) -> std::result::Result<(), String> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let error: Value = resp.json().await.unwrap_or_default();
+    Err(error_message(&error, status))
+}
+
+async fn parse<T: DeserializeOwned>(resp: reqwest::Response) -> std::result::Result<T, String> {
+    let status = resp.status();
+    if !status.is_success() {
+        let error: Value = resp.json().await.unwrap_or_default();
+        return Err(error_message(&error, status));
+    }
+    resp.json()
+        .await
+        .map_err(|e| format!("unexpected response from server: {e}"))
+}
+
+fn error_message(error: &Value, status: StatusCode) -> String {
+    match error["error"].as_str() {
+        Some(message) => format!("{message} ({status})"),
+        None => format!("server returned {status}"),
+    }
+}
+
+fn prompt(label: &str) -> std::result::Result<String, String> {
+    print!("{label}");
+    io::stdout().flush().map_err(|e| e.to_string())?;
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| format!("failed to read input: {e}"))?;
+    Ok(line.trim().to_string())
+}
+
+/// `tb-server admin users`
+pub fn print_users(mut users: Vec<AdminUserInfo>, sort: SortBy) {
+    if users.is_empty() {
+        println!("No users.");
+        return;
+    }
+    match sort {
+        SortBy::Name => users.sort_by(|a, b| a.username.cmp(&b.username)),
+        SortBy::Storage => users.sort_by_key(|u| std::cmp::Reverse(u.storage_bytes)),
+        SortBy::Activity => users.sort_by_key(|u| u.last_active_at),
+    }
+
+    let name_width = users
+        .iter()
+        .map(|u| u.username.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let now = Utc::now();
+    println!(
+        "{:name_width$}  {:>6}  {:>9}  {:>9}  {:<12}  EMAIL",
+        "USER", "ITEMS", "STORAGE", "QUOTA", "LAST ACTIVE"
+    );
+    for user in &users {
+        let mut flags = Vec::new();
+        if user.is_admin {
+            flags.push("admin");
+        }
+        if user.disabled {
+            flags.push("disabled");
+        }
+        if !user.email_verified {
+            flags.push("unverified");
+        }
+        if user.custom_quota {
+            flags.push("own quota");
+        }
+        println!(
+            "{:name_width$}  {:>6}  {:>9}  {:>9}  {:<12}  {}{}",
+            user.username,
+            user.items,
+            format_bytes(user.storage_bytes),
+            user.quota_bytes
+                .map(format_bytes)
+                .unwrap_or_else(|| "-".to_string()),
+            format_last_active(user.last_active_at, now),
+            user.email,
+            if flags.is_empty() {
+                String::new()
+            } else {
+                format!("  [{}]", flags.join(", "))
+            }
+        );
+    }
+    let total: i64 = users.iter().map(|u| u.storage_bytes).sum();
+    println!("{} users, {} stored", users.len(), format_bytes(total));
+}
+
+fn format_last_active(at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> String {
+    let Some(at) = at else {
+        return "never".to_string();
+    };
+    match (now - at).num_days() {
+        0 => "today".to_string(),
+        1 => "1 day ago".to_string(),
+        days => format!("{days} days ago"),
+    }
+}
@@ -1,6 +1,9 @@
 use argon2::password_hash::rand_core::OsRng;
 use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use base64::Engine as _;
+use rand::Rng;
+use sha2::{Digest, Sha256};
 
 /// Hash a password using Argon2id.
 pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
@@ -17,3 +20,16 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool, argon2::passw
         .verify_password(password.as_bytes(), &parsed_hash)
         .is_ok())
 }
+
+/// A random 256-bit token, URL-safe base64 encoded.
+pub fn random_token() -> String {
+    let mut token_bytes = [0u8; 32];
+    rand::thread_rng().fill(&mut token_bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(token_bytes)
+}
+
+/// How a token that is only ever compared is stored, so a copy of the
+/// database cannot be used to sign in.
+pub fn hash_token(token: &str) -> Vec<u8> {
+    Sha256::digest(token.as_bytes()).to_vec()
+}
@@ -0,0 +1,6 @@
+//! `tb-server-sqlite`: the sync server, with SQLite.
+
+#[tokio::main]
+async fn main() {
+    taskbook_server::cli::run().await;
+}
@@ -0,0 +1,308 @@
+//! The command line of `tb-server` and `tb-server-sqlite`.
+
+use std::net::SocketAddr;
+
+use clap::{Parser, Subcommand};
+use tokio::net::TcpListener;
+
+use crate::config::ServerConfig;
+use crate::db::DbPool;
+use crate::{admin, db, invites, mailer, quota, router, shutdown_signal, telemetry};
+
+/// Name of the server binary, which depends on the database it is built for
+const NAME: &str = if cfg!(feature = "sqlite") {
+    "tb-server-sqlite"
+} else {
+    "tb-server"
+};
+
+/// Server for taskbook sync. Configured with environment variables; see
+/// docs/server.md.
+#[derive(Parser)]
+#[command(name = NAME, version)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage invite codes, for TB_REGISTRATION_MODE=invite
+    Invite {
+        #[command(subcommand)]
+        action: InviteCommand,
+    },
+    /// Manage accounts through a running server's admin API
+    Admin {
+        /// Server to connect to (default: this machine, on TB_PORT)
+        #[arg(long)]
+        server: Option<String>,
+        /// Admin account to log in as; its password is read from
+        /// TB_ADMIN_PASSWORD or asked for
+        #[arg(long)]
+        username: Option<String>,
+        #[command(subcommand)]
+        action: AdminCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum InviteCommand {
+    /// Create an invite code and print it
+    Create {
+        /// How many accounts the code can create
+        #[arg(long, default_value_t = 1)]
+        uses: i32,
+        /// Days until the code expires (default: never)
+        #[arg(long, value_name = "DAYS")]
+        expires_days: Option<i64>,
+        /// Who the code is for, shown by `invite list`
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// List invite codes
+    List,
+    /// Delete an invite code
+    Revoke { code: String },
+}
+
+#[derive(Subcommand)]
+enum AdminCommand {
+    /// List accounts with their storage use
+    Users {
+        #[arg(long, value_enum, default_value = "name")]
+        sort: admin::SortBy,
+    },
+    /// Log an account out everywhere and keep it from logging in
+    Disable { username: String },
+    /// Let a disabled account log in again
+    Enable { username: String },
+    /// Set an account's storage quota
+    Quota {
+        username: String,
+        /// Size such as 50M or 1G, or `default` for TB_STORAGE_QUOTA
+        #[arg(value_parser = parse_quota)]
+        size: QuotaSize,
+    },
+    /// Delete accounts that have not been used for a while
+    Purge {
+        /// Delete accounts inactive for at least this many days
+        #[arg(long, value_name = "DAYS")]
+        inactive_days: i64,
+        /// Delete them; without this, only list them
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Give an account the admin role (works on the database directly)
+    Promote { username: String },
+    /// Take the admin role away (works on the database directly)
+    Demote { username: String },
+}
+
+/// Run the command given on the command line, or else serve until shut down
+pub async fn run() {
+    let args = Args::parse();
+    if let Some(command) = args.command {
+        run_command(command).await;
+        return;
+    }
+
+    let _telemetry_guard = telemetry::init_telemetry();
+
+    let config = match ServerConfig::load() {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("configuration error: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let pool = match db::create_pool(&config.database_url).await {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("failed to connect to database: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    // Run migrations
+    if let Err(e) = db::migrate(&pool).await {
+        tracing::error!("failed to run database migrations: {e}");
+        std::process::exit(1);
+    }
+
+    if _telemetry_guard.is_some() {
+        telemetry::spawn_db_pool_metrics(pool.clone());
+    }
+
+    let mailer = match config.smtp.as_ref().map(mailer::Mailer::new).transpose() {
+        Ok(m) => m,
+        Err(e) => {
+            tracing::error!("mail configuration error: {e}");
+            std::process::exit(1);
+        }
+    };
+    if mailer.is_none() {
+        tracing::info!("TB_SMTP_HOST not set, email verification and password reset disabled");
+    }
+    if config.oidc.is_some() {
+        tracing::info!("single sign-on enabled");
+    }
+
+    let app = router::build(pool, &config, mailer);
+    let addr = SocketAddr::from((config.host, config.port));
+
+    tracing::info!("starting taskbook server on {}", addr);
+
+    let listener = match TcpListener::bind(addr).await {
+        Ok(l) => l,
+        Err(e) => {
+            tracing::error!("failed to bind address {addr}: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .unwrap_or_else(|e| {
+        tracing::error!("server error: {e}");
+        std::process::exit(1);
+    });
+
+    tracing::info!("server shut down gracefully");
+}
+
+/// Run a maintenance command instead of serving.
+/// Output is for a terminal, so errors are printed rather than logged.
+async fn run_command(command: Command) {
+    let result = match command {
+        Command::Invite { action } => run_invite(action).await,
+        Command::Admin {
+            server,
+            username,
+            action,
+        } => run_admin(server, username, action).await,
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+async fn run_invite(action: InviteCommand) -> Result<(), String> {
+    let pool = &connect().await?;
+    match action {
+        InviteCommand::Create {
+            uses,
+            expires_days,
+            note,
+        } => invites::create(pool, uses, expires_days, note.as_deref()).await,
+        InviteCommand::List => invites::list(pool).await,
+        InviteCommand::Revoke { code } => invites::revoke(pool, &code).await,
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Bytes for `admin quota`, `None` for the server default
+#[derive(Clone, Copy)]
+struct QuotaSize(Option<i64>);
+
+/// Checked by clap, so a bad size fails before logging in
+fn parse_quota(s: &str) -> Result<QuotaSize, String> {
+    match s {
+        "default" => Ok(QuotaSize(None)),
+        size => quota::parse_size(size).map(|bytes| QuotaSize(Some(bytes))),
+    }
+}
+
+async fn run_admin(
+    server: Option<String>,
+    username: Option<String>,
+    action: AdminCommand,
+) -> Result<(), String> {
+    match action {
+        AdminCommand::Promote { username } => {
+            let pool = &connect().await?;
+            return admin::set_admin(pool, &username, true)
+                .await
+                .map_err(|e| e.to_string());
+        }
+        AdminCommand::Demote { username } => {
+            let pool = &connect().await?;
+            return admin::set_admin(pool, &username, false)
+                .await
+                .map_err(|e| e.to_string());
+        }
+        _ => {}
+    }
+
+    let server = server.unwrap_or_else(|| {
+        let port = std::env::var("TB_PORT").unwrap_or_else(|_| "8080".to_string());
+        format!("http://127.0.0.1:{port}")
+    });
+    let username = username.ok_or("--username is required to log in to the server")?;
+    let client = admin::AdminClient::login(&server, &username).await?;
+
+    let result = match action {
+        AdminCommand::Users { sort } => client
+            .users()
+            .await
+            .map(|users| admin::print_users(users, sort)),
+        AdminCommand::Disable { username } => client
+            .set_disabled(&username, true)
+            .await
+            .map(|()| println!("{username} is disabled and logged out everywhere.")),
+        AdminCommand::Enable { username } => client
+            .set_disabled(&username, false)
+            .await
+            .map(|()| println!("{username} can log in again.")),
+        AdminCommand::Quota { username, size } => {
+            let QuotaSize(quota_bytes) = size;
+            client
+                .set_quota(&username, quota_bytes)
+                .await
+                .map(|()| match quota_bytes {
+                    Some(bytes) => {
+                        println!("{username} may now store {}.", quota::format_bytes(bytes))
+                    }
+                    None => println!("{username} now has the server's default quota."),
+                })
+        }
+        AdminCommand::Purge { inactive_days, yes } => {
+            client.purge(inactive_days, !yes).await.map(|users| {
+                if users.is_empty() {
+                    println!("No accounts inactive for {inactive_days} days.");
+                } else if yes {
+                    println!("Deleted {} account(s): {}", users.len(), users.join(", "));
+                } else {
+                    println!(
+                        "Would delete {} account(s): {}\nRun again with --yes to delete them.",
+                        users.len(),
+                        users.join(", ")
+                    );
+                }
+            })
+        }
+        AdminCommand::Promote { .. } | AdminCommand::Demote { .. } => unreachable!(),
+    };
+
+    // Log out even when the command failed
+    let logout = client.logout().await;
+    result.and(logout)
+}
+
+/// Connect to the configured database and bring its schema up to date
+async fn connect() -> Result<DbPool, String> {
+    let config = ServerConfig::load().map_err(|e| format!("configuration error: {e}"))?;
+    let pool = db::create_pool(&config.database_url)
+        .await
+        .map_err(|e| format!("failed to connect to database: {e}"))?;
+    db::migrate(&pool)
+        .await
+        .map_err(|e| format!("failed to run database migrations: {e}"))?;
+    Ok(pool)
+}
@@ -1,5 +1,7 @@
 use std::net::IpAddr;
 
+use axum::http::HeaderName;
+
 /// Server configuration, loaded from environment variables.
 ///
 /// Database connection is built from individual variables:
@@ -8,27 +10,155 @@ use std::net::IpAddr;
 /// - `TB_DB_NAME` (required) - Database name
 /// - `TB_DB_USER` (required) - Database username
 /// - `TB_DB_PASSWORD` (required) - Database password
+///
+/// Built with the `sqlite` feature, the database is instead the file at
+/// `TB_DB_PATH` (default: `taskbook.db`).
+///
+/// Email (verification and password reset) is optional and only enabled
+/// when `TB_SMTP_HOST` is set; see [`SmtpConfig`]. So is single sign-on,
+/// enabled when `TB_OIDC_DISCOVERY_URL` is set; see [`OidcConfig`].
 pub struct ServerConfig {
     pub host: IpAddr,
     pub port: u16,
+    /// Postgres URL, or SQLite file path with the `sqlite` feature
     pub database_url: String,
+    /// Days a session lasts without being used
     pub session_expiry_days: i64,
+    /// Days a refresh token can renew a session whose token has expired
+    pub refresh_expiry_days: i64,
     /// Allowed CORS origins (comma-separated). If empty, defaults to restrictive.
     pub cors_origins: Vec<String>,
+    /// Who may register (`TB_REGISTRATION_MODE`, default: open)
+    pub registration_mode: RegistrationMode,
+    /// Bytes each account may store (`TB_STORAGE_QUOTA`, default: no limit)
+    pub storage_quota: Option<i64>,
+    /// Days deleted items are kept for restoring (`TB_TRASH_RETENTION_DAYS`)
+    pub trash_retention_days: i64,
+    /// Earlier versions kept of each item (`TB_ITEM_HISTORY`)
+    pub item_history: i64,
+    /// Largest request body or WebSocket message, in bytes
+    /// (`TB_MAX_BODY_SIZE`, default: 10 MiB)
+    pub max_body_size: usize,
+    /// Per-IP request limits
+    pub rate_limits: RateLimitConfig,
+    /// Outgoing mail, if configured
+    pub smtp: Option<SmtpConfig>,
+    /// Single sign-on provider, if configured
+    pub oidc: Option<OidcConfig>,
+    /// Whether webhooks may call addresses on private networks
+    /// (`TB_WEBHOOK_ALLOW_PRIVATE`, default: false)
+    pub webhook_allow_private: bool,
+}
+
+/// Per-IP request limits, counted over a sliding window:
+/// - `TB_RATE_LIMIT_WINDOW` (optional, default: 60) - Window in seconds
+/// - `TB_RATE_LIMIT_AUTH` (optional, default: 10) - Requests per window to
+///   routes that check passwords, codes or tokens
+/// - `TB_RATE_LIMIT_ITEMS` (optional, default: 300) - Requests per window to
+///   routes that read or write items
+/// - `TB_RATE_LIMIT_ROUTES` (optional) - Limits for single routes of either
+///   kind in place of the above, e.g. `/api/v1/login=5,/api/v1/items=600`
+/// - `TB_RATE_LIMIT_BURST` (optional, default: 0) - Requests allowed on top
+///   of each limit, for clients that send several at once
+/// - `TB_CLIENT_IP_HEADER` (optional) - Header a trusted reverse proxy puts
+///   the client's address in, such as `X-Forwarded-For`. Only right behind
+///   exactly one proxy, see [`RateLimits::client_ip`].
+///
+/// A limit of 0 turns it off.
+///
+/// [`RateLimits::client_ip`]: crate::rate_limit::RateLimits::client_ip
+pub struct RateLimitConfig {
+    pub window_secs: u64,
+    pub auth: usize,
+    pub items: usize,
+    /// Added to every limit
+    pub burst: usize,
+    /// Route patterns, as in the router, and their limits
+    pub routes: Vec<(String, usize)>,
+    pub client_ip_header: Option<HeaderName>,
+}
+
+/// Body size limit unless `TB_MAX_BODY_SIZE` is set
+const DEFAULT_MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+/// Who may create an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistrationMode {
+    /// Anyone who can reach the server
+    Open,
+    /// Only with an invite code from `tb-server invite create`
+    Invite,
+    /// Nobody; existing accounts keep working
+    Closed,
+    /// Only while the server has no account yet, for a server of one's own
+    Single,
+}
+
+impl std::str::FromStr for RegistrationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "open" => Ok(Self::Open),
+            "invite" => Ok(Self::Invite),
+            "closed" => Ok(Self::Closed),
+            "single" => Ok(Self::Single),
+            _ => Err("TB_REGISTRATION_MODE must be open, invite, closed or single".to_string()),
+        }
+    }
+}
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Plain connection upgraded with STARTTLS (usually port 587)
+    StartTls,
+    /// TLS from the start (usually port 465)
+    Tls,
+    /// No encryption, for local testing only
+    None,
+}
+
+/// Outgoing mail settings:
+/// - `TB_SMTP_HOST` - SMTP server; mail is disabled without it
+/// - `TB_SMTP_PORT` (optional, default: 587, or 465 with `TB_SMTP_TLS=tls`)
+/// - `TB_SMTP_TLS` (optional, default: starttls) - `starttls`, `tls` or `none`
+/// - `TB_SMTP_USERNAME` / `TB_SMTP_PASSWORD` (optional) - SMTP credentials
+/// - `TB_SMTP_FROM` (required with mail) - Sender address
+/// - `TB_PUBLIC_URL` (required with mail) - Base URL for links in emails
+#[derive(Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub credentials: Option<(String, String)>,
+    pub from: String,
+    pub public_url: String,
+}
+
+/// Single sign-on through an OpenID Connect provider:
+/// - `TB_OIDC_DISCOVERY_URL` - The provider's issuer URL or its
+///   `.well-known/openid-configuration` document; SSO is disabled without it
+/// - `TB_OIDC_CLIENT_ID` / `TB_OIDC_CLIENT_SECRET` (required with SSO) -
+///   Credentials of the client registered with the provider
+/// - `TB_OIDC_SCOPES` (optional, default: `openid email profile`)
+/// - `TB_OIDC_REGISTER` (optional, default: true) - Create accounts for
+///   people signing in for the first time, whatever `TB_REGISTRATION_MODE`
+/// - `TB_PUBLIC_URL` (required with SSO) - The provider redirects to
+///   `<TB_PUBLIC_URL>/api/v1/oidc/callback`
+#[derive(Clone)]
+pub struct OidcConfig {
+    pub discovery_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: String,
+    pub register: bool,
+    pub redirect_url: String,
 }
 
 impl ServerConfig {
     pub fn load() -> Result<Self, String> {
-        let db_host = require_env("TB_DB_HOST")?;
-        let db_port = std::env::var("TB_DB_PORT").unwrap_or_else(|_| "5432".to_string());
-        let db_name = require_env("TB_DB_NAME")?;
-        let db_user = require_env("TB_DB_USER")?;
-        let db_password = require_env("TB_DB_PASSWORD")?;
-
-        let database_url = format!(
-            "postgres://{}:{}@{}:{}/{}",
-            db_user, db_password, db_host, db_port, db_name
-        );
+        let database_url = database_url()?;
 
         let host: IpAddr = std::env::var("TB_HOST")
             .unwrap_or_else(|_| "0.0.0.0".to_string())
@@ -45,6 +175,11 @@ impl ServerConfig {
             .parse()
             .map_err(|_| "TB_SESSION_EXPIRY_DAYS must be a number".to_string())?;
 
+        let refresh_expiry_days: i64 = std::env::var("TB_REFRESH_EXPIRY_DAYS")
+            .unwrap_or_else(|_| "90".to_string())
+            .parse()
+            .map_err(|_| "TB_REFRESH_EXPIRY_DAYS must be a number".to_string())?;
+
         let cors_origins: Vec<String> = std::env::var("TB_CORS_ORIGINS")
             .unwrap_or_default()
             .split(',')
@@ -52,16 +187,341 @@ impl ServerConfig {
             .filter(|s| !s.is_empty())
             .collect();
 
+        let registration_mode: RegistrationMode = std::env::var("TB_REGISTRATION_MODE")
+            .unwrap_or_else(|_| "open".to_string())
+            .parse()?;
+
+        let storage_quota = match std::env::var("TB_STORAGE_QUOTA") {
+            Ok(quota) if !quota.trim().is_empty() => Some(
+                crate::quota::parse_size(&quota).map_err(|e| format!("TB_STORAGE_QUOTA: {e}"))?,
+            ),
+            _ => None,
+        };
+
+        let trash_retention_days: i64 = std::env::var("TB_TRASH_RETENTION_DAYS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .ok()
+            .filter(|days| *days >= 0)
+            .ok_or_else(|| "TB_TRASH_RETENTION_DAYS must be a number of days".to_string())?;
+
+        let item_history: i64 = std::env::var("TB_ITEM_HISTORY")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse()
+            .ok()
+            .filter(|count| *count >= 0)
+            .ok_or_else(|| "TB_ITEM_HISTORY must be a number of versions".to_string())?;
+
+        let max_body_size = std::env::var("TB_MAX_BODY_SIZE")
+            .ok()
+            .filter(|size| !size.trim().is_empty())
+            .map(|size| {
+                crate::quota::parse_size(&size)
+                    .ok()
+                    .filter(|bytes| *bytes > 0)
+                    .and_then(|bytes| usize::try_from(bytes).ok())
+                    .ok_or_else(|| format!("TB_MAX_BODY_SIZE: invalid size {size:?}"))
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_MAX_BODY_SIZE);
+
+        let webhook_allow_private = match std::env::var("TB_WEBHOOK_ALLOW_PRIVATE")
+            .unwrap_or_else(|_| "false".to_string())
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "true" | "1" | "yes" => true,
+            "false" | "0" | "no" => false,
+            _ => return Err("TB_WEBHOOK_ALLOW_PRIVATE must be true or false".to_string()),
+        };
+
         Ok(Self {
             host,
             port,
             database_url,
             session_expiry_days,
+            refresh_expiry_days,
             cors_origins,
+            registration_mode,
+            storage_quota,
+            trash_retention_days,
+            item_history,
+            max_body_size,
+            rate_limits: RateLimitConfig::load()?,
+            smtp: SmtpConfig::load()?,
+            oidc: OidcConfig::load()?,
+            webhook_allow_private,
         })
     }
 }
 
+impl ServerConfig {
+    /// A server of one's own on `host:port` with its database at
+    /// `database_url`: one account, no mail or single sign-on, webhooks that
+    /// may call the local network, and the defaults for everything else
+    pub fn single_user(database_url: String, host: IpAddr, port: u16) -> Self {
+        Self {
+            host,
+            port,
+            database_url,
+            session_expiry_days: 30,
+            refresh_expiry_days: 90,
+            cors_origins: Vec::new(),
+            registration_mode: RegistrationMode::Single,
+            storage_quota: None,
+            trash_retention_days: 30,
+            item_history: 10,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            rate_limits: RateLimitConfig {
+                window_secs: 60,
+                auth: 10,
+                items: 300,
+                burst: 0,
+                routes: Vec::new(),
+                client_ip_header: None,
+            },
+            smtp: None,
+            oidc: None,
+            webhook_allow_private: true,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    fn load() -> Result<Self, String> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// The limits set by the variables `var` looks up
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let number = |key: &str, default: usize| {
+            var(key)
+                .unwrap_or_else(|| default.to_string())
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("{key} must be a number"))
+        };
+
+        let window_secs = number("TB_RATE_LIMIT_WINDOW", 60)?;
+        if window_secs == 0 {
+            return Err("TB_RATE_LIMIT_WINDOW must be at least 1 second".to_string());
+        }
+
+        let client_ip_header = match var("TB_CLIENT_IP_HEADER") {
+            Some(name) if !name.trim().is_empty() => Some(
+                name.trim()
+                    .parse()
+                    .map_err(|_| format!("TB_CLIENT_IP_HEADER: invalid header name {name:?}"))?,
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            window_secs: window_secs as u64,
+            auth: number("TB_RATE_LIMIT_AUTH", 10)?,
+            items: number("TB_RATE_LIMIT_ITEMS", 300)?,
+            burst: number("TB_RATE_LIMIT_BURST", 0)?,
+            routes: parse_route_limits(&var("TB_RATE_LIMIT_ROUTES").unwrap_or_default())?,
+            client_ip_header,
+        })
+    }
+}
+
+/// Parse `TB_RATE_LIMIT_ROUTES`: comma-separated `route=limit` pairs
+fn parse_route_limits(s: &str) -> Result<Vec<(String, usize)>, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (route, limit) = pair
+                .rsplit_once('=')
+                .filter(|(route, _)| route.trim().starts_with('/'))
+                .ok_or_else(|| {
+                    format!("TB_RATE_LIMIT_ROUTES: expected /route=limit, got {pair:?}")
+                })?;
+            let limit = limit
+                .trim()
+                .parse()
+                .map_err(|_| format!("TB_RATE_LIMIT_ROUTES: invalid limit for {route}"))?;
+            Ok((route.trim().to_string(), limit))
+        })
+        .collect()
+}
+
+impl SmtpConfig {
+    fn load() -> Result<Option<Self>, String> {
+        let host = match std::env::var("TB_SMTP_HOST") {
+            Ok(host) if !host.trim().is_empty() => host.trim().to_string(),
+            _ => return Ok(None),
+        };
+
+        let tls = match std::env::var("TB_SMTP_TLS")
+            .unwrap_or_else(|_| "starttls".to_string())
+            .to_lowercase()
+            .as_str()
+        {
+            "starttls" => SmtpTls::StartTls,
+            "tls" => SmtpTls::Tls,
+            "none" => SmtpTls::None,
+            _ => return Err("TB_SMTP_TLS must be starttls, tls or none".to_string()),
+        };
+
+        let default_port = if tls == SmtpTls::Tls { "465" } else { "587" };
+        let port: u16 = std::env::var("TB_SMTP_PORT")
+            .unwrap_or_else(|_| default_port.to_string())
+            .parse()
+            .map_err(|_| "TB_SMTP_PORT must be a valid port number".to_string())?;
+
+        let credentials = match (
+            std::env::var("TB_SMTP_USERNAME"),
+            std::env::var("TB_SMTP_PASSWORD"),
+        ) {
+            (Ok(username), Ok(password)) => Some((username, password)),
+            (Err(_), Err(_)) => None,
+            _ => {
+                return Err("TB_SMTP_USERNAME and TB_SMTP_PASSWORD must be set together".to_string())
+            }
+        };
+
+        let from = require_env("TB_SMTP_FROM")?;
+        let public_url = require_env("TB_PUBLIC_URL")?
+            .trim_end_matches('/')
+            .to_string();
+
+        Ok(Some(Self {
+            host,
+            port,
+            tls,
+            credentials,
+            from,
+            public_url,
+        }))
+    }
+}
+
+impl OidcConfig {
+    fn load() -> Result<Option<Self>, String> {
+        let discovery_url = match std::env::var("TB_OIDC_DISCOVERY_URL") {
+            Ok(url) if !url.trim().is_empty() => url.trim().to_string(),
+            _ => return Ok(None),
+        };
+
+        let register = match std::env::var("TB_OIDC_REGISTER")
+            .unwrap_or_else(|_| "true".to_string())
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "true" | "1" | "yes" => true,
+            "false" | "0" | "no" => false,
+            _ => return Err("TB_OIDC_REGISTER must be true or false".to_string()),
+        };
+
+        let scopes = std::env::var("TB_OIDC_SCOPES")
+            .ok()
+            .filter(|scopes| !scopes.trim().is_empty())
+            .unwrap_or_else(|| "openid email profile".to_string());
+        if !scopes.split_whitespace().any(|scope| scope == "openid") {
+            return Err("TB_OIDC_SCOPES must include openid".to_string());
+        }
+
+        let public_url = require_env("TB_PUBLIC_URL")?;
+
+        Ok(Some(Self {
+            discovery_url,
+            client_id: require_env("TB_OIDC_CLIENT_ID")?,
+            client_secret: require_env("TB_OIDC_CLIENT_SECRET")?,
+            scopes,
+            register,
+            redirect_url: format!(
+                "{}/api/v1/oidc/callback",
+                public_url.trim().trim_end_matches('/')
+            ),
+        }))
+    }
+}
+
+/// The Postgres URL built from the `TB_DB_*` variables
+#[cfg(not(feature = "sqlite"))]
+fn database_url() -> Result<String, String> {
+    let db_host = require_env("TB_DB_HOST")?;
+    let db_port = std::env::var("TB_DB_PORT").unwrap_or_else(|_| "5432".to_string());
+    let db_name = require_env("TB_DB_NAME")?;
+    let db_user = require_env("TB_DB_USER")?;
+    let db_password = require_env("TB_DB_PASSWORD")?;
+
+    Ok(format!(
+        "postgres://{}:{}@{}:{}/{}",
+        db_user, db_password, db_host, db_port, db_name
+    ))
+}
+
+/// The SQLite database file
+#[cfg(feature = "sqlite")]
+fn database_url() -> Result<String, String> {
+    Ok(std::env::var("TB_DB_PATH").unwrap_or_else(|_| "taskbook.db".to_string()))
+}
+
 fn require_env(key: &str) -> Result<String, String> {
     std::env::var(key).map_err(|_| format!("{key} environment variable is required"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registration_mode_parses_case_insensitively() {
+        assert_eq!("open".parse(), Ok(RegistrationMode::Open));
+        assert_eq!(" Invite ".parse(), Ok(RegistrationMode::Invite));
+        assert_eq!("CLOSED".parse(), Ok(RegistrationMode::Closed));
+        assert_eq!("single".parse(), Ok(RegistrationMode::Single));
+        assert!("invite-only".parse::<RegistrationMode>().is_err());
+    }
+
+    #[test]
+    fn route_limits_parse_pairs() {
+        assert_eq!(parse_route_limits(""), Ok(vec![]));
+        assert_eq!(
+            parse_route_limits(" /api/v1/login=5, /api/v1/items = 600,"),
+            Ok(vec![
+                ("/api/v1/login".to_string(), 5),
+                ("/api/v1/items".to_string(), 600),
+            ])
+        );
+        assert!(parse_route_limits("/api/v1/login").is_err());
+        assert!(parse_route_limits("login=5").is_err());
+        assert!(parse_route_limits("/api/v1/login=lots").is_err());
+    }
+
+    #[test]
+    fn rate_limits_read_their_variables() {
+        let load = |vars: &[(&str, &str)]| {
+            RateLimitConfig::from_vars(|key| {
+                vars.iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+
+        let defaults = load(&[]).unwrap();
+        assert_eq!(
+            (defaults.auth, defaults.items, defaults.burst),
+            (10, 300, 0)
+        );
+        assert!(defaults.client_ip_header.is_none());
+
+        let config = load(&[
+            ("TB_RATE_LIMIT_AUTH", "5"),
+            ("TB_RATE_LIMIT_BURST", " 3 "),
+            ("TB_CLIENT_IP_HEADER", "X-Real-IP"),
+        ])
+        .unwrap();
+        assert_eq!((config.auth, config.items, config.burst), (5, 300, 3));
+        assert_eq!(config.client_ip_header.unwrap(), "x-real-ip");
+
+        assert!(load(&[("TB_RATE_LIMIT_BURST", "some")]).is_err());
+        assert!(load(&[("TB_RATE_LIMIT_WINDOW", "0")]).is_err());
+    }
+}
@@ -1,13 +1,47 @@
+//! The database: Postgres with the `postgres` feature, or SQLite with the
+//! `sqlite` feature.
+//!
+//! Handlers use the [`Db`] aliases and stick to SQL both dialects accept;
+//! the few expressions that differ are built by the helpers here.
+
 use std::time::Duration;
 
-use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
-use sqlx::{ConnectOptions, PgPool};
+use sqlx::migrate::Migrator;
+use sqlx::{ConnectOptions, Database, Pool};
 use tracing::log::LevelFilter;
 
+#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
+compile_error!("enable the `postgres` or the `sqlite` feature");
+// Cargo merges the features a build asks for, so this is what building
+// `tb serve` together with the Postgres server would do
+#[cfg(all(feature = "postgres", feature = "sqlite"))]
+compile_error!(
+    "the `postgres` and `sqlite` features pick the database; enable only one \
+     (build taskbook-server and `tb serve` separately)"
+);
+
+#[cfg(not(feature = "sqlite"))]
+pub type Db = sqlx::Postgres;
+#[cfg(feature = "sqlite")]
+pub type Db = sqlx::Sqlite;
+
+pub type DbPool = Pool<Db>;
+pub type DbConnection = <Db as Database>::Connection;
+
+/// A list of strings bound as one parameter; see [`in_list`].
+#[cfg(not(feature = "sqlite"))]
+pub type StringList = Vec<String>;
+/// A list of strings bound as one parameter (a JSON array); see [`in_list`].
+#[cfg(feature = "sqlite")]
+pub type StringList = String;
+
 /// Create a PostgreSQL connection pool with resilience settings.
 ///
 /// Disables `extra_float_digits` startup parameter for PgBouncer compatibility.
-pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
+#[cfg(not(feature = "sqlite"))]
+pub async fn create_pool(database_url: &str) -> Result<DbPool, sqlx::Error> {
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
     let connect_options: PgConnectOptions = database_url
         .parse::<PgConnectOptions>()?
         .extra_float_digits(None)
@@ -21,3 +55,116 @@ pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
         .connect_with(connect_options)
         .await
 }
+
+/// Open the SQLite database file at `path`, creating it if needed.
+///
+/// The pool holds a single connection: SQLite allows one writer at a time,
+/// and a transaction that reads before it writes cannot wait for another
+/// writer to finish, so concurrent ones would fail rather than queue.
+/// Other processes (such as `tb-server admin`) wait up to five seconds for
+/// the server to release the file.
+#[cfg(feature = "sqlite")]
+pub async fn create_pool(path: &str) -> Result<DbPool, sqlx::Error> {
+    use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+
+    let connect_options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .foreign_keys(true)
+        .busy_timeout(Duration::from_secs(5))
+        .log_slow_statements(LevelFilter::Warn, Duration::from_secs(5));
+
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(Duration::from_secs(30))
+        .connect_with(connect_options)
+        .await
+}
+
+#[cfg(not(feature = "sqlite"))]
+static MIGRATOR: Migrator = sqlx::migrate!("src/migrations/postgres");
+#[cfg(feature = "sqlite")]
+static MIGRATOR: Migrator = sqlx::migrate!("src/migrations/sqlite");
+
+/// Bring the schema up to date.
+pub async fn migrate(pool: &DbPool) -> Result<(), sqlx::migrate::MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+/// Whether every migration this build knows of has been applied.
+pub async fn is_migrated(pool: &DbPool) -> Result<bool, sqlx::Error> {
+    let applied: Vec<i64> =
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = TRUE")
+            .fetch_all(pool)
+            .await?;
+    Ok(MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .all(|m| applied.contains(&m.version)))
+}
+
+/// SQL condition that `column` is one of the strings in the parameter
+/// `param`, bound as a [`string_list`].
+pub fn in_list(column: &str, param: &str) -> String {
+    if cfg!(feature = "sqlite") {
+        format!("{column} IN (SELECT value FROM json_each({param}))")
+    } else {
+        format!("{column} = ANY({param})")
+    }
+}
+
+/// `values`, to bind for an [`in_list`] condition
+#[cfg(not(feature = "sqlite"))]
+pub fn string_list(values: &[&str]) -> StringList {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+/// `values`, to bind for an [`in_list`] condition
+#[cfg(feature = "sqlite")]
+pub fn string_list(values: &[&str]) -> StringList {
+    serde_json::to_string(values).expect("strings serialize")
+}
+
+/// SQL expression for the later of two timestamps, either of which may be
+/// NULL.
+pub fn greatest(a: &str, b: &str) -> String {
+    if cfg!(feature = "sqlite") {
+        format!("MAX(COALESCE({a}, {b}), COALESCE({b}, {a}))")
+    } else {
+        format!("GREATEST({a}, {b})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "sqlite"))]
+    fn helpers_build_postgres_expressions() {
+        assert_eq!(in_list("item_key", "$3"), "item_key = ANY($3)");
+        assert_eq!(string_list(&["a", "b"]), vec!["a", "b"]);
+        assert_eq!(greatest("a", "b"), "GREATEST(a, b)");
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn helpers_build_sqlite_expressions() {
+        assert_eq!(
+            in_list("item_key", "$3"),
+            "item_key IN (SELECT value FROM json_each($3))"
+        );
+        assert_eq!(string_list(&["a", "b\""]), r#"["a","b\""]"#);
+        assert_eq!(greatest("a", "b"), "MAX(COALESCE(a, b), COALESCE(b, a))");
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "sqlite")]
+    async fn is_migrated_once_migrations_run() {
+        let pool = create_pool(":memory:").await.unwrap();
+        assert!(is_migrated(&pool).await.is_err());
+        migrate(&pool).await.unwrap();
+        assert!(is_migrated(&pool).await.unwrap());
+    }
+}
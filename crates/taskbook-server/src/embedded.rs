@@ -0,0 +1,121 @@
+//! A single-user server run from another program, such as `tb serve`.
+//!
+//! It keeps everything in one SQLite file and takes no configuration
+//! beyond where to listen: the first account registered is the only one.
+
+use std::net::SocketAddr;
+use std::path::Path;
+
+use tokio::net::TcpListener;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::ServerConfig;
+use crate::{db, router, shutdown_signal};
+
+/// Where to listen unless told otherwise: only this machine can connect
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+
+/// Serve `database` on `addr` until Ctrl+C or SIGTERM, logging to stderr.
+/// Blocks the calling thread, which must not be inside a Tokio runtime.
+pub fn serve(database: &Path, addr: SocketAddr) -> Result<(), String> {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .with_writer(std::io::stderr)
+        .try_init();
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("failed to start the runtime: {e}"))?
+        .block_on(run(database, addr))
+}
+
+async fn run(database: &Path, addr: SocketAddr) -> Result<(), String> {
+    let config = ServerConfig::single_user(database.display().to_string(), addr.ip(), addr.port());
+
+    let pool = db::create_pool(&config.database_url)
+        .await
+        .map_err(|e| format!("failed to open {}: {e}", database.display()))?;
+    db::migrate(&pool)
+        .await
+        .map_err(|e| format!("failed to run database migrations: {e}"))?;
+
+    let app = router::build(pool, &config, None);
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("failed to bind address {addr}: {e}"))?;
+
+    tracing::info!("serving {} on {}", database.display(), addr);
+
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .map_err(|e| format!("server error: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use axum::extract::State;
+    use axum::http::HeaderMap;
+    use axum::Json;
+
+    use super::*;
+    use crate::error::ServerError;
+    use crate::handlers::user::{register, RegisterRequest};
+    use crate::rate_limit::ClientIp;
+    use crate::testing;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    fn account(username: &str) -> RegisterRequest {
+        RegisterRequest {
+            username: username.to_string(),
+            email: format!("{username}@example.com"),
+            password: "correct horse battery staple".to_string(),
+            invite_code: None,
+        }
+    }
+
+    #[test]
+    fn listens_on_loopback_by_default() {
+        let addr: SocketAddr = DEFAULT_LISTEN.parse().unwrap();
+        assert!(addr.ip().is_loopback());
+    }
+
+    #[tokio::test]
+    async fn only_the_first_account_can_register() {
+        let state = testing::state().await.unwrap();
+        let attempt = |username| {
+            register(
+                State(state.clone()),
+                ClientIp(IP),
+                HeaderMap::new(),
+                Json(account(username)),
+            )
+        };
+
+        assert!(attempt("alice").await.is_ok());
+        assert!(matches!(
+            attempt("bob").await,
+            Err(ServerError::Forbidden(_))
+        ));
+        // Not even under the same name again
+        assert!(matches!(
+            attempt("alice").await,
+            Err(ServerError::Forbidden(_))
+        ));
+
+        let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(users, 1);
+    }
+}
@@ -1,9 +1,11 @@
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde_json::json;
 use thiserror::Error;
 
+use crate::quota::format_bytes;
+
 #[derive(Error, Debug)]
 pub enum ServerError {
     #[error("Database error: {0}")]
@@ -15,36 +17,124 @@ pub enum ServerError {
     #[error("Invalid credentials")]
     InvalidCredentials,
 
+    #[error("Two-factor code required")]
+    TotpRequired,
+
+    #[error("Invalid two-factor code")]
+    InvalidTotpCode,
+
     #[error("User already exists")]
     UserAlreadyExists,
 
+    #[error("Invite code required")]
+    InviteRequired,
+
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 
     #[error("Rate limit exceeded")]
     RateLimited,
+
+    #[error("Storage quota exceeded")]
+    QuotaExceeded { used: i64, quota: i64 },
 }
 
-impl IntoResponse for ServerError {
-    fn into_response(self) -> Response {
+/// Set on error responses, so the metrics middleware can count errors by
+/// kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorKind(pub &'static str);
+
+impl ServerError {
+    /// Short name for the kind of error, for metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ServerError::Database(_) => "database",
+            ServerError::Unauthorized => "unauthorized",
+            ServerError::InvalidCredentials => "invalid_credentials",
+            ServerError::TotpRequired => "totp_required",
+            ServerError::InvalidTotpCode => "invalid_totp_code",
+            ServerError::UserAlreadyExists => "user_already_exists",
+            ServerError::InviteRequired => "invite_required",
+            ServerError::Validation(_) => "validation",
+            ServerError::Forbidden(_) => "forbidden",
+            ServerError::NotFound(_) => "not_found",
+            ServerError::Internal(_) => "internal",
+            ServerError::RateLimited => "rate_limited",
+            ServerError::QuotaExceeded { .. } => "quota_exceeded",
+        }
+    }
+
+    fn response(self) -> Response {
         let (status, message) = match &self {
             ServerError::Database(e) => {
                 tracing::error!(error = %e, "database error");
                 (StatusCode::INTERNAL_SERVER_ERROR, "database error")
             }
-            ServerError::Unauthorized => (StatusCode::UNAUTHORIZED, "authentication required"),
+            ServerError::Unauthorized => {
+                // Tells clients (RFC 6750) that the session token is missing,
+                // expired or revoked, rather than a password being wrong
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    [(header::WWW_AUTHENTICATE, r#"Bearer error="invalid_token""#)],
+                    Json(json!({ "error": "authentication required" })),
+                )
+                    .into_response();
+            }
             ServerError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "invalid credentials"),
+            ServerError::TotpRequired => {
+                // The flag tells clients to ask for a code and try again
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    Json(json!({ "error": "two-factor code required", "totp_required": true })),
+                )
+                    .into_response();
+            }
+            ServerError::InvalidTotpCode => (StatusCode::UNAUTHORIZED, "invalid two-factor code"),
             ServerError::UserAlreadyExists => (StatusCode::CONFLICT, "user already exists"),
+            ServerError::InviteRequired => {
+                // The flag tells clients to ask for a code and try again
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json(
+                        json!({ "error": "registration needs an invite code", "invite_required": true }),
+                    ),
+                )
+                    .into_response();
+            }
             ServerError::Validation(msg) => {
                 return (StatusCode::BAD_REQUEST, Json(json!({ "error": msg }))).into_response();
             }
+            ServerError::Forbidden(msg) => {
+                return (StatusCode::FORBIDDEN, Json(json!({ "error": msg }))).into_response();
+            }
+            ServerError::NotFound(msg) => {
+                return (StatusCode::NOT_FOUND, Json(json!({ "error": msg }))).into_response();
+            }
             ServerError::Internal(e) => {
                 tracing::error!(error = %e, "internal error");
                 (StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
             }
+            ServerError::QuotaExceeded { used, quota } => {
+                let message = format!(
+                    "storage quota exceeded: this would use {} of {}",
+                    format_bytes(*used),
+                    format_bytes(*quota)
+                );
+                return (
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    Json(json!({ "error": message, "quota_exceeded": true })),
+                )
+                    .into_response();
+            }
             ServerError::RateLimited => (
                 StatusCode::TOO_MANY_REQUESTS,
                 "too many requests, try again later",
@@ -55,4 +145,13 @@ impl IntoResponse for ServerError {
     }
 }
 
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        let kind = ErrorKind(self.kind());
+        let mut response = self.response();
+        response.extensions_mut().insert(kind);
+        response
+    }
+}
+
 pub type Result<T> = std::result::Result<T, ServerError>;
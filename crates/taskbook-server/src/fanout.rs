@@ -0,0 +1,111 @@
+//! Sync notifications across server instances sharing a database.
+//!
+//! Each change is delivered to this instance's clients straight away, and
+//! also published with Postgres `NOTIFY`. Every instance `LISTEN`s on the
+//! same channel and passes on what the others published, so a client sees
+//! changes whichever instance it is connected to.
+//!
+//! A SQLite database belongs to a single instance, so with the `sqlite`
+//! feature there is nothing to fan out.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(not(feature = "sqlite"))]
+pub use postgres::start;
+
+/// A change, as published to the other instances
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Published {
+    /// The instance the change was made on
+    pub instance: Uuid,
+    pub user_id: Uuid,
+    pub archived: bool,
+}
+
+/// A hub for this instance's clients only.
+#[cfg(feature = "sqlite")]
+pub fn start(_pool: &crate::db::DbPool) -> crate::router::NotificationHub {
+    crate::router::NotificationHub::default()
+}
+
+#[cfg(not(feature = "sqlite"))]
+mod postgres {
+    use std::time::Duration;
+
+    use sqlx::postgres::PgListener;
+    use tokio::sync::mpsc;
+
+    use super::Published;
+    use crate::db::DbPool;
+    use crate::router::{NotificationHub, SyncEvent};
+
+    /// Postgres channel notifications are published on
+    const CHANNEL: &str = "taskbook_sync";
+
+    /// Wait before listening again after the connection failed
+    const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+    /// A hub whose notifications reach clients of every instance using `pool`.
+    pub fn start(pool: &DbPool) -> NotificationHub {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let hub = NotificationHub::with_publisher(tx);
+        tokio::spawn(publish(pool.clone(), rx));
+        tokio::spawn(listen(pool.clone(), hub.clone()));
+        hub
+    }
+
+    async fn publish(pool: DbPool, mut rx: mpsc::UnboundedReceiver<Published>) {
+        while let Some(published) = rx.recv().await {
+            let Ok(payload) = serde_json::to_string(&published) else {
+                continue;
+            };
+            if let Err(e) = sqlx::query("SELECT pg_notify($1, $2)")
+                .bind(CHANNEL)
+                .bind(payload)
+                .execute(&pool)
+                .await
+            {
+                tracing::warn!(error = %e, "failed to publish sync notification");
+            }
+        }
+    }
+
+    async fn listen(pool: DbPool, hub: NotificationHub) {
+        loop {
+            match listen_once(&pool, &hub).await {
+                Ok(()) => tracing::warn!("lost the sync notification connection, reconnecting"),
+                Err(e) => tracing::warn!(error = %e, "cannot listen for sync notifications"),
+            }
+            // Whatever the other instances published meanwhile was missed
+            hub.refresh_all();
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+    }
+
+    /// Pass on notifications until the connection is lost
+    async fn listen_once(pool: &DbPool, hub: &NotificationHub) -> Result<(), sqlx::Error> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(CHANNEL).await?;
+
+        while let Some(notification) = listener.try_recv().await? {
+            let published = match serde_json::from_str::<Published>(notification.payload()) {
+                Ok(published) => published,
+                Err(e) => {
+                    tracing::warn!(error = %e, "ignoring malformed sync notification");
+                    continue;
+                }
+            };
+            // Our own clients were told when the change was made
+            if published.instance != hub.instance() {
+                hub.deliver(
+                    published.user_id,
+                    SyncEvent::DataChanged {
+                        archived: published.archived,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+}
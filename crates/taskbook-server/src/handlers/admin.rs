@@ -0,0 +1,263 @@
+//! Server administration, for users with the admin role.
+//!
+//! The first admin is made with `tb-server admin promote`, which works on
+//! the database directly; everything else goes through these endpoints.
+
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, ServerError};
+use crate::handlers::audit::{self, AuditEvent};
+use crate::middleware::AdminUser;
+use crate::quota;
+use crate::rate_limit::ClientIp;
+use crate::router::AppState;
+
+#[derive(Serialize, Deserialize)]
+pub struct AdminUserInfo {
+    pub username: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub is_admin: bool,
+    pub disabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub last_active_at: Option<DateTime<Utc>>,
+    /// Items and archived items, not counting shared boards
+    pub items: i64,
+    /// Encrypted bytes stored: items, owned shared boards and share links
+    pub storage_bytes: i64,
+    /// The account's storage limit, `None` for no limit
+    pub quota_bytes: Option<i64>,
+    /// Whether `quota_bytes` was set for this account rather than being
+    /// the server default
+    pub custom_quota: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AdminUsersResponse {
+    pub users: Vec<AdminUserInfo>,
+}
+
+#[derive(Deserialize)]
+pub struct SetDisabledRequest {
+    pub disabled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SetQuotaRequest {
+    /// Bytes the account may store; `None` to use the server default
+    pub quota_bytes: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct PurgeRequest {
+    /// Accounts not used for this many days are deleted
+    pub inactive_days: i64,
+    /// Only report which accounts would be deleted
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PurgeResponse {
+    /// Usernames of the deleted (or, on a dry run, matching) accounts
+    pub users: Vec<String>,
+}
+
+/// Shortest inactivity `purge` accepts, so a typo cannot empty the server
+const MIN_PURGE_DAYS: i64 = 30;
+
+type UserRow = (
+    String,
+    String,
+    bool,
+    bool,
+    Option<DateTime<Utc>>,
+    DateTime<Utc>,
+    Option<DateTime<Utc>>,
+    i64,
+    i64,
+    Option<i64>,
+);
+
+/// List every account with its storage use.
+#[tracing::instrument(skip(state))]
+pub async fn list_users(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Result<Json<AdminUsersResponse>> {
+    let rows = sqlx::query_as::<_, UserRow>(&format!(
+        "SELECT u.username, u.email, u.email_verified, u.is_admin, u.disabled_at, \
+                u.created_at, u.last_active_at, \
+                (SELECT COUNT(*) FROM items i WHERE i.user_id = u.id AND i.deleted_at IS NULL), \
+                {}, u.quota_bytes \
+         FROM users u ORDER BY u.username",
+        quota::usage_sql("u.id")
+    ))
+    .fetch_all(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let users = rows
+        .into_iter()
+        .map(
+            |(
+                username,
+                email,
+                email_verified,
+                is_admin,
+                disabled_at,
+                created_at,
+                last_active_at,
+                items,
+                storage_bytes,
+                own_quota,
+            )| AdminUserInfo {
+                username,
+                email,
+                email_verified,
+                is_admin,
+                disabled: disabled_at.is_some(),
+                created_at,
+                last_active_at,
+                items,
+                storage_bytes,
+                quota_bytes: own_quota.or(state.storage_quota),
+                custom_quota: own_quota.is_some(),
+            },
+        )
+        .collect();
+
+    Ok(Json(AdminUsersResponse { users }))
+}
+
+/// Disable or re-enable an account. Disabling logs it out everywhere and
+/// keeps it from logging in; its data is kept.
+#[tracing::instrument(skip(state, headers, admin, req))]
+pub async fn set_disabled(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    admin: AdminUser,
+    Path(username): Path<String>,
+    Json(req): Json<SetDisabledRequest>,
+) -> Result<()> {
+    let user_id = sqlx::query_scalar::<_, uuid::Uuid>("SELECT id FROM users WHERE username = $1")
+        .bind(&username)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(ServerError::Database)?
+        .ok_or_else(|| ServerError::NotFound(format!("no user {username}")))?;
+    if req.disabled && user_id == admin.0.user_id {
+        return Err(ServerError::Validation(
+            "you cannot disable your own account".to_string(),
+        ));
+    }
+
+    let mut tx = state.pool.begin().await.map_err(ServerError::Database)?;
+    sqlx::query(
+        "UPDATE users SET disabled_at = CASE WHEN $1 THEN COALESCE(disabled_at, $2) END \
+         WHERE id = $3",
+    )
+    .bind(req.disabled)
+    .bind(Utc::now())
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(ServerError::Database)?;
+    if req.disabled {
+        sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(ServerError::Database)?;
+    }
+    // In the account's own log, from the admin's request
+    let event = if req.disabled {
+        AuditEvent::AccountDisabled
+    } else {
+        AuditEvent::AccountEnabled
+    };
+    audit::record_in(&mut tx, user_id, event, ip, &headers).await?;
+    tx.commit().await.map_err(ServerError::Database)?;
+
+    tracing::info!(%username, disabled = req.disabled, "account disabled state changed");
+
+    Ok(())
+}
+
+/// Set an account's storage quota, or go back to the server default.
+#[tracing::instrument(skip(state, _admin, req))]
+pub async fn set_quota(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Path(username): Path<String>,
+    Json(req): Json<SetQuotaRequest>,
+) -> Result<()> {
+    if req.quota_bytes.is_some_and(|bytes| bytes < 0) {
+        return Err(ServerError::Validation(
+            "quota_bytes must not be negative".to_string(),
+        ));
+    }
+
+    let result = sqlx::query("UPDATE users SET quota_bytes = $1 WHERE username = $2")
+        .bind(req.quota_bytes)
+        .bind(&username)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+    if result.rows_affected() == 0 {
+        return Err(ServerError::NotFound(format!("no user {username}")));
+    }
+
+    tracing::info!(%username, quota_bytes = ?req.quota_bytes, "storage quota changed");
+
+    Ok(())
+}
+
+/// Delete accounts that have not been used for a while. Admins are never
+/// purged.
+#[tracing::instrument(skip(state, _admin, req))]
+pub async fn purge_inactive(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+    Json(req): Json<PurgeRequest>,
+) -> Result<Json<PurgeResponse>> {
+    if req.inactive_days < MIN_PURGE_DAYS {
+        return Err(ServerError::Validation(format!(
+            "inactive_days must be at least {MIN_PURGE_DAYS}"
+        )));
+    }
+    let cutoff = Utc::now() - Duration::days(req.inactive_days);
+    let inactive = "NOT is_admin AND COALESCE(last_active_at, created_at) < $1";
+
+    let users = if req.dry_run {
+        sqlx::query_scalar::<_, String>(&format!(
+            "SELECT username FROM users WHERE {inactive} ORDER BY username"
+        ))
+        .bind(cutoff)
+        .fetch_all(&state.pool)
+        .await
+    } else {
+        sqlx::query_scalar::<_, String>(&format!(
+            "DELETE FROM users WHERE {inactive} RETURNING username"
+        ))
+        .bind(cutoff)
+        .fetch_all(&state.pool)
+        .await
+    }
+    .map_err(ServerError::Database)?;
+
+    if !req.dry_run {
+        tracing::info!(
+            count = users.len(),
+            days = req.inactive_days,
+            "inactive accounts purged"
+        );
+    }
+
+    Ok(Json(PurgeResponse { users }))
+}
@@ -0,0 +1,446 @@
+//! The audit log: security-relevant events on each account, with where the
+//! request came from, so users can spot activity that was not theirs.
+
+use std::net::IpAddr;
+
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::db::{DbConnection, DbPool};
+use crate::error::{Result, ServerError};
+use crate::middleware::{user_agent, AuthUser};
+use crate::router::AppState;
+
+/// Entries returned by GET /api/v1/me/audit
+const AUDIT_PAGE: i64 = 100;
+
+/// Days an entry is kept. Older entries of a user are deleted when the
+/// next event of theirs is recorded.
+pub const AUDIT_RETENTION_DAYS: i64 = 365;
+
+/// Something that happened to an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEvent {
+    Login,
+    /// A wrong password or two-factor code
+    LoginFailed,
+    /// Logged out of every session
+    LoggedOut,
+    PasswordChanged,
+    /// A new password set from a reset link
+    PasswordReset,
+    SessionRevoked,
+    /// An API token created or revoked
+    TokenCreated,
+    TokenRevoked,
+    DataExported,
+    /// A single sign-on account linked to this one
+    SsoLinked,
+    TotpEnabled,
+    TotpDisabled,
+    /// Disabled or enabled again by an admin
+    AccountDisabled,
+    AccountEnabled,
+}
+
+impl AuditEvent {
+    fn as_str(self) -> &'static str {
+        match self {
+            AuditEvent::Login => "login",
+            AuditEvent::LoginFailed => "login_failed",
+            AuditEvent::LoggedOut => "logged_out",
+            AuditEvent::PasswordChanged => "password_changed",
+            AuditEvent::PasswordReset => "password_reset",
+            AuditEvent::SessionRevoked => "session_revoked",
+            AuditEvent::TokenCreated => "token_created",
+            AuditEvent::TokenRevoked => "token_revoked",
+            AuditEvent::DataExported => "data_exported",
+            AuditEvent::SsoLinked => "sso_linked",
+            AuditEvent::TotpEnabled => "totp_enabled",
+            AuditEvent::TotpDisabled => "totp_disabled",
+            AuditEvent::AccountDisabled => "account_disabled",
+            AuditEvent::AccountEnabled => "account_enabled",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AuditEntry {
+    pub event: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct AuditResponse {
+    /// Newest first
+    pub events: Vec<AuditEntry>,
+}
+
+/// Add `event` to the user's audit log, made by a request from `ip` with
+/// `headers`.
+pub async fn record(
+    pool: &DbPool,
+    user_id: Uuid,
+    event: AuditEvent,
+    ip: IpAddr,
+    headers: &HeaderMap,
+) -> Result<()> {
+    let mut conn = pool.acquire().await.map_err(ServerError::Database)?;
+    record_in(&mut conn, user_id, event, ip, headers).await
+}
+
+/// [`record`] on `conn`, such as the transaction making the change, so the
+/// event is logged if and only if the change is made. Also deletes the
+/// user's entries older than [`AUDIT_RETENTION_DAYS`].
+pub async fn record_in(
+    conn: &mut DbConnection,
+    user_id: Uuid,
+    event: AuditEvent,
+    ip: IpAddr,
+    headers: &HeaderMap,
+) -> Result<()> {
+    sqlx::query("INSERT INTO audit_log (user_id, event, ip, user_agent) VALUES ($1, $2, $3, $4)")
+        .bind(user_id)
+        .bind(event.as_str())
+        .bind(ip.to_string())
+        .bind(user_agent(headers))
+        .execute(&mut *conn)
+        .await
+        .map_err(ServerError::Database)?;
+    sqlx::query("DELETE FROM audit_log WHERE user_id = $1 AND created_at < $2")
+        .bind(user_id)
+        .bind(Utc::now() - Duration::days(AUDIT_RETENTION_DAYS))
+        .execute(&mut *conn)
+        .await
+        .map_err(ServerError::Database)?;
+    Ok(())
+}
+
+/// The latest events in the user's audit log.
+#[tracing::instrument(skip(state))]
+pub async fn list_events(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<AuditResponse>> {
+    let rows = sqlx::query_as::<_, (String, Option<String>, Option<String>, DateTime<Utc>)>(
+        "SELECT event, ip, user_agent, created_at FROM audit_log \
+         WHERE user_id = $1 ORDER BY id DESC LIMIT $2",
+    )
+    .bind(auth.user_id)
+    .bind(AUDIT_PAGE)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let events = rows
+        .into_iter()
+        .map(|(event, ip, user_agent, created_at)| AuditEntry {
+            event,
+            ip,
+            user_agent,
+            created_at,
+        })
+        .collect();
+
+    Ok(Json(AuditResponse { events }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use axum::extract::Path;
+    use axum::http::header;
+
+    use super::*;
+    use crate::auth::hash_password;
+    use crate::handlers::admin::{self, SetDisabledRequest};
+    use crate::handlers::tokens::{self, CreateTokenRequest, Scope};
+    use crate::handlers::user::{
+        self, ChangePasswordRequest, ConfirmTotpRequest, DisableTotpRequest, EnrollTotpRequest,
+        LoginRequest,
+    };
+    use crate::middleware::AdminUser;
+    use crate::rate_limit::ClientIp;
+    use crate::{testing, totp};
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 7));
+    const PASSWORD: &str = "correct horse battery staple";
+
+    fn headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, "tb/1.2.5".parse().unwrap());
+        headers
+    }
+
+    /// Alice, with `PASSWORD` as her password
+    async fn alice(state: &AppState) -> Uuid {
+        let user_id = testing::user(state, "alice").await;
+        sqlx::query("UPDATE users SET password = $1 WHERE id = $2")
+            .bind(hash_password(PASSWORD).unwrap())
+            .bind(user_id)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        user_id
+    }
+
+    fn auth(user_id: Uuid) -> AuthUser {
+        AuthUser {
+            user_id,
+            session_id: Uuid::new_v4(),
+        }
+    }
+
+    async fn login(state: &AppState, password: &str) -> Result<()> {
+        let req = LoginRequest {
+            username: "alice".to_string(),
+            password: password.to_string(),
+            totp_code: None,
+        };
+        user::login(State(state.clone()), ClientIp(IP), headers(), Json(req))
+            .await
+            .map(|_| ())
+    }
+
+    /// The user's log as the endpoint lists it, oldest first
+    async fn events(state: &AppState, user_id: Uuid) -> Vec<String> {
+        let Json(log) = list_events(State(state.clone()), auth(user_id))
+            .await
+            .unwrap();
+        for entry in &log.events {
+            assert_eq!(entry.ip.as_deref(), Some("192.0.2.7"));
+            assert_eq!(entry.user_agent.as_deref(), Some("tb/1.2.5"));
+        }
+        log.events
+            .into_iter()
+            .rev()
+            .map(|entry| entry.event)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn logins_are_recorded_whether_or_not_they_succeed() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = alice(&state).await;
+        let other_id = testing::user(&state, "bob").await;
+
+        assert!(login(&state, "not the password").await.is_err());
+        login(&state, PASSWORD).await.unwrap();
+
+        assert_eq!(events(&state, user_id).await, ["login_failed", "login"]);
+        assert!(events(&state, other_id).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn password_changes_are_recorded() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = alice(&state).await;
+
+        let change = |current: &str| {
+            let req = ChangePasswordRequest {
+                current_password: current.to_string(),
+                new_password: "a whole new password".to_string(),
+            };
+            user::change_password(
+                State(state.clone()),
+                ClientIp(IP),
+                headers(),
+                auth(user_id),
+                Json(req),
+            )
+        };
+        assert!(change("not the password").await.is_err());
+        assert!(events(&state, user_id).await.is_empty());
+        assert!(change(PASSWORD).await.is_ok());
+
+        assert_eq!(events(&state, user_id).await, ["password_changed"]);
+    }
+
+    #[tokio::test]
+    async fn api_tokens_created_and_revoked_are_recorded() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = alice(&state).await;
+
+        let req = CreateTokenRequest {
+            name: "backup".to_string(),
+            scope: Scope::Read,
+            expires_in: None,
+        };
+        let Json(created) = tokens::create_token(
+            State(state.clone()),
+            ClientIp(IP),
+            headers(),
+            auth(user_id),
+            Json(req),
+        )
+        .await
+        .unwrap();
+        tokens::revoke_token(
+            State(state.clone()),
+            ClientIp(IP),
+            headers(),
+            auth(user_id),
+            Path(created.id),
+        )
+        .await
+        .unwrap();
+        // Nothing to revoke, nothing recorded
+        assert!(tokens::revoke_token(
+            State(state.clone()),
+            ClientIp(IP),
+            headers(),
+            auth(user_id),
+            Path(created.id),
+        )
+        .await
+        .is_err());
+
+        assert_eq!(
+            events(&state, user_id).await,
+            ["token_created", "token_revoked"]
+        );
+    }
+
+    #[tokio::test]
+    async fn logouts_are_recorded() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = alice(&state).await;
+
+        login(&state, PASSWORD).await.unwrap();
+        user::logout(State(state.clone()), ClientIp(IP), headers(), auth(user_id))
+            .await
+            .unwrap();
+
+        assert_eq!(events(&state, user_id).await, ["login", "logged_out"]);
+    }
+
+    #[tokio::test]
+    async fn two_factor_changes_are_recorded() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = alice(&state).await;
+
+        let req = EnrollTotpRequest {
+            password: PASSWORD.to_string(),
+        };
+        assert!(
+            user::enroll_totp(State(state.clone()), auth(user_id), Json(req))
+                .await
+                .is_ok()
+        );
+        let secret: Vec<u8> = sqlx::query_scalar("SELECT totp_secret FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+        let now = Utc::now().timestamp();
+        let req = ConfirmTotpRequest {
+            code: totp::code(&secret, now),
+        };
+        user::confirm_totp(
+            State(state.clone()),
+            ClientIp(IP),
+            headers(),
+            auth(user_id),
+            Json(req),
+        )
+        .await
+        .unwrap();
+
+        let disable = |code: String| {
+            let req = DisableTotpRequest {
+                password: PASSWORD.to_string(),
+                code,
+            };
+            user::disable_totp(
+                State(state.clone()),
+                ClientIp(IP),
+                headers(),
+                auth(user_id),
+                Json(req),
+            )
+        };
+        assert!(disable(totp::code(&secret, now - 3600)).await.is_err());
+        assert_eq!(events(&state, user_id).await, ["totp_enabled"]);
+        // The next time step, as the code just used cannot be used again
+        disable(totp::code(&secret, now + 30)).await.unwrap();
+
+        assert_eq!(
+            events(&state, user_id).await,
+            ["totp_enabled", "totp_disabled"]
+        );
+    }
+
+    #[tokio::test]
+    async fn accounts_disabled_by_an_admin_have_it_in_their_log() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = alice(&state).await;
+        let admin_id = testing::user(&state, "root").await;
+
+        let set_disabled = |username: &str, disabled| {
+            admin::set_disabled(
+                State(state.clone()),
+                ClientIp(IP),
+                headers(),
+                AdminUser(auth(admin_id)),
+                Path(username.to_string()),
+                Json(SetDisabledRequest { disabled }),
+            )
+        };
+        set_disabled("alice", true).await.unwrap();
+        set_disabled("alice", false).await.unwrap();
+        // Refused, so not recorded
+        assert!(set_disabled("root", true).await.is_err());
+
+        assert_eq!(
+            events(&state, user_id).await,
+            ["account_disabled", "account_enabled"]
+        );
+        assert!(events(&state, admin_id).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn entries_past_the_retention_period_are_deleted() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = alice(&state).await;
+        let other_id = testing::user(&state, "bob").await;
+        let old = Utc::now() - Duration::days(AUDIT_RETENTION_DAYS + 1);
+        for id in [user_id, other_id] {
+            sqlx::query(
+                "INSERT INTO audit_log (user_id, event, ip, user_agent, created_at) \
+                 VALUES ($1, 'login', '192.0.2.7', 'tb/1.2.5', $2)",
+            )
+            .bind(id)
+            .bind(old)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        }
+
+        login(&state, PASSWORD).await.unwrap();
+
+        assert_eq!(events(&state, user_id).await, ["login"]);
+        // Pruned as each user's next event is recorded
+        assert_eq!(events(&state, other_id).await, ["login"]);
+    }
+}
@@ -0,0 +1,697 @@
+//! Boards shared between accounts.
+//!
+//! A shared board has its own encryption key. The server stores that key
+//! once per member, wrapped with the member's X25519 public key, together
+//! with the board's items encrypted under it. The owner can add and remove
+//! members; members with write access can replace the board's items.
+
+use std::collections::HashMap;
+
+use axum::extract::{Path, State};
+use axum::Json;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::error::{Result, ServerError};
+use crate::handlers::items::{
+    self, replace_items, rows_to_encrypted_items, HistoryResponse, ItemsResponse, PutItemsRequest,
+    RestoreRequest, RestoreResponse, Store, TrashResponse,
+};
+use crate::middleware::{AuthUser, ReadAccess, WriteAccess};
+use crate::router::{AppState, SyncEvent};
+use crate::webhooks::{Changes, Event};
+
+/// X25519 public keys are 32 bytes
+const PUBLIC_KEY_LEN: usize = 32;
+
+/// Upper bound for a wrapped board key (ephemeral key, nonce, name and key)
+const MAX_WRAPPED_KEY_LEN: usize = 1024;
+
+#[derive(Deserialize, Serialize)]
+pub struct PublicKeyRequest {
+    pub public_key: String, // base64-encoded X25519 public key
+}
+
+#[derive(Serialize)]
+pub struct PublicKeyResponse {
+    pub public_key: String,
+}
+
+#[derive(Serialize)]
+pub struct BoardMember {
+    pub username: String,
+    pub can_write: bool,
+}
+
+#[derive(Serialize)]
+pub struct SharedBoard {
+    pub id: Uuid,
+    pub owner: String,
+    pub is_owner: bool,
+    pub can_write: bool,
+    pub wrapped_key: String, // base64, wrapped for the requesting user
+    pub members: Vec<BoardMember>,
+}
+
+#[derive(Serialize)]
+pub struct BoardsResponse {
+    pub boards: Vec<SharedBoard>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateBoardRequest {
+    pub wrapped_key: String,
+}
+
+#[derive(Serialize)]
+pub struct CreateBoardResponse {
+    pub id: Uuid,
+}
+
+#[derive(Deserialize)]
+pub struct ShareBoardRequest {
+    pub username: String,
+    pub wrapped_key: String,
+    pub can_write: bool,
+}
+
+#[derive(Deserialize)]
+pub struct BoardKeyRequest {
+    pub wrapped_key: String,
+}
+
+/// The caller's membership of a board: `(owner_id, can_write)`.
+///
+/// Boards the caller is not a member of are reported as not found, so their
+/// existence is not revealed.
+async fn membership(state: &AppState, board_id: Uuid, user_id: Uuid) -> Result<(Uuid, bool)> {
+    sqlx::query_as::<_, (Uuid, bool)>(
+        "SELECT b.owner_id, m.can_write FROM board_members m \
+         JOIN shared_boards b ON b.id = m.board_id \
+         WHERE m.board_id = $1 AND m.user_id = $2",
+    )
+    .bind(board_id)
+    .bind(user_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(ServerError::Database)?
+    .ok_or_else(|| ServerError::NotFound("board not found".to_string()))
+}
+
+/// Like [`membership`], but only the owner may continue.
+async fn require_owner(state: &AppState, board_id: Uuid, user_id: Uuid) -> Result<()> {
+    let (owner_id, _) = membership(state, board_id, user_id).await?;
+    if owner_id != user_id {
+        return Err(ServerError::Forbidden(
+            "only the board owner can do this".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Like [`membership`], but only members with write access may continue.
+async fn require_write(state: &AppState, board_id: Uuid, user_id: Uuid) -> Result<()> {
+    let (_, can_write) = membership(state, board_id, user_id).await?;
+    if !can_write {
+        return Err(ServerError::Forbidden(
+            "this board is shared with you read-only".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Tell every member's connected clients that the board changed, and
+/// return the members.
+async fn notify_members(state: &AppState, board_id: Uuid) -> Result<Vec<Uuid>> {
+    let members =
+        sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM board_members WHERE board_id = $1")
+            .bind(board_id)
+            .fetch_all(&state.pool)
+            .await
+            .map_err(ServerError::Database)?;
+    for &user_id in &members {
+        state
+            .notifications
+            .notify(user_id, SyncEvent::DataChanged { archived: false });
+    }
+    Ok(members)
+}
+
+fn decode_wrapped_key(wrapped_key: &str) -> Result<Vec<u8>> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(wrapped_key)
+        .map_err(|e| ServerError::Validation(format!("invalid base64 wrapped key: {e}")))?;
+    if bytes.is_empty() || bytes.len() > MAX_WRAPPED_KEY_LEN {
+        return Err(ServerError::Validation(
+            "invalid wrapped key size".to_string(),
+        ));
+    }
+    Ok(bytes)
+}
+
+#[tracing::instrument(skip(state, req))]
+pub async fn put_public_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<PublicKeyRequest>,
+) -> Result<()> {
+    let key = base64::engine::general_purpose::STANDARD
+        .decode(&req.public_key)
+        .map_err(|e| ServerError::Validation(format!("invalid base64 public key: {e}")))?;
+    if key.len() != PUBLIC_KEY_LEN {
+        return Err(ServerError::Validation(
+            "public key must be 32 bytes".to_string(),
+        ));
+    }
+
+    sqlx::query("UPDATE users SET public_key = $1 WHERE id = $2")
+        .bind(&key)
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(state))]
+pub async fn get_public_key(
+    State(state): State<AppState>,
+    _auth: AuthUser,
+    Path(username): Path<String>,
+) -> Result<Json<PublicKeyResponse>> {
+    let key = sqlx::query_scalar::<_, Option<Vec<u8>>>(
+        "SELECT public_key FROM users WHERE username = $1",
+    )
+    .bind(&username)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(ServerError::Database)?
+    .ok_or_else(|| ServerError::NotFound(format!("no user named {username}")))?
+    .ok_or_else(|| {
+        ServerError::NotFound(format!(
+            "{username} has not logged in with sharing support yet"
+        ))
+    })?;
+
+    Ok(Json(PublicKeyResponse {
+        public_key: base64::engine::general_purpose::STANDARD.encode(key),
+    }))
+}
+
+#[tracing::instrument(skip(state))]
+pub async fn list_boards(
+    State(state): State<AppState>,
+    auth: ReadAccess,
+) -> Result<Json<BoardsResponse>> {
+    let boards = boards_of(&state.pool, auth.user_id).await?;
+    Ok(Json(BoardsResponse { boards }))
+}
+
+/// The shared boards `user_id` is a member of, with their members
+pub(crate) async fn boards_of(pool: &DbPool, user_id: Uuid) -> Result<Vec<SharedBoard>> {
+    let rows = sqlx::query_as::<_, (Uuid, Uuid, String, bool, Vec<u8>)>(
+        "SELECT b.id, b.owner_id, u.username, m.can_write, m.wrapped_key FROM board_members m \
+         JOIN shared_boards b ON b.id = m.board_id \
+         JOIN users u ON u.id = b.owner_id \
+         WHERE m.user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let member_rows = sqlx::query_as::<_, (Uuid, String, bool)>(
+        "SELECT m.board_id, u.username, m.can_write FROM board_members m \
+         JOIN users u ON u.id = m.user_id \
+         WHERE m.board_id IN (SELECT board_id FROM board_members WHERE user_id = $1) \
+         ORDER BY u.username",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let mut members: HashMap<Uuid, Vec<BoardMember>> = HashMap::new();
+    for (board_id, username, can_write) in member_rows {
+        members.entry(board_id).or_default().push(BoardMember {
+            username,
+            can_write,
+        });
+    }
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, owner_id, owner, can_write, wrapped_key)| SharedBoard {
+                id,
+                owner,
+                is_owner: owner_id == user_id,
+                can_write,
+                wrapped_key: base64::engine::general_purpose::STANDARD.encode(wrapped_key),
+                members: members.remove(&id).unwrap_or_default(),
+            },
+        )
+        .collect())
+}
+
+#[tracing::instrument(skip(state, req))]
+pub async fn create_board(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<CreateBoardRequest>,
+) -> Result<Json<CreateBoardResponse>> {
+    let wrapped_key = decode_wrapped_key(&req.wrapped_key)?;
+
+    let mut tx = state.pool.begin().await.map_err(ServerError::Database)?;
+    let id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO shared_boards (owner_id) VALUES ($1) RETURNING id",
+    )
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ServerError::Database)?;
+
+    sqlx::query(
+        "INSERT INTO board_members (board_id, user_id, wrapped_key, can_write) \
+         VALUES ($1, $2, $3, true)",
+    )
+    .bind(id)
+    .bind(auth.user_id)
+    .bind(&wrapped_key)
+    .execute(&mut *tx)
+    .await
+    .map_err(ServerError::Database)?;
+    tx.commit().await.map_err(ServerError::Database)?;
+
+    tracing::info!(board_id = %id, "shared board created");
+
+    Ok(Json(CreateBoardResponse { id }))
+}
+
+/// Stop sharing a board altogether. Its items are deleted from the server;
+/// the owner's client moves them back to the owner's own items first.
+#[tracing::instrument(skip(state))]
+pub async fn delete_board(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(board_id): Path<Uuid>,
+) -> Result<()> {
+    require_owner(&state, board_id, auth.user_id).await?;
+    notify_members(&state, board_id).await?;
+
+    sqlx::query("DELETE FROM shared_boards WHERE id = $1")
+        .bind(board_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    tracing::info!(board_id = %board_id, "shared board deleted");
+
+    Ok(())
+}
+
+/// Add a member to a board, or change their access
+#[tracing::instrument(skip(state, req), fields(username = %req.username))]
+pub async fn share_board(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(board_id): Path<Uuid>,
+    Json(req): Json<ShareBoardRequest>,
+) -> Result<()> {
+    require_owner(&state, board_id, auth.user_id).await?;
+    let wrapped_key = decode_wrapped_key(&req.wrapped_key)?;
+
+    let user_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+        .bind(&req.username)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(ServerError::Database)?
+        .ok_or_else(|| ServerError::NotFound(format!("no user named {}", req.username)))?;
+    if user_id == auth.user_id {
+        return Err(ServerError::Validation(
+            "you already own this board".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        "INSERT INTO board_members (board_id, user_id, wrapped_key, can_write) \
+         VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (board_id, user_id) \
+         DO UPDATE SET wrapped_key = EXCLUDED.wrapped_key, can_write = EXCLUDED.can_write",
+    )
+    .bind(board_id)
+    .bind(user_id)
+    .bind(&wrapped_key)
+    .bind(req.can_write)
+    .execute(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    state
+        .notifications
+        .notify(user_id, SyncEvent::DataChanged { archived: false });
+
+    tracing::info!(board_id = %board_id, "board shared");
+
+    Ok(())
+}
+
+/// Replace the caller's own copy of a board's key, wrapped for their new
+/// identity after their encryption key changed.
+#[tracing::instrument(skip(state, req))]
+pub async fn put_board_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(board_id): Path<Uuid>,
+    Json(req): Json<BoardKeyRequest>,
+) -> Result<()> {
+    let wrapped_key = decode_wrapped_key(&req.wrapped_key)?;
+
+    let result = sqlx::query(
+        "UPDATE board_members SET wrapped_key = $1 WHERE board_id = $2 AND user_id = $3",
+    )
+    .bind(&wrapped_key)
+    .bind(board_id)
+    .bind(auth.user_id)
+    .execute(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+    if result.rows_affected() == 0 {
+        return Err(ServerError::NotFound("board not found".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Remove a member from a board. The owner can remove anyone else; other
+/// members can only remove themselves.
+#[tracing::instrument(skip(state))]
+pub async fn unshare_board(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((board_id, username)): Path<(Uuid, String)>,
+) -> Result<()> {
+    let (owner_id, _) = membership(&state, board_id, auth.user_id).await?;
+
+    let user_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = $1")
+        .bind(&username)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(ServerError::Database)?
+        .ok_or_else(|| ServerError::NotFound(format!("no user named {username}")))?;
+
+    if user_id == owner_id {
+        return Err(ServerError::Validation(
+            "the owner cannot leave a board; stop sharing it instead".to_string(),
+        ));
+    }
+    if auth.user_id != owner_id && auth.user_id != user_id {
+        return Err(ServerError::Forbidden(
+            "only the board owner can do this".to_string(),
+        ));
+    }
+
+    sqlx::query("DELETE FROM board_members WHERE board_id = $1 AND user_id = $2")
+        .bind(board_id)
+        .bind(user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    state
+        .notifications
+        .notify(user_id, SyncEvent::DataChanged { archived: false });
+
+    tracing::info!(board_id = %board_id, "board unshared");
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(state))]
+pub async fn get_board_items(
+    State(state): State<AppState>,
+    auth: ReadAccess,
+    Path(board_id): Path<Uuid>,
+) -> Result<Json<ItemsResponse>> {
+    membership(&state, board_id, auth.user_id).await?;
+
+    let rows = sqlx::query_as::<_, (String, Vec<u8>, Vec<u8>)>(
+        "SELECT item_key, data, nonce FROM board_items \
+         WHERE board_id = $1 AND deleted_at IS NULL",
+    )
+    .bind(board_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    Ok(Json(ItemsResponse {
+        items: rows_to_encrypted_items(rows),
+    }))
+}
+
+#[tracing::instrument(skip(state, req), fields(item_count = req.items.len()))]
+pub async fn put_board_items(
+    State(state): State<AppState>,
+    auth: WriteAccess,
+    Path(board_id): Path<Uuid>,
+    Json(req): Json<PutItemsRequest>,
+) -> Result<()> {
+    require_write(&state, board_id, auth.user_id).await?;
+    let changes = replace_items(&state, Store::Board(board_id), false, &req.items).await?;
+
+    let members = notify_members(&state, board_id).await?;
+    state.webhooks.notify(
+        &state.pool,
+        members,
+        auth.user_id,
+        Event::Board(board_id),
+        changes,
+    );
+    Ok(())
+}
+
+/// Items removed from a shared board by uploads, newest first.
+#[tracing::instrument(skip(state))]
+pub async fn get_board_trash(
+    State(state): State<AppState>,
+    auth: ReadAccess,
+    Path(board_id): Path<Uuid>,
+) -> Result<Json<TrashResponse>> {
+    membership(&state, board_id, auth.user_id).await?;
+    Ok(Json(items::trash(&state, Store::Board(board_id)).await?))
+}
+
+/// Put items from a shared board's trash back on the board.
+#[tracing::instrument(skip(state, req))]
+pub async fn restore_board_trash(
+    State(state): State<AppState>,
+    auth: WriteAccess,
+    Path(board_id): Path<Uuid>,
+    Json(req): Json<RestoreRequest>,
+) -> Result<Json<RestoreResponse>> {
+    require_write(&state, board_id, auth.user_id).await?;
+    let restored =
+        items::restore_items(&state, Store::Board(board_id), req.keys.as_deref()).await?;
+    let restored = restored.len() as u64;
+
+    if restored > 0 {
+        let members = notify_members(&state, board_id).await?;
+        state.webhooks.notify(
+            &state.pool,
+            members,
+            auth.user_id,
+            Event::Board(board_id),
+            Changes {
+                changed: restored,
+                removed: 0,
+            },
+        );
+    }
+    tracing::info!(board_id = %board_id, restored, "board items restored from trash");
+
+    Ok(Json(RestoreResponse { restored }))
+}
+
+/// Stored and earlier versions of an item on a shared board, newest first.
+#[tracing::instrument(skip(state))]
+pub async fn get_board_item_history(
+    State(state): State<AppState>,
+    auth: ReadAccess,
+    Path((board_id, key)): Path<(Uuid, String)>,
+) -> Result<Json<HistoryResponse>> {
+    membership(&state, board_id, auth.user_id).await?;
+    Ok(Json(
+        items::history(&state, Store::Board(board_id), key).await?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    /// A board owned by `owner`, shared with `members` as `(id, can_write)`
+    async fn board(state: &AppState, owner: Uuid, members: &[(Uuid, bool)]) -> Uuid {
+        let board_id = sqlx::query_scalar::<_, Uuid>(
+            "INSERT INTO shared_boards (owner_id) VALUES ($1) RETURNING id",
+        )
+        .bind(owner)
+        .fetch_one(&state.pool)
+        .await
+        .unwrap();
+        for &(user_id, can_write) in [(owner, true)].iter().chain(members) {
+            sqlx::query(
+                "INSERT INTO board_members (board_id, user_id, wrapped_key, can_write) \
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(board_id)
+            .bind(user_id)
+            .bind(b"wrapped".to_vec())
+            .bind(can_write)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        }
+        board_id
+    }
+
+    async fn put(
+        state: &AppState,
+        user_id: Uuid,
+        board_id: Uuid,
+        entries: &[(&str, &str)],
+    ) -> Result<()> {
+        put_board_items(
+            State(state.clone()),
+            WriteAccess { user_id },
+            Path(board_id),
+            Json(PutItemsRequest {
+                items: testing::items(entries),
+            }),
+        )
+        .await
+    }
+
+    async fn stored_keys(state: &AppState, board_id: Uuid) -> Vec<String> {
+        let read = ReadAccess {
+            user_id: sqlx::query_scalar("SELECT owner_id FROM shared_boards WHERE id = $1")
+                .bind(board_id)
+                .fetch_one(&state.pool)
+                .await
+                .unwrap(),
+            can_write: true,
+        };
+        let Json(response) = get_board_items(State(state.clone()), read, Path(board_id))
+            .await
+            .unwrap();
+        let mut keys: Vec<String> = response.items.into_keys().collect();
+        keys.sort();
+        keys
+    }
+
+    #[tokio::test]
+    async fn board_writes_count_towards_the_owners_quota() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let owner = testing::user(&state, "owner").await;
+        let member = testing::user(&state, "member").await;
+        let board_id = board(&state, owner, &[(member, true)]).await;
+        sqlx::query("UPDATE users SET quota_bytes = 16 WHERE id = $1")
+            .bind(owner)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        put(&state, member, board_id, &[("a", "twelve bytes")])
+            .await
+            .unwrap();
+        let err = put(
+            &state,
+            member,
+            board_id,
+            &[("a", "twelve bytes"), ("b", "five!")],
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ServerError::QuotaExceeded {
+                used: 17,
+                quota: 16
+            }
+        ));
+        assert_eq!(stored_keys(&state, board_id).await, vec!["a"]);
+
+        // The member's own quota is not what counts
+        sqlx::query("UPDATE users SET quota_bytes = 0 WHERE id = $1")
+            .bind(member)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        put(
+            &state,
+            member,
+            board_id,
+            &[("a", "twelve bytes"), ("b", "four")],
+        )
+        .await
+        .unwrap();
+        assert_eq!(stored_keys(&state, board_id).await, vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn items_removed_from_a_board_can_be_restored() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let owner = testing::user(&state, "owner").await;
+        let reader = testing::user(&state, "reader").await;
+        let board_id = board(&state, owner, &[(reader, false)]).await;
+
+        put(&state, owner, board_id, &[("a", "first"), ("b", "second")])
+            .await
+            .unwrap();
+        put(&state, owner, board_id, &[("a", "first, edited")])
+            .await
+            .unwrap();
+        assert_eq!(stored_keys(&state, board_id).await, vec!["a"]);
+
+        let read = ReadAccess {
+            user_id: reader,
+            can_write: false,
+        };
+        let Json(trash) = get_board_trash(State(state.clone()), read.clone(), Path(board_id))
+            .await
+            .unwrap();
+        let trashed: Vec<&str> = trash.items.iter().map(|item| item.key.as_str()).collect();
+        assert_eq!(trashed, vec!["b"]);
+
+        let Json(history) =
+            get_board_item_history(State(state.clone()), read, Path((board_id, "a".into())))
+                .await
+                .unwrap();
+        assert_eq!(history.versions.len(), 2);
+
+        let restore = |user_id| {
+            restore_board_trash(
+                State(state.clone()),
+                WriteAccess { user_id },
+                Path(board_id),
+                Json(RestoreRequest {
+                    keys: Some(vec!["b".to_string()]),
+                }),
+            )
+        };
+        assert!(matches!(
+            restore(reader).await,
+            Err(ServerError::Forbidden(_))
+        ));
+        let Json(restored) = restore(owner).await.unwrap();
+        assert_eq!(restored.restored, 1);
+        assert_eq!(stored_keys(&state, board_id).await, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,586 @@
+//! Email verification and password reset.
+//!
+//! Both only work when the server is configured to send mail. The emailed
+//! links carry the token in the URL fragment, and the page they open posts
+//! it back, so a token is never in a server log and is not used up by mail
+//! scanners that fetch links.
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::response::{Html, IntoResponse};
+use axum::Json;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::{hash_password, hash_token, random_token};
+use crate::db::DbPool;
+use crate::error::{Result, ServerError};
+use crate::handlers::audit::{self, AuditEvent};
+use crate::handlers::user::{check_totp, validate_password};
+use crate::mailer::Mailer;
+use crate::middleware::AuthUser;
+use crate::rate_limit::ClientIp;
+use crate::router::AppState;
+
+/// How long a verification link stays valid
+const VERIFY_EXPIRY_HOURS: i64 = 48;
+
+/// How long a password reset link stays valid
+const RESET_EXPIRY_HOURS: i64 = 1;
+
+/// Page that the emailed links open
+const LINK_PAGE: &str = include_str!("email_link.html");
+
+#[derive(Clone, Copy)]
+enum Purpose {
+    Verify,
+    Reset,
+}
+
+impl Purpose {
+    fn as_str(self) -> &'static str {
+        match self {
+            Purpose::Verify => "verify",
+            Purpose::Reset => "reset",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SendVerificationResponse {
+    /// The address the email was sent to
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+    /// Needed when two-factor authentication is enabled
+    #[serde(default)]
+    pub totp_code: Option<String>,
+}
+
+fn mailer(state: &AppState) -> Result<&Mailer> {
+    state
+        .mailer
+        .as_ref()
+        .ok_or_else(|| ServerError::NotFound("this server does not send email".to_string()))
+}
+
+/// Create a token for `purpose`, replacing any earlier ones of the user's.
+async fn issue_token(
+    pool: &DbPool,
+    user_id: Uuid,
+    purpose: Purpose,
+    email: &str,
+    lifetime: Duration,
+) -> Result<String> {
+    let token = random_token();
+
+    let mut tx = pool.begin().await.map_err(ServerError::Database)?;
+    sqlx::query("DELETE FROM email_tokens WHERE user_id = $1 AND purpose = $2")
+        .bind(user_id)
+        .bind(purpose.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(ServerError::Database)?;
+    sqlx::query(
+        "INSERT INTO email_tokens (token_hash, user_id, purpose, email, expires_at) \
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(hash_token(&token))
+    .bind(user_id)
+    .bind(purpose.as_str())
+    .bind(email)
+    .bind(Utc::now() + lifetime)
+    .execute(&mut *tx)
+    .await
+    .map_err(ServerError::Database)?;
+    tx.commit().await.map_err(ServerError::Database)?;
+
+    Ok(token)
+}
+
+/// The user and address behind an unexpired token
+async fn find_token(pool: &DbPool, token: &str, purpose: Purpose) -> Result<(Uuid, String)> {
+    sqlx::query_as::<_, (Uuid, String)>(
+        "SELECT user_id, email FROM email_tokens \
+         WHERE token_hash = $1 AND purpose = $2 AND expires_at > $3",
+    )
+    .bind(hash_token(token))
+    .bind(purpose.as_str())
+    .bind(Utc::now())
+    .fetch_optional(pool)
+    .await
+    .map_err(ServerError::Database)?
+    .ok_or_else(|| ServerError::Validation("this link is invalid or has expired".to_string()))
+}
+
+/// Email `username` a link to verify `email`.
+pub(crate) async fn email_verification(
+    pool: &DbPool,
+    mailer: &Mailer,
+    user_id: Uuid,
+    username: &str,
+    email: &str,
+) -> Result<()> {
+    let token = issue_token(
+        pool,
+        user_id,
+        Purpose::Verify,
+        email,
+        Duration::hours(VERIFY_EXPIRY_HOURS),
+    )
+    .await?;
+    let link = mailer.link(&format!("/verify-email#{token}"));
+    mailer.send_later(
+        email.to_string(),
+        "Confirm your email address for taskbook",
+        format!(
+            "Hi {username},\n\n\
+             Open this link to confirm your email address for taskbook:\n\n\
+             {link}\n\n\
+             The link expires in {VERIFY_EXPIRY_HOURS} hours. If you did not create \
+             a taskbook account, you can ignore this email.\n"
+        ),
+    );
+    Ok(())
+}
+
+/// Send (again) a link to verify the account's email address.
+#[tracing::instrument(skip(state))]
+pub async fn send_verification(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<SendVerificationResponse>> {
+    let mailer = mailer(&state)?;
+    let (username, email, verified) = sqlx::query_as::<_, (String, String, bool)>(
+        "SELECT username, email, email_verified FROM users WHERE id = $1",
+    )
+    .bind(auth.user_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+    if verified {
+        return Err(ServerError::Validation(
+            "email address is already verified".to_string(),
+        ));
+    }
+
+    email_verification(&state.pool, mailer, auth.user_id, &username, &email).await?;
+
+    Ok(Json(SendVerificationResponse { email }))
+}
+
+/// Mark an email address as verified, from the token in a verification link.
+#[tracing::instrument(skip(state, req))]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(req): Json<VerifyEmailRequest>,
+) -> Result<()> {
+    mailer(&state)?;
+    let (user_id, email) = find_token(&state.pool, &req.token, Purpose::Verify).await?;
+
+    // Only verify the address the link was sent to
+    sqlx::query("UPDATE users SET email_verified = true WHERE id = $1 AND email = $2")
+        .bind(user_id)
+        .bind(&email)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+    sqlx::query("DELETE FROM email_tokens WHERE user_id = $1 AND purpose = $2")
+        .bind(user_id)
+        .bind(Purpose::Verify.as_str())
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    tracing::info!(user_id = %user_id, "email verified");
+
+    Ok(())
+}
+
+/// Email a password reset link. Responds the same whether or not an account
+/// has the address, so it cannot be used to find out who has one.
+#[tracing::instrument(skip(state, req))]
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    Json(req): Json<ForgotPasswordRequest>,
+) -> Result<()> {
+    let mailer = mailer(&state)?;
+    let user = sqlx::query_as::<_, (Uuid, String, String)>(
+        "SELECT id, username, email FROM users WHERE lower(email) = lower($1)",
+    )
+    .bind(req.email.trim())
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let Some((user_id, username, email)) = user else {
+        tracing::info!("password reset requested for unknown email");
+        return Ok(());
+    };
+
+    let token = issue_token(
+        &state.pool,
+        user_id,
+        Purpose::Reset,
+        &email,
+        Duration::hours(RESET_EXPIRY_HOURS),
+    )
+    .await?;
+    let link = mailer.link(&format!("/reset-password#{token}"));
+    mailer.send_later(
+        email,
+        "Reset your taskbook password",
+        format!(
+            "Hi {username},\n\n\
+             Someone asked to reset the password of your taskbook account. \
+             Open this link to choose a new one:\n\n\
+             {link}\n\n\
+             The link expires in {RESET_EXPIRY_HOURS} hour. If you did not ask for \
+             this, you can ignore this email; your password has not changed.\n"
+        ),
+    );
+
+    tracing::info!(user_id = %user_id, "password reset requested");
+
+    Ok(())
+}
+
+/// Set a new password from the token in a reset link. Every session is
+/// ended, as with a password change.
+#[tracing::instrument(skip(state, headers, req))]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    Json(req): Json<ResetPasswordRequest>,
+) -> Result<()> {
+    mailer(&state)?;
+    let (user_id, email) = find_token(&state.pool, &req.token, Purpose::Reset).await?;
+    validate_password(&req.new_password)?;
+
+    // Access to the mailbox alone must not get past two-factor authentication
+    let (totp_enabled, totp_secret) = sqlx::query_as::<_, (bool, Option<Vec<u8>>)>(
+        "SELECT totp_enabled, totp_secret FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+    if totp_enabled {
+        let secret = totp_secret
+            .ok_or_else(|| ServerError::Internal("two-factor secret missing".to_string()))?;
+        let code = req.totp_code.as_deref().ok_or(ServerError::TotpRequired)?;
+        check_totp(&state.pool, user_id, &secret, code).await?;
+    }
+
+    let password_hash = hash_password(&req.new_password)
+        .map_err(|e| ServerError::Internal(format!("password hashing failed: {e}")))?;
+
+    let mut tx = state.pool.begin().await.map_err(ServerError::Database)?;
+    // The link reached the inbox, which verifies the address too
+    sqlx::query(
+        "UPDATE users SET password = $1, email_verified = email_verified OR email = $2 \
+         WHERE id = $3",
+    )
+    .bind(&password_hash)
+    .bind(&email)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(ServerError::Database)?;
+    sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(ServerError::Database)?;
+    sqlx::query("DELETE FROM email_tokens WHERE user_id = $1 AND purpose = $2")
+        .bind(user_id)
+        .bind(Purpose::Reset.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(ServerError::Database)?;
+    tx.commit().await.map_err(ServerError::Database)?;
+    audit::record(
+        &state.pool,
+        user_id,
+        AuditEvent::PasswordReset,
+        ip,
+        &headers,
+    )
+    .await?;
+
+    tracing::info!(user_id = %user_id, "password reset");
+
+    Ok(())
+}
+
+/// The page behind `/verify-email` and `/reset-password`. It reads the
+/// token from the URL fragment and posts it to the matching endpoint.
+pub async fn view_page() -> impl IntoResponse {
+    (
+        [
+            (header::CACHE_CONTROL, "no-store"),
+            (header::REFERRER_POLICY, "no-referrer"),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; script-src 'unsafe-inline'; \
+                 style-src 'unsafe-inline'; connect-src 'self'",
+            ),
+        ],
+        Html(LINK_PAGE),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use super::*;
+    use crate::auth::verify_password;
+    use crate::config::{SmtpConfig, SmtpTls};
+    use crate::handlers::user::create_session;
+    use crate::testing;
+    use crate::totp;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    const NEW_PASSWORD: &str = "correct horse battery staple";
+
+    /// Server state that sends mail, to a server that is never reached
+    async fn state() -> Option<AppState> {
+        let mut state = testing::state().await?;
+        let config = SmtpConfig {
+            host: "localhost".to_string(),
+            port: 2525,
+            tls: SmtpTls::None,
+            credentials: None,
+            from: "taskbook@example.com".to_string(),
+            public_url: "https://tasks.example.com".to_string(),
+        };
+        state.mailer = Some(Mailer::new(&config).unwrap());
+        Some(state)
+    }
+
+    /// The token a reset link sent to the user's address would carry
+    async fn reset_token(state: &AppState, user_id: Uuid) -> String {
+        let email: String = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+        issue_token(
+            &state.pool,
+            user_id,
+            Purpose::Reset,
+            &email,
+            Duration::hours(RESET_EXPIRY_HOURS),
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn reset(state: &AppState, token: &str, totp_code: Option<String>) -> Result<()> {
+        let req = ResetPasswordRequest {
+            token: token.to_string(),
+            new_password: NEW_PASSWORD.to_string(),
+            totp_code,
+        };
+        reset_password(
+            State(state.clone()),
+            ClientIp(IP),
+            HeaderMap::new(),
+            Json(req),
+        )
+        .await
+    }
+
+    async fn verify(state: &AppState, token: &str) -> Result<()> {
+        let req = VerifyEmailRequest {
+            token: token.to_string(),
+        };
+        verify_email(State(state.clone()), Json(req)).await
+    }
+
+    async fn password_is_new(state: &AppState, user_id: Uuid) -> bool {
+        let hash: String = sqlx::query_scalar("SELECT password FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+        verify_password(NEW_PASSWORD, &hash).unwrap_or(false)
+    }
+
+    async fn email_verified(state: &AppState, user_id: Uuid) -> bool {
+        sqlx::query_scalar("SELECT email_verified FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&state.pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn expired_links_are_refused() {
+        let Some(state) = state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let token = reset_token(&state, user_id).await;
+        sqlx::query("UPDATE email_tokens SET expires_at = $1")
+            .bind(Utc::now() - Duration::minutes(1))
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            reset(&state, &token, None).await,
+            Err(ServerError::Validation(_))
+        ));
+        assert!(!password_is_new(&state, user_id).await);
+    }
+
+    #[tokio::test]
+    async fn links_work_once() {
+        let Some(state) = state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+
+        let token = reset_token(&state, user_id).await;
+        reset(&state, &token, None).await.unwrap();
+        assert!(password_is_new(&state, user_id).await);
+        let logged: Vec<String> = sqlx::query_scalar("SELECT event FROM audit_log")
+            .fetch_all(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(logged, ["password_reset"]);
+        assert!(matches!(
+            reset(&state, &token, None).await,
+            Err(ServerError::Validation(_))
+        ));
+
+        let token = issue_token(
+            &state.pool,
+            user_id,
+            Purpose::Verify,
+            "alice@example.com",
+            Duration::hours(VERIFY_EXPIRY_HOURS),
+        )
+        .await
+        .unwrap();
+        verify(&state, &token).await.unwrap();
+        assert!(matches!(
+            verify(&state, &token).await,
+            Err(ServerError::Validation(_))
+        ));
+
+        // A new link replaces the one before
+        let first = reset_token(&state, user_id).await;
+        let second = reset_token(&state, user_id).await;
+        assert!(reset(&state, &first, None).await.is_err());
+        assert!(reset(&state, &second, None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn resets_need_the_two_factor_code_when_it_is_enabled() {
+        let Some(state) = state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let secret = totp::generate_secret();
+        sqlx::query("UPDATE users SET totp_enabled = true, totp_secret = $1 WHERE id = $2")
+            .bind(&secret)
+            .bind(user_id)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        let token = reset_token(&state, user_id).await;
+
+        assert!(matches!(
+            reset(&state, &token, None).await,
+            Err(ServerError::TotpRequired)
+        ));
+        let now = Utc::now().timestamp();
+        let wrong = totp::code(&secret, now - 3600);
+        assert!(matches!(
+            reset(&state, &token, Some(wrong)).await,
+            Err(ServerError::InvalidTotpCode)
+        ));
+        assert!(!password_is_new(&state, user_id).await);
+
+        reset(&state, &token, Some(totp::code(&secret, now)))
+            .await
+            .unwrap();
+        assert!(password_is_new(&state, user_id).await);
+    }
+
+    #[tokio::test]
+    async fn resets_end_every_session() {
+        let Some(state) = state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let other_id = testing::user(&state, "bob").await;
+        for id in [user_id, user_id, other_id] {
+            create_session(&state, id, None, IP).await.unwrap();
+        }
+
+        let token = reset_token(&state, user_id).await;
+        reset(&state, &token, None).await.unwrap();
+
+        let sessions: Vec<Uuid> = sqlx::query_scalar("SELECT user_id FROM sessions")
+            .fetch_all(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(sessions, vec![other_id]);
+    }
+
+    #[tokio::test]
+    async fn verify_email_only_verifies_the_address_the_link_was_sent_to() {
+        let Some(state) = state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let issue = || {
+            issue_token(
+                &state.pool,
+                user_id,
+                Purpose::Verify,
+                "alice@example.com",
+                Duration::hours(VERIFY_EXPIRY_HOURS),
+            )
+        };
+
+        // The address changed after the link was sent
+        let token = issue().await.unwrap();
+        sqlx::query("UPDATE users SET email = 'alice@example.org' WHERE id = $1")
+            .bind(user_id)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        verify(&state, &token).await.unwrap();
+        assert!(!email_verified(&state, user_id).await);
+
+        sqlx::query("UPDATE users SET email = 'alice@example.com' WHERE id = $1")
+            .bind(user_id)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        let token = issue().await.unwrap();
+        verify(&state, &token).await.unwrap();
+        assert!(email_verified(&state, user_id).await);
+    }
+}
@@ -4,6 +4,7 @@ use std::task::{Context, Poll};
 use std::time::Duration;
 
 use axum::extract::State;
+use axum::http::HeaderMap;
 use axum::response::sse::{Event, KeepAlive, Sse};
 use futures_util::stream::Stream;
 use opentelemetry::metrics::UpDownCounter;
@@ -11,39 +12,41 @@ use opentelemetry::{global, KeyValue};
 use tokio_stream::wrappers::BroadcastStream;
 use tokio_stream::StreamExt;
 
-use crate::middleware::AuthUser;
-use crate::router::{AppState, SyncEvent};
+use crate::middleware::ReadAccess;
+use crate::router::{AppState, Notification, NotificationHub, SyncEvent};
 
-/// Guard that decrements the SSE active-connections counter on drop.
-struct SseConnectionGuard {
+/// Guard that counts a live sync connection in `metric`, decrementing it on
+/// drop.
+pub struct ConnectionGuard {
     counter: UpDownCounter<i64>,
+    endpoint: &'static str,
 }
 
-impl SseConnectionGuard {
-    fn new() -> Self {
+impl ConnectionGuard {
+    pub fn new(metric: &'static str, description: &'static str, endpoint: &'static str) -> Self {
         let meter = global::meter("taskbook-server");
         let counter = meter
-            .i64_up_down_counter("sse.active_connections")
-            .with_description("Number of active SSE connections")
+            .i64_up_down_counter(metric)
+            .with_description(description)
             .build();
-        counter.add(1, &[KeyValue::new("endpoint", "/api/v1/events")]);
-        Self { counter }
+        counter.add(1, &[KeyValue::new("endpoint", endpoint)]);
+        Self { counter, endpoint }
     }
 }
 
-impl Drop for SseConnectionGuard {
+impl Drop for ConnectionGuard {
     fn drop(&mut self) {
         self.counter
-            .add(-1, &[KeyValue::new("endpoint", "/api/v1/events")]);
+            .add(-1, &[KeyValue::new("endpoint", self.endpoint)]);
     }
 }
 
-/// Wrapper stream that holds a [`SseConnectionGuard`]. When the client
+/// Wrapper stream that holds a [`ConnectionGuard`]. When the client
 /// disconnects and the stream is dropped, the counter is automatically
 /// decremented.
 struct TrackedStream<S> {
     inner: S,
-    _guard: SseConnectionGuard,
+    _guard: ConnectionGuard,
 }
 
 impl<S> Stream for TrackedStream<S>
@@ -57,27 +60,104 @@ where
     }
 }
 
+/// How often an idle stream gets a heartbeat comment
+const HEARTBEAT: Duration = Duration::from_secs(15);
+
 /// SSE endpoint that streams real-time sync notifications to authenticated clients.
-#[tracing::instrument(skip(state))]
+///
+/// Each event has an ID. A client reconnecting with `Last-Event-ID` is first
+/// sent what it missed: the latest event for items and for the archive, or
+/// both if the server no longer knows (the ID is too old, or from before a
+/// restart).
+#[tracing::instrument(skip(state, headers))]
 pub async fn events(
     State(state): State<AppState>,
-    auth: AuthUser,
+    auth: ReadAccess,
+    headers: HeaderMap,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-    let rx = state.notifications.subscribe(auth.user_id);
+    let hub = state.notifications;
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok());
+    let subscription = hub.resume(auth.user_id, last_event_id);
+
+    let replay = match (last_event_id, subscription.missed) {
+        (None, _) => Vec::new(),
+        (Some(_), Some(missed)) => latest_per_scope(missed)
+            .into_iter()
+            .map(|n| data_changed(&hub, n))
+            .collect(),
+        (Some(_), None) => [false, true]
+            .into_iter()
+            .map(|archived| {
+                let n = Notification {
+                    id: subscription.last_id,
+                    event: SyncEvent::DataChanged { archived },
+                };
+                data_changed(&hub, n)
+            })
+            .collect(),
+    };
 
-    let stream = BroadcastStream::new(rx).filter_map(|result| match result {
-        Ok(SyncEvent::DataChanged { archived }) => {
-            let data = if archived { "archive" } else { "items" };
-            Some(Ok(Event::default().event("data_changed").data(data)))
-        }
+    let live = BroadcastStream::new(subscription.receiver).filter_map(move |result| match result {
+        Ok(n) => Some(data_changed(&hub, n)),
         // Lagged: receiver fell behind — tell the client to do a full refresh.
-        Err(_) => Some(Ok(Event::default().event("data_changed").data("items"))),
+        Err(_) => Some(Event::default().event("data_changed").data("items")),
     });
+    let stream = tokio_stream::iter(replay).chain(live).map(Ok);
 
     let tracked = TrackedStream {
         inner: stream,
-        _guard: SseConnectionGuard::new(),
+        _guard: ConnectionGuard::new(
+            "sse.active_connections",
+            "Number of active SSE connections",
+            "/api/v1/events",
+        ),
     };
 
-    Sse::new(tracked).keep_alive(KeepAlive::new().interval(Duration::from_secs(15)))
+    Sse::new(tracked).keep_alive(KeepAlive::new().interval(HEARTBEAT).text("heartbeat"))
+}
+
+fn data_changed(hub: &NotificationHub, notification: Notification) -> Event {
+    let SyncEvent::DataChanged { archived } = notification.event;
+    Event::default()
+        .id(hub.event_id(notification.id))
+        .event("data_changed")
+        .data(if archived { "archive" } else { "items" })
+}
+
+/// The last of `notifications` about items and the last about the archive,
+/// in the order they were sent
+fn latest_per_scope(notifications: Vec<Notification>) -> Vec<Notification> {
+    let mut latest: Vec<Notification> = Vec::with_capacity(2);
+    for notification in notifications {
+        let SyncEvent::DataChanged { archived } = notification.event;
+        latest.retain(
+            |n| !matches!(n.event, SyncEvent::DataChanged { archived: a } if a == archived),
+        );
+        latest.push(notification);
+    }
+    latest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_keeps_latest_change_of_each_kind() {
+        let notifications = [false, true, false, false]
+            .into_iter()
+            .enumerate()
+            .map(|(i, archived)| Notification {
+                id: i as u64 + 1,
+                event: SyncEvent::DataChanged { archived },
+            })
+            .collect();
+        let ids: Vec<u64> = latest_per_scope(notifications)
+            .iter()
+            .map(|n| n.id)
+            .collect();
+        assert_eq!(ids, vec![2, 4]);
+    }
 }
@@ -0,0 +1,403 @@
+//! Export of everything the server holds for an account.
+//!
+//! The export is one JSON document: account details, sessions, API tokens,
+//! webhooks, share links and shared boards first, then the encrypted items, archive
+//! and shared board items. Items are streamed from the database as they are
+//! read, so a large account is never held in memory at once. The server
+//! cannot decrypt any of it; `tb sync export` does that on the client.
+
+use std::time::Duration;
+
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use futures_util::TryStreamExt;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::error::{Result, ServerError};
+use crate::handlers::audit::{self, AuditEvent};
+use crate::handlers::boards::{boards_of, SharedBoard};
+use crate::handlers::items::EncryptedItemData;
+use crate::middleware::ReadAccess;
+use crate::quota;
+use crate::rate_limit::ClientIp;
+use crate::router::AppState;
+
+/// Identifies the document, for tools that read it
+const EXPORT_FORMAT: &str = "taskbook-export";
+
+/// Bumped when the layout of the document changes
+const EXPORT_VERSION: u32 = 1;
+
+/// Bytes gathered before a chunk is sent
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// How long a chunk may wait for the client to take it. A stalled download
+/// is abandoned rather than holding a database connection.
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize)]
+pub struct ExportAccount {
+    pub username: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub is_admin: bool,
+    pub two_factor_enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub last_active_at: Option<DateTime<Utc>>,
+    /// base64 X25519 public key used for shared boards, if published
+    pub public_key: Option<String>,
+    pub used_bytes: i64,
+    pub quota_bytes: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct ExportSession {
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ExportToken {
+    pub name: String,
+    pub scope: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A webhook, without its secret
+#[derive(Serialize)]
+pub struct ExportWebhook {
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+    pub last_delivery_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct ExportLink {
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Everything but the items, which follow it in the stream
+#[derive(Serialize)]
+struct ExportHead {
+    format: &'static str,
+    version: u32,
+    exported_at: DateTime<Utc>,
+    account: ExportAccount,
+    sessions: Vec<ExportSession>,
+    api_tokens: Vec<ExportToken>,
+    webhooks: Vec<ExportWebhook>,
+    share_links: Vec<ExportLink>,
+    shared_boards: Vec<SharedBoard>,
+}
+
+type AccountRow = (
+    String,
+    String,
+    bool,
+    bool,
+    bool,
+    DateTime<Utc>,
+    Option<DateTime<Utc>>,
+    Option<Vec<u8>>,
+);
+
+type SessionRow = (
+    DateTime<Utc>,
+    Option<DateTime<Utc>>,
+    Option<String>,
+    Option<String>,
+);
+
+type TokenRow = (
+    String,
+    String,
+    DateTime<Utc>,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+);
+
+/// Download the account's data as one JSON file.
+#[tracing::instrument(skip(state, headers))]
+pub async fn export(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    auth: ReadAccess,
+) -> Result<Response> {
+    let head = export_head(&state, auth.user_id).await?;
+    audit::record(
+        &state.pool,
+        auth.user_id,
+        AuditEvent::DataExported,
+        ip,
+        &headers,
+    )
+    .await?;
+    let filename = format!(
+        "taskbook-export-{}-{}.json",
+        head.account.username,
+        head.exported_at.format("%Y-%m-%d")
+    );
+    let mut prefix = serde_json::to_string(&head)
+        .map_err(|e| ServerError::Internal(format!("export failed: {e}")))?;
+    // Reopen the object so the items can follow
+    prefix.pop();
+
+    let (tx, rx) = mpsc::channel::<std::io::Result<Bytes>>(4);
+    let pool = state.pool.clone();
+    let user_id = auth.user_id;
+    tokio::spawn(async move {
+        if let Err(e) = stream_items(&pool, user_id, prefix, &tx).await {
+            tracing::error!(error = %e, "export failed");
+            // Cuts the response short, so the client sees a broken download
+            // rather than a document with items missing
+            let _ = tx.send(Err(std::io::Error::other(e.to_string()))).await;
+        }
+    });
+
+    tracing::info!(user_id = %auth.user_id, "account exported");
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+        .into_response())
+}
+
+async fn export_head(state: &AppState, user_id: Uuid) -> Result<ExportHead> {
+    let pool = &state.pool;
+    let (
+        username,
+        email,
+        email_verified,
+        is_admin,
+        two_factor_enabled,
+        created_at,
+        last_active_at,
+        public_key,
+    ) = sqlx::query_as::<_, AccountRow>(
+        "SELECT username, email, email_verified, is_admin, totp_enabled, created_at, \
+                last_active_at, public_key FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let mut conn = pool.acquire().await.map_err(ServerError::Database)?;
+    let used_bytes = quota::used(&mut conn, user_id).await?;
+    let quota_bytes = quota::limit(&mut conn, user_id, state.storage_quota).await?;
+    drop(conn);
+
+    let sessions = sqlx::query_as::<_, SessionRow>(
+        "SELECT created_at, last_used_at, user_agent, ip FROM sessions \
+         WHERE user_id = $1 ORDER BY created_at",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(ServerError::Database)?
+    .into_iter()
+    .map(|(created_at, last_used_at, user_agent, ip)| ExportSession {
+        created_at,
+        last_used_at,
+        user_agent,
+        ip,
+    })
+    .collect();
+
+    let api_tokens = sqlx::query_as::<_, TokenRow>(
+        "SELECT name, scope, created_at, last_used_at, expires_at FROM api_tokens \
+         WHERE user_id = $1 ORDER BY created_at",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(ServerError::Database)?
+    .into_iter()
+    .map(
+        |(name, scope, created_at, last_used_at, expires_at)| ExportToken {
+            name,
+            scope,
+            created_at,
+            last_used_at,
+            expires_at,
+        },
+    )
+    .collect();
+
+    let webhooks = sqlx::query_as::<_, (String, DateTime<Utc>, Option<DateTime<Utc>>)>(
+        "SELECT url, created_at, last_delivery_at FROM webhooks \
+         WHERE user_id = $1 ORDER BY created_at",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(ServerError::Database)?
+    .into_iter()
+    .map(|(url, created_at, last_delivery_at)| ExportWebhook {
+        url,
+        created_at,
+        last_delivery_at,
+    })
+    .collect();
+
+    let share_links = sqlx::query_as::<_, (DateTime<Utc>, DateTime<Utc>)>(
+        "SELECT created_at, expires_at FROM share_links \
+         WHERE user_id = $1 AND expires_at > $2 ORDER BY created_at",
+    )
+    .bind(user_id)
+    .bind(Utc::now())
+    .fetch_all(pool)
+    .await
+    .map_err(ServerError::Database)?
+    .into_iter()
+    .map(|(created_at, expires_at)| ExportLink {
+        created_at,
+        expires_at,
+    })
+    .collect();
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    Ok(ExportHead {
+        format: EXPORT_FORMAT,
+        version: EXPORT_VERSION,
+        exported_at: Utc::now(),
+        account: ExportAccount {
+            username,
+            email,
+            email_verified,
+            is_admin,
+            two_factor_enabled,
+            created_at,
+            last_active_at,
+            public_key: public_key.map(|key| engine.encode(key)),
+            used_bytes,
+            quota_bytes,
+        },
+        sessions,
+        api_tokens,
+        webhooks,
+        share_links,
+        shared_boards: boards_of(pool, user_id).await?,
+    })
+}
+
+/// Collects the document and sends it on in chunks
+struct Writer<'a> {
+    buf: String,
+    tx: &'a mpsc::Sender<std::io::Result<Bytes>>,
+}
+
+impl Writer<'_> {
+    async fn write(&mut self, s: &str) -> Result<()> {
+        self.buf.push_str(s);
+        if self.buf.len() >= CHUNK_SIZE {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        let chunk = Bytes::from(std::mem::take(&mut self.buf));
+        match tokio::time::timeout(SEND_TIMEOUT, self.tx.send(Ok(chunk))).await {
+            Ok(Ok(())) => Ok(()),
+            _ => Err(ServerError::Internal(
+                "export download was abandoned".to_string(),
+            )),
+        }
+    }
+
+    /// `"key":{"data":...,"nonce":...}`
+    async fn item(&mut self, key: &str, data: &[u8], nonce: &[u8]) -> Result<()> {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let entry = EncryptedItemData {
+            data: engine.encode(data),
+            nonce: engine.encode(nonce),
+        };
+        let json = serde_json::to_string(&entry)
+            .map_err(|e| ServerError::Internal(format!("export failed: {e}")))?;
+        let key = serde_json::to_string(key)
+            .map_err(|e| ServerError::Internal(format!("export failed: {e}")))?;
+        self.write(&format!("{key}:{json}")).await
+    }
+}
+
+/// Write `prefix`, then the items, archive and shared board items, closing
+/// the document.
+async fn stream_items(
+    pool: &DbPool,
+    user_id: Uuid,
+    prefix: String,
+    tx: &mpsc::Sender<std::io::Result<Bytes>>,
+) -> Result<()> {
+    let mut out = Writer { buf: prefix, tx };
+
+    for (field, archived) in [("items", false), ("archive", true)] {
+        out.write(&format!(",\"{field}\":{{")).await?;
+        let mut rows = sqlx::query_as::<_, (String, Vec<u8>, Vec<u8>)>(
+            "SELECT item_key, data, nonce FROM items \
+             WHERE user_id = $1 AND archived = $2 AND deleted_at IS NULL ORDER BY item_key",
+        )
+        .bind(user_id)
+        .bind(archived)
+        .fetch(pool);
+        let mut first = true;
+        while let Some((key, data, nonce)) = rows.try_next().await.map_err(ServerError::Database)? {
+            if !first {
+                out.write(",").await?;
+            }
+            first = false;
+            out.item(&key, &data, &nonce).await?;
+        }
+        out.write("}").await?;
+    }
+
+    // Items of every shared board the account can read, by board id
+    out.write(",\"board_items\":{").await?;
+    let mut rows = sqlx::query_as::<_, (Uuid, String, Vec<u8>, Vec<u8>)>(
+        "SELECT bi.board_id, bi.item_key, bi.data, bi.nonce FROM board_items bi \
+         JOIN board_members m ON m.board_id = bi.board_id \
+         WHERE m.user_id = $1 AND bi.deleted_at IS NULL ORDER BY bi.board_id, bi.item_key",
+    )
+    .bind(user_id)
+    .fetch(pool);
+    let mut board = None;
+    while let Some((board_id, key, data, nonce)) =
+        rows.try_next().await.map_err(ServerError::Database)?
+    {
+        if board != Some(board_id) {
+            let separator = if board.is_some() { "}," } else { "" };
+            out.write(&format!("{separator}\"{board_id}\":{{")).await?;
+            board = Some(board_id);
+        } else {
+            out.write(",").await?;
+        }
+        out.item(&key, &data, &nonce).await?;
+    }
+    if board.is_some() {
+        out.write("}").await?;
+    }
+    out.write("}}").await?;
+    out.flush().await
+}
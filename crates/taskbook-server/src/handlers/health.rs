@@ -3,6 +3,7 @@ use axum::http::StatusCode;
 use axum::Json;
 use serde_json::{json, Value};
 
+use crate::db;
 use crate::router::AppState;
 
 #[tracing::instrument(skip(state))]
@@ -11,10 +12,35 @@ pub async fn health(State(state): State<AppState>) -> (StatusCode, Json<Value>)
         Ok(_) => (StatusCode::OK, Json(json!({ "status": "ok" }))),
         Err(e) => {
             tracing::error!(error = %e, "health check: database unavailable");
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(json!({ "status": "error", "message": "database unavailable" })),
-            )
+            unavailable("database unavailable")
         }
     }
 }
+
+/// Liveness probe: answers as long as the process is serving requests.
+pub async fn healthz() -> (StatusCode, Json<Value>) {
+    (StatusCode::OK, Json(json!({ "status": "ok" })))
+}
+
+/// Readiness probe: the database is reachable and its schema is up to date.
+#[tracing::instrument(skip(state))]
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<Value>) {
+    match db::is_migrated(&state.pool).await {
+        Ok(true) => (StatusCode::OK, Json(json!({ "status": "ok" }))),
+        Ok(false) => {
+            tracing::warn!("readiness check: migrations pending");
+            unavailable("migrations pending")
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "readiness check: database unavailable");
+            unavailable("database unavailable")
+        }
+    }
+}
+
+fn unavailable(message: &str) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "status": "error", "message": message })),
+    )
+}
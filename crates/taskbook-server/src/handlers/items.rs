@@ -1,13 +1,20 @@
 use std::collections::HashMap;
 
-use axum::extract::State;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use base64::Engine as _;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
+use crate::db::{self, DbConnection, StringList};
 use crate::error::{Result, ServerError};
-use crate::middleware::AuthUser;
+use crate::middleware::{ReadAccess, WriteAccess};
+use crate::quota::QuotaCheck;
 use crate::router::{AppState, SyncEvent};
+use crate::webhooks::{Changes, Event};
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct EncryptedItemData {
@@ -25,9 +32,60 @@ pub struct PutItemsRequest {
     pub items: HashMap<String, EncryptedItemData>,
 }
 
+/// An item removed by an upload, kept until `purge_at` so it can be restored
+#[derive(Serialize)]
+pub struct TrashedItem {
+    pub key: String,
+    #[serde(flatten)]
+    pub item: EncryptedItemData,
+    /// Whether it was removed from the archive
+    pub archived: bool,
+    pub deleted_at: DateTime<Utc>,
+    pub purge_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct TrashResponse {
+    pub items: Vec<TrashedItem>,
+    pub retention_days: i64,
+}
+
+#[derive(Deserialize)]
+pub struct RestoreRequest {
+    /// Keys of the items to restore; every item in the trash if missing
+    #[serde(default)]
+    pub keys: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct RestoreResponse {
+    pub restored: u64,
+}
+
+/// A version of an item, in GET /api/v1/items/:key/history
+#[derive(Serialize)]
+pub struct ItemVersion {
+    #[serde(flatten)]
+    pub item: EncryptedItemData,
+    pub archived: bool,
+    /// When this version was uploaded
+    pub saved_at: DateTime<Utc>,
+    /// Whether this is the version stored now (possibly in the trash)
+    pub current: bool,
+}
+
+#[derive(Serialize)]
+pub struct HistoryResponse {
+    pub key: String,
+    /// Newest first
+    pub versions: Vec<ItemVersion>,
+}
+
+type TrashRow = (String, Vec<u8>, Vec<u8>, bool, DateTime<Utc>);
+
 /// Convert raw database rows `(item_key, data_bytes, nonce_bytes)` into the
 /// base64-encoded `EncryptedItemData` map returned to callers.
-fn rows_to_encrypted_items(
+pub fn rows_to_encrypted_items(
     rows: Vec<(String, Vec<u8>, Vec<u8>)>,
 ) -> HashMap<String, EncryptedItemData> {
     rows.into_iter()
@@ -43,78 +101,548 @@ fn rows_to_encrypted_items(
         .collect()
 }
 
-#[tracing::instrument(skip(state))]
-pub async fn get_items(
-    State(state): State<AppState>,
-    auth: AuthUser,
-) -> Result<Json<ItemsResponse>> {
+/// Hash of the rows `(item_key, data_bytes, nonce_bytes)`, sorted by key,
+/// as a quoted ETag.
+fn items_etag(rows: &[(String, Vec<u8>, Vec<u8>)]) -> String {
+    let mut hasher = Sha256::new();
+    for (key, data, nonce) in rows {
+        for field in [key.as_bytes(), data, nonce] {
+            hasher.update((field.len() as u64).to_be_bytes());
+            hasher.update(field);
+        }
+    }
+    let hex: String = hasher.finalize()[..16]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("\"{hex}\"")
+}
+
+/// Whether an `If-None-Match` header names `etag`
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+/// The user's items or archive with their ETag, or `304 Not Modified` if the
+/// client already has them.
+async fn items_response(
+    state: &AppState,
+    user_id: uuid::Uuid,
+    archived: bool,
+    headers: &HeaderMap,
+) -> Result<Response> {
     let rows = sqlx::query_as::<_, (String, Vec<u8>, Vec<u8>)>(
-        "SELECT item_key, data, nonce FROM items WHERE user_id = $1 AND archived = false",
+        "SELECT item_key, data, nonce FROM items \
+         WHERE user_id = $1 AND archived = $2 AND deleted_at IS NULL ORDER BY item_key",
     )
-    .bind(auth.user_id)
+    .bind(user_id)
+    .bind(archived)
     .fetch_all(&state.pool)
     .await
     .map_err(ServerError::Database)?;
 
-    Ok(Json(ItemsResponse {
-        items: rows_to_encrypted_items(rows),
-    }))
+    let etag = items_etag(&rows);
+    let cache = [(header::ETAG, etag.clone())];
+    if etag_matches(headers, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, cache).into_response());
+    }
+    Ok((
+        cache,
+        Json(ItemsResponse {
+            items: rows_to_encrypted_items(rows),
+        }),
+    )
+        .into_response())
+}
+
+/// The user's items. Answers `304 Not Modified` when `If-None-Match` has the
+/// ETag they were last sent with.
+#[tracing::instrument(skip(state, headers))]
+pub async fn get_items(
+    State(state): State<AppState>,
+    auth: ReadAccess,
+    headers: HeaderMap,
+) -> Result<Response> {
+    items_response(&state, auth.user_id, false, &headers).await
 }
 
 #[tracing::instrument(skip(state, req), fields(item_count = req.items.len()))]
 pub async fn put_items(
     State(state): State<AppState>,
-    auth: AuthUser,
+    auth: WriteAccess,
     Json(req): Json<PutItemsRequest>,
 ) -> Result<()> {
-    replace_items(&state.pool, auth.user_id, false, &req.items).await?;
+    let changes = replace_items(&state, Store::User(auth.user_id), false, &req.items).await?;
     state
         .notifications
         .notify(auth.user_id, SyncEvent::DataChanged { archived: false });
+    state.webhooks.notify(
+        &state.pool,
+        vec![auth.user_id],
+        auth.user_id,
+        Event::for_items(false),
+        changes,
+    );
     Ok(())
 }
 
-#[tracing::instrument(skip(state))]
+/// The user's archive, cached like [`get_items`].
+#[tracing::instrument(skip(state, headers))]
 pub async fn get_archive(
     State(state): State<AppState>,
-    auth: AuthUser,
-) -> Result<Json<ItemsResponse>> {
-    let rows = sqlx::query_as::<_, (String, Vec<u8>, Vec<u8>)>(
-        "SELECT item_key, data, nonce FROM items WHERE user_id = $1 AND archived = true",
-    )
-    .bind(auth.user_id)
-    .fetch_all(&state.pool)
-    .await
-    .map_err(ServerError::Database)?;
-
-    Ok(Json(ItemsResponse {
-        items: rows_to_encrypted_items(rows),
-    }))
+    auth: ReadAccess,
+    headers: HeaderMap,
+) -> Result<Response> {
+    items_response(&state, auth.user_id, true, &headers).await
 }
 
 #[tracing::instrument(skip(state, req), fields(item_count = req.items.len()))]
 pub async fn put_archive(
     State(state): State<AppState>,
-    auth: AuthUser,
+    auth: WriteAccess,
     Json(req): Json<PutItemsRequest>,
 ) -> Result<()> {
-    replace_items(&state.pool, auth.user_id, true, &req.items).await?;
+    let changes = replace_items(&state, Store::User(auth.user_id), true, &req.items).await?;
     state
         .notifications
         .notify(auth.user_id, SyncEvent::DataChanged { archived: true });
+    state.webhooks.notify(
+        &state.pool,
+        vec![auth.user_id],
+        auth.user_id,
+        Event::for_items(true),
+        changes,
+    );
     Ok(())
 }
 
-/// Maximum number of items a user can store per category (active or archived).
-const MAX_ITEMS_PER_CATEGORY: usize = 10_000;
+/// Items removed by uploads in the last `TB_TRASH_RETENTION_DAYS` days,
+/// newest first.
+#[tracing::instrument(skip(state))]
+pub async fn get_trash(
+    State(state): State<AppState>,
+    auth: ReadAccess,
+) -> Result<Json<TrashResponse>> {
+    Ok(Json(trash(&state, Store::User(auth.user_id)).await?))
+}
 
-/// Replace all items for a user (active or archived) with the provided set.
-async fn replace_items(
-    pool: &sqlx::PgPool,
-    user_id: uuid::Uuid,
+/// Put items from the trash back where they were removed from.
+#[tracing::instrument(skip(state, req))]
+pub async fn restore_trash(
+    State(state): State<AppState>,
+    auth: WriteAccess,
+    Json(req): Json<RestoreRequest>,
+) -> Result<Json<RestoreResponse>> {
+    let user_id = auth.user_id;
+    let restored = restore_items(&state, Store::User(user_id), req.keys.as_deref()).await?;
+
+    for archived in [false, true] {
+        let count = restored.iter().filter(|&&r| r == archived).count() as u64;
+        if count > 0 {
+            state
+                .notifications
+                .notify(user_id, SyncEvent::DataChanged { archived });
+            state.webhooks.notify(
+                &state.pool,
+                vec![user_id],
+                user_id,
+                Event::for_items(archived),
+                Changes {
+                    changed: count,
+                    removed: 0,
+                },
+            );
+        }
+    }
+    tracing::info!(user_id = %user_id, restored = restored.len(), "items restored from trash");
+
+    Ok(Json(RestoreResponse {
+        restored: restored.len() as u64,
+    }))
+}
+
+/// The versions of an item the server has, newest first: the stored item
+/// itself (or, briefly after a move, both copies), then earlier revisions.
+#[tracing::instrument(skip(state))]
+pub async fn get_history(
+    State(state): State<AppState>,
+    auth: ReadAccess,
+    Path(key): Path<String>,
+) -> Result<Json<HistoryResponse>> {
+    Ok(Json(history(&state, Store::User(auth.user_id), key).await?))
+}
+
+/// Where items are stored: with a user, as their items and archive, or on
+/// a shared board. Each keeps its own trash and history.
+#[derive(Debug, Clone, Copy)]
+pub enum Store {
+    User(uuid::Uuid),
+    Board(uuid::Uuid),
+}
+
+impl Store {
+    fn id(self) -> uuid::Uuid {
+        match self {
+            Store::User(id) | Store::Board(id) => id,
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            Store::User(_) => "items",
+            Store::Board(_) => "board_items",
+        }
+    }
+
+    fn revisions_table(self) -> &'static str {
+        match self {
+            Store::User(_) => "item_revisions",
+            Store::Board(_) => "board_item_revisions",
+        }
+    }
+
+    /// Column holding the store's id, which the queries below bind as `$1`
+    fn column(self) -> &'static str {
+        match self {
+            Store::User(_) => "user_id",
+            Store::Board(_) => "board_id",
+        }
+    }
+
+    /// Whether the store has an archive besides its active items
+    fn categories(self) -> &'static [bool] {
+        match self {
+            Store::User(_) => &[false, true],
+            Store::Board(_) => &[false],
+        }
+    }
+
+    /// Condition that a row (of the table aliased `alias`) is among the
+    /// store's active items or its archive
+    fn in_category(self, alias: &str, archived: bool) -> String {
+        match self {
+            Store::User(_) => {
+                format!(
+                    "{alias}user_id = $1 AND {alias}archived = {}",
+                    sql_bool(archived)
+                )
+            }
+            Store::Board(_) => format!("{alias}board_id = $1"),
+        }
+    }
+
+    /// The columns that say which category a row is in, and their values
+    fn category_columns(self, archived: bool) -> (&'static str, String) {
+        match self {
+            Store::User(_) => ("user_id, archived", format!("$1, {}", sql_bool(archived))),
+            Store::Board(_) => ("board_id", "$1".to_string()),
+        }
+    }
+
+    /// Expression for whether a row is archived
+    fn archived(self) -> &'static str {
+        match self {
+            Store::User(_) => "archived",
+            Store::Board(_) => "FALSE",
+        }
+    }
+
+    /// The account whose storage quota the items count towards
+    async fn quota_owner(self, conn: &mut DbConnection) -> Result<uuid::Uuid> {
+        match self {
+            Store::User(user_id) => Ok(user_id),
+            Store::Board(board_id) => sqlx::query_scalar::<_, uuid::Uuid>(
+                "SELECT owner_id FROM shared_boards WHERE id = $1",
+            )
+            .bind(board_id)
+            .fetch_one(conn)
+            .await
+            .map_err(ServerError::Database),
+        }
+    }
+}
+
+fn sql_bool(value: bool) -> &'static str {
+    if value {
+        "TRUE"
+    } else {
+        "FALSE"
+    }
+}
+
+/// What is in `store`'s trash, newest first
+pub async fn trash(state: &AppState, store: Store) -> Result<TrashResponse> {
+    let retention = Duration::days(state.trash_retention_days);
+    let rows = sqlx::query_as::<_, TrashRow>(&format!(
+        "SELECT item_key, data, nonce, {}, deleted_at FROM {} \
+         WHERE {} = $1 AND deleted_at > $2 ORDER BY deleted_at DESC, item_key",
+        store.archived(),
+        store.table(),
+        store.column()
+    ))
+    .bind(store.id())
+    .bind(Utc::now() - retention)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let items = rows
+        .into_iter()
+        .map(|(key, data, nonce, archived, deleted_at)| TrashedItem {
+            key,
+            item: EncryptedItemData {
+                data: engine.encode(&data),
+                nonce: engine.encode(&nonce),
+            },
+            archived,
+            deleted_at,
+            purge_at: deleted_at + retention,
+        })
+        .collect();
+
+    Ok(TrashResponse {
+        items,
+        retention_days: state.trash_retention_days,
+    })
+}
+
+/// Take the items with `keys` (all if `None`) out of `store`'s trash.
+/// Rejected like an upload if that would go over the item limit or the
+/// storage quota. Returns whether each restored item is archived.
+pub async fn restore_items(
+    state: &AppState,
+    store: Store,
+    keys: Option<&[String]>,
+) -> Result<Vec<bool>> {
+    let mut tx = state.pool.begin().await.map_err(ServerError::Database)?;
+    purge_trash(&mut tx, store, state.trash_retention_days).await?;
+
+    let owner = store.quota_owner(&mut tx).await?;
+    let quota = QuotaCheck::start(&mut tx, owner, state.storage_quota).await?;
+    let key_list: Vec<&str> = keys.iter().copied().flatten().map(String::as_str).collect();
+    let restored = sqlx::query_scalar::<_, bool>(&format!(
+        "UPDATE {} SET deleted_at = NULL, updated_at = $2 \
+         WHERE {} = $1 AND deleted_at IS NOT NULL AND ($3 OR {}) \
+         RETURNING {}",
+        store.table(),
+        store.column(),
+        db::in_list("item_key", "$4"),
+        store.archived()
+    ))
+    .bind(store.id())
+    .bind(Utc::now())
+    .bind(keys.is_none())
+    .bind(db::string_list(&key_list))
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(ServerError::Database)?;
+
+    for &archived in store.categories() {
+        let count = sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) FROM {} WHERE {} AND deleted_at IS NULL",
+            store.table(),
+            store.in_category("", archived)
+        ))
+        .bind(store.id())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(ServerError::Database)?;
+        if count as usize > MAX_ITEMS_PER_CATEGORY {
+            return Err(ServerError::Validation(format!(
+                "too many items: maximum is {MAX_ITEMS_PER_CATEGORY}, restoring would make {count}"
+            )));
+        }
+    }
+    quota.finish(&mut tx).await?;
+
+    tx.commit().await.map_err(ServerError::Database)?;
+    Ok(restored)
+}
+
+/// Drop items that have been in `store`'s trash longer than
+/// `retention_days`, and the history of items that are gone.
+async fn purge_trash(conn: &mut DbConnection, store: Store, retention_days: i64) -> Result<()> {
+    sqlx::query(&format!(
+        "DELETE FROM {} WHERE {} = $1 AND deleted_at <= $2",
+        store.table(),
+        store.column()
+    ))
+    .bind(store.id())
+    .bind(Utc::now() - Duration::days(retention_days))
+    .execute(&mut *conn)
+    .await
+    .map_err(ServerError::Database)?;
+    sqlx::query(&format!(
+        "DELETE FROM {revisions} AS r WHERE r.{column} = $1 \
+           AND NOT EXISTS (SELECT 1 FROM {table} i \
+                           WHERE i.{column} = $1 AND i.item_key = r.item_key)",
+        revisions = store.revisions_table(),
+        table = store.table(),
+        column = store.column()
+    ))
+    .bind(store.id())
+    .execute(conn)
+    .await
+    .map_err(ServerError::Database)?;
+    Ok(())
+}
+
+/// Keep the stored version of every item in `uploaded` whose data differs
+/// from the upload.
+async fn save_revisions(
+    conn: &mut DbConnection,
+    store: Store,
     archived: bool,
-    items: &HashMap<String, EncryptedItemData>,
+    uploaded: &[(&String, Vec<u8>, Vec<u8>)],
+) -> Result<()> {
+    // SQLite has no arrays: there the upload goes as JSON `[key, hex data]`
+    // pairs
+    let (upload, differs) = if cfg!(feature = "sqlite") {
+        (
+            "(SELECT json_extract(value, '$[0]') AS item_key, \
+                     json_extract(value, '$[1]') AS data FROM json_each($2)) AS u",
+            "hex(i.data) <> u.data",
+        )
+    } else {
+        (
+            "UNNEST($2::TEXT[], $3::BYTEA[]) AS u(item_key, data)",
+            "i.data <> u.data",
+        )
+    };
+    let (columns, values) = store.category_columns(archived);
+    let sql = format!(
+        "INSERT INTO {} ({columns}, item_key, data, nonce, saved_at) \
+         SELECT {values}, i.item_key, i.data, i.nonce, i.updated_at \
+         FROM {} i JOIN {upload} ON u.item_key = i.item_key \
+         WHERE {} AND {differs}",
+        store.revisions_table(),
+        store.table(),
+        store.in_category("i.", archived)
+    );
+
+    #[cfg(not(feature = "sqlite"))]
+    let query = {
+        let keys: Vec<&str> = uploaded.iter().map(|(key, _, _)| key.as_str()).collect();
+        let data: Vec<&[u8]> = uploaded
+            .iter()
+            .map(|(_, data, _)| data.as_slice())
+            .collect();
+        sqlx::query(&sql).bind(store.id()).bind(keys).bind(data)
+    };
+
+    #[cfg(feature = "sqlite")]
+    let query = {
+        let pairs: Vec<(&str, String)> = uploaded
+            .iter()
+            .map(|(key, data, _)| {
+                let hex = data.iter().map(|byte| format!("{byte:02X}")).collect();
+                (key.as_str(), hex)
+            })
+            .collect();
+        sqlx::query(&sql)
+            .bind(store.id())
+            .bind(serde_json::to_string(&pairs).expect("strings serialize"))
+    };
+
+    query.execute(conn).await.map_err(ServerError::Database)?;
+    Ok(())
+}
+
+/// Drop all but the newest `keep` revisions of the items with `keys`.
+async fn trim_revisions(
+    conn: &mut DbConnection,
+    store: Store,
+    keys: &StringList,
+    keep: i64,
 ) -> Result<()> {
+    sqlx::query(&format!(
+        "DELETE FROM {revisions} WHERE id IN ( \
+           SELECT id FROM ( \
+             SELECT id, row_number() OVER (PARTITION BY item_key ORDER BY id DESC) AS n \
+             FROM {revisions} WHERE {column} = $1 AND {keys}) r \
+           WHERE r.n > $3)",
+        revisions = store.revisions_table(),
+        column = store.column(),
+        keys = db::in_list("item_key", "$2")
+    ))
+    .bind(store.id())
+    .bind(keys)
+    .bind(keep)
+    .execute(conn)
+    .await
+    .map_err(ServerError::Database)?;
+    Ok(())
+}
+
+/// The versions of the item `key` in `store`, see [`get_history`]
+pub async fn history(state: &AppState, store: Store, key: String) -> Result<HistoryResponse> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let version = |data: Vec<u8>, nonce: Vec<u8>, archived, saved_at, current| ItemVersion {
+        item: EncryptedItemData {
+            data: engine.encode(&data),
+            nonce: engine.encode(&nonce),
+        },
+        archived,
+        saved_at,
+        current,
+    };
+
+    let stored = sqlx::query_as::<_, (Vec<u8>, Vec<u8>, bool, DateTime<Utc>)>(&format!(
+        "SELECT data, nonce, {}, updated_at FROM {} \
+         WHERE {} = $1 AND item_key = $2 ORDER BY updated_at DESC",
+        store.archived(),
+        store.table(),
+        store.column()
+    ))
+    .bind(store.id())
+    .bind(&key)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+    if stored.is_empty() {
+        return Err(ServerError::NotFound("item not found".to_string()));
+    }
+
+    let revisions = sqlx::query_as::<_, (Vec<u8>, Vec<u8>, bool, DateTime<Utc>)>(&format!(
+        "SELECT data, nonce, {}, saved_at FROM {} \
+         WHERE {} = $1 AND item_key = $2 ORDER BY id DESC",
+        store.archived(),
+        store.revisions_table(),
+        store.column()
+    ))
+    .bind(store.id())
+    .bind(&key)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let versions = stored
+        .into_iter()
+        .map(|(data, nonce, archived, saved_at)| version(data, nonce, archived, saved_at, true))
+        .chain(
+            revisions
+                .into_iter()
+                .map(|(data, nonce, archived, saved_at)| {
+                    version(data, nonce, archived, saved_at, false)
+                }),
+        )
+        .collect();
+
+    Ok(HistoryResponse { key, versions })
+}
+
+/// Maximum number of items a user can store per category (active or archived).
+const MAX_ITEMS_PER_CATEGORY: usize = 10_000;
+
+/// Check the number and size of uploaded items.
+pub fn validate_items(items: &HashMap<String, EncryptedItemData>) -> Result<()> {
     if items.len() > MAX_ITEMS_PER_CATEGORY {
         return Err(ServerError::Validation(format!(
             "too many items: maximum is {MAX_ITEMS_PER_CATEGORY}, got {}",
@@ -122,7 +650,8 @@ async fn replace_items(
         )));
     }
 
-    // Validate individual item sizes
+    // Validate individual item sizes. Keys are client-generated item UUIDs
+    // (older clients sent numeric display IDs); the server treats them as opaque.
     for (key, item) in items {
         if key.len() > 64 {
             return Err(ServerError::Validation(
@@ -139,39 +668,160 @@ async fn replace_items(
         }
     }
 
-    let mut tx = pool.begin().await.map_err(ServerError::Database)?;
+    Ok(())
+}
 
-    sqlx::query("DELETE FROM items WHERE user_id = $1 AND archived = $2")
-        .bind(user_id)
-        .bind(archived)
-        .execute(&mut *tx)
-        .await
-        .map_err(ServerError::Database)?;
+/// Decode an uploaded item into its raw `(data, nonce)` bytes.
+pub fn decode_item(item: &EncryptedItemData) -> Result<(Vec<u8>, Vec<u8>)> {
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(&item.data)
+        .map_err(|e| ServerError::Validation(format!("invalid base64 data: {e}")))?;
+    let nonce = base64::engine::general_purpose::STANDARD
+        .decode(&item.nonce)
+        .map_err(|e| ServerError::Validation(format!("invalid base64 nonce: {e}")))?;
+    Ok((data, nonce))
+}
+
+/// Replace the active items or the archive of `store` with the provided
+/// set. Fails if that takes the owner (of the board, for a shared board)
+/// over their storage quota; uploads that do not grow their storage are
+/// accepted even when already over it.
+///
+/// Items left out are moved to the trash rather than deleted, unless they
+/// were moved to the other category, onto a shared board or off one.
+/// Returns how many items were added or changed and how many went to the
+/// trash.
+pub async fn replace_items(
+    state: &AppState,
+    store: Store,
+    archived: bool,
+    items: &HashMap<String, EncryptedItemData>,
+) -> Result<Changes> {
+    validate_items(items)?;
+
+    let mut tx = state.pool.begin().await.map_err(ServerError::Database)?;
+    let owner = store.quota_owner(&mut tx).await?;
+    let quota = QuotaCheck::start(&mut tx, owner, state.storage_quota).await?;
+
+    let keys: Vec<&str> = items.keys().map(String::as_str).collect();
+    let key_list = db::string_list(&keys);
+
+    // Moved, not deleted: nothing to restore
+    let moved = match store {
+        Store::User(_) => {
+            "EXISTS (SELECT 1 FROM items l WHERE l.user_id = $1 AND l.archived <> d.archived \
+                     AND l.item_key = d.item_key AND l.deleted_at IS NULL) \
+             OR EXISTS (SELECT 1 FROM board_items bi \
+                        JOIN board_members m ON m.board_id = bi.board_id \
+                        WHERE m.user_id = $1 AND bi.item_key = d.item_key \
+                          AND bi.deleted_at IS NULL)"
+        }
+        Store::Board(_) => {
+            "EXISTS (SELECT 1 FROM items l JOIN board_members m ON m.user_id = l.user_id \
+                     WHERE m.board_id = $1 AND l.item_key = d.item_key AND l.deleted_at IS NULL)"
+        }
+    };
+    sqlx::query(&format!(
+        "DELETE FROM {} AS d \
+         WHERE {} AND d.deleted_at IS NULL AND NOT ({}) AND ({moved})",
+        store.table(),
+        store.in_category("d.", archived),
+        db::in_list("d.item_key", "$2")
+    ))
+    .bind(store.id())
+    .bind(&key_list)
+    .execute(&mut *tx)
+    .await
+    .map_err(ServerError::Database)?;
 
+    let removed = sqlx::query(&format!(
+        "UPDATE {} SET deleted_at = $3 \
+         WHERE {} AND deleted_at IS NULL AND NOT ({})",
+        store.table(),
+        store.in_category("", archived),
+        db::in_list("item_key", "$2")
+    ))
+    .bind(store.id())
+    .bind(&key_list)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await
+    .map_err(ServerError::Database)?
+    .rows_affected();
+
+    // Uploaded items that are in the trash elsewhere were moved here since;
+    // that copy is out of date
+    let stale = match store {
+        Store::User(_) => vec![
+            format!(
+                "DELETE FROM items WHERE user_id = $1 AND archived <> {} \
+                   AND deleted_at IS NOT NULL AND {}",
+                sql_bool(archived),
+                db::in_list("item_key", "$2")
+            ),
+            format!(
+                "DELETE FROM board_items WHERE deleted_at IS NOT NULL AND {} \
+                   AND board_id IN (SELECT board_id FROM board_members WHERE user_id = $1)",
+                db::in_list("item_key", "$2")
+            ),
+        ],
+        Store::Board(_) => vec![format!(
+            "DELETE FROM items WHERE deleted_at IS NOT NULL AND {} \
+               AND user_id IN (SELECT user_id FROM board_members WHERE board_id = $1)",
+            db::in_list("item_key", "$2")
+        )],
+    };
+    for query in stale {
+        sqlx::query(&query)
+            .bind(store.id())
+            .bind(&key_list)
+            .execute(&mut *tx)
+            .await
+            .map_err(ServerError::Database)?;
+    }
+
+    purge_trash(&mut tx, store, state.trash_retention_days).await?;
+
+    let mut decoded = Vec::with_capacity(items.len());
     for (key, item) in items {
-        let data = base64::engine::general_purpose::STANDARD
-            .decode(&item.data)
-            .map_err(|e| ServerError::Validation(format!("invalid base64 data: {e}")))?;
-        let nonce = base64::engine::general_purpose::STANDARD
-            .decode(&item.nonce)
-            .map_err(|e| ServerError::Validation(format!("invalid base64 nonce: {e}")))?;
-
-        sqlx::query(
-            "INSERT INTO items (user_id, item_key, data, nonce, archived) VALUES ($1, $2, $3, $4, $5)",
-        )
-        .bind(user_id)
+        let (data, nonce) = decode_item(item)?;
+        decoded.push((key, data, nonce));
+    }
+
+    if state.item_history > 0 {
+        save_revisions(&mut tx, store, archived, &decoded).await?;
+    }
+    trim_revisions(&mut tx, store, &key_list, state.item_history).await?;
+
+    let table = store.table();
+    let (columns, values) = store.category_columns(archived);
+    let mut changed = 0;
+    for (key, data, nonce) in &decoded {
+        // Unchanged items are left alone, so `updated_at` is when they last
+        // changed
+        changed += sqlx::query(&format!(
+            "INSERT INTO {table} ({columns}, item_key, data, nonce) \
+             VALUES ({values}, $2, $3, $4) \
+             ON CONFLICT ({columns}, item_key) DO UPDATE \
+             SET data = EXCLUDED.data, nonce = EXCLUDED.nonce, \
+                 deleted_at = NULL, updated_at = $5 \
+             WHERE {table}.data <> EXCLUDED.data OR {table}.deleted_at IS NOT NULL"
+        ))
+        .bind(store.id())
         .bind(key)
-        .bind(&data)
-        .bind(&nonce)
-        .bind(archived)
+        .bind(data)
+        .bind(nonce)
+        .bind(Utc::now())
         .execute(&mut *tx)
         .await
-        .map_err(ServerError::Database)?;
+        .map_err(ServerError::Database)?
+        .rows_affected();
     }
 
+    quota.finish(&mut tx).await?;
     tx.commit().await.map_err(ServerError::Database)?;
 
-    Ok(())
+    Ok(Changes { changed, removed })
 }
 
 #[cfg(test)]
@@ -203,6 +853,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn items_etag_changes_with_content() {
+        let rows = vec![make_row("a", b"data", b"nonce")];
+        let etag = items_etag(&rows);
+        assert_eq!(etag, items_etag(&rows.clone()));
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+        assert_ne!(etag, items_etag(&[make_row("a", b"data", b"nonce2")]));
+        // Field boundaries count, not just the bytes
+        assert_ne!(etag, items_etag(&[make_row("a", b"datan", b"once")]));
+        assert_ne!(etag, items_etag(&[]));
+    }
+
+    #[test]
+    fn etag_matches_if_none_match_lists() {
+        let mut headers = HeaderMap::new();
+        assert!(!etag_matches(&headers, "\"abc\""));
+        headers.insert(header::IF_NONE_MATCH, "\"xyz\", W/\"abc\"".parse().unwrap());
+        assert!(etag_matches(&headers, "\"abc\""));
+        assert!(!etag_matches(&headers, "\"def\""));
+        headers.insert(header::IF_NONE_MATCH, "*".parse().unwrap());
+        assert!(etag_matches(&headers, "\"def\""));
+    }
+
     #[test]
     fn rows_to_encrypted_items_empty_input() {
         let map = rows_to_encrypted_items(vec![]);
@@ -220,4 +893,137 @@ mod tests {
         assert!(map.contains_key("key-a"));
         assert!(map.contains_key("key-b"));
     }
+
+    mod trash {
+        use super::*;
+        use uuid::Uuid;
+
+        use crate::testing;
+
+        async fn put(state: &AppState, user_id: Uuid, archived: bool, entries: &[(&str, &str)]) {
+            let req = Json(PutItemsRequest {
+                items: testing::items(entries),
+            });
+            let access = WriteAccess { user_id };
+            if archived {
+                put_archive(State(state.clone()), access, req)
+                    .await
+                    .unwrap();
+            } else {
+                put_items(State(state.clone()), access, req).await.unwrap();
+            }
+        }
+
+        async fn restore(state: &AppState, user_id: Uuid, keys: Option<&[&str]>) -> Result<u64> {
+            let keys = keys.map(|keys| keys.iter().map(|key| key.to_string()).collect());
+            restore_trash(
+                State(state.clone()),
+                WriteAccess { user_id },
+                Json(RestoreRequest { keys }),
+            )
+            .await
+            .map(|Json(response)| response.restored)
+        }
+
+        async fn trashed(state: &AppState, user_id: Uuid) -> Vec<(String, bool)> {
+            let read = ReadAccess {
+                user_id,
+                can_write: true,
+            };
+            let Json(trash) = get_trash(State(state.clone()), read).await.unwrap();
+            trash
+                .items
+                .into_iter()
+                .map(|item| (item.key, item.archived))
+                .collect()
+        }
+
+        /// Keys of the items stored, in the trash or not
+        async fn stored(state: &AppState, user_id: Uuid) -> Vec<String> {
+            sqlx::query_scalar("SELECT item_key FROM items WHERE user_id = $1 ORDER BY item_key")
+                .bind(user_id)
+                .fetch_all(&state.pool)
+                .await
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn removed_items_can_be_restored_where_they_were() {
+            let Some(state) = testing::state().await else {
+                return;
+            };
+            let user_id = testing::user(&state, "alice").await;
+            put(&state, user_id, false, &[("a", "first"), ("b", "second")]).await;
+            put(&state, user_id, true, &[("c", "archived")]).await;
+            put(&state, user_id, false, &[("a", "first")]).await;
+            put(&state, user_id, true, &[]).await;
+
+            let mut trash = trashed(&state, user_id).await;
+            trash.sort();
+            assert_eq!(trash, vec![("b".into(), false), ("c".into(), true)]);
+
+            assert_eq!(restore(&state, user_id, Some(&["c"])).await.unwrap(), 1);
+            assert_eq!(trashed(&state, user_id).await, vec![("b".into(), false)]);
+            assert_eq!(restore(&state, user_id, None).await.unwrap(), 1);
+            assert!(trashed(&state, user_id).await.is_empty());
+
+            let archived: Vec<String> = sqlx::query_scalar(
+                "SELECT item_key FROM items \
+                 WHERE archived = TRUE AND deleted_at IS NULL ORDER BY item_key",
+            )
+            .fetch_all(&state.pool)
+            .await
+            .unwrap();
+            assert_eq!(archived, vec!["c"]);
+            assert_eq!(restore(&state, user_id, None).await.unwrap(), 0);
+        }
+
+        #[tokio::test]
+        async fn the_trash_is_purged_after_the_retention_period() {
+            let Some(state) = testing::state().await else {
+                return;
+            };
+            let user_id = testing::user(&state, "alice").await;
+            put(&state, user_id, false, &[("a", "first"), ("b", "second")]).await;
+            put(&state, user_id, false, &[("a", "first")]).await;
+            assert_eq!(stored(&state, user_id).await, vec!["a", "b"]);
+
+            let expired = Utc::now() - Duration::days(state.trash_retention_days + 1);
+            sqlx::query("UPDATE items SET deleted_at = $1 WHERE deleted_at IS NOT NULL")
+                .bind(expired)
+                .execute(&state.pool)
+                .await
+                .unwrap();
+            assert!(trashed(&state, user_id).await.is_empty());
+            assert_eq!(restore(&state, user_id, None).await.unwrap(), 0);
+            assert_eq!(stored(&state, user_id).await, vec!["a"]);
+        }
+
+        #[tokio::test]
+        async fn restores_that_go_over_the_quota_are_rejected() {
+            let Some(state) = testing::state().await else {
+                return;
+            };
+            let user_id = testing::user(&state, "alice").await;
+            sqlx::query("UPDATE users SET quota_bytes = 16 WHERE id = $1")
+                .bind(user_id)
+                .execute(&state.pool)
+                .await
+                .unwrap();
+            put(&state, user_id, false, &[("a", "twelve bytes")]).await;
+            put(&state, user_id, false, &[]).await;
+            // The trash does not count towards the quota
+            put(&state, user_id, false, &[("b", "ten bytes!")]).await;
+
+            let err = restore(&state, user_id, None).await.unwrap_err();
+            assert!(matches!(
+                err,
+                ServerError::QuotaExceeded {
+                    used: 22,
+                    quota: 16
+                }
+            ));
+            assert_eq!(trashed(&state, user_id).await, vec![("a".into(), false)]);
+        }
+    }
 }
@@ -0,0 +1,204 @@
+//! Public read-only links to a board.
+//!
+//! A link holds a snapshot of a board, encrypted by the client with a key
+//! that is only part of the link's URL fragment, which browsers never send
+//! to the server. The page served at `/s/:token` fetches the snapshot and
+//! decrypts it in the browser.
+
+use axum::extract::{Path, State};
+use axum::http::header;
+use axum::response::{Html, IntoResponse};
+use axum::Json;
+use base64::Engine as _;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, ServerError};
+use crate::middleware::AuthUser;
+use crate::router::AppState;
+
+/// AES-GCM nonces are 12 bytes
+const NONCE_LEN: usize = 12;
+
+/// Longest a link may stay valid
+const MAX_EXPIRY_DAYS: i64 = 365;
+
+/// Page that decrypts and renders a snapshot
+const VIEW_PAGE: &str = include_str!("share_link.html");
+
+#[derive(Deserialize)]
+pub struct CreateLinkRequest {
+    pub data: String,  // base64-encoded encrypted snapshot
+    pub nonce: String, // base64-encoded 12-byte nonce
+    /// Seconds until the link expires
+    pub expires_in: i64,
+}
+
+#[derive(Serialize)]
+pub struct CreateLinkResponse {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct LinkInfo {
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct LinksResponse {
+    pub links: Vec<LinkInfo>,
+}
+
+#[derive(Serialize)]
+pub struct LinkDataResponse {
+    pub data: String,
+    pub nonce: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[tracing::instrument(skip(state, req))]
+pub async fn create_link(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<CreateLinkRequest>,
+) -> Result<Json<CreateLinkResponse>> {
+    if req.expires_in <= 0 || req.expires_in > MAX_EXPIRY_DAYS * 24 * 60 * 60 {
+        return Err(ServerError::Validation(format!(
+            "links must expire within {MAX_EXPIRY_DAYS} days"
+        )));
+    }
+    let engine = base64::engine::general_purpose::STANDARD;
+    let data = engine
+        .decode(&req.data)
+        .map_err(|e| ServerError::Validation(format!("invalid base64 data: {e}")))?;
+    let nonce = engine
+        .decode(&req.nonce)
+        .map_err(|e| ServerError::Validation(format!("invalid base64 nonce: {e}")))?;
+    if nonce.len() != NONCE_LEN {
+        return Err(ServerError::Validation(format!(
+            "nonce must be {NONCE_LEN} bytes"
+        )));
+    }
+
+    // Expired links are of no use to anyone; drop them while we are here
+    sqlx::query("DELETE FROM share_links WHERE user_id = $1 AND expires_at <= $2")
+        .bind(auth.user_id)
+        .bind(Utc::now())
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    let mut token_bytes = [0u8; 32];
+    rand::thread_rng().fill(&mut token_bytes);
+    let token = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(token_bytes);
+    let expires_at = Utc::now() + Duration::seconds(req.expires_in);
+
+    sqlx::query(
+        "INSERT INTO share_links (token, user_id, data, nonce, expires_at) \
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(&token)
+    .bind(auth.user_id)
+    .bind(&data)
+    .bind(&nonce)
+    .bind(expires_at)
+    .execute(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    tracing::info!(%expires_at, "share link created");
+
+    Ok(Json(CreateLinkResponse { token, expires_at }))
+}
+
+#[tracing::instrument(skip(state))]
+pub async fn list_links(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<LinksResponse>> {
+    let rows = sqlx::query_as::<_, (String, DateTime<Utc>, DateTime<Utc>)>(
+        "SELECT token, created_at, expires_at FROM share_links \
+         WHERE user_id = $1 AND expires_at > $2 ORDER BY created_at",
+    )
+    .bind(auth.user_id)
+    .bind(Utc::now())
+    .fetch_all(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let links = rows
+        .into_iter()
+        .map(|(token, created_at, expires_at)| LinkInfo {
+            token,
+            created_at,
+            expires_at,
+        })
+        .collect();
+
+    Ok(Json(LinksResponse { links }))
+}
+
+#[tracing::instrument(skip(state, token))]
+pub async fn delete_link(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(token): Path<String>,
+) -> Result<()> {
+    let result = sqlx::query("DELETE FROM share_links WHERE token = $1 AND user_id = $2")
+        .bind(&token)
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServerError::NotFound("link not found".to_string()));
+    }
+    Ok(())
+}
+
+/// The encrypted snapshot behind a link. Needs no authentication.
+#[tracing::instrument(skip(state, token))]
+pub async fn get_link(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<LinkDataResponse>> {
+    let (data, nonce, expires_at) = sqlx::query_as::<_, (Vec<u8>, Vec<u8>, DateTime<Utc>)>(
+        "SELECT data, nonce, expires_at FROM share_links \
+         WHERE token = $1 AND expires_at > $2",
+    )
+    .bind(&token)
+    .bind(Utc::now())
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(ServerError::Database)?
+    .ok_or_else(|| ServerError::NotFound("link not found or expired".to_string()))?;
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    Ok(Json(LinkDataResponse {
+        data: engine.encode(data),
+        nonce: engine.encode(nonce),
+        expires_at,
+    }))
+}
+
+/// The page that renders a link. It is the same for every link; the token
+/// and key are read from the URL by the page itself.
+pub async fn view_link() -> impl IntoResponse {
+    (
+        [
+            (header::CACHE_CONTROL, "no-store"),
+            (header::REFERRER_POLICY, "no-referrer"),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; script-src 'unsafe-inline'; \
+                 style-src 'unsafe-inline'; connect-src 'self'",
+            ),
+        ],
+        Html(VIEW_PAGE),
+    )
+}
@@ -1,4 +1,15 @@
+pub mod admin;
+pub mod audit;
+pub mod boards;
+pub mod email;
 pub mod events;
+pub mod export;
 pub mod health;
 pub mod items;
+pub mod links;
+pub mod oidc;
+pub mod sessions;
+pub mod socket;
+pub mod tokens;
 pub mod user;
+pub mod webhooks;
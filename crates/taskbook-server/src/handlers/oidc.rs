@@ -0,0 +1,487 @@
+//! Single sign-on. The CLI starts a sign-in and opens the provider's page
+//! in a browser; the provider sends the browser back to the callback, and
+//! the CLI polls until the sign-in is done to get its session.
+
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{Html, IntoResponse};
+use axum::Json;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::{hash_password, hash_token, random_token};
+use crate::db::DbPool;
+use crate::error::{Result, ServerError};
+use crate::handlers::audit::{self, AuditEvent};
+use crate::handlers::user::create_session;
+use crate::middleware::{user_agent, AuthUser};
+use crate::oidc::{Claims, Provider};
+use crate::rate_limit::ClientIp;
+use crate::router::AppState;
+
+const DONE_PAGE: &str = include_str!("oidc_done.html");
+
+/// Minutes a sign-in may take, from start to the CLI's last poll
+const SIGN_IN_MINUTES: i64 = 10;
+
+#[derive(Serialize)]
+pub struct StartResponse {
+    /// Page to open in a browser
+    pub authorize_url: String,
+    /// For `POST /api/v1/oidc/poll`
+    pub poll_token: String,
+    /// Seconds until the sign-in expires
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize)]
+pub struct CallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PollRequest {
+    pub poll_token: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PollResponse {
+    /// The browser has not come back yet
+    Pending,
+    /// Signed in, with a new session
+    Complete {
+        token: String,
+        refresh_token: String,
+        /// The account was created by this sign-in
+        created: bool,
+    },
+    /// The provider account is now linked to the caller's
+    Linked,
+}
+
+/// Start signing in with the provider.
+#[tracing::instrument(skip(state))]
+pub async fn start_login(State(state): State<AppState>) -> Result<Json<StartResponse>> {
+    begin(&state, None).await
+}
+
+/// Start linking a provider account to the caller's, so it can sign in.
+#[tracing::instrument(skip(state))]
+pub async fn start_link(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<StartResponse>> {
+    begin(&state, Some(auth.user_id)).await
+}
+
+async fn begin(state: &AppState, link_user_id: Option<Uuid>) -> Result<Json<StartResponse>> {
+    let provider = provider(state)?;
+
+    let login_state = random_token();
+    let poll_token = random_token();
+    let nonce = random_token();
+    let code_verifier = random_token();
+    let authorize_url = provider
+        .authorize_url(&login_state, &nonce, &code_verifier)
+        .await
+        .map_err(ServerError::Internal)?;
+
+    let now = Utc::now();
+    sqlx::query("DELETE FROM oidc_logins WHERE expires_at <= $1")
+        .bind(now)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+    sqlx::query(
+        "INSERT INTO oidc_logins \
+         (state_hash, poll_hash, nonce, code_verifier, link_user_id, expires_at) \
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(hash_token(&login_state))
+    .bind(hash_token(&poll_token))
+    .bind(&nonce)
+    .bind(&code_verifier)
+    .bind(link_user_id)
+    .bind(now + Duration::minutes(SIGN_IN_MINUTES))
+    .execute(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    Ok(Json(StartResponse {
+        authorize_url,
+        poll_token,
+        expires_in: SIGN_IN_MINUTES * 60,
+    }))
+}
+
+/// Where the provider sends the browser back to. Shows a page telling the
+/// person to return to their terminal.
+#[tracing::instrument(skip(state, query))]
+pub async fn callback(
+    State(state): State<AppState>,
+    Query(query): Query<CallbackQuery>,
+) -> impl IntoResponse {
+    let (title, message) = match complete(&state, &query).await {
+        Ok(message) => ("Signed in", message),
+        Err(message) => ("Sign-in failed", message),
+    };
+    let page = DONE_PAGE
+        .replace("{title}", title)
+        .replace("{message}", &escape_html(&message));
+    (
+        [
+            (header::CACHE_CONTROL, "no-store"),
+            (header::REFERRER_POLICY, "no-referrer"),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; style-src 'unsafe-inline'",
+            ),
+        ],
+        Html(page),
+    )
+}
+
+/// Finish the sign-in the callback is for, and record how it went for the
+/// CLI. Returns what to tell the person either way.
+async fn complete(state: &AppState, query: &CallbackQuery) -> std::result::Result<String, String> {
+    let provider = provider(state).map_err(|_| "single sign-on is not enabled".to_string())?;
+    let state_hash = hash_token(query.state.as_deref().unwrap_or_default());
+
+    let login = sqlx::query_as::<_, (String, String, Option<Uuid>)>(
+        "SELECT nonce, code_verifier, link_user_id FROM oidc_logins \
+         WHERE state_hash = $1 AND expires_at > $2 AND user_id IS NULL AND error IS NULL",
+    )
+    .bind(&state_hash)
+    .bind(Utc::now())
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal)?
+    .ok_or_else(|| {
+        "this sign-in has expired or was already used; start again from the terminal".to_string()
+    })?;
+    let (nonce, code_verifier, link_user_id) = login;
+
+    let result = sign_in(state, provider, query, &nonce, &code_verifier, link_user_id).await;
+    let update = match &result {
+        Ok((user_id, created, _)) => {
+            sqlx::query("UPDATE oidc_logins SET user_id = $1, created = $2 WHERE state_hash = $3")
+                .bind(user_id)
+                .bind(created)
+                .bind(&state_hash)
+        }
+        Err(error) => sqlx::query("UPDATE oidc_logins SET error = $1 WHERE state_hash = $2")
+            .bind(error)
+            .bind(&state_hash),
+    };
+    update.execute(&state.pool).await.map_err(internal)?;
+
+    result.map(|(_, _, message)| message)
+}
+
+/// Check the provider's answer and find, link or create the account.
+/// Returns the account, whether it is new, and what to tell the person.
+async fn sign_in(
+    state: &AppState,
+    provider: &Provider,
+    query: &CallbackQuery,
+    nonce: &str,
+    code_verifier: &str,
+    link_user_id: Option<Uuid>,
+) -> std::result::Result<(Uuid, bool, String), String> {
+    if let Some(error) = &query.error {
+        let reason = query.error_description.as_deref().unwrap_or(error);
+        return Err(format!("the provider refused the sign-in: {reason}"));
+    }
+    let code = query
+        .code
+        .as_deref()
+        .ok_or_else(|| "the provider sent no authorization code".to_string())?;
+    let claims = provider
+        .exchange(code, code_verifier, nonce)
+        .await
+        .map_err(|e| {
+            tracing::warn!(error = %e, "single sign-on failed");
+            "the provider's answer could not be verified".to_string()
+        })?;
+
+    let linked = sqlx::query_scalar::<_, Uuid>(
+        "SELECT user_id FROM oidc_identities WHERE issuer = $1 AND subject = $2",
+    )
+    .bind(&claims.iss)
+    .bind(&claims.sub)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal)?;
+
+    if let Some(user_id) = link_user_id {
+        return match linked {
+            Some(linked) if linked == user_id => Ok((
+                user_id,
+                false,
+                "this sign-in was already linked to your account".to_string(),
+            )),
+            Some(_) => Err("this sign-in is linked to another taskbook account".to_string()),
+            None => {
+                link(&state.pool, &claims, user_id)
+                    .await
+                    .map_err(internal)?;
+                tracing::info!(user_id = %user_id, issuer = %claims.iss, "single sign-on linked");
+                Ok((
+                    user_id,
+                    false,
+                    "your account is linked; return to the terminal".to_string(),
+                ))
+            }
+        };
+    }
+
+    if let Some(user_id) = linked {
+        let disabled = sqlx::query_scalar::<_, bool>(
+            "SELECT disabled_at IS NOT NULL FROM users WHERE id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(internal)?;
+        if disabled {
+            tracing::warn!(user_id = %user_id, "single sign-on to disabled account");
+            return Err("this account is disabled".to_string());
+        }
+        return Ok((
+            user_id,
+            false,
+            "you are signed in; return to the terminal".to_string(),
+        ));
+    }
+
+    if !provider.register() {
+        return Err(
+            "no taskbook account is linked to this sign-in; log in with your \
+                    password and run `tb sync link-sso` first"
+                .to_string(),
+        );
+    }
+    let user_id = create_account(&state.pool, &claims).await?;
+    tracing::info!(user_id = %user_id, issuer = %claims.iss, "user registered with single sign-on");
+    Ok((
+        user_id,
+        true,
+        "your account was created; return to the terminal".to_string(),
+    ))
+}
+
+/// Create an account for someone signing in for the first time. Accounts
+/// are never linked by email alone: whoever controls the address at the
+/// provider would get into the account.
+async fn create_account(pool: &DbPool, claims: &Claims) -> std::result::Result<Uuid, String> {
+    let email = claims
+        .email
+        .as_deref()
+        .filter(|email| email.contains('@') && email.len() <= 255)
+        .ok_or_else(|| "the provider did not share an email address".to_string())?;
+
+    let taken = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE email = $1")
+        .bind(email)
+        .fetch_one(pool)
+        .await
+        .map_err(internal)?;
+    if taken > 0 {
+        return Err(
+            "an account with this email already exists; log in with your password \
+                    and run `tb sync link-sso` to link it"
+                .to_string(),
+        );
+    }
+
+    let base = username_base(claims.preferred_username.as_deref(), email);
+    let mut username = base.clone();
+    for n in 2.. {
+        let taken = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE username = $1")
+            .bind(&username)
+            .fetch_one(pool)
+            .await
+            .map_err(internal)?;
+        if taken == 0 {
+            break;
+        }
+        username = format!("{base}-{n}");
+    }
+
+    // The account signs in through the provider; nobody knows this password
+    let password_hash = hash_password(&random_token()).map_err(internal)?;
+
+    let mut tx = pool.begin().await.map_err(internal)?;
+    let user_id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO users (username, email, password, email_verified) \
+         VALUES ($1, $2, $3, $4) RETURNING id",
+    )
+    .bind(&username)
+    .bind(email)
+    .bind(&password_hash)
+    .bind(claims.email_verified)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal)?;
+    sqlx::query("INSERT INTO oidc_identities (issuer, subject, user_id) VALUES ($1, $2, $3)")
+        .bind(&claims.iss)
+        .bind(&claims.sub)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+
+    Ok(user_id)
+}
+
+async fn link(
+    pool: &DbPool,
+    claims: &Claims,
+    user_id: Uuid,
+) -> std::result::Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO oidc_identities (issuer, subject, user_id) VALUES ($1, $2, $3)")
+        .bind(&claims.iss)
+        .bind(&claims.sub)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// How the CLI finds out whether the sign-in is done. The session is
+/// created here, so it records the CLI's address and user agent rather
+/// than the browser's.
+#[tracing::instrument(skip(state, headers, req))]
+pub async fn poll(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    Json(req): Json<PollRequest>,
+) -> Result<Json<PollResponse>> {
+    provider(&state)?;
+    let poll_hash = hash_token(&req.poll_token);
+
+    let login = sqlx::query_as::<_, (Option<Uuid>, Option<Uuid>, bool, Option<String>)>(
+        "SELECT link_user_id, user_id, created, error FROM oidc_logins \
+         WHERE poll_hash = $1 AND expires_at > $2",
+    )
+    .bind(&poll_hash)
+    .bind(Utc::now())
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(ServerError::Database)?
+    .ok_or_else(|| ServerError::NotFound("sign-in not found or expired".to_string()))?;
+    let (link_user_id, user_id, created, error) = login;
+
+    let user_id = match (user_id, error) {
+        (_, Some(error)) => {
+            forget(&state.pool, &poll_hash).await?;
+            return Err(ServerError::Forbidden(error));
+        }
+        (None, None) => return Ok(Json(PollResponse::Pending)),
+        (Some(user_id), None) => user_id,
+    };
+
+    // Only one poll gets the session
+    if !forget(&state.pool, &poll_hash).await? {
+        return Err(ServerError::NotFound(
+            "sign-in not found or expired".to_string(),
+        ));
+    }
+
+    if link_user_id.is_some() {
+        audit::record(&state.pool, user_id, AuditEvent::SsoLinked, ip, &headers).await?;
+        return Ok(Json(PollResponse::Linked));
+    }
+
+    let session = create_session(&state, user_id, user_agent(&headers), ip).await?;
+    audit::record(&state.pool, user_id, AuditEvent::Login, ip, &headers).await?;
+
+    tracing::info!(user_id = %user_id, "user logged in with single sign-on");
+
+    Ok(Json(PollResponse::Complete {
+        token: session.token,
+        refresh_token: session.refresh_token,
+        created,
+    }))
+}
+
+/// Delete a sign-in; false if it was already gone
+async fn forget(pool: &DbPool, poll_hash: &[u8]) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM oidc_logins WHERE poll_hash = $1")
+        .bind(poll_hash)
+        .execute(pool)
+        .await
+        .map_err(ServerError::Database)?;
+    Ok(result.rows_affected() == 1)
+}
+
+fn provider(state: &AppState) -> Result<&Provider> {
+    state.oidc.as_ref().ok_or_else(|| {
+        ServerError::NotFound("single sign-on is not enabled on this server".to_string())
+    })
+}
+
+/// Log an error the person signing in cannot do anything about
+fn internal(e: impl std::fmt::Display) -> String {
+    tracing::error!(error = %e, "single sign-on failed");
+    "the server could not complete the sign-in".to_string()
+}
+
+/// A username for a new account: the provider's preferred username, or
+/// the email address's local part, with characters usernames cannot have
+/// left out.
+fn username_base(preferred: Option<&str>, email: &str) -> String {
+    let source = preferred
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or(email);
+    let local = source.split('@').next().unwrap_or_default();
+
+    // Leaves room for a `-<n>` suffix within the 64-byte limit
+    let mut username = String::new();
+    for c in local
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+    {
+        if username.len() + c.len_utf8() > 56 {
+            break;
+        }
+        username.push(c);
+    }
+    if username.is_empty() {
+        username.push_str("user");
+    }
+    username
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn username_base_prefers_preferred_username() {
+        assert_eq!(username_base(Some("jane.doe"), "j@example.com"), "janedoe");
+        assert_eq!(
+            username_base(Some("jd@corp.example"), "j@example.com"),
+            "jd"
+        );
+        assert_eq!(username_base(None, "jane_d@example.com"), "jane_d");
+        assert_eq!(username_base(Some(" "), "...@example.com"), "user");
+        assert_eq!(
+            username_base(None, &format!("{}@x.y", "a".repeat(80))).len(),
+            56
+        );
+    }
+}
@@ -0,0 +1,425 @@
+//! Listing and revoking a user's sessions, one per logged-in device.
+
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::{hash_token, random_token};
+use crate::db;
+use crate::error::{Result, ServerError};
+use crate::handlers::audit::{self, AuditEvent};
+use crate::middleware::AuthUser;
+use crate::rate_limit::ClientIp;
+use crate::router::AppState;
+
+#[derive(Serialize)]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    /// Whether this is the session the request was made with
+    pub current: bool,
+}
+
+#[derive(Serialize)]
+pub struct SessionsResponse {
+    pub sessions: Vec<SessionInfo>,
+}
+
+#[derive(Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize)]
+pub struct RefreshResponse {
+    pub token: String,
+    /// Replaces the refresh token that was sent, which no longer works
+    pub refresh_token: String,
+}
+
+type SessionRow = (
+    Uuid,
+    DateTime<Utc>,
+    Option<DateTime<Utc>>,
+    DateTime<Utc>,
+    Option<String>,
+    Option<String>,
+);
+
+#[tracing::instrument(skip(state))]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<SessionsResponse>> {
+    let rows = sqlx::query_as::<_, SessionRow>(&format!(
+        "SELECT id, created_at, last_used_at, {}, user_agent, ip FROM sessions \
+         WHERE user_id = $1 AND (expires_at > $2 OR refresh_expires_at > $2) \
+         ORDER BY COALESCE(last_used_at, created_at) DESC",
+        db::greatest("expires_at", "refresh_expires_at")
+    ))
+    .bind(auth.user_id)
+    .bind(Utc::now())
+    .fetch_all(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let sessions = rows
+        .into_iter()
+        .map(
+            |(id, created_at, last_used_at, expires_at, user_agent, ip)| SessionInfo {
+                id,
+                created_at,
+                last_used_at,
+                expires_at,
+                user_agent,
+                ip,
+                current: id == auth.session_id,
+            },
+        )
+        .collect();
+
+    Ok(Json(SessionsResponse { sessions }))
+}
+
+/// Renew a session with its refresh token, once the session token has
+/// expired (or before). Both tokens are replaced, so each refresh token
+/// works once.
+#[tracing::instrument(skip(state, req))]
+pub async fn refresh_session(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Json(req): Json<RefreshRequest>,
+) -> Result<Json<RefreshResponse>> {
+    let token = random_token();
+    let refresh_token = random_token();
+    let now = Utc::now();
+
+    let session = sqlx::query_as::<_, (Uuid, Uuid)>(
+        "UPDATE sessions SET token = $1, refresh_hash = $2, expires_at = $3, \
+                refresh_expires_at = $4, last_used_at = $5, ip = $6 \
+         WHERE refresh_hash = $7 AND refresh_expires_at > $5 \
+           AND user_id IN (SELECT id FROM users WHERE disabled_at IS NULL) \
+         RETURNING id, user_id",
+    )
+    .bind(&token)
+    .bind(hash_token(&refresh_token))
+    .bind(now + Duration::days(state.session_expiry_days))
+    .bind(now + Duration::days(state.refresh_expiry_days))
+    .bind(now)
+    .bind(ip.to_string())
+    .bind(hash_token(&req.refresh_token))
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(ServerError::Database)?
+    .ok_or(ServerError::Unauthorized)?;
+
+    let (session_id, user_id) = session;
+    sqlx::query("UPDATE users SET last_active_at = $1 WHERE id = $2")
+        .bind(now)
+        .bind(user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    tracing::info!(user_id = %user_id, session_id = %session_id, "session refreshed");
+
+    Ok(Json(RefreshResponse {
+        token,
+        refresh_token,
+    }))
+}
+
+/// End one session. The device using it has to log in again.
+#[tracing::instrument(skip(state, headers))]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    auth: AuthUser,
+    Path(session_id): Path<Uuid>,
+) -> Result<()> {
+    let result = sqlx::query("DELETE FROM sessions WHERE id = $1 AND user_id = $2")
+        .bind(session_id)
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServerError::NotFound("session not found".to_string()));
+    }
+    audit::record(
+        &state.pool,
+        auth.user_id,
+        AuditEvent::SessionRevoked,
+        ip,
+        &headers,
+    )
+    .await?;
+
+    tracing::info!(user_id = %auth.user_id, session_id = %session_id, "session revoked");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use axum::extract::FromRequestParts;
+    use axum::http::{header, Request};
+
+    use super::*;
+    use crate::handlers::user::create_session;
+    use crate::testing;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    /// Authenticate a request carrying `token`, as the extractor would
+    async fn authenticate(state: &AppState, token: &str) -> Result<AuthUser> {
+        let (mut parts, ()) = Request::builder()
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .body(())
+            .unwrap()
+            .into_parts();
+        AuthUser::from_request_parts(&mut parts, state).await
+    }
+
+    async fn refresh(state: &AppState, refresh_token: &str) -> Result<RefreshResponse> {
+        let req = RefreshRequest {
+            refresh_token: refresh_token.to_string(),
+        };
+        refresh_session(State(state.clone()), ClientIp(IP), Json(req))
+            .await
+            .map(|Json(response)| response)
+    }
+
+    async fn list(state: &AppState, auth: AuthUser) -> Vec<SessionInfo> {
+        let Json(response) = list_sessions(State(state.clone()), auth).await.unwrap();
+        response.sessions
+    }
+
+    async fn revoke(state: &AppState, auth: AuthUser, session_id: Uuid) -> Result<()> {
+        revoke_session(
+            State(state.clone()),
+            ClientIp(IP),
+            HeaderMap::new(),
+            auth,
+            Path(session_id),
+        )
+        .await
+    }
+
+    async fn set_session(state: &AppState, column: &str, value: DateTime<Utc>) {
+        sqlx::query(&format!("UPDATE sessions SET {column} = $1"))
+            .bind(value)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn refresh_replaces_both_tokens() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let session = create_session(&state, user_id, None, IP).await.unwrap();
+
+        let renewed = refresh(&state, &session.refresh_token).await.unwrap();
+        assert_eq!(
+            authenticate(&state, &renewed.token).await.unwrap().user_id,
+            user_id
+        );
+        assert!(matches!(
+            authenticate(&state, &session.token).await,
+            Err(ServerError::Unauthorized)
+        ));
+        assert!(matches!(
+            refresh(&state, &session.refresh_token).await,
+            Err(ServerError::Unauthorized)
+        ));
+        assert!(refresh(&state, &renewed.refresh_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_can_only_be_refreshed() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let session = create_session(&state, user_id, None, IP).await.unwrap();
+        set_session(&state, "expires_at", Utc::now() - Duration::minutes(1)).await;
+
+        assert!(matches!(
+            authenticate(&state, &session.token).await,
+            Err(ServerError::Unauthorized)
+        ));
+        let renewed = refresh(&state, &session.refresh_token).await.unwrap();
+        assert!(authenticate(&state, &renewed.token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn expired_refresh_tokens_and_disabled_users_are_rejected() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let session = create_session(&state, user_id, None, IP).await.unwrap();
+        set_session(
+            &state,
+            "refresh_expires_at",
+            Utc::now() - Duration::minutes(1),
+        )
+        .await;
+        assert!(matches!(
+            refresh(&state, &session.refresh_token).await,
+            Err(ServerError::Unauthorized)
+        ));
+
+        let session = create_session(&state, user_id, None, IP).await.unwrap();
+        sqlx::query("UPDATE users SET disabled_at = $1 WHERE id = $2")
+            .bind(Utc::now())
+            .bind(user_id)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            refresh(&state, &session.refresh_token).await,
+            Err(ServerError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn sessions_in_use_slide_their_expiry() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let session = create_session(&state, user_id, None, IP).await.unwrap();
+        // Whole seconds, which every database stores exactly
+        let soon =
+            DateTime::from_timestamp((Utc::now() + Duration::minutes(1)).timestamp(), 0).unwrap();
+        set_session(&state, "expires_at", soon).await;
+        set_session(&state, "refresh_expires_at", soon).await;
+
+        // Used recently: left alone
+        authenticate(&state, &session.token).await.unwrap();
+        let expires_at: DateTime<Utc> = sqlx::query_scalar("SELECT expires_at FROM sessions")
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(expires_at, soon);
+
+        set_session(&state, "last_used_at", Utc::now() - Duration::hours(1)).await;
+        authenticate(&state, &session.token).await.unwrap();
+        let (expires_at, refresh_expires_at): (DateTime<Utc>, DateTime<Utc>) =
+            sqlx::query_as("SELECT expires_at, refresh_expires_at FROM sessions")
+                .fetch_one(&state.pool)
+                .await
+                .unwrap();
+        assert!(expires_at > Utc::now() + Duration::days(state.session_expiry_days - 1));
+        assert!(refresh_expires_at > Utc::now() + Duration::days(state.refresh_expiry_days - 1));
+    }
+
+    #[tokio::test]
+    async fn users_see_their_live_sessions_with_the_current_one_marked() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let other_id = testing::user(&state, "bob").await;
+        let expired = create_session(&state, user_id, None, IP).await.unwrap();
+        for column in ["expires_at", "refresh_expires_at"] {
+            set_session(&state, column, Utc::now() - Duration::minutes(1)).await;
+        }
+        let phone = create_session(&state, user_id, Some("tb/1.2.5".to_string()), IP)
+            .await
+            .unwrap();
+        let laptop = create_session(&state, user_id, None, IP).await.unwrap();
+        create_session(&state, other_id, None, IP).await.unwrap();
+        assert!(authenticate(&state, &expired.token).await.is_err());
+
+        let auth = authenticate(&state, &laptop.token).await.unwrap();
+        let current = auth.session_id;
+        let sessions = list(&state, auth).await;
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions.iter().filter(|session| session.current).count(), 1);
+        assert!(sessions
+            .iter()
+            .all(|session| session.current == (session.id == current)));
+        assert!(sessions
+            .iter()
+            .any(|session| session.user_agent.as_deref() == Some("tb/1.2.5")));
+        assert!(sessions
+            .iter()
+            .all(|session| session.ip.as_deref() == Some("127.0.0.1")));
+
+        let phone_id = authenticate(&state, &phone.token).await.unwrap().session_id;
+        assert!(sessions.iter().any(|session| session.id == phone_id));
+    }
+
+    #[tokio::test]
+    async fn revoked_sessions_stop_working() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let phone = create_session(&state, user_id, None, IP).await.unwrap();
+        let laptop = create_session(&state, user_id, None, IP).await.unwrap();
+        let phone_id = authenticate(&state, &phone.token).await.unwrap().session_id;
+
+        let auth = authenticate(&state, &laptop.token).await.unwrap();
+        revoke(&state, auth.clone(), phone_id).await.unwrap();
+        assert!(matches!(
+            authenticate(&state, &phone.token).await,
+            Err(ServerError::Unauthorized)
+        ));
+        assert!(matches!(
+            refresh(&state, &phone.refresh_token).await,
+            Err(ServerError::Unauthorized)
+        ));
+        assert_eq!(list(&state, auth.clone()).await.len(), 1);
+        assert!(matches!(
+            revoke(&state, auth, phone_id).await,
+            Err(ServerError::NotFound(_))
+        ));
+
+        let logged: Vec<String> = sqlx::query_scalar("SELECT event FROM audit_log")
+            .fetch_all(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(logged, ["session_revoked"]);
+    }
+
+    #[tokio::test]
+    async fn other_users_sessions_cannot_be_revoked() {
+        let Some(state) = testing::state().await else {
+            return;
+        };
+        let user_id = testing::user(&state, "alice").await;
+        let other_id = testing::user(&state, "bob").await;
+        let own = create_session(&state, user_id, None, IP).await.unwrap();
+        let other = create_session(&state, other_id, None, IP).await.unwrap();
+        let other_session = authenticate(&state, &other.token).await.unwrap().session_id;
+
+        let auth = authenticate(&state, &own.token).await.unwrap();
+        assert!(matches!(
+            revoke(&state, auth, other_session).await,
+            Err(ServerError::NotFound(_))
+        ));
+        assert!(authenticate(&state, &other.token).await.is_ok());
+        let logged: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_log")
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(logged, 0);
+    }
+}
@@ -0,0 +1,218 @@
+//! WebSocket sync channel, for networks where SSE is buffered or blocked by
+//! a proxy.
+//!
+//! Messages are JSON text frames tagged by `type`. The server sends the same
+//! change notifications as `/api/v1/events`; the client may push item
+//! uploads over the socket instead of `PUT /api/v1/items`, and gets an `ack`
+//! or `error` back for each.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::body::to_bytes;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::error::{Result, ServerError};
+use crate::handlers::events::ConnectionGuard;
+use crate::handlers::items::{replace_items, EncryptedItemData, Store};
+use crate::middleware::ReadAccess;
+use crate::router::{AppState, SyncEvent};
+use crate::webhooks::Event;
+
+/// How often the server pings an idle connection, so proxies keep it open
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Largest error body read back from a [`ServerError`] response
+const MAX_ERROR_SIZE: usize = 64 * 1024;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// Replace the items or the archive, like `PUT /api/v1/items`
+    PutItems {
+        /// Echoed in the reply
+        id: u64,
+        #[serde(default)]
+        archived: bool,
+        items: HashMap<String, EncryptedItemData>,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    /// Items (`"items"`) or the archive (`"archive"`) changed
+    DataChanged { data: &'static str },
+    /// A push was stored
+    Ack { id: u64 },
+    /// A message was refused; `body` is what the HTTP endpoint would return
+    Error {
+        id: Option<u64>,
+        status: u16,
+        #[serde(flatten)]
+        body: serde_json::Value,
+    },
+}
+
+impl ServerMessage {
+    fn data_changed(archived: bool) -> Self {
+        ServerMessage::DataChanged {
+            data: if archived { "archive" } else { "items" },
+        }
+    }
+}
+
+/// Upgrade to a WebSocket carrying sync notifications and item pushes.
+#[tracing::instrument(skip(state, ws))]
+pub async fn socket(
+    State(state): State<AppState>,
+    auth: ReadAccess,
+    ws: WebSocketUpgrade,
+) -> Response {
+    // The same limit as request bodies
+    ws.max_message_size(state.max_body_size)
+        .on_upgrade(move |socket| serve(socket, state, auth))
+}
+
+async fn serve(mut socket: WebSocket, state: AppState, auth: ReadAccess) {
+    let _guard = ConnectionGuard::new(
+        "websocket.active_connections",
+        "Number of active WebSocket connections",
+        "/api/v1/ws",
+    );
+    let mut rx = state.notifications.subscribe(auth.user_id);
+    let mut keep_alive = tokio::time::interval(KEEP_ALIVE);
+
+    loop {
+        let reply = tokio::select! {
+            event = rx.recv() => match event {
+                Ok(notification) => {
+                    let SyncEvent::DataChanged { archived } = notification.event;
+                    ServerMessage::data_changed(archived)
+                }
+                // Lagged: the client fell behind — tell it to do a full refresh.
+                Err(RecvError::Lagged(_)) => ServerMessage::data_changed(false),
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => handle(&state, &auth, &text).await,
+                // Pings are answered by axum; other frames carry nothing for us
+                Some(Ok(Message::Binary(_) | Message::Ping(_) | Message::Pong(_))) => continue,
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+            },
+            _ = keep_alive.tick() => {
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        let Ok(text) = serde_json::to_string(&reply) else {
+            continue;
+        };
+        if socket.send(Message::Text(text)).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Act on a message from the client and say how it went
+async fn handle(state: &AppState, auth: &ReadAccess, text: &str) -> ServerMessage {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            return error_message(
+                None,
+                ServerError::Validation(format!("invalid message: {e}")),
+            )
+            .await
+        }
+    };
+
+    match message {
+        ClientMessage::PutItems {
+            id,
+            archived,
+            items,
+        } => match put_items(state, auth, archived, &items).await {
+            Ok(()) => ServerMessage::Ack { id },
+            Err(e) => error_message(Some(id), e).await,
+        },
+    }
+}
+
+async fn put_items(
+    state: &AppState,
+    auth: &ReadAccess,
+    archived: bool,
+    items: &HashMap<String, EncryptedItemData>,
+) -> Result<()> {
+    if !auth.can_write {
+        return Err(ServerError::Forbidden(
+            "this API token is read-only".to_string(),
+        ));
+    }
+    let changes = replace_items(state, Store::User(auth.user_id), archived, items).await?;
+    state
+        .notifications
+        .notify(auth.user_id, SyncEvent::DataChanged { archived });
+    state.webhooks.notify(
+        &state.pool,
+        vec![auth.user_id],
+        auth.user_id,
+        Event::for_items(archived),
+        changes,
+    );
+    tracing::debug!(
+        user_id = %auth.user_id,
+        archived,
+        item_count = items.len(),
+        "items pushed over WebSocket"
+    );
+    Ok(())
+}
+
+/// The error as the HTTP endpoints would report it (logging it the same way)
+async fn error_message(id: Option<u64>, error: ServerError) -> ServerMessage {
+    let response = error.into_response();
+    let status = response.status().as_u16();
+    let body = to_bytes(response.into_body(), MAX_ERROR_SIZE)
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_else(|| serde_json::json!({ "error": "internal server error" }));
+    ServerMessage::Error { id, status, body }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_are_tagged_by_type() {
+        let json = serde_json::to_string(&ServerMessage::data_changed(true)).unwrap();
+        assert_eq!(json, r#"{"type":"data_changed","data":"archive"}"#);
+
+        let json = serde_json::to_string(&ServerMessage::Error {
+            id: Some(7),
+            status: 403,
+            body: serde_json::json!({ "error": "this API token is read-only" }),
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"error","id":7,"status":403,"error":"this API token is read-only"}"#
+        );
+
+        let message: ClientMessage =
+            serde_json::from_str(r#"{"type":"put_items","id":1,"items":{}}"#).unwrap();
+        let ClientMessage::PutItems { id, archived, .. } = message;
+        assert_eq!(id, 1);
+        assert!(!archived);
+    }
+}
@@ -0,0 +1,243 @@
+//! Personal API tokens, for scripts and scheduled jobs.
+//!
+//! A token works on the item, archive, shared board and event endpoints,
+//! like a session but without expiring unless asked to. A `read` token can
+//! only fetch data. Managing the account (password, devices, tokens and so
+//! on) always needs a login session.
+
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::{hash_token, random_token};
+use crate::error::{Result, ServerError};
+use crate::handlers::audit::{self, AuditEvent};
+use crate::middleware::AuthUser;
+use crate::rate_limit::ClientIp;
+use crate::router::AppState;
+
+/// Starts every API token, so it can be told from a session token (and
+/// found by secret scanners)
+pub const API_TOKEN_PREFIX: &str = "tbp_";
+
+/// Longest a token name may be
+const MAX_NAME_LEN: usize = 64;
+
+/// What an API token may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Fetch items, the archive, shared boards and events
+    Read,
+    /// Also replace items, the archive and shared board items
+    Write,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Write => "write",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "read" => Some(Scope::Read),
+            "write" => Some(Scope::Write),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateTokenRequest {
+    /// What the token is for, e.g. "backup cron job"
+    pub name: String,
+    pub scope: Scope,
+    /// Seconds until the token expires; no expiry if missing
+    #[serde(default)]
+    pub expires_in: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct CreateTokenResponse {
+    pub id: Uuid,
+    /// Shown this once; the server keeps only a hash
+    pub token: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct TokenInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub scope: Scope,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct TokensResponse {
+    pub tokens: Vec<TokenInfo>,
+}
+
+type TokenRow = (
+    Uuid,
+    String,
+    String,
+    DateTime<Utc>,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+);
+
+#[tracing::instrument(skip(state, headers, req))]
+pub async fn create_token(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    auth: AuthUser,
+    Json(req): Json<CreateTokenRequest>,
+) -> Result<Json<CreateTokenResponse>> {
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(ServerError::Validation(format!(
+            "token name must be 1 to {MAX_NAME_LEN} characters"
+        )));
+    }
+    if req.expires_in.is_some_and(|secs| secs <= 0) {
+        return Err(ServerError::Validation(
+            "expires_in must be positive".to_string(),
+        ));
+    }
+
+    // Expired tokens are of no use to anyone; drop them while we are here
+    sqlx::query("DELETE FROM api_tokens WHERE user_id = $1 AND expires_at <= $2")
+        .bind(auth.user_id)
+        .bind(Utc::now())
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    let token = format!("{API_TOKEN_PREFIX}{}", random_token());
+    let expires_at = req
+        .expires_in
+        .map(|secs| Utc::now() + Duration::seconds(secs));
+
+    let id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO api_tokens (user_id, name, token_hash, scope, expires_at) \
+         VALUES ($1, $2, $3, $4, $5) RETURNING id",
+    )
+    .bind(auth.user_id)
+    .bind(name)
+    .bind(hash_token(&token))
+    .bind(req.scope.as_str())
+    .bind(expires_at)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+    audit::record(
+        &state.pool,
+        auth.user_id,
+        AuditEvent::TokenCreated,
+        ip,
+        &headers,
+    )
+    .await?;
+
+    tracing::info!(
+        user_id = %auth.user_id,
+        token_id = %id,
+        scope = req.scope.as_str(),
+        "API token created"
+    );
+
+    Ok(Json(CreateTokenResponse {
+        id,
+        token,
+        expires_at,
+    }))
+}
+
+#[tracing::instrument(skip(state))]
+pub async fn list_tokens(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<TokensResponse>> {
+    let rows = sqlx::query_as::<_, TokenRow>(
+        "SELECT id, name, scope, created_at, last_used_at, expires_at FROM api_tokens \
+         WHERE user_id = $1 AND (expires_at IS NULL OR expires_at > $2) \
+         ORDER BY created_at",
+    )
+    .bind(auth.user_id)
+    .bind(Utc::now())
+    .fetch_all(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let tokens = rows
+        .into_iter()
+        .filter_map(|(id, name, scope, created_at, last_used_at, expires_at)| {
+            Some(TokenInfo {
+                id,
+                name,
+                scope: Scope::parse(&scope)?,
+                created_at,
+                last_used_at,
+                expires_at,
+            })
+        })
+        .collect();
+
+    Ok(Json(TokensResponse { tokens }))
+}
+
+/// Revoke a token. Scripts using it stop working at once.
+#[tracing::instrument(skip(state, headers))]
+pub async fn revoke_token(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    auth: AuthUser,
+    Path(token_id): Path<Uuid>,
+) -> Result<()> {
+    let result = sqlx::query("DELETE FROM api_tokens WHERE id = $1 AND user_id = $2")
+        .bind(token_id)
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServerError::NotFound("token not found".to_string()));
+    }
+    audit::record(
+        &state.pool,
+        auth.user_id,
+        AuditEvent::TokenRevoked,
+        ip,
+        &headers,
+    )
+    .await?;
+
+    tracing::info!(user_id = %auth.user_id, token_id = %token_id, "API token revoked");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_round_trip() {
+        for scope in [Scope::Read, Scope::Write] {
+            assert_eq!(Scope::parse(scope.as_str()), Some(scope));
+        }
+        assert_eq!(Scope::parse("admin"), None);
+    }
+}
@@ -1,72 +1,174 @@
-use std::net::SocketAddr;
+use std::net::IpAddr;
 
-use axum::extract::{ConnectInfo, State};
+use axum::extract::State;
+use axum::http::HeaderMap;
 use axum::Json;
-use base64::Engine;
 use chrono::{Duration, Utc};
-use rand::Rng;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::auth::{hash_password, verify_password};
+use crate::auth::{hash_password, hash_token, random_token, verify_password};
+use crate::config::RegistrationMode;
+use crate::db::DbPool;
 use crate::error::{Result, ServerError};
-use crate::middleware::AuthUser;
+use crate::handlers::audit::{self, AuditEvent};
+use crate::handlers::email;
+use crate::invites;
+use crate::middleware::{user_agent, AuthUser, ReadAccess};
+use crate::quota;
+use crate::rate_limit::ClientIp;
 use crate::router::AppState;
+use crate::totp;
+
+/// Largest key parameters document accepted
+const MAX_KEY_PARAMS_LEN: usize = 1024;
 
 #[derive(Deserialize)]
 pub struct RegisterRequest {
     pub username: String,
     pub email: String,
     pub password: String,
+    /// Needed when the server only lets invited people register
+    #[serde(default)]
+    pub invite_code: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct RegisterResponse {
     pub token: String,
+    /// Renews the session once `token` expires; see `POST /api/v1/refresh`
+    pub refresh_token: String,
 }
 
 #[derive(Deserialize)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    /// Needed once two-factor authentication is enabled
+    #[serde(default)]
+    pub totp_code: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct LoginResponse {
     pub token: String,
+    pub refresh_token: String,
 }
 
 #[derive(Serialize)]
 pub struct MeResponse {
     pub username: String,
     pub email: String,
+    pub email_verified: bool,
+    /// How the encryption key is derived from a passphrase, if it is
+    pub key_params: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct KeyParamsRequest {
+    /// `None` when the account goes back to a random key
+    pub key_params: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UsageResponse {
+    /// Encrypted bytes stored: items, owned shared boards and share links
+    pub used_bytes: i64,
+    /// Bytes the account may store; `None` for no limit
+    pub quota_bytes: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Serialize)]
+pub struct ChangePasswordResponse {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct EnrollTotpRequest {
+    pub password: String,
+}
+
+#[derive(Serialize)]
+pub struct EnrollTotpResponse {
+    /// Base32-encoded secret, for typing into an authenticator app
+    pub secret: String,
+    /// `otpauth://` URI, for authenticator apps that import links or QR codes
+    pub uri: String,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmTotpRequest {
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub struct DisableTotpRequest {
+    pub password: String,
+    pub code: String,
 }
 
 #[tracing::instrument(skip(state, req), fields(username = %req.username))]
 pub async fn register(
     State(state): State<AppState>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
     Json(req): Json<RegisterRequest>,
 ) -> Result<Json<RegisterResponse>> {
-    // Rate limit check
-    if !state.auth_rate_limiter.check(addr.ip()).await {
-        tracing::warn!(ip = %addr.ip(), "register rate limited");
-        return Err(ServerError::RateLimited);
-    }
+    let invite_code = match state.registration_mode {
+        RegistrationMode::Open | RegistrationMode::Single => None,
+        RegistrationMode::Invite => Some(
+            req.invite_code
+                .as_deref()
+                .filter(|code| !code.trim().is_empty())
+                .ok_or(ServerError::InviteRequired)?,
+        ),
+        RegistrationMode::Closed => {
+            return Err(ServerError::Forbidden(
+                "registration is closed on this server".to_string(),
+            ))
+        }
+    };
 
     validate_registration(&req)?;
 
     let password_hash = hash_password(&req.password)
         .map_err(|e| ServerError::Internal(format!("password hashing failed: {e}")))?;
 
+    // The invite is only used up if the account is created
+    let mut tx = state.pool.begin().await.map_err(ServerError::Database)?;
+    if let Some(code) = invite_code {
+        invites::redeem(&mut tx, code).await?;
+    }
+    if state.registration_mode == RegistrationMode::Single {
+        let taken: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users)")
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(ServerError::Database)?;
+        if taken {
+            return Err(ServerError::Forbidden(
+                "this server is for a single account, which already exists".to_string(),
+            ));
+        }
+    }
     let user_id = sqlx::query_scalar::<_, Uuid>(
         "INSERT INTO users (username, email, password) VALUES ($1, $2, $3) RETURNING id",
     )
     .bind(&req.username)
     .bind(&req.email)
     .bind(&password_hash)
-    .fetch_one(&state.pool)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| match e {
         sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
@@ -74,58 +176,100 @@ pub async fn register(
         }
         _ => ServerError::Database(e),
     })?;
+    tx.commit().await.map_err(ServerError::Database)?;
 
-    let token = create_session(&state.pool, user_id, state.session_expiry_days).await?;
+    let session = create_session(&state, user_id, user_agent(&headers), ip).await?;
 
     tracing::info!(username = %req.username, "user registered");
 
-    Ok(Json(RegisterResponse { token }))
+    if let Some(mailer) = &state.mailer {
+        // Registration has succeeded either way; the user can ask again
+        if let Err(e) =
+            email::email_verification(&state.pool, mailer, user_id, &req.username, &req.email).await
+        {
+            tracing::error!(error = %e, "failed to send verification email");
+        }
+    }
+
+    Ok(Json(RegisterResponse {
+        token: session.token,
+        refresh_token: session.refresh_token,
+    }))
 }
 
 #[tracing::instrument(skip(state, req), fields(username = %req.username))]
 pub async fn login(
     State(state): State<AppState>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
     Json(req): Json<LoginRequest>,
 ) -> Result<Json<LoginResponse>> {
-    // Rate limit check
-    if !state.auth_rate_limiter.check(addr.ip()).await {
-        tracing::warn!(ip = %addr.ip(), "login rate limited");
-        return Err(ServerError::RateLimited);
-    }
-
-    let user =
-        sqlx::query_as::<_, (Uuid, String)>("SELECT id, password FROM users WHERE username = $1")
-            .bind(&req.username)
-            .fetch_optional(&state.pool)
-            .await
-            .map_err(ServerError::Database)?
-            .ok_or(ServerError::InvalidCredentials)?;
+    let user = sqlx::query_as::<_, (Uuid, String, bool, Option<Vec<u8>>, bool)>(
+        "SELECT id, password, totp_enabled, totp_secret, disabled_at IS NOT NULL \
+         FROM users WHERE username = $1",
+    )
+    .bind(&req.username)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(ServerError::Database)?
+    .ok_or(ServerError::InvalidCredentials)?;
 
-    let (user_id, password_hash) = user;
+    let (user_id, password_hash, totp_enabled, totp_secret, disabled) = user;
 
     let valid = verify_password(&req.password, &password_hash)
         .map_err(|e| ServerError::Internal(format!("password verification failed: {e}")))?;
 
     if !valid {
         tracing::warn!(username = %req.username, "failed login attempt");
+        audit::record(&state.pool, user_id, AuditEvent::LoginFailed, ip, &headers).await?;
         return Err(ServerError::InvalidCredentials);
     }
 
-    let token = create_session(&state.pool, user_id, state.session_expiry_days).await?;
+    if disabled {
+        tracing::warn!(username = %req.username, "login to disabled account");
+        return Err(ServerError::Forbidden(
+            "this account is disabled".to_string(),
+        ));
+    }
+
+    if totp_enabled {
+        let secret = totp_secret
+            .ok_or_else(|| ServerError::Internal("two-factor secret missing".to_string()))?;
+        let code = req.totp_code.as_deref().ok_or(ServerError::TotpRequired)?;
+        if let Err(e) = check_totp(&state.pool, user_id, &secret, code).await {
+            if matches!(e, ServerError::InvalidTotpCode) {
+                audit::record(&state.pool, user_id, AuditEvent::LoginFailed, ip, &headers).await?;
+            }
+            return Err(e);
+        }
+    }
+
+    let session = create_session(&state, user_id, user_agent(&headers), ip).await?;
+    audit::record(&state.pool, user_id, AuditEvent::Login, ip, &headers).await?;
 
     tracing::info!(username = %req.username, "user logged in");
 
-    Ok(Json(LoginResponse { token }))
+    Ok(Json(LoginResponse {
+        token: session.token,
+        refresh_token: session.refresh_token,
+    }))
 }
 
-#[tracing::instrument(skip(state))]
-pub async fn logout(State(state): State<AppState>, auth: AuthUser) -> Result<()> {
+#[tracing::instrument(skip(state, headers))]
+pub async fn logout(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    auth: AuthUser,
+) -> Result<()> {
+    let mut tx = state.pool.begin().await.map_err(ServerError::Database)?;
     sqlx::query("DELETE FROM sessions WHERE user_id = $1")
         .bind(auth.user_id)
-        .execute(&state.pool)
+        .execute(&mut *tx)
         .await
         .map_err(ServerError::Database)?;
+    audit::record_in(&mut tx, auth.user_id, AuditEvent::LoggedOut, ip, &headers).await?;
+    tx.commit().await.map_err(ServerError::Database)?;
 
     tracing::info!(user_id = %auth.user_id, "user logged out");
 
@@ -133,36 +277,360 @@ pub async fn logout(State(state): State<AppState>, auth: AuthUser) -> Result<()>
 }
 
 #[tracing::instrument(skip(state))]
-pub async fn me(State(state): State<AppState>, auth: AuthUser) -> Result<Json<MeResponse>> {
-    let user =
-        sqlx::query_as::<_, (String, String)>("SELECT username, email FROM users WHERE id = $1")
-            .bind(auth.user_id)
-            .fetch_one(&state.pool)
-            .await
-            .map_err(ServerError::Database)?;
+pub async fn me(State(state): State<AppState>, auth: ReadAccess) -> Result<Json<MeResponse>> {
+    let (username, email, email_verified, key_params) =
+        sqlx::query_as::<_, (String, String, bool, Option<String>)>(
+            "SELECT username, email, email_verified, key_params FROM users WHERE id = $1",
+        )
+        .bind(auth.user_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
 
     Ok(Json(MeResponse {
-        username: user.0,
-        email: user.1,
+        username,
+        email,
+        email_verified,
+        key_params,
+    }))
+}
+
+/// Store how the encryption key is derived from a passphrase, for the
+/// account's other devices. The server never sees the passphrase or key.
+#[tracing::instrument(skip(state, req))]
+pub async fn put_key_params(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<KeyParamsRequest>,
+) -> Result<()> {
+    if req
+        .key_params
+        .as_ref()
+        .is_some_and(|params| params.is_empty() || params.len() > MAX_KEY_PARAMS_LEN)
+    {
+        return Err(ServerError::Validation(
+            "invalid key parameters size".to_string(),
+        ));
+    }
+
+    sqlx::query("UPDATE users SET key_params = $1 WHERE id = $2")
+        .bind(&req.key_params)
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    tracing::info!(user_id = %auth.user_id, "key parameters updated");
+
+    Ok(())
+}
+
+/// Storage used by the account, and its quota.
+#[tracing::instrument(skip(state))]
+pub async fn usage(State(state): State<AppState>, auth: ReadAccess) -> Result<Json<UsageResponse>> {
+    let mut conn = state.pool.acquire().await.map_err(ServerError::Database)?;
+    let used_bytes = quota::used(&mut conn, auth.user_id).await?;
+    let quota_bytes = quota::limit(&mut conn, auth.user_id, state.storage_quota).await?;
+
+    Ok(Json(UsageResponse {
+        used_bytes,
+        quota_bytes,
+    }))
+}
+
+/// Change the password. Every session is ended, including the caller's,
+/// and a new session is returned in its place.
+#[tracing::instrument(skip(state, req))]
+pub async fn change_password(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    auth: AuthUser,
+    Json(req): Json<ChangePasswordRequest>,
+) -> Result<Json<ChangePasswordResponse>> {
+    reauthenticate(&state.pool, auth.user_id, &req.current_password).await?;
+    validate_password(&req.new_password)?;
+
+    let password_hash = hash_password(&req.new_password)
+        .map_err(|e| ServerError::Internal(format!("password hashing failed: {e}")))?;
+
+    let mut tx = state.pool.begin().await.map_err(ServerError::Database)?;
+    sqlx::query("UPDATE users SET password = $1 WHERE id = $2")
+        .bind(&password_hash)
+        .bind(auth.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(ServerError::Database)?;
+    sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+        .bind(auth.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(ServerError::Database)?;
+    tx.commit().await.map_err(ServerError::Database)?;
+
+    let session = create_session(&state, auth.user_id, user_agent(&headers), ip).await?;
+    audit::record(
+        &state.pool,
+        auth.user_id,
+        AuditEvent::PasswordChanged,
+        ip,
+        &headers,
+    )
+    .await?;
+
+    tracing::info!(user_id = %auth.user_id, "password changed");
+
+    Ok(Json(ChangePasswordResponse {
+        token: session.token,
+        refresh_token: session.refresh_token,
+    }))
+}
+
+/// Delete the account. Sessions, items, owned shared boards and share links
+/// go with it through `ON DELETE CASCADE`.
+#[tracing::instrument(skip(state, req))]
+pub async fn delete_account(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<DeleteAccountRequest>,
+) -> Result<()> {
+    reauthenticate(&state.pool, auth.user_id, &req.password).await?;
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    tracing::info!(user_id = %auth.user_id, "account deleted");
+
+    Ok(())
+}
+
+/// Start enrolling in two-factor authentication. Returns a new secret, which
+/// only takes effect once a code from it is confirmed.
+#[tracing::instrument(skip(state, req))]
+pub async fn enroll_totp(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<EnrollTotpRequest>,
+) -> Result<Json<EnrollTotpResponse>> {
+    reauthenticate(&state.pool, auth.user_id, &req.password).await?;
+
+    let (username, enabled) = sqlx::query_as::<_, (String, bool)>(
+        "SELECT username, totp_enabled FROM users WHERE id = $1",
+    )
+    .bind(auth.user_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+    if enabled {
+        return Err(ServerError::Validation(
+            "two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let secret = totp::generate_secret();
+    sqlx::query("UPDATE users SET totp_secret = $1, totp_last_step = NULL WHERE id = $2")
+        .bind(&secret)
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    Ok(Json(EnrollTotpResponse {
+        secret: totp::encode_secret(&secret),
+        uri: totp::provisioning_uri(&secret, &username),
     }))
 }
 
-/// Generate a cryptographically random 256-bit session token.
-async fn create_session(pool: &PgPool, user_id: Uuid, expiry_days: i64) -> Result<String> {
-    let mut token_bytes = [0u8; 32];
-    rand::thread_rng().fill(&mut token_bytes);
-    let token = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(token_bytes);
-    let expires_at = Utc::now() + Duration::days(expiry_days);
+/// Finish enrolling: a valid code from the new secret turns two-factor
+/// authentication on.
+#[tracing::instrument(skip(state, headers, req))]
+pub async fn confirm_totp(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    auth: AuthUser,
+    Json(req): Json<ConfirmTotpRequest>,
+) -> Result<()> {
+    let (enabled, secret) = sqlx::query_as::<_, (bool, Option<Vec<u8>>)>(
+        "SELECT totp_enabled, totp_secret FROM users WHERE id = $1",
+    )
+    .bind(auth.user_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+    if enabled {
+        return Err(ServerError::Validation(
+            "two-factor authentication is already enabled".to_string(),
+        ));
+    }
+    let secret = secret
+        .ok_or_else(|| ServerError::Validation("start two-factor enrollment first".to_string()))?;
+
+    check_totp(&state.pool, auth.user_id, &secret, &req.code).await?;
+
+    let mut tx = state.pool.begin().await.map_err(ServerError::Database)?;
+    sqlx::query("UPDATE users SET totp_enabled = true WHERE id = $1")
+        .bind(auth.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(ServerError::Database)?;
+    audit::record_in(&mut tx, auth.user_id, AuditEvent::TotpEnabled, ip, &headers).await?;
+    tx.commit().await.map_err(ServerError::Database)?;
 
-    sqlx::query("INSERT INTO sessions (user_id, token, expires_at) VALUES ($1, $2, $3)")
+    tracing::info!(user_id = %auth.user_id, "two-factor authentication enabled");
+
+    Ok(())
+}
+
+/// Turn two-factor authentication off. Needs both the password and a code.
+#[tracing::instrument(skip(state, headers, req))]
+pub async fn disable_totp(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    auth: AuthUser,
+    Json(req): Json<DisableTotpRequest>,
+) -> Result<()> {
+    reauthenticate(&state.pool, auth.user_id, &req.password).await?;
+
+    let (enabled, secret) = sqlx::query_as::<_, (bool, Option<Vec<u8>>)>(
+        "SELECT totp_enabled, totp_secret FROM users WHERE id = $1",
+    )
+    .bind(auth.user_id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+    let secret = match (enabled, secret) {
+        (true, Some(secret)) => secret,
+        _ => {
+            return Err(ServerError::Validation(
+                "two-factor authentication is not enabled".to_string(),
+            ))
+        }
+    };
+
+    check_totp(&state.pool, auth.user_id, &secret, &req.code).await?;
+
+    let mut tx = state.pool.begin().await.map_err(ServerError::Database)?;
+    sqlx::query(
+        "UPDATE users SET totp_enabled = false, totp_secret = NULL, totp_last_step = NULL \
+         WHERE id = $1",
+    )
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(ServerError::Database)?;
+    audit::record_in(
+        &mut tx,
+        auth.user_id,
+        AuditEvent::TotpDisabled,
+        ip,
+        &headers,
+    )
+    .await?;
+    tx.commit().await.map_err(ServerError::Database)?;
+
+    tracing::info!(user_id = %auth.user_id, "two-factor authentication disabled");
+
+    Ok(())
+}
+
+/// Check a two-factor code and record its time step, so that the same code
+/// cannot be used twice.
+pub(crate) async fn check_totp(
+    pool: &DbPool,
+    user_id: Uuid,
+    secret: &[u8],
+    code: &str,
+) -> Result<()> {
+    let Some(step) = totp::verify(secret, code, Utc::now().timestamp()) else {
+        tracing::warn!(user_id = %user_id, "invalid two-factor code");
+        return Err(ServerError::InvalidTotpCode);
+    };
+
+    let result = sqlx::query(
+        "UPDATE users SET totp_last_step = $1 \
+         WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1)",
+    )
+    .bind(step)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    if result.rows_affected() == 0 {
+        tracing::warn!(user_id = %user_id, "two-factor code reused");
+        return Err(ServerError::InvalidTotpCode);
+    }
+    Ok(())
+}
+
+/// Check `password` against the user's, for actions that need more than a
+/// session token.
+async fn reauthenticate(pool: &DbPool, user_id: Uuid, password: &str) -> Result<()> {
+    let password_hash = sqlx::query_scalar::<_, String>("SELECT password FROM users WHERE id = $1")
         .bind(user_id)
-        .bind(&token)
-        .bind(expires_at)
-        .execute(pool)
+        .fetch_one(pool)
         .await
         .map_err(ServerError::Database)?;
 
-    Ok(token)
+    let valid = verify_password(password, &password_hash)
+        .map_err(|e| ServerError::Internal(format!("password verification failed: {e}")))?;
+
+    if !valid {
+        tracing::warn!(user_id = %user_id, "failed re-authentication");
+        return Err(ServerError::InvalidCredentials);
+    }
+    Ok(())
+}
+
+/// Tokens for a new session
+pub(crate) struct NewSession {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+/// Start a session with random 256-bit session and refresh tokens.
+pub(crate) async fn create_session(
+    state: &AppState,
+    user_id: Uuid,
+    user_agent: Option<String>,
+    ip: IpAddr,
+) -> Result<NewSession> {
+    let token = random_token();
+    let refresh_token = random_token();
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO sessions \
+         (user_id, token, expires_at, refresh_hash, refresh_expires_at, last_used_at, user_agent, ip) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(user_id)
+    .bind(&token)
+    .bind(now + Duration::days(state.session_expiry_days))
+    .bind(hash_token(&refresh_token))
+    .bind(now + Duration::days(state.refresh_expiry_days))
+    .bind(now)
+    .bind(user_agent)
+    .bind(ip.to_string())
+    .execute(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+    sqlx::query("UPDATE users SET last_active_at = $1 WHERE id = $2")
+        .bind(now)
+        .bind(user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    Ok(NewSession {
+        token,
+        refresh_token,
+    })
 }
 
 /// Validate registration input fields.
@@ -202,13 +670,18 @@ fn validate_registration(req: &RegisterRequest) -> Result<()> {
         ));
     }
 
-    if req.password.len() < 8 {
+    validate_password(&req.password)
+}
+
+/// Validate a new password's length.
+pub(crate) fn validate_password(password: &str) -> Result<()> {
+    if password.len() < 8 {
         return Err(ServerError::Validation(
             "password must be at least 8 characters".to_string(),
         ));
     }
 
-    if req.password.len() > 1024 {
+    if password.len() > 1024 {
         return Err(ServerError::Validation(
             "password must be at most 1024 characters".to_string(),
         ));
@@ -216,3 +689,15 @@ fn validate_registration(req: &RegisterRequest) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_password_checks_length() {
+        assert!(validate_password("short").is_err());
+        assert!(validate_password("long enough").is_ok());
+        assert!(validate_password(&"x".repeat(1025)).is_err());
+    }
+}
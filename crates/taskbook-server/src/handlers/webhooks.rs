@@ -0,0 +1,198 @@
+//! Managing webhooks: URLs sent a signed payload when the user's items,
+//! archive or shared boards change (see [`crate::webhooks`]).
+//!
+//! Like API tokens, webhooks are managed with a login session only.
+
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::random_token;
+use crate::error::{Result, ServerError};
+use crate::middleware::AuthUser;
+use crate::router::AppState;
+use crate::webhooks::{self, Changes, Event, Payload};
+
+/// Starts every webhook secret
+pub const SECRET_PREFIX: &str = "whsec_";
+
+/// Most webhooks an account may have
+const MAX_WEBHOOKS: i64 = 10;
+
+#[derive(Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+}
+
+#[derive(Serialize)]
+pub struct CreateWebhookResponse {
+    pub id: Uuid,
+    pub url: String,
+    /// Signs the payloads; shown this once
+    pub secret: String,
+}
+
+#[derive(Serialize)]
+pub struct WebhookInfo {
+    pub id: Uuid,
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+    pub last_delivery_at: Option<DateTime<Utc>>,
+    /// HTTP status the receiver last answered with
+    pub last_status: Option<i32>,
+    /// Why the last delivery failed, if it did
+    pub last_error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct WebhooksResponse {
+    pub webhooks: Vec<WebhookInfo>,
+}
+
+#[derive(Serialize)]
+pub struct TestWebhookResponse {
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+type WebhookRow = (
+    Uuid,
+    String,
+    DateTime<Utc>,
+    Option<DateTime<Utc>>,
+    Option<i32>,
+    Option<String>,
+);
+
+#[tracing::instrument(skip(state, req))]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<CreateWebhookRequest>,
+) -> Result<Json<CreateWebhookResponse>> {
+    let url = state
+        .webhooks
+        .check_url(req.url.trim())
+        .map_err(ServerError::Validation)?
+        .to_string();
+
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM webhooks WHERE user_id = $1")
+        .bind(auth.user_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+    if count >= MAX_WEBHOOKS {
+        return Err(ServerError::Validation(format!(
+            "an account can have at most {MAX_WEBHOOKS} webhooks"
+        )));
+    }
+
+    let secret = format!("{SECRET_PREFIX}{}", random_token());
+    let id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO webhooks (user_id, url, secret) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(auth.user_id)
+    .bind(&url)
+    .bind(&secret)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    tracing::info!(user_id = %auth.user_id, webhook_id = %id, "webhook created");
+
+    Ok(Json(CreateWebhookResponse { id, url, secret }))
+}
+
+#[tracing::instrument(skip(state))]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<WebhooksResponse>> {
+    let rows = sqlx::query_as::<_, WebhookRow>(
+        "SELECT id, url, created_at, last_delivery_at, last_status, last_error FROM webhooks \
+         WHERE user_id = $1 ORDER BY created_at",
+    )
+    .bind(auth.user_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    let webhooks = rows
+        .into_iter()
+        .map(
+            |(id, url, created_at, last_delivery_at, last_status, last_error)| WebhookInfo {
+                id,
+                url,
+                created_at,
+                last_delivery_at,
+                last_status,
+                last_error,
+            },
+        )
+        .collect();
+
+    Ok(Json(WebhooksResponse { webhooks }))
+}
+
+#[tracing::instrument(skip(state))]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(webhook_id): Path<Uuid>,
+) -> Result<()> {
+    let result = sqlx::query("DELETE FROM webhooks WHERE id = $1 AND user_id = $2")
+        .bind(webhook_id)
+        .bind(auth.user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServerError::NotFound("webhook not found".to_string()));
+    }
+
+    tracing::info!(user_id = %auth.user_id, webhook_id = %webhook_id, "webhook deleted");
+
+    Ok(())
+}
+
+/// Send a `ping` to a webhook now, and report how the receiver answered.
+#[tracing::instrument(skip(state))]
+pub async fn test_webhook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(webhook_id): Path<Uuid>,
+) -> Result<Json<TestWebhookResponse>> {
+    let (url, secret, username) = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT w.url, w.secret, u.username FROM webhooks w \
+         JOIN users u ON u.id = w.user_id WHERE w.id = $1 AND w.user_id = $2",
+    )
+    .bind(webhook_id)
+    .bind(auth.user_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(ServerError::Database)?
+    .ok_or_else(|| ServerError::NotFound("webhook not found".to_string()))?;
+
+    let payload = Payload::new(Event::Ping, username, Changes::default());
+    let body = serde_json::to_vec(&payload)
+        .map_err(|e| ServerError::Internal(format!("cannot encode payload: {e}")))?;
+    let delivery = state
+        .webhooks
+        .deliver(&url, &secret, Event::Ping, &body)
+        .await;
+    webhooks::record(&state.pool, webhook_id, &delivery).await;
+
+    Ok(Json(match delivery {
+        Ok(status) => TestWebhookResponse {
+            status: Some(status),
+            error: None,
+        },
+        Err(error) => TestWebhookResponse {
+            status: None,
+            error: Some(error),
+        },
+    }))
+}
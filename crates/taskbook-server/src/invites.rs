@@ -0,0 +1,143 @@
+//! Invite codes for servers that only let invited people register.
+//!
+//! Codes are created by whoever runs the server, with `tb-server invite`,
+//! and used up by registering with them.
+
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+
+use crate::db::{DbConnection, DbPool};
+use crate::error::{Result, ServerError};
+
+/// Characters in a code; no 0/O, 1/I/L, so codes can be read out and typed
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+/// Groups of four characters in a code
+const CODE_GROUPS: usize = 4;
+
+/// Generate a code such as `K7QM-2XPA-HV9C-TR4E`.
+pub fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_GROUPS)
+        .map(|_| {
+            (0..4)
+                .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Accept codes typed in lower case or with stray spaces.
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// Use up one use of `code`, in the transaction that creates the account.
+pub async fn redeem(conn: &mut DbConnection, code: &str) -> Result<()> {
+    let result = sqlx::query(
+        "UPDATE invites SET uses = uses + 1 \
+         WHERE code = $1 AND uses < max_uses AND (expires_at IS NULL OR expires_at > $2)",
+    )
+    .bind(normalize_code(code))
+    .bind(Utc::now())
+    .execute(conn)
+    .await
+    .map_err(ServerError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServerError::Forbidden(
+            "invalid, used up or expired invite code".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// `tb-server invite create`
+pub async fn create(
+    pool: &DbPool,
+    uses: i32,
+    expires_days: Option<i64>,
+    note: Option<&str>,
+) -> Result<()> {
+    if uses < 1 {
+        return Err(ServerError::Validation(
+            "an invite needs at least one use".to_string(),
+        ));
+    }
+    let code = generate_code();
+    let expires_at = expires_days.map(|days| Utc::now() + Duration::days(days));
+
+    sqlx::query("INSERT INTO invites (code, note, max_uses, expires_at) VALUES ($1, $2, $3, $4)")
+        .bind(&code)
+        .bind(note)
+        .bind(uses)
+        .bind(expires_at)
+        .execute(pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    println!("{code}");
+    Ok(())
+}
+
+/// `tb-server invite list`
+pub async fn list(pool: &DbPool) -> Result<()> {
+    let invites = sqlx::query_as::<_, (String, Option<String>, i32, i32, Option<DateTime<Utc>>)>(
+        "SELECT code, note, max_uses, uses, expires_at FROM invites ORDER BY created_at",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(ServerError::Database)?;
+
+    if invites.is_empty() {
+        println!("No invites.");
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    for (code, note, max_uses, uses, expires_at) in invites {
+        let state = match expires_at {
+            _ if uses >= max_uses => "used up".to_string(),
+            Some(at) if at <= now => "expired".to_string(),
+            Some(at) => format!("expires {}", at.format("%Y-%m-%d")),
+            None => "no expiry".to_string(),
+        };
+        println!(
+            "{code}  {uses}/{max_uses} used  {state}{}",
+            note.map(|n| format!("  {n}")).unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// `tb-server invite revoke <code>`
+pub async fn revoke(pool: &DbPool, code: &str) -> Result<()> {
+    let result = sqlx::query("DELETE FROM invites WHERE code = $1")
+        .bind(normalize_code(code))
+        .execute(pool)
+        .await
+        .map_err(ServerError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServerError::NotFound(format!("no invite {code}")));
+    }
+    println!("Invite {} revoked.", normalize_code(code));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_codes_are_four_groups_of_four() {
+        let code = generate_code();
+        let groups: Vec<&str> = code.split('-').collect();
+        assert_eq!(groups.len(), CODE_GROUPS);
+        assert!(groups
+            .iter()
+            .all(|g| g.len() == 4 && g.bytes().all(|c| CODE_ALPHABET.contains(&c))));
+        assert_eq!(normalize_code(&format!(" {} ", code.to_lowercase())), code);
+    }
+}
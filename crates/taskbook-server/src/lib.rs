@@ -0,0 +1,56 @@
+//! The taskbook sync server. `tb-server` runs it as configured by
+//! environment variables. With the `sqlite` feature instead of `postgres`,
+//! it stores everything in one SQLite file; that build has
+//! `tb-server-sqlite` and [`embedded`], which runs a single-user server
+//! inside another program, such as `tb serve`.
+
+pub mod admin;
+pub mod auth;
+pub mod cli;
+pub mod config;
+pub mod db;
+pub mod error;
+pub mod fanout;
+pub mod handlers;
+pub mod invites;
+pub mod mailer;
+pub mod metrics_middleware;
+pub mod middleware;
+pub mod oidc;
+pub mod quota;
+pub mod rate_limit;
+pub mod router;
+pub mod telemetry;
+pub mod totp;
+pub mod webhooks;
+
+#[cfg(feature = "sqlite")]
+pub mod embedded;
+
+#[cfg(test)]
+mod testing;
+
+/// Resolves on Ctrl+C or SIGTERM, to shut the server down gracefully
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("received Ctrl+C, shutting down"),
+        _ = terminate => tracing::info!("received SIGTERM, shutting down"),
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::{SmtpConfig, SmtpTls};
+
+/// Sends account emails over SMTP.
+#[derive(Clone)]
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    /// Base URL for links in emails, without a trailing slash
+    public_url: Arc<str>,
+}
+
+impl Mailer {
+    pub fn new(config: &SmtpConfig) -> Result<Self, String> {
+        let from: Mailbox = config
+            .from
+            .parse()
+            .map_err(|e| format!("TB_SMTP_FROM is not a valid address: {e}"))?;
+
+        let builder = match config.tls {
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host),
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host),
+            SmtpTls::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                &config.host,
+            )),
+        }
+        .map_err(|e| format!("invalid SMTP host: {e}"))?;
+
+        let builder = builder.port(config.port);
+        let builder = match &config.credentials {
+            Some((username, password)) => {
+                builder.credentials(Credentials::new(username.clone(), password.clone()))
+            }
+            None => builder,
+        };
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+            public_url: config.public_url.as_str().into(),
+        })
+    }
+
+    /// A link to `path` on the server's public URL
+    pub fn link(&self, path: &str) -> String {
+        format!("{}{}", self.public_url, path)
+    }
+
+    /// Send a plain-text email
+    pub async fn send(&self, to: &str, subject: &str, body: String) -> Result<(), String> {
+        let to: Mailbox = to.parse().map_err(|e| format!("invalid recipient: {e}"))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)
+            .map_err(|e| format!("failed to build email: {e}"))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("failed to send email: {e}"))
+    }
+
+    /// Send an email in the background, logging failures. Callers respond
+    /// at once, so response times do not depend on the mail server.
+    pub fn send_later(&self, to: String, subject: &'static str, body: String) {
+        let mailer = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = mailer.send(&to, subject, body).await {
+                tracing::error!(error = %e, "email not sent");
+            }
+        });
+    }
+}
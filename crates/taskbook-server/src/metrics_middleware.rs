@@ -3,22 +3,38 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Instant;
 
+use axum::extract::MatchedPath;
 use axum::http::{Request, Response};
 use opentelemetry::metrics::{Counter, Histogram, UpDownCounter};
 use opentelemetry::{global, KeyValue};
 use tower::{Layer, Service};
 
+use crate::error::ErrorKind;
+
+/// Histogram buckets in seconds, as recommended for
+/// `http.server.request.duration` by the OpenTelemetry semantic conventions.
+/// (The SDK's default buckets are meant for milliseconds.)
+const DURATION_BUCKETS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 7.5, 10.0,
+];
+
 /// Tower [`Layer`] that records HTTP request metrics via OpenTelemetry.
 ///
 /// Recorded instruments:
 /// - `http.server.request.count` — counter by method, route, status
 /// - `http.server.request.duration` — histogram (seconds) by method, route, status
 /// - `http.server.active_requests` — up-down counter by method, route
+/// - `http.server.request.errors` — counter of 4xx and 5xx responses by
+///   method, route, status and `error.type`
+///
+/// The route is the pattern it matched, such as `/api/v1/items/:key/history`,
+/// so tokens and keys in paths stay out of the metrics.
 #[derive(Clone)]
 pub struct HttpMetricsLayer {
     request_count: Counter<u64>,
     request_duration: Histogram<f64>,
     active_requests: UpDownCounter<i64>,
+    request_errors: Counter<u64>,
 }
 
 impl HttpMetricsLayer {
@@ -34,6 +50,7 @@ impl HttpMetricsLayer {
             .f64_histogram("http.server.request.duration")
             .with_description("HTTP request duration in seconds")
             .with_unit("s")
+            .with_boundaries(DURATION_BUCKETS.to_vec())
             .build();
 
         let active_requests = meter
@@ -41,14 +58,26 @@ impl HttpMetricsLayer {
             .with_description("Number of in-flight HTTP requests")
             .build();
 
+        let request_errors = meter
+            .u64_counter("http.server.request.errors")
+            .with_description("HTTP requests answered with a 4xx or 5xx status")
+            .build();
+
         Self {
             request_count,
             request_duration,
             active_requests,
+            request_errors,
         }
     }
 }
 
+impl Default for HttpMetricsLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<S> Layer<S> for HttpMetricsLayer {
     type Service = HttpMetricsService<S>;
 
@@ -58,6 +87,7 @@ impl<S> Layer<S> for HttpMetricsLayer {
             request_count: self.request_count.clone(),
             request_duration: self.request_duration.clone(),
             active_requests: self.active_requests.clone(),
+            request_errors: self.request_errors.clone(),
         }
     }
 }
@@ -68,6 +98,7 @@ pub struct HttpMetricsService<S> {
     request_count: Counter<u64>,
     request_duration: Histogram<f64>,
     active_requests: UpDownCounter<i64>,
+    request_errors: Counter<u64>,
 }
 
 impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for HttpMetricsService<S>
@@ -87,7 +118,7 @@ where
 
     fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
         let method = req.method().to_string();
-        let path = normalize_path(req.uri().path());
+        let path = route(&req);
 
         let active_attrs = vec![
             KeyValue::new("http.request.method", method.clone()),
@@ -98,6 +129,7 @@ where
         let request_count = self.request_count.clone();
         let request_duration = self.request_duration.clone();
         let active_requests = self.active_requests.clone();
+        let request_errors = self.request_errors.clone();
 
         let mut inner = self.inner.clone();
         let start = Instant::now();
@@ -108,30 +140,97 @@ where
             let elapsed = start.elapsed().as_secs_f64();
             active_requests.add(-1, &active_attrs);
 
-            let status = match &result {
-                Ok(resp) => resp.status().as_u16().to_string(),
-                Err(_) => "500".to_string(),
+            let (status, error_type) = match &result {
+                Ok(resp) => (resp.status().as_u16(), error_type(resp)),
+                Err(_) => (500, Some("_OTHER".to_string())),
             };
 
-            let attrs = vec![
+            let mut attrs = vec![
                 KeyValue::new("http.request.method", method),
                 KeyValue::new("http.route", path),
-                KeyValue::new("http.response.status_code", status),
+                KeyValue::new("http.response.status_code", status.to_string()),
             ];
 
             request_count.add(1, &attrs);
             request_duration.record(elapsed, &attrs);
+            if let Some(error_type) = error_type {
+                attrs.push(KeyValue::new("error.type", error_type));
+                request_errors.add(1, &attrs);
+            }
 
             result
         })
     }
 }
 
-/// Normalize the request path for use as a metric attribute.
-///
-/// The current API has no path parameters, so paths are used as-is.
-/// This stub exists for future-proofing — add normalization here if
-/// parameterised routes (e.g. `/items/:id`) are introduced later.
-fn normalize_path(path: &str) -> String {
-    path.to_string()
+/// The route pattern the request matched, or `unmatched` for paths no route
+/// serves (which would otherwise add a series per path)
+fn route<B>(req: &Request<B>) -> String {
+    req.extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string())
+}
+
+/// What went wrong, for responses with an error status: the kind of
+/// [`ServerError`](crate::error::ServerError) if a handler returned one,
+/// else the status code (as for a body rejected by an extractor).
+fn error_type<B>(resp: &Response<B>) -> Option<String> {
+    let status = resp.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return None;
+    }
+    Some(match resp.extensions().get::<ErrorKind>() {
+        Some(ErrorKind(kind)) => kind.to_string(),
+        None => status.as_u16().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{HeaderValue, StatusCode};
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::error::ServerError;
+
+    /// Echoes the route label in a header
+    async fn label(req: Request<Body>, next: axum::middleware::Next) -> axum::response::Response {
+        let route = route(&req);
+        let mut resp = next.run(req).await;
+        resp.headers_mut()
+            .insert("x-route", HeaderValue::from_str(&route).unwrap());
+        resp
+    }
+
+    #[tokio::test]
+    async fn routes_are_labelled_by_pattern() {
+        let router = Router::new()
+            .route("/api/v1/items/:key/history", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn(label));
+
+        for (path, expected) in [
+            ("/api/v1/items/3f2a/history", "/api/v1/items/:key/history"),
+            ("/s/secret-token", "unmatched"),
+        ] {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            let resp = router.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.headers()["x-route"], expected);
+        }
+    }
+
+    #[test]
+    fn errors_are_typed_by_kind_or_status() {
+        let resp = ServerError::NotFound("item not found".to_string()).into_response();
+        assert_eq!(error_type(&resp).as_deref(), Some("not_found"));
+
+        let resp = StatusCode::UNPROCESSABLE_ENTITY.into_response();
+        assert_eq!(error_type(&resp).as_deref(), Some("422"));
+
+        assert_eq!(error_type(&StatusCode::OK.into_response()), None);
+    }
 }
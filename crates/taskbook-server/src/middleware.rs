@@ -1,16 +1,137 @@
 use axum::extract::FromRequestParts;
 use axum::http::request::Parts;
 use axum::http::HeaderMap;
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 use uuid::Uuid;
 
+use crate::auth::hash_token;
+use crate::db;
 use crate::error::ServerError;
+use crate::handlers::tokens::{Scope, API_TOKEN_PREFIX};
 use crate::router::AppState;
 
+/// How stale a session's or API token's `last_used_at` may get before a
+/// request updates it (and pushes back a session's expiry). Keeps most
+/// requests free of a write.
+const LAST_USED_RESOLUTION_MINUTES: i64 = 5;
+
 /// Extracted from the Authorization header after middleware validation.
+/// Only a login session gets one; API tokens are for the endpoints that take
+/// [`ReadAccess`] or [`WriteAccess`].
 #[derive(Debug, Clone)]
 pub struct AuthUser {
     pub user_id: Uuid,
+    /// The session the request was made with
+    pub session_id: Uuid,
+}
+
+/// A user allowed to read their data: with a session or any API token.
+#[derive(Debug, Clone)]
+pub struct ReadAccess {
+    pub user_id: Uuid,
+    /// Whether the caller could also change the data, as with [`WriteAccess`]
+    pub can_write: bool,
+}
+
+/// A user allowed to change their data: with a session or a `write` token.
+#[derive(Debug, Clone)]
+pub struct WriteAccess {
+    pub user_id: Uuid,
+}
+
+/// Who a request's bearer token belongs to
+enum Caller {
+    Session(AuthUser),
+    Token { user_id: Uuid, scope: Scope },
+}
+
+async fn authenticate(parts: &Parts, state: &AppState) -> Result<Caller, ServerError> {
+    let token = extract_bearer_token(&parts.headers).ok_or(ServerError::Unauthorized)?;
+    let now = Utc::now();
+
+    if token.starts_with(API_TOKEN_PREFIX) {
+        let (token_id, user_id, scope, last_used_at) =
+            sqlx::query_as::<_, (Uuid, Uuid, String, Option<DateTime<Utc>>)>(
+                "SELECT t.id, t.user_id, t.scope, t.last_used_at FROM api_tokens t \
+                 JOIN users u ON u.id = t.user_id \
+                 WHERE t.token_hash = $1 AND (t.expires_at IS NULL OR t.expires_at > $2) \
+                   AND u.disabled_at IS NULL",
+            )
+            .bind(hash_token(&token))
+            .bind(now)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(ServerError::Database)?
+            .ok_or(ServerError::Unauthorized)?;
+        let scope = Scope::parse(&scope)
+            .ok_or_else(|| ServerError::Internal(format!("unknown token scope {scope}")))?;
+
+        if is_stale(last_used_at, now) {
+            sqlx::query("UPDATE api_tokens SET last_used_at = $1 WHERE id = $2")
+                .bind(now)
+                .bind(token_id)
+                .execute(&state.pool)
+                .await
+                .map_err(ServerError::Database)?;
+            mark_active(state, user_id, now).await?;
+        }
+        return Ok(Caller::Token { user_id, scope });
+    }
+
+    let (session_id, user_id, last_used_at) =
+        sqlx::query_as::<_, (Uuid, Uuid, Option<DateTime<Utc>>)>(
+            "SELECT id, user_id, last_used_at FROM sessions \
+             WHERE token = $1 AND expires_at > $2",
+        )
+        .bind(&token)
+        .bind(now)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(ServerError::Database)?
+        .ok_or(ServerError::Unauthorized)?;
+
+    if is_stale(last_used_at, now) {
+        // Sliding expiry: a session in use does not run out
+        sqlx::query(&format!(
+            "UPDATE sessions SET last_used_at = $1, expires_at = {}, refresh_expires_at = {} \
+             WHERE id = $4",
+            db::greatest("expires_at", "$2"),
+            db::greatest("refresh_expires_at", "$3")
+        ))
+        .bind(now)
+        .bind(now + Duration::days(state.session_expiry_days))
+        .bind(now + Duration::days(state.refresh_expiry_days))
+        .bind(session_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+        mark_active(state, user_id, now).await?;
+    }
+
+    Ok(Caller::Session(AuthUser {
+        user_id,
+        session_id,
+    }))
+}
+
+fn is_stale(last_used_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    last_used_at.map_or(true, |t| {
+        now - t > Duration::minutes(LAST_USED_RESOLUTION_MINUTES)
+    })
+}
+
+async fn mark_active(
+    state: &AppState,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<(), ServerError> {
+    sqlx::query("UPDATE users SET last_active_at = $1 WHERE id = $2")
+        .bind(now)
+        .bind(user_id)
+        .execute(&state.pool)
+        .await
+        .map_err(ServerError::Database)?;
+    Ok(())
 }
 
 impl FromRequestParts<AppState> for AuthUser {
@@ -28,20 +149,110 @@ impl FromRequestParts<AppState> for AuthUser {
         Self: 'c,
     {
         Box::pin(async move {
-            let headers = &parts.headers;
-            let token = extract_bearer_token(headers).ok_or(ServerError::Unauthorized)?;
+            match authenticate(parts, state).await? {
+                Caller::Session(auth) => Ok(auth),
+                Caller::Token { .. } => Err(ServerError::Forbidden(
+                    "API tokens cannot be used for this; log in instead".to_string(),
+                )),
+            }
+        })
+    }
+}
 
-            let session = sqlx::query_as::<_, (Uuid,)>(
-                "SELECT user_id FROM sessions WHERE token = $1 AND expires_at > $2",
-            )
-            .bind(&token)
-            .bind(Utc::now())
-            .fetch_optional(&state.pool)
-            .await
-            .map_err(ServerError::Database)?
-            .ok_or(ServerError::Unauthorized)?;
+impl FromRequestParts<AppState> for ReadAccess {
+    type Rejection = ServerError;
+
+    fn from_request_parts<'a, 'b, 'c>(
+        parts: &'a mut Parts,
+        state: &'b AppState,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self, Self::Rejection>> + Send + 'c>,
+    >
+    where
+        'a: 'c,
+        'b: 'c,
+        Self: 'c,
+    {
+        Box::pin(async move {
+            Ok(match authenticate(parts, state).await? {
+                Caller::Session(auth) => ReadAccess {
+                    user_id: auth.user_id,
+                    can_write: true,
+                },
+                Caller::Token { user_id, scope } => ReadAccess {
+                    user_id,
+                    can_write: scope == Scope::Write,
+                },
+            })
+        })
+    }
+}
+
+impl FromRequestParts<AppState> for WriteAccess {
+    type Rejection = ServerError;
+
+    fn from_request_parts<'a, 'b, 'c>(
+        parts: &'a mut Parts,
+        state: &'b AppState,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self, Self::Rejection>> + Send + 'c>,
+    >
+    where
+        'a: 'c,
+        'b: 'c,
+        Self: 'c,
+    {
+        Box::pin(async move {
+            let user_id = match authenticate(parts, state).await? {
+                Caller::Session(auth) => auth.user_id,
+                Caller::Token {
+                    user_id,
+                    scope: Scope::Write,
+                } => user_id,
+                Caller::Token { .. } => {
+                    return Err(ServerError::Forbidden(
+                        "this API token is read-only".to_string(),
+                    ))
+                }
+            };
+            Ok(WriteAccess { user_id })
+        })
+    }
+}
+
+/// An authenticated user with the admin role.
+#[derive(Debug, Clone)]
+pub struct AdminUser(pub AuthUser);
+
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = ServerError;
 
-            Ok(AuthUser { user_id: session.0 })
+    fn from_request_parts<'a, 'b, 'c>(
+        parts: &'a mut Parts,
+        state: &'b AppState,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self, Self::Rejection>> + Send + 'c>,
+    >
+    where
+        'a: 'c,
+        'b: 'c,
+        Self: 'c,
+    {
+        Box::pin(async move {
+            let auth = AuthUser::from_request_parts(parts, state).await?;
+
+            let is_admin =
+                sqlx::query_scalar::<_, bool>("SELECT is_admin FROM users WHERE id = $1")
+                    .bind(auth.user_id)
+                    .fetch_one(&state.pool)
+                    .await
+                    .map_err(ServerError::Database)?;
+            if !is_admin {
+                tracing::warn!(user_id = %auth.user_id, "admin endpoint used without admin role");
+                return Err(ServerError::Forbidden("admins only".to_string()));
+            }
+
+            Ok(AdminUser(auth))
         })
     }
 }
@@ -50,3 +261,10 @@ fn extract_bearer_token(headers: &HeaderMap) -> Option<String> {
     let value = headers.get("authorization")?.to_str().ok()?;
     value.strip_prefix("Bearer ").map(|token| token.to_string())
 }
+
+/// The request's `User-Agent`, for labelling the session it starts
+pub fn user_agent(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get("user-agent")?.to_str().ok()?;
+    // Enough to recognise a client; nobody needs a kilobyte of it
+    Some(value.chars().take(256).collect())
+}
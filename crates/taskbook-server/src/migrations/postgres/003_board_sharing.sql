@@ -0,0 +1,30 @@
+-- X25519 public key derived from the user's encryption key, used by other
+-- users to wrap the keys of boards they share with this one
+ALTER TABLE users ADD COLUMN public_key BYTEA;
+
+CREATE TABLE shared_boards (
+    id          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    owner_id    UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- The board's name and key, wrapped for each member (the owner included)
+CREATE TABLE board_members (
+    board_id    UUID NOT NULL REFERENCES shared_boards(id) ON DELETE CASCADE,
+    user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    wrapped_key BYTEA NOT NULL,
+    can_write   BOOLEAN NOT NULL DEFAULT false,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (board_id, user_id)
+);
+
+CREATE INDEX idx_board_members_user ON board_members(user_id);
+
+CREATE TABLE board_items (
+    board_id    UUID NOT NULL REFERENCES shared_boards(id) ON DELETE CASCADE,
+    item_key    VARCHAR(64) NOT NULL,
+    data        BYTEA NOT NULL,
+    nonce       BYTEA NOT NULL,
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (board_id, item_key)
+);
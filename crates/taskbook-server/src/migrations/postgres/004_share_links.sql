@@ -0,0 +1,12 @@
+-- Public read-only links to a snapshot of a board. The snapshot is encrypted
+-- with a key that only travels in the fragment of the link's URL.
+CREATE TABLE share_links (
+    token       VARCHAR(64) PRIMARY KEY,
+    user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    data        BYTEA NOT NULL,
+    nonce       BYTEA NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at  TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_share_links_user ON share_links(user_id);
@@ -0,0 +1,5 @@
+-- Where each session was started and when it was last used, so users can
+-- tell their devices apart and revoke them one by one
+ALTER TABLE sessions ADD COLUMN last_used_at TIMESTAMPTZ;
+ALTER TABLE sessions ADD COLUMN user_agent TEXT;
+ALTER TABLE sessions ADD COLUMN ip TEXT;
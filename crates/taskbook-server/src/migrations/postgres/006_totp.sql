@@ -0,0 +1,7 @@
+-- Two-factor authentication with time-based one-time passwords (RFC 6238).
+-- The secret is stored on enrollment and only required at login once the
+-- user has confirmed it with a code. The last accepted time step stops a
+-- code from being used twice.
+ALTER TABLE users ADD COLUMN totp_secret BYTEA;
+ALTER TABLE users ADD COLUMN totp_enabled BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE users ADD COLUMN totp_last_step BIGINT;
@@ -0,0 +1,14 @@
+-- Email verification and password reset. Tokens are sent by email and
+-- only their SHA-256 hash is stored, like a password.
+ALTER TABLE users ADD COLUMN email_verified BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE email_tokens (
+    token_hash  BYTEA PRIMARY KEY,
+    user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose     VARCHAR(16) NOT NULL,   -- 'verify' or 'reset'
+    email       VARCHAR(255) NOT NULL,  -- Address the token was sent to
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at  TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_email_tokens_user ON email_tokens(user_id, purpose);
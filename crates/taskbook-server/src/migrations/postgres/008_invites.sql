@@ -0,0 +1,9 @@
+-- Invite codes, for servers with TB_REGISTRATION_MODE=invite
+CREATE TABLE invites (
+    code        VARCHAR(64) PRIMARY KEY,
+    note        TEXT,                   -- Who the code is for, to tell codes apart
+    max_uses    INTEGER NOT NULL DEFAULT 1,
+    uses        INTEGER NOT NULL DEFAULT 0,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at  TIMESTAMPTZ             -- NULL means it does not expire
+);
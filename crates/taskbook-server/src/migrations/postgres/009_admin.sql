@@ -0,0 +1,10 @@
+-- Administration: an admin role, disabled accounts, and when each account
+-- was last active, for purging ones that are no longer used
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE users ADD COLUMN disabled_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN last_active_at TIMESTAMPTZ;
+
+UPDATE users SET last_active_at = GREATEST(
+    created_at,
+    (SELECT MAX(COALESCE(s.last_used_at, s.created_at)) FROM sessions s WHERE s.user_id = users.id)
+);
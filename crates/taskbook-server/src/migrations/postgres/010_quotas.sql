@@ -0,0 +1,3 @@
+-- A storage limit set by an admin for one account; without one, the
+-- server's TB_STORAGE_QUOTA applies
+ALTER TABLE users ADD COLUMN quota_bytes BIGINT;
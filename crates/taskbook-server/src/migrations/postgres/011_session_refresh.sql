@@ -0,0 +1,4 @@
+-- Refresh tokens, which renew a session after its token expires. Only a
+-- hash is stored; each use replaces it.
+ALTER TABLE sessions ADD COLUMN refresh_hash BYTEA UNIQUE;
+ALTER TABLE sessions ADD COLUMN refresh_expires_at TIMESTAMPTZ;
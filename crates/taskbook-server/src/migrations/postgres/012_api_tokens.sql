@@ -0,0 +1,13 @@
+-- Personal API tokens, for scripts. Only a hash of each token is stored.
+CREATE TABLE api_tokens (
+    id           UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id      UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name         VARCHAR(64) NOT NULL,
+    token_hash   BYTEA UNIQUE NOT NULL,
+    scope        VARCHAR(16) NOT NULL,   -- 'read' or 'write'
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMPTZ,
+    expires_at   TIMESTAMPTZ
+);
+
+CREATE INDEX idx_api_tokens_user_id ON api_tokens(user_id);
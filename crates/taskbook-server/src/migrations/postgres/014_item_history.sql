@@ -0,0 +1,14 @@
+-- Earlier versions of items, kept when an upload changes them. The newest
+-- TB_ITEM_HISTORY revisions of each item are kept.
+CREATE TABLE item_revisions (
+    id          BIGSERIAL PRIMARY KEY,
+    user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    item_key    VARCHAR(64) NOT NULL,
+    data        BYTEA NOT NULL,
+    nonce       BYTEA NOT NULL,
+    archived    BOOLEAN NOT NULL,
+    saved_at    TIMESTAMPTZ NOT NULL,            -- when this version was uploaded
+    replaced_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_item_revisions_item ON item_revisions(user_id, item_key, id);
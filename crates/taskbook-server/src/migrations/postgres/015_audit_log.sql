@@ -0,0 +1,12 @@
+-- Security-relevant events on each account (logins, failed logins, password
+-- changes, session revocations, exports), listed by GET /api/v1/me/audit
+CREATE TABLE audit_log (
+    id          BIGSERIAL PRIMARY KEY,
+    user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event       VARCHAR(32) NOT NULL,
+    ip          TEXT,
+    user_agent  TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_audit_log_user ON audit_log(user_id, id);
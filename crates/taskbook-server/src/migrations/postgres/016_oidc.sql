@@ -0,0 +1,25 @@
+-- Single sign-on. Each provider account (issuer and subject) is linked to
+-- one taskbook account.
+CREATE TABLE oidc_identities (
+    issuer      TEXT NOT NULL,
+    subject     TEXT NOT NULL,
+    user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (issuer, subject)
+);
+
+CREATE INDEX idx_oidc_identities_user ON oidc_identities(user_id);
+
+-- Sign-ins in progress. The browser comes back with `state` and the CLI
+-- polls with its poll token; only hashes of both are stored.
+CREATE TABLE oidc_logins (
+    state_hash     BYTEA PRIMARY KEY,
+    poll_hash      BYTEA NOT NULL UNIQUE,
+    nonce          TEXT NOT NULL,
+    code_verifier  TEXT NOT NULL,
+    link_user_id   UUID REFERENCES users(id) ON DELETE CASCADE,  -- Set when linking
+    user_id        UUID REFERENCES users(id) ON DELETE CASCADE,  -- Set once signed in
+    created        BOOLEAN NOT NULL DEFAULT false,               -- The account is new
+    error          TEXT,
+    expires_at     TIMESTAMPTZ NOT NULL
+);
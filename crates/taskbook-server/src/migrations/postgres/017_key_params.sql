@@ -0,0 +1,3 @@
+-- How the client derives the encryption key from a passphrase (JSON, opaque
+-- to the server); NULL while the account uses a random key
+ALTER TABLE users ADD COLUMN key_params TEXT;
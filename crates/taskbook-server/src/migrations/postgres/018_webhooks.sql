@@ -0,0 +1,14 @@
+-- URLs sent a signed JSON payload when the user's items change. The secret
+-- signs the payloads, so unlike tokens it is stored as it is.
+CREATE TABLE webhooks (
+    id               UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id          UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url              TEXT NOT NULL,
+    secret           VARCHAR(64) NOT NULL,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_delivery_at TIMESTAMPTZ,
+    last_status      INTEGER,   -- HTTP status of the last delivery
+    last_error       TEXT       -- why the last delivery failed, if it did
+);
+
+CREATE INDEX idx_webhooks_user_id ON webhooks(user_id);
@@ -0,0 +1,17 @@
+-- Items left out of an upload to a shared board go to the board's trash,
+-- and the versions an upload replaces are kept, as for a user's own items.
+ALTER TABLE board_items ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_board_items_trash ON board_items(board_id, deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE TABLE board_item_revisions (
+    id          BIGSERIAL PRIMARY KEY,
+    board_id    UUID NOT NULL REFERENCES shared_boards(id) ON DELETE CASCADE,
+    item_key    VARCHAR(64) NOT NULL,
+    data        BYTEA NOT NULL,
+    nonce       BYTEA NOT NULL,
+    saved_at    TIMESTAMPTZ NOT NULL,            -- when this version was uploaded
+    replaced_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_board_item_revisions_item ON board_item_revisions(board_id, item_key, id);
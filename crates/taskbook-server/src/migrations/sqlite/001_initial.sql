@@ -0,0 +1,31 @@
+-- SQLite has no UUID or TIMESTAMPTZ types: UUIDs are stored as 16-byte
+-- blobs and timestamps as RFC 3339 text in UTC, which sorts by time.
+CREATE TABLE users (
+    id          BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    username    VARCHAR(64) UNIQUE NOT NULL,
+    email       VARCHAR(255) UNIQUE NOT NULL,
+    password    TEXT NOT NULL,
+    created_at  TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE sessions (
+    id          BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    user_id     BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token       VARCHAR(128) UNIQUE NOT NULL,
+    created_at  TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    expires_at  TEXT NOT NULL
+);
+
+CREATE TABLE items (
+    id          BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    user_id     BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    item_key    VARCHAR(64) NOT NULL,
+    data        BLOB NOT NULL,
+    nonce       BLOB NOT NULL,
+    archived    BOOLEAN NOT NULL DEFAULT false,
+    created_at  TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at  TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    UNIQUE(user_id, item_key, archived)
+);
+
+CREATE INDEX idx_items_user ON items(user_id, archived);
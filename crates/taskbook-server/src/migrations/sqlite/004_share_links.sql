@@ -0,0 +1,12 @@
+-- Public read-only links to a snapshot of a board. The snapshot is encrypted
+-- with a key that only travels in the fragment of the link's URL.
+CREATE TABLE share_links (
+    token       VARCHAR(64) PRIMARY KEY,
+    user_id     BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    data        BLOB NOT NULL,
+    nonce       BLOB NOT NULL,
+    created_at  TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    expires_at  TEXT NOT NULL
+);
+
+CREATE INDEX idx_share_links_user ON share_links(user_id);
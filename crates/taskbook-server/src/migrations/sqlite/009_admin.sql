@@ -0,0 +1,13 @@
+-- Administration: an admin role, disabled accounts, and when each account
+-- was last active, for purging ones that are no longer used
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE users ADD COLUMN disabled_at TEXT;
+ALTER TABLE users ADD COLUMN last_active_at TEXT;
+
+UPDATE users SET last_active_at = MAX(
+    created_at,
+    COALESCE(
+        (SELECT MAX(COALESCE(s.last_used_at, s.created_at)) FROM sessions s WHERE s.user_id = users.id),
+        created_at
+    )
+);
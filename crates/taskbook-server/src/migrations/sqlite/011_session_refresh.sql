@@ -0,0 +1,7 @@
+-- Refresh tokens, which renew a session after its token expires. Only a
+-- hash is stored; each use replaces it.
+ALTER TABLE sessions ADD COLUMN refresh_hash BLOB;
+ALTER TABLE sessions ADD COLUMN refresh_expires_at TEXT;
+
+-- SQLite cannot add a UNIQUE column
+CREATE UNIQUE INDEX idx_sessions_refresh_hash ON sessions(refresh_hash);
@@ -0,0 +1,13 @@
+-- Personal API tokens, for scripts. Only a hash of each token is stored.
+CREATE TABLE api_tokens (
+    id           BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    user_id      BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name         VARCHAR(64) NOT NULL,
+    token_hash   BLOB UNIQUE NOT NULL,
+    scope        VARCHAR(16) NOT NULL,   -- 'read' or 'write'
+    created_at   TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    last_used_at TEXT,
+    expires_at   TEXT
+);
+
+CREATE INDEX idx_api_tokens_user_id ON api_tokens(user_id);
@@ -0,0 +1,5 @@
+-- Items left out of an upload are kept for a while as trash, so they can be
+-- restored, instead of being deleted at once.
+ALTER TABLE items ADD COLUMN deleted_at TEXT;
+
+CREATE INDEX idx_items_trash ON items(user_id, deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,14 @@
+-- Earlier versions of items, kept when an upload changes them. The newest
+-- TB_ITEM_HISTORY revisions of each item are kept.
+CREATE TABLE item_revisions (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id     BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    item_key    VARCHAR(64) NOT NULL,
+    data        BLOB NOT NULL,
+    nonce       BLOB NOT NULL,
+    archived    BOOLEAN NOT NULL,
+    saved_at    TEXT NOT NULL,                   -- when this version was uploaded
+    replaced_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX idx_item_revisions_item ON item_revisions(user_id, item_key, id);
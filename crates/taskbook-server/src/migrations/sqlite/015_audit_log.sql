@@ -0,0 +1,12 @@
+-- Security-relevant events on each account (logins, failed logins, password
+-- changes, session revocations, exports), listed by GET /api/v1/me/audit
+CREATE TABLE audit_log (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id     BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event       VARCHAR(32) NOT NULL,
+    ip          TEXT,
+    user_agent  TEXT,
+    created_at  TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX idx_audit_log_user ON audit_log(user_id, id);
@@ -0,0 +1,25 @@
+-- Single sign-on. Each provider account (issuer and subject) is linked to
+-- one taskbook account.
+CREATE TABLE oidc_identities (
+    issuer      TEXT NOT NULL,
+    subject     TEXT NOT NULL,
+    user_id     BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at  TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    PRIMARY KEY (issuer, subject)
+);
+
+CREATE INDEX idx_oidc_identities_user ON oidc_identities(user_id);
+
+-- Sign-ins in progress. The browser comes back with `state` and the CLI
+-- polls with its poll token; only hashes of both are stored.
+CREATE TABLE oidc_logins (
+    state_hash     BLOB PRIMARY KEY,
+    poll_hash      BLOB NOT NULL UNIQUE,
+    nonce          TEXT NOT NULL,
+    code_verifier  TEXT NOT NULL,
+    link_user_id   BLOB REFERENCES users(id) ON DELETE CASCADE,  -- Set when linking
+    user_id        BLOB REFERENCES users(id) ON DELETE CASCADE,  -- Set once signed in
+    created        BOOLEAN NOT NULL DEFAULT false,               -- The account is new
+    error          TEXT,
+    expires_at     TEXT NOT NULL
+);
@@ -0,0 +1,14 @@
+-- URLs sent a signed JSON payload when the user's items change. The secret
+-- signs the payloads, so unlike tokens it is stored as it is.
+CREATE TABLE webhooks (
+    id               BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    user_id          BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url              TEXT NOT NULL,
+    secret           VARCHAR(64) NOT NULL,
+    created_at       TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    last_delivery_at TEXT,
+    last_status      INTEGER,   -- HTTP status of the last delivery
+    last_error       TEXT       -- why the last delivery failed, if it did
+);
+
+CREATE INDEX idx_webhooks_user_id ON webhooks(user_id);
@@ -0,0 +1,278 @@
+//! Single sign-on through an OpenID Connect provider, with the
+//! authorization code flow and PKCE.
+//!
+//! The ID token is taken straight from the provider's token endpoint over
+//! TLS, authenticated with the client secret, so its signature is not
+//! checked again; its claims are (OpenID Connect Core 3.1.3.7).
+
+use std::sync::Arc;
+
+use base64::Engine as _;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+use crate::config::OidcConfig;
+
+/// The parts of the provider's discovery document that are used
+#[derive(Debug, Clone, Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// The `aud` claim: one client ID or several
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, client_id: &str) -> bool {
+        match self {
+            Audience::One(aud) => aud == client_id,
+            Audience::Many(auds) => auds.iter().any(|aud| aud == client_id),
+        }
+    }
+}
+
+/// Claims of an ID token
+#[derive(Debug, Deserialize)]
+pub struct Claims {
+    pub iss: String,
+    pub sub: String,
+    aud: Audience,
+    exp: i64,
+    #[serde(default)]
+    nonce: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    #[serde(default)]
+    pub preferred_username: Option<String>,
+}
+
+impl Claims {
+    /// Check that the token was issued by `issuer` to `client_id` for the
+    /// sign-in with `nonce`, and has not expired at `now`.
+    fn validate(&self, issuer: &str, client_id: &str, nonce: &str, now: i64) -> Result<(), String> {
+        if self.iss != issuer {
+            return Err(format!("ID token issued by {}, not {issuer}", self.iss));
+        }
+        if !self.aud.contains(client_id) {
+            return Err("ID token issued to another client".to_string());
+        }
+        if self.exp <= now {
+            return Err("ID token expired".to_string());
+        }
+        if self.nonce.as_deref() != Some(nonce) {
+            return Err("ID token is for another sign-in".to_string());
+        }
+        if self.sub.is_empty() {
+            return Err("ID token has no subject".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// An OpenID Connect provider, see [`OidcConfig`]. Its discovery document
+/// is fetched on first use.
+#[derive(Clone)]
+pub struct Provider {
+    config: Arc<OidcConfig>,
+    http: reqwest::Client,
+    discovery: Arc<OnceCell<Discovery>>,
+}
+
+impl Provider {
+    pub fn new(config: &OidcConfig) -> Self {
+        Self {
+            config: Arc::new(config.clone()),
+            http: reqwest::Client::new(),
+            discovery: Arc::default(),
+        }
+    }
+
+    /// Whether people signing in for the first time get an account
+    pub fn register(&self) -> bool {
+        self.config.register
+    }
+
+    async fn discovery(&self) -> Result<&Discovery, String> {
+        self.discovery
+            .get_or_try_init(|| async {
+                let url = discovery_document_url(&self.config.discovery_url);
+                self.http
+                    .get(&url)
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status)
+                    .map_err(|e| format!("failed to fetch {url}: {e}"))?
+                    .json::<Discovery>()
+                    .await
+                    .map_err(|e| format!("invalid discovery document at {url}: {e}"))
+            })
+            .await
+    }
+
+    /// Where to send the browser to sign in. `state` comes back with the
+    /// callback, `nonce` in the ID token.
+    pub async fn authorize_url(
+        &self,
+        state: &str,
+        nonce: &str,
+        code_verifier: &str,
+    ) -> Result<String, String> {
+        let discovery = self.discovery().await?;
+        let mut url = reqwest::Url::parse(&discovery.authorization_endpoint)
+            .map_err(|e| format!("invalid authorization endpoint: {e}"))?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_url)
+            .append_pair("scope", &self.config.scopes)
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", &pkce_challenge(code_verifier))
+            .append_pair("code_challenge_method", "S256");
+        Ok(url.into())
+    }
+
+    /// Exchange the callback's authorization code for the signed-in
+    /// person's validated claims.
+    pub async fn exchange(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> Result<Claims, String> {
+        let discovery = self.discovery().await?;
+        let resp = self
+            .http
+            .post(&discovery.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.config.redirect_url),
+                ("client_id", &self.config.client_id),
+                ("client_secret", &self.config.client_secret),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await
+            .map_err(|e| format!("token request failed: {e}"))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("token request failed with {status}: {body}"));
+        }
+        let token: TokenResponse = resp
+            .json()
+            .await
+            .map_err(|e| format!("invalid token response: {e}"))?;
+
+        let claims = decode_id_token(&token.id_token)?;
+        claims.validate(
+            &discovery.issuer,
+            &self.config.client_id,
+            nonce,
+            chrono::Utc::now().timestamp(),
+        )?;
+        Ok(claims)
+    }
+}
+
+/// The discovery document's URL, given it or the issuer's
+fn discovery_document_url(url: &str) -> String {
+    if url.ends_with("/.well-known/openid-configuration") {
+        url.to_string()
+    } else {
+        format!(
+            "{}/.well-known/openid-configuration",
+            url.trim_end_matches('/')
+        )
+    }
+}
+
+/// The PKCE `S256` challenge for `verifier` (RFC 7636)
+fn pkce_challenge(verifier: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// The claims in a JWT's payload
+fn decode_id_token(token: &str) -> Result<Claims, String> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| "ID token is not a JWT".to_string())?;
+    let json = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|e| format!("invalid ID token encoding: {e}"))?;
+    serde_json::from_slice(&json).map_err(|e| format!("invalid ID token claims: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(claims: serde_json::Value) -> String {
+        let encode = |bytes: &[u8]| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+        format!(
+            "{}.{}.signature",
+            encode(br#"{"alg":"RS256"}"#),
+            encode(claims.to_string().as_bytes())
+        )
+    }
+
+    #[test]
+    fn pkce_challenge_matches_rfc_7636_example() {
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn discovery_url_accepts_issuer_or_document() {
+        assert_eq!(
+            discovery_document_url("https://sso.example.com/"),
+            "https://sso.example.com/.well-known/openid-configuration"
+        );
+        assert_eq!(
+            discovery_document_url("https://sso.example.com/.well-known/openid-configuration"),
+            "https://sso.example.com/.well-known/openid-configuration"
+        );
+    }
+
+    #[test]
+    fn claims_are_checked_against_the_sign_in() {
+        let claims = decode_id_token(&token(serde_json::json!({
+            "iss": "https://sso.example.com",
+            "sub": "248289761001",
+            "aud": ["taskbook", "other"],
+            "exp": 1_000,
+            "nonce": "n-0S6",
+            "email": "jane@example.com",
+            "email_verified": true,
+        })))
+        .unwrap();
+        assert_eq!(claims.email.as_deref(), Some("jane@example.com"));
+
+        let check = |issuer, client, nonce, now| claims.validate(issuer, client, nonce, now);
+        assert!(check("https://sso.example.com", "taskbook", "n-0S6", 999).is_ok());
+        assert!(check("https://evil.example.com", "taskbook", "n-0S6", 999).is_err());
+        assert!(check("https://sso.example.com", "another", "n-0S6", 999).is_err());
+        assert!(check("https://sso.example.com", "taskbook", "other", 999).is_err());
+        assert!(check("https://sso.example.com", "taskbook", "n-0S6", 1_000).is_err());
+        assert!(decode_id_token("not-a-jwt").is_err());
+    }
+}
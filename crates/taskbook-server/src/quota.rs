@@ -0,0 +1,159 @@
+//! Per-user storage quotas.
+//!
+//! An account's storage is the encrypted data of its items and of the items
+//! on the shared boards it owns (those in the trash excepted), and of its
+//! share links.
+//! The limit is `TB_STORAGE_QUOTA`, unless an admin has set one for the
+//! account.
+
+use uuid::Uuid;
+
+use crate::db::DbConnection;
+use crate::error::{Result, ServerError};
+
+/// SQL expression for the bytes stored by the user whose id is `user_id`
+/// (a column or a parameter).
+pub fn usage_sql(user_id: &str) -> String {
+    format!(
+        "(CAST((SELECT COALESCE(SUM(length(i.data)), 0) FROM items i \
+                WHERE i.user_id = {user_id} AND i.deleted_at IS NULL) AS BIGINT) \
+        + CAST((SELECT COALESCE(SUM(length(bi.data)), 0) FROM board_items bi \
+                JOIN shared_boards b ON b.id = bi.board_id \
+                WHERE b.owner_id = {user_id} AND bi.deleted_at IS NULL) AS BIGINT) \
+        + CAST((SELECT COALESCE(SUM(length(l.data)), 0) FROM share_links l \
+                WHERE l.user_id = {user_id}) AS BIGINT))"
+    )
+}
+
+/// Bytes stored by a user.
+pub async fn used(conn: &mut DbConnection, user_id: Uuid) -> Result<i64> {
+    sqlx::query_scalar::<_, i64>(&format!("SELECT {}", usage_sql("$1")))
+        .bind(user_id)
+        .fetch_one(conn)
+        .await
+        .map_err(ServerError::Database)
+}
+
+/// A user's limit: their own if an admin set one, else the server default.
+pub async fn limit(
+    conn: &mut DbConnection,
+    user_id: Uuid,
+    default: Option<i64>,
+) -> Result<Option<i64>> {
+    let own = sqlx::query_scalar::<_, Option<i64>>("SELECT quota_bytes FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(conn)
+        .await
+        .map_err(ServerError::Database)?;
+    Ok(own.or(default))
+}
+
+/// Rejects a change that makes an account's storage grow past its limit.
+///
+/// Changes that do not grow it are accepted even when the account is
+/// already over its limit.
+pub struct QuotaCheck {
+    user_id: Uuid,
+    quota: Option<i64>,
+    used_before: i64,
+}
+
+impl QuotaCheck {
+    /// Note what `user_id` stores before a change
+    pub async fn start(
+        conn: &mut DbConnection,
+        user_id: Uuid,
+        default: Option<i64>,
+    ) -> Result<Self> {
+        let quota = limit(conn, user_id, default).await?;
+        let used_before = match quota {
+            Some(_) => used(conn, user_id).await?,
+            None => 0,
+        };
+        Ok(Self {
+            user_id,
+            quota,
+            used_before,
+        })
+    }
+
+    /// Fail if the change made within the same transaction went over the limit
+    pub async fn finish(self, conn: &mut DbConnection) -> Result<()> {
+        let Some(quota) = self.quota else {
+            return Ok(());
+        };
+        let used = used(conn, self.user_id).await?;
+        if used > quota && used > self.used_before {
+            tracing::warn!(user_id = %self.user_id, used, quota, "storage quota exceeded");
+            return Err(ServerError::QuotaExceeded { used, quota });
+        }
+        Ok(())
+    }
+}
+
+/// Parse a size such as `500000`, `512K`, `100M` or `1G` (binary units).
+pub fn parse_size(s: &str) -> std::result::Result<i64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let multiplier: i64 = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => {
+            return Err(format!(
+                "invalid size {s:?}: use bytes or a K, M, G or T suffix"
+            ))
+        }
+    };
+    digits
+        .parse::<i64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size {s:?}"))
+}
+
+/// Byte count in binary units, e.g. `12.3 KiB`
+pub fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{value:.1} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_accepts_units() {
+        assert_eq!(parse_size("500000"), Ok(500_000));
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size("100 MB"), Ok(100 * 1024 * 1024));
+        assert_eq!(parse_size("1GiB"), Ok(1 << 30));
+        assert_eq!(parse_size("2g"), Ok(2 << 30));
+        assert!(parse_size("").is_err());
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("-5M").is_err());
+    }
+
+    #[test]
+    fn format_bytes_uses_binary_units() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MiB");
+    }
+}
@@ -1,23 +1,35 @@
 use std::collections::HashMap;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::Instant;
 
+use axum::extract::{ConnectInfo, FromRequestParts, MatchedPath, Request, State};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderName};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
 use tokio::sync::Mutex;
 
+use crate::config::RateLimitConfig;
+use crate::error::ServerError;
+use crate::router::AppState;
+
 /// Simple in-memory per-IP sliding window rate limiter.
 #[derive(Clone)]
 pub struct RateLimiter {
     state: Arc<Mutex<HashMap<IpAddr, Vec<Instant>>>>,
     max_requests: usize,
+    /// Requests allowed on top of `max_requests`
+    burst: usize,
     window: std::time::Duration,
 }
 
 impl RateLimiter {
-    pub fn new(max_requests: usize, window_secs: u64) -> Self {
+    pub fn new(max_requests: usize, burst: usize, window_secs: u64) -> Self {
         Self {
             state: Arc::new(Mutex::new(HashMap::new())),
             max_requests,
+            burst,
             window: std::time::Duration::from_secs(window_secs),
         }
     }
@@ -34,7 +46,7 @@ impl RateLimiter {
         // Remove expired entries
         timestamps.retain(|t| now.duration_since(*t) < window);
 
-        if timestamps.len() >= self.max_requests {
+        if timestamps.len() >= self.max_requests + self.burst {
             return false;
         }
 
@@ -42,3 +54,211 @@ impl RateLimiter {
         true
     }
 }
+
+/// A group of routes sharing a limit
+#[derive(Debug, Clone, Copy)]
+pub enum Group {
+    /// Routes that check passwords, codes or tokens
+    Auth,
+    /// Routes that read or write items
+    Items,
+}
+
+/// The server's rate limits, built from [`RateLimitConfig`]. A limit of
+/// zero leaves its routes unlimited.
+#[derive(Clone)]
+pub struct RateLimits {
+    auth: Option<RateLimiter>,
+    items: Option<RateLimiter>,
+    /// Limits for single routes by pattern, in place of their group's
+    routes: HashMap<String, Option<RateLimiter>>,
+    /// Header a trusted proxy puts the client's address in
+    client_ip_header: Option<HeaderName>,
+}
+
+impl RateLimits {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let limiter = |max_requests: usize| {
+            (max_requests > 0)
+                .then(|| RateLimiter::new(max_requests, config.burst, config.window_secs))
+        };
+        Self {
+            auth: limiter(config.auth),
+            items: limiter(config.items),
+            routes: config
+                .routes
+                .iter()
+                .map(|(route, max_requests)| (route.clone(), limiter(*max_requests)))
+                .collect(),
+            client_ip_header: config.client_ip_header.clone(),
+        }
+    }
+
+    /// The limiter for requests to `route`, if they are limited
+    fn limiter(&self, group: Group, route: Option<&str>) -> Option<&RateLimiter> {
+        if let Some(limiter) = route.and_then(|route| self.routes.get(route)) {
+            return limiter.as_ref();
+        }
+        match group {
+            Group::Auth => self.auth.as_ref(),
+            Group::Items => self.items.as_ref(),
+        }
+    }
+
+    /// The client's address: the last one in the trusted proxy's header if
+    /// one is configured and valid, otherwise the peer's.
+    ///
+    /// The last address is the one the proxy in front of the server saw,
+    /// so this is only right behind exactly one trusted proxy. Behind a
+    /// chain of them it is the address of the proxy before the last one,
+    /// and without a proxy clients can send any address they like.
+    pub fn client_ip(&self, headers: &HeaderMap, peer: IpAddr) -> IpAddr {
+        self.client_ip_header
+            .as_ref()
+            .and_then(|name| headers.get(name))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(peer)
+    }
+}
+
+/// The address of the client making a request, see [`RateLimits::client_ip`].
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = ServerError;
+
+    fn from_request_parts<'a, 'b, 'c>(
+        parts: &'a mut Parts,
+        state: &'b AppState,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self, Self::Rejection>> + Send + 'c>,
+    >
+    where
+        'a: 'c,
+        'b: 'c,
+        Self: 'c,
+    {
+        Box::pin(async move {
+            let ConnectInfo(peer) = ConnectInfo::<SocketAddr>::from_request_parts(parts, state)
+                .await
+                .map_err(|e| ServerError::Internal(e.to_string()))?;
+            Ok(Self(state.rate_limits.client_ip(&parts.headers, peer.ip())))
+        })
+    }
+}
+
+/// Middleware limiting routes that check passwords, codes or tokens.
+pub async fn limit_auth(
+    State(state): State<AppState>,
+    ip: ClientIp,
+    route: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    limit(&state, Group::Auth, ip, route, request, next).await
+}
+
+/// Middleware limiting routes that read or write items.
+pub async fn limit_items(
+    State(state): State<AppState>,
+    ip: ClientIp,
+    route: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    limit(&state, Group::Items, ip, route, request, next).await
+}
+
+async fn limit(
+    state: &AppState,
+    group: Group,
+    ClientIp(ip): ClientIp,
+    route: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = route.as_ref().map(MatchedPath::as_str);
+    if let Some(limiter) = state.rate_limits.limiter(group, route) {
+        if !limiter.check(ip).await {
+            tracing::warn!(ip = %ip, route, ?group, "rate limited");
+            return ServerError::RateLimited.into_response();
+        }
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(client_ip_header: Option<&str>) -> RateLimits {
+        RateLimits::new(&RateLimitConfig {
+            window_secs: 60,
+            auth: 2,
+            items: 0,
+            burst: 1,
+            routes: vec![
+                ("/api/v1/login".to_string(), 1),
+                ("/api/v1/register".to_string(), 0),
+            ],
+            client_ip_header: client_ip_header.map(|h| h.parse().unwrap()),
+        })
+    }
+
+    #[tokio::test]
+    async fn limiter_allows_max_requests_per_window() {
+        let limiter = RateLimiter::new(2, 0, 60);
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        assert!(limiter.check(ip).await);
+        assert!(limiter.check(ip).await);
+        assert!(!limiter.check(ip).await);
+        assert!(limiter.check("192.0.2.2".parse().unwrap()).await);
+    }
+
+    #[tokio::test]
+    async fn burst_is_allowed_on_top_of_the_limit() {
+        let limiter = RateLimiter::new(2, 1, 60);
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        for _ in 0..3 {
+            assert!(limiter.check(ip).await);
+        }
+        assert!(!limiter.check(ip).await);
+    }
+
+    #[test]
+    fn route_overrides_take_precedence_over_group() {
+        let limits = limits(None);
+        let max = |group, route| {
+            limits
+                .limiter(group, route)
+                .map(|limiter| limiter.max_requests)
+        };
+        assert_eq!(max(Group::Auth, Some("/api/v1/refresh")), Some(2));
+        assert_eq!(max(Group::Auth, None), Some(2));
+        assert_eq!(max(Group::Auth, Some("/api/v1/login")), Some(1));
+        assert_eq!(max(Group::Auth, Some("/api/v1/register")), None);
+        assert_eq!(max(Group::Items, Some("/api/v1/items")), None);
+    }
+
+    #[test]
+    fn client_ip_comes_from_trusted_header_only() {
+        let peer: IpAddr = "10.0.0.1".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            "203.0.113.9, 198.51.100.7".parse().unwrap(),
+        );
+
+        assert_eq!(limits(None).client_ip(&headers, peer), peer);
+        let trusted = limits(Some("X-Forwarded-For"));
+        assert_eq!(
+            trusted.client_ip(&headers, peer),
+            "198.51.100.7".parse::<IpAddr>().unwrap()
+        );
+        headers.insert("x-forwarded-for", "unknown".parse().unwrap());
+        assert_eq!(trusted.client_ip(&headers, peer), peer);
+    }
+}
@@ -1,18 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, RwLock};
 
 use axum::http::HeaderValue;
-use axum::routing::{delete, get, post, put};
+use axum::middleware::from_fn_with_state;
+use axum::routing::{delete, get, patch, post, put};
 use axum::Router;
-use sqlx::PgPool;
-use tokio::sync::broadcast;
+use chrono::Utc;
+use tokio::sync::{broadcast, mpsc};
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::limit::RequestBodyLimitLayer;
 use uuid::Uuid;
 
-use crate::handlers::{events, health, items, user};
+use crate::config::{RegistrationMode, ServerConfig};
+use crate::db::DbPool;
+use crate::fanout::{self, Published};
+use crate::handlers::{
+    admin, audit, boards, email, events, export, health, items, links, oidc, sessions, socket,
+    tokens, user, webhooks,
+};
+use crate::mailer::Mailer;
 use crate::metrics_middleware::HttpMetricsLayer;
-use crate::rate_limit::RateLimiter;
+use crate::oidc::Provider;
+use crate::rate_limit::{self, RateLimits};
+use crate::webhooks::Webhooks;
 
 /// Event broadcast to connected SSE clients when data changes.
 #[derive(Debug, Clone)]
@@ -21,67 +32,317 @@ pub enum SyncEvent {
     DataChanged { archived: bool },
 }
 
+/// A [`SyncEvent`] numbered in the order it was sent to its user.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub id: u64,
+    pub event: SyncEvent,
+}
+
+/// Notifications sent to each user that are kept for clients resuming
+/// with `Last-Event-ID`.
+const RECENT_NOTIFICATIONS: usize = 64;
+
+/// A user's broadcast channel and latest notifications
+struct UserChannel {
+    sender: broadcast::Sender<Notification>,
+    last_id: u64,
+    recent: VecDeque<Notification>,
+}
+
+/// A live subscription, and what the subscriber missed before it
+pub struct Subscription {
+    pub receiver: broadcast::Receiver<Notification>,
+    /// Events sent since the ID the client resumed from, or `None` if they
+    /// are no longer known (too old, or sent before the server restarted)
+    pub missed: Option<Vec<Notification>>,
+    /// ID of the latest notification sent to the user
+    pub last_id: u64,
+}
+
 /// Per-user broadcast hub for real-time sync notifications.
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct NotificationHub {
-    senders: Arc<RwLock<HashMap<Uuid, broadcast::Sender<SyncEvent>>>>,
+    senders: Arc<RwLock<HashMap<Uuid, UserChannel>>>,
+    /// Tells this server run's event IDs from those of earlier runs
+    epoch: u64,
+    /// Tells this instance's notifications from those of other instances
+    instance: Uuid,
+    /// Passes notifications on to the other instances, see [`fanout`]
+    publisher: Option<mpsc::UnboundedSender<Published>>,
+}
+
+impl Default for NotificationHub {
+    fn default() -> Self {
+        Self {
+            senders: Arc::default(),
+            epoch: Utc::now().timestamp_millis().unsigned_abs(),
+            instance: Uuid::new_v4(),
+            publisher: None,
+        }
+    }
 }
 
 impl NotificationHub {
+    /// A hub that also sends each notification to `publisher`
+    #[cfg_attr(feature = "sqlite", allow(dead_code))]
+    pub fn with_publisher(publisher: mpsc::UnboundedSender<Published>) -> Self {
+        Self {
+            publisher: Some(publisher),
+            ..Self::default()
+        }
+    }
+
+    #[cfg_attr(feature = "sqlite", allow(dead_code))]
+    pub fn instance(&self) -> Uuid {
+        self.instance
+    }
+
     /// Subscribe to notifications for the given user.
     /// Creates a new broadcast channel if one doesn't exist yet.
-    pub fn subscribe(&self, user_id: Uuid) -> broadcast::Receiver<SyncEvent> {
+    pub fn subscribe(&self, user_id: Uuid) -> broadcast::Receiver<Notification> {
+        self.resume(user_id, None).receiver
+    }
+
+    /// Subscribe to notifications for the given user, along with those
+    /// sent after the event ID `last_event_id`.
+    pub fn resume(&self, user_id: Uuid, last_event_id: Option<&str>) -> Subscription {
         let mut map = self.senders.write().unwrap();
-        let sender = map
-            .entry(user_id)
-            .or_insert_with(|| broadcast::channel(64).0);
-        sender.subscribe()
+        let channel = map.entry(user_id).or_insert_with(|| UserChannel {
+            sender: broadcast::channel(64).0,
+            last_id: 0,
+            recent: VecDeque::new(),
+        });
+
+        let missed = last_event_id.and_then(|id| {
+            let seen = self.parse_event_id(id)?;
+            let oldest = channel.recent.front().map_or(channel.last_id + 1, |n| n.id);
+            // Anything between `seen` and the oldest kept has been dropped
+            if seen > channel.last_id || seen + 1 < oldest {
+                return None;
+            }
+            Some(
+                channel
+                    .recent
+                    .iter()
+                    .filter(|n| n.id > seen)
+                    .cloned()
+                    .collect(),
+            )
+        });
+
+        Subscription {
+            receiver: channel.sender.subscribe(),
+            missed,
+            last_id: channel.last_id,
+        }
     }
 
-    /// Send a notification to all connected clients for the given user.
+    /// Send a notification to all connected clients for the given user,
+    /// on every instance.
     pub fn notify(&self, user_id: Uuid, event: SyncEvent) {
-        let map = self.senders.read().unwrap();
-        if let Some(sender) = map.get(&user_id) {
+        if let Some(publisher) = &self.publisher {
+            let SyncEvent::DataChanged { archived } = event;
+            let _ = publisher.send(Published {
+                instance: self.instance,
+                user_id,
+                archived,
+            });
+        }
+        self.deliver(user_id, event);
+    }
+
+    /// Send a notification to the given user's clients on this instance.
+    pub fn deliver(&self, user_id: Uuid, event: SyncEvent) {
+        let mut map = self.senders.write().unwrap();
+        if let Some(channel) = map.get_mut(&user_id) {
+            channel.last_id += 1;
+            let notification = Notification {
+                id: channel.last_id,
+                event,
+            };
+            if channel.recent.len() == RECENT_NOTIFICATIONS {
+                channel.recent.pop_front();
+            }
+            channel.recent.push_back(notification.clone());
             // Ignore send errors — they just mean no receivers are connected.
-            let _ = sender.send(event);
+            let _ = channel.sender.send(notification);
+        }
+    }
+
+    /// Tell every client on this instance to refresh everything, for when
+    /// notifications may have been missed.
+    #[cfg_attr(feature = "sqlite", allow(dead_code))]
+    pub fn refresh_all(&self) {
+        let users: Vec<Uuid> = self.senders.read().unwrap().keys().copied().collect();
+        for user_id in users {
+            for archived in [false, true] {
+                self.deliver(user_id, SyncEvent::DataChanged { archived });
+            }
+        }
+    }
+
+    /// The SSE event ID for notification `id`
+    pub fn event_id(&self, id: u64) -> String {
+        format!("{}-{id}", self.epoch)
+    }
+
+    /// The notification number in an event ID from this server run
+    fn parse_event_id(&self, event_id: &str) -> Option<u64> {
+        let (epoch, id) = event_id.trim().split_once('-')?;
+        if epoch.parse::<u64>().ok()? != self.epoch {
+            return None;
         }
+        id.parse().ok()
     }
 }
 
 #[derive(Clone)]
 pub struct AppState {
-    pub pool: PgPool,
+    pub pool: DbPool,
     pub session_expiry_days: i64,
-    pub auth_rate_limiter: RateLimiter,
+    pub refresh_expiry_days: i64,
+    pub rate_limits: RateLimits,
     pub notifications: NotificationHub,
+    /// Set when the server is configured to send email
+    pub mailer: Option<Mailer>,
+    /// Set when single sign-on is configured
+    pub oidc: Option<Provider>,
+    pub registration_mode: RegistrationMode,
+    /// Default bytes each account may store
+    pub storage_quota: Option<i64>,
+    /// Days deleted items are kept before they are purged
+    pub trash_retention_days: i64,
+    /// Earlier versions kept of each item
+    pub item_history: i64,
+    /// Largest request body or WebSocket message, in bytes
+    pub max_body_size: usize,
+    pub webhooks: Webhooks,
 }
 
-pub fn build(pool: PgPool, session_expiry_days: i64, cors_origins: &[String]) -> Router {
-    // 10 auth requests per IP per 60 seconds
-    let auth_rate_limiter = RateLimiter::new(10, 60);
+impl AppState {
+    pub fn new(pool: DbPool, config: &ServerConfig, mailer: Option<Mailer>) -> Self {
+        let notifications = fanout::start(&pool);
+        Self {
+            pool,
+            session_expiry_days: config.session_expiry_days,
+            refresh_expiry_days: config.refresh_expiry_days,
+            rate_limits: RateLimits::new(&config.rate_limits),
+            notifications,
+            mailer,
+            oidc: config.oidc.as_ref().map(Provider::new),
+            registration_mode: config.registration_mode,
+            storage_quota: config.storage_quota,
+            trash_retention_days: config.trash_retention_days,
+            item_history: config.item_history,
+            max_body_size: config.max_body_size,
+            webhooks: Webhooks::new(config.webhook_allow_private),
+        }
+    }
+}
 
-    let state = AppState {
-        pool,
-        session_expiry_days,
-        auth_rate_limiter,
-        notifications: NotificationHub::default(),
-    };
+pub fn build(pool: DbPool, config: &ServerConfig, mailer: Option<Mailer>) -> Router {
+    let state = AppState::new(pool, config, mailer);
 
-    let cors = build_cors_layer(cors_origins);
+    let cors = build_cors_layer(&config.cors_origins);
 
-    let router = Router::new()
-        .route("/api/v1/health", get(health::health))
+    // Routes that check passwords, codes or tokens
+    let auth_routes = Router::new()
         .route("/api/v1/register", post(user::register))
         .route("/api/v1/login", post(user::login))
-        .route("/api/v1/logout", delete(user::logout))
-        .route("/api/v1/me", get(user::me))
+        .route("/api/v1/refresh", post(sessions::refresh_session))
+        .route("/api/v1/me", delete(user::delete_account))
+        .route("/api/v1/me/password", patch(user::change_password))
+        .route("/api/v1/me/totp", post(user::enroll_totp))
+        .route("/api/v1/me/totp", delete(user::disable_totp))
+        .route("/api/v1/me/totp/confirm", post(user::confirm_totp))
+        .route("/api/v1/me/email/verify", post(email::send_verification))
+        .route("/api/v1/password/forgot", post(email::forgot_password))
+        .route("/api/v1/password/reset", post(email::reset_password))
+        .route("/api/v1/oidc/start", post(oidc::start_login))
+        .route("/api/v1/oidc/link", post(oidc::start_link))
+        .route("/api/v1/oidc/callback", get(oidc::callback))
+        .route_layer(from_fn_with_state(state.clone(), rate_limit::limit_auth));
+
+    let item_routes = Router::new()
         .route("/api/v1/items", get(items::get_items))
         .route("/api/v1/items", put(items::put_items))
         .route("/api/v1/items/archive", get(items::get_archive))
         .route("/api/v1/items/archive", put(items::put_archive))
+        .route("/api/v1/items/trash", get(items::get_trash))
+        .route("/api/v1/items/trash/restore", post(items::restore_trash))
+        .route("/api/v1/items/:key/history", get(items::get_history))
+        .route("/api/v1/boards/:id/items", get(boards::get_board_items))
+        .route("/api/v1/boards/:id/items", put(boards::put_board_items))
+        .route("/api/v1/boards/:id/trash", get(boards::get_board_trash))
+        .route(
+            "/api/v1/boards/:id/trash/restore",
+            post(boards::restore_board_trash),
+        )
+        .route(
+            "/api/v1/boards/:id/items/:key/history",
+            get(boards::get_board_item_history),
+        )
+        .route_layer(from_fn_with_state(state.clone(), rate_limit::limit_items));
+
+    let router = Router::new()
+        .merge(auth_routes)
+        .merge(item_routes)
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .route("/api/v1/health", get(health::health))
+        .route("/api/v1/logout", delete(user::logout))
+        .route("/api/v1/me", get(user::me))
+        .route("/api/v1/me/usage", get(user::usage))
+        .route("/api/v1/me/export", get(export::export))
+        .route("/api/v1/me/audit", get(audit::list_events))
+        .route("/api/v1/me/key-params", put(user::put_key_params))
+        .route("/api/v1/email/verify", post(email::verify_email))
+        .route("/verify-email", get(email::view_page))
+        .route("/reset-password", get(email::view_page))
+        // Polled every few seconds, so outside the auth limit; poll tokens
+        // are random 256-bit values
+        .route("/api/v1/oidc/poll", post(oidc::poll))
+        .route("/api/v1/sessions", get(sessions::list_sessions))
+        .route("/api/v1/sessions/:id", delete(sessions::revoke_session))
+        .route("/api/v1/tokens", get(tokens::list_tokens))
+        .route("/api/v1/tokens", post(tokens::create_token))
+        .route("/api/v1/tokens/:id", delete(tokens::revoke_token))
+        .route("/api/v1/webhooks", get(webhooks::list_webhooks))
+        .route("/api/v1/webhooks", post(webhooks::create_webhook))
+        .route("/api/v1/webhooks/:id", delete(webhooks::delete_webhook))
+        .route("/api/v1/webhooks/:id/test", post(webhooks::test_webhook))
+        .route("/api/v1/me/public-key", put(boards::put_public_key))
+        .route(
+            "/api/v1/users/:username/public-key",
+            get(boards::get_public_key),
+        )
+        .route("/api/v1/boards", get(boards::list_boards))
+        .route("/api/v1/boards", post(boards::create_board))
+        .route("/api/v1/boards/:id", delete(boards::delete_board))
+        .route("/api/v1/boards/:id/members", put(boards::share_board))
+        .route("/api/v1/boards/:id/key", put(boards::put_board_key))
+        .route(
+            "/api/v1/boards/:id/members/:username",
+            delete(boards::unshare_board),
+        )
+        .route("/api/v1/links", get(links::list_links))
+        .route("/api/v1/links", post(links::create_link))
+        .route("/api/v1/links/:token", get(links::get_link))
+        .route("/api/v1/links/:token", delete(links::delete_link))
+        .route("/s/:token", get(links::view_link))
         .route("/api/v1/events", get(events::events))
-        // 10 MB body limit for item uploads
-        .layer(RequestBodyLimitLayer::new(10 * 1024 * 1024))
+        .route("/api/v1/ws", get(socket::socket))
+        .route("/api/v1/admin/users", get(admin::list_users))
+        .route(
+            "/api/v1/admin/users/:username/disabled",
+            put(admin::set_disabled),
+        )
+        .route("/api/v1/admin/users/:username/quota", put(admin::set_quota))
+        .route("/api/v1/admin/purge", post(admin::purge_inactive))
+        .layer(RequestBodyLimitLayer::new(config.max_body_size))
+        // gzip or brotli, as the client accepts; SSE streams are left as is
+        .layer(CompressionLayer::new())
         .layer(cors);
 
     let router = if std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").is_ok() {
@@ -99,12 +360,15 @@ fn build_cors_layer(origins: &[String]) -> CorsLayer {
             axum::http::Method::GET,
             axum::http::Method::POST,
             axum::http::Method::PUT,
+            axum::http::Method::PATCH,
             axum::http::Method::DELETE,
         ])
         .allow_headers([
             axum::http::header::CONTENT_TYPE,
             axum::http::header::AUTHORIZATION,
-        ]);
+            axum::http::header::IF_NONE_MATCH,
+        ])
+        .expose_headers([axum::http::header::ETAG]);
 
     if origins.is_empty() {
         // No TB_CORS_ORIGINS configured. Use http://localhost as the default
@@ -119,3 +383,61 @@ fn build_cors_layer(origins: &[String]) -> CorsLayer {
         cors.allow_origin(AllowOrigin::list(parsed))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn missed_ids(hub: &NotificationHub, user_id: Uuid, last_event_id: &str) -> Option<Vec<u64>> {
+        hub.resume(user_id, Some(last_event_id))
+            .missed
+            .map(|missed| missed.iter().map(|n| n.id).collect())
+    }
+
+    #[test]
+    fn resume_replays_what_was_missed_while_known() {
+        let hub = NotificationHub::default();
+        let user_id = Uuid::new_v4();
+        let _rx = hub.subscribe(user_id);
+        for _ in 0..3 {
+            hub.notify(user_id, SyncEvent::DataChanged { archived: false });
+        }
+
+        assert_eq!(
+            missed_ids(&hub, user_id, &hub.event_id(1)),
+            Some(vec![2, 3])
+        );
+        assert_eq!(missed_ids(&hub, user_id, &hub.event_id(3)), Some(vec![]));
+        // From another server run, or an ID never sent
+        assert_eq!(missed_ids(&hub, user_id, "1-1"), None);
+        assert_eq!(missed_ids(&hub, user_id, &hub.event_id(9)), None);
+
+        for _ in 0..RECENT_NOTIFICATIONS {
+            hub.notify(user_id, SyncEvent::DataChanged { archived: true });
+        }
+        assert_eq!(missed_ids(&hub, user_id, &hub.event_id(1)), None);
+        assert_eq!(
+            missed_ids(&hub, user_id, &hub.event_id(3)).map(|ids| ids.len()),
+            Some(RECENT_NOTIFICATIONS)
+        );
+    }
+
+    #[test]
+    fn notify_publishes_to_other_instances_and_deliver_does_not() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let hub = NotificationHub::with_publisher(tx);
+        let user_id = Uuid::new_v4();
+        let mut local = hub.subscribe(user_id);
+
+        hub.notify(user_id, SyncEvent::DataChanged { archived: true });
+        let published = rx.try_recv().unwrap();
+        assert_eq!(published.instance, hub.instance());
+        assert_eq!(published.user_id, user_id);
+        assert!(published.archived);
+        assert_eq!(local.try_recv().unwrap().id, 1);
+
+        hub.deliver(user_id, SyncEvent::DataChanged { archived: false });
+        assert!(rx.try_recv().is_err());
+        assert_eq!(local.try_recv().unwrap().id, 2);
+    }
+}
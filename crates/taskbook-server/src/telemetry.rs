@@ -12,6 +12,8 @@ use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 
+use crate::db::DbPool;
+
 /// Guard that flushes and shuts down OTel providers on drop.
 pub struct TelemetryGuard {
     tracer_provider: SdkTracerProvider,
@@ -150,7 +152,7 @@ pub fn init_telemetry() -> Option<TelemetryGuard> {
 ///
 /// Only meaningful when OTel is active, but safe to call regardless — when no
 /// meter provider is configured the callbacks are simply never invoked.
-pub fn spawn_db_pool_metrics(pool: sqlx::PgPool) {
+pub fn spawn_db_pool_metrics(pool: DbPool) {
     let meter = global::meter("taskbook-server");
 
     let pool_total = pool.clone();
@@ -0,0 +1,88 @@
+//! Helpers for tests that run against a fresh database: in memory with
+//! SQLite, or a schema of its own in the Postgres database at
+//! `DATABASE_URL`. Without `DATABASE_URL`, the Postgres build skips them.
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+use base64::Engine as _;
+use uuid::Uuid;
+
+use crate::config::ServerConfig;
+use crate::db;
+use crate::handlers::items::EncryptedItemData;
+use crate::router::AppState;
+
+/// Server state over an empty, migrated database, or `None` if there is
+/// no database to test against
+#[cfg(feature = "sqlite")]
+pub async fn state() -> Option<AppState> {
+    let config = ServerConfig::single_user(":memory:".to_string(), Ipv4Addr::LOCALHOST.into(), 0);
+    let pool = db::create_pool(&config.database_url).await.unwrap();
+    db::migrate(&pool).await.unwrap();
+    Some(AppState::new(pool, &config, None))
+}
+
+/// Server state over an empty, migrated database, or `None` if there is
+/// no database to test against.
+///
+/// Each call migrates a new schema, so tests can run at the same time;
+/// the schemas are left behind, so point `DATABASE_URL` at a database
+/// kept for tests.
+#[cfg(not(feature = "sqlite"))]
+pub async fn state() -> Option<AppState> {
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+    use sqlx::{Connection, PgConnection};
+
+    let Ok(url) = std::env::var("DATABASE_URL") else {
+        eprintln!("DATABASE_URL is not set; skipping a test that needs Postgres");
+        return None;
+    };
+    let schema = format!("test_{}", Uuid::new_v4().simple());
+    let mut connection = PgConnection::connect(&url).await.unwrap();
+    sqlx::query(&format!("CREATE SCHEMA {schema}"))
+        .execute(&mut connection)
+        .await
+        .unwrap();
+
+    let options = url
+        .parse::<PgConnectOptions>()
+        .unwrap()
+        .options([("search_path", schema.as_str())]);
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect_with(options)
+        .await
+        .unwrap();
+    db::migrate(&pool).await.unwrap();
+    let config = ServerConfig::single_user(url, Ipv4Addr::LOCALHOST.into(), 0);
+    Some(AppState::new(pool, &config, None))
+}
+
+/// Add an account named `username`, returning its id
+pub async fn user(state: &AppState, username: &str) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO users (username, email, password) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(username)
+    .bind(format!("{username}@example.com"))
+    .bind("not a hash")
+    .fetch_one(&state.pool)
+    .await
+    .unwrap()
+}
+
+/// Uploaded items with these keys and (unencrypted, for the test) data
+pub fn items(entries: &[(&str, &str)]) -> HashMap<String, EncryptedItemData> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    entries
+        .iter()
+        .map(|(key, data)| {
+            let item = EncryptedItemData {
+                data: engine.encode(data),
+                nonce: engine.encode("nonce"),
+            };
+            (key.to_string(), item)
+        })
+        .collect()
+}
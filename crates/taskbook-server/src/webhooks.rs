@@ -0,0 +1,515 @@
+//! Webhooks: a signed JSON payload posted to each URL a user registered
+//! whenever their items change, for chat and automation integrations.
+//!
+//! Items are encrypted on the client, so a payload says what changed (the
+//! items, the archive or a shared board), who changed it and how many items,
+//! but never what they say. The body is signed with HMAC-SHA256 under the
+//! webhook's secret, in `X-Taskbook-Signature: sha256=<hex>`. Deliveries run
+//! in the background and never hold up the request that caused them; one
+//! that fails is retried twice.
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{redirect, Url};
+use serde::Serialize;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::db::DbPool;
+
+pub const SIGNATURE_HEADER: &str = "X-Taskbook-Signature";
+pub const EVENT_HEADER: &str = "X-Taskbook-Event";
+pub const DELIVERY_HEADER: &str = "X-Taskbook-Delivery";
+
+/// How long a receiver has to answer
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Waits before retrying a failed delivery
+const RETRY_DELAYS: [Duration; 2] = [Duration::from_secs(10), Duration::from_secs(60)];
+
+/// Longest URL that can be registered
+pub const MAX_URL_LEN: usize = 2048;
+
+/// What a payload is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The user's items changed
+    Items,
+    /// The user's archive changed
+    Archive,
+    /// The items of a shared board the user is a member of changed
+    Board(Uuid),
+    /// Sent on request, to try a webhook out
+    Ping,
+}
+
+impl Event {
+    pub fn for_items(archived: bool) -> Self {
+        if archived {
+            Event::Archive
+        } else {
+            Event::Items
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Event::Items => "items.changed",
+            Event::Archive => "archive.changed",
+            Event::Board(_) => "board.changed",
+            Event::Ping => "ping",
+        }
+    }
+}
+
+/// How many items an upload added or changed, and how many it removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Changes {
+    pub changed: u64,
+    pub removed: u64,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.changed == 0 && self.removed == 0
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Payload {
+    pub event: &'static str,
+    /// Who made the change
+    pub user: String,
+    /// The shared board, for `board.changed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub board: Option<Uuid>,
+    pub changed: u64,
+    pub removed: u64,
+    pub timestamp: DateTime<Utc>,
+    /// A one-line summary, under the names Slack (`text`) and Discord
+    /// (`content`) post, so their incoming webhook URLs work as they are
+    pub text: String,
+    pub content: String,
+}
+
+impl Payload {
+    pub fn new(event: Event, user: String, changes: Changes) -> Self {
+        let text = summary(&user, event, changes);
+        Self {
+            event: event.as_str(),
+            board: match event {
+                Event::Board(id) => Some(id),
+                _ => None,
+            },
+            user,
+            changed: changes.changed,
+            removed: changes.removed,
+            timestamp: Utc::now(),
+            content: text.clone(),
+            text,
+        }
+    }
+}
+
+/// The outcome of one delivery: the receiver's HTTP status, or why there
+/// was none
+pub type Delivery = Result<u16, String>;
+
+/// Sends webhook payloads.
+#[derive(Clone)]
+pub struct Webhooks {
+    http: reqwest::Client,
+    /// Whether URLs may point at loopback and private network addresses
+    allow_private: bool,
+}
+
+impl Webhooks {
+    pub fn new(allow_private: bool) -> Self {
+        let http = client_builder().build().expect("the HTTP client builds");
+        Self {
+            http,
+            allow_private,
+        }
+    }
+
+    /// Check a URL before it is registered.
+    pub fn check_url(&self, url: &str) -> Result<Url, String> {
+        if url.len() > MAX_URL_LEN {
+            return Err(format!("URL must be at most {MAX_URL_LEN} characters"));
+        }
+        let parsed = Url::parse(url).map_err(|e| format!("invalid URL: {e}"))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err("URL must start with http:// or https://".to_string());
+        }
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| "URL has no host".to_string())?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_end_matches('.')
+            .to_lowercase();
+        if !self.allow_private {
+            let private = match host.parse::<IpAddr>() {
+                Ok(ip) => !is_public(ip),
+                Err(_) => host == "localhost" || host.ends_with(".localhost"),
+            };
+            if private {
+                return Err("webhooks cannot call private network addresses".to_string());
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Send `event` to the webhooks of each of `recipients`, in the
+    /// background. Uploads that changed nothing send nothing.
+    pub fn notify(
+        &self,
+        pool: &DbPool,
+        recipients: Vec<Uuid>,
+        actor: Uuid,
+        event: Event,
+        changes: Changes,
+    ) {
+        if changes.is_empty() {
+            return;
+        }
+        let webhooks = self.clone();
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            if let Err(e) = webhooks
+                .notify_all(&pool, &recipients, actor, event, changes)
+                .await
+            {
+                tracing::warn!(error = %e, "cannot send webhooks");
+            }
+        });
+    }
+
+    async fn notify_all(
+        &self,
+        pool: &DbPool,
+        recipients: &[Uuid],
+        actor: Uuid,
+        event: Event,
+        changes: Changes,
+    ) -> Result<(), sqlx::Error> {
+        let mut hooks = Vec::new();
+        for user_id in recipients {
+            hooks.extend(
+                sqlx::query_as::<_, (Uuid, String, String)>(
+                    "SELECT id, url, secret FROM webhooks WHERE user_id = $1",
+                )
+                .bind(user_id)
+                .fetch_all(pool)
+                .await?,
+            );
+        }
+        if hooks.is_empty() {
+            return Ok(());
+        }
+
+        let username = sqlx::query_scalar::<_, String>("SELECT username FROM users WHERE id = $1")
+            .bind(actor)
+            .fetch_optional(pool)
+            .await?
+            .unwrap_or_default();
+        let body = serde_json::to_vec(&Payload::new(event, username, changes))
+            .expect("payloads serialize");
+
+        for (id, url, secret) in hooks {
+            let webhooks = self.clone();
+            let pool = pool.clone();
+            let body = body.clone();
+            // Each on its own, so a slow receiver does not hold up the others
+            tokio::spawn(async move {
+                let mut delays = RETRY_DELAYS.iter();
+                loop {
+                    let delivery = webhooks.deliver(&url, &secret, event, &body).await;
+                    let retry = match &delivery {
+                        Ok(status) => *status == 429 || *status >= 500,
+                        Err(_) => true,
+                    };
+                    match delays.next().filter(|_| retry) {
+                        Some(delay) => tokio::time::sleep(*delay).await,
+                        None => {
+                            record(&pool, id, &delivery).await;
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Post a signed `body` to `url` once.
+    pub async fn deliver(&self, url: &str, secret: &str, event: Event, body: &[u8]) -> Delivery {
+        let parsed = Url::parse(url).map_err(|e| format!("invalid URL: {e}"))?;
+        let pinned;
+        let http = if self.allow_private {
+            &self.http
+        } else {
+            // Connect to the address that was checked, not to whatever the
+            // name resolves to by the time the request is made
+            let addr = check_resolved(&parsed).await?;
+            let host = parsed.host_str().unwrap_or_default();
+            pinned = client_builder()
+                .resolve(host, addr)
+                .build()
+                .map_err(|e| e.to_string())?;
+            &pinned
+        };
+
+        let resp = http
+            .post(parsed)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.as_str())
+            .header(DELIVERY_HEADER, Uuid::new_v4().to_string())
+            .header(SIGNATURE_HEADER, signature(secret, body))
+            .body(body.to_vec())
+            .send()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+        Ok(resp.status().as_u16())
+    }
+}
+
+/// Settings for every client that delivers webhooks
+fn client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        // A redirect could lead anywhere, past the address check
+        .redirect(redirect::Policy::none())
+        .user_agent(concat!("taskbook-server/", env!("CARGO_PKG_VERSION")))
+}
+
+/// The address to deliver to at `url`. A host name that resolves to a
+/// private address is refused, as one registered before it did would
+/// otherwise reach the internal network.
+async fn check_resolved(url: &Url) -> Result<SocketAddr, String> {
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Err("URL has no host".to_string());
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("cannot resolve {host}: {e}"))?
+        .collect();
+    if addrs.iter().any(|addr| !is_public(addr.ip())) {
+        return Err(format!("{host} resolves to a private network address"));
+    }
+    addrs
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("cannot resolve {host}"))
+}
+
+/// Remember how the latest delivery to webhook `id` went, for its owner
+pub async fn record(pool: &DbPool, id: Uuid, delivery: &Delivery) {
+    let (status, error) = match delivery {
+        Ok(status) if (200..300).contains(status) => (Some(*status as i32), None),
+        Ok(status) => (Some(*status as i32), Some(format!("HTTP {status}"))),
+        Err(e) => (None, Some(e.clone())),
+    };
+    if let Some(error) = &error {
+        tracing::info!(webhook_id = %id, error, "webhook delivery failed");
+    }
+    if let Err(e) = sqlx::query(
+        "UPDATE webhooks SET last_delivery_at = $2, last_status = $3, last_error = $4 \
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(Utc::now())
+    .bind(status)
+    .bind(error)
+    .execute(pool)
+    .await
+    {
+        tracing::warn!(error = %e, "cannot record webhook delivery");
+    }
+}
+
+/// The `X-Taskbook-Signature` value for `body`
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("sha256={hex}")
+}
+
+/// Whether `ip` is on the public internet, rather than loopback, a private
+/// or link-local network, or otherwise special
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    // Unique local, fc00::/7
+                    || (first & 0xfe00) == 0xfc00
+                    // Link-local, fe80::/10
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// A line saying what happened, e.g. "alice changed 2 items and removed 1"
+fn summary(user: &str, event: Event, changes: Changes) -> String {
+    let items = |n: u64| if n == 1 { "item" } else { "items" };
+    let mut parts = Vec::new();
+    if changes.changed > 0 {
+        parts.push(format!(
+            "changed {} {}",
+            changes.changed,
+            items(changes.changed)
+        ));
+    }
+    if changes.removed > 0 {
+        parts.push(format!(
+            "removed {} {}",
+            changes.removed,
+            items(changes.removed)
+        ));
+    }
+    let what = parts.join(" and ");
+    match event {
+        Event::Items => format!("{user} {what} in taskbook"),
+        Event::Archive => format!("{user} {what} in the taskbook archive"),
+        Event::Board(_) => format!("{user} {what} on a shared taskbook board"),
+        Event::Ping => "Test delivery from taskbook".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        assert_eq!(
+            signature("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn private_addresses_are_refused_unless_allowed() {
+        let webhooks = Webhooks::new(false);
+        assert!(webhooks
+            .check_url("https://hooks.slack.com/services/T/B/x")
+            .is_ok());
+        for url in [
+            "ftp://example.com/hook",
+            "http://localhost:8080/hook",
+            "http://127.0.0.1/hook",
+            "http://10.1.2.3/hook",
+            "http://192.168.0.10/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(webhooks.check_url(url).is_err(), "{url}");
+        }
+        assert!(Webhooks::new(true)
+            .check_url("http://192.168.0.10/hook")
+            .is_ok());
+    }
+
+    #[test]
+    fn payloads_summarize_the_change() {
+        let payload = Payload::new(
+            Event::Archive,
+            "alice".to_string(),
+            Changes {
+                changed: 2,
+                removed: 1,
+            },
+        );
+        assert_eq!(payload.event, "archive.changed");
+        assert_eq!(
+            payload.text,
+            "alice changed 2 items and removed 1 item in the taskbook archive"
+        );
+        assert_eq!(payload.content, payload.text);
+        assert!(payload.board.is_none());
+
+        let board = Uuid::new_v4();
+        let payload = Payload::new(
+            Event::Board(board),
+            "bob".to_string(),
+            Changes {
+                changed: 1,
+                removed: 0,
+            },
+        );
+        assert_eq!(payload.board, Some(board));
+        assert_eq!(
+            payload.text,
+            "bob changed 1 item on a shared taskbook board"
+        );
+    }
+
+    #[tokio::test]
+    async fn deliveries_go_to_the_checked_address() {
+        let url = Url::parse("http://1.1.1.1:8080/hook").unwrap();
+        assert_eq!(
+            check_resolved(&url).await.unwrap(),
+            "1.1.1.1:8080".parse().unwrap()
+        );
+        let url = Url::parse("http://localhost/hook").unwrap();
+        assert!(check_resolved(&url).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn redirects_are_not_followed() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"{}") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(
+                    b"HTTP/1.1 302 Found\r\nLocation: http://169.254.169.254/\r\n\
+                      Content-Length: 0\r\n\r\n",
+                )
+                .unwrap();
+        });
+
+        let delivery = Webhooks::new(true)
+            .deliver(&url, "secret", Event::Items, b"{}")
+            .await;
+        assert_eq!(delivery, Ok(302));
+        server.join().unwrap();
+    }
+}